@@ -3,6 +3,7 @@ package net.mullvad.mullvadvpn.viewmodel.location
 import net.mullvad.mullvadvpn.lib.common.util.relaylist.filterOnSearchTerm
 import net.mullvad.mullvadvpn.lib.model.CustomListId
 import net.mullvad.mullvadvpn.lib.model.GeoLocationId
+import net.mullvad.mullvadvpn.lib.model.HostnamePatternId
 import net.mullvad.mullvadvpn.lib.model.MultihopRelayListType
 import net.mullvad.mullvadvpn.lib.model.RelayItem
 import net.mullvad.mullvadvpn.lib.model.RelayItemId
@@ -467,6 +468,7 @@ internal fun RelayItemId.expandKey(parent: CustomListId? = null) =
         when (this) {
             is CustomListId -> value
             is GeoLocationId -> code
+            is HostnamePatternId -> value
         }
 
 internal fun RelayItemSelection.selectedByThisEntryExitList(relayListType: RelayListType) =
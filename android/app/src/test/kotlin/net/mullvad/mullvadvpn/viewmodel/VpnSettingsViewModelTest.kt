@@ -31,6 +31,7 @@ import net.mullvad.mullvadvpn.lib.model.DaitaSettings
 import net.mullvad.mullvadvpn.lib.model.IpVersion
 import net.mullvad.mullvadvpn.lib.model.Mtu
 import net.mullvad.mullvadvpn.lib.model.ObfuscationMode
+import net.mullvad.mullvadvpn.lib.model.ObfuscationSensitivity
 import net.mullvad.mullvadvpn.lib.model.ObfuscationSettings
 import net.mullvad.mullvadvpn.lib.model.QuantumResistantState
 import net.mullvad.mullvadvpn.lib.model.Recents
@@ -276,6 +277,7 @@ class VpnSettingsViewModelTest {
                         udp2tcp = Udp2TcpObfuscationSettings(Constraint.Any),
                         shadowsocks = ShadowsocksObfuscationSettings(Constraint.Any),
                         wireguardPort = Constraint.Any,
+                        autoSensitivity = ObfuscationSensitivity.Normal,
                     ),
                 customLists = emptyList(),
                 allowLan = false,
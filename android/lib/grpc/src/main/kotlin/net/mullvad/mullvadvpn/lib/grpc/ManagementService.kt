@@ -259,6 +259,10 @@ class ManagementService(
                         }
                         ManagementInterface.DaemonEvent.EventCase.REMOVE_DEVICE -> {}
                         ManagementInterface.DaemonEvent.EventCase.LEAK_INFO -> {}
+                        ManagementInterface.DaemonEvent.EventCase.ACCOUNT_EXPIRED -> {}
+                        ManagementInterface.DaemonEvent.EventCase.RELAY_SELECTION_FALLBACK -> {}
+                        ManagementInterface.DaemonEvent.EventCase.CAPTIVE_PORTAL_DETECTED -> {}
+                        ManagementInterface.DaemonEvent.EventCase.TRUSTED_NETWORK_CHANGED -> {}
                         ManagementInterface.DaemonEvent.EventCase.EVENT_NOT_SET -> {}
                     }
                 }
@@ -12,9 +12,11 @@ import net.mullvad.mullvadvpn.lib.model.DefaultDnsOptions
 import net.mullvad.mullvadvpn.lib.model.DnsOptions
 import net.mullvad.mullvadvpn.lib.model.DnsState
 import net.mullvad.mullvadvpn.lib.model.GeoLocationId
+import net.mullvad.mullvadvpn.lib.model.HostnamePatternId
 import net.mullvad.mullvadvpn.lib.model.IpVersion
 import net.mullvad.mullvadvpn.lib.model.NewAccessMethodSetting
 import net.mullvad.mullvadvpn.lib.model.ObfuscationMode
+import net.mullvad.mullvadvpn.lib.model.ObfuscationSensitivity
 import net.mullvad.mullvadvpn.lib.model.ObfuscationSettings
 import net.mullvad.mullvadvpn.lib.model.Ownership
 import net.mullvad.mullvadvpn.lib.model.PlayPurchase
@@ -37,6 +39,7 @@ internal fun Constraint<RelayItemId>.fromDomain(): ManagementInterface.LocationC
                     when (val relayItemId = value) {
                         is CustomListId -> setCustomList(relayItemId.value)
                         is GeoLocationId -> setLocation(relayItemId.fromDomain())
+                        is HostnamePatternId -> setHostnamePattern(relayItemId.value)
                     }
                 }
             }
@@ -83,6 +86,7 @@ internal fun ObfuscationSettings.fromDomain(): ManagementInterface.ObfuscationSe
         .setUdp2Tcp(udp2tcp.fromDomain())
         .setShadowsocks(shadowsocks.fromDomain())
         .setWireguardPort(wireguardPort.fromDomain())
+        .setAutoSensitivity(autoSensitivity.fromDomain())
         .build()
 
 internal fun ObfuscationMode.fromDomain():
@@ -100,6 +104,15 @@ internal fun ObfuscationMode.fromDomain():
         ObfuscationMode.Off -> ManagementInterface.ObfuscationSettings.SelectedObfuscation.OFF
     }
 
+internal fun ObfuscationSensitivity.fromDomain():
+    ManagementInterface.ObfuscationSettings.AutoSensitivity =
+    when (this) {
+        ObfuscationSensitivity.Normal ->
+            ManagementInterface.ObfuscationSettings.AutoSensitivity.NORMAL
+        ObfuscationSensitivity.Low -> ManagementInterface.ObfuscationSettings.AutoSensitivity.LOW
+        ObfuscationSensitivity.High -> ManagementInterface.ObfuscationSettings.AutoSensitivity.HIGH
+    }
+
 internal fun Udp2TcpObfuscationSettings.fromDomain():
     ManagementInterface.ObfuscationSettings.Udp2TcpObfuscation =
     when (val port = port) {
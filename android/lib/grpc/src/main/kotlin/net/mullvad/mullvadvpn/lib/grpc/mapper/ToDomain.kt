@@ -44,10 +44,12 @@ import net.mullvad.mullvadvpn.lib.model.ErrorStateCause
 import net.mullvad.mullvadvpn.lib.model.FeatureIndicator
 import net.mullvad.mullvadvpn.lib.model.GeoIpLocation
 import net.mullvad.mullvadvpn.lib.model.GeoLocationId
+import net.mullvad.mullvadvpn.lib.model.HostnamePatternId
 import net.mullvad.mullvadvpn.lib.model.IpVersion
 import net.mullvad.mullvadvpn.lib.model.Mtu
 import net.mullvad.mullvadvpn.lib.model.ObfuscationEndpoint
 import net.mullvad.mullvadvpn.lib.model.ObfuscationMode
+import net.mullvad.mullvadvpn.lib.model.ObfuscationSensitivity
 import net.mullvad.mullvadvpn.lib.model.ObfuscationSettings
 import net.mullvad.mullvadvpn.lib.model.ObfuscationType
 import net.mullvad.mullvadvpn.lib.model.Ownership
@@ -265,6 +267,10 @@ internal fun ManagementInterface.ErrorState.toDomain(
                 ManagementInterface.ErrorState.Cause.TUNNEL_PARAMETER_ERROR ->
                     ErrorStateCause.TunnelParameterError(parameterError.toDomain())
                 ManagementInterface.ErrorState.Cause.IS_OFFLINE -> ErrorStateCause.IsOffline
+                ManagementInterface.ErrorState.Cause.CONNECT_TIMEOUT ->
+                    ErrorStateCause.ConnectTimeout
+                ManagementInterface.ErrorState.Cause.RECONNECT_LIMIT_REACHED ->
+                    ErrorStateCause.ReconnectLimitReached
                 ManagementInterface.ErrorState.Cause.SPLIT_TUNNEL_ERROR ->
                     ErrorStateCause.StartTunnelError
                 ManagementInterface.ErrorState.Cause.UNRECOGNIZED,
@@ -311,6 +317,8 @@ internal fun ManagementInterface.ErrorState.GenerationError.toDomain(): Paramete
             ParameterGenerationError.NoMatchingRelayExit
         ManagementInterface.ErrorState.GenerationError.NO_MATCHING_RELAY ->
             ParameterGenerationError.NoMatchingRelay
+        ManagementInterface.ErrorState.GenerationError.NO_MATCHING_DAITA_RELAY ->
+            ParameterGenerationError.NoMatchingDaitaRelay
         ManagementInterface.ErrorState.GenerationError.NO_MATCHING_BRIDGE_RELAY ->
             ParameterGenerationError.NoMatchingBridgeRelay
         ManagementInterface.ErrorState.GenerationError.CUSTOM_TUNNEL_HOST_RESOLUTION_ERROR ->
@@ -368,6 +376,8 @@ internal fun ManagementInterface.LocationConstraint.toDomain(): Constraint<Relay
             Constraint.Only(CustomListId(customList))
         ManagementInterface.LocationConstraint.TypeCase.LOCATION ->
             Constraint.Only(location.toDomain())
+        ManagementInterface.LocationConstraint.TypeCase.HOSTNAME_PATTERN ->
+            Constraint.Only(HostnamePatternId(hostnamePattern))
         ManagementInterface.LocationConstraint.TypeCase.TYPE_NOT_SET -> Constraint.Any
         else -> throw IllegalArgumentException("Invalid location constraint")
     }
@@ -416,8 +426,20 @@ internal fun ManagementInterface.ObfuscationSettings.toDomain(): ObfuscationSett
         udp2tcp = udp2Tcp.toDomain(),
         shadowsocks = shadowsocks.toDomain(),
         wireguardPort = wireguardPort.toDomain(),
+        autoSensitivity = autoSensitivity.toDomain(),
     )
 
+internal fun ManagementInterface.ObfuscationSettings.AutoSensitivity.toDomain():
+    ObfuscationSensitivity =
+    when (this) {
+        ManagementInterface.ObfuscationSettings.AutoSensitivity.NORMAL ->
+            ObfuscationSensitivity.Normal
+        ManagementInterface.ObfuscationSettings.AutoSensitivity.LOW -> ObfuscationSensitivity.Low
+        ManagementInterface.ObfuscationSettings.AutoSensitivity.HIGH -> ObfuscationSensitivity.High
+        ManagementInterface.ObfuscationSettings.AutoSensitivity.UNRECOGNIZED ->
+            ObfuscationSensitivity.Normal
+    }
+
 internal fun ManagementInterface.ObfuscationSettings.SelectedObfuscation.toDomain():
     ObfuscationMode =
     when (this) {
@@ -25,6 +25,10 @@ sealed class ErrorStateCause {
 
     data object IsOffline : ErrorStateCause()
 
+    data object ConnectTimeout : ErrorStateCause()
+
+    data object ReconnectLimitReached : ErrorStateCause()
+
     data object NotPrepared : ErrorStateCause()
 
     data class OtherAlwaysOnApp(val appName: String) : ErrorStateCause()
@@ -0,0 +1,7 @@
+package net.mullvad.mullvadvpn.lib.model
+
+enum class ObfuscationSensitivity {
+    Normal,
+    Low,
+    High,
+}
@@ -4,6 +4,7 @@ enum class ParameterGenerationError {
     NoMatchingRelayEntry,
     NoMatchingRelayExit,
     NoMatchingRelay,
+    NoMatchingDaitaRelay,
     NoMatchingBridgeRelay,
     CustomTunnelHostResolutionError,
     Ipv4_Unavailable,
@@ -16,6 +16,14 @@ value class CustomListId(val value: String) : RelayItemId, Parcelable {
     companion object
 }
 
+/** A location set by hostname pattern, which can only be configured outside of the app. */
+@optics
+@Parcelize
+@JvmInline
+value class HostnamePatternId(val value: String) : RelayItemId, Parcelable {
+    companion object
+}
+
 @optics
 @Parcelize
 sealed interface GeoLocationId : RelayItemId, Parcelable {
@@ -265,6 +265,9 @@ private fun ErrorStateCause.errorMessageId(): String =
         is ErrorStateCause.DnsError -> stringResource(R.string.set_dns_error)
         is ErrorStateCause.StartTunnelError -> stringResource(R.string.start_tunnel_error)
         is ErrorStateCause.IsOffline -> stringResource(R.string.is_offline)
+        is ErrorStateCause.ConnectTimeout -> stringResource(R.string.connect_timeout)
+        is ErrorStateCause.ReconnectLimitReached ->
+            stringResource(R.string.reconnect_limit_reached)
         is ErrorStateCause.TunnelParameterError -> stringResource(error.errorMessageId())
         is ErrorStateCause.NotPrepared ->
             stringResource(R.string.vpn_permission_error_notification_message)
@@ -301,6 +304,9 @@ private fun ParameterGenerationError.errorMessageId(): Int =
         ParameterGenerationError.NoMatchingRelayEntry -> {
             R.string.no_matching_relay_entry
         }
+        ParameterGenerationError.NoMatchingDaitaRelay -> {
+            R.string.no_matching_daita_relay
+        }
         ParameterGenerationError.CustomTunnelHostResolutionError ->
             R.string.custom_tunnel_host_resolution_error
         ParameterGenerationError.Ipv4_Unavailable -> R.string.ip_version_v4_unavailable
@@ -11,6 +11,7 @@ import net.mullvad.mullvadvpn.lib.model.Constraint
 import net.mullvad.mullvadvpn.lib.model.CustomListId
 import net.mullvad.mullvadvpn.lib.model.GeoLocationId
 import net.mullvad.mullvadvpn.lib.model.HopSelection
+import net.mullvad.mullvadvpn.lib.model.HostnamePatternId
 import net.mullvad.mullvadvpn.lib.model.RelayItem
 import net.mullvad.mullvadvpn.lib.model.RelayItemId
 import net.mullvad.mullvadvpn.lib.repository.RelayListRepository
@@ -58,6 +59,7 @@ class HopSelectionUseCase(
             when (val id = this.value) {
                 is CustomListId -> customLists.firstOrNull { it.id == id }
                 is GeoLocationId -> relayList.findByGeoLocationId(id)
+                is HostnamePatternId -> null
             }?.let(Constraint<RelayItem>::Only)
         } else {
             Constraint.Any
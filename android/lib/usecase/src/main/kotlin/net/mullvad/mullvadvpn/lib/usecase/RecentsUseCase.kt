@@ -6,6 +6,7 @@ import kotlinx.coroutines.flow.map
 import net.mullvad.mullvadvpn.lib.common.util.relaylist.findByGeoLocationId
 import net.mullvad.mullvadvpn.lib.model.CustomListId
 import net.mullvad.mullvadvpn.lib.model.GeoLocationId
+import net.mullvad.mullvadvpn.lib.model.HostnamePatternId
 import net.mullvad.mullvadvpn.lib.model.MultihopRelayListType
 import net.mullvad.mullvadvpn.lib.model.Recent
 import net.mullvad.mullvadvpn.lib.model.Recents
@@ -71,5 +72,6 @@ class RecentsUseCase(
         when (this) {
             is CustomListId -> customLists.firstOrNull { this == it.id && it.hasChildren }
             is GeoLocationId -> relayList.findByGeoLocationId(this)
+            is HostnamePatternId -> null
         }
 }
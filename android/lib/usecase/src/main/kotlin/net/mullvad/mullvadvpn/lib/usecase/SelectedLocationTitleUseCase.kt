@@ -7,6 +7,7 @@ import net.mullvad.mullvadvpn.lib.model.Constraint
 import net.mullvad.mullvadvpn.lib.model.CustomList
 import net.mullvad.mullvadvpn.lib.model.CustomListId
 import net.mullvad.mullvadvpn.lib.model.GeoLocationId
+import net.mullvad.mullvadvpn.lib.model.HostnamePatternId
 import net.mullvad.mullvadvpn.lib.model.RelayItem
 import net.mullvad.mullvadvpn.lib.model.RelayItemId
 import net.mullvad.mullvadvpn.lib.repository.CustomListsRepository
@@ -39,6 +40,7 @@ class SelectedLocationTitleUseCase(
             is GeoLocationId.Hostname -> createRelayTitle(relayCountries, relayItemId)
             is GeoLocationId.City -> relayCountries.findCity(relayItemId)?.name
             is GeoLocationId.Country -> relayCountries.firstOrNull { it.id == relayItemId }?.name
+            is HostnamePatternId -> relayItemId.value
         }
 
     private fun createRelayTitle(
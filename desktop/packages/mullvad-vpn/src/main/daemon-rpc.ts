@@ -23,6 +23,7 @@ import {
   ISettings,
   NewAccessMethodSetting,
   NewCustomList,
+  ObfuscationSensitivity,
   ObfuscationSettings,
   ObfuscationType,
   RelaySettings,
@@ -362,6 +363,24 @@ export class DaemonRpc extends GrpcClient {
       grpcObfuscationSettings.setWireguardPort(wireGuardPortSettings);
     }
 
+    switch (obfuscationSettings.autoSensitivity) {
+      case ObfuscationSensitivity.normal:
+        grpcObfuscationSettings.setAutoSensitivity(
+          grpcTypes.ObfuscationSettings.AutoSensitivity.NORMAL,
+        );
+        break;
+      case ObfuscationSensitivity.low:
+        grpcObfuscationSettings.setAutoSensitivity(
+          grpcTypes.ObfuscationSettings.AutoSensitivity.LOW,
+        );
+        break;
+      case ObfuscationSensitivity.high:
+        grpcObfuscationSettings.setAutoSensitivity(
+          grpcTypes.ObfuscationSettings.AutoSensitivity.HIGH,
+        );
+        break;
+    }
+
     await this.call<grpcTypes.ObfuscationSettings, Empty>(
       this.client.setObfuscationSettings,
       grpcObfuscationSettings,
@@ -2,6 +2,7 @@ import {
   ApiAccessMethodSettings,
   IRelaySettingsNormal,
   ISettings,
+  ObfuscationSensitivity,
   ObfuscationType,
   Ownership,
 } from '../shared/daemon-rpc-types';
@@ -66,6 +67,7 @@ export function getDefaultSettings(): ISettings {
       wireGuardPortSettings: {
         port: 'any',
       },
+      autoSensitivity: ObfuscationSensitivity.normal,
     },
     customLists: [],
     apiAccessMethods: getDefaultApiAccessMethods(),
@@ -42,6 +42,7 @@ import {
   LoggedOutDeviceState,
   NewAccessMethodSetting,
   NewCustomList,
+  ObfuscationSensitivity,
   ObfuscationSettings,
   ObfuscationType,
   Ownership,
@@ -267,6 +268,16 @@ function convertFromTunnelStateError(state: grpcTypes.ErrorState.AsObject): Erro
         ...baseError,
         cause: ErrorStateCause.needFullDiskPermissions,
       };
+    case grpcTypes.ErrorState.Cause.CONNECT_TIMEOUT:
+      return {
+        ...baseError,
+        cause: ErrorStateCause.connectTimeout,
+      };
+    case grpcTypes.ErrorState.Cause.RECONNECT_LIMIT_REACHED:
+      return {
+        ...baseError,
+        cause: ErrorStateCause.reconnectLimitReached,
+      };
     // These are only ever created on Android
     case grpcTypes.ErrorState.Cause.INVALID_DNS_SERVERS:
     case grpcTypes.ErrorState.Cause.NOT_PREPARED:
@@ -313,6 +324,8 @@ function convertFromParameterError(
       return TunnelParameterError.noMatchingRelay;
     case grpcTypes.ErrorState.GenerationError.NO_MATCHING_BRIDGE_RELAY:
       return TunnelParameterError.noMatchingBridgeRelay;
+    case grpcTypes.ErrorState.GenerationError.NO_MATCHING_DAITA_RELAY:
+      return TunnelParameterError.noMatchingDaitaRelay;
     case grpcTypes.ErrorState.GenerationError.CUSTOM_TUNNEL_HOST_RESOLUTION_ERROR:
       return TunnelParameterError.customTunnelHostResolutionError;
     case grpcTypes.ErrorState.GenerationError.NETWORK_IPV4_UNAVAILABLE:
@@ -610,6 +623,16 @@ function convertFromObfuscationSettings(
       break;
   }
 
+  let autoSensitivity = ObfuscationSensitivity.normal;
+  switch (obfuscationSettings?.autoSensitivity) {
+    case grpcTypes.ObfuscationSettings.AutoSensitivity.LOW:
+      autoSensitivity = ObfuscationSensitivity.low;
+      break;
+    case grpcTypes.ObfuscationSettings.AutoSensitivity.HIGH:
+      autoSensitivity = ObfuscationSensitivity.high;
+      break;
+  }
+
   return {
     selectedObfuscation: selectedObfuscationType,
     udp2tcpSettings: obfuscationSettings?.udp2tcp
@@ -621,6 +644,7 @@ function convertFromObfuscationSettings(
     wireGuardPortSettings: obfuscationSettings?.wireguardPort
       ? { port: convertFromConstraint(obfuscationSettings.wireguardPort.port) }
       : { port: 'any' },
+    autoSensitivity,
   };
 }
 
@@ -9,6 +9,7 @@ import {
   IpVersion,
   IWireguardEndpointData,
   LiftedConstraint,
+  ObfuscationSensitivity,
   ObfuscationSettings,
   ObfuscationType,
   Ownership,
@@ -155,6 +156,7 @@ const initialState: ISettingsReduxState = {
     wireGuardPortSettings: {
       port: 'any',
     },
+    autoSensitivity: ObfuscationSensitivity.normal,
   },
   customLists: [],
   apiAccessMethods: getDefaultApiAccessMethods(),
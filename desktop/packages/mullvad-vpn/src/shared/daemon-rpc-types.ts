@@ -50,6 +50,8 @@ export enum ErrorStateCause {
   isOffline,
   splitTunnelError,
   needFullDiskPermissions,
+  connectTimeout,
+  reconnectLimitReached,
 }
 
 export enum AuthFailedError {
@@ -62,6 +64,7 @@ export enum AuthFailedError {
 export enum TunnelParameterError {
   noMatchingRelay,
   noMatchingBridgeRelay,
+  noMatchingDaitaRelay,
   customTunnelHostResolutionError,
   ipv4Unavailable,
   ipv6Unavailable,
@@ -75,7 +78,9 @@ export type ErrorStateDetails =
         | ErrorStateCause.startTunnelError
         | ErrorStateCause.isOffline
         | ErrorStateCause.splitTunnelError
-        | ErrorStateCause.needFullDiskPermissions;
+        | ErrorStateCause.needFullDiskPermissions
+        | ErrorStateCause.connectTimeout
+        | ErrorStateCause.reconnectLimitReached;
       blockingError?: FirewallPolicyError;
     }
   | {
@@ -485,11 +490,18 @@ export enum ObfuscationType {
   wireGuardPort,
 }
 
+export enum ObfuscationSensitivity {
+  normal,
+  low,
+  high,
+}
+
 export type ObfuscationSettings = {
   selectedObfuscation: ObfuscationType;
   udp2tcpSettings: Udp2TcpObfuscationSettings;
   shadowsocksSettings: ShadowsocksSettings;
   wireGuardPortSettings: WireGuardPortObfuscationSettings;
+  autoSensitivity: ObfuscationSensitivity;
 };
 
 export interface ISocketAddress {
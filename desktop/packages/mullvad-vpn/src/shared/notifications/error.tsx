@@ -233,6 +233,16 @@ export class ErrorNotificationProvider
           );
         case ErrorStateCause.needFullDiskPermissions:
           return messages.pgettext('notifications', 'Failed to enable split tunneling.');
+        case ErrorStateCause.connectTimeout:
+          return messages.pgettext(
+            'notifications',
+            'Unable to connect within the configured connect timeout. Reconnect to try again.',
+          );
+        case ErrorStateCause.reconnectLimitReached:
+          return messages.pgettext(
+            'notifications',
+            'Unable to connect within the configured number of reconnect attempts. Reconnect to try again.',
+          );
         case ErrorStateCause.splitTunnelError:
           switch (process.platform ?? window.env.platform) {
             case 'darwin':
@@ -259,6 +269,11 @@ export class ErrorNotificationProvider
           'notifications',
           'No servers match your settings, try changing server or other settings.',
         );
+      case TunnelParameterError.noMatchingDaitaRelay:
+        return messages.pgettext(
+          'notifications',
+          'No DAITA-compatible servers match your settings, try changing server or DAITA settings.',
+        );
       case TunnelParameterError.customTunnelHostResolutionError:
         return messages.pgettext(
           'notifications',
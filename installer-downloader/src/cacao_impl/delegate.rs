@@ -29,6 +29,13 @@ impl AppDelegate for AppWindow {
         self.beta_link.set_callback(callback);
     }
 
+    fn on_throttle_toggle<F>(&mut self, callback: F)
+    where
+        F: Fn() + Send + 'static,
+    {
+        self.throttle_toggle.set_callback(callback);
+    }
+
     fn set_status_text(&mut self, text: &str) {
         self.status_text.set_text(text);
     }
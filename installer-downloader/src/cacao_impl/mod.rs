@@ -7,7 +7,7 @@ use ui::{Action, AppImpl};
 mod delegate;
 mod ui;
 
-pub fn main() {
+pub fn main() -> Result<(), EnvError> {
     let app = App::new("net.mullvad.MullvadVPNLoader", AppImpl::default());
 
     // Load "global" values and resources
@@ -16,6 +16,7 @@ pub fn main() {
         Err(EnvError::Arch) => {
             unreachable!("The CPU architecture will always be retrievable on macOS")
         }
+        Err(error) => return Err(error),
     };
 
     let cb: Mutex<Option<ui::MainThreadCallback>> = Mutex::new(Some(Box::new(|self_| {
@@ -24,4 +25,6 @@ pub fn main() {
     cacao::appkit::App::<ui::AppImpl, _>::dispatch_main(Action::QueueMain(cb));
 
     app.run();
+
+    Ok(())
 }
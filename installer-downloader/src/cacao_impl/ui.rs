@@ -11,6 +11,7 @@ use cacao::layout::{Layout, LayoutConstraint};
 use cacao::notification_center::Dispatcher;
 use cacao::objc::{class, msg_send, sel, sel_impl};
 use cacao::progress::ProgressIndicator;
+use cacao::switch::Switch;
 use cacao::text::Label;
 use cacao::view::View;
 use objc_id::Id;
@@ -18,7 +19,7 @@ use objc_id::Id;
 use crate::delegate::ErrorMessage;
 use crate::resource::{
     BANNER_DESC, BETA_LINK_TEXT, BETA_PREFACE_DESC, CANCEL_BUTTON_TEXT, DOWNLOAD_BUTTON_TEXT,
-    STABLE_LINK_TEXT, WINDOW_HEIGHT, WINDOW_TITLE, WINDOW_WIDTH,
+    STABLE_LINK_TEXT, THROTTLE_TOGGLE_TEXT, WINDOW_HEIGHT, WINDOW_TITLE, WINDOW_WIDTH,
 };
 
 /// Logo render in the banner
@@ -161,6 +162,8 @@ pub struct AppWindow {
     pub beta_link: LinkToBeta,
 
     pub stable_link: LinkToStable,
+
+    pub throttle_toggle: ThrottleToggle,
 }
 
 pub struct ErrorView {
@@ -218,6 +221,33 @@ macro_rules! button_wrapper {
     };
 }
 
+/// Checkbox used to enable or disable download throttling
+pub struct ThrottleToggle {
+    pub switch: Switch,
+}
+
+impl Default for ThrottleToggle {
+    fn default() -> Self {
+        Self {
+            switch: Switch::new(THROTTLE_TOGGLE_TEXT),
+        }
+    }
+}
+
+impl ThrottleToggle {
+    /// Register a callback to be executed on the main thread when this checkbox is toggled.
+    pub fn set_callback(&mut self, callback: impl Fn() + Send + 'static) {
+        // See `button_wrapper` for why this is wrapped in an Arc<Mutex>.
+        let callback = Box::new(callback) as Box<dyn Fn() + Send>;
+        let callback = Arc::new(Mutex::new(callback));
+        self.switch.set_action(move || {
+            let callback = callback.clone();
+            let callback = Action::ButtonClick { callback };
+            cacao::appkit::App::<super::ui::AppImpl, _>::dispatch_main(callback);
+        });
+    }
+}
+
 button_wrapper!(LinkToBeta, BETA_LINK_TEXT);
 button_wrapper!(LinkToStable, format!("← {STABLE_LINK_TEXT}"));
 button_wrapper!(DownloadButton, DOWNLOAD_BUTTON_TEXT);
@@ -323,6 +353,8 @@ impl AppWindow {
         self.stable_link.set_bordered(false);
         self.main_view.add_subview(&*self.stable_link);
 
+        self.main_view.add_subview(&self.throttle_toggle.switch);
+
         let status_text_position_y = self.status_text_position_y.get_or_insert_with(|| {
             self.status_text
                 .top
@@ -406,6 +438,15 @@ impl AppWindow {
             self.stable_link
                 .center_y
                 .constraint_equal_to(&self.beta_link_preface.center_y),
+            self.throttle_toggle
+                .switch
+                .right
+                .constraint_equal_to(&self.main_view.right)
+                .offset(-24.),
+            self.throttle_toggle
+                .switch
+                .center_y
+                .constraint_equal_to(&self.beta_link_preface.center_y),
         ]);
     }
 
@@ -2,21 +2,28 @@
 
 use crate::{
     delegate::{AppDelegate, AppDelegateQueue},
-    environment::Environment,
+    environment::{Architecture, Environment},
     resource::{self, VERIFYING_CACHED},
     temp::DirectoryProvider,
-    ui_downloader::{UiAppDownloader, UiAppDownloaderParameters, UiProgressUpdater},
+    ui_downloader::{
+        UiAppDownloader, UiAppDownloaderParameters, UiProgressUpdater, UiVerifyProgress,
+    },
 };
 
 use mullvad_update::{
     api::{HttpVersionInfoProvider, MetaRepositoryPlatform},
     app::{self, AppCache, AppDownloader, DownloadedInstaller, HttpAppDownloader},
-    local::METADATA_FILENAME,
+    local::{LocalInstaller, METADATA_FILENAME},
     version::{Metadata, VersionInfo, VersionParameters, rollout::SUPPORTED_VERSION},
     version_provider::VersionInfoProvider,
 };
 use rand::seq::IndexedRandom;
-use std::{cmp::Ordering, path::PathBuf};
+use std::{
+    cmp::Ordering,
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::{Arc, atomic::AtomicBool},
+};
 use tokio::{
     sync::{mpsc, oneshot},
     task::JoinHandle,
@@ -28,8 +35,12 @@ enum TaskMessage {
     Cancel,
     TryBeta,
     TryStable,
+    ToggleThrottle,
 }
 
+/// Maximum download rate, in bytes per second, used when the user has enabled throttling
+const THROTTLED_DOWNLOAD_RATE: NonZeroUsize = NonZeroUsize::new(1024 * 1024).unwrap();
+
 /// See the [module-level docs](self).
 pub struct AppController {}
 
@@ -79,7 +90,7 @@ impl AppController {
     pub fn initialize<D, A, C, DirProvider>(
         delegate: &mut D,
         mut version_provider: impl VersionInfoProvider + Send + 'static,
-        environment: Environment,
+        mut environment: Environment,
     ) where
         D: AppDelegate + 'static,
         A: From<UiAppDownloaderParameters<D>> + AppDownloader + 'static,
@@ -138,6 +149,17 @@ impl AppController {
                 }
             };
 
+            if let Some(local_installer) = environment.offline_install.take() {
+                install_offline::<D>(
+                    queue,
+                    local_installer,
+                    &working_dir,
+                    environment.architecture,
+                )
+                .await;
+                return;
+            }
+
             let metadata_path = working_dir.directory.join(METADATA_FILENAME);
             version_provider.set_metadata_dump_path(metadata_path);
 
@@ -191,6 +213,10 @@ impl AppController {
         delegate.on_stable_link(move || {
             let _ = tx.try_send(TaskMessage::TryStable);
         });
+        let tx = task_tx.clone();
+        delegate.on_throttle_toggle(move || {
+            let _ = tx.try_send(TaskMessage::ToggleThrottle);
+        });
     }
 }
 
@@ -199,7 +225,7 @@ async fn fetch_app_version_info<Delegate, Cache>(
     queue: Delegate::Queue,
     version_provider: impl VersionInfoProvider + Send,
     working_directory: &WorkingDirectory,
-    Environment { architecture }: Environment,
+    Environment { architecture, .. }: Environment,
 ) -> VersionInfo
 where
     Delegate: AppDelegate + 'static,
@@ -218,6 +244,8 @@ where
             allow_empty: false,
             // The downloader allows any version
             lowest_metadata_version: mullvad_update::version::MIN_VERIFY_METADATA_VERSION,
+            // The downloader UI is not localized
+            locale: None,
         };
 
         let err = match version_provider.get_version_info(&version_params).await {
@@ -346,6 +374,7 @@ where
                     self_.set_status_text(VERIFYING_CACHED);
 
                     let ui_installer = UiAppDownloader::new(&*self_, installer);
+                    let verify_progress = UiVerifyProgress::new(self_.queue());
 
                     self_.on_error_message_retry(move || {
                         let _ = retry_tx.try_send(Some(Action::Retry));
@@ -355,7 +384,9 @@ where
                     });
 
                     tokio::spawn(async move {
-                        if let Err(err) = app::install_and_upgrade(ui_installer).await {
+                        if let Err(err) =
+                            app::install_and_upgrade(ui_installer, verify_progress).await
+                        {
                             log::error!("install_and_upgrade failed: {err:?}");
                         } else {
                             let _ = action_tx.send(None).await;
@@ -386,12 +417,108 @@ where
     }
 }
 
+/// Verify and launch an installer provided by the user, without fetching anything from the network.
+async fn install_offline<Delegate: AppDelegate + 'static>(
+    queue: Delegate::Queue,
+    local_installer: LocalInstaller,
+    working_directory: &WorkingDirectory,
+    architecture: Architecture,
+) {
+    let version_params = VersionParameters {
+        architecture,
+        rollout: SUPPORTED_VERSION,
+        allow_empty: false,
+        // The downloader allows any version
+        lowest_metadata_version: mullvad_update::version::MIN_VERIFY_METADATA_VERSION,
+        // The downloader UI is not localized
+        locale: None,
+    };
+
+    loop {
+        // `true` if the user wants to retry, `false` if they want to quit
+        let (action_tx, mut action_rx) = mpsc::channel::<bool>(1);
+        let (retry_tx, cancel_tx) = (action_tx.clone(), action_tx);
+
+        queue.queue_main(move |self_| {
+            self_.hide_error_message();
+            self_.clear_download_text();
+            self_.hide_download_button();
+            self_.hide_beta_text();
+            self_.hide_stable_text();
+            self_.show_cancel_button();
+            self_.disable_cancel_button();
+            self_.hide_download_progress();
+            self_.set_status_text(VERIFYING_CACHED);
+
+            self_.on_error_message_retry(move || {
+                let _ = retry_tx.try_send(true);
+            });
+            self_.on_error_message_cancel(move || {
+                let _ = cancel_tx.try_send(false);
+            });
+        });
+
+        match local_installer
+            .import(&working_directory.directory, &version_params)
+            .await
+        {
+            Ok(installer) => {
+                queue.queue_main(move |self_| {
+                    let ui_installer = UiAppDownloader::new(&*self_, installer);
+                    let verify_progress = UiVerifyProgress::new(self_.queue());
+
+                    tokio::spawn(async move {
+                        if let Err(err) =
+                            app::install_and_upgrade(ui_installer, verify_progress).await
+                        {
+                            log::error!("install_and_upgrade failed: {err:?}");
+                        }
+                    });
+                });
+            }
+            Err(err) => {
+                log::error!("Failed to load local installer: {err:?}");
+                queue.queue_main(|self_| {
+                    self_.clear_status_text();
+                    self_.hide_cancel_button();
+
+                    self_.show_error_message(crate::delegate::ErrorMessage {
+                        status_text: resource::OFFLINE_INSTALL_FAILED_DESC.to_owned(),
+                        cancel_button_text: resource::VERIFICATION_FAILED_CANCEL_BUTTON_TEXT
+                            .to_owned(),
+                        retry_button_text: resource::VERIFICATION_FAILED_RETRY_BUTTON_TEXT
+                            .to_owned(),
+                    });
+                });
+            }
+        }
+
+        // On success, the app quits. Otherwise, wait for the user to press either button. If the
+        // buttons are gone, there is nothing left to wait for.
+        if action_rx.recv().await != Some(true) {
+            log::debug!("Cancelling offline install");
+            queue.queue_main(|self_| {
+                self_.quit();
+            });
+            std::future::pending::<()>().await;
+        }
+        log::debug!("Retrying offline install");
+    }
+}
+
 #[derive(Clone, Copy, PartialEq)]
 enum TargetVersion {
     Beta,
     Stable,
 }
 
+/// Download task started by [ActionMessageHandler::begin_download]
+struct ActiveDownload {
+    task: JoinHandle<()>,
+    /// See [UiAppDownloader::install_started]
+    install_started: Arc<AtomicBool>,
+}
+
 /// Async worker that handles actions such as initiating a download, cancelling it, and updating
 /// labels.
 struct ActionMessageHandler<
@@ -401,8 +528,10 @@ struct ActionMessageHandler<
     queue: D::Queue,
     tx: mpsc::Sender<TaskMessage>,
     version_info: VersionInfo,
-    active_download: Option<JoinHandle<()>>,
+    active_download: Option<ActiveDownload>,
     target_version: TargetVersion,
+    /// Whether to limit the download rate. This takes effect when the next download begins.
+    throttle: bool,
     working_directory: WorkingDirectory,
 
     _marker: std::marker::PhantomData<A>,
@@ -425,6 +554,7 @@ impl<D: AppDelegate + 'static, A: From<UiAppDownloaderParameters<D>> + AppDownlo
             version_info,
             active_download: None,
             target_version: TargetVersion::Stable,
+            throttle: false,
             working_directory,
 
             _marker: std::marker::PhantomData,
@@ -439,6 +569,7 @@ impl<D: AppDelegate + 'static, A: From<UiAppDownloaderParameters<D>> + AppDownlo
         match msg {
             TaskMessage::TryBeta => self.handle_try_beta(),
             TaskMessage::TryStable => self.handle_try_stable(),
+            TaskMessage::ToggleThrottle => self.handle_toggle_throttle(),
             TaskMessage::BeginDownload => self.begin_download().await,
             TaskMessage::Cancel => self.cancel().await,
         }
@@ -473,6 +604,11 @@ impl<D: AppDelegate + 'static, A: From<UiAppDownloaderParameters<D>> + AppDownlo
         });
     }
 
+    fn handle_toggle_throttle(&mut self) {
+        self.throttle = !self.throttle;
+        log::debug!("Download throttling enabled: {}", self.throttle);
+    }
+
     async fn begin_download(&mut self) {
         self.cancel_download().await;
 
@@ -494,6 +630,7 @@ impl<D: AppDelegate + 'static, A: From<UiAppDownloaderParameters<D>> + AppDownlo
         let (tx, rx) = oneshot::channel();
         let target_version = self.target_version;
         let version_info = self.version_info.clone();
+        let rate_limit = self.throttle.then_some(THROTTLED_DOWNLOAD_RATE);
         self.queue.queue_main(move |self_| {
             let selected_version = match target_version {
                 TargetVersion::Stable => &version_info.stable,
@@ -522,20 +659,32 @@ impl<D: AppDelegate + 'static, A: From<UiAppDownloaderParameters<D>> + AppDownlo
                 app_progress: UiProgressUpdater::new(self_.queue()),
                 app_sha256,
                 cache_dir: download_dir,
+                rate_limit,
             });
 
             let ui_downloader = UiAppDownloader::new(self_, downloader);
-            let _ = tx.send(tokio::spawn(async move {
-                if let Err(err) = app::download_install_and_upgrade(ui_downloader).await {
+            let install_started = ui_downloader.install_started();
+            let verify_progress = UiVerifyProgress::new(self_.queue());
+            let task = tokio::spawn(async move {
+                if let Err(err) =
+                    app::download_install_and_upgrade(ui_downloader, verify_progress).await
+                {
                     log::error!("install_and_upgrade failed: {err:?}");
                 }
-            }));
+            });
+            let _ = tx.send(ActiveDownload {
+                task,
+                install_started,
+            });
         });
         self.active_download = rx.await.ok();
     }
 
     async fn cancel(&mut self) {
-        self.cancel_download().await;
+        if self.cancel_download().await {
+            log::debug!("Ignoring cancellation since the installer has already been launched");
+            return;
+        }
 
         let selected_version = match self.target_version {
             TargetVersion::Stable => &self.version_info.stable,
@@ -570,12 +719,18 @@ impl<D: AppDelegate + 'static, A: From<UiAppDownloaderParameters<D>> + AppDownlo
         });
     }
 
-    async fn cancel_download(&mut self) {
-        if let Some(active_download) = self.active_download.take() {
-            log::debug!("Interrupting ongoing download");
-            active_download.abort();
-            let _ = active_download.await;
-        }
+    /// Abort any ongoing download or verification. Returns `true` if the installer had already
+    /// been launched, in which case it is left running.
+    async fn cancel_download(&mut self) -> bool {
+        let Some(active_download) = self.active_download.take() else {
+            return false;
+        };
+        log::debug!("Interrupting ongoing download");
+        active_download.task.abort();
+        let _ = active_download.task.await;
+        active_download
+            .install_started
+            .load(std::sync::atomic::Ordering::SeqCst)
     }
 }
 
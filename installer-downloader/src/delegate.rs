@@ -27,6 +27,11 @@ pub trait AppDelegate {
     where
         F: Fn() + Send + 'static;
 
+    /// Register click handler for the download throttle toggle
+    fn on_throttle_toggle<F>(&mut self, callback: F)
+    where
+        F: Fn() + Send + 'static;
+
     /// Set status text
     fn set_status_text(&mut self, text: &str);
 
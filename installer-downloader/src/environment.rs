@@ -1,6 +1,13 @@
+use std::{ffi::OsString, path::PathBuf};
+
+use mullvad_update::local::LocalInstaller;
+
 /// The environment consists of globals and/or constants which need to be computed at runtime.
 pub struct Environment {
     pub architecture: Architecture,
+    /// Installer and signed metadata provided on the command line. If set, the installer is
+    /// verified and launched without fetching anything from the network.
+    pub offline_install: Option<LocalInstaller>,
 }
 
 pub type Architecture = mullvad_update::format::Architecture;
@@ -8,14 +15,69 @@ pub type Architecture = mullvad_update::format::Architecture;
 pub enum Error {
     /// Failed to get the host's CPU architecture.
     Arch,
+    /// Invalid command line arguments.
+    Args,
+}
+
+impl Error {
+    /// Message to show to the user
+    pub fn description(&self) -> &'static str {
+        match self {
+            Error::Arch => "Failed to detect CPU architecture",
+            Error::Args => crate::resource::INVALID_ARGS_DESC,
+        }
+    }
 }
 
+/// Command line flag for the path to a local installer
+const INSTALLER_ARG: &str = "--installer";
+/// Command line flag for the path to the signed metadata describing a local installer
+const METADATA_ARG: &str = "--metadata";
+
 impl Environment {
     /// Try to load the environment.
     pub fn load() -> Result<Self, Error> {
         let architecture = Self::get_arch()?;
+        let offline_install = Self::parse_offline_install(std::env::args_os().skip(1))?;
+
+        Ok(Environment {
+            architecture,
+            offline_install,
+        })
+    }
+
+    /// Parse `--installer <path> --metadata <path>`, used for installing without internet access.
+    /// Either both or none of the arguments must be given. Unrecognized arguments are ignored.
+    fn parse_offline_install(
+        mut args: impl Iterator<Item = OsString>,
+    ) -> Result<Option<LocalInstaller>, Error> {
+        let mut installer_path = None;
+        let mut metadata_path = None;
+
+        while let Some(arg) = args.next() {
+            let target = if arg == INSTALLER_ARG {
+                &mut installer_path
+            } else if arg == METADATA_ARG {
+                &mut metadata_path
+            } else {
+                log::warn!("Ignoring unrecognized argument: {}", arg.to_string_lossy());
+                continue;
+            };
+            let path = args.next().ok_or(Error::Args)?;
+            *target = Some(PathBuf::from(path));
+        }
 
-        Ok(Environment { architecture })
+        match (installer_path, metadata_path) {
+            (Some(installer_path), Some(metadata_path)) => Ok(Some(LocalInstaller {
+                installer_path,
+                metadata_path,
+            })),
+            (None, None) => Ok(None),
+            _ => {
+                log::error!("Both {INSTALLER_ARG} and {METADATA_ARG} must be provided");
+                Err(Error::Args)
+            }
+        }
     }
 
     /// Try to map the host's CPU architecture to one of the CPU architectures the Mullvad VPN app
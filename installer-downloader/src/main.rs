@@ -28,7 +28,10 @@ mod inner {
         super::winapi_impl::main();
 
         #[cfg(target_os = "macos")]
-        super::cacao_impl::main();
+        if let Err(error) = super::cacao_impl::main() {
+            ::log::error!("{}", error.description());
+            std::process::exit(1);
+        }
     }
 }
 
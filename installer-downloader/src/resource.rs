@@ -22,6 +22,9 @@ pub const BETA_LINK_TEXT: &str = "Click here!";
 /// Stable link text
 pub const STABLE_LINK_TEXT: &str = "Back to stable version";
 
+/// Download throttle toggle text
+pub const THROTTLE_TOGGLE_TEXT: &str = "Limit download speed";
+
 /// Dimensions of cancel button (including padding)
 pub const CANCEL_BUTTON_SIZE: (usize, usize) = (150, 40);
 
@@ -71,6 +74,9 @@ pub const DOWNLOADING_DESC_PREFIX: &str = "Downloading from";
 /// Displayed after completed download
 pub const DOWNLOAD_COMPLETE_DESC: &str = "Download complete. Verifying...";
 
+/// The first part of "Verifying... (x%)", displayed while verifying the installer
+pub const VERIFYING_DESC_PREFIX: &str = "Verifying";
+
 /// Displayed when download fails
 pub const DOWNLOAD_FAILED_DESC: &str = "Download failed, please check your internet connection or if you have enough space on your hard drive and try downloading again.";
 
@@ -83,6 +89,12 @@ pub const DOWNLOAD_FAILED_CANCEL_BUTTON_TEXT: &str = "Cancel";
 /// Displayed verifying a cached app installer.
 pub const VERIFYING_CACHED: &str = "Verifying...";
 
+/// Displayed when an installer provided on the command line cannot be used
+pub const OFFLINE_INSTALL_FAILED_DESC: &str = "Failed to load the provided installer, please make sure that the installer and metadata files are valid and try again.";
+
+/// Displayed when the command line arguments are invalid
+pub const INVALID_ARGS_DESC: &str = "Invalid arguments. To install without an internet connection, provide both --installer <path> and --metadata <path>.";
+
 /// Displayed when download fails
 pub const VERIFICATION_FAILED_DESC: &str = "Failed to verify download, please try downloading again or contact our support by sending an email to support@mullvadvpn.net with a description of what happened.";
 
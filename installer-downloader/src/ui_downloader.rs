@@ -1,5 +1,5 @@
-//! This module hooks up [AppDelegate]s to arbitrary implementations of [AppDownloader] and
-//! [fetch::ProgressUpdater].
+//! This module hooks up [AppDelegate]s to arbitrary implementations of [AppDownloader],
+//! [fetch::ProgressUpdater], and [VerifyProgress].
 
 use crate::{
     delegate::{AppDelegate, AppDelegateQueue},
@@ -8,6 +8,11 @@ use crate::{
 use mullvad_update::{
     app::{self, AppDownloader, AppDownloaderParameters, DownloadedInstaller, VerifiedInstaller},
     fetch,
+    verify::VerifyProgress,
+};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
 };
 
 /// [AppDownloader] that delegates the actual work to some underlying `downloader` and uses it to
@@ -16,6 +21,8 @@ pub struct UiAppDownloader<Delegate: AppDelegate + 'static, Downloader> {
     downloader: Downloader,
     /// Queue used to control the app UI
     queue: Delegate::Queue,
+    /// Set once the installer has been launched. See [UiAppDownloader::install_started].
+    install_started: Arc<AtomicBool>,
 }
 
 /// Parameters for [UiAppDownloader]
@@ -27,8 +34,15 @@ impl<Delegate: AppDelegate, Downloader: Send + 'static> UiAppDownloader<Delegate
         Self {
             downloader,
             queue: delegate.queue(),
+            install_started: Arc::default(),
         }
     }
+
+    /// Return a flag that is set once installation begins. This is the point of no return: the
+    /// installer keeps running even if the task driving this downloader is aborted.
+    pub fn install_started(&self) -> Arc<AtomicBool> {
+        self.install_started.clone()
+    }
 }
 
 impl<Delegate: AppDelegate, Downloader: AppDownloader + Send + 'static> AppDownloader
@@ -37,14 +51,15 @@ impl<Delegate: AppDelegate, Downloader: AppDownloader + Send + 'static> AppDownl
     async fn download_executable(self) -> Result<impl DownloadedInstaller, app::DownloadError> {
         match self.downloader.download_executable().await {
             Ok(installer) => {
+                // The download can still be cancelled during verification
                 self.queue.queue_main(move |self_| {
                     self_.set_download_text(resource::DOWNLOAD_COMPLETE_DESC);
-                    self_.disable_cancel_button();
                 });
 
                 Ok(UiAppDownloader::<Delegate, _> {
                     downloader: installer,
                     queue: self.queue,
+                    install_started: self.install_started,
                 })
             }
             Err(err) => {
@@ -71,8 +86,11 @@ impl<Delegate: AppDelegate, Downloader: AppDownloader + Send + 'static> AppDownl
 impl<Delegate: AppDelegate, Downloader: DownloadedInstaller + Send + 'static> DownloadedInstaller
     for UiAppDownloader<Delegate, Downloader>
 {
-    async fn verify(self) -> Result<impl VerifiedInstaller, app::DownloadError> {
-        match self.downloader.verify().await {
+    async fn verify(
+        self,
+        progress: impl VerifyProgress,
+    ) -> Result<impl VerifiedInstaller, app::DownloadError> {
+        match self.downloader.verify(progress).await {
             Ok(verified) => {
                 self.queue.queue_main(move |self_| {
                     self_.set_download_text(resource::VERIFICATION_SUCCEEDED_DESC);
@@ -81,6 +99,7 @@ impl<Delegate: AppDelegate, Downloader: DownloadedInstaller + Send + 'static> Do
                 Ok(UiAppDownloader::<Delegate, _> {
                     downloader: verified,
                     queue: self.queue,
+                    install_started: self.install_started,
                 })
             }
             Err(error) => {
@@ -114,35 +133,55 @@ impl<Delegate: AppDelegate, I: VerifiedInstaller + Send + 'static> VerifiedInsta
     for UiAppDownloader<Delegate, I>
 {
     async fn install(self) -> Result<(), app::DownloadError> {
-        match self.downloader.install().await {
-            Ok(()) => {
-                self.queue.queue_main(move |self_| {
-                    // Success!
-                    self_.quit();
-                });
-                Ok(())
-            }
-            Err(error) => {
-                self.queue.queue_main(move |self_| {
-                    self_.clear_status_text();
-                    self_.clear_download_text();
-                    self_.hide_download_progress();
-                    self_.hide_download_button();
-                    self_.hide_cancel_button();
+        self.queue.queue_main(move |self_| {
+            self_.disable_cancel_button();
+        });
+        self.install_started.store(true, Ordering::SeqCst);
 
-                    self_.show_error_message(crate::delegate::ErrorMessage {
-                        status_text: resource::LAUNCH_FAILED_DESC.to_owned(),
-                        cancel_button_text: resource::LAUNCH_FAILED_CANCEL_BUTTON_TEXT.to_owned(),
-                        retry_button_text: resource::LAUNCH_FAILED_RETRY_BUTTON_TEXT.to_owned(),
+        // This is the point of no return. Run the installer in a separate task so that it is not
+        // interrupted if the download is cancelled.
+        let queue = self.queue;
+        let installer = self.downloader;
+        tokio::spawn(async move {
+            match installer.install().await {
+                Ok(()) => {
+                    queue.queue_main(move |self_| {
+                        // Success!
+                        self_.quit();
                     });
-                });
+                    Ok(())
+                }
+                Err(error) => {
+                    queue.queue_main(move |self_| {
+                        self_.clear_status_text();
+                        self_.clear_download_text();
+                        self_.hide_download_progress();
+                        self_.hide_download_button();
+                        self_.hide_cancel_button();
 
-                Err(error)
+                        self_.show_error_message(crate::delegate::ErrorMessage {
+                            status_text: resource::LAUNCH_FAILED_DESC.to_owned(),
+                            cancel_button_text: resource::LAUNCH_FAILED_CANCEL_BUTTON_TEXT
+                                .to_owned(),
+                            retry_button_text: resource::LAUNCH_FAILED_RETRY_BUTTON_TEXT.to_owned(),
+                        });
+                    });
+
+                    Err(error)
+                }
             }
-        }
+        })
+        .await
+        .unwrap_or_else(|error| Err(install_task_error(error)))
     }
 }
 
+/// Convert a failure to run the installer task into a [app::DownloadError].
+fn install_task_error(error: tokio::task::JoinError) -> app::DownloadError {
+    log::error!("Installer task failed: {error}");
+    app::DownloadError::Launch(std::io::Error::other(error))
+}
+
 /// Implementation of [fetch::ProgressUpdater] that updates some [AppDelegate].
 pub struct UiProgressUpdater<Delegate: AppDelegate> {
     domain: Option<String>,
@@ -223,3 +262,44 @@ impl<Delegate: AppDelegate + 'static> fetch::ProgressUpdater for UiProgressUpdat
         self.domain = Some(domain.to_owned());
     }
 }
+
+/// Implementation of [VerifyProgress] that updates some [AppDelegate].
+pub struct UiVerifyProgress<Delegate: AppDelegate> {
+    prev_progress: Option<u32>,
+    queue: Delegate::Queue,
+}
+
+impl<Delegate: AppDelegate> UiVerifyProgress<Delegate> {
+    pub fn new(queue: Delegate::Queue) -> Self {
+        Self {
+            prev_progress: None,
+            queue,
+        }
+    }
+
+    fn need_update(&mut self, complete: u32) -> bool {
+        if self.prev_progress == Some(complete) {
+            // Unconditionally updating causes flickering
+            return false;
+        }
+        self.prev_progress = Some(complete);
+        true
+    }
+}
+
+impl<Delegate: AppDelegate + 'static> VerifyProgress for UiVerifyProgress<Delegate> {
+    fn set_verify_progress(&mut self, fraction_complete: f32) {
+        let value = UiProgressUpdater::<Delegate>::complete_from_percentage(fraction_complete);
+
+        if !self.need_update(value) {
+            return;
+        }
+
+        let status = format!("{}... ({value}%)", resource::VERIFYING_DESC_PREFIX);
+
+        self.queue.queue_main(move |self_| {
+            self_.set_download_progress(value);
+            self_.set_download_text(&status);
+        });
+    }
+}
@@ -39,6 +39,13 @@ impl AppDelegate for AppWindow {
         register_frame_click_handler(self.stable_message_frame.handle, callback);
     }
 
+    fn on_throttle_toggle<F>(&mut self, callback: F)
+    where
+        F: Fn() + Send + 'static,
+    {
+        register_click_handler(self.window.handle, self.throttle_toggle.handle, callback);
+    }
+
     fn set_status_text(&mut self, text: &str) {
         self.status_text.set_visible(true);
         self.status_text.set_text(text);
@@ -35,8 +35,8 @@ pub fn main() {
 }
 
 fn fatal_environment_error(error: EnvError) -> ! {
-    let content = match error {
-        EnvError::Arch => "Failed to detect CPU architecture",
-    };
-    nwg::fatal_message(installer_downloader::resource::WINDOW_TITLE, content)
+    nwg::fatal_message(
+        installer_downloader::resource::WINDOW_TITLE,
+        error.description(),
+    )
 }
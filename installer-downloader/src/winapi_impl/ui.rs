@@ -15,8 +15,8 @@ use windows_sys::Win32::UI::WindowsAndMessaging::WM_CTLCOLORSTATIC;
 
 use crate::resource::{
     BANNER_DESC, BETA_LINK_TEXT, BETA_PREFACE_DESC, CANCEL_BUTTON_SIZE, CANCEL_BUTTON_TEXT,
-    DOWNLOAD_BUTTON_SIZE, DOWNLOAD_BUTTON_TEXT, STABLE_LINK_TEXT, WINDOW_HEIGHT, WINDOW_TITLE,
-    WINDOW_WIDTH,
+    DOWNLOAD_BUTTON_SIZE, DOWNLOAD_BUTTON_TEXT, STABLE_LINK_TEXT, THROTTLE_TOGGLE_TEXT,
+    WINDOW_HEIGHT, WINDOW_TITLE, WINDOW_WIDTH,
 };
 
 use super::delegate::QueueContext;
@@ -75,6 +75,8 @@ pub struct AppWindow {
     pub stable_prefix: nwg::Label,
     pub stable_link: nwg::Label,
 
+    pub throttle_toggle: nwg::CheckBox,
+
     pub error_view: ErrorView,
 }
 
@@ -239,6 +241,12 @@ impl AppWindow {
             .h_align(nwg::HTextAlign::Left)
             .build(&mut self.stable_link)?;
 
+        nwg::CheckBox::builder()
+            .parent(&self.window)
+            .size((160, 24))
+            .text(THROTTLE_TOGGLE_TEXT)
+            .build(&mut self.throttle_toggle)?;
+
         const PROGRESS_BAR_MARGIN: i32 = 48;
         nwg::ProgressBar::builder()
             .parent(&self.window)
@@ -304,6 +312,11 @@ impl AppWindow {
         );
         handle_link_messages(&self.window.handle, &self.beta_link, BETA_LINK_HANDLER_ID)?;
 
+        self.throttle_toggle.set_position(
+            self.window.size().0 as i32 - 24 - self.throttle_toggle.size().0 as i32,
+            self.beta_prefix.position().1,
+        );
+
         self.window.set_visible(true);
 
         self.error_view.layout(&self.window.handle)?;
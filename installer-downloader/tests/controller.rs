@@ -10,8 +10,9 @@ use insta::assert_yaml_snapshot;
 use installer_downloader::controller::AppController;
 use mock::{
     FAKE_ENVIRONMENT, FakeAppCacheEmpty, FakeAppCacheHappyPath, FakeAppCacheVerifyFail,
-    FakeAppDelegate, FakeAppDownloaderHappyPath, FakeAppDownloaderVerifyFail,
-    FakeDirectoryProvider, FakeVersionInfoProvider,
+    FakeAppDelegate, FakeAppDownloaderHappyPath, FakeAppDownloaderSlowInstall,
+    FakeAppDownloaderSlowVerify, FakeAppDownloaderVerifyFail, FakeDirectoryProvider,
+    FakeVersionInfoProvider, SLOW_STEP_DURATION,
 };
 use std::{
     sync::{Arc, atomic::AtomicBool},
@@ -200,6 +201,139 @@ async fn test_failed_verification() {
     assert_yaml_snapshot!(delegate.state);
 }
 
+/// Test that the download can be cancelled while the installer is being verified
+#[tokio::test(start_paused = true)]
+async fn test_cancel_during_verification() {
+    let mut delegate = FakeAppDelegate::default();
+    AppController::initialize::<
+        _,
+        FakeAppDownloaderSlowVerify,
+        FakeAppCacheEmpty,
+        FakeDirectoryProvider<true>,
+    >(
+        &mut delegate,
+        FakeVersionInfoProvider::default(),
+        FAKE_ENVIRONMENT,
+    );
+
+    // Wait for the version info
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let queue = delegate.queue.clone();
+    queue.run_callbacks(&mut delegate);
+
+    // Initiate download
+    let cb = delegate
+        .download_callback
+        .take()
+        .expect("no download callback registered");
+    cb();
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let queue = delegate.queue.clone();
+    queue.run_callbacks(&mut delegate);
+
+    // Wait for the download to complete. Verification is now in progress
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let queue = delegate.queue.clone();
+    queue.run_callbacks(&mut delegate);
+
+    assert!(delegate.state.cancel_button_visible);
+    assert!(delegate.state.cancel_button_enabled);
+
+    // Cancel verification
+    let cb = delegate
+        .cancel_callback
+        .as_ref()
+        .expect("no cancel callback registered");
+    cb();
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let queue = delegate.queue.clone();
+    queue.run_callbacks(&mut delegate);
+
+    // We should be back at the download screen
+    assert!(delegate.state.download_button_visible);
+    assert!(!delegate.state.cancel_button_visible);
+
+    // The installer should never be launched
+    tokio::time::sleep(SLOW_STEP_DURATION).await;
+
+    let queue = delegate.queue.clone();
+    queue.run_callbacks(&mut delegate);
+
+    assert!(!delegate.state.quit);
+}
+
+/// Test that cancellation is disabled, and ignored, once the installer has been launched
+#[tokio::test(start_paused = true)]
+async fn test_cancel_during_install() {
+    let mut delegate = FakeAppDelegate::default();
+    AppController::initialize::<
+        _,
+        FakeAppDownloaderSlowInstall,
+        FakeAppCacheEmpty,
+        FakeDirectoryProvider<true>,
+    >(
+        &mut delegate,
+        FakeVersionInfoProvider::default(),
+        FAKE_ENVIRONMENT,
+    );
+
+    // Wait for the version info
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let queue = delegate.queue.clone();
+    queue.run_callbacks(&mut delegate);
+
+    // Initiate download
+    let cb = delegate
+        .download_callback
+        .take()
+        .expect("no download callback registered");
+    cb();
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let queue = delegate.queue.clone();
+    queue.run_callbacks(&mut delegate);
+
+    // Wait for the download and verification to complete. Installation is now in progress
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let queue = delegate.queue.clone();
+    queue.run_callbacks(&mut delegate);
+
+    assert!(delegate.state.cancel_button_visible);
+    assert!(!delegate.state.cancel_button_enabled);
+
+    // Attempt to cancel anyway
+    let cb = delegate
+        .cancel_callback
+        .as_ref()
+        .expect("no cancel callback registered");
+    cb();
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let queue = delegate.queue.clone();
+    queue.run_callbacks(&mut delegate);
+
+    // The UI should not have returned to the download screen
+    assert!(!delegate.state.download_button_visible);
+
+    // The installer should finish regardless
+    tokio::time::sleep(SLOW_STEP_DURATION).await;
+
+    let queue = delegate.queue.clone();
+    queue.run_callbacks(&mut delegate);
+
+    assert!(delegate.state.quit);
+}
+
 /// Test failing to create the download directory
 #[tokio::test(start_paused = true)]
 async fn test_failed_directory_creation() {
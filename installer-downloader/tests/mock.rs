@@ -11,6 +11,7 @@ use mullvad_update::app::{
 };
 use mullvad_update::fetch::ProgressUpdater;
 use mullvad_update::format::response::{Response, SignedResponse};
+use mullvad_update::verify::VerifyProgress;
 use mullvad_update::version::{Metadata, VersionInfo, VersionParameters};
 use mullvad_update::version_provider::VersionInfoProvider;
 use mullvad_version::Version;
@@ -19,6 +20,7 @@ use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
 use std::vec::Vec;
 
 /// Fake version info provider
@@ -41,6 +43,7 @@ pub static FAKE_VERSION: LazyLock<VersionInfo> = LazyLock::new(|| VersionInfo {
 
 pub const FAKE_ENVIRONMENT: Environment = Environment {
     architecture: Architecture::X86,
+    offline_install: None,
 };
 
 impl VersionInfoProvider for FakeVersionInfoProvider {
@@ -84,8 +87,17 @@ pub type FakeAppCacheEmpty = FakeAppCache<false, FakeInstaller<true, true, true>
 /// Downloader for which the verification step fails
 pub type FakeAppDownloaderVerifyFail = FakeAppDownloader<true, false, false>;
 
-impl<const A: bool, const B: bool, const C: bool> From<UiAppDownloaderParameters<FakeAppDelegate>>
-    for FakeAppDownloader<A, B, C>
+/// Downloader for which the verification step takes [SLOW_STEP_DURATION] to succeed
+pub type FakeAppDownloaderSlowVerify = FakeAppDownloader<true, true, true, true, false>;
+
+/// Downloader for which the install step takes [SLOW_STEP_DURATION] to succeed
+pub type FakeAppDownloaderSlowInstall = FakeAppDownloader<true, true, true, false, true>;
+
+/// How long a slow verification or install step takes
+pub const SLOW_STEP_DURATION: Duration = Duration::from_secs(10);
+
+impl<const A: bool, const B: bool, const C: bool, const D: bool, const E: bool>
+    From<UiAppDownloaderParameters<FakeAppDelegate>> for FakeAppDownloader<A, B, C, D, E>
 {
     fn from(params: UiAppDownloaderParameters<FakeAppDelegate>) -> Self {
         FakeAppDownloader { params }
@@ -98,10 +110,14 @@ impl<const A: bool, const B: bool, const C: bool> From<UiAppDownloaderParameters
 /// * EXE_SUCCEED - whether fetching the binary succeeds
 /// * VERIFY_SUCCEED - whether verifying the binary succeeds
 /// * LAUNCH_SUCCEED - whether launching the binary succeeds
+/// * SLOW_VERIFY - whether verifying the binary takes [SLOW_STEP_DURATION]
+/// * SLOW_INSTALL - whether launching the binary takes [SLOW_STEP_DURATION]
 pub struct FakeAppDownloader<
     const EXE_SUCCEED: bool,
     const VERIFY_SUCCEED: bool,
     const LAUNCH_SUCCEED: bool,
+    const SLOW_VERIFY: bool = false,
+    const SLOW_INSTALL: bool = false,
 > {
     params: UiAppDownloaderParameters<FakeAppDelegate>,
 }
@@ -116,17 +132,31 @@ pub struct FakeInstaller<
     const EXE_SUCCEED: bool,
     const VERIFY_SUCCEED: bool,
     const LAUNCH_SUCCEED: bool,
+    const SLOW_VERIFY: bool = false,
+    const SLOW_INSTALL: bool = false,
 >;
 
-impl<const EXE_SUCCEED: bool, const VERIFY_SUCCEED: bool, const LAUNCH_SUCCEED: bool> AppDownloader
-    for FakeAppDownloader<EXE_SUCCEED, VERIFY_SUCCEED, LAUNCH_SUCCEED>
+impl<
+    const EXE_SUCCEED: bool,
+    const VERIFY_SUCCEED: bool,
+    const LAUNCH_SUCCEED: bool,
+    const SLOW_VERIFY: bool,
+    const SLOW_INSTALL: bool,
+> AppDownloader
+    for FakeAppDownloader<EXE_SUCCEED, VERIFY_SUCCEED, LAUNCH_SUCCEED, SLOW_VERIFY, SLOW_INSTALL>
 {
     async fn download_executable(mut self) -> Result<impl DownloadedInstaller, DownloadError> {
         self.params.app_progress.set_url(&self.params.app_url);
         self.params.app_progress.clear_progress();
         if EXE_SUCCEED {
             self.params.app_progress.set_progress(1.);
-            Ok(FakeInstaller::<EXE_SUCCEED, VERIFY_SUCCEED, LAUNCH_SUCCEED>)
+            Ok(FakeInstaller::<
+                EXE_SUCCEED,
+                VERIFY_SUCCEED,
+                LAUNCH_SUCCEED,
+                SLOW_VERIFY,
+                SLOW_INSTALL,
+            >)
         } else {
             Err(DownloadError::FetchApp(anyhow::anyhow!(
                 "fetching app failed"
@@ -165,11 +195,24 @@ where
     }
 }
 
-impl<const EXE_SUCCEED: bool, const VERIFY_SUCCEED: bool, const LAUNCH_SUCCEED: bool>
-    DownloadedInstaller for FakeInstaller<EXE_SUCCEED, VERIFY_SUCCEED, LAUNCH_SUCCEED>
+impl<
+    const EXE_SUCCEED: bool,
+    const VERIFY_SUCCEED: bool,
+    const LAUNCH_SUCCEED: bool,
+    const SLOW_VERIFY: bool,
+    const SLOW_INSTALL: bool,
+> DownloadedInstaller
+    for FakeInstaller<EXE_SUCCEED, VERIFY_SUCCEED, LAUNCH_SUCCEED, SLOW_VERIFY, SLOW_INSTALL>
 {
-    async fn verify(self) -> Result<impl VerifiedInstaller, DownloadError> {
+    async fn verify(
+        self,
+        mut progress: impl VerifyProgress,
+    ) -> Result<impl VerifiedInstaller, DownloadError> {
+        if SLOW_VERIFY {
+            tokio::time::sleep(SLOW_STEP_DURATION).await;
+        }
         if VERIFY_SUCCEED {
+            progress.set_verify_progress(1.);
             Ok(self)
         } else {
             Err(DownloadError::Verification(anyhow::anyhow!(
@@ -188,10 +231,19 @@ impl<const EXE_SUCCEED: bool, const VERIFY_SUCCEED: bool, const LAUNCH_SUCCEED:
     }
 }
 
-impl<const EXE_SUCCEED: bool, const VERIFY_SUCCEED: bool, const LAUNCH_SUCCEED: bool>
-    VerifiedInstaller for FakeInstaller<EXE_SUCCEED, VERIFY_SUCCEED, LAUNCH_SUCCEED>
+impl<
+    const EXE_SUCCEED: bool,
+    const VERIFY_SUCCEED: bool,
+    const LAUNCH_SUCCEED: bool,
+    const SLOW_VERIFY: bool,
+    const SLOW_INSTALL: bool,
+> VerifiedInstaller
+    for FakeInstaller<EXE_SUCCEED, VERIFY_SUCCEED, LAUNCH_SUCCEED, SLOW_VERIFY, SLOW_INSTALL>
 {
     async fn install(self) -> Result<(), DownloadError> {
+        if SLOW_INSTALL {
+            tokio::time::sleep(SLOW_STEP_DURATION).await;
+        }
         if LAUNCH_SUCCEED {
             Ok(())
         } else {
@@ -237,6 +289,8 @@ pub struct FakeAppDelegate {
     pub beta_callback: Option<Box<dyn Fn() + Send>>,
     /// Callback registered by `on_stable_link`
     pub stable_callback: Option<Box<dyn Fn() + Send>>,
+    /// Callback registered by `on_throttle_toggle`
+    pub throttle_callback: Option<Box<dyn Fn() + Send>>,
     /// Callback registered by `on_error_cancel`
     pub error_cancel_callback: Option<Box<dyn Fn() + Send>>,
     /// Callback registered by `on_error_retry`
@@ -302,6 +356,14 @@ impl AppDelegate for FakeAppDelegate {
         self.stable_callback = Some(Box::new(callback));
     }
 
+    fn on_throttle_toggle<F>(&mut self, callback: F)
+    where
+        F: Fn() + Send + 'static,
+    {
+        self.state.call_log.push("on_throttle_toggle".into());
+        self.throttle_callback = Some(Box::new(callback));
+    }
+
     fn set_status_text(&mut self, text: &str) {
         self.state.call_log.push(format!("set_status_text: {text}"));
         self.state.status_text = text.to_owned();
@@ -155,7 +155,7 @@ impl AccessTokenStore {
 
     /// Remove an access token if the API response calls for it.
     pub fn check_response<T>(&self, account: &AccountNumber, response: &Result<T, rest::Error>) {
-        if let Err(rest::Error::ApiError(_status, code)) = response
+        if let Err(rest::Error::ApiError(_status, code, _)) = response
             && code == crate::INVALID_ACCESS_TOKEN
         {
             let _ = self
@@ -163,6 +163,13 @@ impl AccessTokenStore {
                 .unbounded_send(StoreAction::InvalidateToken(account.to_owned()));
         }
     }
+
+    /// Forget the access token for an account, so that a new one is requested next time.
+    pub fn invalidate_token(&self, account: &AccountNumber) {
+        let _ = self
+            .tx
+            .unbounded_send(StoreAction::InvalidateToken(account.to_owned()));
+    }
 }
 
 async fn fetch_access_token(
@@ -181,3 +188,165 @@ async fn fetch_access_token(
         .expected_status(&[StatusCode::OK]);
     service.request(rest_request).await?.deserialize().await
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{NullDnsResolver, availability::ApiAvailability, proxy::ApiConnectionMode};
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// How the token server answers a request
+    #[derive(Clone, Copy)]
+    enum Reply {
+        Token,
+        Error,
+        /// Never respond
+        Hang,
+    }
+
+    /// Serve access tokens over plain HTTP, answering the request with index `n` as
+    /// `reply(n)`. Every served token is unique. Returns the address to connect to and a counter
+    /// of received requests.
+    async fn spawn_token_server(
+        reply: fn(usize) -> Reply,
+    ) -> (std::net::SocketAddr, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    let _ = stream.read(&mut buf).await;
+                    let (status, body) = match reply(n) {
+                        Reply::Token => (
+                            "200 OK",
+                            format!(
+                                r#"{{"access_token":"token{n}","expiry":"{}"}}"#,
+                                (chrono::Utc::now() + chrono::TimeDelta::hours(1)).to_rfc3339()
+                            ),
+                        ),
+                        Reply::Error => (
+                            "500 Internal Server Error",
+                            r#"{"code":"INTERNAL_ERROR","error":"Internal error"}"#.to_owned(),
+                        ),
+                        Reply::Hang => {
+                            std::future::pending::<()>().await;
+                            unreachable!()
+                        }
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {status}\r\nconnection: close\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                        body.len()
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        (addr, requests)
+    }
+
+    fn token_store(addr: std::net::SocketAddr) -> AccessTokenStore {
+        let availability = ApiAvailability::default();
+        availability.unsuspend();
+        let service = rest::RequestService::spawn(
+            availability,
+            ApiConnectionMode::Direct.into_provider(),
+            Arc::new(NullDnsResolver),
+            None,
+            None,
+            #[cfg(target_os = "android")]
+            None,
+            true,
+        );
+        AccessTokenStore::new(service, addr.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_token() {
+        let (addr, requests) = spawn_token_server(|_| Reply::Token).await;
+        let store = token_store(addr);
+        let account = "1234123412341234".to_owned();
+
+        assert_eq!(store.get_token(&account).await.unwrap(), "token0");
+        assert_eq!(store.get_token(&account).await.unwrap(), "token0");
+        assert_eq!(requests.load(Ordering::SeqCst), 1, "token should be cached");
+
+        store.invalidate_token(&account);
+        assert_eq!(store.get_token(&account).await.unwrap(), "token1");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    /// Invalidating the token of an account should not affect other accounts, and invalidating
+    /// an account without a token should do nothing
+    #[tokio::test]
+    async fn test_invalidate_other_account() {
+        let (addr, requests) = spawn_token_server(|_| Reply::Token).await;
+        let store = token_store(addr);
+        let account = "1234123412341234".to_owned();
+        let other_account = "5678567856785678".to_owned();
+
+        store.invalidate_token(&other_account);
+        assert_eq!(store.get_token(&account).await.unwrap(), "token0");
+
+        store.invalidate_token(&other_account);
+        assert_eq!(store.get_token(&account).await.unwrap(), "token0");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        assert_eq!(store.get_token(&other_account).await.unwrap(), "token1");
+    }
+
+    /// Invalidating a token while it is being requested should abort the request, and the next
+    /// request should get a new token
+    #[tokio::test]
+    async fn test_invalidate_inflight_request() {
+        let (addr, requests) = spawn_token_server(|n| match n {
+            0 => Reply::Hang,
+            _ => Reply::Token,
+        })
+        .await;
+        let store = token_store(addr);
+        let account = "1234123412341234".to_owned();
+
+        let pending = tokio::spawn({
+            let store = store.clone();
+            let account = account.clone();
+            async move { store.get_token(&account).await }
+        });
+        while requests.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        store.invalidate_token(&account);
+        assert!(matches!(pending.await.unwrap(), Err(rest::Error::Aborted)));
+        assert_eq!(store.get_token(&account).await.unwrap(), "token1");
+    }
+
+    /// A failed token request should be reported to the caller, and not be cached
+    #[tokio::test]
+    async fn test_token_request_error() {
+        let (addr, requests) = spawn_token_server(|n| match n {
+            0 => Reply::Error,
+            _ => Reply::Token,
+        })
+        .await;
+        let store = token_store(addr);
+        let account = "1234123412341234".to_owned();
+
+        assert!(matches!(
+            store.get_token(&account).await,
+            Err(rest::Error::ApiError(status, ..)) if status == StatusCode::INTERNAL_SERVER_ERROR
+        ));
+        assert_eq!(store.get_token(&account).await.unwrap(), "token1");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+}
@@ -12,7 +12,9 @@ use futures::{
     StreamExt,
     channel::{mpsc, oneshot},
 };
-use mullvad_types::access_method::{AccessMethod, AccessMethodSetting, Id, Settings};
+use mullvad_types::access_method::{
+    AccessMethod, AccessMethodSetting, Id, LastSuccessfulAccessMethod, Settings,
+};
 use talpid_types::net::AllowedEndpoint;
 
 pub enum Message {
@@ -24,6 +26,9 @@ pub enum Message {
         ResponseTx<Option<ResolvedConnectionMode>>,
         AccessMethodSetting,
     ),
+    /// A request succeeded using the current access method
+    ReportSuccess,
+    GetLastSuccess(ResponseTx<Option<LastSuccessfulAccessMethod>>),
 }
 
 pub enum AccessMethodEvent {
@@ -107,6 +112,8 @@ impl std::fmt::Display for Message {
             Message::Rotate(_) => f.write_str("Rotate"),
             Message::Update(..) => f.write_str("Update"),
             Message::Resolve(..) => f.write_str("Resolve"),
+            Message::ReportSuccess => f.write_str("ReportSuccess"),
+            Message::GetLastSuccess(_) => f.write_str("GetLastSuccess"),
         }
     }
 }
@@ -176,6 +183,16 @@ impl AccessModeSelectorHandle {
             })
     }
 
+    /// Return the access method which carried the most recent successful API request, if any
+    /// request has succeeded since the daemon started.
+    pub async fn get_last_successful(&self) -> Result<Option<LastSuccessfulAccessMethod>> {
+        self.send_command(Message::GetLastSuccess)
+            .await
+            .inspect_err(|_| {
+                log::debug!("Failed to get the last successful access method");
+            })
+    }
+
     pub async fn rotate(&self) -> Result<ApiConnectionMode> {
         self.send_command(Message::Rotate).await.inspect_err(|_| {
             log::debug!("Failed while getting the next access method");
@@ -218,6 +235,10 @@ impl ConnectionModeProvider for AccessModeConnectionModeProvider {
             handle.rotate().await.ok();
         }
     }
+
+    fn report_success(&self) {
+        let _ = self.handle.cmd_tx.unbounded_send(Message::ReportSuccess);
+    }
 }
 
 /// A small actor which takes care of handling the logic around rotating
@@ -239,6 +260,7 @@ pub struct AccessModeSelector<B: AccessMethodResolver> {
     current: ResolvedConnectionMode,
     /// `index` is used to keep track of the [`AccessMethodSetting`] to use.
     index: usize,
+    last_success: Option<LastSuccessfulAccessMethod>,
 }
 
 impl<B: AccessMethodResolver + 'static> AccessModeSelector<B> {
@@ -277,6 +299,7 @@ impl<B: AccessMethodResolver + 'static> AccessModeSelector<B> {
             connection_mode_provider_sender: change_tx,
             current: initial_connection_mode,
             index,
+            last_success: None,
         };
 
         tokio::spawn(selector.into_future());
@@ -298,6 +321,11 @@ impl<B: AccessMethodResolver + 'static> AccessModeSelector<B> {
                 Message::Rotate(tx) => self.on_next_connection_mode(tx).await,
                 Message::Update(tx, values) => self.on_update_access_methods(tx, values).await,
                 Message::Resolve(tx, setting) => self.on_resolve_access_method(tx, setting).await,
+                Message::ReportSuccess => {
+                    self.on_report_success();
+                    Ok(())
+                }
+                Message::GetLastSuccess(tx) => self.reply(tx, self.last_success.clone()),
             };
             match execution {
                 Ok(_) => (),
@@ -323,6 +351,13 @@ impl<B: AccessMethodResolver + 'static> AccessModeSelector<B> {
         self.reply(tx, self.current.clone())
     }
 
+    fn on_report_success(&mut self) {
+        self.last_success = Some(LastSuccessfulAccessMethod {
+            id: self.current.setting.get_id(),
+            timestamp: chrono::Utc::now(),
+        });
+    }
+
     async fn on_use_access_method(&mut self, tx: ResponseTx<()>, id: Id) -> Result<()> {
         self.use_access_method(id).await;
         self.reply(tx, ())
@@ -2,6 +2,7 @@
 
 use crate::{ApiEndpoint, DnsResolver};
 use async_trait::async_trait;
+use mullvad_types::api::{ApiAddressInfo, ApiAddressSource};
 use std::{io, net::SocketAddr, path::Path, sync::Arc};
 use tokio::{
     fs,
@@ -88,7 +89,17 @@ pub type AddressCache = GenericAddressCache<FileAddressCacheBacking>;
 impl<Backing: AddressCacheBacking> GenericAddressCache<Backing> {
     /// Initialise cache using a hardcoded address and a Backing for writing to
     pub fn new_with_address(endpoint: &ApiEndpoint, backing: Backing) -> Self {
-        Self::new_inner(endpoint.address(), endpoint.host().to_owned(), backing)
+        let source = if !endpoint.address.is_empty() || endpoint.host.is_some() {
+            ApiAddressSource::Override
+        } else {
+            ApiAddressSource::Bundled
+        };
+        Self::new_inner(
+            endpoint.address(),
+            source,
+            endpoint.host().to_owned(),
+            backing,
+        )
     }
 
     /// Initialize cache using the hardcoded address, and write changes to `write_path`.
@@ -104,7 +115,12 @@ impl<Backing: AddressCacheBacking> GenericAddressCache<Backing> {
 
     pub async fn from_backing(hostname: String, backing: Backing) -> Result<Self, Error> {
         let address = read_address_backing(&backing).await?;
-        Ok(Self::new_inner(address, hostname, backing))
+        Ok(Self::new_inner(
+            address,
+            ApiAddressSource::Cached,
+            hostname,
+            backing,
+        ))
     }
 
     /// Initialize cache using `read_path`, and write changes to `write_path`.
@@ -124,8 +140,13 @@ impl<Backing: AddressCacheBacking> GenericAddressCache<Backing> {
         .await
     }
 
-    fn new_inner(address: SocketAddr, hostname: String, backing: Backing) -> Self {
-        let cache = AddressCacheInner::from_address(address);
+    fn new_inner(
+        address: SocketAddr,
+        source: ApiAddressSource,
+        hostname: String,
+        backing: Backing,
+    ) -> Self {
+        let cache = AddressCacheInner { address, source };
         log::debug!("Using API address: {}", cache.address);
 
         Self {
@@ -149,6 +170,15 @@ impl<Backing: AddressCacheBacking> GenericAddressCache<Backing> {
         self.inner.lock().await.address
     }
 
+    /// Returns the currently selected address and where it comes from.
+    pub async fn get_address_info(&self) -> ApiAddressInfo {
+        let inner = self.inner.lock().await;
+        ApiAddressInfo {
+            address: inner.address,
+            source: inner.source,
+        }
+    }
+
     pub async fn set_address(&self, address: SocketAddr) -> Result<(), Error> {
         let mut inner = self.inner.lock().await;
         if address != inner.address {
@@ -157,6 +187,7 @@ impl<Backing: AddressCacheBacking> GenericAddressCache<Backing> {
                 Err(err) => return Err(err),
             };
             inner.address = address;
+            inner.source = ApiAddressSource::Cached;
         }
         Ok(())
     }
@@ -171,12 +202,7 @@ impl<Backing: AddressCacheBacking> GenericAddressCache<Backing> {
 #[derive(Clone, PartialEq, Eq)]
 struct AddressCacheInner {
     address: SocketAddr,
-}
-
-impl AddressCacheInner {
-    fn from_address(address: SocketAddr) -> Self {
-        Self { address }
-    }
+    source: ApiAddressSource,
 }
 
 async fn read_address_backing<T: AddressCacheBacking>(backing: &T) -> Result<SocketAddr, Error> {
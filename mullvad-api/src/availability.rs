@@ -32,6 +32,7 @@ pub struct State {
     pause_background: bool,
     offline: bool,
     inactive: bool,
+    low_power: bool,
 }
 
 impl State {
@@ -46,6 +47,15 @@ impl State {
     pub const fn is_offline(&self) -> bool {
         self.offline
     }
+
+    pub const fn is_low_power(&self) -> bool {
+        self.low_power
+    }
+
+    /// Non-essential background requests are additionally paused in low-power mode.
+    pub const fn is_non_essential_paused(&self) -> bool {
+        self.is_background_paused() || self.low_power
+    }
 }
 
 impl ApiAvailability {
@@ -115,6 +125,12 @@ impl ApiAvailability {
         self.acquire().set_offline(offline);
     }
 
+    /// Enable or disable low-power mode. This pauses non-essential background requests, such as
+    /// version checks, until it is disabled again.
+    pub fn set_low_power(&self, low_power: bool) {
+        self.acquire().set_low_power(low_power);
+    }
+
     fn set_inactive(&self) {
         self.acquire().set_inactive();
     }
@@ -124,6 +140,11 @@ impl ApiAvailability {
         self.get_state().is_offline()
     }
 
+    /// Check if low-power mode is enabled
+    pub fn is_low_power(&self) -> bool {
+        self.get_state().is_low_power()
+    }
+
     fn get_state(&self) -> State {
         self.acquire().state
     }
@@ -143,10 +164,29 @@ impl ApiAvailability {
         }
     }
 
+    /// Run `task` once non-essential background requests are allowed, see
+    /// [Self::wait_non_essential].
+    pub fn when_non_essential<F: Future<Output = O>, O>(
+        &self,
+        task: F,
+    ) -> impl Future<Output = O> + use<F, O> {
+        let wait_task = self.wait_non_essential();
+        async move {
+            let _ = wait_task.await;
+            task.await
+        }
+    }
+
     pub fn wait_background(&self) -> impl Future<Output = Result<(), Error>> + use<> {
         self.wait_for_state(|state| !state.is_background_paused())
     }
 
+    /// Wait until non-essential background requests are allowed. Unlike
+    /// [Self::wait_background], this also waits for low-power mode to be disabled.
+    pub fn wait_non_essential(&self) -> impl Future<Output = Result<(), Error>> + use<> {
+        self.wait_for_state(|state| !state.is_non_essential_paused())
+    }
+
     pub fn when_online<F: Future<Output = O>, O>(
         &self,
         task: F,
@@ -241,6 +281,20 @@ impl ApiAvailabilityState {
         }
     }
 
+    fn set_low_power(&mut self, low_power: bool) {
+        if self.state.low_power != low_power {
+            if low_power {
+                log::debug!("Pausing non-essential API requests due to low-power mode");
+            } else {
+                log::debug!(
+                    "Resuming non-essential API requests since low-power mode was disabled"
+                );
+            }
+            self.state.low_power = low_power;
+            let _ = self.tx.send(self.state);
+        }
+    }
+
     fn pause_background(&mut self) {
         if !self.state.pause_background {
             log::debug!("Pausing background API requests");
@@ -309,4 +363,27 @@ mod test {
             "Inactivity timer should be inactive because 'INACTIVITY_TIME' has passed"
         )
     }
+
+    /// Test that low-power mode only pauses non-essential requests, and that they resume once it
+    /// is disabled.
+    #[tokio::test(start_paused = true)]
+    async fn test_low_power() {
+        let availability = ApiAvailability::default();
+        availability.set_low_power(true);
+
+        let state = availability.get_state();
+        assert!(!state.is_background_paused());
+        assert!(state.is_non_essential_paused());
+
+        let wait = tokio::spawn(availability.wait_non_essential());
+        let task = tokio::spawn(availability.when_non_essential(async { 1 }));
+        tokio::task::yield_now().await;
+        assert!(!wait.is_finished());
+        assert!(!task.is_finished());
+
+        availability.set_low_power(false);
+        wait.await.unwrap().unwrap();
+        assert_eq!(task.await.unwrap(), 1);
+        assert!(!availability.get_state().is_non_essential_paused());
+    }
 }
@@ -74,7 +74,7 @@ impl FfiClient {
 
         let endpoint = ApiEndpoint {
             host: Some(api_hostname.clone()),
-            address: Some(api_address),
+            address: vec![api_address],
             #[cfg(feature = "api-override")]
             force_direct: false,
             #[cfg(any(feature = "api-override", test))]
@@ -4,7 +4,7 @@ use crate::{
     proxy::{ApiConnection, ApiConnectionMode, ProxyConfig},
     tls_stream::TlsStream,
 };
-use futures::{StreamExt, channel::mpsc, future, pin_mut};
+use futures::{StreamExt, channel::mpsc, future, pin_mut, stream::FuturesUnordered};
 #[cfg(target_os = "android")]
 use futures::{channel::oneshot, sink::SinkExt};
 use http::uri::Scheme;
@@ -43,8 +43,17 @@ use tower::Service;
 
 #[cfg(any(feature = "api-override", test))]
 use crate::proxy::ConnectionDecorator;
+#[cfg(feature = "network-simulation")]
+use crate::simulated_stream::SimulatedStream;
+#[cfg(feature = "network-simulation")]
+use mullvad_types::network_simulation::SimulatedNetworkConditions;
+#[cfg(feature = "network-simulation")]
+use rand::{Rng, SeedableRng, rngs::StdRng};
 
 const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long to wait for a connection attempt before also trying the next address, as recommended
+/// by RFC 8305.
+const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
 
 #[derive(Clone)]
 pub struct HttpsConnectorWithSniHandle {
@@ -63,11 +72,33 @@ impl HttpsConnectorWithSniHandle {
             .tx
             .unbounded_send(HttpsConnectorRequest::SetConnectionMode(proxy));
     }
+
+    /// Send `sni_hostname` as the server name in new connections, instead of the hostname of the
+    /// URI, and stop all streams produced by this connector
+    pub fn set_sni_hostname(&self, sni_hostname: Option<String>) {
+        let _ = self
+            .tx
+            .unbounded_send(HttpsConnectorRequest::SetSniHostname(sni_hostname));
+    }
+
+    /// Apply simulated network conditions to the reads and writes of new connections, and stop all
+    /// streams produced by this connector
+    #[cfg(feature = "network-simulation")]
+    pub fn set_simulated_network_conditions(&self, conditions: SimulatedNetworkConditions) {
+        let _ = self
+            .tx
+            .unbounded_send(HttpsConnectorRequest::SetSimulatedNetworkConditions(
+                conditions,
+            ));
+    }
 }
 
 enum HttpsConnectorRequest {
     Reset,
     SetConnectionMode(ApiConnectionMode),
+    SetSniHostname(Option<String>),
+    #[cfg(feature = "network-simulation")]
+    SetSimulatedNetworkConditions(SimulatedNetworkConditions),
 }
 
 #[derive(Clone)]
@@ -84,21 +115,32 @@ enum InnerConnectionMode {
 }
 
 impl InnerConnectionMode {
+    /// Connect to `addrs`, which must not be empty. When connecting directly, all addresses are
+    /// tried. Otherwise, only the first address is passed to the proxy.
+    ///
+    /// If `sni_hostname` is set, it is sent in the ClientHello instead of `hostname`. If
+    /// `address_attempt_timeout` is set, direct connections try the addresses one at a time in
+    /// order instead of racing them.
     async fn connect(
         self,
         hostname: &str,
-        addr: &SocketAddr,
+        sni_hostname: Option<&str>,
+        addrs: &[SocketAddr],
+        address_attempt_timeout: Option<Duration>,
         #[cfg(target_os = "android")] socket_bypass_tx: Option<mpsc::Sender<SocketBypassRequest>>,
         #[cfg(any(feature = "api-override", test))] disable_tls: bool,
     ) -> Result<ApiConnection, std::io::Error> {
+        let addr = &addrs[0];
         match self {
             // Set up a TCP-socket connection.
             InnerConnectionMode::Direct => {
-                let first_hop = *addr;
+                let first_hop = addrs.to_vec();
                 let make_proxy_stream = |tcp_stream| async { Ok(tcp_stream) };
                 Self::connect_proxied(
                     first_hop,
+                    address_attempt_timeout,
                     hostname,
+                    sni_hostname,
                     make_proxy_stream,
                     #[cfg(target_os = "android")]
                     socket_bypass_tx,
@@ -109,7 +151,7 @@ impl InnerConnectionMode {
             }
             // Set up a Shadowsocks-connection.
             InnerConnectionMode::Shadowsocks(shadowsocks) => {
-                let first_hop = shadowsocks.params.peer;
+                let first_hop = vec![shadowsocks.params.peer];
                 let make_proxy_stream = |tcp_stream| async {
                     Ok(ProxyClientStream::from_stream(
                         shadowsocks.proxy_context,
@@ -121,7 +163,9 @@ impl InnerConnectionMode {
                 };
                 Self::connect_proxied(
                     first_hop,
+                    None,
                     hostname,
+                    sni_hostname,
                     make_proxy_stream,
                     #[cfg(target_os = "android")]
                     socket_bypass_tx,
@@ -132,7 +176,7 @@ impl InnerConnectionMode {
             }
             // Set up a SOCKS5-connection.
             InnerConnectionMode::Socks5(socks) => {
-                let first_hop = socks.peer;
+                let first_hop = vec![socks.peer];
                 let make_proxy_stream = |tcp_stream| async {
                     match socks.authentication {
                         None => {
@@ -153,7 +197,9 @@ impl InnerConnectionMode {
                 };
                 Self::connect_proxied(
                     first_hop,
+                    None,
                     hostname,
+                    sni_hostname,
                     make_proxy_stream,
                     #[cfg(target_os = "android")]
                     socket_bypass_tx,
@@ -163,14 +209,16 @@ impl InnerConnectionMode {
                 .await
             }
             InnerConnectionMode::EncryptedDnsProxy(proxy_config) => {
-                let first_hop = SocketAddr::V4(proxy_config.addr);
+                let first_hop = vec![SocketAddr::V4(proxy_config.addr)];
                 let make_proxy_stream = |tcp_stream| async {
                     let forwarder = EncryptedDNSForwarder::from_stream(&proxy_config, tcp_stream);
                     Ok(forwarder)
                 };
                 Self::connect_proxied(
                     first_hop,
+                    None,
                     hostname,
+                    sni_hostname,
                     make_proxy_stream,
                     #[cfg(target_os = "android")]
                     socket_bypass_tx,
@@ -182,7 +230,9 @@ impl InnerConnectionMode {
         }
     }
 
-    /// Create an [`ApiConnection`] from a [`TcpStream`].
+    /// Create an [`ApiConnection`] from a [`TcpStream`] connected to any of `first_hop`. The
+    /// addresses are raced, unless `address_attempt_timeout` is set, in which case they are tried
+    /// in order.
     ///
     /// The `make_proxy_stream` closure receives a [`TcpStream`] and produces a
     /// stream which can send to and receive data from some server using any
@@ -195,8 +245,10 @@ impl InnerConnectionMode {
     /// [`TcpStream`] itself. See for example how a connection is established
     /// from connection mode [`InnerConnectionMode::Direct`].
     async fn connect_proxied<ProxyFactory, ProxyFuture, Proxy>(
-        first_hop: SocketAddr,
+        first_hop: Vec<SocketAddr>,
+        address_attempt_timeout: Option<Duration>,
         hostname: &str,
+        sni_hostname: Option<&str>,
         make_proxy_stream: ProxyFactory,
         #[cfg(target_os = "android")] socket_bypass_tx: Option<mpsc::Sender<SocketBypassRequest>>,
         #[cfg(any(feature = "api-override", test))] disable_tls: bool,
@@ -206,12 +258,19 @@ impl InnerConnectionMode {
         ProxyFuture: Future<Output = io::Result<Proxy>>,
         Proxy: AsyncRead + AsyncWrite + Unpin + Send + 'static,
     {
-        let socket = HttpsConnectorWithSni::open_socket(
-            first_hop,
-            #[cfg(target_os = "android")]
-            socket_bypass_tx,
-        )
-        .await?;
+        let open_socket = |addr| {
+            HttpsConnectorWithSni::open_socket(
+                addr,
+                #[cfg(target_os = "android")]
+                socket_bypass_tx.clone(),
+            )
+        };
+        let socket = match address_attempt_timeout {
+            Some(attempt_timeout) => {
+                connect_in_order(first_hop, attempt_timeout, open_socket).await?
+            }
+            None => race_connection_attempts(first_hop, open_socket).await?,
+        };
 
         let proxy = make_proxy_stream(socket).await?;
 
@@ -220,7 +279,12 @@ impl InnerConnectionMode {
             return Ok(ApiConnection::new(Box::new(ConnectionDecorator(proxy))));
         }
 
-        let tls_stream = TlsStream::connect_https(proxy, hostname).await?;
+        let tls_stream = match sni_hostname {
+            Some(sni_hostname) => {
+                TlsStream::connect_https_with_sni(proxy, hostname, sni_hostname).await?
+            }
+            None => TlsStream::connect_https(proxy, hostname).await?,
+        };
         Ok(ApiConnection::new(Box::new(tls_stream)))
     }
 }
@@ -299,6 +363,8 @@ pub struct HttpsConnectorWithSni {
     inner: Arc<Mutex<HttpsConnectorWithSniInner>>,
     abort_notify: Arc<tokio::sync::Notify>,
     dns_resolver: Arc<dyn DnsResolver>,
+    /// If set, addresses are tried one at a time in order, each for at most this long
+    address_attempt_timeout: Option<Duration>,
     #[cfg(target_os = "android")]
     socket_bypass_tx: Option<mpsc::Sender<SocketBypassRequest>>,
     #[cfg(any(feature = "api-override", test))]
@@ -308,6 +374,13 @@ pub struct HttpsConnectorWithSni {
 struct HttpsConnectorWithSniInner {
     stream_handles: Vec<AbortableStreamHandle>,
     proxy_config: InnerConnectionMode,
+    /// Server name to send in the ClientHello instead of the hostname of the URI
+    sni_hostname: Option<Arc<str>>,
+    #[cfg(feature = "network-simulation")]
+    simulated_conditions: SimulatedNetworkConditions,
+    /// Seeds the RNG of each new stream, so that a seed makes the simulation reproducible
+    #[cfg(feature = "network-simulation")]
+    simulation_rng: StdRng,
 }
 
 #[cfg(target_os = "android")]
@@ -316,6 +389,8 @@ pub type SocketBypassRequest = (RawFd, oneshot::Sender<()>);
 impl HttpsConnectorWithSni {
     pub fn new(
         dns_resolver: Arc<dyn DnsResolver>,
+        sni_hostname: Option<String>,
+        address_attempt_timeout: Option<Duration>,
         #[cfg(target_os = "android")] socket_bypass_tx: Option<mpsc::Sender<SocketBypassRequest>>,
         #[cfg(any(feature = "api-override", test))] disable_tls: bool,
     ) -> (Self, HttpsConnectorWithSniHandle) {
@@ -324,6 +399,11 @@ impl HttpsConnectorWithSni {
         let inner = Arc::new(Mutex::new(HttpsConnectorWithSniInner {
             stream_handles: vec![],
             proxy_config: InnerConnectionMode::Direct,
+            sni_hostname: sni_hostname.map(Arc::from),
+            #[cfg(feature = "network-simulation")]
+            simulated_conditions: SimulatedNetworkConditions::default(),
+            #[cfg(feature = "network-simulation")]
+            simulation_rng: StdRng::from_os_rng(),
         }));
 
         let inner_copy = inner.clone();
@@ -334,20 +414,35 @@ impl HttpsConnectorWithSni {
                 let handles = {
                     let mut inner = inner_copy.lock().unwrap();
 
-                    if let HttpsConnectorRequest::SetConnectionMode(config) = request {
-                        match InnerConnectionMode::try_from(config) {
-                            Ok(config) => {
-                                inner.proxy_config = config;
-                            }
-                            Err(error) => {
-                                log::error!(
-                                    "{}",
-                                    error.display_chain_with_msg(
-                                        "Failed to parse new API proxy config"
-                                    )
-                                );
+                    match request {
+                        HttpsConnectorRequest::Reset => (),
+                        HttpsConnectorRequest::SetConnectionMode(config) => {
+                            match InnerConnectionMode::try_from(config) {
+                                Ok(config) => {
+                                    inner.proxy_config = config;
+                                }
+                                Err(error) => {
+                                    log::error!(
+                                        "{}",
+                                        error.display_chain_with_msg(
+                                            "Failed to parse new API proxy config"
+                                        )
+                                    );
+                                }
                             }
                         }
+                        HttpsConnectorRequest::SetSniHostname(sni_hostname) => {
+                            inner.sni_hostname = sni_hostname.map(Arc::from);
+                        }
+                        #[cfg(feature = "network-simulation")]
+                        HttpsConnectorRequest::SetSimulatedNetworkConditions(conditions) => {
+                            log::debug!("Simulating network conditions: {conditions:?}");
+                            inner.simulated_conditions = conditions;
+                            inner.simulation_rng = match conditions.seed {
+                                Some(seed) => StdRng::seed_from_u64(seed),
+                                None => StdRng::from_os_rng(),
+                            };
+                        }
                     }
 
                     std::mem::take(&mut inner.stream_handles)
@@ -364,6 +459,7 @@ impl HttpsConnectorWithSni {
                 inner,
                 abort_notify,
                 dns_resolver,
+                address_attempt_timeout,
                 #[cfg(target_os = "android")]
                 socket_bypass_tx,
                 #[cfg(any(feature = "api-override", test))]
@@ -399,10 +495,15 @@ impl HttpsConnectorWithSni {
             .map_err(|err| io::Error::new(io::ErrorKind::TimedOut, err))?
     }
 
-    /// Resolve the provided `uri` to an IP and port. If the URI contains an IP, that IP will be used.
+    /// Resolve the provided `uri` to IPs and ports. If the URI contains an IP, that IP will be used.
     /// Otherwise `dns_resolver` will be used as a fallback.
     /// If the URI contains a port, then that port will be used.
-    async fn resolve_address(dns_resolver: &dyn DnsResolver, uri: Uri) -> io::Result<SocketAddr> {
+    ///
+    /// The returned list is never empty.
+    async fn resolve_addresses(
+        dns_resolver: &dyn DnsResolver,
+        uri: Uri,
+    ) -> io::Result<Vec<SocketAddr>> {
         const DEFAULT_PORT: u16 = 443;
 
         let hostname = uri.host().ok_or_else(|| {
@@ -410,20 +511,96 @@ impl HttpsConnectorWithSni {
         })?;
         let port = uri.port_u16();
         if let Ok(addr) = hostname.parse::<IpAddr>() {
-            return Ok(SocketAddr::new(addr, port.unwrap_or(DEFAULT_PORT)));
+            return Ok(vec![SocketAddr::new(addr, port.unwrap_or(DEFAULT_PORT))]);
         }
 
         let addrs = dns_resolver.resolve(hostname.to_owned()).await?;
-        let addr = addrs
-            .first()
-            .ok_or_else(|| io::Error::other("Empty DNS response"))?;
-        let port = match (addr.port(), port) {
-            (_, Some(port)) => port,
-            (0, None) => DEFAULT_PORT,
-            (addr_port, None) => addr_port,
-        };
-        Ok(SocketAddr::new(addr.ip(), port))
+        if addrs.is_empty() {
+            return Err(io::Error::other("Empty DNS response"));
+        }
+        Ok(addrs
+            .into_iter()
+            .map(|addr| {
+                let port = match (addr.port(), port) {
+                    (_, Some(port)) => port,
+                    (0, None) => DEFAULT_PORT,
+                    (addr_port, None) => addr_port,
+                };
+                SocketAddr::new(addr.ip(), port)
+            })
+            .collect())
+    }
+}
+
+/// Connect to any of `addrs` using `connect`, racing the attempts as described in RFC 8305
+/// ("Happy Eyeballs"). Addresses are tried in order, alternating between IPv6 and IPv4 and
+/// starting with IPv6. Each attempt is given a head start of [`CONNECTION_ATTEMPT_DELAY`] before
+/// an attempt to the next address is started, or less if it fails. The first successful
+/// connection is returned, and the other attempts are dropped.
+async fn race_connection_attempts<T, F, Fut>(addrs: Vec<SocketAddr>, connect: F) -> io::Result<T>
+where
+    F: Fn(SocketAddr) -> Fut,
+    Fut: Future<Output = io::Result<T>>,
+{
+    let mut remaining = interleave_address_families(addrs).into_iter();
+    let mut attempts = FuturesUnordered::new();
+    let mut last_error = io::Error::other("No addresses to connect to");
+
+    attempts.extend(remaining.next().map(&connect));
+    while !attempts.is_empty() {
+        let next_attempt_delay = tokio::time::sleep(CONNECTION_ATTEMPT_DELAY);
+        tokio::select! {
+            Some(result) = attempts.next() => match result {
+                Ok(stream) => return Ok(stream),
+                Err(error) => {
+                    last_error = error;
+                    attempts.extend(remaining.next().map(&connect));
+                }
+            },
+            _ = next_attempt_delay, if !remaining.as_slice().is_empty() => {
+                attempts.extend(remaining.next().map(&connect));
+            }
+        }
     }
+    Err(last_error)
+}
+
+/// Connect to the first of `addrs` that accepts a connection using `connect`. The addresses are
+/// tried one at a time in order, and each attempt is given at most `attempt_timeout`.
+async fn connect_in_order<T, F, Fut>(
+    addrs: Vec<SocketAddr>,
+    attempt_timeout: Duration,
+    connect: F,
+) -> io::Result<T>
+where
+    F: Fn(SocketAddr) -> Fut,
+    Fut: Future<Output = io::Result<T>>,
+{
+    let mut last_error = io::Error::other("No addresses to connect to");
+    for addr in addrs {
+        match timeout(attempt_timeout, connect(addr)).await {
+            Ok(Ok(stream)) => return Ok(stream),
+            Ok(Err(error)) => last_error = error,
+            Err(elapsed) => last_error = io::Error::new(io::ErrorKind::TimedOut, elapsed),
+        }
+        log::debug!("Failed to connect to API address {addr}: {last_error}");
+    }
+    Err(last_error)
+}
+
+/// Order `addrs` by alternating between IPv6 and IPv4 addresses, starting with IPv6. The relative
+/// order within each family is kept.
+fn interleave_address_families(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let (v6, v4): (Vec<_>, Vec<_>) = addrs.into_iter().partition(SocketAddr::is_ipv6);
+    let (mut v6, mut v4) = (v6.into_iter(), v4.into_iter());
+    let mut interleaved = Vec::with_capacity(v6.len() + v4.len());
+    loop {
+        match (v6.next(), v4.next()) {
+            (None, None) => break,
+            (v6, v4) => interleaved.extend(v6.into_iter().chain(v4)),
+        }
+    }
+    interleaved
 }
 
 impl fmt::Debug for HttpsConnectorWithSni {
@@ -450,6 +627,7 @@ impl Service<Uri> for HttpsConnectorWithSni {
         #[cfg(target_os = "android")]
         let socket_bypass_tx = self.socket_bypass_tx.clone();
         let dns_resolver = self.dns_resolver.clone();
+        let address_attempt_timeout = self.address_attempt_timeout;
 
         #[cfg(any(feature = "api-override", test))]
         let disable_tls = self.disable_tls;
@@ -467,16 +645,21 @@ impl Service<Uri> for HttpsConnectorWithSni {
                     "invalid url, missing host",
                 ));
             };
-            let addr = Self::resolve_address(&*dns_resolver, uri).await?;
+            let addrs = Self::resolve_addresses(&*dns_resolver, uri).await?;
 
             // Loop until we have established a connection. This starts over if a new endpoint
             // is selected while connecting.
             let stream = loop {
                 let notify = abort_notify.notified();
-                let proxy_config = { inner.lock().unwrap().proxy_config.clone() };
+                let (proxy_config, sni_hostname) = {
+                    let inner = inner.lock().unwrap();
+                    (inner.proxy_config.clone(), inner.sni_hostname.clone())
+                };
                 let stream_fut = proxy_config.connect(
                     &hostname,
-                    &addr,
+                    sni_hostname.as_deref(),
+                    &addrs,
+                    address_attempt_timeout,
                     #[cfg(target_os = "android")]
                     socket_bypass_tx.clone(),
                     #[cfg(any(feature = "api-override", test))]
@@ -493,6 +676,18 @@ impl Service<Uri> for HttpsConnectorWithSni {
                 }
             };
 
+            #[cfg(feature = "network-simulation")]
+            let stream = {
+                let mut inner = inner.lock().unwrap();
+                let conditions = inner.simulated_conditions;
+                if conditions.is_empty() {
+                    stream
+                } else {
+                    let rng = StdRng::seed_from_u64(inner.simulation_rng.random());
+                    ApiConnection::new(Box::new(SimulatedStream::new(stream, conditions, rng)))
+                }
+            };
+
             let (stream, socket_handle) = AbortableStream::new(stream);
 
             {
@@ -506,3 +701,138 @@ impl Service<Uri> for HttpsConnectorWithSni {
         Box::pin(fut)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DEAD_V6: &str = "[2001:db8::1]:443";
+    const LIVE_V4: &str = "192.0.2.1:443";
+
+    /// Connecting to the IPv6 address never completes, like when IPv6 traffic is dropped.
+    async fn connect(addr: SocketAddr) -> io::Result<SocketAddr> {
+        if addr.is_ipv6() {
+            return future::pending().await;
+        }
+        Ok(addr)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fallback_to_ipv4() {
+        let start = tokio::time::Instant::now();
+        let addrs = vec![DEAD_V6.parse().unwrap(), LIVE_V4.parse().unwrap()];
+
+        let connected = race_connection_attempts(addrs, connect).await.unwrap();
+
+        assert_eq!(connected, LIVE_V4.parse().unwrap());
+        assert_eq!(start.elapsed(), CONNECTION_ATTEMPT_DELAY);
+    }
+
+    /// Resolves every hostname to both IPv4 and IPv6 addresses
+    struct DualStackResolver;
+
+    #[async_trait::async_trait]
+    impl DnsResolver for DualStackResolver {
+        async fn resolve(&self, _host: String) -> io::Result<Vec<SocketAddr>> {
+            Ok([
+                "192.0.2.1:0",
+                "192.0.2.2:0",
+                "[2001:db8::1]:0",
+                "[2001:db8::2]:0",
+            ]
+            .iter()
+            .map(|addr| addr.parse().unwrap())
+            .collect())
+        }
+    }
+
+    /// A dual-stack host where the IPv6 attempts stall is reached over IPv4 once the first IPv6
+    /// attempt has had its head start, without waiting for it to time out.
+    #[tokio::test(start_paused = true)]
+    async fn test_dual_stack_ipv6_stalls() {
+        let uri = Uri::from_static("https://api.example.com");
+        let addrs = HttpsConnectorWithSni::resolve_addresses(&DualStackResolver, uri)
+            .await
+            .unwrap();
+
+        let start = tokio::time::Instant::now();
+        let attempts = Mutex::new(vec![]);
+        let connected = race_connection_attempts(addrs, |addr| {
+            attempts.lock().unwrap().push((addr, start.elapsed()));
+            connect(addr)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(connected, "192.0.2.1:443".parse().unwrap());
+        assert_eq!(start.elapsed(), CONNECTION_ATTEMPT_DELAY);
+        assert_eq!(
+            *attempts.lock().unwrap(),
+            [
+                ("[2001:db8::1]:443".parse().unwrap(), Duration::ZERO),
+                ("192.0.2.1:443".parse().unwrap(), CONNECTION_ATTEMPT_DELAY),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fallback_on_error() {
+        let start = tokio::time::Instant::now();
+        let addrs = vec![DEAD_V6.parse().unwrap(), LIVE_V4.parse().unwrap()];
+
+        let connected = race_connection_attempts(addrs, |addr| async move {
+            if addr.is_ipv6() {
+                return Err(io::Error::from(io::ErrorKind::ConnectionRefused));
+            }
+            Ok(addr)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(connected, LIVE_V4.parse().unwrap());
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_all_attempts_fail() {
+        let addrs = vec![LIVE_V4.parse().unwrap()];
+        let result: io::Result<()> = race_connection_attempts(addrs, |_| async {
+            Err(io::Error::from(io::ErrorKind::ConnectionRefused))
+        })
+        .await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::ConnectionRefused);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_connect_in_order() {
+        let attempt_timeout = Duration::from_secs(2);
+        let start = tokio::time::Instant::now();
+        let addrs = vec![DEAD_V6.parse().unwrap(), LIVE_V4.parse().unwrap()];
+
+        let connected = connect_in_order(addrs, attempt_timeout, connect)
+            .await
+            .unwrap();
+
+        assert_eq!(connected, LIVE_V4.parse().unwrap());
+        assert_eq!(start.elapsed(), attempt_timeout);
+
+        let addrs = vec![DEAD_V6.parse().unwrap()];
+        let error = connect_in_order(addrs, attempt_timeout, connect)
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn test_interleave_address_families() {
+        let addrs: Vec<SocketAddr> = ["1.1.1.1:443", "2.2.2.2:443", "[::1]:443", "3.3.3.3:443"]
+            .iter()
+            .map(|addr| addr.parse().unwrap())
+            .collect();
+        let expected: Vec<SocketAddr> = ["[::1]:443", "1.1.1.1:443", "2.2.2.2:443", "3.3.3.3:443"]
+            .iter()
+            .map(|addr| addr.parse().unwrap())
+            .collect();
+        assert_eq!(interleave_address_families(addrs), expected);
+    }
+}
@@ -3,11 +3,17 @@ use async_trait::async_trait;
 #[cfg(target_os = "android")]
 use futures::channel::mpsc;
 use hyper::body::Incoming;
-use mullvad_types::account::{AccountData, AccountNumber, VoucherSubmission};
 #[cfg(target_os = "android")]
 use mullvad_types::account::{PlayPurchase, PlayPurchasePaymentToken};
+use mullvad_types::{
+    account::{AccountData, AccountNumber, VoucherSubmission},
+    api::ApiAddressSource,
+};
 use proxy::{ApiConnectionMode, ConnectionModeProvider};
-use std::{collections::BTreeMap, future::Future, io, net::SocketAddr, path::Path, sync::Arc};
+use std::{
+    collections::BTreeMap, future::Future, io, net::SocketAddr, path::Path, sync::Arc,
+    time::Duration,
+};
 use talpid_types::ErrorExt;
 
 pub mod availability;
@@ -20,9 +26,12 @@ mod abortable_stream;
 pub mod access_mode;
 mod https_client_with_sni;
 pub mod proxy;
+#[cfg(feature = "network-simulation")]
+mod simulated_stream;
 mod tls_stream;
 #[cfg(target_os = "android")]
 pub use crate::https_client_with_sni::SocketBypassRequest;
+pub use crate::tls_stream::is_valid_sni_hostname;
 
 mod access;
 mod address_cache;
@@ -58,6 +67,10 @@ pub const PUBKEY_IN_USE: &str = "PUBKEY_IN_USE";
 
 pub const API_IP_CACHE_FILENAME: &str = "api-ip-address.txt";
 
+/// How long to wait for a connection to one of several overridden API addresses before trying the
+/// next one.
+const API_ADDRESS_ATTEMPT_TIMEOUT: Duration = Duration::from_secs(2);
+
 const ACCOUNTS_URL_PREFIX: &str = "accounts/v1";
 const APP_URL_PREFIX: &str = "app/v1";
 
@@ -78,17 +91,20 @@ pub struct ApiEndpoint {
     /// Use the associated function [`Self::host`] to read this value with a
     /// default fallback if `MULLVAD_API_HOST` was not set.
     pub host: Option<String>,
-    /// An overriden API address. Initialized with the value of the environment
-    /// variable `MULLVAD_API_ADDR` if it has been set.
+    /// Overriden API addresses. Initialized with the comma-separated addresses in
+    /// the environment variable `MULLVAD_API_ADDR` if it has been set.
     ///
-    /// Use the associated function [`Self::address()`] to read this value with
-    /// a default fallback if `MULLVAD_API_ADDR` was not set.
+    /// Use the associated functions [`Self::address()`] and [`Self::addresses()`]
+    /// to read this value with a default fallback if `MULLVAD_API_ADDR` was not set.
     ///
     /// # Note
     ///
-    /// If [`Self::address`] is populated with [`Some(SocketAddr)`], it should
-    /// always be respected when establishing API connections.
-    pub address: Option<SocketAddr>,
+    /// If [`Self::address`] is not empty, it should always be respected when
+    /// establishing API connections. If it contains several addresses, they are
+    /// tried in order. Proxied connections only use the first address. The
+    /// daemon refuses to start with several addresses, since only one address
+    /// can be allowed in the firewall.
+    pub address: Vec<SocketAddr>,
     #[cfg(any(feature = "api-override", test))]
     pub disable_tls: bool,
     #[cfg(feature = "api-override")]
@@ -109,23 +125,64 @@ pub struct ApiEndpoint {
     pub force_direct: bool,
 }
 
+/// An environment variable used to configure the [`ApiEndpoint`] has invalid contents.
+#[derive(thiserror::Error, Debug)]
+pub enum EnvError {
+    #[error("{var} does not contain valid UTF-8")]
+    NotUnicode { var: &'static str },
+
+    #[error("Unable to resolve API IP address from {var}={host}")]
+    UnresolvableHost {
+        var: &'static str,
+        host: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("{var}={value} is not a valid socketaddr")]
+    InvalidSocketAddr {
+        var: &'static str,
+        value: String,
+        #[source]
+        source: std::net::AddrParseError,
+    },
+}
+
+impl EnvError {
+    /// The name of the offending environment variable
+    pub fn var(&self) -> &'static str {
+        match self {
+            EnvError::NotUnicode { var }
+            | EnvError::UnresolvableHost { var, .. }
+            | EnvError::InvalidSocketAddr { var, .. } => var,
+        }
+    }
+}
+
 impl ApiEndpoint {
     /// Returns the endpoint to connect to the API over.
     ///
     /// # Panics
     ///
     /// Panics if `MULLVAD_API_ADDR`, `MULLVAD_API_HOST` or
-    /// `MULLVAD_API_DISABLE_TLS` has invalid contents.
-    #[cfg(feature = "api-override")]
+    /// `MULLVAD_API_DISABLE_TLS` has invalid contents. See [`Self::try_from_env_vars`] for a
+    /// non-panicking alternative.
     pub fn from_env_vars() -> ApiEndpoint {
-        let host_var = Self::read_var(env::API_HOST_VAR);
-        let address_var = Self::read_var(env::API_ADDR_VAR);
-        let disable_tls_var = Self::read_var(env::DISABLE_TLS_VAR);
-        let force_direct = Self::read_var(env::API_FORCE_DIRECT_VAR);
+        Self::try_from_env_vars().unwrap_or_else(|error| panic!("{}", error.display_chain()))
+    }
+
+    /// Returns the endpoint to connect to the API over, or an error if `MULLVAD_API_ADDR`,
+    /// `MULLVAD_API_HOST` or `MULLVAD_API_DISABLE_TLS` has invalid contents.
+    #[cfg(feature = "api-override")]
+    pub fn try_from_env_vars() -> Result<ApiEndpoint, EnvError> {
+        let host_var = Self::read_var(env::API_HOST_VAR)?;
+        let address_var = Self::read_var(env::API_ADDR_VAR)?;
+        let disable_tls_var = Self::read_var(env::DISABLE_TLS_VAR)?;
+        let force_direct = Self::read_var(env::API_FORCE_DIRECT_VAR)?;
 
         let mut api = ApiEndpoint {
             host: None,
-            address: None,
+            address: vec![],
             disable_tls: false,
             force_direct: force_direct
                 .map(|force_direct| force_direct != "0")
@@ -143,27 +200,22 @@ impl ApiEndpoint {
                 );
                 api.address = format!("{host}:{API_PORT_DEFAULT}")
                     .to_socket_addrs()
-                    .unwrap_or_else(|_| {
-                        panic!(
-                            "Unable to resolve API IP address from host {host}:{API_PORT_DEFAULT}"
-                        )
-                    })
-                    .next();
+                    .map_err(|source| EnvError::UnresolvableHost {
+                        var: env::API_HOST_VAR,
+                        host: host.clone(),
+                        source,
+                    })?
+                    .take(1)
+                    .collect();
                 api.host = Some(host);
             }
             (host, Some(address)) => {
-                let addr = address.parse().unwrap_or_else(|_| {
-                    panic!(
-                        "{api_addr}={address} is not a valid socketaddr",
-                        api_addr = env::API_ADDR_VAR,
-                    )
-                });
-                api.address = Some(addr);
+                api.address = Self::parse_addresses(env::API_ADDR_VAR, &address)?;
                 api.host = host;
             }
         }
 
-        if api.host.is_none() && api.address.is_none() {
+        if api.host.is_none() && api.address.is_empty() {
             if disable_tls_var.is_some() {
                 log::warn!(
                     "{disable_tls} is ignored since {api_host} and {api_addr} are not set",
@@ -181,7 +233,12 @@ impl ApiEndpoint {
             log::debug!(
                 "Overriding API. Using {host} at {scheme}{addr} (force direct={direct})",
                 host = api.host(),
-                addr = api.address(),
+                addr = api
+                    .addresses()
+                    .iter()
+                    .map(SocketAddr::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
                 scheme = if api.disable_tls {
                     "http://"
                 } else {
@@ -190,22 +247,35 @@ impl ApiEndpoint {
                 direct = api.force_direct,
             );
         }
-        api
+        Ok(api)
     }
 
     #[cfg(feature = "api-override")]
     pub fn should_disable_address_cache(&self) -> bool {
-        self.host.is_some() || self.address.is_some()
+        self.host.is_some() || !self.address.is_empty()
     }
 
-    /// Returns the endpoint to connect to the API over.
-    ///
-    /// # Panics
-    ///
-    /// Panics if `MULLVAD_API_ADDR`, `MULLVAD_API_HOST` or
-    /// `MULLVAD_API_DISABLE_TLS` has invalid contents.
+    /// Parse a comma-separated list of socket addresses, such as `1.2.3.4:443,[::1]:443`, from
+    /// the environment variable `var`. Every entry must be a valid address.
+    #[cfg(any(feature = "api-override", test))]
+    fn parse_addresses(var: &'static str, value: &str) -> Result<Vec<SocketAddr>, EnvError> {
+        value
+            .split(',')
+            .map(|entry| {
+                let entry = entry.trim();
+                entry.parse().map_err(|source| EnvError::InvalidSocketAddr {
+                    var,
+                    value: entry.to_owned(),
+                    source,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the endpoint to connect to the API over, or an error if `MULLVAD_API_ADDR`,
+    /// `MULLVAD_API_HOST` or `MULLVAD_API_DISABLE_TLS` has invalid contents.
     #[cfg(not(feature = "api-override"))]
-    pub fn from_env_vars() -> ApiEndpoint {
+    pub fn try_from_env_vars() -> Result<ApiEndpoint, EnvError> {
         let env_vars = [
             env::API_HOST_VAR,
             env::API_ADDR_VAR,
@@ -213,19 +283,23 @@ impl ApiEndpoint {
             env::API_FORCE_DIRECT_VAR,
         ];
 
-        if env_vars.map(Self::read_var).iter().any(Option::is_some) {
+        let mut is_set = false;
+        for var in env_vars {
+            is_set |= Self::read_var(var)?.is_some();
+        }
+        if is_set {
             log::warn!(
                 "These variables are ignored in production builds: {env_vars_pretty}",
                 env_vars_pretty = env_vars.join(", ")
             );
         }
 
-        ApiEndpoint {
+        Ok(ApiEndpoint {
             host: None,
-            address: None,
+            address: vec![],
             #[cfg(test)]
             disable_tls: false,
-        }
+        })
     }
 
     /// Returns a new API endpoint with the given host and socket address.
@@ -236,7 +310,7 @@ impl ApiEndpoint {
     ) -> Self {
         Self {
             host: Some(host),
-            address: Some(address),
+            address: vec![address],
             #[cfg(any(feature = "api-override", test))]
             disable_tls,
             #[cfg(feature = "api-override")]
@@ -245,7 +319,7 @@ impl ApiEndpoint {
     }
 
     pub fn set_addr(&mut self, address: SocketAddr) {
-        self.address = Some(address);
+        self.address = vec![address];
     }
 
     /// Read the [`Self::host`] value, falling back to
@@ -254,26 +328,34 @@ impl ApiEndpoint {
         self.host.as_deref().unwrap_or(API_HOST_DEFAULT)
     }
 
-    /// Read the [`Self::address`] value, falling back to
+    /// Read the first [`Self::address`] value, falling back to
     /// [`Self::API_IP_DEFAULT`] as default value if it does not exist.
     pub fn address(&self) -> SocketAddr {
         self.address
+            .first()
+            .copied()
             .unwrap_or(SocketAddr::new(API_IP_DEFAULT, API_PORT_DEFAULT))
     }
 
+    /// Read all [`Self::address`] values in the order they should be tried, falling back to
+    /// [`Self::API_IP_DEFAULT`] as default value if there are none.
+    pub fn addresses(&self) -> Vec<SocketAddr> {
+        if self.address.is_empty() {
+            vec![self.address()]
+        } else {
+            self.address.clone()
+        }
+    }
+
     /// Try to read the value of an environment variable. Returns `None` if the
-    /// environment variable has not been set.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the environment variable was found, but it did not contain
+    /// environment variable has not been set, and an error if it did not contain
     /// valid unicode data.
-    fn read_var(key: &'static str) -> Option<String> {
+    fn read_var(key: &'static str) -> Result<Option<String>, EnvError> {
         use std::env;
         match env::var(key) {
-            Ok(v) => Some(v),
-            Err(env::VarError::NotPresent) => None,
-            Err(env::VarError::NotUnicode(_)) => panic!("{key} does not contain valid UTF-8"),
+            Ok(v) => Ok(Some(v)),
+            Err(env::VarError::NotPresent) => Ok(None),
+            Err(env::VarError::NotUnicode(_)) => Err(EnvError::NotUnicode { var: key }),
         }
     }
 }
@@ -309,12 +391,31 @@ impl DnsResolver for NullDnsResolver {
     }
 }
 
+/// DNS resolver that resolves the API hostname to a fixed list of addresses, and any other
+/// hostname using `fallback`.
+struct ApiAddressesResolver {
+    hostname: String,
+    addresses: Vec<SocketAddr>,
+    fallback: Arc<dyn DnsResolver>,
+}
+
+#[async_trait]
+impl DnsResolver for ApiAddressesResolver {
+    async fn resolve(&self, host: String) -> io::Result<Vec<SocketAddr>> {
+        if host.eq_ignore_ascii_case(&self.hostname) {
+            return Ok(self.addresses.clone());
+        }
+        self.fallback.resolve(host).await
+    }
+}
+
 /// A type that helps with the creation of API connections.
 pub struct Runtime {
     handle: tokio::runtime::Handle,
     address_cache: AddressCache,
     api_availability: availability::ApiAvailability,
     endpoint: ApiEndpoint,
+    sni_hostname: Option<String>,
     #[cfg(target_os = "android")]
     socket_bypass_tx: Option<mpsc::Sender<SocketBypassRequest>>,
 }
@@ -332,6 +433,9 @@ pub enum Error {
 
     #[error("DNS resolution error")]
     ResolutionFailed(#[from] std::io::Error),
+
+    #[error("The API returned no API addresses")]
+    NoApiAddresses,
 }
 
 impl Runtime {
@@ -346,6 +450,7 @@ impl Runtime {
             address_cache: AddressCache::new(endpoint, None),
             api_availability: ApiAvailability::default(),
             endpoint: endpoint.clone(),
+            sni_hostname: None,
             #[cfg(target_os = "android")]
             socket_bypass_tx,
         }
@@ -407,11 +512,22 @@ impl Runtime {
             address_cache,
             api_availability,
             endpoint: endpoint.clone(),
+            sni_hostname: None,
             #[cfg(target_os = "android")]
             socket_bypass_tx,
         })
     }
 
+    /// Send `sni_hostname` as the server name when connecting to the API, instead of the API
+    /// hostname. This may help on networks that block connections by server name.
+    ///
+    /// The server certificate is still verified against the API hostname. This only affects
+    /// services created after this is called. Use [rest::RequestServiceHandle::set_sni_hostname]
+    /// to change it for an existing service.
+    pub fn set_sni_hostname(&mut self, sni_hostname: Option<String>) {
+        self.sni_hostname = sni_hostname;
+    }
+
     /// Returns a request factory initialized to create requests for the master API Assumes an API
     /// endpoint that is constructed from env vars, or uses default values.
     pub fn mullvad_rest_handle<T: ConnectionModeProvider + 'static>(
@@ -421,6 +537,7 @@ impl Runtime {
         let service = self.new_request_service(
             connection_mode_provider,
             Arc::new(self.address_cache.clone()),
+            self.sni_hostname.clone(),
             #[cfg(target_os = "android")]
             self.socket_bypass_tx.clone(),
             #[cfg(any(feature = "api-override", test))]
@@ -438,6 +555,7 @@ impl Runtime {
         self.new_request_service(
             ApiConnectionMode::Direct.into_provider(),
             Arc::new(dns_resolver),
+            None,
             #[cfg(target_os = "android")]
             None,
             #[cfg(any(feature = "api-override", test))]
@@ -446,17 +564,34 @@ impl Runtime {
     }
 
     /// Creates a new request service and returns a handle to it.
+    ///
+    /// If the endpoint has several overridden addresses, they are tried one at a time in order,
+    /// each for at most [`API_ADDRESS_ATTEMPT_TIMEOUT`].
     fn new_request_service<T: ConnectionModeProvider + 'static>(
         &self,
         connection_mode_provider: T,
         dns_resolver: Arc<dyn DnsResolver>,
+        sni_hostname: Option<String>,
         #[cfg(target_os = "android")] socket_bypass_tx: Option<mpsc::Sender<SocketBypassRequest>>,
         #[cfg(any(feature = "api-override", test))] disable_tls: bool,
     ) -> rest::RequestServiceHandle {
+        let (dns_resolver, address_attempt_timeout) = if self.endpoint.address.len() > 1 {
+            let resolver: Arc<dyn DnsResolver> = Arc::new(ApiAddressesResolver {
+                hostname: self.endpoint.host().to_owned(),
+                addresses: self.endpoint.address.clone(),
+                fallback: dns_resolver,
+            });
+            (resolver, Some(API_ADDRESS_ATTEMPT_TIMEOUT))
+        } else {
+            (dns_resolver, None)
+        };
+
         rest::RequestService::spawn(
             self.api_availability.clone(),
             connection_mode_provider,
             dns_resolver,
+            sni_hostname,
+            address_attempt_timeout,
             #[cfg(target_os = "android")]
             socket_bypass_tx,
             #[cfg(any(feature = "api-override", test))]
@@ -759,8 +894,13 @@ impl ApiProxy {
         Self { handle }
     }
 
+    /// Fetch the current API addresses, no faster than the background download limit
     pub async fn get_api_addrs(&self) -> Result<Vec<SocketAddr>, rest::Error> {
-        self.get_api_addrs_response().await?.deserialize().await
+        let download_limit = self.handle.service.background_download_limit();
+        self.get_api_addrs_response()
+            .await?
+            .deserialize_with_rate_limit(download_limit)
+            .await
     }
 
     pub async fn get_api_addrs_response(&self) -> Result<rest::Response<Incoming>, rest::Error> {
@@ -773,6 +913,19 @@ impl ApiProxy {
         self.handle.service.request(request).await
     }
 
+    /// Fetch the current API addresses and store the first one in `address_cache`, so that new
+    /// connections use it. An overridden address is left as is. Returns the address in use.
+    pub async fn refresh_address(&self, address_cache: &AddressCache) -> Result<SocketAddr, Error> {
+        let info = address_cache.get_address_info().await;
+        if info.source == ApiAddressSource::Override {
+            return Ok(info.address);
+        }
+        let addresses = self.get_api_addrs().await?;
+        let address = *addresses.first().ok_or(Error::NoApiAddresses)?;
+        address_cache.set_address(address).await?;
+        Ok(address)
+    }
+
     /// Check the availablility of `{APP_URL_PREFIX}/api-addrs`.
     pub async fn api_addrs_available(&self) -> Result<bool, rest::Error> {
         let request = self
@@ -784,4 +937,146 @@ impl ApiProxy {
         let response = self.handle.service.request(request).await?;
         Ok(response.status().is_success())
     }
+
+    /// Return the time reported in the `Date` header of a response from the API.
+    pub async fn get_server_time(&self) -> Result<chrono::DateTime<chrono::Utc>, rest::Error> {
+        let request = self
+            .handle
+            .factory
+            .head(&format!("{APP_URL_PREFIX}/api-addrs"))?
+            .expected_status(&[StatusCode::OK]);
+
+        let response = self.handle.service.request(request).await?;
+        response
+            .headers()
+            .get(hyper::header::DATE)
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| chrono::DateTime::parse_from_rfc2822(date).ok())
+            .map(|date| date.to_utc())
+            .ok_or(rest::Error::InvalidHeaderError)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mockito::Server;
+
+    #[test]
+    fn test_parse_addresses() {
+        let addresses = ApiEndpoint::parse_addresses("VAR", "1.2.3.4:443,[::1]:443").unwrap();
+        assert_eq!(
+            addresses,
+            vec![
+                "1.2.3.4:443".parse::<SocketAddr>().unwrap(),
+                "[::1]:443".parse::<SocketAddr>().unwrap(),
+            ]
+        );
+
+        let single = ApiEndpoint::parse_addresses("VAR", "1.2.3.4:443").unwrap();
+        assert_eq!(single, vec!["1.2.3.4:443".parse::<SocketAddr>().unwrap()]);
+
+        for malformed in ["1.2.3.4:443,", "1.2.3.4:443,::1", "1.2.3.4", ""] {
+            assert!(
+                matches!(
+                    ApiEndpoint::parse_addresses("VAR", malformed),
+                    Err(EnvError::InvalidSocketAddr { var: "VAR", .. })
+                ),
+                "{malformed:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_endpoint_addresses() {
+        let endpoint = ApiEndpoint {
+            host: None,
+            address: vec!["1.2.3.4:443".parse().unwrap(), "[::1]:443".parse().unwrap()],
+            disable_tls: false,
+            #[cfg(feature = "api-override")]
+            force_direct: false,
+        };
+        assert_eq!(endpoint.address(), "1.2.3.4:443".parse().unwrap());
+        assert_eq!(endpoint.addresses(), endpoint.address);
+
+        let endpoint = ApiEndpoint {
+            address: vec![],
+            ..endpoint
+        };
+        assert_eq!(
+            endpoint.addresses(),
+            vec![SocketAddr::new(API_IP_DEFAULT, API_PORT_DEFAULT)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_address_source() {
+        let endpoint = ApiEndpoint {
+            host: None,
+            address: vec![],
+            disable_tls: false,
+            #[cfg(feature = "api-override")]
+            force_direct: false,
+        };
+        let info = AddressCache::new(&endpoint, None).get_address_info().await;
+        assert_eq!(info.source, ApiAddressSource::Bundled);
+
+        // Overriding only the hostname is still an override
+        let endpoint = ApiEndpoint {
+            host: Some("api.example.com".to_owned()),
+            ..endpoint
+        };
+        let info = AddressCache::new(&endpoint, None).get_address_info().await;
+        assert_eq!(info.address, endpoint.address());
+        assert_eq!(info.source, ApiAddressSource::Override);
+
+        let endpoint = ApiEndpoint {
+            host: None,
+            address: vec!["192.0.2.1:443".parse().unwrap()],
+            ..endpoint
+        };
+        let info = AddressCache::new(&endpoint, None).get_address_info().await;
+        assert_eq!(info.source, ApiAddressSource::Override);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_address() {
+        let mut server = Server::new_async().await;
+        let new_address: SocketAddr = "192.0.2.1:443".parse().unwrap();
+        let api_addrs = server
+            .mock("GET", &*format!("/{APP_URL_PREFIX}/api-addrs"))
+            .with_header("content-type", "application/json")
+            .with_body(format!(r#"["{new_address}"]"#))
+            .expect(1)
+            .create_async()
+            .await;
+
+        let endpoint = ApiEndpoint::new("localhost".to_owned(), server.socket_address(), true);
+        let runtime = Runtime::new(
+            tokio::runtime::Handle::current(),
+            &endpoint,
+            #[cfg(target_os = "android")]
+            None,
+        );
+        runtime.availability_handle().unsuspend();
+        let api_proxy =
+            ApiProxy::new(runtime.mullvad_rest_handle(ApiConnectionMode::Direct.into_provider()));
+
+        let address_cache = AddressCache::new(&ApiEndpoint::from_env_vars(), None);
+        assert_eq!(
+            api_proxy.refresh_address(&address_cache).await.unwrap(),
+            new_address
+        );
+        let info = address_cache.get_address_info().await;
+        assert_eq!(info.address, new_address);
+        assert_eq!(info.source, ApiAddressSource::Cached);
+
+        // An overridden address is not re-resolved
+        let overridden = runtime.address_cache();
+        assert_eq!(
+            api_proxy.refresh_address(overridden).await.unwrap(),
+            server.socket_address()
+        );
+        api_addrs.assert_async().await;
+    }
 }
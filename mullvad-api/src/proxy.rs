@@ -27,6 +27,9 @@ pub trait ConnectionModeProvider: Send {
 
     /// Receive changes to the connection mode, announced by the provider
     fn receive(&mut self) -> impl std::future::Future<Output = Option<ApiConnectionMode>> + Send;
+
+    /// Report that a request succeeded using the current connection mode
+    fn report_success(&self) {}
 }
 
 pub struct StaticConnectionModeProvider {
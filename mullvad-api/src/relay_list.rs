@@ -33,12 +33,13 @@ impl RelayListProxy {
         Self { handle }
     }
 
-    /// Fetch the relay list
+    /// Fetch the relay list, no faster than the background download limit
     pub fn relay_list(
         &self,
         prev_etag: Option<ETag>,
     ) -> impl Future<Output = Result<Option<CachedRelayList>, rest::Error>> {
         let request = self.relay_list_response(prev_etag.clone());
+        let download_limit = self.handle.service.background_download_limit();
 
         async move {
             let response = request.await?;
@@ -51,8 +52,10 @@ impl RelayListProxy {
                 _ => {
                     // If the API returns a response, it *should* contain an ETag. But this might not be the case.
                     let etag = Self::extract_etag(&response);
-                    let relay_list: ServerRelayList =
-                        response.deserialize().await.inspect_err(|_err| {
+                    let relay_list: ServerRelayList = response
+                        .deserialize_with_rate_limit(download_limit)
+                        .await
+                        .inspect_err(|_err| {
                             log::error!("Failed to deserialize API response of relay list")
                         })?;
 
@@ -398,6 +401,8 @@ impl WireGuardRelay {
             shadowsocks_extra_addr_in: HashSet::from_iter(self.shadowsocks_extra_addr_in),
             quic: self.features.quic.map(relay_list::Quic::from),
             lwo: self.features.lwo.is_some(),
+            ipv4_addr_in: None,
+            ipv6_addr_in: None,
         };
 
         relay_list::WireguardRelay::new(
@@ -18,16 +18,27 @@ use hyper::{
     header::{self, HeaderValue},
 };
 use hyper_util::client::legacy::connect::Connect;
-use mullvad_types::account::AccountNumber;
+#[cfg(feature = "network-simulation")]
+use mullvad_types::network_simulation::SimulatedNetworkConditions;
+use mullvad_types::{
+    account::AccountNumber,
+    api::{ApiErrorResponse, ApiRequestLimit, InflightApiRequest},
+};
 use std::{
     borrow::Cow,
+    collections::BTreeMap,
     convert::Infallible,
     error::Error as StdError,
+    num::NonZeroU32,
     str::FromStr,
-    sync::{Arc, Weak},
-    time::Duration,
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::{Duration, Instant},
 };
 use talpid_types::ErrorExt;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, TryAcquireError};
 
 pub use hyper::StatusCode;
 
@@ -35,6 +46,10 @@ const USER_AGENT: &str = "mullvad-app";
 
 pub type Result<T> = std::result::Result<T, Error>;
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Maximum number of bytes of an error response body that is retained in [`Error::ApiError`].
+pub const MAX_RAW_ERROR_BODY_SIZE: usize = 4 * 1024;
+/// Maximum number of bytes of an error response body that is read. The rest is discarded.
+const MAX_ERROR_BODY_SIZE: usize = 64 * 1024;
 
 /// Describes all the ways a REST request can fail
 #[derive(thiserror::Error, Debug, Clone)]
@@ -63,9 +78,10 @@ pub enum Error {
     #[error("Failed to deserialize data")]
     DeserializeError(#[from] Arc<serde_json::Error>),
 
-    /// Unexpected response code
+    /// Unexpected response code. The raw response body is retained, truncated to
+    /// [`MAX_RAW_ERROR_BODY_SIZE`] bytes, if the response had one.
     #[error("Unexpected response status code {0} - {1}")]
-    ApiError(StatusCode, String),
+    ApiError(StatusCode, String, Option<Bytes>),
 
     /// The string given was not a valid URI.
     #[error("Not a valid URI {0}")]
@@ -149,11 +165,15 @@ type RequestClient =
 pub(crate) struct RequestService<T: ConnectionModeProvider> {
     command_tx: Weak<mpsc::UnboundedSender<RequestCommand>>,
     command_rx: mpsc::UnboundedReceiver<RequestCommand>,
+    connector: HttpsConnectorWithSni,
     connector_handle: HttpsConnectorWithSniHandle,
     client: RequestClient,
     connection_mode_provider: T,
     connection_mode_generation: usize,
     api_availability: ApiAvailability,
+    inflight_requests: InflightRequests,
+    last_api_error: Arc<Mutex<Option<ApiErrorResponse>>>,
+    concurrency_limiter: ConcurrencyLimiter,
 }
 
 impl<T: ConnectionModeProvider + 'static> RequestService<T> {
@@ -162,11 +182,15 @@ impl<T: ConnectionModeProvider + 'static> RequestService<T> {
         api_availability: ApiAvailability,
         connection_mode_provider: T,
         dns_resolver: Arc<dyn DnsResolver>,
+        sni_hostname: Option<String>,
+        address_attempt_timeout: Option<Duration>,
         #[cfg(target_os = "android")] socket_bypass_tx: Option<mpsc::Sender<SocketBypassRequest>>,
         #[cfg(any(feature = "api-override", test))] disable_tls: bool,
     ) -> RequestServiceHandle {
         let (connector, connector_handle) = HttpsConnectorWithSni::new(
             dns_resolver,
+            sni_hostname,
+            address_attempt_timeout,
             #[cfg(target_os = "android")]
             socket_bypass_tx.clone(),
             #[cfg(any(feature = "api-override", test))]
@@ -176,26 +200,43 @@ impl<T: ConnectionModeProvider + 'static> RequestService<T> {
         connector_handle.set_connection_mode(connection_mode_provider.initial());
 
         let (command_tx, command_rx) = mpsc::unbounded();
-        let client =
-            hyper_util::client::legacy::Client::builder(hyper_util::rt::TokioExecutor::new())
-                .build(connector);
+        let client = Self::new_client(connector.clone());
 
         let command_tx = Arc::new(command_tx);
+        let inflight_requests = InflightRequests::default();
+        let last_api_error = Arc::new(Mutex::new(None));
+        let concurrency_limiter = ConcurrencyLimiter::default();
 
         let service = Self {
             command_tx: Arc::downgrade(&command_tx),
             command_rx,
+            connector,
             connector_handle,
             client,
             connection_mode_provider,
             connection_mode_generation: 0,
             api_availability,
+            inflight_requests: inflight_requests.clone(),
+            last_api_error: last_api_error.clone(),
+            concurrency_limiter: concurrency_limiter.clone(),
+        };
+        let handle = RequestServiceHandle {
+            tx: command_tx,
+            inflight_requests,
+            last_api_error,
+            concurrency_limiter,
+            background_download_limit: Arc::default(),
         };
-        let handle = RequestServiceHandle { tx: command_tx };
         tokio::spawn(service.into_future());
         handle
     }
 
+    /// Create a client with an empty connection pool
+    fn new_client(connector: HttpsConnectorWithSni) -> RequestClient {
+        hyper_util::client::legacy::Client::builder(hyper_util::rt::TokioExecutor::new())
+            .build(connector)
+    }
+
     async fn into_future(mut self) {
         loop {
             tokio::select! {
@@ -225,6 +266,23 @@ impl<T: ConnectionModeProvider + 'static> RequestService<T> {
             RequestCommand::Reset => {
                 self.connector_handle.reset();
             }
+            RequestCommand::ResetConnections => {
+                // In-flight requests keep their own reference to the old pool, so they are not
+                // interrupted. Its connections are closed once they complete.
+                self.client = Self::new_client(self.connector.clone());
+            }
+            RequestCommand::SetSniHostname(sni_hostname) => {
+                self.connector_handle.set_sni_hostname(sni_hostname);
+                // Pooled connections would otherwise be reused with the old server name
+                self.client = Self::new_client(self.connector.clone());
+            }
+            #[cfg(feature = "network-simulation")]
+            RequestCommand::SetSimulatedNetworkConditions(conditions) => {
+                self.connector_handle
+                    .set_simulated_network_conditions(conditions);
+                // Pooled connections would otherwise be reused without the simulated conditions
+                self.client = Self::new_client(self.connector.clone());
+            }
             RequestCommand::NextApiConfig(generation) => {
                 if generation == self.connection_mode_generation {
                     self.connection_mode_generation =
@@ -232,6 +290,13 @@ impl<T: ConnectionModeProvider + 'static> RequestService<T> {
                     self.connection_mode_provider.rotate().await;
                 }
             }
+            RequestCommand::RequestSucceeded(generation) => {
+                // Successes from before the connection mode was rotated say nothing about the
+                // current mode
+                if generation == self.connection_mode_generation {
+                    self.connection_mode_provider.report_success();
+                }
+            }
         }
     }
 
@@ -241,6 +306,11 @@ impl<T: ConnectionModeProvider + 'static> RequestService<T> {
         completion_tx: oneshot::Sender<Result<Response<Incoming>>>,
     ) {
         let tx = self.command_tx.upgrade();
+        let method = request.request.method().to_string();
+        let path = request.uri().path().to_owned();
+        let inflight_guard = self.inflight_requests.insert(method.clone(), path.clone());
+        let last_api_error = self.last_api_error.clone();
+        let concurrency_limiter = self.concurrency_limiter.clone();
 
         let api_availability = self.api_availability.clone();
         let request_future = request
@@ -250,18 +320,37 @@ impl<T: ConnectionModeProvider + 'static> RequestService<T> {
         let connection_mode_generation = self.connection_mode_generation;
 
         tokio::spawn(async move {
+            let permit = concurrency_limiter.acquire().await;
             let response = request_future.await.map_err(|error| error.map_aborted());
-
-            // Switch API endpoint if the request failed due to a network error
-            if let Err(err) = &response
-                && err.is_network_error()
-                && !api_availability.is_offline()
-            {
-                log::error!("{}", err.display_chain_with_msg("HTTP request failed"));
-                if let Some(tx) = tx {
-                    let _ = tx
-                        .unbounded_send(RequestCommand::NextApiConfig(connection_mode_generation));
+            drop(permit);
+            drop(inflight_guard);
+
+            match &response {
+                Ok(_) => {
+                    if let Some(tx) = tx {
+                        let _ = tx.unbounded_send(RequestCommand::RequestSucceeded(
+                            connection_mode_generation,
+                        ));
+                    }
                 }
+                // Switch API endpoint if the request failed due to a network error
+                Err(err) if err.is_network_error() && !api_availability.is_offline() => {
+                    log::error!("{}", err.display_chain_with_msg("HTTP request failed"));
+                    if let Some(tx) = tx {
+                        let _ = tx.unbounded_send(RequestCommand::NextApiConfig(
+                            connection_mode_generation,
+                        ));
+                    }
+                }
+                Err(Error::ApiError(status, _, body)) => {
+                    *last_api_error.lock().unwrap() = Some(ApiErrorResponse {
+                        method,
+                        path,
+                        status: status.as_u16(),
+                        body: body.as_deref().map(<[u8]>::to_vec).unwrap_or_default(),
+                    });
+                }
+                Err(_) => (),
             }
 
             let _ = completion_tx.send(response);
@@ -269,10 +358,142 @@ impl<T: ConnectionModeProvider + 'static> RequestService<T> {
     }
 }
 
+/// Requests that have been submitted to a `RequestService` but have not completed yet
+#[derive(Clone, Default)]
+struct InflightRequests(Arc<Mutex<InflightRequestsInner>>);
+
+#[derive(Default)]
+struct InflightRequestsInner {
+    next_id: u64,
+    requests: BTreeMap<u64, (String, String, Instant)>,
+}
+
+impl InflightRequests {
+    /// Track a request until the returned guard is dropped.
+    fn insert(&self, method: String, path: String) -> InflightRequestGuard {
+        let mut inner = self.0.lock().unwrap();
+        let id = inner.next_id;
+        inner.next_id = inner.next_id.wrapping_add(1);
+        inner.requests.insert(id, (method, path, Instant::now()));
+        InflightRequestGuard {
+            id,
+            requests: self.clone(),
+        }
+    }
+
+    /// Return all in-flight requests, oldest first.
+    fn snapshot(&self) -> Vec<InflightApiRequest> {
+        let inner = self.0.lock().unwrap();
+        inner
+            .requests
+            .values()
+            .map(|(method, path, started)| InflightApiRequest {
+                method: method.clone(),
+                path: path.clone(),
+                elapsed: started.elapsed(),
+            })
+            .collect()
+    }
+}
+
+struct InflightRequestGuard {
+    id: u64,
+    requests: InflightRequests,
+}
+
+impl Drop for InflightRequestGuard {
+    fn drop(&mut self) {
+        self.requests.0.lock().unwrap().requests.remove(&self.id);
+    }
+}
+
+/// Limits the number of requests that may be sent at once. Requests exceeding the limit wait
+/// until an earlier request completes.
+#[derive(Clone, Default)]
+struct ConcurrencyLimiter {
+    /// The maximum number of concurrent requests, and the semaphore enforcing it. `None` if the
+    /// number of requests is unbounded.
+    semaphore: Arc<Mutex<Option<(u32, Arc<Semaphore>)>>>,
+    queued_requests: Arc<AtomicU32>,
+}
+
+impl ConcurrencyLimiter {
+    /// Set the maximum number of concurrent requests. Zero means unbounded. Requests that are
+    /// already in flight are not affected, but requests that are waiting are subject to the new
+    /// limit.
+    fn set_max_concurrent_requests(&self, max_concurrent_requests: u32) {
+        let mut semaphore = self.semaphore.lock().unwrap();
+        if semaphore.as_ref().map(|(max, _)| *max).unwrap_or(0) == max_concurrent_requests {
+            return;
+        }
+        // Wake up any waiting requests, so that they wait for the new semaphore instead
+        if let Some((_, old_semaphore)) = semaphore.take() {
+            old_semaphore.close();
+        }
+        if max_concurrent_requests > 0 {
+            *semaphore = Some((
+                max_concurrent_requests,
+                Arc::new(Semaphore::new(max_concurrent_requests as usize)),
+            ));
+        }
+    }
+
+    fn limit(&self) -> ApiRequestLimit {
+        ApiRequestLimit {
+            max_concurrent_requests: self
+                .semaphore
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|(max, _)| *max)
+                .unwrap_or(0),
+            queued_requests: self.queued_requests.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Wait until another request may be sent. The request counts against the limit until the
+    /// returned permit is dropped. Returns `None` if the number of requests is unbounded.
+    async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        loop {
+            let semaphore = self.semaphore.lock().unwrap().as_ref()?.1.clone();
+            match semaphore.clone().try_acquire_owned() {
+                Ok(permit) => return Some(permit),
+                Err(TryAcquireError::Closed) => continue,
+                Err(TryAcquireError::NoPermits) => (),
+            }
+            let _queued = QueuedRequestGuard::new(self.queued_requests.clone());
+            if let Ok(permit) = semaphore.acquire_owned().await {
+                return Some(permit);
+            }
+            // The limit was changed while waiting
+        }
+    }
+}
+
+struct QueuedRequestGuard(Arc<AtomicU32>);
+
+impl QueuedRequestGuard {
+    fn new(queued_requests: Arc<AtomicU32>) -> Self {
+        queued_requests.fetch_add(1, Ordering::SeqCst);
+        Self(queued_requests)
+    }
+}
+
+impl Drop for QueuedRequestGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 #[derive(Clone)]
 /// A handle to interact with a spawned `RequestService`.
 pub struct RequestServiceHandle {
     tx: Arc<mpsc::UnboundedSender<RequestCommand>>,
+    inflight_requests: InflightRequests,
+    last_api_error: Arc<Mutex<Option<ApiErrorResponse>>>,
+    concurrency_limiter: ConcurrencyLimiter,
+    /// Maximum download speed of background fetches, in bytes per second. Zero means unlimited.
+    background_download_limit: Arc<AtomicU32>,
 }
 
 impl RequestServiceHandle {
@@ -281,6 +502,65 @@ impl RequestServiceHandle {
         let _ = self.tx.unbounded_send(RequestCommand::Reset);
     }
 
+    /// Drops all pooled connections, so that subsequent requests establish new connections.
+    /// Unlike [RequestServiceHandle::reset], this does not interrupt in-flight requests.
+    pub fn reset_connections(&self) {
+        let _ = self.tx.unbounded_send(RequestCommand::ResetConnections);
+    }
+
+    /// Send `sni_hostname` as the server name in new API connections, instead of the API
+    /// hostname. Pass `None` to send the API hostname.
+    pub fn set_sni_hostname(&self, sni_hostname: Option<String>) {
+        let _ = self
+            .tx
+            .unbounded_send(RequestCommand::SetSniHostname(sni_hostname));
+    }
+
+    /// Simulates packet loss and latency on all new API connections. Pass
+    /// [SimulatedNetworkConditions::default] to stop simulating.
+    #[cfg(feature = "network-simulation")]
+    pub fn set_simulated_network_conditions(&self, conditions: SimulatedNetworkConditions) {
+        let _ = self
+            .tx
+            .unbounded_send(RequestCommand::SetSimulatedNetworkConditions(conditions));
+    }
+
+    /// Returns the requests that have been submitted but have not completed yet, oldest first.
+    pub fn inflight_requests(&self) -> Vec<InflightApiRequest> {
+        self.inflight_requests.snapshot()
+    }
+
+    /// Returns the response to the most recent request which failed with an unexpected status
+    /// code, if any.
+    pub fn last_api_error(&self) -> Option<ApiErrorResponse> {
+        self.last_api_error.lock().unwrap().clone()
+    }
+
+    /// Sets the maximum number of requests that may be in flight at once. Excess requests wait
+    /// until an earlier request completes. Zero means unbounded.
+    pub fn set_max_concurrent_requests(&self, max_concurrent_requests: u32) {
+        self.concurrency_limiter
+            .set_max_concurrent_requests(max_concurrent_requests);
+    }
+
+    /// Returns the maximum number of concurrent requests, and the number of requests waiting
+    /// because of it.
+    pub fn request_limit(&self) -> ApiRequestLimit {
+        self.concurrency_limiter.limit()
+    }
+
+    /// Sets the maximum download speed, in bytes per second, of background fetches such as the
+    /// relay list and API addresses. Zero means unlimited.
+    pub fn set_background_download_limit(&self, bytes_per_second: u32) {
+        self.background_download_limit
+            .store(bytes_per_second, Ordering::SeqCst);
+    }
+
+    /// Returns the maximum download speed of background fetches, or `None` if it is unlimited.
+    pub fn background_download_limit(&self) -> Option<NonZeroU32> {
+        NonZeroU32::new(self.background_download_limit.load(Ordering::SeqCst))
+    }
+
     /// Submits a `RestRequest` for execution to the request service.
     pub async fn request<B>(&self, request: Request<B>) -> Result<Response<Incoming>>
     where
@@ -304,7 +584,13 @@ pub(crate) enum RequestCommand {
         oneshot::Sender<std::result::Result<Response<Incoming>, Error>>,
     ),
     Reset,
+    ResetConnections,
     NextApiConfig(usize),
+    /// A request succeeded using the connection mode of the given generation
+    RequestSucceeded(usize),
+    SetSniHostname(Option<String>),
+    #[cfg(feature = "network-simulation")]
+    SetSimulatedNetworkConditions(SimulatedNetworkConditions),
 }
 
 /// A REST request that is sent to the RequestService to be executed.
@@ -522,6 +808,21 @@ where
         deserialize_body_inner(self.response).await
     }
 
+    /// Like [Response::deserialize], but reads the body no faster than `bytes_per_second`, if
+    /// given.
+    pub async fn deserialize_with_rate_limit<T: serde::de::DeserializeOwned>(
+        self,
+        bytes_per_second: Option<NonZeroU32>,
+    ) -> Result<T> {
+        match bytes_per_second {
+            Some(bytes_per_second) => {
+                let body = self.body_with_rate_limit(bytes_per_second).await?;
+                Ok(serde_json::from_slice(&body)?)
+            }
+            None => self.deserialize().await,
+        }
+    }
+
     pub async fn body(self) -> Result<Vec<u8>> {
         Ok(BodyExt::collect(self.response).await?.to_bytes().to_vec())
     }
@@ -539,6 +840,39 @@ where
 
         Ok(data)
     }
+
+    /// Read at most `size_limit` bytes of the body. The rest of the body is discarded.
+    pub async fn body_prefix(self, size_limit: usize) -> Result<Vec<u8>> {
+        let mut data: Vec<u8> = vec![];
+        let mut stream = self.response.into_data_stream();
+
+        while data.len() < size_limit {
+            let Some(chunk) = stream.next().await else {
+                break;
+            };
+            data.extend(chunk?.chunk());
+        }
+        data.truncate(size_limit);
+
+        Ok(data)
+    }
+
+    /// Read the body no faster than `bytes_per_second`, by pausing after each chunk until the
+    /// average speed is within the limit.
+    pub async fn body_with_rate_limit(self, bytes_per_second: NonZeroU32) -> Result<Vec<u8>> {
+        let start = tokio::time::Instant::now();
+        let mut data: Vec<u8> = vec![];
+        let mut stream = self.response.into_data_stream();
+
+        while let Some(chunk) = stream.next().await {
+            data.extend(chunk?.chunk());
+            let min_elapsed =
+                Duration::from_secs_f64(data.len() as f64 / f64::from(bytes_per_second.get()));
+            tokio::time::sleep_until(start + min_elapsed).await;
+        }
+
+        Ok(data)
+    }
 }
 
 #[derive(serde::Deserialize)]
@@ -623,6 +957,13 @@ impl RequestFactory {
         self.default_timeout = timeout;
         self
     }
+
+    /// Forget the access token for `account`, if requests are authenticated.
+    pub fn invalidate_access_token(&self, account: &AccountNumber) {
+        if let Some(store) = &self.token_store {
+            store.invalidate_token(account);
+        }
+    }
     fn json_request_with_bytes(
         &self,
         method: Method,
@@ -695,29 +1036,60 @@ where
         status => match get_body_length(&response) {
             0 => status.canonical_reason().unwrap_or("Unexpected error"),
             _length => {
-                return match response.headers().get("content-type") {
-                    Some(content_type) if content_type == "application/problem+json" => {
-                        // TODO: We should make sure we unify the new error format and the old
-                        // error format so that they both produce the same Errors for the same
-                        // problems after being processed.
-                        let err: NewErrorResponse = deserialize_body_inner(response).await?;
+                let is_problem_json = response
+                    .headers()
+                    .get("content-type")
+                    .is_some_and(|content_type| content_type == "application/problem+json");
+                let body = read_error_body(response).await?;
+                let raw_body = body.slice(..body.len().min(MAX_RAW_ERROR_BODY_SIZE));
+                let code = if is_problem_json {
+                    // TODO: We should make sure we unify the new error format and the old
+                    // error format so that they both produce the same Errors for the same
+                    // problems after being processed.
+                    serde_json::from_slice::<NewErrorResponse>(&body).map(|err| {
                         // The new error type replaces the `code` field with the `type` field.
                         // This is what is used to programmatically check the error.
-                        Err(Error::ApiError(
-                            status,
-                            err.r#type
-                                .unwrap_or_else(|| String::from(DEFAULT_ERROR_TYPE)),
-                        ))
-                    }
-                    _ => {
-                        let err: OldErrorResponse = deserialize_body_inner(response).await?;
-                        Err(Error::ApiError(status, err.code))
-                    }
+                        err.r#type
+                            .unwrap_or_else(|| String::from(DEFAULT_ERROR_TYPE))
+                    })
+                } else {
+                    serde_json::from_slice::<OldErrorResponse>(&body).map(|err| err.code)
                 };
+                // Keep the status and raw body even if the body is not a known error format
+                let code = code.unwrap_or_else(|error| {
+                    log::debug!(
+                        "{}",
+                        error.display_chain_with_msg("Failed to parse API error response")
+                    );
+                    status
+                        .canonical_reason()
+                        .unwrap_or("Unexpected error")
+                        .to_owned()
+                });
+                return Err(Error::ApiError(status, code, Some(raw_body)));
             }
         },
     };
-    Err(Error::ApiError(status, error_message.to_owned()))
+    Err(Error::ApiError(status, error_message.to_owned(), None))
+}
+
+/// Read at most [MAX_ERROR_BODY_SIZE] bytes of the body of `response`.
+async fn read_error_body<B: Body>(response: hyper::Response<B>) -> Result<Bytes>
+where
+    Error: From<B::Error>,
+{
+    let mut data: Vec<u8> = vec![];
+    let mut stream = std::pin::pin!(response.into_body().into_data_stream());
+
+    while let Some(chunk) = stream.next().await {
+        data.extend(chunk?.chunk());
+        if data.len() >= MAX_ERROR_BODY_SIZE {
+            data.truncate(MAX_ERROR_BODY_SIZE);
+            break;
+        }
+    }
+
+    Ok(Bytes::from(data))
 }
 
 async fn deserialize_body_inner<T, B>(response: hyper::Response<B>) -> Result<T>
@@ -773,3 +1145,338 @@ impl_into_arc_err!(hyper_util::client::legacy::Error);
 impl_into_arc_err!(serde_json::Error);
 impl_into_arc_err!(http::Error);
 impl_into_arc_err!(http::uri::InvalidUri);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{NullDnsResolver, proxy::ApiConnectionMode};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// Serve empty responses over plain HTTP, keeping connections alive. Returns the address to
+    /// connect to and a counter of accepted connections.
+    async fn spawn_server() -> (std::net::SocketAddr, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    while let Ok(n) = stream.read(&mut buf).await {
+                        if n == 0 {
+                            break;
+                        }
+                        let response = b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n";
+                        if stream.write_all(response).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        (addr, connections)
+    }
+
+    async fn send(service: &RequestServiceHandle, uri: &str) {
+        let request = get(uri).unwrap().expected_status(&[StatusCode::OK]);
+        service
+            .request(request)
+            .await
+            .unwrap()
+            .body()
+            .await
+            .unwrap();
+        // The connection is returned to the pool in the background
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    /// This uses real sockets, so time must not be paused. Otherwise, timeouts could be triggered
+    /// while waiting for I/O.
+    #[tokio::test]
+    async fn test_reset_connections() {
+        let (addr, connections) = spawn_server().await;
+        let availability = ApiAvailability::default();
+        availability.unsuspend();
+        let service = RequestService::spawn(
+            availability,
+            ApiConnectionMode::Direct.into_provider(),
+            Arc::new(NullDnsResolver),
+            None,
+            None,
+            #[cfg(target_os = "android")]
+            None,
+            true,
+        );
+        let uri = format!("https://{addr}/");
+
+        send(&service, &uri).await;
+        send(&service, &uri).await;
+        assert_eq!(
+            connections.load(Ordering::SeqCst),
+            1,
+            "pooled connection should be reused"
+        );
+
+        service.reset_connections();
+        send(&service, &uri).await;
+        assert_eq!(
+            connections.load(Ordering::SeqCst),
+            2,
+            "request should use a new connection"
+        );
+    }
+
+    /// Serve empty responses over plain HTTP, but only once `release` has a permit for each one.
+    /// Returns the address to connect to and a counter of received requests.
+    async fn spawn_stalling_server(
+        release: Arc<Semaphore>,
+    ) -> (std::net::SocketAddr, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let received = Arc::new(AtomicUsize::new(0));
+        let counter = received.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let counter = counter.clone();
+                let release = release.clone();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    while let Ok(n) = stream.read(&mut buf).await {
+                        if n == 0 {
+                            break;
+                        }
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        release.acquire().await.unwrap().forget();
+                        let response = b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n";
+                        if stream.write_all(response).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        (addr, received)
+    }
+
+    /// Wait until `counter` reaches `expected`, and check that it does not go past it.
+    async fn wait_for_count(counter: &AtomicUsize, expected: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while counter.load(Ordering::SeqCst) < expected {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("timed out waiting for requests");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(counter.load(Ordering::SeqCst), expected);
+    }
+
+    /// A request exceeding the limit is not sent until an earlier request has completed.
+    ///
+    /// This uses real sockets, so time must not be paused.
+    #[tokio::test]
+    async fn test_request_service_concurrency_limit() {
+        const MAX_CONCURRENT_REQUESTS: u32 = 2;
+
+        let release = Arc::new(Semaphore::new(0));
+        let (addr, received) = spawn_stalling_server(release.clone()).await;
+        let availability = ApiAvailability::default();
+        availability.unsuspend();
+        let service = RequestService::spawn(
+            availability,
+            ApiConnectionMode::Direct.into_provider(),
+            Arc::new(NullDnsResolver),
+            None,
+            None,
+            #[cfg(target_os = "android")]
+            None,
+            true,
+        );
+        service.set_max_concurrent_requests(MAX_CONCURRENT_REQUESTS);
+        let uri = format!("https://{addr}/");
+
+        let requests: Vec<_> = (0..=MAX_CONCURRENT_REQUESTS)
+            .map(|_| {
+                let service = service.clone();
+                let uri = uri.clone();
+                tokio::spawn(async move { send(&service, &uri).await })
+            })
+            .collect();
+
+        wait_for_count(&received, MAX_CONCURRENT_REQUESTS as usize).await;
+        assert_eq!(
+            service.request_limit(),
+            ApiRequestLimit {
+                max_concurrent_requests: MAX_CONCURRENT_REQUESTS,
+                queued_requests: 1,
+            }
+        );
+
+        // Completing one request lets the waiting request through
+        release.add_permits(1);
+        wait_for_count(&received, MAX_CONCURRENT_REQUESTS as usize + 1).await;
+        assert_eq!(service.request_limit().queued_requests, 0);
+
+        release.add_permits(MAX_CONCURRENT_REQUESTS as usize);
+        for request in requests {
+            request.await.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_inflight_requests() {
+        let requests = InflightRequests::default();
+        let first = requests.insert("GET".to_owned(), "/app/v1/api-addrs".to_owned());
+        let second = requests.insert("POST".to_owned(), "/app/v1/submit-voucher".to_owned());
+
+        let paths: Vec<_> = requests
+            .snapshot()
+            .into_iter()
+            .map(|request| request.path)
+            .collect();
+        assert_eq!(paths, ["/app/v1/api-addrs", "/app/v1/submit-voucher"]);
+
+        drop(first);
+        let snapshot = requests.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].method, "POST");
+
+        drop(second);
+        assert!(requests.snapshot().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_concurrency_limit() {
+        const MAX_CONCURRENT_REQUESTS: u32 = 2;
+        const REQUESTS: u32 = 6;
+
+        let limiter = ConcurrencyLimiter::default();
+        assert!(
+            limiter.acquire().await.is_none(),
+            "should be unbounded by default"
+        );
+        limiter.set_max_concurrent_requests(MAX_CONCURRENT_REQUESTS);
+
+        let active = Arc::new(AtomicUsize::new(0));
+        let max_active = Arc::new(AtomicUsize::new(0));
+        let requests: Vec<_> = (0..REQUESTS)
+            .map(|_| {
+                let limiter = limiter.clone();
+                let active = active.clone();
+                let max_active = max_active.clone();
+                tokio::spawn(async move {
+                    let _permit = limiter.acquire().await;
+                    let now_active = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_active.fetch_max(now_active, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        assert_eq!(
+            limiter.limit(),
+            ApiRequestLimit {
+                max_concurrent_requests: MAX_CONCURRENT_REQUESTS,
+                queued_requests: REQUESTS - MAX_CONCURRENT_REQUESTS,
+            }
+        );
+
+        for request in requests {
+            request.await.unwrap();
+        }
+        assert_eq!(
+            max_active.load(Ordering::SeqCst),
+            MAX_CONCURRENT_REQUESTS as usize
+        );
+        assert_eq!(limiter.limit().queued_requests, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_body_with_rate_limit() {
+        let body = vec![0u8; 1000];
+        let response = Response::new(hyper::Response::new(Full::new(Bytes::from(body.clone()))));
+
+        let start = tokio::time::Instant::now();
+        let bytes_per_second = NonZeroU32::new(100).unwrap();
+        assert_eq!(
+            response
+                .body_with_rate_limit(bytes_per_second)
+                .await
+                .unwrap(),
+            body
+        );
+        assert!(start.elapsed() >= Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_error_response_retains_raw_body() {
+        let error_response = |body: String| {
+            hyper::Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .header(header::CONTENT_LENGTH, body.len())
+                .body(Full::new(Bytes::from(body)))
+                .unwrap()
+        };
+
+        let body = r#"{"code":"INVALID_VOUCHER","error":"Invalid voucher"}"#.to_owned();
+        match handle_error_response::<(), _>(error_response(body.clone())).await {
+            Err(Error::ApiError(status, code, Some(raw_body))) => {
+                assert_eq!(status, StatusCode::BAD_REQUEST);
+                assert_eq!(code, "INVALID_VOUCHER");
+                assert_eq!(raw_body, body.as_bytes());
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+
+        let padding = "x".repeat(2 * MAX_RAW_ERROR_BODY_SIZE);
+        let body = format!(r#"{{"code":"INVALID_VOUCHER","error":"{padding}"}}"#);
+        match handle_error_response::<(), _>(error_response(body)).await {
+            Err(Error::ApiError(_, _, Some(raw_body))) => {
+                assert_eq!(raw_body.len(), MAX_RAW_ERROR_BODY_SIZE);
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    /// Bodies that are not a known error format, or too large to read, still result in an
+    /// [Error::ApiError] with the status and raw body
+    #[tokio::test]
+    async fn test_error_response_unparsable_body() {
+        let error_response = |body: String| {
+            hyper::Response::builder()
+                .status(StatusCode::BAD_GATEWAY)
+                .header(header::CONTENT_LENGTH, body.len())
+                .body(Full::new(Bytes::from(body)))
+                .unwrap()
+        };
+
+        let body = "<html>Bad gateway</html>".to_owned();
+        match handle_error_response::<(), _>(error_response(body.clone())).await {
+            Err(Error::ApiError(status, code, Some(raw_body))) => {
+                assert_eq!(status, StatusCode::BAD_GATEWAY);
+                assert_eq!(code, "Bad Gateway");
+                assert_eq!(raw_body, body.as_bytes());
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+
+        let padding = "x".repeat(2 * MAX_ERROR_BODY_SIZE);
+        let body = format!(r#"{{"code":"INVALID_VOUCHER","error":"{padding}"}}"#);
+        match handle_error_response::<(), _>(error_response(body)).await {
+            Err(Error::ApiError(status, code, Some(raw_body))) => {
+                assert_eq!(status, StatusCode::BAD_GATEWAY);
+                assert_eq!(code, "Bad Gateway");
+                assert_eq!(raw_body.len(), MAX_RAW_ERROR_BODY_SIZE);
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+}
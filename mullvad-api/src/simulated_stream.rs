@@ -0,0 +1,237 @@
+//! Wrapper around a stream that simulates packet loss and latency, for testing how poor
+//! connectivity is handled.
+//!
+//! Every read and write is delayed by the added latency. Each of them may also be lost, in which
+//! case it is retransmitted after a timeout that doubles for every lost attempt, like TCP does.
+//! If too many retransmissions are lost, the read or write fails with
+//! [io::ErrorKind::TimedOut].
+
+use hyper_util::client::legacy::connect::{Connected, Connection};
+use mullvad_types::network_simulation::SimulatedNetworkConditions;
+use rand::{Rng, rngs::StdRng};
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::Sleep,
+};
+
+/// Time until the first retransmission, which is the minimum retransmission timeout of TCP
+const INITIAL_RETRANSMISSION_TIMEOUT: Duration = Duration::from_secs(1);
+/// Number of retransmissions before giving up
+const MAX_RETRANSMISSIONS: u32 = 5;
+const READ_BUFFER_SIZE: usize = 16 * 1024;
+
+/// A pending delivery of data
+struct Delay {
+    sleep: Pin<Box<Sleep>>,
+    /// Whether the data is lost for good once the delay has elapsed
+    lost: bool,
+}
+
+pub struct SimulatedStream<S> {
+    stream: S,
+    conditions: SimulatedNetworkConditions,
+    rng: StdRng,
+    /// Data that has been read from `stream`, but not delivered yet
+    read_buffer: Vec<u8>,
+    read_delay: Option<Delay>,
+    write_delay: Option<Delay>,
+}
+
+impl<S> SimulatedStream<S> {
+    /// Apply `conditions` to `stream`. Whether data is lost is decided by `rng`, so that a seeded
+    /// RNG gives reproducible results.
+    pub fn new(stream: S, conditions: SimulatedNetworkConditions, rng: StdRng) -> Self {
+        Self {
+            stream,
+            conditions,
+            rng,
+            read_buffer: vec![],
+            read_delay: None,
+            write_delay: None,
+        }
+    }
+
+    fn next_delay(&mut self) -> Delay {
+        let mut delay = self.conditions.added_latency;
+        let mut retransmission_timeout = INITIAL_RETRANSMISSION_TIMEOUT;
+        let mut lost = false;
+        for attempt in 0..=MAX_RETRANSMISSIONS {
+            if self.rng.random_range(0..100) >= self.conditions.loss_percent() {
+                break;
+            }
+            if attempt == MAX_RETRANSMISSIONS {
+                lost = true;
+                break;
+            }
+            delay += retransmission_timeout;
+            retransmission_timeout *= 2;
+        }
+        Delay {
+            sleep: Box::pin(tokio::time::sleep(delay)),
+            lost,
+        }
+    }
+}
+
+impl Delay {
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.sleep.as_mut().poll(cx));
+        if self.lost {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "simulated packet loss",
+            )));
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> AsyncRead for SimulatedStream<S>
+where
+    S: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        loop {
+            if let Some(delay) = &mut this.read_delay {
+                let result = ready!(delay.poll(cx));
+                this.read_delay = None;
+                if let Err(error) = result {
+                    this.read_buffer.clear();
+                    return Poll::Ready(Err(error));
+                }
+            }
+            if !this.read_buffer.is_empty() {
+                let len = this.read_buffer.len().min(buf.remaining());
+                buf.put_slice(&this.read_buffer[..len]);
+                this.read_buffer.drain(..len);
+                return Poll::Ready(Ok(()));
+            }
+
+            let mut data = [0u8; READ_BUFFER_SIZE];
+            let mut data = ReadBuf::new(&mut data);
+            ready!(Pin::new(&mut this.stream).poll_read(cx, &mut data))?;
+            if data.filled().is_empty() {
+                // The stream has ended
+                return Poll::Ready(Ok(()));
+            }
+            this.read_buffer.extend_from_slice(data.filled());
+            this.read_delay = Some(this.next_delay());
+        }
+    }
+}
+
+impl<S> AsyncWrite for SimulatedStream<S>
+where
+    S: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        if this.write_delay.is_none() {
+            this.write_delay = Some(this.next_delay());
+        }
+        let delay = this.write_delay.as_mut().expect("delay was just set");
+        if let Err(error) = ready!(delay.poll(cx)) {
+            this.write_delay = None;
+            return Poll::Ready(Err(error));
+        }
+        // The delay is kept until the data has been written, so that it is not applied again if
+        // the stream is not ready
+        let result = ready!(Pin::new(&mut this.stream).poll_write(cx, buf));
+        this.write_delay = None;
+        Poll::Ready(result)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+impl<S: Connection> Connection for SimulatedStream<S> {
+    fn connected(&self) -> Connected {
+        self.stream.connected()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::SeedableRng;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn stream(
+        loss_percent: u8,
+        added_latency: Duration,
+        seed: u64,
+    ) -> (
+        SimulatedStream<tokio::io::DuplexStream>,
+        tokio::io::DuplexStream,
+    ) {
+        let (client, server) = tokio::io::duplex(1024);
+        let conditions = SimulatedNetworkConditions::new(loss_percent, added_latency).unwrap();
+        let rng = StdRng::seed_from_u64(seed);
+        (SimulatedStream::new(client, conditions, rng), server)
+    }
+
+    /// Reads and writes are each delayed by the added latency
+    #[tokio::test(start_paused = true)]
+    async fn test_latency() {
+        let latency = Duration::from_millis(300);
+        let (mut stream, mut server) = stream(0, latency, 0);
+
+        let start = tokio::time::Instant::now();
+        stream.write_all(b"ping").await.unwrap();
+        assert_eq!(start.elapsed(), latency);
+
+        server.write_all(b"pong").await.unwrap();
+        let mut response = [0u8; 4];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(&response, b"pong");
+        assert_eq!(start.elapsed(), 2 * latency);
+    }
+
+    /// Data is retransmitted until the stream gives up
+    #[tokio::test(start_paused = true)]
+    async fn test_loss() {
+        let (mut stream, _server) = stream(100, Duration::ZERO, 0);
+
+        let start = tokio::time::Instant::now();
+        let error = stream.write_all(b"ping").await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        // 1 + 2 + 4 + 8 + 16 seconds of retransmission timeouts
+        assert_eq!(start.elapsed(), Duration::from_secs(31));
+    }
+
+    /// The same seed gives the same delays
+    #[tokio::test(start_paused = true)]
+    async fn test_seed() {
+        async fn write_time(seed: u64) -> Duration {
+            let (mut stream, _server) = stream(50, Duration::ZERO, seed);
+            let start = tokio::time::Instant::now();
+            for _ in 0..10 {
+                let _ = stream.write(b"ping").await;
+            }
+            start.elapsed()
+        }
+        assert_eq!(write_time(1).await, write_time(1).await);
+    }
+}
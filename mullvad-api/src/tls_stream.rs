@@ -12,7 +12,14 @@ use std::sync::LazyLock;
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio_rustls::{
     TlsConnector,
-    rustls::{self, ClientConfig, pki_types::ServerName},
+    rustls::{
+        self, ClientConfig, DigitallySignedStruct, SignatureScheme,
+        client::{
+            WebPkiServerVerifier,
+            danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        },
+        pki_types::{ServerName, UnixTime},
+    },
 };
 
 const LE_ROOT_CERT: &[u8] = include_bytes!("../le_root_cert.pem");
@@ -21,16 +28,22 @@ pub struct TlsStream<S: AsyncRead + AsyncWrite + Unpin> {
     stream: tokio_rustls::client::TlsStream<S>,
 }
 
+static ROOT_CERTS: LazyLock<Arc<rustls::RootCertStore>> =
+    LazyLock::new(|| Arc::new(read_cert_store().expect("Failed to parse pem file")));
+
 static TLS_CONFIG: LazyLock<Arc<ClientConfig>> = LazyLock::new(|| {
-    let config =
-        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
-            .with_protocol_versions(&[&rustls::version::TLS13])
-            .expect("ring crypt-prover should support TLS 1.3")
-            .with_root_certificates(read_cert_store().expect("Failed to parse pem file"))
-            .with_no_client_auth();
+    let config = client_config_builder()
+        .with_root_certificates(ROOT_CERTS.clone())
+        .with_no_client_auth();
     Arc::new(config)
 });
 
+fn client_config_builder() -> rustls::ConfigBuilder<ClientConfig, rustls::WantsVerifier> {
+    ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .expect("ring crypt-prover should support TLS 1.3")
+}
+
 impl<S> TlsStream<S>
 where
     S: AsyncRead + AsyncWrite + Unpin,
@@ -39,6 +52,33 @@ where
         Self::connect_https_with_client_config(stream, domain, Arc::clone(&TLS_CONFIG)).await
     }
 
+    /// Connect to `domain`, but send `sni_hostname` as the server name in the ClientHello.
+    ///
+    /// The server certificate is still verified against `domain`, so the server must present a
+    /// certificate that is valid for `domain`. This does not work for domain fronting where the
+    /// front only presents a certificate for `sni_hostname`.
+    pub async fn connect_https_with_sni(
+        stream: S,
+        domain: &str,
+        sni_hostname: &str,
+    ) -> io::Result<TlsStream<S>> {
+        let verifier = WebPkiServerVerifier::builder_with_provider(
+            ROOT_CERTS.clone(),
+            Arc::new(rustls::crypto::ring::default_provider()),
+        )
+        .build()
+        .map_err(io::Error::other)?;
+        let verifier = VerifyAsHost {
+            inner: verifier,
+            host: parse_server_name(domain)?,
+        };
+        let config = client_config_builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+        Self::connect_https_with_client_config(stream, sni_hostname, Arc::new(config)).await
+    }
+
     pub async fn connect_https_with_client_config(
         stream: S,
         domain: &str,
@@ -46,15 +86,7 @@ where
     ) -> io::Result<TlsStream<S>> {
         let connector = TlsConnector::from(client_config);
 
-        let host = match ServerName::try_from(domain.to_owned()) {
-            Ok(n) => n,
-            Err(_) => {
-                return Err(io::Error::new(
-                    ErrorKind::InvalidInput,
-                    format!("invalid hostname \"{domain}\""),
-                ));
-            }
-        };
+        let host = parse_server_name(domain)?;
 
         let stream = connector.connect(host, stream).await?;
 
@@ -62,6 +94,65 @@ where
     }
 }
 
+/// Return whether `hostname` can be sent as the server name in a ClientHello. IP addresses are
+/// rejected, since they are never sent as SNI.
+pub fn is_valid_sni_hostname(hostname: &str) -> bool {
+    matches!(ServerName::try_from(hostname), Ok(ServerName::DnsName(_)))
+}
+
+fn parse_server_name(domain: &str) -> io::Result<ServerName<'static>> {
+    ServerName::try_from(domain.to_owned()).map_err(|_| {
+        io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid hostname \"{domain}\""),
+        )
+    })
+}
+
+/// Certificate verifier that verifies the server certificate against `host`, regardless of the
+/// server name that was sent in the ClientHello.
+#[derive(Debug)]
+struct VerifyAsHost {
+    inner: Arc<WebPkiServerVerifier>,
+    host: ServerName<'static>,
+}
+
+impl ServerCertVerifier for VerifyAsHost {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.inner
+            .verify_server_cert(end_entity, intermediates, &self.host, ocsp_response, now)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
 fn read_cert_store() -> Result<rustls::RootCertStore, rustls_pki_types::pem::Error> {
     let mut cert_store = rustls::RootCertStore::empty();
 
@@ -126,4 +217,42 @@ mod test {
     fn test_cert_loading() {
         let _certs = read_cert_store();
     }
+
+    #[test]
+    fn test_valid_sni_hostname() {
+        assert!(is_valid_sni_hostname("front.example.com"));
+        assert!(is_valid_sni_hostname("localhost"));
+        assert!(!is_valid_sni_hostname(""));
+        assert!(!is_valid_sni_hostname("not a hostname"));
+        assert!(!is_valid_sni_hostname("192.0.2.1"));
+        assert!(!is_valid_sni_hostname("2001:db8::1"));
+    }
+
+    #[tokio::test]
+    async fn test_custom_sni() {
+        use tokio::io::AsyncReadExt;
+
+        let (client, mut server) = tokio::io::duplex(4096);
+        let client = tokio::spawn(TlsStream::connect_https_with_sni(
+            client,
+            "api.mullvad.net",
+            "front.example.com",
+        ));
+
+        let mut acceptor = rustls::server::Acceptor::default();
+        let mut buf = [0u8; 4096];
+        let accepted = loop {
+            let len = server.read(&mut buf).await.unwrap();
+            assert!(len > 0, "client closed the stream");
+            acceptor.read_tls(&mut &buf[..len]).unwrap();
+            if let Some(accepted) = acceptor.accept().map_err(|(error, _)| error).unwrap() {
+                break accepted;
+            }
+        };
+        assert_eq!(
+            accepted.client_hello().server_name(),
+            Some("front.example.com")
+        );
+        client.abort();
+    }
 }
@@ -117,6 +117,8 @@ impl AppVersionProxy {
                 // NOTE: On Linux, version metadata contains no installers
                 allow_empty: cfg!(target_os = "linux"),
                 lowest_metadata_version,
+                // The daemon does not know the user's locale
+                locale: None,
             };
 
             let current_version =
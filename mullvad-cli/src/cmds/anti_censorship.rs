@@ -4,10 +4,13 @@ use mullvad_management_interface::MullvadProxyClient;
 use mullvad_types::{
     constraints::Constraint,
     relay_constraints::{
-        ObfuscationSettings, SelectedObfuscation, ShadowsocksSettings, Udp2TcpObfuscationSettings,
-        WireguardPortSettings,
+        ObfuscationSensitivity, ObfuscationSettings, SelectedObfuscation, ShadowsocksSettings,
+        Udp2TcpObfuscationSettings, WireguardPortSettings,
     },
 };
+use talpid_types::net::proxy::Shadowsocks;
+
+use super::proxies::ShadowsocksAdd;
 
 #[derive(Subcommand, Debug)]
 pub enum AntiCensorship {
@@ -24,6 +27,11 @@ pub enum SetCommands {
     /// Specify which anti-censorship method to use, if any.
     Mode { mode: SelectedObfuscation },
 
+    /// Specify how eagerly anti-censorship is used when the mode is 'auto'. A low sensitivity
+    /// retries without anti-censorship more times, and a high sensitivity uses it after a single
+    /// failed attempt.
+    AutoSensitivity { sensitivity: ObfuscationSensitivity },
+
     /// Configure udp2tcp anti-censorship.
     Udp2tcp {
         /// Port to use, or 'any'
@@ -37,6 +45,10 @@ pub enum SetCommands {
         #[arg(long, short = 'p')]
         port: Constraint<u16>,
     },
+    /// Configure a custom Shadowsocks server to use for Shadowsocks anti-censorship, instead of
+    /// the servers run by relays. The server forwards traffic to the selected relay.
+    #[clap(subcommand)]
+    CustomShadowsocks(CustomShadowsocksCommands),
     /// Configure WireGuard port anti-censorship.
     WireguardPort {
         /// Port to use
@@ -45,6 +57,14 @@ pub enum SetCommands {
     },
 }
 
+#[derive(Subcommand, Debug, Clone)]
+pub enum CustomShadowsocksCommands {
+    /// Use a custom Shadowsocks server
+    Set(ShadowsocksAdd),
+    /// Use the Shadowsocks servers run by relays
+    Unset,
+}
+
 impl AntiCensorship {
     pub async fn handle(self) -> Result<()> {
         match self {
@@ -52,6 +72,10 @@ impl AntiCensorship {
                 let mut rpc = MullvadProxyClient::new().await?;
                 let obfuscation_settings = rpc.get_settings().await?.obfuscation_settings;
                 println!("mode: {}", obfuscation_settings.selected_obfuscation);
+                println!(
+                    "auto sensitivity: {}",
+                    obfuscation_settings.auto_sensitivity
+                );
                 println!("udp2tcp settings: {}", obfuscation_settings.udp2tcp);
                 println!("shadowsocks settings: {}", obfuscation_settings.shadowsocks);
                 println!(
@@ -76,6 +100,9 @@ impl AntiCensorship {
                 })
                 .await?;
             }
+            SetCommands::AutoSensitivity { sensitivity } => {
+                rpc.set_obfuscation_auto_sensitivity(sensitivity).await?;
+            }
             SetCommands::Udp2tcp { port } => {
                 rpc.set_obfuscation_settings(ObfuscationSettings {
                     udp2tcp: Udp2TcpObfuscationSettings { port },
@@ -85,7 +112,26 @@ impl AntiCensorship {
             }
             SetCommands::Shadowsocks { port } => {
                 rpc.set_obfuscation_settings(ObfuscationSettings {
-                    shadowsocks: ShadowsocksSettings { port },
+                    shadowsocks: ShadowsocksSettings {
+                        port,
+                        ..current_settings.shadowsocks.clone()
+                    },
+                    ..current_settings
+                })
+                .await?;
+            }
+            SetCommands::CustomShadowsocks(cmd) => {
+                let custom_server = match cmd {
+                    CustomShadowsocksCommands::Set(server) => {
+                        Constraint::Only(Shadowsocks::from(server))
+                    }
+                    CustomShadowsocksCommands::Unset => Constraint::Any,
+                };
+                rpc.set_obfuscation_settings(ObfuscationSettings {
+                    shadowsocks: ShadowsocksSettings {
+                        custom_server,
+                        ..current_settings.shadowsocks.clone()
+                    },
                     ..current_settings
                 })
                 .await?;
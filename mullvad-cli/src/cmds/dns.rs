@@ -1,7 +1,9 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use clap::Subcommand;
 use mullvad_management_interface::MullvadProxyClient;
-use mullvad_types::settings::{CustomDnsOptions, DefaultDnsOptions, DnsOptions, DnsState};
+use mullvad_types::settings::{
+    CustomDnsOptions, DefaultDnsOptions, DnsOptions, DnsResolverSource, DnsState, SplitDnsRule,
+};
 use std::net::IpAddr;
 
 #[derive(Subcommand, Debug)]
@@ -14,6 +16,38 @@ pub enum Dns {
         #[clap(subcommand)]
         cmd: DnsSet,
     },
+
+    /// Resolve specific domains using other DNS servers. Only supported on macOS
+    Split {
+        #[clap(subcommand)]
+        cmd: SplitDns,
+    },
+
+    /// Set the order in which DNS server sources are used when custom DNS is enabled. Servers
+    /// from later sources are only used if the earlier ones cannot be reached
+    Order {
+        /// One or more DNS server sources, in order of priority
+        #[arg(required(true), num_args = 1..)]
+        sources: Vec<DnsResolverSource>,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum SplitDns {
+    /// Resolve a domain and its subdomains using the given DNS servers
+    Add {
+        /// Domain, such as "corp.example.com"
+        domain: String,
+        /// One or more IP addresses pointing to DNS resolvers
+        #[arg(required(true), num_args = 1..)]
+        servers: Vec<IpAddr>,
+    },
+
+    /// Remove the split DNS rule for a domain
+    Remove { domain: String },
+
+    /// Remove all split DNS rules
+    Clear,
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -82,6 +116,8 @@ impl Dns {
             Dns::Set {
                 cmd: DnsSet::Custom { servers },
             } => Self::set_custom(servers).await,
+            Dns::Split { cmd } => Self::split(cmd).await,
+            Dns::Order { sources } => Self::set_order(sources).await,
         }
     }
 
@@ -110,8 +146,18 @@ impl Dns {
                 for server in &options.custom_options.addresses {
                     println!("{server}");
                 }
+                let order: Vec<_> = options
+                    .resolver_order
+                    .iter()
+                    .map(|source| format!("{source:?}").to_lowercase())
+                    .collect();
+                println!("Source order: {}", order.join(", "));
             }
         }
+        for rule in &options.split_dns {
+            let servers: Vec<_> = rule.servers.iter().map(|addr| addr.to_string()).collect();
+            println!("Split DNS for {}: {}", rule.domain, servers.join(", "));
+        }
 
         Ok(())
     }
@@ -155,4 +201,43 @@ impl Dns {
         println!("Updated DNS settings");
         Ok(())
     }
+
+    async fn set_order(sources: Vec<DnsResolverSource>) -> Result<()> {
+        DnsResolverSource::validate_order(&sources)?;
+        let mut rpc = MullvadProxyClient::new().await?;
+        let settings = rpc.get_settings().await?;
+        rpc.set_dns_options(DnsOptions {
+            resolver_order: sources,
+            ..settings.tunnel_options.dns_options
+        })
+        .await?;
+        println!("Updated DNS settings");
+        Ok(())
+    }
+
+    async fn split(cmd: SplitDns) -> Result<()> {
+        let mut rpc = MullvadProxyClient::new().await?;
+        let mut options = rpc.get_settings().await?.tunnel_options.dns_options;
+        match cmd {
+            SplitDns::Add { domain, servers } => {
+                let rule = SplitDnsRule::new(&domain, servers)?;
+                options
+                    .split_dns
+                    .retain(|existing| existing.domain != rule.domain);
+                options.split_dns.push(rule);
+            }
+            SplitDns::Remove { domain } => {
+                let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+                let len = options.split_dns.len();
+                options.split_dns.retain(|rule| rule.domain != domain);
+                if options.split_dns.len() == len {
+                    return Err(anyhow!("No split DNS rule for \"{domain}\""));
+                }
+            }
+            SplitDns::Clear => options.split_dns.clear(),
+        }
+        rpc.set_dns_options(options).await?;
+        println!("Updated DNS settings");
+        Ok(())
+    }
 }
@@ -107,6 +107,10 @@ pub enum SetCommands {
     #[clap(subcommand)]
     Entry(EntryArgs),
 
+    /// Pick a new random relay on every connection attempt, rather than favoring some relays.
+    /// The previous exit relay is avoided if possible.
+    Randomize { randomize: BooleanOption },
+
     /// Set a custom WireGuard relay
     Custom {
         /// Hostname or IP
@@ -190,6 +194,22 @@ pub enum OverrideSetCommands {
         /// The IPv6 address to use to connect to this server
         address: Ipv6Addr,
     },
+    /// Override the IPv4 address of the WireGuard endpoint only, for a given relay. Unlike
+    /// `ipv4`, this does not affect obfuscation
+    WireguardIpv4 {
+        /// The unique hostname for the server to set the override on
+        hostname: String,
+        /// The IPv4 address to use for the WireGuard endpoint of this server
+        address: Ipv4Addr,
+    },
+    /// Override the IPv6 address of the WireGuard endpoint only, for a given relay. Unlike
+    /// `ipv6`, this does not affect obfuscation
+    WireguardIpv6 {
+        /// The unique hostname for the server to set the override on
+        hostname: String,
+        /// The IPv6 address to use for the WireGuard endpoint of this server
+        address: Ipv6Addr,
+    },
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -198,6 +218,10 @@ pub enum OverrideUnsetCommands {
     Ipv4 { hostname: String },
     /// Remove overridden entry IPv6 address for the given server
     Ipv6 { hostname: String },
+    /// Remove overridden WireGuard endpoint IPv4 address for the given server
+    WireguardIpv4 { hostname: String },
+    /// Remove overridden WireGuard endpoint IPv6 address for the given server
+    WireguardIpv6 { hostname: String },
 }
 
 impl Relay {
@@ -237,6 +261,14 @@ impl Relay {
 
                 print_option!("Provider(s)", constraints.providers,);
                 print_option!("Ownership", constraints.ownership,);
+                print_option!(
+                    "Randomize each connection",
+                    if settings.randomize_relay_each_connect {
+                        "on"
+                    } else {
+                        "off"
+                    },
+                );
 
                 println!("WireGuard constraints");
 
@@ -393,6 +425,12 @@ impl Relay {
                 })
                 .await
             }
+            SetCommands::Randomize { randomize } => {
+                let mut rpc = MullvadProxyClient::new().await?;
+                rpc.set_randomize_relay_each_connect(*randomize).await?;
+                println!("Changed relay randomization setting");
+                Ok(())
+            }
             SetCommands::Custom {
                 host,
                 port,
@@ -618,6 +656,12 @@ impl Relay {
                     if let Some(ipv6) = relay_override.ipv6_addr_in {
                         println!("{:<12}ipv6: {ipv6}", " ");
                     }
+                    if let Some(ipv4) = relay_override.wireguard_ipv4_addr_in {
+                        println!("{:<12}wireguard ipv4: {ipv4}", " ");
+                    }
+                    if let Some(ipv6) = relay_override.wireguard_ipv6_addr_in {
+                        println!("{:<12}wireguard ipv6: {ipv6}", " ");
+                    }
                 };
 
                 for country in countries_with_overrides {
@@ -654,6 +698,22 @@ impl Relay {
                     )
                     .await?;
                 }
+                OverrideSetCommands::WireguardIpv4 { hostname, address } => {
+                    Self::update_override(
+                        &hostname,
+                        |relay_override| relay_override.wireguard_ipv4_addr_in = Some(address),
+                        true,
+                    )
+                    .await?;
+                }
+                OverrideSetCommands::WireguardIpv6 { hostname, address } => {
+                    Self::update_override(
+                        &hostname,
+                        |relay_override| relay_override.wireguard_ipv6_addr_in = Some(address),
+                        true,
+                    )
+                    .await?;
+                }
             },
             OverrideCommands::Unset(cmds) => match cmds {
                 OverrideUnsetCommands::Ipv4 { hostname } => {
@@ -672,6 +732,22 @@ impl Relay {
                     )
                     .await?;
                 }
+                OverrideUnsetCommands::WireguardIpv4 { hostname } => {
+                    Self::update_override(
+                        &hostname,
+                        |relay_override| relay_override.wireguard_ipv4_addr_in = None,
+                        false,
+                    )
+                    .await?;
+                }
+                OverrideUnsetCommands::WireguardIpv6 { hostname } => {
+                    Self::update_override(
+                        &hostname,
+                        |relay_override| relay_override.wireguard_ipv6_addr_in = None,
+                        false,
+                    )
+                    .await?;
+                }
             },
             OverrideCommands::ClearAll { confirm } => {
                 if confirm
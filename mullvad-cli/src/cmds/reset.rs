@@ -13,6 +13,32 @@ pub async fn handle_factory_reset(assume_yes: bool) -> Result<()> {
     Ok(())
 }
 
+pub async fn handle_factory_reset_preview() -> Result<()> {
+    let mut rpc = MullvadProxyClient::new().await?;
+    let preview = rpc.factory_reset_preview().await?;
+
+    println!("A factory reset would:");
+    println!("- Disconnect");
+    if let Some(device) = &preview.device {
+        println!("- Log out and remove the device \"{device}\" from the account");
+    }
+    if preview.account_history {
+        println!("- Clear the account history");
+    }
+    if preview.settings {
+        println!("- Reset all settings to the default");
+    }
+    let files = preview
+        .settings_files
+        .iter()
+        .chain(&preview.cache_files)
+        .chain(&preview.log_files);
+    for path in files {
+        println!("- Remove {}", path.display());
+    }
+    Ok(())
+}
+
 pub async fn handle_settings_reset(assume_yes: bool) -> Result<()> {
     if !assume_yes
         && !receive_confirmation(
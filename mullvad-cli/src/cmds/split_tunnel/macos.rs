@@ -1,4 +1,5 @@
 use anyhow::Result;
+use ipnetwork::IpNetwork;
 use std::path::PathBuf;
 
 use clap::Subcommand;
@@ -22,8 +23,16 @@ pub enum SplitTunnel {
 
 #[derive(Subcommand, Debug)]
 pub enum App {
-    Add { path: PathBuf },
-    Remove { path: PathBuf },
+    Add {
+        path: PathBuf,
+        /// Only exclude traffic to this subnet from the tunnel. May be given more than once. By
+        /// default, all traffic from the app is excluded.
+        #[arg(long = "subnet")]
+        subnets: Vec<IpNetwork>,
+    },
+    Remove {
+        path: PathBuf,
+    },
     Clear,
 }
 
@@ -40,7 +49,18 @@ impl SplitTunnel {
 
                 println!("Excluded applications:");
                 for path in &settings.apps {
-                    println!("{}", path.display());
+                    match settings.allowed_subnets.get(path) {
+                        Some(subnets) => println!(
+                            "{} (only for {})",
+                            path.display(),
+                            subnets
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                        None => println!("{}", path.display()),
+                    }
                 }
 
                 Ok(())
@@ -57,11 +77,14 @@ impl SplitTunnel {
 
     async fn app(subcmd: App) -> Result<()> {
         match subcmd {
-            App::Add { path } => {
-                MullvadProxyClient::new()
-                    .await?
-                    .add_split_tunnel_app(path)
-                    .await?;
+            App::Add { path, subnets } => {
+                let mut rpc = MullvadProxyClient::new().await?;
+                if subnets.is_empty() {
+                    rpc.add_split_tunnel_app(path).await?;
+                } else {
+                    rpc.add_split_tunnel_app_with_subnets(path, &subnets)
+                        .await?;
+                }
                 println!("Added path to excluded apps list");
                 Ok(())
             }
@@ -74,6 +74,26 @@ impl Status {
                     };
                     print_debug_or_json(&args, "Leak detected", &leak)?;
                 }
+                DaemonEvent::AccountExpired(action) => {
+                    print_debug_or_json(&args, "Account expired", &action)?;
+                }
+                DaemonEvent::RelaySelectionFallback(fallback) => {
+                    print_debug_or_json(&args, "Relay selection fallback", &fallback)?;
+                }
+                DaemonEvent::CaptivePortalDetected => {
+                    if !print_debug_or_json(&args, "Captive portal detected", &())? {
+                        println!("Captive portal detected. Waiting for it to be passed");
+                    }
+                }
+                DaemonEvent::TrustedNetworkChanged(trusted) => {
+                    if !print_debug_or_json(&args, "Trusted network changed", &trusted)? {
+                        if trusted {
+                            println!("Joined a trusted network");
+                        } else {
+                            println!("Left the trusted network");
+                        }
+                    }
+                }
             }
         }
         Ok(())
@@ -6,6 +6,7 @@ use mullvad_types::{
     relay_constraints::{AllowedIps, RelaySettings, WireguardConstraints},
     wireguard::{QuantumResistantState, RotationInterval},
 };
+use std::net::IpAddr;
 
 use super::BooleanOption;
 use crate::print_option;
@@ -25,6 +26,12 @@ pub enum TunnelOptions {
     /// Configure the tunnel MTU, or 'any'
     Mtu { mtu: Constraint<u16> },
 
+    /// Configure the persistent keepalive interval in seconds, or 'any' to disable it
+    Keepalive { interval: Constraint<u16> },
+
+    /// Bind the tunnel socket to a local IP address, or 'any' to let the OS choose
+    BindAddress { address: Constraint<IpAddr> },
+
     /// Configure quantum-resistant key exchange
     QuantumResistant { state: QuantumResistantState },
 
@@ -34,6 +41,10 @@ pub enum TunnelOptions {
     /// Configure whether to enable DAITA direct only
     DaitaDirectOnly { state: BooleanOption },
 
+    /// Configure whether to fail to connect rather than connecting without DAITA when no relay
+    /// that supports DAITA matches the constraints
+    DaitaRequired { state: BooleanOption },
+
     /// Specify custom allowed IPs for WireGuard tunnels. Use comma-separated values of IPs and IP ranges in CIDR notation.
     /// A empty string resets to the default value, where all traffic is allowed, i.e. (0.0.0.0/0,::/0).
     /// For CIDR ranges, host bits must be zero (e.g., "10.0.0.0/24" is valid, "10.0.0.1/24" is not).
@@ -54,6 +65,10 @@ pub enum TunnelOptions {
     /// Enable or disable IPv6 in the tunnel
     #[clap(arg_required_else_help = true)]
     Ipv6 { state: BooleanOption },
+
+    /// Limit how many times to retry connecting before blocking traffic, or 'any' to retry
+    /// indefinitely
+    MaxReconnectAttempts { max_attempts: Constraint<u32> },
 }
 
 impl Tunnel {
@@ -78,6 +93,22 @@ impl Tunnel {
                 .map(|val| val.to_string())
                 .unwrap_or("unset".to_string()),
         );
+        print_option!(
+            "Keepalive",
+            tunnel_options
+                .wireguard
+                .keepalive
+                .map(|val| format!("{val} s"))
+                .unwrap_or("unset".to_string()),
+        );
+        print_option!(
+            "Bind address",
+            tunnel_options
+                .wireguard
+                .bind_address
+                .map(|val| val.to_string())
+                .unwrap_or("unset".to_string()),
+        );
         print_option!(
             "Quantum resistance",
             tunnel_options.wireguard.quantum_resistant,
@@ -125,6 +156,13 @@ impl Tunnel {
                 "off"
             }
         );
+        print_option!(
+            "Max reconnect attempts",
+            rpc.get_max_reconnect_attempts()
+                .await?
+                .map(|val| val.to_string())
+                .unwrap_or("unset".to_string()),
+        );
 
         Ok(())
     }
@@ -137,6 +175,14 @@ impl Tunnel {
                 rpc.set_wireguard_mtu(mtu.option()).await?;
                 println!("MTU parameter has been updated");
             }
+            TunnelOptions::Keepalive { interval } => {
+                rpc.set_wireguard_keepalive(interval.option()).await?;
+                println!("Keepalive interval has been updated");
+            }
+            TunnelOptions::BindAddress { address } => {
+                rpc.set_tunnel_bind_address(address.option()).await?;
+                println!("Bind address has been updated");
+            }
             TunnelOptions::QuantumResistant { state } => {
                 rpc.set_quantum_resistant_tunnel(state).await?;
                 println!("Quantum resistant setting has been updated");
@@ -149,6 +195,10 @@ impl Tunnel {
                 rpc.set_daita_direct_only(*state).await?;
                 println!("Direct only setting has been updated");
             }
+            TunnelOptions::DaitaRequired { state } => {
+                rpc.set_daita_required(*state).await?;
+                println!("DAITA required setting has been updated");
+            }
             TunnelOptions::AllowedIps { allowed_ips } => {
                 let ips = AllowedIps::parse(allowed_ips.split(','))?;
                 rpc.set_wireguard_allowed_ips(ips).await?;
@@ -175,6 +225,11 @@ impl Tunnel {
                 rpc.set_enable_ipv6(*state).await?;
                 println!("IPv6: {state}");
             }
+            TunnelOptions::MaxReconnectAttempts { max_attempts } => {
+                rpc.set_max_reconnect_attempts(max_attempts.option())
+                    .await?;
+                println!("Max reconnect attempts has been updated");
+            }
         }
 
         Ok(())
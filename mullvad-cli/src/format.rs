@@ -33,11 +33,13 @@ pub fn print_state(state: &TunnelState, previous_state: Option<&TunnelState>, ve
         Disconnected {
             location,
             locked_down,
+            disconnect_reason,
         } => {
             let old_location = match previous_state {
                 Some(Disconnected {
                     location,
                     locked_down: was_locked_down,
+                    ..
                 }) => {
                     if *locked_down && !was_locked_down {
                         print_option!("Internet access is blocked due to lockdown mode");
@@ -48,6 +50,9 @@ pub fn print_state(state: &TunnelState, previous_state: Option<&TunnelState>, ve
                 }
                 _ => {
                     println!("Disconnected");
+                    if let Some(reason) = disconnect_reason {
+                        print_option!("Reason", reason);
+                    }
                     if *locked_down {
                         print_option!("Internet access is blocked due to lockdown mode");
                     }
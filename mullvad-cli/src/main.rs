@@ -125,6 +125,9 @@ enum Cli {
     FactoryReset {
         #[clap(long, short = 'y', default_value_t = false)]
         assume_yes: bool,
+        /// List what would be removed, without removing anything
+        #[clap(long, default_value_t = false)]
+        dry_run: bool,
     },
 
     /// Reset settings only, but remain logged in and keep logs and caches
@@ -178,7 +181,11 @@ async fn main() -> Result<()> {
         Cli::AntiCensorship(cmd) => cmd.handle().await,
         Cli::ApiAccess(cmd) => cmd.handle().await,
         Cli::Version => version::print().await,
-        Cli::FactoryReset { assume_yes } => reset::handle_factory_reset(assume_yes).await,
+        Cli::FactoryReset {
+            assume_yes,
+            dry_run: false,
+        } => reset::handle_factory_reset(assume_yes).await,
+        Cli::FactoryReset { dry_run: true, .. } => reset::handle_factory_reset_preview().await,
         Cli::ResetSettings { assume_yes } => reset::handle_settings_reset(assume_yes).await,
         Cli::Relay(cmd) => cmd.handle().await,
         Cli::Tunnel(cmd) => cmd.handle().await,
@@ -1,9 +1,23 @@
 use crate::{Daemon, settings};
 use mullvad_api::{ApiProxy, access_mode, proxy::ApiConnectionMode, rest};
 use mullvad_types::{
-    access_method::{self, AccessMethod, AccessMethodSetting},
+    access_method::{
+        self, AccessMethod, AccessMethodSearch, AccessMethodSetting, AccessMethodTestResult,
+    },
     settings::Settings,
 };
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+use talpid_types::{
+    ErrorExt,
+    net::proxy::{CustomProxy, SHADOWSOCKS_CIPHERS, SocksAuth},
+};
+
+/// Time after which an access method test is aborted and the access method is
+/// considered unreachable.
+const ACCESS_METHOD_TEST_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -26,6 +40,12 @@ pub enum Error {
     /// Access methods settings error
     #[error("Settings error")]
     Settings(#[from] settings::Error),
+    /// Access methods could not be serialized or deserialized
+    #[error("Failed to serialize or deserialize access methods")]
+    Serialization(#[from] serde_json::Error),
+    /// An imported access method is not valid
+    #[error("Imported access method \"{name}\" is invalid: {reason}")]
+    InvalidImport { name: String, reason: &'static str },
 }
 
 impl Daemon {
@@ -142,6 +162,66 @@ impl Daemon {
         Ok(())
     }
 
+    /// Serialize all custom [`AccessMethodSetting`]s as JSON.
+    ///
+    /// If `include_secrets` is `false`, SOCKS5 credentials and Shadowsocks
+    /// passwords are left out of the exported access methods, and the access
+    /// methods are marked so that they cannot be imported.
+    pub fn export_access_methods(&self, include_secrets: bool) -> Result<String, Error> {
+        let access_methods: Vec<_> = self
+            .settings
+            .api_access_methods
+            .iter_custom()
+            .cloned()
+            .map(|mut setting| {
+                let secrets_stripped =
+                    !include_secrets && strip_secrets(&mut setting.access_method);
+                ExportedAccessMethod {
+                    setting,
+                    secrets_stripped,
+                }
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&access_methods)?)
+    }
+
+    /// Add the custom [`AccessMethodSetting`]s in `json`, as produced by
+    /// [`Daemon::export_access_methods`], to the daemon's settings.
+    ///
+    /// All access methods are validated before any of them are added. Access
+    /// methods which were exported without their secrets are rejected. Imported
+    /// access methods are given new ids, and are renamed if their name collides
+    /// with an existing access method.
+    pub async fn import_access_methods(&mut self, json: &str) -> Result<(), Error> {
+        let imported: Vec<ExportedAccessMethod> = serde_json::from_str(json)?;
+        imported.iter().try_for_each(validate_import)?;
+
+        let mut taken_names: HashSet<String> = self
+            .settings
+            .api_access_methods
+            .iter()
+            .map(AccessMethodSetting::get_name)
+            .collect();
+        let access_methods: Vec<_> = imported
+            .into_iter()
+            .map(|ExportedAccessMethod { setting, .. }| {
+                let name = unique_name(setting.name, &taken_names);
+                taken_names.insert(name.clone());
+                AccessMethodSetting::new(name, setting.enabled, setting.access_method)
+            })
+            .collect();
+
+        self.settings
+            .try_update(|settings| -> Result<(), access_method::Error> {
+                for access_method in access_methods {
+                    settings.api_access_methods.append(access_method)?;
+                }
+                Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+
     /// Return the [`AccessMethodSetting`] which is currently used to access the
     /// Mullvad API.
     pub async fn get_current_access_method(&self) -> Result<AccessMethodSetting, Error> {
@@ -156,7 +236,6 @@ impl Daemon {
     /// different kinds of testing contexts, such as testing
     /// [`AccessMethodSetting`]s or on the fly testing of
     /// [`talpid_types::net::proxy::CustomProxy`]s.
-    #[cfg(not(target_os = "android"))]
     pub(crate) async fn test_access_method(
         proxy: talpid_types::net::AllowedEndpoint,
         access_method_selector: access_mode::AccessModeSelectorHandle,
@@ -166,35 +245,191 @@ impl Daemon {
         )>,
         api_proxy: ApiProxy,
     ) -> Result<bool, Error> {
+        Self::with_allowed_endpoint(
+            proxy,
+            access_method_selector,
+            daemon_event_sender,
+            Self::perform_api_request(api_proxy),
+        )
+        .await?
+    }
+
+    /// Allow `endpoint` through the firewall while `test` runs, and then allow
+    /// the endpoint of the active access method again.
+    ///
+    /// The firewall is restored by the test itself, also if it fails or times
+    /// out, so that no test runs with the endpoint left behind by another.
+    #[cfg(not(target_os = "android"))]
+    async fn with_allowed_endpoint<T>(
+        endpoint: talpid_types::net::AllowedEndpoint,
+        access_method_selector: access_mode::AccessModeSelectorHandle,
+        daemon_event_sender: crate::DaemonEventSender<(
+            access_mode::AccessMethodEvent,
+            futures::channel::oneshot::Sender<()>,
+        )>,
+        test: impl Future<Output = T>,
+    ) -> Result<T, Error> {
         let reset = access_method_selector
             .get_current()
             .await
             .map(|connection_mode| connection_mode.endpoint)?;
-
-        access_mode::AccessMethodEvent::Allow { endpoint: proxy }
-            .send(daemon_event_sender.to_unbounded_sender())
-            .await?;
-
-        let result = Self::perform_api_request(api_proxy).await;
-
-        access_mode::AccessMethodEvent::Allow { endpoint: reset }
-            .send(daemon_event_sender.to_unbounded_sender())
-            .await?;
-
-        result
+        let result = allow_endpoint_during(
+            endpoint,
+            reset,
+            daemon_event_sender.to_unbounded_sender(),
+            test,
+        )
+        .await?;
+        Ok(result)
     }
 
     #[cfg(target_os = "android")]
-    pub(crate) async fn test_access_method(
+    async fn with_allowed_endpoint<T>(
         _: talpid_types::net::AllowedEndpoint,
         _: access_mode::AccessModeSelectorHandle,
         _: crate::DaemonEventSender<(
             access_mode::AccessMethodEvent,
             futures::channel::oneshot::Sender<()>,
         )>,
+        test: impl Future<Output = T>,
+    ) -> Result<T, Error> {
+        Ok(test.await)
+    }
+
+    /// Test if the API is reachable via each of the `test_subjects`.
+    ///
+    /// Only one API endpoint is allowed through the firewall at a time, so
+    /// the access methods are tested one at a time, in order. Each test is
+    /// given [`ACCESS_METHOD_TEST_TIMEOUT`] to finish. Access methods which
+    /// could not be resolved, fail or time out are reported as unreachable,
+    /// along with the reason. The results are returned in the same order as
+    /// `test_subjects`.
+    pub(crate) async fn test_access_methods(
+        test_subjects: Vec<TestSubject>,
+        access_method_selector: access_mode::AccessModeSelectorHandle,
+        daemon_event_sender: crate::DaemonEventSender<(
+            access_mode::AccessMethodEvent,
+            futures::channel::oneshot::Sender<()>,
+        )>,
+    ) -> Vec<AccessMethodTestResult> {
+        run_access_method_tests(test_subjects, false, |(test_subject, api_proxy)| {
+            Self::test_access_method_with_timeout(
+                test_subject,
+                api_proxy,
+                access_method_selector.clone(),
+                daemon_event_sender.clone(),
+            )
+        })
+        .await
+    }
+
+    /// Test the `test_subjects` one at a time, in order, and stop at the first
+    /// access method which can reach the API.
+    ///
+    /// Each test is given [`ACCESS_METHOD_TEST_TIMEOUT`] to finish.
+    pub(crate) async fn find_working_access_method(
+        test_subjects: Vec<TestSubject>,
+        access_method_selector: access_mode::AccessModeSelectorHandle,
+        daemon_event_sender: crate::DaemonEventSender<(
+            access_mode::AccessMethodEvent,
+            futures::channel::oneshot::Sender<()>,
+        )>,
+    ) -> AccessMethodSearch {
+        let results = run_access_method_tests(test_subjects, true, |(test_subject, api_proxy)| {
+            Self::test_access_method_with_timeout(
+                test_subject,
+                api_proxy,
+                access_method_selector.clone(),
+                daemon_event_sender.clone(),
+            )
+        })
+        .await;
+
+        access_method_search(results)
+    }
+
+    /// Test if the API is reachable via `test_subject`, giving up after
+    /// [`ACCESS_METHOD_TEST_TIMEOUT`].
+    async fn test_access_method_with_timeout(
+        test_subject: access_mode::ResolvedConnectionMode,
         api_proxy: ApiProxy,
-    ) -> Result<bool, Error> {
-        Self::perform_api_request(api_proxy).await
+        access_method_selector: access_mode::AccessModeSelectorHandle,
+        daemon_event_sender: crate::DaemonEventSender<(
+            access_mode::AccessMethodEvent,
+            futures::channel::oneshot::Sender<()>,
+        )>,
+    ) -> Result<bool, String> {
+        // Only the request is timed out, so that the firewall is always restored
+        let test = tokio::time::timeout(
+            ACCESS_METHOD_TEST_TIMEOUT,
+            Self::perform_api_request(api_proxy),
+        );
+        match Self::with_allowed_endpoint(
+            test_subject.endpoint,
+            access_method_selector,
+            daemon_event_sender,
+            test,
+        )
+        .await
+        {
+            Ok(Ok(result)) => result.map_err(|error| error.display_chain()),
+            Err(error) => Err(error.display_chain()),
+            Ok(Err(_)) => {
+                log::debug!(
+                    "Testing API access method {} timed out",
+                    test_subject.setting.name
+                );
+                Err(format!(
+                    "Timed out after {} seconds",
+                    ACCESS_METHOD_TEST_TIMEOUT.as_secs()
+                ))
+            }
+        }
+    }
+
+    /// Resolve all enabled access methods in priority order, and create an
+    /// [`ApiProxy`] for each one. Access methods which cannot be resolved are
+    /// kept in place, along with the reason, so that they are reported as
+    /// unreachable.
+    pub(crate) async fn resolve_enabled_access_methods(&mut self) -> Vec<TestSubject> {
+        let enabled_methods: Vec<_> = self
+            .settings
+            .api_access_methods
+            .iter()
+            .filter(|setting| setting.enabled())
+            .cloned()
+            .collect();
+
+        let mut test_subjects = vec![];
+        for access_method in enabled_methods {
+            let test_subject = match self
+                .access_mode_handler
+                .resolve(access_method.clone())
+                .await
+            {
+                Ok(Some(test_subject)) => {
+                    let api_proxy =
+                        self.create_limited_api_proxy(test_subject.connection_mode.clone());
+                    Ok((test_subject, api_proxy))
+                }
+                Ok(None) => {
+                    log::debug!("Could not resolve API access method {}", access_method.name);
+                    Err("Could not be resolved".to_owned())
+                }
+                Err(error) => {
+                    log::debug!(
+                        "{}",
+                        error.display_chain_with_msg(&format!(
+                            "Failed to resolve API access method {}",
+                            access_method.name
+                        ))
+                    );
+                    Err(error.display_chain())
+                }
+            };
+            test_subjects.push((access_method.get_id(), test_subject));
+        }
+        test_subjects
     }
 
     /// Create an [`ApiProxy`] which will perform all REST requests against one
@@ -215,3 +450,399 @@ impl Daemon {
         Ok(api_proxy.api_addrs_available().await?)
     }
 }
+
+/// An enabled access method which is ready to be tested, or the reason why it
+/// cannot be tested.
+pub(crate) type TestSubject = (
+    access_method::Id,
+    Result<(access_mode::ResolvedConnectionMode, ApiProxy), String>,
+);
+
+/// Run `test` on each of `test_subjects`, one at a time and in order, and
+/// collect the results. Subjects which cannot be tested are reported as
+/// unreachable. If `stop_when_reachable` is `true`, no subjects are tested
+/// after the first reachable one.
+async fn run_access_method_tests<T, F, Fut>(
+    test_subjects: Vec<(access_method::Id, Result<T, String>)>,
+    stop_when_reachable: bool,
+    mut test: F,
+) -> Vec<AccessMethodTestResult>
+where
+    F: FnMut(T) -> Fut,
+    Fut: Future<Output = Result<bool, String>>,
+{
+    let mut results = vec![];
+    for (id, test_subject) in test_subjects {
+        let start = Instant::now();
+        let outcome = match test_subject {
+            Ok(test_subject) => test(test_subject).await,
+            Err(error) => Err(error),
+        };
+        let reachable = matches!(outcome, Ok(true));
+        results.push(AccessMethodTestResult {
+            id,
+            reachable,
+            duration: start.elapsed(),
+            error: outcome.err(),
+        });
+        if reachable && stop_when_reachable {
+            break;
+        }
+    }
+    results
+}
+
+/// Allow `endpoint` through the firewall while `test` runs, and then allow
+/// `reset` again, whatever the outcome of `test`.
+#[cfg(not(target_os = "android"))]
+async fn allow_endpoint_during<T>(
+    endpoint: talpid_types::net::AllowedEndpoint,
+    reset: talpid_types::net::AllowedEndpoint,
+    event_tx: futures::channel::mpsc::UnboundedSender<(
+        access_mode::AccessMethodEvent,
+        futures::channel::oneshot::Sender<()>,
+    )>,
+    test: impl Future<Output = T>,
+) -> Result<T, access_mode::Error> {
+    access_mode::AccessMethodEvent::Allow { endpoint }
+        .send(event_tx.clone())
+        .await?;
+    let result = test.await;
+    access_mode::AccessMethodEvent::Allow { endpoint: reset }
+        .send(event_tx)
+        .await?;
+    Ok(result)
+}
+
+/// Summarize the `results` of testing access methods until one was reachable.
+/// The working access method, if any, is the last one that was tested.
+fn access_method_search(results: Vec<AccessMethodTestResult>) -> AccessMethodSearch {
+    AccessMethodSearch {
+        working: results
+            .last()
+            .filter(|result| result.reachable)
+            .map(|result| result.id.clone()),
+        results,
+    }
+}
+
+/// An [`AccessMethodSetting`] as exported by [`Daemon::export_access_methods`].
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct ExportedAccessMethod {
+    #[serde(flatten)]
+    setting: AccessMethodSetting,
+    /// Whether credentials were left out of the access method on export
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    secrets_stripped: bool,
+}
+
+/// Remove any credentials from `access_method`. Returns whether anything was removed.
+fn strip_secrets(access_method: &mut AccessMethod) -> bool {
+    match access_method {
+        AccessMethod::Custom(CustomProxy::Shadowsocks(shadowsocks)) => {
+            shadowsocks.password.clear();
+            true
+        }
+        AccessMethod::Custom(CustomProxy::Socks5Remote(socks5)) => socks5.auth.take().is_some(),
+        AccessMethod::Custom(CustomProxy::Socks5Local(_)) | AccessMethod::BuiltIn(_) => false,
+    }
+}
+
+/// Check that an imported access method is a well-formed custom proxy, which
+/// was exported with its secrets.
+fn validate_import(imported: &ExportedAccessMethod) -> Result<(), Error> {
+    let setting = &imported.setting;
+    let invalid = |reason| Error::InvalidImport {
+        name: setting.name.clone(),
+        reason,
+    };
+    if imported.secrets_stripped {
+        return Err(invalid("the credentials were left out on export"));
+    }
+    if setting.name.is_empty() {
+        return Err(invalid("the name is empty"));
+    }
+    match &setting.access_method {
+        AccessMethod::BuiltIn(_) => Err(invalid("built-in access methods cannot be imported")),
+        AccessMethod::Custom(CustomProxy::Shadowsocks(shadowsocks)) => {
+            if !SHADOWSOCKS_CIPHERS.contains(&shadowsocks.cipher.as_str()) {
+                return Err(invalid("unsupported Shadowsocks cipher"));
+            }
+            Ok(())
+        }
+        AccessMethod::Custom(CustomProxy::Socks5Local(socks5)) => {
+            if socks5.local_port == 0 {
+                return Err(invalid("the local port must not be 0"));
+            }
+            Ok(())
+        }
+        AccessMethod::Custom(CustomProxy::Socks5Remote(socks5)) => {
+            if let Some(auth) = &socks5.auth {
+                SocksAuth::new(auth.username().to_owned(), auth.password().to_owned())
+                    .map_err(|_| invalid("invalid SOCKS5 credentials"))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Return `name`, or `name` with a numbered suffix if it is already in `taken_names`.
+fn unique_name(name: String, taken_names: &HashSet<String>) -> String {
+    if !taken_names.contains(&name) {
+        return name;
+    }
+    (2..)
+        .map(|n| format!("{name} ({n})"))
+        .find(|candidate| !taken_names.contains(candidate))
+        .expect("ran out of names")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+    use talpid_types::net::proxy::{Shadowsocks, Socks5Remote};
+
+    /// Tests never overlap, and every subject is reported in order, including those which could
+    /// not be tested.
+    #[tokio::test]
+    async fn test_run_access_method_tests_in_order() {
+        let ids: Vec<_> = (0..4).map(|_| access_method::Id::new()).collect();
+        let test_subjects = vec![
+            (ids[0].clone(), Ok(Err("unreachable".to_owned()))),
+            (ids[1].clone(), Err("unresolved".to_owned())),
+            (ids[2].clone(), Ok(Ok(true))),
+            (ids[3].clone(), Ok(Ok(false))),
+        ];
+
+        let events = Rc::new(RefCell::new(vec![]));
+        let results = run_access_method_tests(test_subjects, false, |outcome| {
+            let events = events.clone();
+            async move {
+                events.borrow_mut().push("start");
+                tokio::task::yield_now().await;
+                events.borrow_mut().push("end");
+                outcome
+            }
+        })
+        .await;
+
+        assert_eq!(*events.borrow(), ["start", "end"].repeat(3));
+        let summary: Vec<_> = results
+            .into_iter()
+            .map(|result| (result.id, result.reachable, result.error))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (ids[0].clone(), false, Some("unreachable".to_owned())),
+                (ids[1].clone(), false, Some("unresolved".to_owned())),
+                (ids[2].clone(), true, None),
+                (ids[3].clone(), false, None),
+            ]
+        );
+    }
+
+    /// No access methods are tested after the first reachable one.
+    #[tokio::test]
+    async fn test_run_access_method_tests_stop_when_reachable() {
+        let ids: Vec<_> = (0..3).map(|_| access_method::Id::new()).collect();
+        let test_subjects = vec![
+            (ids[0].clone(), Err("unresolved".to_owned())),
+            (ids[1].clone(), Ok(true)),
+            (ids[2].clone(), Ok(true)),
+        ];
+
+        let results = run_access_method_tests(test_subjects, true, |reachable| {
+            std::future::ready(Ok(reachable))
+        })
+        .await;
+
+        let tested: Vec<_> = results.into_iter().map(|result| result.id).collect();
+        assert_eq!(tested, ids[..2]);
+    }
+
+    /// Access methods which could not be resolved are reported in settings
+    /// order, among those which were tested, and the search stops at the first
+    /// working access method.
+    #[tokio::test]
+    async fn test_find_working_access_method_in_order() {
+        let ids: Vec<_> = (0..5).map(|_| access_method::Id::new()).collect();
+        let test_subjects = vec![
+            (ids[0].clone(), Err("unresolved".to_owned())),
+            (ids[1].clone(), Ok(Err("unreachable".to_owned()))),
+            (ids[2].clone(), Err("unresolved".to_owned())),
+            (ids[3].clone(), Ok(Ok(true))),
+            (ids[4].clone(), Ok(Ok(true))),
+        ];
+
+        let search = access_method_search(
+            run_access_method_tests(test_subjects, true, std::future::ready).await,
+        );
+
+        assert_eq!(search.working, Some(ids[3].clone()));
+        let summary: Vec<_> = search
+            .results
+            .into_iter()
+            .map(|result| (result.id, result.error))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (ids[0].clone(), Some("unresolved".to_owned())),
+                (ids[1].clone(), Some("unreachable".to_owned())),
+                (ids[2].clone(), Some("unresolved".to_owned())),
+                (ids[3].clone(), None),
+            ]
+        );
+    }
+
+    /// No access method is working if none of them could reach the API.
+    #[tokio::test]
+    async fn test_find_working_access_method_none_reachable() {
+        let ids: Vec<_> = (0..2).map(|_| access_method::Id::new()).collect();
+        let test_subjects = vec![
+            (ids[0].clone(), Ok(Ok(false))),
+            (ids[1].clone(), Err("unresolved".to_owned())),
+        ];
+
+        let search = access_method_search(
+            run_access_method_tests(test_subjects, true, std::future::ready).await,
+        );
+
+        assert_eq!(search.working, None);
+        let tested: Vec<_> = search.results.into_iter().map(|result| result.id).collect();
+        assert_eq!(tested, ids);
+    }
+
+    /// An access method's endpoint is only allowed while it is tested, also if
+    /// the test times out, so the next test starts from the original endpoint.
+    #[cfg(not(target_os = "android"))]
+    #[tokio::test(start_paused = true)]
+    async fn test_allow_endpoint_during_timeout() {
+        use futures::StreamExt;
+
+        let endpoint = crate::api::resolve_allowed_endpoint(
+            &ApiConnectionMode::Direct,
+            "192.0.2.1:443".parse().unwrap(),
+        );
+        let reset = crate::api::resolve_allowed_endpoint(
+            &ApiConnectionMode::Direct,
+            "192.0.2.2:443".parse().unwrap(),
+        );
+
+        let (event_tx, mut event_rx) = futures::channel::mpsc::unbounded();
+        let allowed = tokio::spawn(async move {
+            let mut allowed = vec![];
+            while let Some((event, done_tx)) = event_rx.next().await {
+                if let access_mode::AccessMethodEvent::Allow { endpoint } = event {
+                    allowed.push(endpoint);
+                }
+                let _ = done_tx.send(());
+            }
+            allowed
+        });
+
+        let test = tokio::time::timeout(ACCESS_METHOD_TEST_TIMEOUT, std::future::pending::<()>());
+        let result = allow_endpoint_during(endpoint.clone(), reset.clone(), event_tx, test)
+            .await
+            .unwrap();
+
+        assert!(result.is_err());
+        assert_eq!(allowed.await.unwrap(), [endpoint, reset]);
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let taken_names: HashSet<_> = ["foo".to_owned(), "foo (2)".to_owned()].into();
+        assert_eq!(unique_name("bar".to_owned(), &taken_names), "bar");
+        assert_eq!(unique_name("foo".to_owned(), &taken_names), "foo (3)");
+    }
+
+    fn exported(setting: AccessMethodSetting) -> ExportedAccessMethod {
+        ExportedAccessMethod {
+            setting,
+            secrets_stripped: false,
+        }
+    }
+
+    #[test]
+    fn test_strip_secrets() {
+        let auth = SocksAuth::new("user".to_owned(), "hunter2".to_owned()).unwrap();
+        let mut socks5 = AccessMethod::from(Socks5Remote::new_with_authentication(
+            ([1, 2, 3, 4], 1080),
+            auth,
+        ));
+        assert!(strip_secrets(&mut socks5));
+        assert_eq!(
+            socks5,
+            AccessMethod::from(Socks5Remote::new(([1, 2, 3, 4], 1080)))
+        );
+        // Nothing is stripped from a proxy without credentials
+        assert!(!strip_secrets(&mut socks5));
+    }
+
+    /// Access methods exported without their secrets must not be imported with
+    /// empty credentials.
+    #[test]
+    fn test_import_stripped_secrets() {
+        let mut setting = AccessMethodSetting::new(
+            "ss".to_owned(),
+            true,
+            Shadowsocks::new(
+                ([1, 2, 3, 4], 443),
+                "aes-256-gcm".to_owned(),
+                "pw".to_owned(),
+            )
+            .into(),
+        );
+        let secrets_stripped = strip_secrets(&mut setting.access_method);
+        let json = serde_json::to_string(&ExportedAccessMethod {
+            setting: setting.clone(),
+            secrets_stripped,
+        })
+        .unwrap();
+
+        let imported: ExportedAccessMethod = serde_json::from_str(&json).unwrap();
+        assert!(imported.secrets_stripped);
+        assert!(matches!(
+            validate_import(&imported),
+            Err(Error::InvalidImport { .. })
+        ));
+
+        // Access methods exported with their secrets are not marked
+        let json = serde_json::to_string(&exported(setting)).unwrap();
+        assert!(!json.contains("secrets_stripped"));
+        let imported: ExportedAccessMethod = serde_json::from_str(&json).unwrap();
+        assert!(!imported.secrets_stripped);
+    }
+
+    #[test]
+    fn test_validate_import() {
+        let valid = AccessMethodSetting::new(
+            "ss".to_owned(),
+            true,
+            Shadowsocks::new(
+                ([1, 2, 3, 4], 443),
+                "aes-256-gcm".to_owned(),
+                "pw".to_owned(),
+            )
+            .into(),
+        );
+        assert!(validate_import(&exported(valid)).is_ok());
+
+        let bad_cipher = AccessMethodSetting::new(
+            "ss".to_owned(),
+            true,
+            Shadowsocks::new(([1, 2, 3, 4], 443), "rot13".to_owned(), "pw".to_owned()).into(),
+        );
+        assert!(validate_import(&exported(bad_cipher)).is_err());
+
+        let built_in = AccessMethodSetting::new(
+            "direct".to_owned(),
+            true,
+            access_method::BuiltInAccessMethod::Direct.into(),
+        );
+        assert!(validate_import(&exported(built_in)).is_err());
+    }
+}
@@ -10,6 +10,7 @@ use mullvad_api::{
     access_mode::AccessMethodResolver,
     availability::ApiAvailability,
     proxy::{ApiConnectionMode, ProxyConfig},
+    rest::RequestServiceHandle,
 };
 use mullvad_encrypted_dns_proxy::state::EncryptedDnsProxyState;
 use mullvad_management_interface::async_trait;
@@ -21,6 +22,7 @@ use talpid_types::net::AllowedEndpoint;
 use talpid_types::net::Endpoint;
 use talpid_types::net::TransportProtocol;
 use talpid_types::net::{AllowedClients, Connectivity, proxy::CustomProxy};
+use tokio::sync::watch;
 
 pub struct DaemonAccessMethodResolver {
     relay_selector: RelaySelector,
@@ -152,26 +154,39 @@ pub(crate) fn create_bypass_tx(
     Some(bypass_tx)
 }
 
-/// Forwards the received values from `offline_state_rx` to the [`ApiAvailability`].
+/// Forwards the received values from `offline_state_rx` to the [`ApiAvailability`]. Returns a
+/// receiver of the most recent connectivity.
+///
+/// Pooled API connections are dropped whenever connectivity changes, since they may no longer be
+/// usable.
 pub(crate) fn forward_offline_state(
     api_availability: ApiAvailability,
+    api_service: RequestServiceHandle,
     mut offline_state_rx: mpsc::UnboundedReceiver<Connectivity>,
-) {
+) -> watch::Receiver<Connectivity> {
+    let (connectivity_tx, connectivity_rx) = watch::channel(Connectivity::PresumeOnline);
     tokio::spawn(async move {
-        let is_offline = offline_state_rx
+        let mut connectivity = offline_state_rx
             .next()
             .await
-            .expect("missing initial offline state")
-            .is_offline();
+            .expect("missing initial offline state");
+        let is_offline = connectivity.is_offline();
         log::info!(
             "Initial offline state - {state}",
             state = if is_offline { "offline" } else { "online" },
         );
         api_availability.set_offline(is_offline);
+        connectivity_tx.send_replace(connectivity);
 
         while let Some(state) = offline_state_rx.next().await {
             log::info!("Detecting changes to offline state - {state:?}");
             api_availability.set_offline(state.is_offline());
+            if state != connectivity {
+                api_service.reset_connections();
+                connectivity = state;
+            }
+            connectivity_tx.send_replace(state);
         }
     });
+    connectivity_rx
 }
@@ -4,6 +4,7 @@
 use mullvad_api::ApiEndpoint;
 use mullvad_api::{AddressCache, ApiProxy, rest::MullvadRestHandle};
 use std::time::Duration;
+use talpid_types::ErrorExt;
 
 const API_IP_CHECK_INITIAL: Duration = Duration::from_mins(15);
 const API_IP_CHECK_INTERVAL: Duration = Duration::from_hours(24);
@@ -26,31 +27,23 @@ pub async fn run_api_address_fetcher(
     loop {
         talpid_time::sleep(next_delay).await;
 
-        if let Err(error) = availability.wait_background().await {
+        if let Err(error) = availability.wait_non_essential().await {
             log::error!("Failed while waiting for API: {}", error);
             continue;
         }
-        match api_proxy.clone().get_api_addrs().await {
-            Ok(new_addrs) => {
-                if let Some(addr) = new_addrs.first() {
-                    log::debug!(
-                        "Fetched new API address {:?}. Fetching again in {} hours",
-                        addr,
-                        API_IP_CHECK_INTERVAL.as_secs() / (60 * 60)
-                    );
-                    if let Err(err) = address_cache.set_address(*addr).await {
-                        log::error!("Failed to save newly updated API address: {}", err);
-                    }
-                } else {
-                    log::error!("API returned no API addresses");
-                }
-
+        match api_proxy.refresh_address(&address_cache).await {
+            Ok(addr) => {
+                log::debug!(
+                    "Fetched new API address {:?}. Fetching again in {} hours",
+                    addr,
+                    API_IP_CHECK_INTERVAL.as_secs() / (60 * 60)
+                );
                 next_delay = API_IP_CHECK_INTERVAL;
             }
             Err(err) => {
                 log::error!(
-                    "Failed to fetch new API addresses: {}. Retrying in {} seconds",
-                    err,
+                    "Failed to update API address: {}. Retrying in {} seconds",
+                    err.display_chain(),
                     API_IP_CHECK_ERROR_INTERVAL.as_secs()
                 );
 
@@ -0,0 +1,125 @@
+//! Measures and removes the files that the daemon caches on disk.
+
+use crate::{relay_list::RELAYS_FILENAME, version::DOWNLOAD_DIRNAME};
+use futures::future::BoxFuture;
+use mullvad_types::cache::{CacheKind, CacheUsage};
+use std::path::{Path, PathBuf};
+use tokio::{fs, io};
+
+/// Extension of logs that have been rotated, and so are no longer written to
+const ROTATED_LOG_EXTENSION: &str = ".old.log";
+
+/// Return the size of each kind of cached file. Files that cannot be read are not counted.
+pub async fn usage(cache_dir: &Path, log_dir: Option<&Path>) -> CacheUsage {
+    let logs = match log_dir {
+        Some(log_dir) => {
+            let mut size = 0;
+            for path in list_files(log_dir).await {
+                size += path_size(path).await;
+            }
+            size
+        }
+        None => 0,
+    };
+    CacheUsage {
+        relay_list: path_size(cache_dir.join(RELAYS_FILENAME)).await,
+        api_address: path_size(cache_dir.join(mullvad_api::API_IP_CACHE_FILENAME)).await,
+        installers: path_size(cache_dir.join(DOWNLOAD_DIRNAME)).await,
+        logs,
+    }
+}
+
+/// Remove the cached files of the given kind. Missing files are ignored.
+///
+/// Where in-app upgrades are enabled, installers must be removed through the version router
+/// instead, so that an installer is never removed during an upgrade.
+pub async fn clear(kind: CacheKind, cache_dir: &Path, log_dir: Option<&Path>) -> io::Result<()> {
+    match kind {
+        CacheKind::RelayList => remove_file(&cache_dir.join(RELAYS_FILENAME)).await,
+        CacheKind::ApiAddress => {
+            remove_file(&cache_dir.join(mullvad_api::API_IP_CACHE_FILENAME)).await
+        }
+        CacheKind::Installers => match fs::remove_dir_all(cache_dir.join(DOWNLOAD_DIRNAME)).await {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        },
+        CacheKind::Logs => {
+            let Some(log_dir) = log_dir else {
+                return Ok(());
+            };
+            for path in list_files(log_dir).await {
+                if is_rotated_log(&path) {
+                    remove_file(&path).await?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn is_rotated_log(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(ROTATED_LOG_EXTENSION))
+}
+
+async fn remove_file(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path).await {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+/// Return all files directly within `dir`
+async fn list_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = vec![];
+    let Ok(mut entries) = fs::read_dir(dir).await else {
+        return files;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if entry
+            .file_type()
+            .await
+            .is_ok_and(|file_type| file_type.is_file())
+        {
+            files.push(entry.path());
+        }
+    }
+    files
+}
+
+/// Return the size of a file, or the total size of all files within a directory
+fn path_size(path: PathBuf) -> BoxFuture<'static, u64> {
+    Box::pin(async move {
+        let Ok(metadata) = fs::symlink_metadata(&path).await else {
+            return 0;
+        };
+        if !metadata.is_dir() {
+            return metadata.len();
+        }
+        let Ok(mut entries) = fs::read_dir(&path).await else {
+            return 0;
+        };
+        let mut size = 0;
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            size += path_size(entry.path()).await;
+        }
+        size
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_rotated_log() {
+        assert!(is_rotated_log(Path::new(
+            "/var/log/mullvad-vpn/daemon.old.log"
+        )));
+        assert!(!is_rotated_log(Path::new(
+            "/var/log/mullvad-vpn/daemon.log"
+        )));
+        assert!(!is_rotated_log(Path::new("/var/log/mullvad-vpn/old.txt")));
+    }
+}
@@ -0,0 +1,266 @@
+//! Detects captive portals before connecting.
+//!
+//! The Mullvad connection checking service is requested over plain HTTP. It either answers with
+//! the connection info as JSON, or redirects to the same service over HTTPS. Captive portals
+//! intercept the request and respond with a redirect to a login page, or with the login page
+//! itself. While a portal is detected, the probe is repeated until it passes.
+
+use crate::{DaemonEventSender, InternalDaemonEvent, geoip::MULLVAD_CONNCHECK_HOST};
+use futures::future::{AbortHandle, abortable};
+use std::{io, time::Duration};
+use talpid_core::mpsc::Sender;
+use talpid_types::ErrorExt;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+const PROBE_PATH: &str = "/json";
+const PROBE_PORT: u16 = 80;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often to probe again while a captive portal is detected
+const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+/// Large enough for the headers and the connection info
+const MAX_RESPONSE_SIZE: usize = 4096;
+/// Field that is always included in the connection info
+const EXPECTED_FIELD: &str = "\"mullvad_exit_ip\"";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Failed to send captive portal probe")]
+    Io(#[source] io::Error),
+    #[error("Timed out waiting for captive portal probe")]
+    Timeout,
+    #[error("Malformed response to captive portal probe")]
+    MalformedResponse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptivePortalEvent {
+    /// The check that sent the event
+    check_id: usize,
+    status: CheckStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// A captive portal was detected. The connection is deferred until the check passes.
+    Detected,
+    /// There is no captive portal, or the check could not be performed. It is safe to connect.
+    Passed,
+}
+
+impl From<CaptivePortalEvent> for InternalDaemonEvent {
+    fn from(event: CaptivePortalEvent) -> Self {
+        InternalDaemonEvent::CaptivePortalEvent(event)
+    }
+}
+
+/// Runs at most one captive portal check at a time.
+pub struct CaptivePortalChecker {
+    event_tx: DaemonEventSender<CaptivePortalEvent>,
+    /// Unique ID for each check. Events from checks that have been replaced or cancelled are
+    /// ignored.
+    check_id: usize,
+    check: Option<AbortHandle>,
+}
+
+impl CaptivePortalChecker {
+    pub fn new(event_tx: DaemonEventSender<CaptivePortalEvent>) -> Self {
+        Self {
+            event_tx,
+            check_id: 0,
+            check: None,
+        }
+    }
+
+    /// Start a new check, replacing any running check.
+    pub fn start(&mut self) {
+        self.cancel();
+        self.check_id = self.check_id.wrapping_add(1);
+        self.check = Some(spawn_check(self.check_id, self.event_tx.clone()));
+    }
+
+    /// Cancel the running check. Returns whether a check was running.
+    pub fn cancel(&mut self) -> bool {
+        let Some(check) = self.check.take() else {
+            return false;
+        };
+        check.abort();
+        true
+    }
+
+    /// Return the status reported by `event`, or `None` if the event does not belong to the
+    /// running check. The check is no longer running once it has passed.
+    pub fn handle_event(&mut self, event: CaptivePortalEvent) -> Option<CheckStatus> {
+        if self.check.is_none() || event.check_id != self.check_id {
+            return None;
+        }
+        if event.status == CheckStatus::Passed {
+            self.check = None;
+        }
+        Some(event.status)
+    }
+}
+
+/// Probe for a captive portal until there is none. [CheckStatus::Detected] is sent the first time
+/// a portal is detected, and [CheckStatus::Passed] once the check passes.
+///
+/// If the probe fails, e.g. because the firewall blocks it, the check is considered passed, so
+/// that connecting is never prevented by a broken probe.
+fn spawn_check(check_id: usize, event_tx: DaemonEventSender<CaptivePortalEvent>) -> AbortHandle {
+    let send = move |status| event_tx.send(CaptivePortalEvent { check_id, status });
+    let (check, abort_handle) = abortable(async move {
+        let mut detected = false;
+        loop {
+            match probe().await {
+                Ok(false) => break,
+                Ok(true) => {
+                    if !detected {
+                        log::info!("Captive portal detected. Deferring connection");
+                        detected = true;
+                        let _ = send(CheckStatus::Detected);
+                    }
+                }
+                Err(error) => {
+                    log::warn!(
+                        "{}",
+                        error.display_chain_with_msg("Unable to check for captive portal")
+                    );
+                    break;
+                }
+            }
+            tokio::time::sleep(RETRY_INTERVAL).await;
+        }
+        let _ = send(CheckStatus::Passed);
+    });
+    tokio::spawn(check);
+    abort_handle
+}
+
+/// Return whether a captive portal intercepted the probe request.
+async fn probe() -> Result<bool, Error> {
+    let response = tokio::time::timeout(PROBE_TIMEOUT, send_probe())
+        .await
+        .map_err(|_| Error::Timeout)??;
+    is_captive_portal(&response, &MULLVAD_CONNCHECK_HOST)
+}
+
+async fn send_probe() -> Result<Vec<u8>, Error> {
+    let host = MULLVAD_CONNCHECK_HOST.as_str();
+    let mut stream = TcpStream::connect((host, PROBE_PORT))
+        .await
+        .map_err(Error::Io)?;
+    let request = format!("GET {PROBE_PATH} HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\r\n");
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(Error::Io)?;
+
+    let mut response = Vec::new();
+    stream
+        .take(MAX_RESPONSE_SIZE as u64)
+        .read_to_end(&mut response)
+        .await
+        .map_err(Error::Io)?;
+    Ok(response)
+}
+
+/// Return whether the response to a probe sent to `host` was intercepted. Any response other
+/// than the connection info or a redirect to `host` over HTTPS is considered intercepted.
+fn is_captive_portal(response: &[u8], host: &str) -> Result<bool, Error> {
+    let response = String::from_utf8_lossy(response);
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&*response, ""));
+    let mut lines = head.lines();
+
+    let mut status_line = lines
+        .next()
+        .ok_or(Error::MalformedResponse)?
+        .split_whitespace();
+    let (Some(version), Some(status)) = (status_line.next(), status_line.next()) else {
+        return Err(Error::MalformedResponse);
+    };
+    if !version.starts_with("HTTP/") {
+        return Err(Error::MalformedResponse);
+    }
+    let status: u16 = status.parse().map_err(|_| Error::MalformedResponse)?;
+
+    let passed = match status {
+        200 => body.contains(EXPECTED_FIELD),
+        301 | 302 | 307 | 308 => {
+            let expected_location = format!("https://{host}/");
+            lines
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("location"))
+                .is_some_and(|(_, location)| location.trim().starts_with(&expected_location))
+        }
+        _ => false,
+    };
+    Ok(!passed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::channel::mpsc;
+    use std::sync::Arc;
+
+    const HOST: &str = "am.i.mullvad.net";
+
+    #[test]
+    fn test_is_captive_portal() {
+        let connection_info = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"ip\":\"1.2.3.4\",\"mullvad_exit_ip\":false}";
+        assert!(!is_captive_portal(connection_info, HOST).unwrap());
+        let https_redirect =
+            b"HTTP/1.1 301 Moved Permanently\r\nlocation: https://am.i.mullvad.net/json\r\n\r\n";
+        assert!(!is_captive_portal(https_redirect, HOST).unwrap());
+
+        let portal_redirect = b"HTTP/1.1 302 Found\r\nLocation: http://portal/login\r\n\r\n";
+        assert!(is_captive_portal(portal_redirect, HOST).unwrap());
+        let lookalike_redirect =
+            b"HTTP/1.1 302 Found\r\nLocation: https://am.i.mullvad.net.portal/\r\n\r\n";
+        assert!(is_captive_portal(lookalike_redirect, HOST).unwrap());
+        let login_page = b"HTTP/1.0 200 OK\r\n\r\n<html>Log in</html>";
+        assert!(is_captive_portal(login_page, HOST).unwrap());
+        let auth_required = b"HTTP/1.1 511 Network Authentication Required\r\n\r\n";
+        assert!(is_captive_portal(auth_required, HOST).unwrap());
+
+        assert!(is_captive_portal(b"", HOST).is_err());
+        assert!(is_captive_portal(b"garbage\r\n", HOST).is_err());
+        assert!(is_captive_portal(b"HTTP/1.1 abc\r\n", HOST).is_err());
+    }
+
+    /// Events from checks that are no longer running should be ignored
+    #[test]
+    fn test_stale_events() {
+        let (tx, _rx) = mpsc::unbounded();
+        let tx = Arc::new(tx);
+        let mut checker = CaptivePortalChecker::new(
+            DaemonEventSender::new(Arc::downgrade(&tx)).to_specialized_sender(),
+        );
+        let event = |check_id, status| CaptivePortalEvent { check_id, status };
+
+        // Pretend that the second check is running
+        let (abort_handle, _) = AbortHandle::new_pair();
+        checker.check_id = 2;
+        checker.check = Some(abort_handle);
+
+        assert_eq!(checker.handle_event(event(1, CheckStatus::Passed)), None);
+        assert_eq!(
+            checker.handle_event(event(2, CheckStatus::Detected)),
+            Some(CheckStatus::Detected)
+        );
+        assert_eq!(
+            checker.handle_event(event(2, CheckStatus::Passed)),
+            Some(CheckStatus::Passed)
+        );
+        // The check has passed, so it is no longer running
+        assert_eq!(checker.handle_event(event(2, CheckStatus::Passed)), None);
+
+        let (abort_handle, _) = AbortHandle::new_pair();
+        checker.check = Some(abort_handle);
+        assert!(checker.cancel());
+        assert!(!checker.cancel());
+        assert_eq!(checker.handle_event(event(2, CheckStatus::Passed)), None);
+    }
+}
@@ -1,6 +1,6 @@
 #![cfg(not(target_os = "android"))]
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tokio::{fs, io};
 
 #[derive(thiserror::Error, Debug)]
@@ -19,11 +19,9 @@ pub enum Error {
     #[error("Failed to get file type info")]
     FileType(#[source] io::Error),
 
-    #[cfg(target_os = "windows")]
     #[error("Failed to get dir entry")]
     FileEntry(#[source] io::Error),
 
-    #[cfg(target_os = "windows")]
     #[error("Failed to read dir entries")]
     ReadDir(#[source] io::Error),
 }
@@ -43,6 +41,32 @@ async fn clear_cache_directory() -> Result<(), Error> {
     clear_directory(&cache_dir).await
 }
 
+/// Return the entries that [clear_directories] would remove from the log directory
+pub async fn log_directory_entries() -> Result<Vec<PathBuf>, Error> {
+    let log_dir = mullvad_paths::get_log_dir().map_err(Error::Path)?;
+    list_directory(&log_dir).await
+}
+
+/// Return the entries that [clear_directories] would remove from the cache directory
+pub async fn cache_directory_entries() -> Result<Vec<PathBuf>, Error> {
+    let cache_dir = mullvad_paths::cache_dir().map_err(Error::Path)?;
+    list_directory(&cache_dir).await
+}
+
+async fn list_directory(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut dir = match fs::read_dir(path).await {
+        Ok(dir) => dir,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(Error::ReadDir(error)),
+    };
+    let mut entries = vec![];
+    while let Some(entry) = dir.next_entry().await.map_err(Error::FileEntry)? {
+        entries.push(entry.path());
+    }
+    entries.sort();
+    Ok(entries)
+}
+
 async fn clear_directory(path: &Path) -> Result<(), Error> {
     #[cfg(not(target_os = "windows"))]
     {
@@ -0,0 +1,166 @@
+//! Connects and disconnects according to the weekly schedule in the settings.
+//!
+//! The schedule is only enforced at its boundaries, i.e. when the current local time enters or
+//! leaves one of the ranges. Starting the daemon or changing the schedule does not change the
+//! target state, and a manual connect or disconnect lasts until the next boundary.
+
+use crate::{DaemonEventSender, InternalDaemonEvent};
+use chrono::{Local, Timelike};
+use mullvad_types::{
+    schedule::{ConnectSchedule, WeekTime},
+    states::{DisconnectReason, TargetState},
+};
+use std::time::Duration;
+use talpid_core::mpsc::Sender;
+use tokio::sync::watch;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleEvent {
+    /// A scheduled range has started
+    Connect,
+    /// A scheduled range has ended
+    Disconnect,
+}
+
+impl ScheduleEvent {
+    /// Return the target state that the daemon should enter.
+    pub const fn target_state(self) -> TargetState {
+        match self {
+            ScheduleEvent::Connect => TargetState::Secured,
+            ScheduleEvent::Disconnect => TargetState::Unsecured,
+        }
+    }
+
+    /// Return the reason to report if the event causes the daemon to disconnect.
+    pub const fn disconnect_reason(self) -> Option<DisconnectReason> {
+        match self {
+            ScheduleEvent::Connect => None,
+            ScheduleEvent::Disconnect => Some(DisconnectReason::Schedule),
+        }
+    }
+}
+
+impl From<ScheduleEvent> for InternalDaemonEvent {
+    fn from(event: ScheduleEvent) -> Self {
+        InternalDaemonEvent::ConnectScheduleEvent(event)
+    }
+}
+
+/// Handle used to update the schedule of a running [spawn]ed task.
+#[derive(Clone)]
+pub struct ScheduleHandle {
+    schedule_tx: watch::Sender<ConnectSchedule>,
+}
+
+impl ScheduleHandle {
+    pub fn set_schedule(&self, schedule: &ConnectSchedule) {
+        self.schedule_tx.send_if_modified(|current| {
+            if current != schedule {
+                *current = schedule.clone();
+                true
+            } else {
+                false
+            }
+        });
+    }
+}
+
+/// Spawn a task that sends a [ScheduleEvent] whenever a boundary of the schedule is crossed.
+/// The task stops once the daemon stops receiving events.
+pub fn spawn(
+    schedule: ConnectSchedule,
+    event_tx: DaemonEventSender<ScheduleEvent>,
+) -> ScheduleHandle {
+    let (schedule_tx, schedule_rx) = watch::channel(schedule);
+    tokio::spawn(run(schedule_rx, event_tx));
+    ScheduleHandle { schedule_tx }
+}
+
+async fn run(
+    mut schedule_rx: watch::Receiver<ConnectSchedule>,
+    event_tx: DaemonEventSender<ScheduleEvent>,
+) {
+    let mut was_active = is_active(&schedule_rx.borrow_and_update());
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(until_next_minute()) => (),
+            changed = schedule_rx.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+                // A new schedule only takes effect at its next boundary
+                was_active = is_active(&schedule_rx.borrow_and_update());
+                continue;
+            }
+        }
+
+        let now_active = is_active(&schedule_rx.borrow_and_update());
+        if let Some(event) = boundary_event(was_active, now_active) {
+            match event {
+                ScheduleEvent::Connect => log::info!("Connecting according to schedule"),
+                ScheduleEvent::Disconnect => log::info!("Disconnecting according to schedule"),
+            }
+            if event_tx.send(event).is_err() {
+                return;
+            }
+        }
+        was_active = now_active;
+    }
+}
+
+/// Return whether the current local time is in one of the ranges of `schedule`, or `None` if the
+/// schedule is empty.
+fn is_active(schedule: &ConnectSchedule) -> Option<bool> {
+    (!schedule.is_empty()).then(|| schedule.is_active(WeekTime::from_datetime(&Local::now())))
+}
+
+/// Return the event to send when the schedule goes from `was_active` to `is_active`, if a boundary
+/// of the schedule was crossed.
+fn boundary_event(was_active: Option<bool>, is_active: Option<bool>) -> Option<ScheduleEvent> {
+    match (was_active, is_active) {
+        (Some(false), Some(true)) => Some(ScheduleEvent::Connect),
+        (Some(true), Some(false)) => Some(ScheduleEvent::Disconnect),
+        _ => None,
+    }
+}
+
+/// The schedule has a granularity of minutes, so it only needs to be checked when a new minute
+/// starts.
+fn until_next_minute() -> Duration {
+    let now = Local::now();
+    let elapsed = Duration::new(u64::from(now.second()), now.nanosecond() % 1_000_000_000);
+    Duration::from_secs(60).saturating_sub(elapsed)
+}
+
+#[test]
+fn test_schedule_disconnect_reason() {
+    assert_eq!(
+        ScheduleEvent::Disconnect.target_state(),
+        TargetState::Unsecured
+    );
+    assert_eq!(
+        ScheduleEvent::Disconnect.disconnect_reason(),
+        Some(DisconnectReason::Schedule)
+    );
+
+    assert_eq!(ScheduleEvent::Connect.target_state(), TargetState::Secured);
+    assert_eq!(ScheduleEvent::Connect.disconnect_reason(), None);
+}
+
+/// Only crossing a boundary changes the target state. Starting with, or switching to, an empty
+/// schedule does not.
+#[test]
+fn test_boundary_event() {
+    assert_eq!(
+        boundary_event(Some(false), Some(true)),
+        Some(ScheduleEvent::Connect)
+    );
+    assert_eq!(
+        boundary_event(Some(true), Some(false)),
+        Some(ScheduleEvent::Disconnect)
+    );
+    assert_eq!(boundary_event(Some(true), Some(true)), None);
+    assert_eq!(boundary_event(Some(false), Some(false)), None);
+    assert_eq!(boundary_event(None, Some(true)), None);
+    assert_eq!(boundary_event(Some(true), None), None);
+}
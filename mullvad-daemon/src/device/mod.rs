@@ -8,7 +8,7 @@ use mullvad_api::rest;
 #[cfg(target_os = "android")]
 use mullvad_types::account::{PlayPurchase, PlayPurchasePaymentToken};
 use mullvad_types::{
-    account::{AccountNumber, VoucherSubmission},
+    account::{AccountNumber, LoginBackoff, VoucherSubmission},
     device::{
         AccountAndDevice, Device, DeviceEvent, DeviceEventCause, DeviceId, DeviceName, DeviceState,
     },
@@ -17,7 +17,7 @@ use mullvad_types::{
 
 use std::{
     future::Future,
-    path::Path,
+    path::{Path, PathBuf},
     sync::{
         Arc,
         atomic::{AtomicBool, Ordering},
@@ -38,6 +38,9 @@ pub(crate) use service::{AccountService, DeviceService};
 /// File that used to store account and device data.
 const DEVICE_CACHE_FILENAME: &str = "device.json";
 
+/// File that stores the devices of accounts which have been switched away from.
+const SAVED_DEVICES_FILENAME: &str = "saved-devices.json";
+
 /// How long to keep the known status for [AccountManagerHandle::validate_device].
 const VALIDITY_CACHE_TIMEOUT: Duration = Duration::from_secs(10);
 
@@ -48,6 +51,14 @@ const LOGOUT_TIMEOUT: Duration = Duration::from_secs(2);
 /// a WireGuard tunnel.
 const WG_DEVICE_CHECK_THRESHOLD: usize = 3;
 
+/// Number of logins in a row that may fail due to an invalid account number before further logins
+/// are delayed.
+const LOGIN_FAILURES_BEFORE_BACKOFF: u32 = 3;
+/// Delay after the first failure past [LOGIN_FAILURES_BEFORE_BACKOFF]. It doubles with every
+/// further failure.
+const LOGIN_BACKOFF_INITIAL_DELAY: Duration = Duration::from_secs(5);
+const LOGIN_BACKOFF_MAX_DELAY: Duration = Duration::from_secs(10 * 60);
+
 #[derive(thiserror::Error, Debug, Clone)]
 pub enum Error {
     #[error("The account already has a maximum number of devices")]
@@ -74,6 +85,8 @@ pub enum Error {
     AccountChange,
     #[error("The account manager is down")]
     AccountManagerDown,
+    #[error("Too many failed logins. The next login is allowed at {0}")]
+    LoginBackoff(DateTime<Utc>),
 }
 
 macro_rules! impl_into_arc_err {
@@ -291,23 +304,77 @@ impl Error {
     }
 }
 
+/// Delays logins after logins in a row have failed because the account number was invalid.
+#[derive(Debug, Default)]
+struct LoginBackoffTracker {
+    consecutive_failures: u32,
+    next_attempt: Option<DateTime<Utc>>,
+}
+
+impl LoginBackoffTracker {
+    /// Return an error if a login is not allowed yet.
+    fn check(&self) -> Result<(), Error> {
+        match self.next_attempt {
+            Some(next_attempt) if next_attempt > Utc::now() => {
+                Err(Error::LoginBackoff(next_attempt))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn record_failure(&mut self) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.next_attempt = Self::delay(self.consecutive_failures).map(|delay| {
+            Utc::now() + chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::MAX)
+        });
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    fn status(&self) -> LoginBackoff {
+        LoginBackoff {
+            consecutive_failures: self.consecutive_failures,
+            next_attempt: self
+                .next_attempt
+                .filter(|next_attempt| *next_attempt > Utc::now()),
+        }
+    }
+
+    /// Return how long to wait after `failures` failed logins in a row.
+    fn delay(failures: u32) -> Option<Duration> {
+        let exponent = failures.checked_sub(LOGIN_FAILURES_BEFORE_BACKOFF)?;
+        let delay = LOGIN_BACKOFF_INITIAL_DELAY
+            .checked_mul(2u32.saturating_pow(exponent))
+            .unwrap_or(LOGIN_BACKOFF_MAX_DELAY);
+        Some(delay.min(LOGIN_BACKOFF_MAX_DELAY))
+    }
+}
+
 type ResponseTx<T> = oneshot::Sender<Result<T, Error>>;
 
 enum AccountManagerCommand {
     Login(AccountNumber, ResponseTx<()>),
+    Switch(AccountNumber, ResponseTx<()>),
     Logout(ResponseTx<()>),
+    ClearSavedDevices(ResponseTx<()>),
+    GetSavedAccounts(ResponseTx<Vec<AccountNumber>>),
     SetData(PrivateAccountAndDevice, ResponseTx<()>),
     GetData(ResponseTx<PrivateDeviceState>),
     GetDataAfterLogin(ResponseTx<PrivateDeviceState>),
     RotateKey(ResponseTx<()>),
     SetRotationInterval(RotationInterval, ResponseTx<()>),
     ValidateDevice(ResponseTx<()>),
+    Reauthenticate(ResponseTx<()>),
     SubmitVoucher(String, ResponseTx<VoucherSubmission>),
     #[cfg(target_os = "android")]
     InitPlayPurchase(ResponseTx<PlayPurchasePaymentToken>),
     #[cfg(target_os = "android")]
     VerifyPlayPurchase(ResponseTx<()>, PlayPurchase),
     CheckExpiry(ResponseTx<DateTime<Utc>>),
+    GetLoginBackoff(ResponseTx<LoginBackoff>),
+    ClearLoginBackoff(ResponseTx<()>),
     Shutdown(oneshot::Sender<()>),
 }
 
@@ -324,10 +391,29 @@ impl AccountManagerHandle {
             .await
     }
 
+    /// Log in with a different account without logging out first. The device of the current
+    /// account is kept, and is reused if the account is switched back to later.
+    pub async fn switch_account(&self, number: AccountNumber) -> Result<(), Error> {
+        self.send_command(|tx| AccountManagerCommand::Switch(number, tx))
+            .await
+    }
+
     pub async fn logout(&self) -> Result<(), Error> {
         self.send_command(AccountManagerCommand::Logout).await
     }
 
+    /// Return the accounts whose devices are kept by [`AccountManagerHandle::switch_account`].
+    pub async fn saved_accounts(&self) -> Result<Vec<AccountNumber>, Error> {
+        self.send_command(AccountManagerCommand::GetSavedAccounts)
+            .await
+    }
+
+    /// Remove all devices kept by [`AccountManagerHandle::switch_account`].
+    pub async fn clear_saved_devices(&self) -> Result<(), Error> {
+        self.send_command(AccountManagerCommand::ClearSavedDevices)
+            .await
+    }
+
     pub async fn set(&self, data: PrivateAccountAndDevice) -> Result<(), Error> {
         self.send_command(|tx| AccountManagerCommand::SetData(data, tx))
             .await
@@ -356,6 +442,13 @@ impl AccountManagerHandle {
             .await
     }
 
+    /// Request a new access token for the current account and validate the current device with
+    /// it. The device and its WireGuard key are kept.
+    pub async fn reauthenticate(&self) -> Result<(), Error> {
+        self.send_command(AccountManagerCommand::Reauthenticate)
+            .await
+    }
+
     pub async fn submit_voucher(&self, voucher: String) -> Result<VoucherSubmission, Error> {
         self.send_command(move |tx| AccountManagerCommand::SubmitVoucher(voucher, tx))
             .await
@@ -365,6 +458,18 @@ impl AccountManagerHandle {
         self.send_command(AccountManagerCommand::CheckExpiry).await
     }
 
+    /// Return whether logins are currently delayed due to failed logins.
+    pub async fn login_backoff(&self) -> Result<LoginBackoff, Error> {
+        self.send_command(AccountManagerCommand::GetLoginBackoff)
+            .await
+    }
+
+    /// Forget all failed logins, so that the next login is allowed immediately.
+    pub async fn clear_login_backoff(&self) -> Result<(), Error> {
+        self.send_command(AccountManagerCommand::ClearLoginBackoff)
+            .await
+    }
+
     #[cfg(target_os = "android")]
     pub async fn init_play_purchase(&self) -> Result<PlayPurchasePaymentToken, Error> {
         self.send_command(AccountManagerCommand::InitPlayPurchase)
@@ -399,6 +504,7 @@ impl AccountManagerHandle {
 
 pub(crate) struct AccountManager {
     cacher: DeviceCacher,
+    saved_devices: SavedDevices,
     account_service: AccountService,
     device_service: DeviceService,
     data: PrivateDeviceState,
@@ -409,6 +515,7 @@ pub(crate) struct AccountManager {
     expiry_requests: Vec<ResponseTx<DateTime<Utc>>>,
     rotation_requests: Vec<ResponseTx<()>>,
     data_requests: Vec<ResponseTx<PrivateDeviceState>>,
+    login_backoff: LoginBackoffTracker,
 }
 
 impl AccountManager {
@@ -421,6 +528,7 @@ impl AccountManager {
         listener_tx: impl Sender<AccountEvent> + Send + 'static,
     ) -> Result<(AccountManagerHandle, PrivateDeviceState), Error> {
         let (cacher, data) = DeviceCacher::new(settings_dir).await?;
+        let saved_devices = SavedDevices::load(settings_dir).await;
         let number = data.device().map(|state| state.account_number.clone());
         let api_availability = rest_handle.availability.clone();
         let account_service =
@@ -431,6 +539,7 @@ impl AccountManager {
         let device_service = DeviceService::new(rest_handle, api_availability);
         let manager = AccountManager {
             cacher,
+            saved_devices,
             account_service: account_service.clone(),
             device_service: device_service.clone(),
             data: data.clone(),
@@ -441,6 +550,7 @@ impl AccountManager {
             expiry_requests: vec![],
             rotation_requests: vec![],
             data_requests: vec![],
+            login_backoff: LoginBackoffTracker::default(),
         };
 
         tokio::spawn(manager.run(cmd_rx));
@@ -475,14 +585,28 @@ impl AccountManager {
                             break;
                         }
                         Some(AccountManagerCommand::Login(number, tx)) => {
+                            if let Err(error) = self.login_backoff.check() {
+                                let _ = tx.send(Err(error));
+                                continue;
+                            }
                             let job = self.device_service
                                 .generate_for_account(number);
                             current_api_call.set_login(Box::pin(job), tx);
                         }
+                        Some(AccountManagerCommand::Switch(number, tx)) => {
+                            current_api_call.clear();
+                            self.switch_account(number, tx, &mut current_api_call).await;
+                        }
                         Some(AccountManagerCommand::Logout(tx)) => {
                             current_api_call.clear();
                             self.logout(tx).await;
                         }
+                        Some(AccountManagerCommand::ClearSavedDevices(tx)) => {
+                            self.clear_saved_devices(tx).await;
+                        }
+                        Some(AccountManagerCommand::GetSavedAccounts(tx)) => {
+                            let _ = tx.send(Ok(self.saved_devices.account_numbers()));
+                        }
                         Some(AccountManagerCommand::SetData(data, tx)) => {
                             let _ = tx.send(self.set(PrivateDeviceEvent::Login(data)).await);
                         }
@@ -525,12 +649,22 @@ impl AccountManager {
                         Some(AccountManagerCommand::ValidateDevice(tx)) => {
                             self.handle_validation_request(tx, &mut current_api_call);
                         },
+                        Some(AccountManagerCommand::Reauthenticate(tx)) => {
+                            self.handle_reauthentication_request(tx, &mut current_api_call);
+                        },
                         Some(AccountManagerCommand::SubmitVoucher(voucher, tx)) => {
                             self.handle_voucher_submission(tx, voucher, &mut current_api_call);
                         },
                         Some(AccountManagerCommand::CheckExpiry(tx)) => {
                             self.handle_expiry_request(tx, &mut current_api_call);
                         },
+                        Some(AccountManagerCommand::GetLoginBackoff(tx)) => {
+                            let _ = tx.send(Ok(self.login_backoff.status()));
+                        },
+                        Some(AccountManagerCommand::ClearLoginBackoff(tx)) => {
+                            self.login_backoff.reset();
+                            let _ = tx.send(Ok(()));
+                        },
                         #[cfg(target_os = "android")]
                         Some(AccountManagerCommand::InitPlayPurchase(tx)) => {
                             self.handle_init_play_purchase(tx, &mut current_api_call);
@@ -583,6 +717,28 @@ impl AccountManager {
         }
     }
 
+    /// Drop the access token of the current account and validate the device using a new one. If
+    /// the account or device is no longer valid, the device is revoked as for any validation.
+    fn handle_reauthentication_request(
+        &mut self,
+        tx: ResponseTx<()>,
+        current_api_call: &mut api::CurrentApiCall,
+    ) {
+        if current_api_call.is_logging_in() {
+            let _ = tx.send(Err(Error::AccountChange));
+            return;
+        }
+        let Some(config) = self.data.device() else {
+            let _ = tx.send(Err(Error::NoDevice));
+            return;
+        };
+        log::debug!("Reauthenticating the current device");
+        self.device_service
+            .invalidate_access_token(&config.account_number);
+        self.last_validation = None;
+        self.handle_validation_request(tx, current_api_call);
+    }
+
     fn handle_voucher_submission(
         &mut self,
         tx: ResponseTx<VoucherSubmission>,
@@ -733,6 +889,11 @@ impl AccountManager {
         device_response: Result<PrivateAccountAndDevice, Error>,
         tx: ResponseTx<()>,
     ) {
+        match &device_response {
+            Ok(_) => self.login_backoff.reset(),
+            Err(Error::InvalidAccount) => self.login_backoff.record_failure(),
+            Err(_) => (),
+        }
         let _ =
             tx.send(async { self.set(PrivateDeviceEvent::Login(device_response?)).await }.await);
         let data = self.data.clone();
@@ -979,6 +1140,72 @@ impl AccountManager {
         });
     }
 
+    async fn switch_account(
+        &mut self,
+        number: AccountNumber,
+        tx: ResponseTx<()>,
+        current_api_call: &mut api::CurrentApiCall,
+    ) {
+        if self
+            .data
+            .device()
+            .is_some_and(|config| config.account_number == number)
+        {
+            let _ = tx.send(Ok(()));
+            return;
+        }
+
+        // The current device stays set until it is replaced by the new one below, so no logout
+        // event is emitted. The daemon therefore never disconnects during the switch, but
+        // reconnects with the new device, which keeps the firewall up throughout.
+
+        // Keep the current device so that it is not removed once the new one is set
+        if let Some(config) = self.data.device().cloned()
+            && let Err(err) = self.saved_devices.insert(config).await
+        {
+            let _ = tx.send(Err(err));
+            return;
+        }
+
+        match self.saved_devices.take(&number).await {
+            Ok(Some(saved_config)) => {
+                log::debug!("Reusing saved device for account");
+                let _ = tx.send(self.set(PrivateDeviceEvent::Login(saved_config)).await);
+                let data = self.data.clone();
+                Self::drain_requests(&mut self.data_requests, || Ok(data.clone()));
+
+                // The saved device may have been removed since it was last used
+                let (validation_tx, _) = oneshot::channel();
+                self.handle_validation_request(validation_tx, current_api_call);
+            }
+            Ok(None) => {
+                if let Err(error) = self.login_backoff.check() {
+                    let _ = tx.send(Err(error));
+                    return;
+                }
+                let job = self.device_service.generate_for_account(number);
+                current_api_call.set_login(Box::pin(job), tx);
+            }
+            Err(err) => {
+                let _ = tx.send(Err(err));
+            }
+        }
+    }
+
+    async fn clear_saved_devices(&mut self, tx: ResponseTx<()>) {
+        match self.saved_devices.clear().await {
+            Ok(saved_configs) => {
+                for config in saved_configs {
+                    tokio::spawn(self.logout_api_call(config));
+                }
+                let _ = tx.send(Ok(()));
+            }
+            Err(err) => {
+                let _ = tx.send(Err(err));
+            }
+        }
+    }
+
     async fn logout(&mut self, tx: ResponseTx<()>) {
         Self::drain_requests(&mut self.data_requests, || Err(Error::AccountChange));
         if self.data.logged_out() {
@@ -991,6 +1218,14 @@ impl AccountManager {
         }
 
         let old_config = self.data.logout();
+        if let Some(old_config) = &old_config
+            && let Err(error) = self.saved_devices.remove(old_config).await
+        {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to remove device from saved devices")
+            );
+        }
 
         self.listeners.retain(|listener| {
             listener
@@ -1044,6 +1279,7 @@ impl AccountManager {
 
         if let Some(old_config) = self.data.logout()
             && device_state.device().map(|d| &d.device.id) != Some(&old_config.device.id)
+            && !self.saved_devices.contains(&old_config)
         {
             tokio::spawn(self.logout_api_call(old_config));
         }
@@ -1251,6 +1487,103 @@ impl DeviceCacher {
     }
 }
 
+/// Devices of accounts other than the current one, which are kept so that they can be reused
+/// when switching accounts.
+struct SavedDevices {
+    path: PathBuf,
+    devices: Vec<PrivateAccountAndDevice>,
+}
+
+impl SavedDevices {
+    async fn load(settings_dir: &Path) -> Self {
+        let path = settings_dir.join(SAVED_DEVICES_FILENAME);
+        let devices = match fs::read(&path).await {
+            Ok(data) => serde_json::from_slice(&data).unwrap_or_else(|error| {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Wiping saved devices due to an error")
+                );
+                vec![]
+            }),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => vec![],
+            Err(error) => {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to read saved devices")
+                );
+                vec![]
+            }
+        };
+        SavedDevices { path, devices }
+    }
+
+    fn account_numbers(&self) -> Vec<AccountNumber> {
+        self.devices
+            .iter()
+            .map(|saved| saved.account_number.clone())
+            .collect()
+    }
+
+    fn contains(&self, config: &PrivateAccountAndDevice) -> bool {
+        self.devices
+            .iter()
+            .any(|saved| saved.device.id == config.device.id)
+    }
+
+    /// Save `config`, replacing any saved device for the same account.
+    async fn insert(&mut self, config: PrivateAccountAndDevice) -> Result<(), Error> {
+        self.devices
+            .retain(|saved| saved.account_number != config.account_number);
+        self.devices.push(config);
+        self.save().await
+    }
+
+    async fn remove(&mut self, config: &PrivateAccountAndDevice) -> Result<(), Error> {
+        if !self.contains(config) {
+            return Ok(());
+        }
+        self.devices
+            .retain(|saved| saved.device.id != config.device.id);
+        self.save().await
+    }
+
+    /// Remove and return the saved device for `account_number`, if there is one.
+    async fn take(
+        &mut self,
+        account_number: &AccountNumber,
+    ) -> Result<Option<PrivateAccountAndDevice>, Error> {
+        let Some(index) = self
+            .devices
+            .iter()
+            .position(|saved| &saved.account_number == account_number)
+        else {
+            return Ok(None);
+        };
+        let config = self.devices.remove(index);
+        self.save().await?;
+        Ok(Some(config))
+    }
+
+    async fn clear(&mut self) -> Result<Vec<PrivateAccountAndDevice>, Error> {
+        let devices = std::mem::take(&mut self.devices);
+        self.save().await?;
+        Ok(devices)
+    }
+
+    async fn save(&self) -> Result<(), Error> {
+        let data = serde_json::to_vec_pretty(&self.devices)?;
+        let mut file = fs::OpenOptions::from(DeviceCacher::file_options())
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(&data).await?;
+        file.sync_data().await?;
+        Ok(())
+    }
+}
+
 /// Checks if the current device is valid if a WireGuard tunnel cannot be set up
 /// after multiple attempts.
 pub(crate) struct TunnelStateChangeHandler {
@@ -1382,7 +1715,11 @@ mod test {
     };
     use talpid_types::tunnel::TunnelStateTransition;
 
-    use super::{Error, TunnelStateChangeHandler, WG_DEVICE_CHECK_THRESHOLD};
+    use super::{
+        Error, LOGIN_BACKOFF_MAX_DELAY, LOGIN_FAILURES_BEFORE_BACKOFF, LoginBackoffTracker,
+        PrivateAccountAndDevice, PrivateDevice, SavedDevices, TunnelStateChangeHandler,
+        WG_DEVICE_CHECK_THRESHOLD,
+    };
 
     const TIMEOUT_ERROR: Error = Error::OtherRestError(mullvad_api::rest::Error::TimeoutError);
 
@@ -1397,6 +1734,100 @@ mod test {
         );
     }
 
+    /// Logins should only be delayed once enough logins in a row have failed
+    #[test]
+    fn test_login_backoff() {
+        let mut backoff = LoginBackoffTracker::default();
+        for _ in 0..LOGIN_FAILURES_BEFORE_BACKOFF - 1 {
+            backoff.record_failure();
+        }
+        assert!(backoff.check().is_ok());
+        assert_eq!(backoff.status().next_attempt, None);
+
+        backoff.record_failure();
+        assert!(matches!(backoff.check(), Err(Error::LoginBackoff(_))));
+        assert_eq!(
+            backoff.status().consecutive_failures,
+            LOGIN_FAILURES_BEFORE_BACKOFF
+        );
+        assert!(backoff.status().next_attempt.is_some());
+
+        assert!(
+            LoginBackoffTracker::delay(LOGIN_FAILURES_BEFORE_BACKOFF + 1)
+                > LoginBackoffTracker::delay(LOGIN_FAILURES_BEFORE_BACKOFF)
+        );
+        assert_eq!(
+            LoginBackoffTracker::delay(u32::MAX),
+            Some(LOGIN_BACKOFF_MAX_DELAY)
+        );
+
+        backoff.reset();
+        assert!(backoff.check().is_ok());
+        assert_eq!(backoff.status().consecutive_failures, 0);
+    }
+
+    fn saved_device(account_number: &str, device_id: &str) -> PrivateAccountAndDevice {
+        PrivateAccountAndDevice {
+            account_number: account_number.to_owned(),
+            device: PrivateDevice {
+                id: device_id.to_owned(),
+                name: "happy seagull".to_owned(),
+                wg_data: mullvad_types::wireguard::WireguardData {
+                    private_key: talpid_types::net::wireguard::PrivateKey::new_from_random(),
+                    addresses: mullvad_types::wireguard::AssociatedAddresses {
+                        ipv4_address: "10.64.0.1/32".parse().unwrap(),
+                        ipv6_address: "fc00:bbbb:bbbb:bb01::1/128".parse().unwrap(),
+                    },
+                    created: chrono::Utc::now(),
+                },
+                hijack_dns: false,
+                created: chrono::Utc::now(),
+            },
+        }
+    }
+
+    /// Saved devices should be kept per account, and persist across restarts
+    #[tokio::test]
+    async fn test_saved_devices() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        let first = saved_device("1111111111111111", "first");
+        let second = saved_device("2222222222222222", "second");
+        let replacement = saved_device("1111111111111111", "replacement");
+
+        let mut saved = SavedDevices::load(dir).await;
+        assert!(saved.account_numbers().is_empty());
+        saved.insert(first.clone()).await.unwrap();
+        saved.insert(second.clone()).await.unwrap();
+        // A device for an account that already has one replaces it
+        saved.insert(replacement.clone()).await.unwrap();
+        assert!(!saved.contains(&first));
+        assert!(saved.contains(&replacement));
+
+        let mut saved = SavedDevices::load(dir).await;
+        assert_eq!(
+            saved.account_numbers(),
+            [
+                second.account_number.clone(),
+                replacement.account_number.clone()
+            ]
+        );
+
+        assert_eq!(
+            saved.take(&replacement.account_number).await.unwrap(),
+            Some(replacement.clone())
+        );
+        assert_eq!(saved.take(&replacement.account_number).await.unwrap(), None);
+
+        // Removing a device that is not saved does nothing
+        saved.remove(&first).await.unwrap();
+        assert_eq!(saved.account_numbers(), [second.account_number.clone()]);
+
+        assert_eq!(saved.clear().await.unwrap(), [second]);
+        assert!(SavedDevices::load(dir).await.account_numbers().is_empty());
+    }
+
     /// Starting a new connection loop should resume device validity checks
     #[test]
     fn test_device_check_reset() {
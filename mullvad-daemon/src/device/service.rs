@@ -29,16 +29,24 @@ const RETRY_BACKOFF_STRATEGY: Jittered<ExponentialBackoff> = Jittered::jitter(
 pub struct DeviceService {
     api_availability: ApiAvailability,
     proxy: DevicesProxy,
+    factory: rest::RequestFactory,
 }
 
 impl DeviceService {
     pub fn new(handle: rest::MullvadRestHandle, api_availability: ApiAvailability) -> Self {
         Self {
+            factory: handle.factory.clone(),
             proxy: DevicesProxy::new(handle),
             api_availability,
         }
     }
 
+    /// Forget the cached access token for an account, so that a new one is requested by the
+    /// next API call.
+    pub fn invalidate_access_token(&self, account_number: &AccountNumber) {
+        self.factory.invalidate_access_token(account_number);
+    }
+
     /// Generate a new device for a given account number
     pub fn generate_for_account(
         &self,
@@ -207,10 +215,11 @@ impl DeviceService {
 
         let rotate_retry_strategy = std::iter::repeat(Duration::from_hours(24));
 
+        // Scheduled rotations are postponed while in low-power mode
         let addresses = retry_future(
             move || {
                 let task = proxy.replace_wg_key(number.clone(), device.clone(), pubkey.clone());
-                api_handle.when_bg_resumes(task)
+                api_handle.when_non_essential(task)
             },
             should_retry_backoff,
             rotate_retry_strategy,
@@ -452,7 +461,7 @@ fn handle_account_data_result(
             api_availability.pause_background();
             true
         }
-        Err(mullvad_api::rest::Error::ApiError(_status, code)) => {
+        Err(mullvad_api::rest::Error::ApiError(_status, code, _)) => {
             if code == mullvad_api::INVALID_ACCOUNT {
                 api_availability.pause_background();
                 return true;
@@ -474,7 +483,7 @@ fn should_retry_backoff<T>(result: &Result<T, rest::Error>) -> bool {
     match result {
         Ok(_) => false,
         Err(error) => {
-            if let rest::Error::ApiError(status, code) = error {
+            if let rest::Error::ApiError(status, code, _) = error {
                 *status != rest::StatusCode::NOT_FOUND
                     && code != mullvad_api::DEVICE_NOT_FOUND
                     && code != mullvad_api::INVALID_ACCOUNT
@@ -489,7 +498,7 @@ fn should_retry_backoff<T>(result: &Result<T, rest::Error>) -> bool {
 
 fn map_rest_error(error: rest::Error) -> Error {
     match error {
-        rest::Error::ApiError(_status, ref code) => match code.as_str() {
+        rest::Error::ApiError(_status, ref code, _) => match code.as_str() {
             // TODO: Implement invalid payment
             mullvad_api::DEVICE_NOT_FOUND => Error::InvalidDevice,
             mullvad_api::INVALID_ACCOUNT => Error::InvalidAccount,
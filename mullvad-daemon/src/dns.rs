@@ -1,8 +1,10 @@
 use std::net::{IpAddr, Ipv4Addr};
 
-use mullvad_types::settings::{DnsOptions, DnsState};
+use mullvad_types::settings::{
+    DnsOptions, DnsResolverSource, DnsServerSource, DnsState, EffectiveDnsServer,
+};
 use talpid_core::firewall::is_local_address;
-use talpid_dns::DnsConfig;
+use talpid_dns::{DnsConfig, SplitDnsConfig};
 
 /// When we want to block certain contents with the help of DNS server side,
 /// we compute the resolver IP to use based on these constants. The last
@@ -17,58 +19,144 @@ const DNS_SOCIAL_MEDIA_BLOCKING_IP_BIT: u8 = 1 << 5; // 0b00100000
 
 /// Return the DNS resolvers to use
 pub fn addresses_from_options(options: &DnsOptions) -> DnsConfig {
+    let split_config = options
+        .split_dns
+        .iter()
+        .map(|rule| {
+            let (non_tunnel_config, tunnel_config) = partition_by_locality(&rule.servers);
+            SplitDnsConfig {
+                domain: rule.domain.clone(),
+                tunnel_config,
+                non_tunnel_config,
+            }
+        })
+        .collect();
+    default_addresses_from_options(options).with_split_config(split_config)
+}
+
+/// Return the DNS resolvers to use for domains not covered by split DNS
+fn default_addresses_from_options(options: &DnsOptions) -> DnsConfig {
     match options.state {
-        DnsState::Default => {
-            // Check if we should use a custom blocking DNS resolver.
-            // And if so, compute the IP.
-            let mut last_byte: u8 = 0;
+        DnsState::Default => match content_blocker_address(options) {
+            Some(address) => DnsConfig::from_addresses(&[address], &[]),
+            None => DnsConfig::default(),
+        },
+        DnsState::Custom => custom_addresses_from_options(options),
+    }
+}
 
-            if options.default_options.block_ads {
-                last_byte |= DNS_AD_BLOCKING_IP_BIT;
-            }
-            if options.default_options.block_trackers {
-                last_byte |= DNS_TRACKER_BLOCKING_IP_BIT;
-            }
-            if options.default_options.block_malware {
-                last_byte |= DNS_MALWARE_BLOCKING_IP_BIT;
-            }
-            if options.default_options.block_adult_content {
-                last_byte |= DNS_ADULT_BLOCKING_IP_BIT;
-            }
-            if options.default_options.block_gambling {
-                last_byte |= DNS_GAMBLING_BLOCKING_IP_BIT;
-            }
-            if options.default_options.block_social_media {
-                last_byte |= DNS_SOCIAL_MEDIA_BLOCKING_IP_BIT;
-            }
+/// Return the DNS resolvers to use when custom DNS is enabled, in the order given by
+/// `resolver_order`.
+fn custom_addresses_from_options(options: &DnsOptions) -> DnsConfig {
+    let mut tunnel_config = vec![];
+    let mut non_tunnel_config = vec![];
+    let mut gateway_position = None;
 
-            if last_byte != 0 {
-                let mut dns_ip = DNS_BLOCKING_IP_BASE.octets();
-                dns_ip[dns_ip.len() - 1] |= last_byte;
-                DnsConfig::from_addresses(&[IpAddr::V4(Ipv4Addr::from(dns_ip))], &[])
-            } else {
-                DnsConfig::default()
+    for source in &options.resolver_order {
+        match source {
+            DnsResolverSource::Custom => {
+                let (non_tunnel, tunnel) = partition_by_locality(&options.custom_options.addresses);
+                tunnel_config.extend(tunnel);
+                non_tunnel_config.extend(non_tunnel);
             }
+            DnsResolverSource::Mullvad => match content_blocker_address(options) {
+                Some(address) => tunnel_config.push(address),
+                None => gateway_position = Some(tunnel_config.len()),
+            },
         }
-        DnsState::Custom if options.custom_options.addresses.is_empty() => DnsConfig::default(),
-        DnsState::Custom => {
-            let (non_tunnel_config, tunnel_config): (Vec<_>, Vec<_>) = options
-                .custom_options
-                .addresses
-                .iter()
-                .copied()
-                // Private IP ranges should not be tunneled
-                .partition(|addr| is_local_address(*addr));
-            DnsConfig::from_addresses(&tunnel_config, &non_tunnel_config)
+    }
+
+    match gateway_position {
+        Some(position) => {
+            DnsConfig::from_addresses_with_gateway(&tunnel_config, &non_tunnel_config, position)
         }
+        None if tunnel_config.is_empty() && non_tunnel_config.is_empty() => DnsConfig::default(),
+        None => DnsConfig::from_addresses(&tunnel_config, &non_tunnel_config),
+    }
+}
+
+/// Return the content blocking DNS resolver selected by the default DNS options, if any
+/// content blockers are enabled.
+fn content_blocker_address(options: &DnsOptions) -> Option<IpAddr> {
+    let mut last_byte: u8 = 0;
+
+    if options.default_options.block_ads {
+        last_byte |= DNS_AD_BLOCKING_IP_BIT;
+    }
+    if options.default_options.block_trackers {
+        last_byte |= DNS_TRACKER_BLOCKING_IP_BIT;
+    }
+    if options.default_options.block_malware {
+        last_byte |= DNS_MALWARE_BLOCKING_IP_BIT;
+    }
+    if options.default_options.block_adult_content {
+        last_byte |= DNS_ADULT_BLOCKING_IP_BIT;
+    }
+    if options.default_options.block_gambling {
+        last_byte |= DNS_GAMBLING_BLOCKING_IP_BIT;
+    }
+    if options.default_options.block_social_media {
+        last_byte |= DNS_SOCIAL_MEDIA_BLOCKING_IP_BIT;
+    }
+
+    if last_byte == 0 {
+        return None;
     }
+    let mut dns_ip = DNS_BLOCKING_IP_BASE.octets();
+    dns_ip[dns_ip.len() - 1] |= last_byte;
+    Some(IpAddr::V4(Ipv4Addr::from(dns_ip)))
+}
+
+/// Split `addresses` into local and non-local addresses. Private IP ranges should not be
+/// tunneled.
+fn partition_by_locality(addresses: &[IpAddr]) -> (Vec<IpAddr>, Vec<IpAddr>) {
+    addresses
+        .iter()
+        .copied()
+        .partition(|addr| is_local_address(*addr))
+}
+
+/// Return the DNS servers used by a tunnel with the gateways `gateways`, given the DNS `options`.
+pub fn effective_dns_servers(options: &DnsOptions, gateways: &[IpAddr]) -> Vec<EffectiveDnsServer> {
+    let content_blocker = content_blocker_address(options);
+    let source = |address: IpAddr| match options.state {
+        DnsState::Custom if options.custom_options.addresses.contains(&address) => {
+            DnsServerSource::Custom
+        }
+        _ if Some(address) == content_blocker => DnsServerSource::ContentBlocker,
+        DnsState::Default | DnsState::Custom => DnsServerSource::Relay,
+    };
+    let split_servers = options
+        .split_dns
+        .iter()
+        .flat_map(|rule| rule.servers.iter().copied())
+        .map(|address| EffectiveDnsServer {
+            address,
+            source: DnsServerSource::SplitDns,
+        });
+    addresses_from_options(options)
+        .resolve(
+            gateways,
+            #[cfg(target_os = "macos")]
+            53,
+        )
+        .addresses()
+        .map(|address| EffectiveDnsServer {
+            address,
+            source: source(address),
+        })
+        .chain(split_servers)
+        .collect()
 }
 
 #[cfg(test)]
 mod test {
-    use crate::dns::addresses_from_options;
-    use mullvad_types::settings::{CustomDnsOptions, DefaultDnsOptions, DnsOptions, DnsState};
-    use talpid_dns::DnsConfig;
+    use crate::dns::{addresses_from_options, effective_dns_servers};
+    use mullvad_types::settings::{
+        CustomDnsOptions, DefaultDnsOptions, DnsOptions, DnsResolverSource, DnsServerSource,
+        DnsState, EffectiveDnsServer, SplitDnsRule,
+    };
+    use talpid_dns::{DnsConfig, SplitDnsConfig};
 
     #[test]
     fn test_default_dns() {
@@ -76,6 +164,8 @@ mod test {
             state: DnsState::Default,
             custom_options: CustomDnsOptions::default(),
             default_options: DefaultDnsOptions::default(),
+            split_dns: vec![],
+            resolver_order: vec![DnsResolverSource::Custom],
         };
 
         assert_eq!(addresses_from_options(&public_cfg), DnsConfig::default());
@@ -90,6 +180,8 @@ mod test {
                 block_ads: true,
                 ..DefaultDnsOptions::default()
             },
+            split_dns: vec![],
+            resolver_order: vec![DnsResolverSource::Custom],
         };
 
         assert_eq!(
@@ -109,6 +201,8 @@ mod test {
                 addresses: vec![public_ip, private_ip],
             },
             default_options: DefaultDnsOptions::default(),
+            split_dns: vec![],
+            resolver_order: vec![DnsResolverSource::Custom],
         };
 
         assert_eq!(
@@ -116,4 +210,103 @@ mod test {
             DnsConfig::from_addresses(&[public_ip], &[private_ip],)
         );
     }
+
+    #[test]
+    fn test_resolver_order() {
+        let gateway = "10.64.0.1".parse().unwrap();
+        let custom_ip = "1.2.3.4".parse().unwrap();
+        let mut options = DnsOptions {
+            state: DnsState::Custom,
+            custom_options: CustomDnsOptions {
+                addresses: vec![custom_ip],
+            },
+            resolver_order: vec![DnsResolverSource::Mullvad, DnsResolverSource::Custom],
+            ..DnsOptions::default()
+        };
+
+        assert_eq!(
+            effective_dns_servers(&options, &[gateway]),
+            vec![
+                EffectiveDnsServer {
+                    address: gateway,
+                    source: DnsServerSource::Relay,
+                },
+                EffectiveDnsServer {
+                    address: custom_ip,
+                    source: DnsServerSource::Custom,
+                },
+            ]
+        );
+
+        options.default_options.block_ads = true;
+        options.resolver_order = vec![DnsResolverSource::Custom, DnsResolverSource::Mullvad];
+        assert_eq!(
+            effective_dns_servers(&options, &[gateway]),
+            vec![
+                EffectiveDnsServer {
+                    address: custom_ip,
+                    source: DnsServerSource::Custom,
+                },
+                EffectiveDnsServer {
+                    address: "100.64.0.1".parse().unwrap(),
+                    source: DnsServerSource::ContentBlocker,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_effective_dns_servers() {
+        let gateway = "10.64.0.1".parse().unwrap();
+        let mut options = DnsOptions::default();
+
+        assert_eq!(
+            effective_dns_servers(&options, &[gateway]),
+            vec![EffectiveDnsServer {
+                address: gateway,
+                source: DnsServerSource::Relay,
+            }]
+        );
+
+        options.default_options.block_ads = true;
+        assert_eq!(
+            effective_dns_servers(&options, &[gateway]),
+            vec![EffectiveDnsServer {
+                address: "100.64.0.1".parse().unwrap(),
+                source: DnsServerSource::ContentBlocker,
+            }]
+        );
+
+        let custom_ip = "1.2.3.4".parse().unwrap();
+        options.state = DnsState::Custom;
+        options.custom_options.addresses = vec![custom_ip];
+        assert_eq!(
+            effective_dns_servers(&options, &[gateway]),
+            vec![EffectiveDnsServer {
+                address: custom_ip,
+                source: DnsServerSource::Custom,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_split_dns() {
+        let public_ip = "1.2.3.4".parse().unwrap();
+        let private_ip = "172.16.10.1".parse().unwrap();
+        let options = DnsOptions {
+            split_dns: vec![
+                SplitDnsRule::new("corp.example.com", vec![public_ip, private_ip]).unwrap(),
+            ],
+            ..DnsOptions::default()
+        };
+
+        assert_eq!(
+            addresses_from_options(&options),
+            DnsConfig::default().with_split_config(vec![SplitDnsConfig {
+                domain: "corp.example.com".to_owned(),
+                tunnel_config: vec![public_ip],
+                non_tunnel_config: vec![private_ip],
+            }])
+        );
+    }
 }
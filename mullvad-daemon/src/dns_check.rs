@@ -0,0 +1,182 @@
+//! Tests whether DNS servers are reachable and resolve queries recursively.
+//!
+//! A query is sent over UDP to each server like any other traffic from the daemon, so it goes
+//! through the tunnel while connected.
+
+use futures::future::join_all;
+use mullvad_types::settings::{DnsServerReachability, DnsServerTestResult};
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+use talpid_types::ErrorExt;
+use tokio::net::UdpSocket;
+
+/// Name to resolve. Any name outside of the zones that a server is authoritative for requires
+/// recursion.
+const QUERY_NAME: &str = "mullvad.net";
+const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+const DNS_PORT: u16 = 53;
+const HEADER_SIZE: usize = 12;
+/// Largest response to a query over UDP without EDNS
+const MAX_RESPONSE_SIZE: usize = 512;
+
+const FLAG_RESPONSE: u16 = 0x8000;
+const FLAG_RECURSION_DESIRED: u16 = 0x0100;
+const FLAG_RECURSION_AVAILABLE: u16 = 0x0080;
+const RCODE_MASK: u16 = 0x000f;
+const RCODE_NO_ERROR: u16 = 0;
+const RCODE_NAME_ERROR: u16 = 3;
+const RCODE_REFUSED: u16 = 5;
+
+const QTYPE_A: u16 = 1;
+const QCLASS_IN: u16 = 1;
+
+/// Test all `servers` concurrently. `is_blocked` tells whether the firewall blocks DNS to a
+/// server, in which case no query is sent to it.
+pub async fn test_servers(
+    servers: Vec<IpAddr>,
+    is_blocked: impl Fn(&IpAddr) -> bool,
+) -> Vec<DnsServerTestResult> {
+    join_all(servers.into_iter().map(|address| {
+        let blocked = is_blocked(&address);
+        async move {
+            let reachability = if blocked {
+                DnsServerReachability::Blocked
+            } else {
+                test_server(address).await
+            };
+            DnsServerTestResult {
+                address,
+                reachability,
+            }
+        }
+    }))
+    .await
+}
+
+async fn test_server(address: IpAddr) -> DnsServerReachability {
+    let id = rand::random();
+    match tokio::time::timeout(QUERY_TIMEOUT, query(address, id)).await {
+        Ok(Ok(reachability)) => reachability,
+        Ok(Err(error)) => {
+            log::debug!(
+                "{}",
+                error.display_chain_with_msg(&format!("Failed to query DNS server {address}"))
+            );
+            DnsServerReachability::NoResponse
+        }
+        Err(_) => DnsServerReachability::NoResponse,
+    }
+}
+
+async fn query(address: IpAddr, id: u16) -> io::Result<DnsServerReachability> {
+    let bind_address = match address {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    let socket = UdpSocket::bind((bind_address, 0)).await?;
+    socket.connect(SocketAddr::new(address, DNS_PORT)).await?;
+    socket.send(&build_query(id)).await?;
+
+    let mut response = [0u8; MAX_RESPONSE_SIZE];
+    loop {
+        let len = socket.recv(&mut response).await?;
+        // Ignore anything that is not a response to this query
+        if let Some(reachability) = parse_response(&response[..len], id) {
+            return Ok(reachability);
+        }
+    }
+}
+
+/// Build a recursive query for the A record of [QUERY_NAME].
+fn build_query(id: u16) -> Vec<u8> {
+    let mut query = Vec::with_capacity(HEADER_SIZE + QUERY_NAME.len() + 6);
+    query.extend_from_slice(&id.to_be_bytes());
+    query.extend_from_slice(&FLAG_RECURSION_DESIRED.to_be_bytes());
+    // One question, and no answer, authority, or additional records
+    query.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in QUERY_NAME.split('.') {
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&QTYPE_A.to_be_bytes());
+    query.extend_from_slice(&QCLASS_IN.to_be_bytes());
+    query
+}
+
+/// Classify a response by its header. Returns `None` if `response` is not a response to the
+/// query with the given `id`.
+fn parse_response(response: &[u8], id: u16) -> Option<DnsServerReachability> {
+    if response.len() < HEADER_SIZE {
+        return None;
+    }
+    let response_id = u16::from_be_bytes([response[0], response[1]]);
+    let flags = u16::from_be_bytes([response[2], response[3]]);
+    if response_id != id || flags & FLAG_RESPONSE == 0 {
+        return None;
+    }
+
+    let reachability = match flags & RCODE_MASK {
+        RCODE_REFUSED => DnsServerReachability::RecursionRefused,
+        _ if flags & FLAG_RECURSION_AVAILABLE == 0 => DnsServerReachability::RecursionRefused,
+        RCODE_NO_ERROR | RCODE_NAME_ERROR => DnsServerReachability::Answered,
+        _ => DnsServerReachability::Failed,
+    };
+    Some(reachability)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn response(id: u16, flags: u16) -> Vec<u8> {
+        let mut response = build_query(id);
+        response[2..4].copy_from_slice(&flags.to_be_bytes());
+        response
+    }
+
+    #[test]
+    fn test_build_query() {
+        let query = build_query(0x1234);
+        assert_eq!(&query[..4], &[0x12, 0x34, 0x01, 0x00]);
+        assert_eq!(
+            &query[HEADER_SIZE..],
+            b"\x07mullvad\x03net\x00\x00\x01\x00\x01"
+        );
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let answered = FLAG_RESPONSE | FLAG_RECURSION_DESIRED | FLAG_RECURSION_AVAILABLE;
+        assert_eq!(
+            parse_response(&response(1, answered), 1),
+            Some(DnsServerReachability::Answered)
+        );
+        assert_eq!(
+            parse_response(&response(1, answered | RCODE_NAME_ERROR), 1),
+            Some(DnsServerReachability::Answered)
+        );
+        assert_eq!(
+            parse_response(&response(1, answered | 2), 1),
+            Some(DnsServerReachability::Failed)
+        );
+
+        // Refused, or recursion not available
+        assert_eq!(
+            parse_response(&response(1, answered | RCODE_REFUSED), 1),
+            Some(DnsServerReachability::RecursionRefused)
+        );
+        assert_eq!(
+            parse_response(&response(1, FLAG_RESPONSE), 1),
+            Some(DnsServerReachability::RecursionRefused)
+        );
+
+        // Not a response to the query
+        assert_eq!(parse_response(&response(2, answered), 1), None);
+        assert_eq!(parse_response(&build_query(1), 1), None);
+        assert_eq!(parse_response(&[0; 4], 1), None);
+    }
+}
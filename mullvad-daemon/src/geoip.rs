@@ -1,8 +1,10 @@
-use std::time::Duration;
+use std::{future::Future, net::ToSocketAddrs, time::Duration};
 
 use futures::join;
 use mullvad_api::rest::{Error, RequestServiceHandle};
-use mullvad_types::location::{AmIMullvad, GeoIpLocation, LocationEventData};
+use mullvad_types::location::{
+    AmIMullvad, AmIMullvadDnsResolver, DnsLeakResult, GeoIpLocation, LocationEventData,
+};
 use std::sync::LazyLock;
 use talpid_core::mpsc::Sender;
 use talpid_future::retry::{ExponentialBackoff, Jittered, retry_future};
@@ -19,7 +21,7 @@ use crate::{DaemonEventSender, InternalDaemonEvent};
 // production build, a warning will be logged and the env variable *won´t* have
 // any effect on the api call. The default host name `am.i.mullvad.net` will
 // always be used in release mode.
-static MULLVAD_CONNCHECK_HOST: LazyLock<String> = LazyLock::new(|| {
+pub(crate) static MULLVAD_CONNCHECK_HOST: LazyLock<String> = LazyLock::new(|| {
     const DEFAULT_CONNCHECK_HOST: &str = "am.i.mullvad.net";
     let conncheck_host_var = std::env::var("MULLVAD_CONNCHECK_HOST").ok();
     let host = if cfg!(feature = "api-override") {
@@ -88,6 +90,42 @@ impl GeoIpHandler {
     pub fn abort_current_request(&mut self) {
         self.rest_service.reset();
     }
+
+    /// Check whether DNS queries leak outside of Mullvad.
+    ///
+    /// This resolves a unique subdomain of the am.i.mullvad.net DNS leak service
+    /// using the system resolver, and then asks the service which resolvers
+    /// performed the lookup.
+    pub fn check_dns_leak(&self) -> impl Future<Output = Result<DnsLeakResult, Error>> + use<> {
+        let rest_service = self.rest_service.clone();
+        async move {
+            let id = format!("{:032x}", rand::random::<u128>());
+            let hostname = format!("{id}.dnsleak.{}", *MULLVAD_CONNCHECK_HOST);
+            // The lookup is only used to make the resolvers contact the leak service, so the
+            // addresses are irrelevant. If it fails, no resolver may be reported below, which
+            // makes the result unknown.
+            match tokio::task::spawn_blocking(move || (hostname.as_str(), 0).to_socket_addrs())
+                .await
+            {
+                Ok(Ok(_)) => (),
+                Ok(Err(error)) => {
+                    log::debug!("{}", error.display_chain_with_msg("DNS leak lookup failed"))
+                }
+                Err(error) => {
+                    log::error!(
+                        "{}",
+                        error.display_chain_with_msg("DNS leak lookup task failed")
+                    )
+                }
+            }
+
+            let uri = format!("https://{}/dnsleak/{id}", *MULLVAD_CONNCHECK_HOST);
+            let request = mullvad_api::rest::get(&uri)?;
+            let resolvers: Vec<AmIMullvadDnsResolver> =
+                rest_service.request(request).await?.deserialize().await?;
+            Ok(DnsLeakResult::from_resolvers(resolvers))
+        }
+    }
 }
 
 /// Fetch the current `GeoIpLocation` from am.i.mullvad.net. Handles retries on network errors.
@@ -164,3 +202,58 @@ fn log_network_error(err: Error, version: &'static str) {
         log::debug!("{}", err.display_chain_with_msg(err_message));
     }
 }
+
+#[cfg(test)]
+mod test {
+    use mullvad_types::location::{AmIMullvadDnsResolver, DnsLeakResult};
+
+    /// A DNS leak service response where one of the resolvers is not operated by Mullvad.
+    const LEAKING_RESPONSE: &str = r#"[
+        {
+            "ip": "10.64.0.1",
+            "country": "Sweden",
+            "mullvad_dns": true,
+            "mullvad_dns_hostname": "se-got-dns-001",
+            "organization": "Mullvad VPN"
+        },
+        {
+            "ip": "8.8.8.8",
+            "country": "United States",
+            "mullvad_dns": false,
+            "mullvad_dns_hostname": "",
+            "organization": "Google"
+        }
+    ]"#;
+
+    #[test]
+    fn test_dns_leak_detected() {
+        let resolvers: Vec<AmIMullvadDnsResolver> = serde_json::from_str(LEAKING_RESPONSE).unwrap();
+        let result = DnsLeakResult::from_resolvers(resolvers);
+
+        assert_eq!(result.leaking, Some(true));
+        assert_eq!(
+            result.resolvers,
+            vec!["10.64.0.1".parse().unwrap(), "8.8.8.8".parse().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_no_dns_leak() {
+        let resolvers: Vec<AmIMullvadDnsResolver> =
+            serde_json::from_str(r#"[{"ip": "10.64.0.1", "mullvad_dns": true}]"#).unwrap();
+        assert_eq!(
+            DnsLeakResult::from_resolvers(resolvers).leaking,
+            Some(false)
+        );
+    }
+
+    /// Without any reported resolver, it is unknown whether DNS queries leak
+    #[test]
+    fn test_dns_leak_unknown() {
+        let resolvers: Vec<AmIMullvadDnsResolver> = serde_json::from_str("[]").unwrap();
+        let result = DnsLeakResult::from_resolvers(resolvers);
+
+        assert_eq!(result.leaking, None);
+        assert!(result.resolvers.is_empty());
+    }
+}
@@ -6,11 +6,15 @@ pub mod account_history;
 mod android_dns;
 mod api;
 mod api_address_updater;
+mod cache_usage;
+mod captive_portal;
 #[cfg(not(target_os = "android"))]
 mod cleanup;
+mod connect_schedule;
 mod custom_list;
 pub mod device;
 mod dns;
+mod dns_check;
 pub mod exception_logging;
 mod geoip;
 mod leak_checker;
@@ -19,18 +23,32 @@ pub mod logging;
 mod macos;
 pub mod management_interface;
 mod migrations;
+mod network_interfaces;
+mod notifications;
+mod obfuscation_benchmark;
+mod pause;
+mod profile;
 mod relay_list;
+mod relay_port_test;
+#[cfg(feature = "diagnostics")]
+mod resource_usage;
+mod restart_reason;
 #[cfg(not(target_os = "android"))]
 pub mod rpc_uniqueness_check;
 pub mod runtime;
 pub mod settings;
 pub mod shutdown;
 mod target_state;
+mod trusted_network;
 mod tunnel;
+mod tunnel_mtu;
+mod usage_stats;
 pub mod version;
 
 use crate::{
-    relay_list::parsed_relays::parse_relays_from_file, target_state::PersistentTargetState,
+    notifications::NotificationTracker, relay_list::parsed_relays::parse_relays_from_file,
+    restart_reason::ShutdownRecorder, target_state::PersistentTargetState,
+    usage_stats::UsageStatsTracker,
 };
 use api::DaemonAccessMethodResolver;
 use device::{AccountEvent, PrivateAccountAndDevice, PrivateDeviceEvent};
@@ -54,22 +72,53 @@ use mullvad_types::settings::SplitApp;
 #[cfg(daita)]
 use mullvad_types::wireguard::DaitaSettings;
 use mullvad_types::{
-    access_method::{AccessMethod, AccessMethodSetting},
-    account::{AccountData, AccountNumber, VoucherSubmission},
+    access_method::{
+        AccessMethod, AccessMethodSearch, AccessMethodSetting, AccessMethodTestResult,
+        LastSuccessfulAccessMethod,
+    },
+    account::{AccountData, AccountNumber, LoginBackoff, VoucherSubmission},
+    api::{ApiAddressInfo, ApiErrorResponse, ApiRequestLimit, InflightApiRequest},
     auth_failed::AuthFailed,
-    constraints::Constraint,
+    cache::{CacheKind, CacheUsage},
+    clock::ClockCheck,
+    connection_config::EffectiveConnectionConfig,
+    connection_history::{
+        ConnectionAttempt, ConnectionAttemptHistory, ConnectionAttemptOutcome, NetworkProbeResults,
+    },
+    constraints::{Constraint, Match},
     custom_list::CustomList,
     device::{Device, DeviceEvent, DeviceEventCause, DeviceId, DeviceState, RemoveDeviceEvent},
     features::{FeatureIndicator, FeatureIndicators, compute_feature_indicators},
-    location::{GeoIpLocation, LocationEventData},
+    location::{CityCode, CountryCode, DnsLeakResult, GeoIpLocation, LocationEventData},
+    notification::Notification,
+    profile::ProfilesSettings,
     relay_constraints::{
-        ObfuscationSettings, RelayOverride, RelaySettings, allowed_ip::AllowedIps,
+        ObfuscationSensitivity, ObfuscationSettings, ObfuscationState, Ownership, RelayOverride,
+        RelaySettings, SelectedObfuscation,
+        allowed_ip::{self, AllowedIps, AllowedIpsWarning},
+    },
+    relay_link::{self, RelayLink},
+    relay_list::{
+        ObfuscationBenchmarkResult, RelayFeature, RelayFeatureSupport, RelayFeatures, RelayList,
+        RelayListReport, RelayListUpdateInfo, RelayLocationsSummary, RelayPortReachability,
+        RelayTransparencyInfo, WireguardRelay,
+    },
+    schedule::ConnectSchedule,
+    settings::{
+        AccountExpiryAction, DnsOptions, DnsServerTestResult, DnsState, EffectiveDnsServer,
+        FactoryResetPreview, LockableSetting, RelayListDownloadSettings, Settings,
+        SettingsIntegrity, SettingsLock, SettingsMigration, SettingsSnapshot,
+    },
+    states::{
+        DisconnectReason, RestartReason, Secured, TargetState, TargetStateStrict, TunnelState,
+    },
+    trusted_network::{CurrentNetwork, TrustedNetwork, TrustedNetworksSettings},
+    usage_stats::UsageStats,
+    version::{ActiveDownload, AppVersionInfo},
+    wireguard::{
+        PublicKey, QuantumResistantState, RotationInterval, TunnelCryptoInfo, TunnelMtuInfo,
+        WIREGUARD_HANDSHAKE_PROTOCOL, WireguardKeyAge,
     },
-    relay_list::RelayList,
-    settings::{DnsOptions, Settings},
-    states::{Secured, TargetState, TargetStateStrict, TunnelState},
-    version::AppVersionInfo,
-    wireguard::{PublicKey, QuantumResistantState, RotationInterval},
 };
 use mullvad_types::{
     relay_constraints::{
@@ -83,11 +132,12 @@ use relay_list::{RelayListUpdater, RelayListUpdaterHandle};
 use settings::SettingsPersister;
 use std::collections::BTreeSet;
 #[cfg(any(target_os = "windows", target_os = "android", target_os = "macos"))]
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 #[cfg(target_os = "android")]
 use std::os::unix::io::RawFd;
 use std::{
     marker::PhantomData,
+    net::IpAddr,
     path::PathBuf,
     pin::Pin,
     sync::{Arc, Weak},
@@ -109,10 +159,13 @@ use talpid_types::android::AndroidContext;
 use talpid_types::split_tunnel::ExcludedProcess;
 use talpid_types::{
     ErrorExt,
-    net::IpVersion,
+    firewall::FirewallInfo,
+    net::{
+        Connectivity, InstalledRoute, IpVersion, TransportProtocol, wireguard::HandshakeDiagnostics,
+    },
     tunnel::{ErrorStateCause, TunnelStateTransition},
 };
-use tokio::io;
+use tokio::{io, sync::watch};
 
 #[cfg(target_os = "windows")]
 pub mod service {
@@ -139,6 +192,12 @@ pub enum Error {
     #[error("REST request failed")]
     RestError(#[source] mullvad_api::rest::Error),
 
+    #[error("Failed to re-resolve the API address")]
+    RefreshApiAddress(#[source] mullvad_api::Error),
+
+    #[error("Only one overridden API address can be allowed in the firewall")]
+    MultipleApiAddresses,
+
     #[error("Management interface error")]
     ManagementInterfaceError(#[source] management_interface::Error),
 
@@ -179,7 +238,6 @@ pub enum Error {
     #[error("Unable to initialize split tunneling")]
     InitSplitTunneling(#[source] split_tunnel::Error),
 
-    #[cfg(any(target_os = "windows", target_os = "android", target_os = "macos"))]
     #[error("Split tunneling error")]
     SplitTunnelError(#[source] split_tunnel::Error),
 
@@ -192,6 +250,12 @@ pub enum Error {
     #[error("No account history available for the token")]
     NoAccountNumberHistory,
 
+    #[error("The trusted network cannot be detected on this platform")]
+    TrustedNetworkUnsupported,
+
+    #[error("The SSID hash is neither trusted nor the hash of the current network")]
+    UnknownSsidHash,
+
     #[error("Settings error")]
     SettingsError(#[source] settings::Error),
 
@@ -218,9 +282,52 @@ pub enum Error {
 
     #[error("API connection mode error")]
     ApiConnectionModeError(#[source] mullvad_api::access_mode::Error),
+
+    #[error("Invalid relay link")]
+    RelayLinkError(#[source] relay_link::Error),
+
     #[error("No custom bridge has been specified")]
     NoCustomProxySaved,
 
+    #[error("There is no relay with the hostname {0}")]
+    RelayNotFound(String),
+
+    #[error("Logging to file is disabled")]
+    NoLogDirectory,
+
+    #[error("Failed to set the log filter")]
+    SetLogFilter(#[source] tracing_subscriber::reload::Error),
+
+    #[error("There is no active notification with the ID {0}")]
+    NotificationNotFound(String),
+
+    #[error("Failed to resolve the connection configuration")]
+    ConnectionConfig(#[source] tunnel::Error),
+
+    #[error("The device is offline")]
+    Offline,
+
+    #[error("The tunnel can only be paused while the target state is secured")]
+    PauseNotSecured,
+
+    #[error("The tunnel must be paused for a nonzero duration of at most 24 hours")]
+    InvalidPauseDuration,
+
+    #[error("An obfuscation benchmark is already running")]
+    ObfuscationBenchmarkInProgress,
+
+    #[error("Relay ports can only be tested while disconnected and not blocking traffic")]
+    RelayPortTestNotDisconnected,
+
+    #[error("Each obfuscation method to benchmark must be specific and only be given once")]
+    InvalidObfuscationBenchmark,
+
+    #[error("Failed to remove cached files")]
+    ClearCache(#[source] io::Error),
+
+    #[error("Failed to remove downloaded installers")]
+    ClearInstallers(#[source] version::Error),
+
     #[cfg(target_os = "macos")]
     #[error("Failed to set exclusion group")]
     GroupIdError(#[source] io::Error),
@@ -240,8 +347,18 @@ pub enum DaemonCommand {
     SetTargetState(oneshot::Sender<bool>, TargetState),
     /// Reconnect the tunnel, if one is connecting/connected.
     Reconnect(oneshot::Sender<bool>),
+    /// Disconnect, and reconnect once the duration has passed. Pausing again while paused
+    /// restarts the pause with the new duration.
+    PauseTunnel(ResponseTx<(), Error>, Duration),
+    /// End a pause early and reconnect. Returns whether the tunnel was paused.
+    ResumeTunnel(oneshot::Sender<bool>),
+    /// Connect to the relay with the given hostname, ignoring the relay constraints. Normal relay
+    /// selection is used again the next time the tunnel is reconnected.
+    ConnectToRelayOnce(ResponseTx<(), Error>, String),
     /// Request the current state.
     GetState(oneshot::Sender<TunnelState>),
+    /// Request whether the firewall is currently blocking traffic outside the tunnel.
+    IsKillSwitchActive(oneshot::Sender<bool>),
     CreateNewAccount(ResponseTx<String, Error>),
     /// Request the metadata for an account.
     GetAccountData(
@@ -258,6 +375,38 @@ pub enum DaemonCommand {
     ClearAccountHistory(ResponseTx<(), Error>),
     /// Get the list of countries and cities where there are relays.
     GetRelayLocations(oneshot::Sender<RelayList>),
+    /// Get the countries and cities where there are relays, with the number of relays in each.
+    GetRelayLocationsSummary(oneshot::Sender<RelayLocationsSummary>),
+    /// Get the relays in a city, given a country code and a city code. Returns `None` if the city
+    /// is not in the relay list.
+    GetRelaysInCity(
+        oneshot::Sender<Option<Vec<WireguardRelay>>>,
+        CountryCode,
+        CityCode,
+    ),
+    /// Check which of the features requested by the current settings are supported by the relays
+    /// matching the given relay settings
+    CheckRelayFeatureSupport(oneshot::Sender<RelayFeatureSupport>, RelaySettings),
+    /// Validate the relay list currently in use
+    RelayListSelfTest(oneshot::Sender<RelayListReport>),
+    /// Return the schedule of automatic relay list updates
+    GetRelayListUpdateInfo(oneshot::Sender<RelayListUpdateInfo>),
+    /// Test whether a port on the relay with the given hostname is reachable
+    TestRelayPort(
+        ResponseTx<RelayPortReachability, Error>,
+        String,
+        u16,
+        TransportProtocol,
+    ),
+    /// Return ownership and hosting information about the relay with the given hostname
+    GetRelayTransparencyInfo(ResponseTx<RelayTransparencyInfo, Error>, String),
+    /// Connect to the relay with the given hostname using each of the given obfuscation methods,
+    /// and measure their performance
+    BenchmarkObfuscation(
+        ResponseTx<Vec<ObfuscationBenchmarkResult>, Error>,
+        String,
+        Vec<SelectedObfuscation>,
+    ),
     /// Trigger an asynchronous relay list update. This returns before the relay list is actually
     /// updated.
     UpdateRelayLocations,
@@ -265,8 +414,20 @@ pub enum DaemonCommand {
     GetBridges(oneshot::Sender<BridgeList>),
     /// Log in with a given account and create a new device.
     LoginAccount(ResponseTx<(), Error>, AccountNumber),
+    /// Switch to another account without logging out. The current device is kept so that
+    /// switching back to it later does not require creating a new device.
+    SwitchAccount(ResponseTx<(), Error>, AccountNumber),
+    /// Return the accounts that can be switched back to without creating a new device.
+    GetSavedAccounts(ResponseTx<Vec<AccountNumber>, Error>),
     /// Log out of the current account and remove the device, if they exist.
     LogoutAccount(ResponseTx<(), Error>),
+    /// Request a new access token for the current account and validate the current device,
+    /// keeping its WireGuard key. The device is revoked if it is no longer valid.
+    Reauthenticate(ResponseTx<(), Error>),
+    /// Return whether logins are delayed due to failed logins.
+    GetLoginBackoff(ResponseTx<LoginBackoff, Error>),
+    /// Forget failed logins so that the next login is allowed immediately.
+    ClearLoginBackoff(ResponseTx<(), Error>),
     /// Return the current device configuration.
     GetDevice(ResponseTx<DeviceState, Error>),
     /// Update/check the current device, if there is one.
@@ -281,26 +442,60 @@ pub enum DaemonCommand {
     SetAllowLan(ResponseTx<(), settings::Error>, bool),
     /// Set the beta program setting.
     SetShowBetaReleases(ResponseTx<(), settings::Error>, bool),
+    /// Set whether to pick a new random relay on every connection attempt.
+    SetRandomizeRelayEachConnect(ResponseTx<(), settings::Error>, bool),
+    /// Set whether to pause non-essential background API requests.
+    SetLowPowerMode(ResponseTx<(), settings::Error>, bool),
+    /// Set whether to check for a captive portal before connecting.
+    SetCaptivePortalCheck(ResponseTx<(), settings::Error>, bool),
+    /// Set how long to keep retrying to connect before entering the error state.
+    SetConnectTimeout(ResponseTx<(), settings::Error>, Duration),
+    /// Set how many times to retry connecting before entering the error state.
+    SetMaxReconnectAttempts(ResponseTx<(), settings::Error>, Option<u32>),
+    /// Stop waiting for a detected captive portal and connect immediately.
+    DismissCaptivePortal(oneshot::Sender<()>),
     /// Set the lockdown_mode setting.
     #[cfg(not(target_os = "android"))]
     SetLockdownMode(ResponseTx<(), settings::Error>, bool),
     /// Set the auto-connect setting.
     SetAutoConnect(ResponseTx<(), settings::Error>, bool),
+    /// Set the schedule of when to connect and disconnect
+    SetConnectSchedule(ResponseTx<(), settings::Error>, ConnectSchedule),
+    /// Set the networks on which to not connect automatically
+    SetTrustedNetworks(ResponseTx<(), Error>, TrustedNetworksSettings),
+    /// Trust the Wi-Fi network with the given SSID. Only a salted hash of it is stored
+    AddTrustedSsid(ResponseTx<(), Error>, Vec<u8>),
     /// Set if IPv6 should be enabled in the tunnel
     SetEnableIpv6(ResponseTx<(), settings::Error>, bool),
     /// Set if recents should be enabled
     SetEnableRecents(ResponseTx<(), settings::Error>, bool),
     /// Set whether to enable PQ PSK exchange in the tunnel
     SetQuantumResistantTunnel(ResponseTx<(), settings::Error>, QuantumResistantState),
+    /// Set what to do when the account expires while connected
+    SetOnAccountExpiry(ResponseTx<(), settings::Error>, AccountExpiryAction),
     /// Set DAITA settings for the tunnel
     #[cfg(daita)]
     SetEnableDaita(ResponseTx<(), settings::Error>, bool),
     #[cfg(daita)]
     SetDaitaUseMultihopIfNecessary(ResponseTx<(), settings::Error>, bool),
+    /// Set whether to fail to connect rather than connecting without DAITA
+    #[cfg(daita)]
+    SetDaitaRequired(ResponseTx<(), settings::Error>, bool),
     #[cfg(daita)]
     SetDaitaSettings(ResponseTx<(), settings::Error>, DaitaSettings),
     /// Set DNS options or servers to use
     SetDnsOptions(ResponseTx<(), settings::Error>, DnsOptions),
+    /// Get the DNS servers currently used by the tunnel
+    GetEffectiveDnsServers(oneshot::Sender<Vec<EffectiveDnsServer>>),
+    /// Return the configuration that a tunnel would use if it was started now, without
+    /// connecting
+    GetEffectiveConnectionConfig(ResponseTx<EffectiveConnectionConfig, Error>),
+    /// Get the routes that the daemon has added to the routing table
+    GetInstalledRoutes(ResponseTx<Vec<InstalledRoute>, Error>),
+    /// Send a test query to each of the given DNS servers
+    TestCustomDns(oneshot::Sender<Vec<DnsServerTestResult>>, Vec<IpAddr>),
+    /// Check whether DNS queries leak outside of Mullvad
+    CheckDnsLeak(ResponseTx<DnsLeakResult, Error>),
     /// Set override options to use for a given relay
     SetRelayOverride(ResponseTx<(), settings::Error>, RelayOverride),
     /// Remove all relay override options
@@ -308,18 +503,91 @@ pub enum DaemonCommand {
     /// Toggle macOS network check leak
     /// Set MTU for wireguard tunnels
     SetWireguardMtu(ResponseTx<(), settings::Error>, Option<u16>),
+    /// Set persistent keepalive interval for wireguard tunnels
+    SetWireguardKeepalive(ResponseTx<(), settings::Error>, Option<u16>),
+    /// Set the local address that the tunnel socket is bound to
+    SetTunnelBindAddress(ResponseTx<(), settings::Error>, Option<IpAddr>),
     /// Set allowed IPs for wireguard tunnels
     SetWireguardAllowedIps(ResponseTx<(), settings::Error>, Constraint<AllowedIps>),
+    /// Check allowed IPs for conflicts with the local network and DNS settings
+    ValidateWireguardAllowedIps(oneshot::Sender<Vec<AllowedIpsWarning>>, AllowedIps),
     /// Set automatic key rotation interval for wireguard tunnels
     SetWireguardRotationInterval(ResponseTx<(), settings::Error>, Option<RotationInterval>),
     /// Get the daemon settings
     GetSettings(oneshot::Sender<Settings>),
     /// Reset all daemon settings to the defaults
     ResetSettings(ResponseTx<(), settings::Error>),
+    /// Check whether the settings file on disk can be loaded, without applying it
+    VerifySettingsIntegrity(oneshot::Sender<SettingsIntegrity>),
+    /// Return the settings migration steps that were applied when the daemon started
+    GetAppliedSettingsMigrations(oneshot::Sender<Vec<SettingsMigration>>),
+    /// Lock the given settings with a password
+    LockSettings(
+        ResponseTx<(), settings::Error>,
+        Vec<LockableSetting>,
+        String,
+    ),
+    /// Unlock all settings using a password
+    UnlockSettings(ResponseTx<(), settings::Error>, String),
+    /// Return which settings are locked
+    GetSettingsLock(oneshot::Sender<SettingsLock>),
+    /// Save the current relay, obfuscation, and tunnel settings as a named profile
+    SaveProfile(ResponseTx<(), Error>, String),
+    /// Apply the settings in a named profile, and optionally reconnect
+    ApplyProfile(ResponseTx<(), Error>, String, bool),
+    /// Return all saved profiles
+    ListProfiles(oneshot::Sender<ProfilesSettings>),
+    /// Delete a named profile
+    DeleteProfile(ResponseTx<(), Error>, String),
+    /// Encode the relay and obfuscation settings as a shareable link
+    ExportRelayLink(ResponseTx<String, Error>),
+    /// Apply the relay and obfuscation settings in a shareable link
+    ImportRelayLink(ResponseTx<(), Error>, String),
+    /// Return the disk usage of the files cached by the daemon
+    GetCacheUsage(oneshot::Sender<CacheUsage>),
+    /// Remove the given kinds of cached files
+    ClearCache(ResponseTx<(), Error>, Vec<CacheKind>),
+    /// Return the API requests that have been sent but have not completed yet
+    GetInflightApiRequests(oneshot::Sender<Vec<InflightApiRequest>>),
+    /// Return the response to the most recent API request that failed with an unexpected status
+    GetLastApiError(oneshot::Sender<Option<ApiErrorResponse>>),
+    /// Set the maximum number of API requests that may be in flight at once
+    SetMaxConcurrentApiRequests(ResponseTx<(), settings::Error>, u32),
+    /// Set the server name to send when connecting to the API, instead of the API hostname
+    SetApiSniHostname(ResponseTx<(), settings::Error>, Option<String>),
+    /// Return the maximum number of concurrent API requests, and the number of queued requests
+    GetApiRequestLimit(oneshot::Sender<ApiRequestLimit>),
+    /// Set the bandwidth limit for relay list and API address downloads, and whether to skip
+    /// automatic relay list updates
+    SetRelayListDownloadSettings(ResponseTx<(), settings::Error>, RelayListDownloadSettings),
+    /// Return the address that is used to reach the API, and where it comes from
+    GetApiAddressInfo(oneshot::Sender<ApiAddressInfo>),
+    /// Drop all pooled API connections, so that subsequent requests establish new connections
+    ResetApiConnections(oneshot::Sender<()>),
+    /// Discard state that may be stale after moving to another network: re-resolve the API
+    /// address, drop API connections, refresh the relay list, and reconnect the tunnel if it is
+    /// connected
+    HandleNetworkChange(ResponseTx<(), Error>),
+    /// Compare the system clock with the time reported by the API
+    CheckSystemClock(ResponseTx<ClockCheck, mullvad_api::rest::Error>),
     /// Generate new wireguard key
     RotateWireguardKey(ResponseTx<(), Error>),
     /// Return a public key of the currently set wireguard private key, if there is one
     GetWireguardKey(ResponseTx<Option<PublicKey>, Error>),
+    /// Return the age of the current wireguard key, or `None` if logged out
+    GetWireguardKeyAge(oneshot::Sender<Option<WireguardKeyAge>>),
+    /// Return diagnostics for the last tunnel that timed out before becoming functional
+    GetLastHandshakeDiagnostics(oneshot::Sender<Option<HandshakeDiagnostics>>),
+    /// Return the MTU and per-packet overhead of the tunnel, if it is connected
+    GetTunnelMtuInfo(oneshot::Sender<Option<TunnelMtuInfo>>),
+    /// Return the cryptographic details of the tunnel, if it is connected
+    GetTunnelCryptoInfo(oneshot::Sender<Option<TunnelCryptoInfo>>),
+    /// Return the allowed IPs configured on the tunnel device, if the tunnel is connected
+    GetAppliedAllowedIps(oneshot::Sender<Option<Vec<ipnetwork::IpNetwork>>>),
+    /// Return the most recent output of the daemon logs, optionally redacted
+    CollectLogs(ResponseTx<String, Error>, bool),
+    /// Set the log filter, a `RUST_LOG` string
+    SetLogFilter(ResponseTx<(), Error>, String),
     /// Create custom list
     CreateCustomList(
         ResponseTx<mullvad_types::custom_list::Id, Error>,
@@ -349,8 +617,20 @@ pub enum DaemonCommand {
     ClearCustomApiAccessMethods(ResponseTx<(), Error>),
     /// Get the currently used API access method
     GetCurrentAccessMethod(ResponseTx<AccessMethodSetting, Error>),
+    /// Get the API access method which carried the most recent successful API request
+    GetLastSuccessfulAccessMethod(ResponseTx<Option<LastSuccessfulAccessMethod>, Error>),
     /// Test an API access method
     TestApiAccessMethodById(ResponseTx<bool, Error>, mullvad_types::access_method::Id),
+    /// Test all enabled API access methods, returning whether each one could reach the API and
+    /// how long its test took
+    TestAllApiAccessMethods(ResponseTx<Vec<AccessMethodTestResult>, Error>),
+    /// Export all custom API access methods as JSON, optionally including proxy credentials
+    ExportApiAccessMethods(ResponseTx<String, Error>, bool),
+    /// Import custom API access methods from JSON
+    ImportApiAccessMethods(ResponseTx<(), Error>, String),
+    /// Test enabled API access methods one at a time, in priority order, until one can reach
+    /// the API. If `bool` is true, the first working access method is set as the active one
+    FindWorkingApiAccessMethod(ResponseTx<AccessMethodSearch, Error>, bool),
     /// Test a custom API access method
     TestCustomApiAccessMethod(
         ResponseTx<bool, Error>,
@@ -358,6 +638,8 @@ pub enum DaemonCommand {
     ),
     /// Get information about the currently running and latest app versions
     GetVersionInfo(oneshot::Sender<Result<AppVersionInfo, Error>>),
+    /// Get the download size in bytes of the suggested upgrade, if any
+    GetUpgradeDownloadSize(ResponseTx<Option<u64>, Error>),
     /// Return whether the daemon is performing post-upgrade tasks
     IsPerformingPostUpgrade(oneshot::Sender<bool>),
     /// Get current version of the app
@@ -365,6 +647,9 @@ pub enum DaemonCommand {
     /// Remove settings and clear the cache
     #[cfg(not(target_os = "android"))]
     FactoryReset(ResponseTx<(), Error>),
+    /// Return what a factory reset would remove
+    #[cfg(not(target_os = "android"))]
+    GetFactoryResetPreview(ResponseTx<FactoryResetPreview, Error>),
     /// Return whether split tunneling is available
     #[cfg(any(target_os = "linux", target_os = "windows"))]
     SplitTunnelIsSupported(oneshot::Sender<bool>),
@@ -373,16 +658,17 @@ pub enum DaemonCommand {
     GetSplitTunnelProcesses(ResponseTx<Vec<i32>, split_tunnel::Error>),
     /// Exclude traffic of a process (PID) from the tunnel
     #[cfg(target_os = "linux")]
-    AddSplitTunnelProcess(ResponseTx<(), split_tunnel::Error>, i32),
+    AddSplitTunnelProcess(ResponseTx<(), Error>, i32),
     /// Remove process (PID) from list of processes excluded from the tunnel
     #[cfg(target_os = "linux")]
-    RemoveSplitTunnelProcess(ResponseTx<(), split_tunnel::Error>, i32),
+    RemoveSplitTunnelProcess(ResponseTx<(), Error>, i32),
     /// Clear list of processes excluded from the tunnel
     #[cfg(target_os = "linux")]
-    ClearSplitTunnelProcesses(ResponseTx<(), split_tunnel::Error>),
-    /// Exclude traffic of an application from the tunnel
+    ClearSplitTunnelProcesses(ResponseTx<(), Error>),
+    /// Exclude traffic of an application from the tunnel. If any subnets are given, only traffic
+    /// to those subnets is excluded.
     #[cfg(any(target_os = "windows", target_os = "android", target_os = "macos"))]
-    AddSplitTunnelApp(ResponseTx<(), Error>, SplitApp),
+    AddSplitTunnelApp(ResponseTx<(), Error>, SplitApp, Vec<ipnetwork::IpNetwork>),
     /// Remove application from list of apps to exclude from the tunnel
     #[cfg(any(target_os = "windows", target_os = "android", target_os = "macos"))]
     RemoveSplitTunnelApp(ResponseTx<(), Error>, SplitApp),
@@ -400,9 +686,38 @@ pub enum DaemonCommand {
     CheckVolumes(ResponseTx<(), Error>),
     /// Register settings for WireGuard obfuscator
     SetObfuscationSettings(ResponseTx<(), settings::Error>, ObfuscationSettings),
+    /// Set how eagerly automatic obfuscation is used
+    SetObfuscationAutoSensitivity(ResponseTx<(), settings::Error>, ObfuscationSensitivity),
+    /// Return the obfuscation used by the most recent tunnel
+    GetObfuscationState(oneshot::Sender<ObfuscationState>),
+    /// Return the most recent attempts to establish a tunnel, oldest first
+    GetConnectionAttemptHistory(oneshot::Sender<Vec<ConnectionAttempt>>),
+    /// Return whether UDP and TCP appear to be reachable, judging by recent connection attempts
+    GetNetworkProbeResults(oneshot::Sender<NetworkProbeResults>),
     /// Saves the target tunnel state and enters a blocking state. The state is restored
     /// upon restart.
     PrepareRestart(bool),
+    /// Return why the daemon was last restarted, if known
+    GetLastRestartReason(oneshot::Sender<Option<RestartReason>>),
+    /// Return the active notifications that have not been acknowledged
+    GetNotifications(oneshot::Sender<Vec<Notification>>),
+    /// Dismiss the notification with the given ID, so that it is not returned again
+    AcknowledgeNotification(ResponseTx<(), Error>, String),
+    /// Return usage statistics accumulated across tunnel sessions
+    GetUsageStats(oneshot::Sender<UsageStats>),
+    /// Forget the accumulated usage statistics
+    ResetUsageStats(oneshot::Sender<()>),
+    /// Return the firewall backend and any known problems with it
+    GetFirewallInfo(oneshot::Sender<FirewallInfo>),
+    /// Simulate lost and delayed API connection attempts
+    #[cfg(feature = "network-simulation")]
+    SetSimulatedNetworkConditions(
+        oneshot::Sender<()>,
+        mullvad_types::network_simulation::SimulatedNetworkConditions,
+    ),
+    /// Return the memory and handle usage of the daemon
+    #[cfg(feature = "diagnostics")]
+    GetResourceUsage(oneshot::Sender<mullvad_types::resource_usage::ResourceUsage>),
     /// Causes a socket to bypass the tunnel. This has no effect when connected. It is only used
     /// to bypass the tunnel in blocking states.
     #[cfg(target_os = "android")]
@@ -417,6 +732,15 @@ pub enum DaemonCommand {
     ApplyJsonSettings(ResponseTx<(), settings::patch::Error>, String),
     /// Return a JSON blob containing all overridable settings, if there are any
     ExportJsonSettings(ResponseTx<String, settings::patch::Error>),
+    /// Store a copy of the settings in a snapshot with the given label
+    CreateSettingsSnapshot(
+        ResponseTx<SettingsSnapshot, settings::snapshot::Error>,
+        String,
+    ),
+    /// Apply the settings snapshot with the given label
+    RestoreSettingsSnapshot(ResponseTx<(), settings::snapshot::Error>, String),
+    /// Return all stored settings snapshots
+    ListSettingsSnapshots(ResponseTx<Vec<SettingsSnapshot>, settings::snapshot::Error>),
     /// Request the current feature indicators.
     GetFeatureIndicators(oneshot::Sender<FeatureIndicators>),
     // Updates the default (initial) country selection that the user will see when starting the
@@ -451,10 +775,17 @@ pub enum DaemonCommand {
     ///
     /// If an upgrade had previously been started but not completed the daemon should continue the upgrade process at the appropriate step. The client need not be notified about this detail.
     AppUpgrade(ResponseTx<(), version::Error>),
+    /// Download and verify the suggested upgrade without announcing it, so that a later
+    /// [DaemonCommand::AppUpgrade] does not have to download it.
+    PrefetchUpdate(ResponseTx<(), version::Error>),
     /// Prompt the daemon to abort the current upgrade.
     AppUpgradeAbort(ResponseTx<(), version::Error>),
     /// Return the storage path for the installers during in-app upgrades.
     GetAppUpgradeCacheDir(ResponseTx<PathBuf, version::Error>),
+    /// Return the installer downloads that are in progress.
+    ListActiveDownloads(ResponseTx<Vec<ActiveDownload>, version::Error>),
+    /// Abort an installer download that is in progress.
+    CancelDownload(ResponseTx<(), version::Error>, u32),
 }
 
 /// All events that can happen in the daemon. Sent from various threads and exposed interfaces.
@@ -486,12 +817,28 @@ pub(crate) enum InternalDaemonEvent {
     ExcludedPathsEvent(ExcludedPathsUpdate, oneshot::Sender<Result<(), Error>>),
     /// A network leak was detected.
     LeakDetected(LeakInfo),
+    /// The captive portal check before connecting has made progress.
+    CaptivePortalEvent(captive_portal::CaptivePortalEvent),
+    /// A boundary of the connect schedule was crossed.
+    ConnectScheduleEvent(connect_schedule::ScheduleEvent),
+    /// The network that the device is connected to was detected or changed.
+    CurrentNetworkChanged(CurrentNetwork),
+    /// The tunnel has been paused for the requested duration.
+    PauseEnded,
+    /// An attempt of the running obfuscation benchmark has made progress.
+    ObfuscationBenchmarkEvent(obfuscation_benchmark::BenchmarkEvent),
+    /// The usage statistics should be saved.
+    FlushUsageStats,
 }
 
 #[cfg(any(target_os = "windows", target_os = "android", target_os = "macos"))]
 pub(crate) enum ExcludedPathsUpdate {
     SetState(bool),
-    SetPaths(HashSet<SplitApp>),
+    /// Set the excluded apps and the subnets that they are restricted to
+    SetPaths(
+        HashSet<SplitApp>,
+        HashMap<SplitApp, Vec<ipnetwork::IpNetwork>>,
+    ),
 }
 
 impl From<TunnelStateTransition> for InternalDaemonEvent {
@@ -660,11 +1007,27 @@ where
 pub struct Daemon {
     tunnel_state: TunnelState,
     target_state: PersistentTargetState,
+    shutdown_recorder: ShutdownRecorder,
+    usage_stats: UsageStatsTracker,
+    notifications: NotificationTracker,
     #[cfg(target_os = "linux")]
     exclude_pids: split_tunnel::PidManager,
     rx: mpsc::UnboundedReceiver<InternalDaemonEvent>,
     tx: DaemonEventSender,
     reconnection_job: Option<AbortHandle>,
+    /// Ends the current pause of the tunnel
+    pause: pause::TunnelPause,
+    obfuscation_benchmark: Option<obfuscation_benchmark::Benchmark>,
+    /// Used to measure the throughput of obfuscation benchmarks. It is separate from the location
+    /// handler, since that resets its requests on every tunnel state change.
+    benchmark_rest_service: mullvad_api::rest::RequestServiceHandle,
+    captive_portal_checker: captive_portal::CaptivePortalChecker,
+    /// Why the daemon last disconnected on its own. Cleared when a client changes the target state.
+    disconnect_reason: Option<DisconnectReason>,
+    /// Whether the account expired while connected with
+    /// [`AccountExpiryAction::StayConnectedUntilDrop`]. Traffic is blocked as soon as the tunnel
+    /// is no longer connected.
+    block_on_tunnel_drop: bool,
     management_interface: ManagementInterfaceServer,
     migration_complete: migrations::MigrationComplete,
     settings: SettingsPersister,
@@ -678,13 +1041,27 @@ pub struct Daemon {
     relay_selector: RelaySelector,
     relay_list_updater: RelayListUpdaterHandle,
     parameters_generator: tunnel::ParametersGenerator,
+    /// The most recent connectivity reported by the offline monitor
+    connectivity: watch::Receiver<Connectivity>,
     shutdown_tasks: Vec<Pin<Box<dyn Future<Output = ()> + Send + Sync>>>,
     tunnel_state_machine_handle: TunnelStateMachineHandle,
     #[cfg(target_os = "windows")]
     volume_update_tx: mpsc::UnboundedSender<()>,
     location_handler: GeoIpHandler,
+    route_manager: RouteManagerHandle,
     leak_checker: LeakChecker,
+    connection_attempts: ConnectionAttemptHistory,
+    /// The network that the device is connected to, once it has been detected
+    current_network: Option<CurrentNetwork>,
+    /// Whether the current network is one of the trusted networks
+    on_trusted_network: bool,
+    /// Key that SSIDs of trusted networks are salted with
+    ssid_hash_key: trusted_network::SsidHashKeyFile,
     cache_dir: PathBuf,
+    log_dir: Option<PathBuf>,
+    applied_settings_migrations: Vec<SettingsMigration>,
+    unlock_backoff: settings::lock::UnlockBackoffTracker,
+    log_handle: logging::LogHandle,
 }
 pub struct DaemonConfig {
     pub log_dir: Option<PathBuf>,
@@ -703,6 +1080,10 @@ impl Daemon {
         config: DaemonConfig,
         daemon_command_channel: DaemonCommandChannel,
     ) -> Result<Self, Error> {
+        if config.endpoint.address.len() > 1 {
+            return Err(Error::MultipleApiAddresses);
+        }
+
         #[cfg(target_os = "macos")]
         tokio::spawn(macos::allow_incoming_connections());
         #[cfg(target_os = "macos")]
@@ -714,7 +1095,7 @@ impl Daemon {
             command_sender,
             config.rpc_socket_path,
             app_upgrade_broadcast.clone(),
-            config.log_handle,
+            config.log_handle.clone(),
         )
         .map_err(Error::ManagementInterfaceError)?;
 
@@ -731,7 +1112,7 @@ impl Daemon {
             .map_err(|_| Error::DaemonUnavailable)?;
 
         mullvad_api::proxy::ApiConnectionMode::try_delete_cache(&config.cache_dir).await;
-        let api_runtime = mullvad_api::Runtime::with_cache(
+        let mut api_runtime = mullvad_api::Runtime::with_cache(
             &config.endpoint,
             &config.cache_dir,
             true,
@@ -744,14 +1125,14 @@ impl Daemon {
         let api_availability = api_runtime.availability_handle();
         api_availability.suspend();
 
-        let migration_data = migrations::migrate_all(&config.cache_dir, &config.settings_dir)
+        let migration_outcome = migrations::migrate_all(&config.cache_dir, &config.settings_dir)
             .await
             .unwrap_or_else(|error| {
                 log::error!(
                     "{}",
                     error.display_chain_with_msg("Failed to migrate settings or cache")
                 );
-                None
+                migrations::MigrationOutcome::default()
             });
 
         let settings_event_listener = management_interface.notifier().clone();
@@ -762,6 +1143,8 @@ impl Daemon {
             settings_event_listener.notify_settings(settings.to_owned());
         });
 
+        api_availability.set_low_power(settings.low_power_mode);
+
         let initial_relay_list = parse_relays_from_file(&config.cache_dir, &config.resource_dir)
             .inspect_err(|err| log::error!("{err}"))
             .ok();
@@ -799,7 +1182,14 @@ impl Daemon {
             .await
             .map_err(Error::ApiConnectionModeError)?;
 
+        api_runtime.set_sni_hostname(settings.api_sni_hostname.clone());
         let api_handle = api_runtime.mullvad_rest_handle(access_mode_provider);
+        api_handle
+            .service()
+            .set_max_concurrent_requests(settings.max_concurrent_api_requests);
+        api_handle
+            .service()
+            .set_background_download_limit(settings.relay_list_download.bandwidth_limit);
 
         // Continually update the API IP
         tokio::spawn(api_address_updater::run_api_address_fetcher(
@@ -818,7 +1208,7 @@ impl Daemon {
             });
         });
 
-        let migration_complete = if let Some(migration_data) = migration_data {
+        let migration_complete = if let Some(migration_data) = migration_outcome.data {
             migrations::migrate_device(
                 migration_data,
                 api_handle.clone(),
@@ -854,6 +1244,14 @@ impl Daemon {
         } else {
             PersistentTargetState::new(&config.cache_dir).await
         };
+        let shutdown_recorder = ShutdownRecorder::new(&config.cache_dir).await;
+        let usage_stats = UsageStatsTracker::new(&config.settings_dir).await;
+        let ssid_hash_key = trusted_network::SsidHashKeyFile::load(&config.settings_dir).await;
+        let notifications = NotificationTracker::new(
+            &config.settings_dir,
+            matches!(data, device::PrivateDeviceState::Revoked),
+        )
+        .await;
 
         #[cfg(any(target_os = "windows", target_os = "android", target_os = "macos"))]
         let exclude_paths = if settings.split_tunnel.enable_exclusions {
@@ -868,6 +1266,9 @@ impl Daemon {
             vec![]
         };
 
+        #[cfg(target_os = "macos")]
+        let exclude_subnets = tunnel_allowed_subnets(&settings.split_tunnel.allowed_subnets);
+
         #[cfg(target_os = "linux")]
         let split_tunneling_pid_manager = split_tunnel::PidManager::default();
 
@@ -923,9 +1324,13 @@ impl Daemon {
                 reset_firewall: *target_state != TargetState::Secured,
                 #[cfg(any(target_os = "windows", target_os = "android", target_os = "macos"))]
                 exclude_paths,
+                #[cfg(target_os = "macos")]
+                exclude_subnets,
+                connect_timeout: settings.connect_timeout,
+                max_reconnect_attempts: settings.max_reconnect_attempts,
             },
             parameters_generator.clone(),
-            config.log_dir,
+            config.log_dir.clone(),
             config.resource_dir.clone(),
             internal_event_tx.to_specialized_sender(),
             offline_state_tx,
@@ -947,7 +1352,16 @@ impl Daemon {
         .await
         .map_err(Error::TunnelError)?;
 
-        api::forward_offline_state(api_availability.clone(), offline_state_rx);
+        let connectivity = api::forward_offline_state(
+            api_availability.clone(),
+            api_handle.service(),
+            offline_state_rx,
+        );
+
+        let fallback_listener = management_interface.notifier().clone();
+        relay_selector.set_fallback_listener(move |fallback| {
+            fallback_listener.notify_relay_selection_fallback(fallback);
+        });
 
         let relay_list_listener = management_interface.notifier().clone();
         let internal_event_tx_clone = internal_event_tx.clone();
@@ -966,6 +1380,7 @@ impl Daemon {
             settings.relay_overrides.clone(),
             on_relay_list_update,
             initial_relay_list,
+            settings.relay_list_download.metered_network,
         );
 
         // Notify the relay list updater when new relay IP overrides are available.
@@ -974,8 +1389,12 @@ impl Daemon {
             // Notify relay selector of changes to the settings/selector config
             let mut relay_list_updater = relay_list_updater_handle.clone();
             let overrides = settings.relay_overrides.clone();
+            let metered_network = settings.relay_list_download.metered_network;
             tokio::spawn(async move {
                 relay_list_updater.update_overrides(overrides).await;
+                relay_list_updater
+                    .set_metered_network(metered_network)
+                    .await;
             });
         });
 
@@ -1009,26 +1428,63 @@ impl Daemon {
             config.cache_dir.clone(),
             internal_event_tx.to_specialized_sender(),
             settings.show_beta_releases,
+            settings.relay_list_download.metered_network,
             #[cfg(not(target_os = "android"))]
             rollout,
             app_upgrade_broadcast,
         );
 
-        // Attempt to download a fresh relay list
-        relay_list_updater.update().await;
+        #[cfg(in_app_upgrade)]
+        {
+            let version_handle = version_handle.clone();
+            settings.register_change_listener(move |settings| {
+                let metered_network = settings.relay_list_download.metered_network;
+                if let Err(error) = version_handle.set_metered_network(metered_network) {
+                    log::error!("Failed to set whether the network is metered: {error}");
+                }
+            });
+        }
+
+        // Attempt to download a fresh relay list, unless the network is metered
+        if !settings.relay_list_download.metered_network {
+            relay_list_updater.update().await;
+        }
 
         let location_handler = GeoIpHandler::new(
             api_runtime.rest_handle(
                 #[cfg(not(target_os = "android"))]
                 mullvad_api::DefaultDnsResolver,
                 #[cfg(target_os = "android")]
-                android_dns::AndroidDnsResolver::new(connectivity_listener),
+                android_dns::AndroidDnsResolver::new(connectivity_listener.clone()),
             ),
             internal_event_tx.clone().to_specialized_sender(),
         );
+        let benchmark_rest_service = api_runtime.rest_handle(
+            #[cfg(not(target_os = "android"))]
+            mullvad_api::DefaultDnsResolver,
+            #[cfg(target_os = "android")]
+            android_dns::AndroidDnsResolver::new(connectivity_listener),
+        );
+
+        usage_stats::spawn_flush_timer(internal_event_tx.to_specialized_sender());
+
+        let schedule_handle = connect_schedule::spawn(
+            settings.connect_schedule.clone(),
+            internal_event_tx.to_specialized_sender(),
+        );
+        settings.register_change_listener(move |settings| {
+            schedule_handle.set_schedule(&settings.connect_schedule);
+        });
+
+        #[cfg(not(target_os = "android"))]
+        trusted_network::spawn(
+            route_manager.clone(),
+            ssid_hash_key.key().clone(),
+            internal_event_tx.to_specialized_sender(),
+        );
 
         let leak_checker = {
-            let mut leak_checker = LeakChecker::new(route_manager);
+            let mut leak_checker = LeakChecker::new(route_manager.clone());
             let internal_event_tx = internal_event_tx.clone();
             leak_checker.add_leak_callback(move |info| {
                 internal_event_tx
@@ -1037,19 +1493,30 @@ impl Daemon {
             });
             leak_checker
         };
+        let captive_portal_checker =
+            captive_portal::CaptivePortalChecker::new(internal_event_tx.to_specialized_sender());
 
         let daemon = Daemon {
             tunnel_state: TunnelState::Disconnected {
                 location: None,
                 #[cfg(not(target_os = "android"))]
                 locked_down: settings.lockdown_mode,
+                disconnect_reason: None,
             },
             target_state,
+            shutdown_recorder,
+            usage_stats,
+            notifications,
             #[cfg(target_os = "linux")]
             exclude_pids: split_tunneling_pid_manager,
             rx: internal_event_rx,
             tx: internal_event_tx,
             reconnection_job: None,
+            pause: pause::TunnelPause::default(),
+            obfuscation_benchmark: None,
+            benchmark_rest_service,
+            captive_portal_checker,
+            disconnect_reason: None,
             management_interface,
             migration_complete,
             settings,
@@ -1063,13 +1530,24 @@ impl Daemon {
             relay_selector,
             relay_list_updater,
             parameters_generator,
+            connectivity,
             shutdown_tasks: vec![],
             tunnel_state_machine_handle,
             #[cfg(target_os = "windows")]
             volume_update_tx,
             location_handler,
+            route_manager,
             leak_checker,
+            connection_attempts: ConnectionAttemptHistory::default(),
+            current_network: None,
+            on_trusted_network: false,
+            ssid_hash_key,
+            block_on_tunnel_drop: false,
             cache_dir: config.cache_dir,
+            log_dir: config.log_dir,
+            applied_settings_migrations: migration_outcome.applied,
+            unlock_backoff: settings::lock::UnlockBackoffTracker::default(),
+            log_handle: config.log_handle,
         };
 
         api_availability.unsuspend();
@@ -1099,6 +1577,9 @@ impl Daemon {
 
     fn handle_initial_target_state(&mut self) {
         match self.target_state.to_strict() {
+            either::Either::Right(_) if self.settings.captive_portal_check => {
+                self.start_captive_portal_check();
+            }
             either::Either::Right(state) => {
                 self.send_tunnel_command(Self::secured_state_to_tunnel_command(state));
             }
@@ -1149,6 +1630,8 @@ impl Daemon {
             api_runtime,
             tunnel_state_machine_handle,
             target_state,
+            shutdown_recorder,
+            usage_stats,
             account_manager,
             ..
         } = self;
@@ -1158,6 +1641,10 @@ impl Daemon {
         }
 
         target_state.finalize().await;
+        shutdown_recorder.finalize().await;
+        usage_stats
+            .finalize(tunnel_state_machine_handle.tunnel_traffic())
+            .await;
         account_manager.shutdown().await;
 
         tunnel_state_machine_handle.try_join().await;
@@ -1189,7 +1676,7 @@ impl Daemon {
                 should_stop = true;
             }
             NewAppVersionInfo(app_version_info) => {
-                self.handle_new_app_version_info(app_version_info);
+                self.handle_new_app_version_info(app_version_info).await;
             }
             DeviceEvent(event) => self.handle_device_event(event).await,
             AccessMethodEvent {
@@ -1208,6 +1695,22 @@ impl Daemon {
                 log::warn!("{leak_info:?}");
                 self.handle_leak_event(leak_info)
             }
+            CaptivePortalEvent(event) => self.handle_captive_portal_event(event),
+            ConnectScheduleEvent(event) => self.handle_connect_schedule_event(event).await,
+            CurrentNetworkChanged(network) => self.handle_current_network_changed(network).await,
+            PauseEnded => {
+                if self.end_pause().await {
+                    log::info!("Reconnecting since the pause has ended");
+                }
+            }
+            ObfuscationBenchmarkEvent(event) => {
+                self.handle_obfuscation_benchmark_event(event).await
+            }
+            FlushUsageStats => {
+                self.usage_stats
+                    .flush(self.tunnel_state_machine_handle.tunnel_traffic())
+                    .await
+            }
         }
         should_stop
     }
@@ -1228,9 +1731,13 @@ impl Daemon {
             TunnelStateTransition::Disconnected { locked_down } => TunnelState::Disconnected {
                 location: None,
                 locked_down,
+                disconnect_reason: self.disconnect_reason,
             },
             #[cfg(target_os = "android")]
-            TunnelStateTransition::Disconnected {} => TunnelState::Disconnected { location: None },
+            TunnelStateTransition::Disconnected {} => TunnelState::Disconnected {
+                location: None,
+                disconnect_reason: self.disconnect_reason,
+            },
             TunnelStateTransition::Connecting(endpoint) => {
                 let feature_indicators = compute_feature_indicators(
                     self.settings.settings(),
@@ -1275,6 +1782,33 @@ impl Daemon {
 
         log::debug!("New tunnel state: {:?}", tunnel_state);
 
+        match &tunnel_state {
+            TunnelState::Connecting {
+                endpoint, location, ..
+            } => self
+                .connection_attempts
+                .start(endpoint.clone(), location.clone()),
+            TunnelState::Connected { .. } => self
+                .connection_attempts
+                .finish(ConnectionAttemptOutcome::Connected),
+            TunnelState::Disconnecting(_) | TunnelState::Disconnected { .. } => self
+                .connection_attempts
+                .finish(ConnectionAttemptOutcome::Aborted),
+            TunnelState::Error(error_state) => {
+                self.connection_attempts
+                    .finish(ConnectionAttemptOutcome::Failed(
+                        error_state.cause().to_string(),
+                    ))
+            }
+        }
+
+        self.usage_stats
+            .on_tunnel_state(
+                &tunnel_state,
+                self.tunnel_state_machine_handle.tunnel_traffic(),
+            )
+            .await;
+
         match tunnel_state {
             TunnelState::Disconnected { .. } => {
                 self.api_handle.availability.reset_inactivity_timer();
@@ -1284,6 +1818,18 @@ impl Daemon {
             }
         }
 
+        if self.block_on_tunnel_drop && !tunnel_state.is_connected() {
+            self.block_on_tunnel_drop = false;
+            if matches!(tunnel_state, TunnelState::Connecting { .. }) {
+                log::debug!(
+                    "Entering blocking state since the tunnel dropped and the account is out of time"
+                );
+                self.send_tunnel_command(TunnelCommand::Block(ErrorStateCause::AuthFailed(Some(
+                    AuthFailed::ExpiredAccount.as_str().to_string(),
+                ))));
+            }
+        }
+
         match &tunnel_state {
             TunnelState::Connecting { .. } => {
                 log::debug!("Settings: {}", self.settings.summary());
@@ -1315,6 +1861,7 @@ impl Daemon {
             .notifier()
             .notify_new_state(tunnel_state);
         self.fetch_am_i_mullvad();
+        self.update_obfuscation_benchmark().await;
     }
 
     /// Get the geographical location from am.i.mullvad.net. When it arrives,
@@ -1370,6 +1917,7 @@ impl Daemon {
                 ref mut location,
                 #[cfg(not(target_os = "android"))]
                     locked_down: _,
+                disconnect_reason: _,
             } => *location = Some(fetched_location),
             TunnelState::Connected {
                 ref mut location, ..
@@ -1480,16 +2028,43 @@ impl Daemon {
         match command {
             SetTargetState(tx, state) => self.on_set_target_state(tx, state).await,
             Reconnect(tx) => self.on_reconnect(tx),
+            PauseTunnel(tx, duration) => self.on_pause_tunnel(tx, duration).await,
+            ResumeTunnel(tx) => self.on_resume_tunnel(tx).await,
+            ConnectToRelayOnce(tx, hostname) => self.on_connect_to_relay_once(tx, hostname).await,
             GetState(tx) => self.on_get_state(tx),
+            IsKillSwitchActive(tx) => self.on_is_kill_switch_active(tx),
             CreateNewAccount(tx) => self.on_create_new_account(tx),
             GetAccountData(tx, account_number) => self.on_get_account_data(tx, account_number),
             GetWwwAuthToken(tx) => self.on_get_www_auth_token(tx).await,
             SubmitVoucher(tx, voucher) => self.on_submit_voucher(tx, voucher),
             GetRelayLocations(tx) => self.on_get_relay_locations(tx),
+            GetRelayLocationsSummary(tx) => self.on_get_relay_locations_summary(tx),
+            GetRelaysInCity(tx, country_code, city_code) => {
+                self.on_get_relays_in_city(tx, country_code, city_code)
+            }
+            CheckRelayFeatureSupport(tx, relay_settings) => {
+                self.on_check_relay_feature_support(tx, relay_settings)
+            }
+            RelayListSelfTest(tx) => self.on_relay_list_self_test(tx),
+            GetRelayListUpdateInfo(tx) => self.on_get_relay_list_update_info(tx),
+            TestRelayPort(tx, hostname, port, protocol) => {
+                self.on_test_relay_port(tx, hostname, port, protocol)
+            }
+            GetRelayTransparencyInfo(tx, hostname) => {
+                self.on_get_relay_transparency_info(tx, hostname)
+            }
+            BenchmarkObfuscation(tx, hostname, methods) => {
+                self.on_benchmark_obfuscation(tx, hostname, methods).await
+            }
             UpdateRelayLocations => self.on_update_relay_locations().await,
             UpdateDefaultLocationCountry(tx) => self.on_update_default_location(tx).await,
             LoginAccount(tx, account_number) => self.on_login_account(tx, account_number),
+            SwitchAccount(tx, account_number) => self.on_switch_account(tx, account_number),
+            GetSavedAccounts(tx) => self.on_get_saved_accounts(tx),
             LogoutAccount(tx) => self.on_logout_account(tx),
+            GetLoginBackoff(tx) => self.on_get_login_backoff(tx),
+            ClearLoginBackoff(tx) => self.on_clear_login_backoff(tx),
+            Reauthenticate(tx) => self.on_reauthenticate(tx),
             GetDevice(tx) => self.on_get_device(tx),
             UpdateDevice(tx) => self.on_update_device(tx),
             ListDevices(tx, account_number) => self.on_list_devices(tx, account_number),
@@ -1501,11 +2076,31 @@ impl Daemon {
             SetRelaySettings(tx, update) => self.on_set_relay_settings(tx, update).await,
             SetAllowLan(tx, allow_lan) => self.on_set_allow_lan(tx, allow_lan).await,
             SetShowBetaReleases(tx, enabled) => self.on_set_show_beta_releases(tx, enabled).await,
+            SetRandomizeRelayEachConnect(tx, enabled) => {
+                self.on_set_randomize_relay_each_connect(tx, enabled).await
+            }
+            SetLowPowerMode(tx, enabled) => self.on_set_low_power_mode(tx, enabled).await,
+            SetCaptivePortalCheck(tx, enabled) => {
+                self.on_set_captive_portal_check(tx, enabled).await
+            }
+            SetConnectTimeout(tx, timeout) => self.on_set_connect_timeout(tx, timeout).await,
+            SetMaxReconnectAttempts(tx, max_attempts) => {
+                self.on_set_max_reconnect_attempts(tx, max_attempts).await
+            }
+            DismissCaptivePortal(tx) => self.on_dismiss_captive_portal(tx),
             #[cfg(not(target_os = "android"))]
             SetLockdownMode(tx, lockdown_mode) => {
                 self.on_set_lockdown_mode(tx, lockdown_mode).await
             }
             SetAutoConnect(tx, auto_connect) => self.on_set_auto_connect(tx, auto_connect).await,
+            SetConnectSchedule(tx, schedule) => self.on_set_connect_schedule(tx, schedule).await,
+            SetTrustedNetworks(tx, trusted_networks) => {
+                self.on_set_trusted_networks(tx, trusted_networks).await
+            }
+            AddTrustedSsid(tx, ssid) => {
+                let network = TrustedNetwork::ssid(&ssid, self.ssid_hash_key.key());
+                self.on_add_trusted_network(tx, network).await
+            }
             SetEnableIpv6(tx, enable_ipv6) => self.on_set_enable_ipv6(tx, enable_ipv6).await,
             SetEnableRecents(tx, enable_recents) => {
                 self.on_set_enable_recents(tx, enable_recents).await
@@ -1514,6 +2109,7 @@ impl Daemon {
                 self.on_set_quantum_resistant_tunnel(tx, quantum_resistant_state)
                     .await
             }
+            SetOnAccountExpiry(tx, action) => self.on_set_on_account_expiry(tx, action).await,
             #[cfg(daita)]
             SetEnableDaita(tx, value) => self.on_set_daita_enabled(tx, value).await,
             #[cfg(daita)]
@@ -1521,25 +2117,77 @@ impl Daemon {
                 self.on_set_daita_use_multihop_if_necessary(tx, value).await
             }
             #[cfg(daita)]
+            SetDaitaRequired(tx, value) => self.on_set_daita_required(tx, value).await,
+            #[cfg(daita)]
             SetDaitaSettings(tx, daita_settings) => {
                 self.on_set_daita_settings(tx, daita_settings).await
             }
             SetDnsOptions(tx, dns_servers) => self.on_set_dns_options(tx, dns_servers).await,
+            GetEffectiveDnsServers(tx) => self.on_get_effective_dns_servers(tx),
+            GetEffectiveConnectionConfig(tx) => self.on_get_effective_connection_config(tx),
+            GetInstalledRoutes(tx) => self.on_get_installed_routes(tx),
+            TestCustomDns(tx, servers) => self.on_test_custom_dns(tx, servers),
+            CheckDnsLeak(tx) => self.on_check_dns_leak(tx),
             SetRelayOverride(tx, relay_override) => {
                 self.on_set_relay_override(tx, relay_override).await
             }
             ClearAllRelayOverrides(tx) => self.on_clear_all_relay_overrides(tx).await,
             SetWireguardMtu(tx, mtu) => self.on_set_wireguard_mtu(tx, mtu).await,
+            SetWireguardKeepalive(tx, interval) => {
+                self.on_set_wireguard_keepalive(tx, interval).await
+            }
+            SetTunnelBindAddress(tx, address) => self.on_set_tunnel_bind_address(tx, address).await,
             SetWireguardAllowedIps(tx, allowed_ips) => {
                 self.on_set_wireguard_allowed_ips(tx, allowed_ips).await
             }
+            ValidateWireguardAllowedIps(tx, allowed_ips) => {
+                self.on_validate_wireguard_allowed_ips(tx, allowed_ips)
+            }
             SetWireguardRotationInterval(tx, interval) => {
                 self.on_set_wireguard_rotation_interval(tx, interval).await
             }
             GetSettings(tx) => self.on_get_settings(tx),
             ResetSettings(tx) => self.on_reset_settings(tx).await,
+            VerifySettingsIntegrity(tx) => self.on_verify_settings_integrity(tx).await,
+            GetAppliedSettingsMigrations(tx) => self.on_get_applied_settings_migrations(tx),
+            LockSettings(tx, settings, password) => {
+                self.on_lock_settings(tx, settings, password).await
+            }
+            UnlockSettings(tx, password) => self.on_unlock_settings(tx, password).await,
+            GetSettingsLock(tx) => self.on_get_settings_lock(tx),
+            SaveProfile(tx, name) => self.on_save_profile(tx, name).await,
+            ApplyProfile(tx, name, reconnect) => self.on_apply_profile(tx, name, reconnect).await,
+            ListProfiles(tx) => self.on_list_profiles(tx),
+            DeleteProfile(tx, name) => self.on_delete_profile(tx, name).await,
+            ExportRelayLink(tx) => self.on_export_relay_link(tx),
+            ImportRelayLink(tx, link) => self.on_import_relay_link(tx, link).await,
+            GetCacheUsage(tx) => self.on_get_cache_usage(tx),
+            GetInflightApiRequests(tx) => self.on_get_inflight_api_requests(tx),
+            GetLastApiError(tx) => self.on_get_last_api_error(tx),
+            SetMaxConcurrentApiRequests(tx, max_requests) => {
+                self.on_set_max_concurrent_api_requests(tx, max_requests)
+                    .await
+            }
+            GetApiRequestLimit(tx) => self.on_get_api_request_limit(tx),
+            SetApiSniHostname(tx, hostname) => self.on_set_api_sni_hostname(tx, hostname).await,
+            SetRelayListDownloadSettings(tx, download_settings) => {
+                self.on_set_relay_list_download_settings(tx, download_settings)
+                    .await
+            }
+            GetApiAddressInfo(tx) => self.on_get_api_address_info(tx),
+            ResetApiConnections(tx) => self.on_reset_api_connections(tx),
+            HandleNetworkChange(tx) => self.on_handle_network_change(tx),
+            CheckSystemClock(tx) => self.on_check_system_clock(tx),
+            ClearCache(tx, kinds) => self.on_clear_cache(tx, kinds),
             RotateWireguardKey(tx) => self.on_rotate_wireguard_key(tx),
             GetWireguardKey(tx) => self.on_get_wireguard_key(tx).await,
+            GetWireguardKeyAge(tx) => self.on_get_wireguard_key_age(tx).await,
+            GetLastHandshakeDiagnostics(tx) => self.on_get_last_handshake_diagnostics(tx),
+            GetTunnelMtuInfo(tx) => self.on_get_tunnel_mtu_info(tx),
+            GetTunnelCryptoInfo(tx) => self.on_get_tunnel_crypto_info(tx),
+            GetAppliedAllowedIps(tx) => self.on_get_applied_allowed_ips(tx),
+            CollectLogs(tx, redact) => self.on_collect_logs(tx, redact),
+            SetLogFilter(tx, log_filter) => self.on_set_log_filter(tx, log_filter),
             CreateCustomList(tx, name, locations) => {
                 self.on_create_custom_list(tx, name, locations).await
             }
@@ -1547,6 +2195,7 @@ impl Daemon {
             UpdateCustomList(tx, update) => self.on_update_custom_list(tx, update).await,
             ClearCustomLists(tx) => self.on_clear_custom_lists(tx).await,
             GetVersionInfo(tx) => self.on_get_version_info(tx),
+            GetUpgradeDownloadSize(tx) => self.on_get_upgrade_download_size(tx),
             AddApiAccessMethod(tx, name, enabled, access_method) => {
                 self.on_add_access_method(tx, name, enabled, access_method)
                     .await
@@ -1555,13 +2204,24 @@ impl Daemon {
             UpdateApiAccessMethod(tx, method) => self.on_update_api_access_method(tx, method).await,
             ClearCustomApiAccessMethods(tx) => self.on_clear_custom_api_access_methods(tx).await,
             GetCurrentAccessMethod(tx) => self.on_get_current_api_access_method(tx),
+            GetLastSuccessfulAccessMethod(tx) => self.on_get_last_successful_access_method(tx),
             SetApiAccessMethod(tx, method) => self.on_set_api_access_method(tx, method).await,
             TestApiAccessMethodById(tx, method) => self.on_test_api_access_method(tx, method).await,
+            TestAllApiAccessMethods(tx) => self.on_test_all_api_access_methods(tx).await,
+            ExportApiAccessMethods(tx, include_secrets) => {
+                self.on_export_api_access_methods(tx, include_secrets)
+            }
+            ImportApiAccessMethods(tx, json) => self.on_import_api_access_methods(tx, json).await,
+            FindWorkingApiAccessMethod(tx, set_active) => {
+                self.on_find_working_api_access_method(tx, set_active).await
+            }
             TestCustomApiAccessMethod(tx, proxy) => self.on_test_proxy_as_access_method(tx, proxy),
             IsPerformingPostUpgrade(tx) => self.on_is_performing_post_upgrade(tx),
             GetCurrentVersion(tx) => self.on_get_current_version(tx),
             #[cfg(not(target_os = "android"))]
             FactoryReset(tx) => self.on_factory_reset(tx).await,
+            #[cfg(not(target_os = "android"))]
+            GetFactoryResetPreview(tx) => self.on_get_factory_reset_preview(tx).await,
             #[cfg(any(target_os = "linux", target_os = "windows"))]
             SplitTunnelIsSupported(tx) => self.on_split_tunnel_is_supported(tx),
             #[cfg(target_os = "linux")]
@@ -1573,7 +2233,7 @@ impl Daemon {
             #[cfg(target_os = "linux")]
             ClearSplitTunnelProcesses(tx) => self.on_clear_split_tunnel_processes(tx),
             #[cfg(any(target_os = "windows", target_os = "android", target_os = "macos"))]
-            AddSplitTunnelApp(tx, app) => self.on_add_split_tunnel_app(tx, app),
+            AddSplitTunnelApp(tx, app, subnets) => self.on_add_split_tunnel_app(tx, app, subnets),
             #[cfg(any(target_os = "windows", target_os = "android", target_os = "macos"))]
             RemoveSplitTunnelApp(tx, path) => self.on_remove_split_tunnel_app(tx, path),
             #[cfg(any(target_os = "windows", target_os = "android", target_os = "macos"))]
@@ -1587,7 +2247,26 @@ impl Daemon {
             SetObfuscationSettings(tx, settings) => {
                 self.on_set_obfuscation_settings(tx, settings).await
             }
-            PrepareRestart(shutdown) => self.on_prepare_restart(shutdown),
+            SetObfuscationAutoSensitivity(tx, sensitivity) => {
+                self.on_set_obfuscation_auto_sensitivity(tx, sensitivity)
+                    .await
+            }
+            GetObfuscationState(tx) => self.on_get_obfuscation_state(tx).await,
+            GetConnectionAttemptHistory(tx) => self.on_get_connection_attempt_history(tx),
+            GetNetworkProbeResults(tx) => self.on_get_network_probe_results(tx),
+            PrepareRestart(shutdown) => self.on_prepare_restart(shutdown).await,
+            GetLastRestartReason(tx) => self.on_get_last_restart_reason(tx),
+            GetNotifications(tx) => self.on_get_notifications(tx),
+            AcknowledgeNotification(tx, id) => self.on_acknowledge_notification(tx, id).await,
+            GetUsageStats(tx) => self.on_get_usage_stats(tx),
+            ResetUsageStats(tx) => self.on_reset_usage_stats(tx).await,
+            GetFirewallInfo(tx) => self.on_get_firewall_info(tx),
+            #[cfg(feature = "network-simulation")]
+            SetSimulatedNetworkConditions(tx, conditions) => {
+                self.on_set_simulated_network_conditions(tx, conditions)
+            }
+            #[cfg(feature = "diagnostics")]
+            GetResourceUsage(tx) => self.on_get_resource_usage(tx),
             #[cfg(target_os = "android")]
             BypassSocket(fd, tx) => self.on_bypass_socket(fd, tx),
             #[cfg(target_os = "android")]
@@ -1598,6 +2277,11 @@ impl Daemon {
             }
             ApplyJsonSettings(tx, blob) => self.on_apply_json_settings(tx, blob).await,
             ExportJsonSettings(tx) => self.on_export_json_settings(tx),
+            CreateSettingsSnapshot(tx, label) => self.on_create_settings_snapshot(tx, label).await,
+            RestoreSettingsSnapshot(tx, label) => {
+                self.on_restore_settings_snapshot(tx, label).await
+            }
+            ListSettingsSnapshots(tx) => self.on_list_settings_snapshots(tx).await,
             GetFeatureIndicators(tx) => self.on_get_feature_indicators(tx),
             DisableRelay { relay, tx } => self.on_toggle_relay(relay, false, tx),
             EnableRelay { relay, tx } => self.on_toggle_relay(relay, true, tx),
@@ -1615,13 +2299,19 @@ impl Daemon {
                 let _ = tx.send(());
             }
             AppUpgrade(tx) => self.on_app_upgrade(tx).await,
+            PrefetchUpdate(tx) => self.on_prefetch_update(tx).await,
             AppUpgradeAbort(tx) => self.on_app_upgrade_abort(tx).await,
             GetAppUpgradeCacheDir(tx) => self.on_get_app_upgrade_cache_dir(tx).await,
+            ListActiveDownloads(tx) => self.on_list_active_downloads(tx).await,
+            CancelDownload(tx, id) => self.on_cancel_download(tx, id).await,
             GetBridges(tx) => self.on_get_bridges(tx),
         }
     }
 
-    fn handle_new_app_version_info(&mut self, app_version_info: AppVersionInfo) {
+    async fn handle_new_app_version_info(&mut self, app_version_info: AppVersionInfo) {
+        self.notifications
+            .on_app_version_info(app_version_info.clone())
+            .await;
         self.management_interface
             .notifier()
             .notify_app_version(app_version_info);
@@ -1631,7 +2321,76 @@ impl Daemon {
         self.management_interface.notifier().notify_leak(leak);
     }
 
+    fn handle_captive_portal_event(&mut self, event: captive_portal::CaptivePortalEvent) {
+        match self.captive_portal_checker.handle_event(event) {
+            Some(captive_portal::CheckStatus::Detected) => {
+                self.management_interface
+                    .notifier()
+                    .notify_captive_portal_detected();
+            }
+            Some(captive_portal::CheckStatus::Passed)
+                if *self.target_state == TargetState::Secured =>
+            {
+                log::debug!("Captive portal check passed. Connecting");
+                self.send_tunnel_command(TunnelCommand::Connect);
+            }
+            _ => (),
+        }
+    }
+
+    async fn handle_connect_schedule_event(&mut self, event: connect_schedule::ScheduleEvent) {
+        match event.disconnect_reason() {
+            Some(reason) => self.disconnect_automatically(reason).await,
+            None => {
+                self.set_target_state(event.target_state()).await;
+            }
+        }
+    }
+
+    async fn handle_current_network_changed(&mut self, network: CurrentNetwork) {
+        let first_detection = self.current_network.is_none();
+        self.current_network = Some(network);
+        self.update_trusted_network(first_detection).await;
+    }
+
+    /// Check whether the current network is trusted, and disconnect or reconnect if the device
+    /// joined or left a trusted network. Auto-connect happens before the network is first
+    /// detected, so it is undone if the first network is trusted.
+    async fn update_trusted_network(&mut self, first_detection: bool) {
+        let Some(network) = &self.current_network else {
+            return;
+        };
+        let trusted = self.settings.trusted_networks.is_trusted(network);
+        if trusted == self.on_trusted_network {
+            return;
+        }
+        self.on_trusted_network = trusted;
+        self.management_interface
+            .notifier()
+            .notify_trusted_network_changed(trusted);
+
+        if trusted {
+            let suppress_auto_connect = first_detection && self.settings.auto_connect;
+            if suppress_auto_connect || self.settings.trusted_networks.disconnect {
+                log::info!("Disconnecting since a trusted network was joined");
+                self.disconnect_automatically(DisconnectReason::TrustedNetwork)
+                    .await;
+            } else {
+                log::info!("Joined a trusted network");
+            }
+        } else if self.disconnect_reason == Some(DisconnectReason::TrustedNetwork) {
+            log::info!("Reconnecting since the trusted network was left");
+            self.set_target_state(TargetState::Secured).await;
+        } else {
+            log::info!("Left the trusted network");
+        }
+    }
+
     async fn handle_device_event(&mut self, event: AccountEvent) {
+        match &event {
+            AccountEvent::Device(event) => self.notifications.on_device_event(event).await,
+            AccountEvent::Expiry(expiry) => self.notifications.on_account_expiry(*expiry).await,
+        }
         match &event {
             AccountEvent::Device(PrivateDeviceEvent::Login(device)) => {
                 if let Err(error) = self
@@ -1664,7 +2423,8 @@ impl Daemon {
             }
             AccountEvent::Device(PrivateDeviceEvent::Logout) => {
                 log::info!("Disconnecting because account number was cleared");
-                self.set_target_state(TargetState::Unsecured).await;
+                self.disconnect_automatically(DisconnectReason::LoggedOut)
+                    .await;
             }
             AccountEvent::Device(PrivateDeviceEvent::Revoked) => {
                 // If we're currently in a secured state, reconnect to make sure we immediately
@@ -1678,6 +2438,7 @@ impl Daemon {
             }
             AccountEvent::Expiry(expiry) if *self.target_state == TargetState::Secured => {
                 if expiry >= &chrono::Utc::now() {
+                    self.block_on_tunnel_drop = false;
                     if let TunnelState::Error(ref state) = self.tunnel_state
                         && matches!(state.cause(), ErrorStateCause::AuthFailed(_))
                     {
@@ -1685,10 +2446,7 @@ impl Daemon {
                         self.connect_tunnel();
                     }
                 } else {
-                    log::debug!("Entering blocking state since the account is out of time");
-                    self.send_tunnel_command(TunnelCommand::Block(ErrorStateCause::AuthFailed(
-                        Some(AuthFailed::ExpiredAccount.as_str().to_string()),
-                    )))
+                    self.handle_account_expired().await;
                 }
             }
             _ => (),
@@ -1700,6 +2458,36 @@ impl Daemon {
         }
     }
 
+    /// Apply [`Settings::on_account_expiry`] after the account was found to be out of time while
+    /// the target state is secured.
+    async fn handle_account_expired(&mut self) {
+        let action = self.settings.on_account_expiry;
+        match action {
+            AccountExpiryAction::Disconnect => {
+                log::debug!("Disconnecting since the account is out of time");
+                self.disconnect_automatically(DisconnectReason::AccountExpired)
+                    .await;
+            }
+            AccountExpiryAction::StayConnectedUntilDrop
+                if matches!(self.tunnel_state, TunnelState::Connected { .. }) =>
+            {
+                log::debug!(
+                    "Staying connected until the tunnel drops even though the account is out of time"
+                );
+                self.block_on_tunnel_drop = true;
+            }
+            AccountExpiryAction::Block | AccountExpiryAction::StayConnectedUntilDrop => {
+                log::debug!("Entering blocking state since the account is out of time");
+                self.send_tunnel_command(TunnelCommand::Block(ErrorStateCause::AuthFailed(Some(
+                    AuthFailed::ExpiredAccount.as_str().to_string(),
+                ))))
+            }
+        }
+        self.management_interface
+            .notifier()
+            .notify_account_expired(action);
+    }
+
     fn save_connection_mode_to_cache(&self, connection_mode: ApiConnectionMode) {
         // Save the new connection mode to cache!
         let cache_dir = self.cache_dir.clone();
@@ -1848,9 +2636,12 @@ impl Daemon {
                 }
                 save_result
             }
-            ExcludedPathsUpdate::SetPaths(paths) => self
+            ExcludedPathsUpdate::SetPaths(paths, allowed_subnets) => self
                 .settings
-                .update(move |settings| settings.split_tunnel.apps = paths)
+                .update(move |settings| {
+                    settings.split_tunnel.apps = paths;
+                    settings.split_tunnel.allowed_subnets = allowed_subnets;
+                })
                 .await
                 .map_err(Error::SettingsError),
         };
@@ -1862,8 +2653,28 @@ impl Daemon {
         tx: oneshot::Sender<bool>,
         new_target_state: TargetState,
     ) {
+        // An explicit target state overrides any benchmark or pause
+        if self.obfuscation_benchmark.is_some() {
+            self.finish_obfuscation_benchmark(Some(new_target_state))
+                .await;
+            Self::oneshot_send(tx, true, "state change initiated");
+            return;
+        }
+        let was_paused = self.is_paused();
+        if was_paused {
+            self.pause.cancel();
+            self.update_disconnect_reason(None);
+        }
         let state_change_initated = self.set_target_state(new_target_state).await;
-        Self::oneshot_send(tx, state_change_initated, "state change initiated");
+        if was_paused {
+            // The target state was not saved while paused
+            self.target_state.set(new_target_state).await;
+        }
+        Self::oneshot_send(
+            tx,
+            state_change_initated || was_paused,
+            "state change initiated",
+        );
     }
 
     fn on_reconnect(&mut self, tx: oneshot::Sender<bool>) {
@@ -1876,10 +2687,195 @@ impl Daemon {
         }
     }
 
+    async fn on_pause_tunnel(&mut self, tx: ResponseTx<(), Error>, duration: Duration) {
+        let result = self.pause_tunnel(duration).await;
+        Self::oneshot_send(tx, result, "pause_tunnel response");
+    }
+
+    async fn pause_tunnel(&mut self, duration: Duration) -> Result<(), Error> {
+        self.settings
+            .check_unlocked(LockableSetting::AutoConnect)
+            .map_err(Error::SettingsError)?;
+        let reason = self.pause.start(
+            *self.target_state,
+            self.disconnect_reason,
+            duration,
+            self.tx.clone(),
+        )?;
+
+        log::info!("Pausing the tunnel for {} seconds", duration.as_secs());
+        if self.is_paused() {
+            self.update_disconnect_reason(Some(reason));
+        } else {
+            // The pause is not saved, so the tunnel is secured if the daemon restarts
+            self.update_target_state(TargetState::Unsecured, false)
+                .await;
+            self.disconnect_reason = Some(reason);
+        }
+        Ok(())
+    }
+
+    async fn on_resume_tunnel(&mut self, tx: oneshot::Sender<bool>) {
+        let resumed = self.end_pause().await;
+        Self::oneshot_send(tx, resumed, "resume_tunnel response");
+    }
+
+    fn is_paused(&self) -> bool {
+        pause::is_paused(self.disconnect_reason)
+    }
+
+    /// Reconnect if the tunnel is paused. Returns whether it was paused.
+    async fn end_pause(&mut self) -> bool {
+        match self.pause.end(self.disconnect_reason) {
+            Some(target_state) => {
+                self.set_target_state(target_state).await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Change the disconnect reason without changing the target state, and notify clients if the
+    /// tunnel is disconnected.
+    fn update_disconnect_reason(&mut self, reason: Option<DisconnectReason>) {
+        self.disconnect_reason = reason;
+        if let TunnelState::Disconnected {
+            disconnect_reason, ..
+        } = &mut self.tunnel_state
+        {
+            *disconnect_reason = reason;
+            self.management_interface
+                .notifier()
+                .notify_new_state(self.tunnel_state.clone());
+        }
+    }
+
+    async fn on_connect_to_relay_once(&mut self, tx: ResponseTx<(), Error>, hostname: String) {
+        let Some(location) = self.relay_location(&hostname) else {
+            Self::oneshot_send(
+                tx,
+                Err(Error::RelayNotFound(hostname)),
+                "connect_to_relay_once response",
+            );
+            return;
+        };
+
+        log::info!("Connecting to {hostname} once");
+        self.parameters_generator.set_relay_override(location).await;
+        if !self.set_target_state(TargetState::Secured).await {
+            self.connect_tunnel();
+        }
+        Self::oneshot_send(tx, Ok(()), "connect_to_relay_once response");
+    }
+
+    /// Return a location constraint that matches only the relay with the given hostname.
+    fn relay_location(&self, hostname: &str) -> Option<GeographicLocationConstraint> {
+        self.relay_selector.relay_list(|relay_list| {
+            relay_list
+                .relays()
+                .find(|relay| relay.hostname == hostname)
+                .map(|relay| {
+                    GeographicLocationConstraint::hostname(
+                        relay.location.country_code.clone(),
+                        relay.location.city_code.clone(),
+                        relay.hostname.clone(),
+                    )
+                })
+        })
+    }
+
+    /// Connect using the next method of the running obfuscation benchmark, or finish the
+    /// benchmark if there are no methods left.
+    async fn benchmark_next_obfuscation_method(&mut self) {
+        let Some(benchmark) = self.obfuscation_benchmark.as_mut() else {
+            return;
+        };
+        let Some(method) = benchmark.start_next(self.tx.to_specialized_sender()) else {
+            self.finish_obfuscation_benchmark(None).await;
+            return;
+        };
+        let location = benchmark.location.clone();
+
+        log::debug!("Benchmarking obfuscation method {method}");
+        self.parameters_generator
+            .hold_relay_override(location, method)
+            .await;
+        // The benchmark is temporary, so the target state is not saved
+        if !self.update_target_state(TargetState::Secured, false).await {
+            self.connect_tunnel();
+        }
+    }
+
+    /// Stop the running obfuscation benchmark and respond with the results so far. Then enter
+    /// `target_state`, or the target state from before the benchmark if it is `None`.
+    async fn finish_obfuscation_benchmark(&mut self, target_state: Option<TargetState>) {
+        let Some(benchmark) = self.obfuscation_benchmark.take() else {
+            return;
+        };
+        let obfuscation_benchmark::FinishedBenchmark {
+            tx,
+            results,
+            target_state,
+        } = benchmark.finish(target_state);
+
+        log::info!("Obfuscation benchmark finished");
+        self.parameters_generator.clear_relay_override().await;
+        if !self.set_target_state(target_state).await && target_state == TargetState::Secured {
+            // Reconnect using the normal relay selection
+            self.connect_tunnel();
+        }
+        Self::oneshot_send(tx, Ok(results), "benchmark_obfuscation response");
+    }
+
+    async fn update_obfuscation_benchmark(&mut self) {
+        let Some(benchmark) = self.obfuscation_benchmark.as_mut() else {
+            return;
+        };
+        // Ignore states of tunnels that were started before the current method was
+        let Some(retry_attempt) = self.parameters_generator.relay_override_attempt().await else {
+            return;
+        };
+        let start_next = match &self.tunnel_state {
+            TunnelState::Connected { .. } => {
+                let probe =
+                    obfuscation_benchmark::measure_throughput(self.benchmark_rest_service.clone());
+                benchmark.on_connected(probe, self.tx.to_specialized_sender());
+                false
+            }
+            TunnelState::Error(_) => benchmark.on_failed(),
+            // Retrying means that the first attempt to connect using the method failed
+            TunnelState::Connecting { .. } if retry_attempt > 0 => benchmark.on_failed(),
+            _ => false,
+        };
+        if start_next {
+            self.benchmark_next_obfuscation_method().await;
+        }
+    }
+
+    async fn handle_obfuscation_benchmark_event(
+        &mut self,
+        event: obfuscation_benchmark::BenchmarkEvent,
+    ) {
+        let Some(benchmark) = self.obfuscation_benchmark.as_mut() else {
+            return;
+        };
+        if benchmark.handle_event(event) {
+            self.benchmark_next_obfuscation_method().await;
+        }
+    }
+
     fn on_get_state(&self, tx: oneshot::Sender<TunnelState>) {
         Self::oneshot_send(tx, self.tunnel_state.clone(), "current state");
     }
 
+    fn on_is_kill_switch_active(&self, tx: oneshot::Sender<bool>) {
+        Self::oneshot_send(
+            tx,
+            self.tunnel_state.is_blocking(),
+            "is_kill_switch_active response",
+        );
+    }
+
     fn on_is_performing_post_upgrade(&self, tx: oneshot::Sender<bool>) {
         let performing_post_upgrade = !self.migration_complete.is_complete();
         Self::oneshot_send(tx, performing_post_upgrade, "performing post upgrade");
@@ -1970,23 +2966,204 @@ impl Daemon {
         Self::oneshot_send(tx, self.relay_selector.get_relays(), "relay locations");
     }
 
-    fn on_get_bridges(&mut self, tx: oneshot::Sender<BridgeList>) {
-        Self::oneshot_send(tx, self.relay_selector.get_bridges(), "bridges");
+    fn on_get_relay_locations_summary(&mut self, tx: oneshot::Sender<RelayLocationsSummary>) {
+        let summary = self
+            .relay_selector
+            .relay_list(|relay_list| relay_list.summary());
+        Self::oneshot_send(tx, summary, "relay locations summary");
     }
 
-    async fn on_update_relay_locations(&mut self) {
-        self.relay_list_updater.update().await;
+    fn on_get_relays_in_city(
+        &mut self,
+        tx: oneshot::Sender<Option<Vec<WireguardRelay>>>,
+        country_code: CountryCode,
+        city_code: CityCode,
+    ) {
+        let relays = self.relay_selector.relay_list(|relay_list| {
+            relay_list
+                .lookup_city(country_code.clone(), city_code.clone())
+                .map(|city| city.relays.clone())
+        });
+        Self::oneshot_send(tx, relays, "relays in city");
     }
 
-    async fn on_update_default_location(&mut self, tx: ResponseTx<(), settings::Error>) {
-        log::info!(
-            "should_update_default_country: {}",
-            &self.settings.update_default_location
-        );
-
-        if self.settings.update_default_location
-            && let Some(location) = self.tunnel_state.get_location()
-            && let Some(country_code) = self.relay_selector.relay_list(|relays| {
+    fn on_check_relay_feature_support(
+        &self,
+        tx: oneshot::Sender<RelayFeatureSupport>,
+        relay_settings: RelaySettings,
+    ) {
+        let mut requested = vec![];
+        #[cfg(daita)]
+        if self.settings.tunnel_options.wireguard.daita.enabled {
+            requested.push(RelayFeature::Daita);
+        }
+        match self.settings.obfuscation_settings.selected_obfuscation {
+            SelectedObfuscation::Quic => requested.push(RelayFeature::Quic),
+            SelectedObfuscation::Lwo => requested.push(RelayFeature::Lwo),
+            _ => (),
+        }
+        let relays = self
+            .relay_selector
+            .get_relays_matching_settings(relay_settings)
+            .iter()
+            .map(RelayFeatures::from)
+            .collect();
+        let support = RelayFeatureSupport { requested, relays };
+        Self::oneshot_send(tx, support, "relay feature support");
+    }
+
+    fn on_relay_list_self_test(&self, tx: oneshot::Sender<RelayListReport>) {
+        let relays = self.relay_selector.get_relays();
+        let bridges = self.relay_selector.get_bridges();
+        let cache_dir = self.cache_dir.clone();
+        // Parsing the cached relay list may take a while, so don't block the event loop
+        tokio::task::spawn_blocking(move || {
+            let report = relay_list::self_test::self_test(&cache_dir, &relays, &bridges);
+            Self::oneshot_send(tx, report, "relay_list_self_test response");
+        });
+    }
+
+    fn on_get_relay_list_update_info(&self, tx: oneshot::Sender<RelayListUpdateInfo>) {
+        let mut relay_list_updater = self.relay_list_updater.clone();
+        tokio::spawn(async move {
+            match relay_list_updater.update_info().await {
+                Ok(info) => Self::oneshot_send(tx, info, "get_relay_list_update_info response"),
+                Err(error) => log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to get relay list update info")
+                ),
+            }
+        });
+    }
+
+    fn on_test_relay_port(
+        &self,
+        tx: ResponseTx<RelayPortReachability, Error>,
+        hostname: String,
+        port: u16,
+        protocol: TransportProtocol,
+    ) {
+        if !self.tunnel_state.is_disconnected() || self.tunnel_state.is_blocking() {
+            Self::oneshot_send(
+                tx,
+                Err(Error::RelayPortTestNotDisconnected),
+                "test_relay_port response",
+            );
+            return;
+        }
+        let address = self.relay_selector.relay_list(|relay_list| {
+            relay_list
+                .relays()
+                .find(|relay| relay.hostname == hostname)
+                .map(|relay| match protocol {
+                    TransportProtocol::Tcp => relay.ipv4_addr_in,
+                    TransportProtocol::Udp => relay.wireguard_ipv4_addr_in(),
+                })
+        });
+        let Some(address) = address else {
+            Self::oneshot_send(
+                tx,
+                Err(Error::RelayNotFound(hostname)),
+                "test_relay_port response",
+            );
+            return;
+        };
+        tokio::spawn(async move {
+            let reachability = relay_port_test::test_port(address.into(), port, protocol).await;
+            Self::oneshot_send(tx, Ok(reachability), "test_relay_port response");
+        });
+    }
+
+    fn on_get_relay_transparency_info(
+        &self,
+        tx: ResponseTx<RelayTransparencyInfo, Error>,
+        hostname: String,
+    ) {
+        let (providers, ownership) = match &self.settings.relay_settings {
+            RelaySettings::Normal(constraints) => {
+                (constraints.providers.clone(), constraints.ownership)
+            }
+            RelaySettings::CustomTunnelEndpoint(_) => (Constraint::Any, Constraint::Any),
+        };
+        let info = self.relay_selector.relay_list(|relay_list| {
+            relay_list
+                .relays()
+                .find(|relay| relay.hostname == hostname)
+                .map(|relay| RelayTransparencyInfo {
+                    hostname: relay.hostname.clone(),
+                    ownership: if relay.owned {
+                        Ownership::MullvadOwned
+                    } else {
+                        Ownership::Rented
+                    },
+                    provider: relay.provider.clone(),
+                    active: relay.active,
+                    in_listed_country: relay.include_in_country,
+                    matches_filters: providers.matches(relay) && ownership.matches(relay),
+                })
+        });
+        let result = info.ok_or(Error::RelayNotFound(hostname));
+        Self::oneshot_send(tx, result, "get_relay_transparency_info response");
+    }
+
+    async fn on_benchmark_obfuscation(
+        &mut self,
+        tx: ResponseTx<Vec<ObfuscationBenchmarkResult>, Error>,
+        hostname: String,
+        methods: Vec<SelectedObfuscation>,
+    ) {
+        if self.obfuscation_benchmark.is_some() {
+            Self::oneshot_send(
+                tx,
+                Err(Error::ObfuscationBenchmarkInProgress),
+                "benchmark_obfuscation response",
+            );
+            return;
+        }
+        if !obfuscation_benchmark::Benchmark::is_valid(&methods) {
+            Self::oneshot_send(
+                tx,
+                Err(Error::InvalidObfuscationBenchmark),
+                "benchmark_obfuscation response",
+            );
+            return;
+        }
+        let Some(location) = self.relay_location(&hostname) else {
+            Self::oneshot_send(
+                tx,
+                Err(Error::RelayNotFound(hostname)),
+                "benchmark_obfuscation response",
+            );
+            return;
+        };
+
+        log::info!("Benchmarking obfuscation methods against {hostname}");
+        self.obfuscation_benchmark = Some(obfuscation_benchmark::Benchmark::new(
+            location,
+            methods,
+            *self.target_state,
+            tx,
+        ));
+        self.benchmark_next_obfuscation_method().await;
+    }
+
+    fn on_get_bridges(&mut self, tx: oneshot::Sender<BridgeList>) {
+        Self::oneshot_send(tx, self.relay_selector.get_bridges(), "bridges");
+    }
+
+    async fn on_update_relay_locations(&mut self) {
+        self.relay_list_updater.update().await;
+    }
+
+    async fn on_update_default_location(&mut self, tx: ResponseTx<(), settings::Error>) {
+        log::info!(
+            "should_update_default_country: {}",
+            &self.settings.update_default_location
+        );
+
+        if self.settings.update_default_location
+            && let Some(location) = self.tunnel_state.get_location()
+            && let Some(country_code) = self.relay_selector.relay_list(|relays| {
                 relays
                     .lookup_country_code_by_name(&location.country)
                     .or_else(|| relays.get_nearest_country_with_relay(location))
@@ -2015,13 +3192,42 @@ impl Daemon {
         let account_manager = self.account_manager.clone();
         let availability = self.api_runtime.availability_handle();
 
+        tokio::spawn(async move {
+            let result = Self::login(account_manager, availability, account_number).await;
+            Self::oneshot_send(tx, result, "login_account response");
+        });
+    }
+
+    /// Log in to `account_number` and resume background API requests.
+    async fn login(
+        account_manager: device::AccountManagerHandle,
+        availability: mullvad_api::availability::ApiAvailability,
+        account_number: AccountNumber,
+    ) -> Result<(), Error> {
+        account_manager
+            .login(account_number)
+            .await
+            .map_err(|error| {
+                log::error!("{}", error.display_chain_with_msg("Login failed"));
+                Error::LoginError(error)
+            })?;
+
+        availability.resume_background();
+
+        Ok(())
+    }
+
+    fn on_switch_account(&mut self, tx: ResponseTx<(), Error>, account_number: String) {
+        let account_manager = self.account_manager.clone();
+        let availability = self.api_runtime.availability_handle();
+
         tokio::spawn(async move {
             let result = async {
                 account_manager
-                    .login(account_number)
+                    .switch_account(account_number)
                     .await
                     .map_err(|error| {
-                        log::error!("{}", error.display_chain_with_msg("Login failed"));
+                        log::error!("{}", error.display_chain_with_msg("Account switch failed"));
                         Error::LoginError(error)
                     })?;
 
@@ -2029,7 +3235,49 @@ impl Daemon {
 
                 Ok(())
             };
-            Self::oneshot_send(tx, result.await, "login_account response");
+            Self::oneshot_send(tx, result.await, "switch_account response");
+        });
+    }
+
+    fn on_get_saved_accounts(&mut self, tx: ResponseTx<Vec<AccountNumber>, Error>) {
+        let account_manager = self.account_manager.clone();
+        tokio::spawn(async move {
+            Self::oneshot_send(
+                tx,
+                account_manager
+                    .saved_accounts()
+                    .await
+                    .map_err(Error::LoginError),
+                "get_saved_accounts response",
+            );
+        });
+    }
+
+    fn on_get_login_backoff(&mut self, tx: ResponseTx<LoginBackoff, Error>) {
+        let account_manager = self.account_manager.clone();
+        tokio::spawn(async move {
+            Self::oneshot_send(
+                tx,
+                account_manager
+                    .login_backoff()
+                    .await
+                    .map_err(Error::LoginError),
+                "get_login_backoff response",
+            );
+        });
+    }
+
+    fn on_clear_login_backoff(&mut self, tx: ResponseTx<(), Error>) {
+        let account_manager = self.account_manager.clone();
+        tokio::spawn(async move {
+            Self::oneshot_send(
+                tx,
+                account_manager
+                    .clear_login_backoff()
+                    .await
+                    .map_err(Error::LoginError),
+                "clear_login_backoff response",
+            );
         });
     }
 
@@ -2046,6 +3294,22 @@ impl Daemon {
         });
     }
 
+    fn on_reauthenticate(&mut self, tx: ResponseTx<(), Error>) {
+        let account_manager = self.account_manager.clone();
+        tokio::spawn(async move {
+            let result = async {
+                account_manager.reauthenticate().await.map_err(|error| {
+                    log::error!(
+                        "{}",
+                        error.display_chain_with_msg("Reauthentication failed")
+                    );
+                    Error::LoginError(error)
+                })
+            };
+            Self::oneshot_send(tx, result.await, "reauthenticate response");
+        });
+    }
+
     fn on_get_device(&mut self, tx: ResponseTx<DeviceState, Error>) {
         let account_manager = self.account_manager.clone();
         tokio::spawn(async move {
@@ -2156,6 +3420,20 @@ impl Daemon {
         });
     }
 
+    fn on_get_upgrade_download_size(&mut self, tx: ResponseTx<Option<u64>, Error>) {
+        let handle = self.version_handle.clone();
+        tokio::spawn(async move {
+            Self::oneshot_send(
+                tx,
+                handle
+                    .get_upgrade_download_size()
+                    .await
+                    .map_err(Error::VersionCheckError),
+                "get_upgrade_download_size response",
+            );
+        });
+    }
+
     fn on_get_current_version(&mut self, tx: oneshot::Sender<mullvad_version::Version>) {
         Self::oneshot_send(
             tx,
@@ -2168,8 +3446,24 @@ impl Daemon {
 
     #[cfg(not(target_os = "android"))]
     async fn on_factory_reset(&mut self, tx: ResponseTx<(), Error>) {
+        if let Err(error) = self.settings.check_no_settings_locked() {
+            Self::oneshot_send(
+                tx,
+                Err(Error::SettingsError(error)),
+                "factory_reset response",
+            );
+            return;
+        }
+
         let mut last_error = None;
 
+        if let Err(error) = self.account_manager.clear_saved_devices().await {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to clear saved devices")
+            );
+        }
+
         if let Err(error) = self.account_manager.logout().await {
             log::error!(
                 "{}",
@@ -2190,6 +3484,30 @@ impl Daemon {
             last_error = Some("Failed to reset settings");
         }
 
+        if let Err(error) = self.usage_stats.remove().await {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to remove usage stats")
+            );
+            last_error = Some("Failed to remove usage stats");
+        }
+
+        if let Err(error) = self.notifications.remove().await {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to remove acknowledged notifications")
+            );
+            last_error = Some("Failed to remove acknowledged notifications");
+        }
+
+        if let Err(error) = self.ssid_hash_key.remove().await {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to remove the SSID hash key")
+            );
+            last_error = Some("Failed to remove the SSID hash key");
+        }
+
         // Shut the daemon down.
         let _ = self.tx.send(InternalDaemonEvent::TriggerShutdown(false));
 
@@ -2208,6 +3526,60 @@ impl Daemon {
         }));
     }
 
+    #[cfg(not(target_os = "android"))]
+    async fn on_get_factory_reset_preview(&mut self, tx: ResponseTx<FactoryResetPreview, Error>) {
+        let device = match self.account_manager.data().await {
+            Ok(state) => state.into_device().map(|device| device.device.name),
+            Err(error) => {
+                log::error!("{}", error.display_chain_with_msg("Failed to get device"));
+                None
+            }
+        };
+
+        let defaults = Settings {
+            rollout_threshold_seed: self.settings.rollout_threshold_seed,
+            ..Settings::default()
+        };
+
+        let mut settings_files = vec![];
+        let stored_files = [
+            self.usage_stats.path(),
+            Some(self.notifications.path()),
+            Some(self.ssid_hash_key.path()),
+        ];
+        for path in stored_files.into_iter().flatten() {
+            if tokio::fs::try_exists(path).await.unwrap_or(false) {
+                settings_files.push(path.to_path_buf());
+            }
+        }
+
+        let files = async {
+            let cache_files = cleanup::cache_directory_entries().await?;
+            let log_files = cleanup::log_directory_entries().await?;
+            Ok::<_, cleanup::Error>((cache_files, log_files))
+        };
+        let result = match files.await {
+            Ok((cache_files, log_files)) => Ok(FactoryResetPreview {
+                settings: *self.settings.settings() != defaults,
+                account_history: self.account_history.get().is_some(),
+                device,
+                cache_files,
+                log_files,
+                settings_files,
+            }),
+            Err(error) => {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to list cache and log directories")
+                );
+                Err(Error::FactoryResetError(
+                    "Failed to list cache and log directories",
+                ))
+            }
+        };
+        Self::oneshot_send(tx, result, "get_factory_reset_preview response");
+    }
+
     #[cfg(target_os = "windows")]
     fn on_split_tunnel_is_supported(&mut self, tx: oneshot::Sender<bool>) {
         Self::oneshot_send(
@@ -2232,33 +3604,41 @@ impl Daemon {
     }
 
     #[cfg(target_os = "linux")]
-    fn on_add_split_tunnel_process(&mut self, tx: ResponseTx<(), split_tunnel::Error>, pid: i32) {
-        let result = self.exclude_pids.add(pid).inspect_err(|error| {
-            log::error!("{}", error.display_chain_with_msg("Unable to add PID"));
-        });
+    fn on_add_split_tunnel_process(&mut self, tx: ResponseTx<(), Error>, pid: i32) {
+        let result = self.update_split_tunnel_processes(|pids| pids.add(pid), "Unable to add PID");
         Self::oneshot_send(tx, result, "add_split_tunnel_process response");
     }
 
     #[cfg(target_os = "linux")]
-    fn on_remove_split_tunnel_process(
-        &mut self,
-        tx: ResponseTx<(), split_tunnel::Error>,
-        pid: i32,
-    ) {
-        let result = self.exclude_pids.remove(pid).inspect_err(|error| {
-            log::error!("{}", error.display_chain_with_msg("Unable to remove PID"));
-        });
+    fn on_remove_split_tunnel_process(&mut self, tx: ResponseTx<(), Error>, pid: i32) {
+        let result =
+            self.update_split_tunnel_processes(|pids| pids.remove(pid), "Unable to remove PID");
         Self::oneshot_send(tx, result, "remove_split_tunnel_process response");
     }
 
     #[cfg(target_os = "linux")]
-    fn on_clear_split_tunnel_processes(&mut self, tx: ResponseTx<(), split_tunnel::Error>) {
-        let result = self.exclude_pids.clear().inspect_err(|error| {
-            log::error!("{}", error.display_chain_with_msg("Unable to clear PIDs"));
-        });
+    fn on_clear_split_tunnel_processes(&mut self, tx: ResponseTx<(), Error>) {
+        let result =
+            self.update_split_tunnel_processes(|pids| pids.clear(), "Unable to clear PIDs");
         Self::oneshot_send(tx, result, "clear_split_tunnel_processes response");
     }
 
+    /// Change the excluded processes, unless split tunneling is locked. The processes are not
+    /// stored in the settings, so the lock must be checked here.
+    #[cfg(target_os = "linux")]
+    fn update_split_tunnel_processes(
+        &mut self,
+        update: impl FnOnce(&mut split_tunnel::PidManager) -> Result<(), split_tunnel::Error>,
+        error_msg: &str,
+    ) -> Result<(), Error> {
+        self.settings
+            .check_unlocked(LockableSetting::SplitTunnel)
+            .map_err(Error::SettingsError)?;
+        update(&mut self.exclude_pids)
+            .inspect_err(|error| log::error!("{}", error.display_chain_with_msg(error_msg)))
+            .map_err(Error::SplitTunnelError)
+    }
+
     /// Update the split app paths in both the settings and tunnel
     #[cfg(any(target_os = "windows", target_os = "android"))]
     fn set_split_tunnel_paths(
@@ -2269,7 +3649,7 @@ impl Daemon {
         update: ExcludedPathsUpdate,
     ) {
         let new_list = match update {
-            ExcludedPathsUpdate::SetPaths(ref paths) => {
+            ExcludedPathsUpdate::SetPaths(ref paths, _) => {
                 if *paths == settings.split_tunnel.apps {
                     Self::oneshot_send(tx, Ok(()), response_msg);
                     return;
@@ -2279,7 +3659,7 @@ impl Daemon {
             ExcludedPathsUpdate::SetState(_) => settings.split_tunnel.apps.iter(),
         };
         let new_state = match update {
-            ExcludedPathsUpdate::SetPaths(_) => settings.split_tunnel.enable_exclusions,
+            ExcludedPathsUpdate::SetPaths(..) => settings.split_tunnel.enable_exclusions,
             ExcludedPathsUpdate::SetState(state) => {
                 if state == settings.split_tunnel.enable_exclusions {
                     Self::oneshot_send(tx, Ok(()), response_msg);
@@ -2339,8 +3719,16 @@ impl Daemon {
         settings: Settings,
         update: ExcludedPathsUpdate,
     ) {
+        if let ExcludedPathsUpdate::SetPaths(_, ref allowed_subnets) = update {
+            self.send_tunnel_command(TunnelCommand::SetExcludedAppSubnets(
+                tunnel_allowed_subnets(allowed_subnets),
+            ));
+        }
+
         let tunnel_list = match update {
-            ExcludedPathsUpdate::SetPaths(ref paths) if settings.split_tunnel.enable_exclusions => {
+            ExcludedPathsUpdate::SetPaths(ref paths, _)
+                if settings.split_tunnel.enable_exclusions =>
+            {
                 paths
                     .iter()
                     .cloned()
@@ -2381,20 +3769,34 @@ impl Daemon {
     }
 
     #[cfg(any(target_os = "windows", target_os = "macos", target_os = "android"))]
-    fn on_add_split_tunnel_app(&mut self, tx: ResponseTx<(), Error>, app: SplitApp) {
+    fn on_add_split_tunnel_app(
+        &mut self,
+        tx: ResponseTx<(), Error>,
+        app: SplitApp,
+        subnets: Vec<ipnetwork::IpNetwork>,
+    ) {
         let settings = self.settings.to_settings();
 
         let excluded_apps = {
             let mut apps = settings.split_tunnel.apps.clone();
-            apps.insert(app);
+            apps.insert(app.clone());
             apps
         };
+        let allowed_subnets = {
+            let mut allowed_subnets = settings.split_tunnel.allowed_subnets.clone();
+            if subnets.is_empty() {
+                allowed_subnets.remove(&app);
+            } else {
+                allowed_subnets.insert(app, subnets);
+            }
+            allowed_subnets
+        };
 
         self.set_split_tunnel_paths(
             tx,
             "add_split_tunnel_app response",
             settings,
-            ExcludedPathsUpdate::SetPaths(excluded_apps),
+            ExcludedPathsUpdate::SetPaths(excluded_apps, allowed_subnets),
         );
     }
 
@@ -2402,17 +3804,23 @@ impl Daemon {
     fn on_remove_split_tunnel_app(&mut self, tx: ResponseTx<(), Error>, app: impl Into<SplitApp>) {
         let settings = self.settings.to_settings();
 
+        let app = app.into();
         let excluded_apps = {
             let mut apps = settings.split_tunnel.apps.clone();
-            apps.remove(&app.into());
+            apps.remove(&app);
             apps
         };
+        let allowed_subnets = {
+            let mut allowed_subnets = settings.split_tunnel.allowed_subnets.clone();
+            allowed_subnets.remove(&app);
+            allowed_subnets
+        };
 
         self.set_split_tunnel_paths(
             tx,
             "remove_split_tunnel_app response",
             settings,
-            ExcludedPathsUpdate::SetPaths(excluded_apps),
+            ExcludedPathsUpdate::SetPaths(excluded_apps, allowed_subnets),
         );
     }
 
@@ -2424,7 +3832,7 @@ impl Daemon {
             tx,
             "clear_split_tunnel_apps response",
             settings,
-            ExcludedPathsUpdate::SetPaths(new_list),
+            ExcludedPathsUpdate::SetPaths(new_list, HashMap::new()),
         );
     }
 
@@ -2550,73 +3958,278 @@ impl Daemon {
         }
     }
 
-    #[cfg(not(target_os = "android"))]
-    async fn on_set_lockdown_mode(
+    async fn on_set_randomize_relay_each_connect(
         &mut self,
         tx: ResponseTx<(), settings::Error>,
-        lockdown_mode: bool,
+        enabled: bool,
     ) {
         match self
             .settings
-            .update(move |settings| settings.lockdown_mode = lockdown_mode)
+            .update(move |settings| settings.randomize_relay_each_connect = enabled)
             .await
         {
-            Ok(settings_changed) => {
-                if settings_changed {
-                    self.send_tunnel_command(TunnelCommand::LockdownMode(
-                        LockdownMode::from(lockdown_mode),
-                        oneshot_map(tx, |tx, ()| {
-                            Self::oneshot_send(tx, Ok(()), "set_lockdown_mode response");
-                        }),
-                    ));
-                } else {
-                    Self::oneshot_send(tx, Ok(()), "set_lockdown_mode response");
-                }
+            Ok(_) => {
+                Self::oneshot_send(tx, Ok(()), "set_randomize_relay_each_connect response");
             }
             Err(e) => {
                 log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
-                Self::oneshot_send(tx, Err(e), "set_lockdown_mode response");
+                Self::oneshot_send(tx, Err(e), "set_randomize_relay_each_connect response");
             }
         }
     }
 
-    async fn on_set_auto_connect(
+    async fn on_set_low_power_mode(&mut self, tx: ResponseTx<(), settings::Error>, enabled: bool) {
+        match self
+            .settings
+            .update(move |settings| settings.low_power_mode = enabled)
+            .await
+        {
+            Ok(_) => {
+                self.api_handle.availability.set_low_power(enabled);
+                Self::oneshot_send(tx, Ok(()), "set_low_power_mode response");
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set_low_power_mode response");
+            }
+        }
+    }
+
+    async fn on_set_captive_portal_check(
         &mut self,
         tx: ResponseTx<(), settings::Error>,
-        auto_connect: bool,
+        enabled: bool,
     ) {
         match self
             .settings
-            .update(move |settings| settings.auto_connect = auto_connect)
+            .update(move |settings| settings.captive_portal_check = enabled)
             .await
         {
-            Ok(_settings_changed) => {
-                Self::oneshot_send(tx, Ok(()), "set auto-connect response");
+            Ok(_) => {
+                if !enabled {
+                    self.skip_captive_portal_check();
+                }
+                Self::oneshot_send(tx, Ok(()), "set_captive_portal_check response");
             }
             Err(e) => {
                 log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
-                Self::oneshot_send(tx, Err(e), "set auto-connect response");
+                Self::oneshot_send(tx, Err(e), "set_captive_portal_check response");
             }
         }
     }
 
-    async fn on_set_obfuscation_settings(
+    async fn on_set_connect_timeout(
         &mut self,
         tx: ResponseTx<(), settings::Error>,
-        new_settings: ObfuscationSettings,
+        timeout: Duration,
     ) {
         match self
             .settings
-            .update(move |settings| settings.obfuscation_settings = new_settings)
+            .update(move |settings| settings.connect_timeout = timeout)
             .await
         {
             Ok(settings_changed) => {
                 if settings_changed {
-                    self.reconnect_tunnel();
+                    self.send_tunnel_command(TunnelCommand::ConnectTimeout(timeout));
                 }
-                Self::oneshot_send(tx, Ok(()), "set_obfuscation_settings");
+                Self::oneshot_send(tx, Ok(()), "set_connect_timeout response");
             }
-            Err(err) => {
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set_connect_timeout response");
+            }
+        }
+    }
+
+    async fn on_set_max_reconnect_attempts(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        max_attempts: Option<u32>,
+    ) {
+        match self
+            .settings
+            .update(move |settings| settings.max_reconnect_attempts = max_attempts)
+            .await
+        {
+            Ok(settings_changed) => {
+                if settings_changed {
+                    self.send_tunnel_command(TunnelCommand::MaxReconnectAttempts(max_attempts));
+                }
+                Self::oneshot_send(tx, Ok(()), "set_max_reconnect_attempts response");
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set_max_reconnect_attempts response");
+            }
+        }
+    }
+
+    fn on_dismiss_captive_portal(&mut self, tx: oneshot::Sender<()>) {
+        self.skip_captive_portal_check();
+        Self::oneshot_send(tx, (), "dismiss_captive_portal response");
+    }
+
+    /// Stop any pending captive portal check and connect if the target state is secured.
+    fn skip_captive_portal_check(&mut self) {
+        if !self.captive_portal_checker.cancel() {
+            return;
+        }
+        if *self.target_state == TargetState::Secured {
+            log::info!("Captive portal check dismissed. Connecting");
+            self.send_tunnel_command(TunnelCommand::Connect);
+        }
+    }
+
+    #[cfg(not(target_os = "android"))]
+    async fn on_set_lockdown_mode(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        lockdown_mode: bool,
+    ) {
+        match self
+            .settings
+            .update(move |settings| settings.lockdown_mode = lockdown_mode)
+            .await
+        {
+            Ok(settings_changed) => {
+                if settings_changed {
+                    self.send_tunnel_command(TunnelCommand::LockdownMode(
+                        LockdownMode::from(lockdown_mode),
+                        oneshot_map(tx, |tx, ()| {
+                            Self::oneshot_send(tx, Ok(()), "set_lockdown_mode response");
+                        }),
+                    ));
+                } else {
+                    Self::oneshot_send(tx, Ok(()), "set_lockdown_mode response");
+                }
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set_lockdown_mode response");
+            }
+        }
+    }
+
+    async fn on_set_auto_connect(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        auto_connect: bool,
+    ) {
+        match self
+            .settings
+            .update(move |settings| settings.auto_connect = auto_connect)
+            .await
+        {
+            Ok(_settings_changed) => {
+                Self::oneshot_send(tx, Ok(()), "set auto-connect response");
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set auto-connect response");
+            }
+        }
+    }
+
+    async fn on_set_connect_schedule(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        schedule: ConnectSchedule,
+    ) {
+        match self
+            .settings
+            .update(move |settings| settings.connect_schedule = schedule)
+            .await
+        {
+            Ok(_settings_changed) => {
+                Self::oneshot_send(tx, Ok(()), "set connect schedule response");
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set connect schedule response");
+            }
+        }
+    }
+
+    async fn on_set_trusted_networks(
+        &mut self,
+        tx: ResponseTx<(), Error>,
+        trusted_networks: TrustedNetworksSettings,
+    ) {
+        let result = self.set_trusted_networks(trusted_networks).await;
+        Self::oneshot_send(tx, result, "set trusted networks response");
+    }
+
+    async fn on_add_trusted_network(&mut self, tx: ResponseTx<(), Error>, network: TrustedNetwork) {
+        let mut trusted_networks = self.settings.trusted_networks.clone();
+        if !trusted_networks.networks.contains(&network) {
+            trusted_networks.networks.push(network);
+        }
+        let result = self.set_trusted_networks(trusted_networks).await;
+        Self::oneshot_send(tx, result, "add trusted network response");
+    }
+
+    /// Save `trusted_networks`, unless any of them cannot be detected on this platform.
+    async fn set_trusted_networks(
+        &mut self,
+        trusted_networks: TrustedNetworksSettings,
+    ) -> Result<(), Error> {
+        if !trusted_networks
+            .networks
+            .iter()
+            .all(trusted_network::is_detectable)
+        {
+            return Err(Error::TrustedNetworkUnsupported);
+        }
+        // Clients cannot compute salted SSID hashes, so any other hash could never match
+        let current_ssid_hash = self
+            .current_network
+            .as_ref()
+            .and_then(|network| network.ssid_hash.as_ref());
+        let is_known = |network: &TrustedNetwork| match network {
+            TrustedNetwork::SsidHash(hash) => {
+                current_ssid_hash == Some(hash)
+                    || self.settings.trusted_networks.networks.contains(network)
+            }
+            _ => true,
+        };
+        if !trusted_networks.networks.iter().all(is_known) {
+            return Err(Error::UnknownSsidHash);
+        }
+        match self
+            .settings
+            .update(move |settings| settings.trusted_networks = trusted_networks)
+            .await
+        {
+            Ok(settings_changed) => {
+                if settings_changed {
+                    self.update_trusted_network(false).await;
+                }
+                Ok(())
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Err(Error::SettingsError(e))
+            }
+        }
+    }
+
+    async fn on_set_obfuscation_settings(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        new_settings: ObfuscationSettings,
+    ) {
+        match self
+            .settings
+            .update(move |settings| settings.obfuscation_settings = new_settings)
+            .await
+        {
+            Ok(settings_changed) => {
+                if settings_changed {
+                    self.reconnect_tunnel();
+                }
+                Self::oneshot_send(tx, Ok(()), "set_obfuscation_settings");
+            }
+            Err(err) => {
                 log::error!(
                     "{}",
                     err.display_chain_with_msg("Failed to set obfuscation settings")
@@ -2626,6 +4239,58 @@ impl Daemon {
         }
     }
 
+    async fn on_set_obfuscation_auto_sensitivity(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        sensitivity: ObfuscationSensitivity,
+    ) {
+        match self
+            .settings
+            .update(move |settings| settings.obfuscation_settings.auto_sensitivity = sensitivity)
+            .await
+        {
+            Ok(settings_changed) => {
+                if settings_changed {
+                    self.reconnect_tunnel();
+                }
+                Self::oneshot_send(tx, Ok(()), "set_obfuscation_auto_sensitivity");
+            }
+            Err(err) => {
+                log::error!(
+                    "{}",
+                    err.display_chain_with_msg("Failed to set obfuscation auto sensitivity")
+                );
+                Self::oneshot_send(tx, Err(err), "set_obfuscation_auto_sensitivity");
+            }
+        }
+    }
+
+    async fn on_get_obfuscation_state(&self, tx: oneshot::Sender<ObfuscationState>) {
+        let attempts = self.parameters_generator.get_obfuscation_attempts().await;
+        let active = match self.tunnel_state {
+            TunnelState::Connecting { .. } | TunnelState::Connected { .. } => {
+                attempts.last().cloned()
+            }
+            _ => None,
+        };
+        let state = ObfuscationState {
+            requested: self.settings.obfuscation_settings.selected_obfuscation,
+            active,
+            attempts,
+        };
+        Self::oneshot_send(tx, state, "get_obfuscation_state response");
+    }
+
+    fn on_get_connection_attempt_history(&self, tx: oneshot::Sender<Vec<ConnectionAttempt>>) {
+        let attempts = self.connection_attempts.clone().into();
+        Self::oneshot_send(tx, attempts, "get_connection_attempt_history response");
+    }
+
+    fn on_get_network_probe_results(&self, tx: oneshot::Sender<NetworkProbeResults>) {
+        let results = self.connection_attempts.probe_results();
+        Self::oneshot_send(tx, results, "get_network_probe_results response");
+    }
+
     async fn on_set_enable_ipv6(&mut self, tx: ResponseTx<(), settings::Error>, enable_ipv6: bool) {
         match self
             .settings
@@ -2701,6 +4366,24 @@ impl Daemon {
         }
     }
 
+    async fn on_set_on_account_expiry(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        action: AccountExpiryAction,
+    ) {
+        match self
+            .settings
+            .update(|settings| settings.on_account_expiry = action)
+            .await
+        {
+            Ok(_) => Self::oneshot_send(tx, Ok(()), "set_on_account_expiry response"),
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set_on_account_expiry response");
+            }
+        }
+    }
+
     #[cfg(daita)]
     async fn on_set_daita_enabled(&mut self, tx: ResponseTx<(), settings::Error>, value: bool) {
         let result = self
@@ -2767,6 +4450,34 @@ impl Daemon {
         }
     }
 
+    #[cfg(daita)]
+    async fn on_set_daita_required(&mut self, tx: ResponseTx<(), settings::Error>, value: bool) {
+        match self
+            .settings
+            .update(|settings| settings.tunnel_options.wireguard.daita.required = value)
+            .await
+        {
+            Ok(settings_changed) => {
+                Self::oneshot_send(tx, Ok(()), "set_daita_required response");
+
+                if let RelaySettings::CustomTunnelEndpoint(_) = &self.settings.relay_settings {
+                    return; // DAITA is not supported for custom relays
+                }
+
+                let daita_enabled = self.settings.tunnel_options.wireguard.daita.enabled;
+
+                if settings_changed && daita_enabled {
+                    log::info!("Reconnecting because DAITA settings changed");
+                    self.reconnect_tunnel();
+                }
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set_daita_required response");
+            }
+        }
+    }
+
     #[cfg(daita)]
     async fn on_set_daita_settings(
         &mut self,
@@ -2824,6 +4535,88 @@ impl Daemon {
         }
     }
 
+    fn on_get_effective_dns_servers(&self, tx: oneshot::Sender<Vec<EffectiveDnsServer>>) {
+        if !matches!(self.tunnel_state, TunnelState::Connected { .. }) {
+            Self::oneshot_send(tx, vec![], "get_effective_dns_servers response");
+            return;
+        }
+        let dns_options = self.settings.tunnel_options.dns_options.clone();
+        let parameters_generator = self.parameters_generator.clone();
+        tokio::spawn(async move {
+            let gateways = parameters_generator.get_last_gateways().await;
+            let servers = dns::effective_dns_servers(&dns_options, &gateways);
+            Self::oneshot_send(tx, servers, "get_effective_dns_servers response");
+        });
+    }
+
+    fn on_get_effective_connection_config(&self, tx: ResponseTx<EffectiveConnectionConfig, Error>) {
+        // A new tunnel would not be connected while offline
+        let Some(ip_availability) = self.connectivity.borrow().availability() else {
+            Self::oneshot_send(
+                tx,
+                Err(Error::Offline),
+                "get_effective_connection_config response",
+            );
+            return;
+        };
+        let parameters_generator = self.parameters_generator.clone();
+        tokio::spawn(async move {
+            let config = parameters_generator
+                .effective_config(ip_availability)
+                .await
+                .map_err(Error::ConnectionConfig);
+            Self::oneshot_send(tx, config, "get_effective_connection_config response");
+        });
+    }
+
+    fn on_get_installed_routes(&self, tx: ResponseTx<Vec<InstalledRoute>, Error>) {
+        let route_manager = self.route_manager.clone();
+        tokio::spawn(async move {
+            let routes = route_manager
+                .get_installed_routes()
+                .await
+                .map_err(Error::RouteManager);
+            Self::oneshot_send(tx, routes, "get_installed_routes response");
+        });
+    }
+
+    fn on_test_custom_dns(
+        &self,
+        tx: oneshot::Sender<Vec<DnsServerTestResult>>,
+        servers: Vec<IpAddr>,
+    ) {
+        let connected = matches!(self.tunnel_state, TunnelState::Connected { .. });
+        let blocking = self.tunnel_state.is_blocking();
+        let dns_options = self.settings.tunnel_options.dns_options.clone();
+        let parameters_generator = self.parameters_generator.clone();
+        tokio::spawn(async move {
+            // While the firewall is blocking, DNS is only allowed to the servers used by the
+            // tunnel
+            let allowed_servers: Vec<IpAddr> = if connected {
+                let gateways = parameters_generator.get_last_gateways().await;
+                dns::effective_dns_servers(&dns_options, &gateways)
+                    .into_iter()
+                    .map(|server| server.address)
+                    .collect()
+            } else {
+                vec![]
+            };
+            let results = dns_check::test_servers(servers, |address| {
+                blocking && !allowed_servers.contains(address)
+            })
+            .await;
+            Self::oneshot_send(tx, results, "test_custom_dns response");
+        });
+    }
+
+    fn on_check_dns_leak(&self, tx: ResponseTx<DnsLeakResult, Error>) {
+        let check = self.location_handler.check_dns_leak();
+        tokio::spawn(async move {
+            let result = check.await.map_err(Error::RestError);
+            Self::oneshot_send(tx, result, "check_dns_leak response");
+        });
+    }
+
     async fn on_set_relay_override(
         &mut self,
         tx: ResponseTx<(), settings::Error>,
@@ -2892,6 +4685,56 @@ impl Daemon {
         }
     }
 
+    async fn on_set_wireguard_keepalive(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        interval: Option<u16>,
+    ) {
+        match self
+            .settings
+            .update(move |settings| settings.tunnel_options.wireguard.keepalive = interval)
+            .await
+        {
+            Ok(settings_changed) => {
+                Self::oneshot_send(tx, Ok(()), "set_wireguard_keepalive response");
+                if settings_changed {
+                    log::info!(
+                        "Initiating tunnel restart because the WireGuard keepalive setting changed"
+                    );
+                    self.reconnect_tunnel();
+                }
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set_wireguard_keepalive response");
+            }
+        }
+    }
+
+    async fn on_set_tunnel_bind_address(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        address: Option<IpAddr>,
+    ) {
+        match self
+            .settings
+            .update(move |settings| settings.tunnel_options.wireguard.bind_address = address)
+            .await
+        {
+            Ok(settings_changed) => {
+                Self::oneshot_send(tx, Ok(()), "set_tunnel_bind_address response");
+                if settings_changed {
+                    log::info!("Initiating tunnel restart because the bind address changed");
+                    self.reconnect_tunnel();
+                }
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set_tunnel_bind_address response");
+            }
+        }
+    }
+
     async fn on_set_wireguard_rotation_interval(
         &mut self,
         tx: ResponseTx<(), settings::Error>,
@@ -2953,6 +4796,50 @@ impl Daemon {
         }
     }
 
+    fn on_validate_wireguard_allowed_ips(
+        &self,
+        tx: oneshot::Sender<Vec<AllowedIpsWarning>>,
+        allowed_ips: AllowedIps,
+    ) {
+        let dns_options = &self.settings.tunnel_options.dns_options;
+        let dns_servers = match dns_options.state {
+            DnsState::Custom => dns_options.custom_options.addresses.clone(),
+            DnsState::Default => vec![],
+        };
+
+        let tunnel_interface = self
+            .tunnel_state
+            .endpoint()
+            .and_then(|endpoint| endpoint.tunnel_interface.clone());
+        let allow_lan = self.settings.allow_lan;
+        let route_manager = self.route_manager.clone();
+        tokio::spawn(async move {
+            let routes = route_manager.get_routes().await.unwrap_or_else(|error| {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to read the routing table")
+                );
+                vec![]
+            });
+            let local_routes = routes
+                .into_iter()
+                .filter(|route| tunnel_interface.is_none() || route.interface != tunnel_interface)
+                .map(|route| route.destination)
+                .collect();
+
+            let context = allowed_ip::ValidationContext {
+                allow_lan,
+                dns_servers,
+                local_routes,
+            };
+            Self::oneshot_send(
+                tx,
+                allowed_ips.validate(&context),
+                "validate_wireguard_allowed_ips response",
+            );
+        });
+    }
+
     fn on_rotate_wireguard_key(&self, tx: ResponseTx<(), Error>) {
         let manager = self.account_manager.clone();
         tokio::spawn(async move {
@@ -2973,6 +4860,96 @@ impl Daemon {
         Self::oneshot_send(tx, result, "get_wireguard_key response");
     }
 
+    async fn on_get_wireguard_key_age(&self, tx: oneshot::Sender<Option<WireguardKeyAge>>) {
+        let key_age = match self.account_manager.data().await.map(|s| s.into_device()) {
+            Ok(Some(config)) => Some(WireguardKeyAge::new(
+                config.device.wg_data.created,
+                chrono::Utc::now(),
+            )),
+            _ => None,
+        };
+        Self::oneshot_send(tx, key_age, "get_wireguard_key_age response");
+    }
+
+    fn on_get_last_handshake_diagnostics(&self, tx: oneshot::Sender<Option<HandshakeDiagnostics>>) {
+        Self::oneshot_send(
+            tx,
+            self.tunnel_state_machine_handle
+                .last_handshake_diagnostics(),
+            "get_last_handshake_diagnostics response",
+        );
+    }
+
+    fn on_get_tunnel_mtu_info(&self, tx: oneshot::Sender<Option<TunnelMtuInfo>>) {
+        let info = match &self.tunnel_state {
+            TunnelState::Connected { endpoint, .. } => Some(tunnel_mtu::tunnel_mtu_info(
+                endpoint,
+                self.settings.tunnel_options.wireguard.mtu,
+            )),
+            _ => None,
+        };
+        Self::oneshot_send(tx, info, "get_tunnel_mtu_info response");
+    }
+
+    fn on_get_tunnel_crypto_info(&self, tx: oneshot::Sender<Option<TunnelCryptoInfo>>) {
+        let info = match &self.tunnel_state {
+            TunnelState::Connected { endpoint, .. } => Some(TunnelCryptoInfo {
+                handshake_protocol: WIREGUARD_HANDSHAKE_PROTOCOL.to_owned(),
+                quantum_resistant: endpoint.quantum_resistant,
+            }),
+            _ => None,
+        };
+        Self::oneshot_send(tx, info, "get_tunnel_crypto_info response");
+    }
+
+    fn on_get_applied_allowed_ips(&self, tx: oneshot::Sender<Option<Vec<ipnetwork::IpNetwork>>>) {
+        Self::oneshot_send(
+            tx,
+            self.tunnel_state_machine_handle.applied_allowed_ips(),
+            "get_applied_allowed_ips response",
+        );
+    }
+
+    fn on_set_log_filter(&self, tx: ResponseTx<(), Error>, log_filter: String) {
+        let result = self
+            .settings
+            .check_unlocked(LockableSetting::General)
+            .map_err(Error::SettingsError)
+            .and_then(|()| {
+                self.log_handle
+                    .set_log_filter(log_filter)
+                    .map_err(Error::SetLogFilter)
+            });
+        Self::oneshot_send(tx, result, "set_log_filter response");
+    }
+
+    fn on_collect_logs(&self, tx: ResponseTx<String, Error>, redact: bool) {
+        let Some(log_dir) = self.log_dir.clone() else {
+            Self::oneshot_send(tx, Err(Error::NoLogDirectory), "collect_logs response");
+            return;
+        };
+        let account_manager = self.account_manager.clone();
+        tokio::spawn(async move {
+            // The device key is not matched by any of the generic patterns
+            let redact_custom_strings = match account_manager.data().await {
+                Ok(state) if redact => state
+                    .into_device()
+                    .map(|device| vec![device.device.wg_data.get_public_key().to_base64()])
+                    .unwrap_or_default(),
+                _ => vec![],
+            };
+            // Reading and redacting the logs may take a while, so don't block the runtime
+            tokio::task::spawn_blocking(move || {
+                let logs = mullvad_problem_report::collect_daemon_logs(
+                    &log_dir,
+                    redact,
+                    redact_custom_strings,
+                );
+                Self::oneshot_send(tx, Ok(logs), "collect_logs response");
+            });
+        });
+    }
+
     async fn on_create_custom_list(
         &mut self,
         tx: ResponseTx<mullvad_types::custom_list::Id, Error>,
@@ -3054,6 +5031,21 @@ impl Daemon {
         Self::oneshot_send(tx, result, "clear_custom_api_access_methods response");
     }
 
+    fn on_export_api_access_methods(&self, tx: ResponseTx<String, Error>, include_secrets: bool) {
+        let result = self
+            .export_access_methods(include_secrets)
+            .map_err(Error::AccessMethodError);
+        Self::oneshot_send(tx, result, "export_api_access_methods response");
+    }
+
+    async fn on_import_api_access_methods(&mut self, tx: ResponseTx<(), Error>, json: String) {
+        let result = self
+            .import_access_methods(&json)
+            .await
+            .map_err(Error::AccessMethodError);
+        Self::oneshot_send(tx, result, "import_api_access_methods response");
+    }
+
     fn on_get_current_api_access_method(&mut self, tx: ResponseTx<AccessMethodSetting, Error>) {
         let handle = self.access_mode_handler.clone();
         tokio::spawn(async move {
@@ -3066,6 +5058,20 @@ impl Daemon {
         });
     }
 
+    fn on_get_last_successful_access_method(
+        &mut self,
+        tx: ResponseTx<Option<LastSuccessfulAccessMethod>, Error>,
+    ) {
+        let handle = self.access_mode_handler.clone();
+        tokio::spawn(async move {
+            let result = handle
+                .get_last_successful()
+                .await
+                .map_err(Error::ApiConnectionModeError);
+            Self::oneshot_send(tx, result, "get_last_successful_access_method response");
+        });
+    }
+
     fn on_test_proxy_as_access_method(
         &mut self,
         tx: ResponseTx<bool, Error>,
@@ -3097,102 +5103,432 @@ impl Daemon {
         });
     }
 
-    async fn on_test_api_access_method(
+    async fn on_test_api_access_method(
+        &mut self,
+        tx: ResponseTx<bool, Error>,
+        access_method: mullvad_types::access_method::Id,
+    ) {
+        let reply =
+            |response| Self::oneshot_send(tx, response, "on_test_api_access_method response");
+
+        let access_method = match self.get_api_access_method(access_method) {
+            Ok(x) => x,
+            Err(err) => {
+                reply(Err(Error::AccessMethodError(err)));
+                return;
+            }
+        };
+
+        let test_subject = match self
+            .access_mode_handler
+            .resolve(access_method.clone())
+            .await
+        {
+            Ok(Some(test_subject)) => test_subject,
+            Ok(None) => {
+                let error =
+                    Error::ApiConnectionModeError(mullvad_api::access_mode::Error::Resolve {
+                        access_method: access_method.access_method,
+                    });
+                reply(Err(error));
+                return;
+            }
+            Err(err) => {
+                reply(Err(Error::ApiConnectionModeError(err)));
+                return;
+            }
+        };
+
+        let api_proxy = self.create_limited_api_proxy(test_subject.connection_mode);
+        let daemon_event_sender = self.tx.to_specialized_sender();
+        let access_method_selector = self.access_mode_handler.clone();
+
+        tokio::spawn(async move {
+            let result = Self::test_access_method(
+                test_subject.endpoint,
+                access_method_selector,
+                daemon_event_sender,
+                api_proxy,
+            )
+            .await
+            .map_err(Error::AccessMethodError);
+
+            log::debug!(
+                "API access method {method} {verdict}",
+                method = test_subject.setting.name,
+                verdict = match result {
+                    Ok(true) => "could successfully connect to the Mullvad API",
+                    _ => "could not connect to the Mullvad API",
+                }
+            );
+
+            reply(result);
+        });
+    }
+
+    async fn on_test_all_api_access_methods(
+        &mut self,
+        tx: ResponseTx<Vec<AccessMethodTestResult>, Error>,
+    ) {
+        let test_subjects = self.resolve_enabled_access_methods().await;
+
+        let daemon_event_sender = self.tx.to_specialized_sender();
+        let access_method_selector = self.access_mode_handler.clone();
+
+        tokio::spawn(async move {
+            let results = Self::test_access_methods(
+                test_subjects,
+                access_method_selector,
+                daemon_event_sender,
+            )
+            .await;
+            Self::oneshot_send(tx, Ok(results), "on_test_all_api_access_methods response");
+        });
+    }
+
+    async fn on_find_working_api_access_method(
+        &mut self,
+        tx: ResponseTx<AccessMethodSearch, Error>,
+        set_active: bool,
+    ) {
+        let test_subjects = self.resolve_enabled_access_methods().await;
+
+        let daemon_event_sender = self.tx.to_specialized_sender();
+        let daemon_command_tx = self.tx.to_specialized_sender();
+        let access_method_selector = self.access_mode_handler.clone();
+
+        tokio::spawn(async move {
+            let search = Self::find_working_access_method(
+                test_subjects,
+                access_method_selector,
+                daemon_event_sender,
+            )
+            .await;
+
+            let result = match search.working.clone() {
+                Some(id) if set_active => {
+                    let (set_tx, set_rx) = oneshot::channel();
+                    let _ = daemon_command_tx.send(DaemonCommand::SetApiAccessMethod(set_tx, id));
+                    match set_rx.await {
+                        Ok(Ok(())) => Ok(search),
+                        Ok(Err(error)) => Err(error),
+                        Err(_) => Err(Error::DaemonUnavailable),
+                    }
+                }
+                _ => Ok(search),
+            };
+            Self::oneshot_send(tx, result, "on_find_working_api_access_method response");
+        });
+    }
+
+    fn on_get_settings(&self, tx: oneshot::Sender<Settings>) {
+        Self::oneshot_send(tx, self.settings.to_settings(), "get_settings response");
+    }
+
+    async fn on_verify_settings_integrity(&self, tx: oneshot::Sender<SettingsIntegrity>) {
+        let integrity = self.settings.verify_integrity().await;
+        Self::oneshot_send(tx, integrity, "verify_settings_integrity response");
+    }
+
+    fn on_get_applied_settings_migrations(&self, tx: oneshot::Sender<Vec<SettingsMigration>>) {
+        Self::oneshot_send(
+            tx,
+            self.applied_settings_migrations.clone(),
+            "get_applied_settings_migrations response",
+        );
+    }
+
+    async fn on_lock_settings(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        locked: Vec<LockableSetting>,
+        password: String,
+    ) {
+        let result = settings::lock::lock(&mut self.settings, locked, password)
+            .await
+            .map(|_| log::info!("Locked settings"));
+        Self::oneshot_send(tx, result, "lock_settings response");
+    }
+
+    async fn on_unlock_settings(&mut self, tx: ResponseTx<(), settings::Error>, password: String) {
+        let result = settings::lock::unlock(&mut self.settings, &mut self.unlock_backoff, password)
+            .await
+            .map(|settings_changed| {
+                if settings_changed {
+                    log::info!("Unlocked settings");
+                }
+            });
+        Self::oneshot_send(tx, result, "unlock_settings response");
+    }
+
+    fn on_get_settings_lock(&self, tx: oneshot::Sender<SettingsLock>) {
+        Self::oneshot_send(
+            tx,
+            self.settings.settings_lock.clone(),
+            "get_settings_lock response",
+        );
+    }
+
+    async fn on_save_profile(&mut self, tx: ResponseTx<(), Error>, name: String) {
+        let result = self.save_profile(name).await;
+        Self::oneshot_send(tx, result, "save_profile response");
+    }
+
+    async fn on_apply_profile(&mut self, tx: ResponseTx<(), Error>, name: String, reconnect: bool) {
+        let result = self.apply_profile(name, reconnect).await;
+        Self::oneshot_send(tx, result, "apply_profile response");
+    }
+
+    fn on_list_profiles(&self, tx: oneshot::Sender<ProfilesSettings>) {
+        Self::oneshot_send(tx, self.list_profiles(), "list_profiles response");
+    }
+
+    async fn on_delete_profile(&mut self, tx: ResponseTx<(), Error>, name: String) {
+        let result = self.delete_profile(name).await;
+        Self::oneshot_send(tx, result, "delete_profile response");
+    }
+
+    fn on_export_relay_link(&self, tx: ResponseTx<String, Error>) {
+        let link = RelayLink::from_settings(&self.settings)
+            .and_then(|link| link.encode())
+            .map_err(Error::RelayLinkError);
+        Self::oneshot_send(tx, link, "export_relay_link response");
+    }
+
+    async fn on_import_relay_link(&mut self, tx: ResponseTx<(), Error>, link: String) {
+        let result = self.import_relay_link(&link).await;
+        Self::oneshot_send(tx, result, "import_relay_link response");
+    }
+
+    async fn import_relay_link(&mut self, link: &str) -> Result<(), Error> {
+        let link = RelayLink::decode(link).map_err(Error::RelayLinkError)?;
+        link.validate(&self.settings.custom_lists)
+            .map_err(Error::RelayLinkError)?;
+        let settings_changed = self
+            .settings
+            .update(move |settings| link.apply_to(settings))
+            .await
+            .map_err(Error::SettingsError)?;
+        if settings_changed {
+            log::info!("Initiating tunnel restart because a relay link was imported");
+            self.reconnect_tunnel();
+        }
+        Ok(())
+    }
+
+    fn on_get_cache_usage(&self, tx: oneshot::Sender<CacheUsage>) {
+        let cache_dir = self.cache_dir.clone();
+        let log_dir = self.log_dir.clone();
+        tokio::spawn(async move {
+            let usage = cache_usage::usage(&cache_dir, log_dir.as_deref()).await;
+            Self::oneshot_send(tx, usage, "get_cache_usage response");
+        });
+    }
+
+    fn on_get_inflight_api_requests(&self, tx: oneshot::Sender<Vec<InflightApiRequest>>) {
+        let requests = self.api_handle.service().inflight_requests();
+        Self::oneshot_send(tx, requests, "get_inflight_api_requests response");
+    }
+
+    fn on_get_last_api_error(&self, tx: oneshot::Sender<Option<ApiErrorResponse>>) {
+        let error = self.api_handle.service().last_api_error();
+        Self::oneshot_send(tx, error, "get_last_api_error response");
+    }
+
+    async fn on_set_max_concurrent_api_requests(
         &mut self,
-        tx: ResponseTx<bool, Error>,
-        access_method: mullvad_types::access_method::Id,
+        tx: ResponseTx<(), settings::Error>,
+        max_requests: u32,
     ) {
-        let reply =
-            |response| Self::oneshot_send(tx, response, "on_test_api_access_method response");
-
-        let access_method = match self.get_api_access_method(access_method) {
-            Ok(x) => x,
-            Err(err) => {
-                reply(Err(Error::AccessMethodError(err)));
-                return;
+        match self
+            .settings
+            .update(move |settings| settings.max_concurrent_api_requests = max_requests)
+            .await
+        {
+            Ok(_) => {
+                self.api_handle
+                    .service()
+                    .set_max_concurrent_requests(max_requests);
+                Self::oneshot_send(tx, Ok(()), "set_max_concurrent_api_requests response");
             }
-        };
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set_max_concurrent_api_requests response");
+            }
+        }
+    }
 
-        let test_subject = match self
-            .access_mode_handler
-            .resolve(access_method.clone())
+    async fn on_set_api_sni_hostname(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        hostname: Option<String>,
+    ) {
+        let new_hostname = hostname.clone();
+        match self
+            .settings
+            .update(move |settings| settings.api_sni_hostname = new_hostname)
             .await
         {
-            Ok(Some(test_subject)) => test_subject,
-            Ok(None) => {
-                let error =
-                    Error::ApiConnectionModeError(mullvad_api::access_mode::Error::Resolve {
-                        access_method: access_method.access_method,
-                    });
-                reply(Err(error));
-                return;
+            Ok(settings_changed) => {
+                if settings_changed {
+                    self.api_handle.service().set_sni_hostname(hostname);
+                }
+                Self::oneshot_send(tx, Ok(()), "set_api_sni_hostname response");
             }
-            Err(err) => {
-                reply(Err(Error::ApiConnectionModeError(err)));
-                return;
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set_api_sni_hostname response");
             }
-        };
+        }
+    }
 
-        let api_proxy = self.create_limited_api_proxy(test_subject.connection_mode);
-        let daemon_event_sender = self.tx.to_specialized_sender();
-        let access_method_selector = self.access_mode_handler.clone();
+    fn on_get_api_request_limit(&self, tx: oneshot::Sender<ApiRequestLimit>) {
+        let limit = self.api_handle.service().request_limit();
+        Self::oneshot_send(tx, limit, "get_api_request_limit response");
+    }
 
-        tokio::spawn(async move {
-            let result = Self::test_access_method(
-                test_subject.endpoint,
-                access_method_selector,
-                daemon_event_sender,
-                api_proxy,
-            )
+    async fn on_set_relay_list_download_settings(
+        &mut self,
+        tx: ResponseTx<(), settings::Error>,
+        download_settings: RelayListDownloadSettings,
+    ) {
+        // The relay list updater is notified of `metered_network` by the settings listener
+        match self
+            .settings
+            .update(move |settings| settings.relay_list_download = download_settings)
             .await
-            .map_err(Error::AccessMethodError);
+        {
+            Ok(_) => {
+                self.api_handle
+                    .service()
+                    .set_background_download_limit(download_settings.bandwidth_limit);
+                Self::oneshot_send(tx, Ok(()), "set_relay_list_download_settings response");
+            }
+            Err(e) => {
+                log::error!("{}", e.display_chain_with_msg("Unable to save settings"));
+                Self::oneshot_send(tx, Err(e), "set_relay_list_download_settings response");
+            }
+        }
+    }
 
-            log::debug!(
-                "API access method {method} {verdict}",
-                method = test_subject.setting.name,
-                verdict = match result {
-                    Ok(true) => "could successfully connect to the Mullvad API",
-                    _ => "could not connect to the Mullvad API",
-                }
-            );
+    fn on_get_api_address_info(&self, tx: oneshot::Sender<ApiAddressInfo>) {
+        let address_cache = self.api_runtime.address_cache().clone();
+        tokio::spawn(async move {
+            let info = address_cache.get_address_info().await;
+            Self::oneshot_send(tx, info, "get_api_address_info response");
+        });
+    }
 
-            reply(result);
+    fn on_reset_api_connections(&self, tx: oneshot::Sender<()>) {
+        self.api_handle.service().reset_connections();
+        Self::oneshot_send(tx, (), "reset_api_connections response");
+    }
+
+    fn on_handle_network_change(&mut self, tx: ResponseTx<(), Error>) {
+        log::info!("Handling network change");
+        self.api_handle.service().reset();
+        let reconnect = self.tunnel_state.is_connected();
+
+        let api_proxy = mullvad_api::ApiProxy::new(self.api_handle.clone());
+        let address_cache = self.api_runtime.address_cache().clone();
+        let mut relay_list_updater = self.relay_list_updater.clone();
+        let daemon_command_tx = self.tx.to_specialized_sender();
+        tokio::spawn(async move {
+            let result = api_proxy
+                .refresh_address(&address_cache)
+                .await
+                .map(|address| log::debug!("Re-resolved API address: {address}"))
+                .map_err(Error::RefreshApiAddress);
+            // Reconnect once the address has been refreshed, so that the new connection uses the
+            // new API address
+            if reconnect {
+                let (reconnect_tx, reconnect_rx) = oneshot::channel();
+                let _ = daemon_command_tx.send(DaemonCommand::Reconnect(reconnect_tx));
+                let _ = reconnect_rx.await;
+            }
+            relay_list_updater.update().await;
+            Self::oneshot_send(tx, result, "handle_network_change response");
         });
     }
 
-    fn on_get_settings(&self, tx: oneshot::Sender<Settings>) {
-        Self::oneshot_send(tx, self.settings.to_settings(), "get_settings response");
+    fn on_check_system_clock(&self, tx: ResponseTx<ClockCheck, mullvad_api::rest::Error>) {
+        let api_proxy = mullvad_api::ApiProxy::new(self.api_handle.clone());
+        tokio::spawn(async move {
+            let request_sent = chrono::Utc::now();
+            let result = api_proxy
+                .get_server_time()
+                .await
+                .map(|api_time| ClockCheck::new(request_sent, chrono::Utc::now(), api_time));
+            Self::oneshot_send(tx, result, "check_system_clock response");
+        });
+    }
+
+    fn on_clear_cache(&self, tx: ResponseTx<(), Error>, kinds: Vec<CacheKind>) {
+        let cache_dir = self.cache_dir.clone();
+        let log_dir = self.log_dir.clone();
+        #[cfg(in_app_upgrade)]
+        let version_handle = self.version_handle.clone();
+        tokio::spawn(async move {
+            let result: Result<(), Error> = async {
+                // Remove installers first, so that nothing is removed if an upgrade is in progress
+                if kinds.contains(&CacheKind::Installers) {
+                    #[cfg(in_app_upgrade)]
+                    version_handle
+                        .clear_downloads()
+                        .await
+                        .map_err(Error::ClearInstallers)?;
+                    #[cfg(not(in_app_upgrade))]
+                    cache_usage::clear(CacheKind::Installers, &cache_dir, log_dir.as_deref())
+                        .await
+                        .map_err(Error::ClearCache)?;
+                }
+                for kind in kinds
+                    .into_iter()
+                    .filter(|kind| *kind != CacheKind::Installers)
+                {
+                    cache_usage::clear(kind, &cache_dir, log_dir.as_deref())
+                        .await
+                        .map_err(Error::ClearCache)?;
+                }
+                Ok(())
+            }
+            .await;
+            Self::oneshot_send(tx, result, "clear_cache response");
+        });
     }
 
     async fn on_reset_settings(&mut self, tx: ResponseTx<(), settings::Error>) {
         let result = self.settings.reset().await;
         Self::oneshot_send(tx, result, "reset_settings response");
 
+        self.apply_settings();
+        self.reconnect_tunnel();
+    }
+
+    /// Pass all settings on to the tunnel state machine and the API runtime, which keep their own
+    /// copies. This is needed when the settings are replaced as a whole, e.g. when they are reset
+    /// or restored from a snapshot.
+    fn apply_settings(&mut self) {
         // TODO: All of the functions below should probably be handled by settings observers
         //       whenever settings are updated. For instance, changing "allow_lan" should probably
         //       cause a tunnel command to be sent.
 
-        #[cfg(any(target_os = "windows", target_os = "macos", target_os = "android"))]
-        {
-            let (tx, _rx) = oneshot::channel();
-            self.send_tunnel_command(TunnelCommand::SetExcludedApps(tx, vec![]));
-        }
-
-        #[cfg(not(target_os = "android"))]
-        {
-            let (tx, _rx) = oneshot::channel();
-            self.send_tunnel_command(TunnelCommand::LockdownMode(
-                LockdownMode::from(self.settings.lockdown_mode),
-                tx,
-            ));
+        for command in tunnel_commands(&self.settings) {
+            self.send_tunnel_command(command);
         }
 
-        let (tx, _rx) = oneshot::channel();
-        self.send_tunnel_command(TunnelCommand::AllowLan(self.settings.allow_lan, tx));
-
-        let (tx, _rx) = oneshot::channel();
-        let dns = dns::addresses_from_options(&self.settings.tunnel_options.dns_options);
-        self.send_tunnel_command(TunnelCommand::Dns(dns, tx));
+        self.api_handle
+            .service()
+            .set_sni_hostname(self.settings.api_sni_hostname.clone());
+        self.api_handle
+            .service()
+            .set_max_concurrent_requests(self.settings.max_concurrent_api_requests);
+        self.api_handle
+            .availability
+            .set_low_power(self.settings.low_power_mode);
+        self.api_handle
+            .service()
+            .set_background_download_limit(self.settings.relay_list_download.bandwidth_limit);
 
         let version_handle = self.version_handle.clone();
         let show_beta_releases = self.settings.show_beta_releases;
@@ -3224,8 +5560,6 @@ impl Daemon {
                 );
             }
         });
-
-        self.reconnect_tunnel();
     }
 
     #[cfg(not(target_os = "android"))]
@@ -3296,7 +5630,7 @@ impl Daemon {
     ///
     /// - `shutdown`: If the daemon should shut down itself when after setting the secured target
     ///   state. set to `false` if the intention is to close the daemon process manually.
-    fn on_prepare_restart(&mut self, shutdown: bool) {
+    async fn on_prepare_restart(&mut self, shutdown: bool) {
         // TODO: See if this can be made to also shut down the daemon
         //       without causing the service to be restarted.
         #[cfg(not(target_os = "android"))]
@@ -3317,12 +5651,72 @@ impl Daemon {
             ));
         }
         self.target_state.lock();
+        self.shutdown_recorder.prepare_restart().await;
 
         if shutdown {
             let _ = self.tx.send(InternalDaemonEvent::TriggerShutdown(false));
         }
     }
 
+    fn on_get_last_restart_reason(&self, tx: oneshot::Sender<Option<RestartReason>>) {
+        let reason = self.shutdown_recorder.last_restart_reason();
+        Self::oneshot_send(tx, reason, "get_last_restart_reason response");
+    }
+
+    fn on_get_notifications(&self, tx: oneshot::Sender<Vec<Notification>>) {
+        let notifications = self.notifications.notifications();
+        Self::oneshot_send(tx, notifications, "get_notifications response");
+    }
+
+    async fn on_acknowledge_notification(&mut self, tx: ResponseTx<(), Error>, id: String) {
+        let result = if self.notifications.acknowledge(&id).await {
+            Ok(())
+        } else {
+            Err(Error::NotificationNotFound(id))
+        };
+        Self::oneshot_send(tx, result, "acknowledge_notification response");
+    }
+
+    fn on_get_usage_stats(&self, tx: oneshot::Sender<UsageStats>) {
+        let stats = self
+            .usage_stats
+            .stats(self.tunnel_state_machine_handle.tunnel_traffic());
+        Self::oneshot_send(tx, stats, "get_usage_stats response");
+    }
+
+    async fn on_reset_usage_stats(&mut self, tx: oneshot::Sender<()>) {
+        self.usage_stats
+            .reset(self.tunnel_state_machine_handle.tunnel_traffic())
+            .await;
+        Self::oneshot_send(tx, (), "reset_usage_stats response");
+    }
+
+    fn on_get_firewall_info(&self, tx: oneshot::Sender<FirewallInfo>) {
+        let info = self.tunnel_state_machine_handle.firewall_info();
+        Self::oneshot_send(tx, info, "get_firewall_info response");
+    }
+
+    #[cfg(feature = "network-simulation")]
+    fn on_set_simulated_network_conditions(
+        &mut self,
+        tx: oneshot::Sender<()>,
+        conditions: mullvad_types::network_simulation::SimulatedNetworkConditions,
+    ) {
+        self.api_handle
+            .service()
+            .set_simulated_network_conditions(conditions);
+        Self::oneshot_send(tx, (), "set_simulated_network_conditions response");
+    }
+
+    #[cfg(feature = "diagnostics")]
+    fn on_get_resource_usage(
+        &self,
+        tx: oneshot::Sender<mullvad_types::resource_usage::ResourceUsage>,
+    ) {
+        let usage = resource_usage::resource_usage();
+        Self::oneshot_send(tx, usage, "get_resource_usage response");
+    }
+
     #[cfg(target_os = "android")]
     fn on_bypass_socket(&mut self, fd: RawFd, tx: oneshot::Sender<()>) {
         match self.tunnel_state {
@@ -3384,6 +5778,36 @@ impl Daemon {
         Self::oneshot_send(tx, result, "export_json_settings response");
     }
 
+    async fn on_create_settings_snapshot(
+        &mut self,
+        tx: ResponseTx<SettingsSnapshot, settings::snapshot::Error>,
+        label: String,
+    ) {
+        let result = settings::snapshot::create(&self.cache_dir, &self.settings, &label).await;
+        Self::oneshot_send(tx, result, "create_settings_snapshot response");
+    }
+
+    async fn on_restore_settings_snapshot(
+        &mut self,
+        tx: ResponseTx<(), settings::snapshot::Error>,
+        label: String,
+    ) {
+        let result = settings::snapshot::restore(&self.cache_dir, &mut self.settings, &label).await;
+        if result.is_ok() {
+            self.apply_settings();
+            self.reconnect_tunnel();
+        }
+        Self::oneshot_send(tx, result, "restore_settings_snapshot response");
+    }
+
+    async fn on_list_settings_snapshots(
+        &mut self,
+        tx: ResponseTx<Vec<SettingsSnapshot>, settings::snapshot::Error>,
+    ) {
+        let result = settings::snapshot::list(&self.cache_dir).await;
+        Self::oneshot_send(tx, result, "list_settings_snapshots response");
+    }
+
     fn on_get_feature_indicators(&self, tx: oneshot::Sender<FeatureIndicators>) {
         let feature_indicators = match &self.tunnel_state {
             TunnelState::Connecting {
@@ -3451,6 +5875,20 @@ impl Daemon {
         };
     }
 
+    #[cfg_attr(not(in_app_upgrade), expect(clippy::unused_async))]
+    async fn on_prefetch_update(&self, tx: ResponseTx<(), version::Error>) {
+        #[cfg(in_app_upgrade)]
+        {
+            let result = self.version_handle.prefetch_update().await;
+            Self::oneshot_send(tx, result, "on_prefetch_update response");
+        }
+        #[cfg(not(in_app_upgrade))]
+        {
+            log::warn!("Ignoring prefetch command as in-app upgrades are disabled on this OS");
+            Self::oneshot_send(tx, Ok(()), "on_prefetch_update response")
+        };
+    }
+
     #[cfg_attr(not(in_app_upgrade), expect(clippy::unused_async))]
     async fn on_app_upgrade_abort(&self, tx: ResponseTx<(), version::Error>) {
         #[cfg(in_app_upgrade)]
@@ -3468,6 +5906,32 @@ impl Daemon {
         };
     }
 
+    #[cfg_attr(not(in_app_upgrade), expect(clippy::unused_async))]
+    async fn on_list_active_downloads(&self, tx: ResponseTx<Vec<ActiveDownload>, version::Error>) {
+        #[cfg(in_app_upgrade)]
+        {
+            let result = self.version_handle.list_active_downloads().await;
+            Self::oneshot_send(tx, result, "on_list_active_downloads response");
+        }
+        #[cfg(not(in_app_upgrade))]
+        Self::oneshot_send(tx, Ok(vec![]), "on_list_active_downloads response");
+    }
+
+    #[cfg_attr(not(in_app_upgrade), expect(clippy::unused_async, unused_variables))]
+    async fn on_cancel_download(&self, tx: ResponseTx<(), version::Error>, id: u32) {
+        #[cfg(in_app_upgrade)]
+        {
+            let result = self.version_handle.cancel_download(id).await;
+            Self::oneshot_send(tx, result, "on_cancel_download response");
+        }
+        #[cfg(not(in_app_upgrade))]
+        Self::oneshot_send(
+            tx,
+            Err(version::Error::NoSuchDownload),
+            "on_cancel_download response",
+        );
+    }
+
     #[cfg_attr(not(in_app_upgrade), expect(clippy::unused_async))]
     async fn on_get_app_upgrade_cache_dir(&self, tx: ResponseTx<PathBuf, version::Error>) {
         #[cfg(in_app_upgrade)]
@@ -3493,14 +5957,28 @@ impl Daemon {
     /// progress towards that state.
     /// Returns a bool representing whether a state change was initiated.
     async fn set_target_state(&mut self, new_state: TargetState) -> bool {
+        self.update_target_state(new_state, true).await
+    }
+
+    /// Change the target state. If `save` is false, the previously saved target state is used if
+    /// the daemon restarts.
+    async fn update_target_state(&mut self, new_state: TargetState, save: bool) -> bool {
         if new_state != *self.target_state || self.tunnel_state.is_in_error_state() {
             log::debug!("Target state {:?} => {:?}", *self.target_state, new_state);
 
-            self.target_state.set(new_state).await;
+            if save {
+                self.target_state.set(new_state).await;
+            } else {
+                self.target_state.set_unsaved(new_state);
+            }
+            self.disconnect_reason = None;
 
             match *self.target_state {
                 TargetState::Secured => self.connect_tunnel(),
-                TargetState::Unsecured => self.disconnect_tunnel(),
+                TargetState::Unsecured => {
+                    self.cancel_captive_portal_check();
+                    self.disconnect_tunnel();
+                }
             }
             true
         } else {
@@ -3508,10 +5986,39 @@ impl Daemon {
         }
     }
 
+    /// Disconnect without a client asking for it. `reason` is included in the disconnected tunnel
+    /// state, so that clients can tell the user why.
+    async fn disconnect_automatically(&mut self, reason: DisconnectReason) {
+        if self.is_paused() {
+            // Stay disconnected once the pause ends, also if the daemon restarts
+            self.pause.cancel();
+            self.target_state.set(TargetState::Unsecured).await;
+            self.update_disconnect_reason(Some(reason));
+        } else if self.set_target_state(TargetState::Unsecured).await {
+            self.disconnect_reason = Some(reason);
+        }
+    }
+
     fn connect_tunnel(&mut self) {
+        // Only check for captive portals when leaving the disconnected state. Once connecting,
+        // the firewall would block the probe anyway.
+        if self.settings.captive_portal_check && self.tunnel_state.is_disconnected() {
+            self.start_captive_portal_check();
+            return;
+        }
         self.send_tunnel_command(TunnelCommand::Connect);
     }
 
+    /// Defer connecting until there is no captive portal. Restarts any pending check.
+    fn start_captive_portal_check(&mut self) {
+        log::debug!("Checking for captive portal before connecting");
+        self.captive_portal_checker.start();
+    }
+
+    fn cancel_captive_portal_check(&mut self) {
+        self.captive_portal_checker.cancel();
+    }
+
     fn disconnect_tunnel(&self) {
         self.send_tunnel_command(TunnelCommand::Disconnect);
     }
@@ -3577,6 +6084,74 @@ fn oneshot_map<T1: Send + 'static, T2: Send + 'static>(
     new_tx
 }
 
+/// Return the commands that pass the settings that the tunnel state machine keeps a copy of on to
+/// it. The results of the commands are ignored.
+fn tunnel_commands(settings: &Settings) -> Vec<TunnelCommand> {
+    let mut commands = vec![];
+
+    #[cfg(any(target_os = "windows", target_os = "macos", target_os = "android"))]
+    {
+        let excluded_apps = if settings.split_tunnel.enable_exclusions {
+            settings
+                .split_tunnel
+                .apps
+                .iter()
+                .cloned()
+                .map(SplitApp::to_tunnel_command_repr)
+                .collect()
+        } else {
+            vec![]
+        };
+        let (tx, _rx) = oneshot::channel();
+        commands.push(TunnelCommand::SetExcludedApps(tx, excluded_apps));
+    }
+
+    #[cfg(target_os = "macos")]
+    commands.push(TunnelCommand::SetExcludedAppSubnets(
+        tunnel_allowed_subnets(&settings.split_tunnel.allowed_subnets),
+    ));
+
+    #[cfg(not(target_os = "android"))]
+    {
+        let (tx, _rx) = oneshot::channel();
+        commands.push(TunnelCommand::LockdownMode(
+            LockdownMode::from(settings.lockdown_mode),
+            tx,
+        ));
+    }
+
+    let (tx, _rx) = oneshot::channel();
+    commands.push(TunnelCommand::AllowLan(settings.allow_lan, tx));
+
+    commands.push(TunnelCommand::ConnectTimeout(settings.connect_timeout));
+    commands.push(TunnelCommand::MaxReconnectAttempts(
+        settings.max_reconnect_attempts,
+    ));
+
+    let (tx, _rx) = oneshot::channel();
+    let dns = dns::addresses_from_options(&settings.tunnel_options.dns_options);
+    commands.push(TunnelCommand::Dns(dns, tx));
+
+    commands
+}
+
+/// Convert the allowed subnets of split tunnel apps to the form expected by the tunnel state
+/// machine.
+#[cfg(target_os = "macos")]
+fn tunnel_allowed_subnets(
+    allowed_subnets: &HashMap<SplitApp, Vec<ipnetwork::IpNetwork>>,
+) -> split_tunnel::AllowedSubnets {
+    allowed_subnets
+        .iter()
+        .map(|(app, subnets)| {
+            (
+                PathBuf::from(app.clone().to_tunnel_command_repr()),
+                subnets.clone(),
+            )
+        })
+        .collect()
+}
+
 /// Remove any old RPC socket (if it exists).
 #[cfg(not(target_os = "windows"))]
 pub async fn cleanup_old_rpc_socket(rpc_socket_path: impl AsRef<std::path::Path>) {
@@ -3586,3 +6161,36 @@ pub async fn cleanup_old_rpc_socket(rpc_socket_path: impl AsRef<std::path::Path>
         log::error!("Failed to remove old RPC socket: {}", err);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A lockdown mode restored from a snapshot should be passed on to the tunnel
+    #[cfg(not(target_os = "android"))]
+    #[tokio::test]
+    async fn test_restored_lockdown_mode_reaches_tunnel() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut settings = SettingsPersister::load(dir.path()).await;
+        settings
+            .update(|settings| settings.lockdown_mode = true)
+            .await
+            .unwrap();
+        settings::snapshot::create(dir.path(), &settings, "lockdown")
+            .await
+            .unwrap();
+        settings
+            .update(|settings| settings.lockdown_mode = false)
+            .await
+            .unwrap();
+
+        settings::snapshot::restore(dir.path(), &mut settings, "lockdown")
+            .await
+            .unwrap();
+
+        assert!(tunnel_commands(&settings).iter().any(|command| matches!(
+            command,
+            TunnelCommand::LockdownMode(LockdownMode::Enabled { .. }, _)
+        )));
+    }
+}
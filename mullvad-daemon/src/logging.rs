@@ -1,5 +1,7 @@
 use mullvad_logging::{EnvFilter, LevelFilter, silence_crates};
+use mullvad_types::logging::{LogLevel, LogRecord};
 use std::{
+    fmt::{self, Write},
     io,
     path::PathBuf,
     sync::{
@@ -8,11 +10,15 @@ use std::{
     },
 };
 use talpid_core::logging::rotate_log;
+use tracing::{
+    Event, Subscriber,
+    field::{Field, Visit},
+};
 use tracing_appender::non_blocking;
 use tracing_subscriber::{
-    Registry,
+    Layer, Registry,
     fmt::{MakeWriter, format::FmtSpan, writer::OptionalWriter},
-    layer::SubscriberExt,
+    layer::{Context, SubscriberExt},
     reload::Handle,
     util::SubscriberInitExt,
 };
@@ -47,6 +53,10 @@ impl<'a, T: Clone + io::Write> MakeWriter<'a> for OptionalMakeWriter<T> {
 
 const DATE_TIME_FORMAT_STR: &str = "[%Y-%m-%d %H:%M:%S%.3f]";
 
+/// Number of log records that are kept for each subscriber of the record stream. If a subscriber
+/// falls further behind than this, the oldest records are dropped for that subscriber.
+const LOG_RECORD_BUFFER_SIZE: usize = 1024;
+
 /// Whether a [log] logger has been initialized.
 // the log crate doesn't provide a nice way to tell if a logger has been initialized :(
 static LOG_ENABLED: AtomicBool = AtomicBool::new(false);
@@ -60,6 +70,7 @@ pub fn is_enabled() -> bool {
 pub struct LogHandle {
     env_filter: Handle<EnvFilter, Registry>,
     log_stream: LogStreamer,
+    record_stream: tokio::sync::broadcast::Sender<LogRecord>,
     _file_appender_guard: Option<Arc<non_blocking::WorkerGuard>>,
 }
 
@@ -115,6 +126,78 @@ impl io::Write for LogStreamer {
     }
 }
 
+/// A [`Layer`] that turns log events into [`LogRecord`]s and broadcasts them.
+///
+/// To read the records, check out [`LogHandle::get_log_record_stream`].
+struct LogRecordLayer {
+    tx: tokio::sync::broadcast::Sender<LogRecord>,
+}
+
+impl<S: Subscriber> Layer<S> for LogRecordLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        // Avoid formatting the record if no-one is listening
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+        let metadata = event.metadata();
+        let mut visitor = RecordVisitor::default();
+        event.record(&mut visitor);
+        let _ = self.tx.send(LogRecord {
+            level: log_level(*metadata.level()),
+            target: visitor
+                .log_target
+                .unwrap_or_else(|| metadata.target().to_owned()),
+            message: if visitor.message.is_empty() {
+                visitor.fields.trim_start().to_owned()
+            } else {
+                visitor.message + &visitor.fields
+            },
+            timestamp: chrono::Utc::now(),
+        });
+    }
+}
+
+/// Collects the message of an event, followed by any other fields as `key=value`.
+#[derive(Default)]
+struct RecordVisitor {
+    message: String,
+    fields: String,
+    /// Target of events that originate from the `log` crate. These are emitted with the target
+    /// "log", and the real target in the `log.target` field.
+    log_target: Option<String>,
+}
+
+impl Visit for RecordVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message = value.to_owned(),
+            "log.target" => self.log_target = Some(value.to_owned()),
+            _ => self.record_debug(field, &value),
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{value:?}"),
+            // Remaining metadata of events from the `log` crate
+            name if name.starts_with("log.") => (),
+            name => {
+                let _ = write!(self.fields, " {name}={value:?}");
+            }
+        }
+    }
+}
+
+fn log_level(level: tracing::Level) -> LogLevel {
+    match level {
+        tracing::Level::ERROR => LogLevel::Error,
+        tracing::Level::WARN => LogLevel::Warn,
+        tracing::Level::INFO => LogLevel::Info,
+        tracing::Level::DEBUG => LogLevel::Debug,
+        tracing::Level::TRACE => LogLevel::Trace,
+    }
+}
+
 impl LogHandle {
     /// Adjust the log level.
     ///
@@ -132,6 +215,14 @@ impl LogHandle {
     pub fn get_log_stream(&self) -> tokio::sync::broadcast::Receiver<String> {
         self.log_stream.tx.subscribe()
     }
+
+    /// Subscribe to new log events as structured records.
+    ///
+    /// Only events that pass the filter set by [`Self::set_log_filter`] are emitted. A receiver that
+    /// falls behind loses the oldest records, see [`LOG_RECORD_BUFFER_SIZE`].
+    pub fn get_log_record_stream(&self) -> tokio::sync::broadcast::Receiver<LogRecord> {
+        self.record_stream.subscribe()
+    }
 }
 
 /// Initialize a global logger.
@@ -173,15 +264,19 @@ pub fn init_logger(
 
     let (tx, _) = tokio::sync::broadcast::channel(128);
     let log_stream = LogStreamer { tx };
+    let (record_stream, _) = tokio::sync::broadcast::channel(LOG_RECORD_BUFFER_SIZE);
 
     let (user_filter, reload_handle) = tracing_subscriber::reload::Layer::new(default_filter);
     let reload_handle = LogHandle {
         env_filter: reload_handle,
         log_stream: log_stream.clone(),
+        record_stream: record_stream.clone(),
         _file_appender_guard,
     };
 
-    let reg = tracing_subscriber::registry().with(user_filter);
+    let reg = tracing_subscriber::registry()
+        .with(user_filter)
+        .with(LogRecordLayer { tx: record_stream });
     let stdout_formatter = tracing_subscriber::fmt::layer()
         .with_ansi(true)
         .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE);
@@ -234,3 +329,75 @@ pub fn init_logger(
 
     Ok(reload_handle)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Emit the events in `f` to a [LogRecordLayer], and return the records it broadcasts
+    fn records(f: impl FnOnce()) -> Vec<LogRecord> {
+        let (tx, mut rx) = tokio::sync::broadcast::channel(LOG_RECORD_BUFFER_SIZE);
+        let subscriber = tracing_subscriber::registry().with(LogRecordLayer { tx });
+        tracing::subscriber::with_default(subscriber, f);
+        std::iter::from_fn(|| rx.try_recv().ok()).collect()
+    }
+
+    #[test]
+    fn test_log_record_layer() {
+        let records = records(|| {
+            tracing::warn!(target: "mullvad_daemon::test", "hello {}", "world");
+            tracing::trace!(target: "mullvad_daemon::test", "trace");
+        });
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].level, LogLevel::Warn);
+        assert_eq!(records[0].target, "mullvad_daemon::test");
+        assert_eq!(records[0].message, "hello world");
+        assert_eq!(records[1].level, LogLevel::Trace);
+    }
+
+    /// Fields other than the message should be appended to it, or make up the message if there
+    /// is none
+    #[test]
+    fn test_log_record_fields() {
+        let records = records(|| {
+            tracing::info!(attempt = 2, endpoint = "10.0.0.1", "Connecting");
+            tracing::info!(reason = ?Some(1));
+        });
+
+        assert_eq!(
+            records[0].message,
+            r#"Connecting attempt=2 endpoint="10.0.0.1""#
+        );
+        assert_eq!(records[1].message, "reason=Some(1)");
+    }
+
+    /// Events from the `log` crate should get their target from the `log.target` field, and the
+    /// other `log.*` fields should be left out
+    #[test]
+    fn test_log_record_from_log_crate() {
+        let records = records(|| {
+            tracing::error!(
+                target: "log",
+                log.target = "talpid_core::firewall",
+                log.module_path = "talpid_core::firewall",
+                log.line = 10,
+                "Failed to apply policy"
+            );
+        });
+
+        assert_eq!(records[0].level, LogLevel::Error);
+        assert_eq!(records[0].target, "talpid_core::firewall");
+        assert_eq!(records[0].message, "Failed to apply policy");
+    }
+
+    /// Nothing should be sent if there are no receivers
+    #[test]
+    fn test_log_record_without_receivers() {
+        let (tx, _) = tokio::sync::broadcast::channel(LOG_RECORD_BUFFER_SIZE);
+        let subscriber = tracing_subscriber::registry().with(LogRecordLayer { tx: tx.clone() });
+        tracing::subscriber::with_default(subscriber, || tracing::info!("message"));
+
+        assert_eq!(tx.len(), 0);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{DaemonCommand, DaemonCommandSender, account_history, device};
+use crate::{DaemonCommand, DaemonCommandSender, account_history, device, network_interfaces};
 use futures::{
     StreamExt,
     channel::{mpsc, oneshot},
@@ -12,40 +12,73 @@ use mullvad_management_interface::{
 use mullvad_types::relay_constraints::GeographicLocationConstraint;
 use mullvad_types::{
     account::AccountNumber,
+    cache::CacheKind,
+    constraints::Constraint,
+    features::{SplitTunnelKind, SupportedFeatures},
+    logging::{LogLevel, LogStreamEvent},
     relay_constraints::{
-        ObfuscationSettings, RelayOverride, RelaySettings, allowed_ip::AllowedIps,
+        ObfuscationSensitivity, ObfuscationSettings, RelayOverride, RelaySettings,
+        allowed_ip::AllowedIps,
     },
+    relay_link,
     relay_list::RelayList,
-    settings::{DnsOptions, Settings},
+    schedule::ConnectSchedule,
+    settings::{DnsOptions, LockableSetting, RelayListDownloadSettings, Settings},
     states::{TargetState, TunnelState},
+    trusted_network::TrustedNetworksSettings,
     version,
-    wireguard::{RotationInterval, RotationIntervalError},
+    wireguard::{KEEPALIVE_INTERVAL_RANGE, RotationInterval, RotationIntervalError},
 };
 use std::collections::BTreeSet;
 use std::{
+    net::{IpAddr, UdpSocket},
     path::PathBuf,
     str::FromStr,
     sync::{Arc, Mutex},
     time::Duration,
 };
-use talpid_types::ErrorExt;
+use talpid_types::{
+    ErrorExt,
+    net::{ObfuscationType, TransportProtocol, proxy::SHADOWSOCKS_CIPHERS},
+};
 use tokio::time::timeout;
-use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::wrappers::{ReceiverStream, UnboundedReceiverStream};
 
 const RPC_SERVER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
 
+/// Number of log records that may be queued for a `StreamLogs` client
+const LOG_RECORD_STREAM_BUFFER_SIZE: usize = 64;
+
+/// Longest path that fits in `sockaddr_un::sun_path`, excluding the terminating null byte
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const MAX_SOCKET_PATH_LEN: usize = 107;
+#[cfg(target_os = "macos")]
+const MAX_SOCKET_PATH_LEN: usize = 103;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     // Unable to start the management interface server
     #[error("Unable to start management interface server")]
     SetupError(#[source] mullvad_management_interface::Error),
+
+    /// Unix sockets cannot be bound to paths longer than the platform limit.
+    #[cfg(unix)]
+    #[error(
+        "Management interface socket path is {len} bytes long, but at most {max_len} bytes are \
+        supported: {}. Set MULLVAD_RPC_SOCKET_PATH to a shorter path", path.display()
+    )]
+    SocketPathTooLong {
+        path: PathBuf,
+        len: usize,
+        max_len: usize,
+    },
 }
 
 pub type AppUpgradeBroadcast = tokio::sync::broadcast::Sender<version::AppUpgradeEvent>;
 
 struct ManagementServiceImpl {
     daemon_tx: DaemonCommandSender,
-    subscriptions: Arc<Mutex<Vec<EventsListenerSender>>>,
+    subscriptions: Arc<Mutex<EventSubscriptions>>,
     pub app_upgrade_broadcast: AppUpgradeBroadcast,
     log_reload_handle: crate::logging::LogHandle,
 }
@@ -66,6 +99,7 @@ impl ManagementService for ManagementServiceImpl {
     type EventsListenStream = EventsListenerReceiver;
     type AppUpgradeEventsListenStream = AppUpgradeEventListenerReceiver;
     type LogListenStream = UnboundedReceiverStream<Result<types::LogMessage, Status>>;
+    type StreamLogsStream = ReceiverStream<Result<types::LogStreamEvent, Status>>;
 
     // Control and get the tunnel state
     //
@@ -96,6 +130,37 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(reconnect_issued))
     }
 
+    async fn pause_tunnel(&self, request: Request<types::Duration>) -> ServiceResult<()> {
+        let duration = Duration::try_from(request.into_inner())
+            .map_err(|_| Status::invalid_argument("invalid pause duration"))?;
+        log::debug!("pause_tunnel({} seconds)", duration.as_secs());
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::PauseTunnel(tx, duration))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn resume_tunnel(&self, _: Request<()>) -> ServiceResult<bool> {
+        log::debug!("resume_tunnel");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ResumeTunnel(tx))?;
+        let resumed = self.wait_for_result(rx).await?;
+        Ok(Response::new(resumed))
+    }
+
+    async fn connect_to_relay_once(&self, request: Request<String>) -> ServiceResult<()> {
+        let hostname = request.into_inner();
+        log::debug!("connect_to_relay_once({hostname})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ConnectToRelayOnce(tx, hostname))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
     async fn get_tunnel_state(&self, _: Request<()>) -> ServiceResult<types::TunnelState> {
         log::debug!("get_tunnel_state");
         let (tx, rx) = oneshot::channel();
@@ -104,6 +169,14 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(types::TunnelState::from(state)))
     }
 
+    async fn is_kill_switch_active(&self, _: Request<()>) -> ServiceResult<bool> {
+        log::debug!("is_kill_switch_active");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::IsKillSwitchActive(tx))?;
+        let active = self.wait_for_result(rx).await?;
+        Ok(Response::new(active))
+    }
+
     // Control the daemon and receive events
     //
 
@@ -111,7 +184,7 @@ impl ManagementService for ManagementServiceImpl {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
 
         let mut subscriptions = self.subscriptions.lock().unwrap();
-        subscriptions.push(tx);
+        subscriptions.insert(tx);
 
         Ok(Response::new(UnboundedReceiverStream::new(rx)))
     }
@@ -130,6 +203,106 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    async fn get_last_restart_reason(&self, _: Request<()>) -> ServiceResult<types::RestartReason> {
+        log::debug!("get_last_restart_reason");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetLastRestartReason(tx))?;
+        match self.wait_for_result(rx).await? {
+            Some(reason) => Ok(Response::new(types::RestartReason::from(reason))),
+            None => Err(Status::not_found("the last restart reason is unknown")),
+        }
+    }
+
+    async fn get_usage_stats(&self, _: Request<()>) -> ServiceResult<types::UsageStats> {
+        log::debug!("get_usage_stats");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetUsageStats(tx))?;
+        let stats = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::UsageStats::from(stats)))
+    }
+
+    async fn get_notifications(&self, _: Request<()>) -> ServiceResult<types::Notifications> {
+        log::debug!("get_notifications");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetNotifications(tx))?;
+        let notifications = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::Notifications {
+            notifications: notifications
+                .into_iter()
+                .map(types::Notification::from)
+                .collect(),
+        }))
+    }
+
+    async fn acknowledge_notification(&self, request: Request<String>) -> ServiceResult<()> {
+        let id = request.into_inner();
+        log::debug!("acknowledge_notification({id})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::AcknowledgeNotification(tx, id))?;
+        self.wait_for_result(rx).await?.map_err(map_daemon_error)?;
+        Ok(Response::new(()))
+    }
+
+    async fn reset_usage_stats(&self, _: Request<()>) -> ServiceResult<()> {
+        log::debug!("reset_usage_stats");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ResetUsageStats(tx))?;
+        self.wait_for_result(rx).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn get_firewall_info(&self, _: Request<()>) -> ServiceResult<types::FirewallInfo> {
+        log::debug!("get_firewall_info");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetFirewallInfo(tx))?;
+        let info = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::FirewallInfo::from(info)))
+    }
+
+    #[cfg(feature = "network-simulation")]
+    async fn set_simulated_network_conditions(
+        &self,
+        request: Request<types::SimulatedNetworkConditions>,
+    ) -> ServiceResult<()> {
+        let conditions = mullvad_types::network_simulation::SimulatedNetworkConditions::try_from(
+            request.into_inner(),
+        )
+        .map_err(map_protobuf_type_err)?;
+        log::debug!("set_simulated_network_conditions({conditions:?})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetSimulatedNetworkConditions(tx, conditions))?;
+        self.wait_for_result(rx).await?;
+        Ok(Response::new(()))
+    }
+
+    #[cfg(not(feature = "network-simulation"))]
+    async fn set_simulated_network_conditions(
+        &self,
+        _: Request<types::SimulatedNetworkConditions>,
+    ) -> ServiceResult<()> {
+        log::debug!("set_simulated_network_conditions");
+        Err(Status::unimplemented(
+            "The daemon was built without support for simulated network conditions",
+        ))
+    }
+
+    #[cfg(feature = "diagnostics")]
+    async fn get_resource_usage(&self, _: Request<()>) -> ServiceResult<types::ResourceUsage> {
+        log::debug!("get_resource_usage");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetResourceUsage(tx))?;
+        let usage = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::ResourceUsage::from(usage)))
+    }
+
+    #[cfg(not(feature = "diagnostics"))]
+    async fn get_resource_usage(&self, _: Request<()>) -> ServiceResult<types::ResourceUsage> {
+        log::debug!("get_resource_usage");
+        Err(Status::unimplemented(
+            "The daemon was built without support for diagnostics",
+        ))
+    }
+
     async fn factory_reset(&self, _: Request<()>) -> ServiceResult<()> {
         #[cfg(not(target_os = "android"))]
         {
@@ -147,6 +320,166 @@ impl ManagementService for ManagementServiceImpl {
         }
     }
 
+    async fn get_factory_reset_preview(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::FactoryResetPreview> {
+        #[cfg(not(target_os = "android"))]
+        {
+            log::debug!("get_factory_reset_preview");
+            let (tx, rx) = oneshot::channel();
+            self.send_command_to_daemon(DaemonCommand::GetFactoryResetPreview(tx))?;
+            self.wait_for_result(rx)
+                .await?
+                .map(types::FactoryResetPreview::from)
+                .map(Response::new)
+                .map_err(map_daemon_error)
+        }
+        #[cfg(target_os = "android")]
+        {
+            Ok(Response::new(types::FactoryResetPreview::default()))
+        }
+    }
+
+    async fn get_cache_usage(&self, _: Request<()>) -> ServiceResult<types::CacheUsage> {
+        log::debug!("get_cache_usage");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetCacheUsage(tx))?;
+        let usage = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::CacheUsage::from(usage)))
+    }
+
+    async fn get_inflight_api_requests(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::InflightApiRequests> {
+        log::debug!("get_inflight_api_requests");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetInflightApiRequests(tx))?;
+        let requests = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::InflightApiRequests {
+            requests: requests
+                .into_iter()
+                .map(types::InflightApiRequest::from)
+                .collect(),
+        }))
+    }
+
+    async fn get_last_api_error(&self, _: Request<()>) -> ServiceResult<types::ApiErrorResponse> {
+        log::debug!("get_last_api_error");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetLastApiError(tx))?;
+        match self.wait_for_result(rx).await? {
+            Some(error) => Ok(Response::new(types::ApiErrorResponse::from(error))),
+            None => Err(Status::not_found(
+                "no API request has failed since the daemon started",
+            )),
+        }
+    }
+
+    async fn set_max_concurrent_api_requests(&self, request: Request<u32>) -> ServiceResult<()> {
+        let max_requests = request.into_inner();
+        log::debug!("set_max_concurrent_api_requests({})", max_requests);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetMaxConcurrentApiRequests(tx, max_requests))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn set_api_sni_hostname(
+        &self,
+        request: Request<types::ApiSniHostname>,
+    ) -> ServiceResult<()> {
+        let hostname = request.into_inner().hostname;
+        log::debug!("set_api_sni_hostname({:?})", hostname);
+        if let Some(hostname) = &hostname
+            && !mullvad_api::is_valid_sni_hostname(hostname)
+        {
+            return Err(Status::invalid_argument(format!(
+                "Invalid SNI hostname \"{hostname}\": must be a DNS name, not an IP address"
+            )));
+        }
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetApiSniHostname(tx, hostname))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn set_relay_list_download_settings(
+        &self,
+        request: Request<types::RelayListDownloadSettings>,
+    ) -> ServiceResult<()> {
+        let download_settings = RelayListDownloadSettings::from(request.into_inner());
+        log::debug!("set_relay_list_download_settings({:?})", download_settings);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetRelayListDownloadSettings(
+            tx,
+            download_settings,
+        ))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn get_api_request_limit(&self, _: Request<()>) -> ServiceResult<types::ApiRequestLimit> {
+        log::debug!("get_api_request_limit");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetApiRequestLimit(tx))?;
+        let limit = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::ApiRequestLimit::from(limit)))
+    }
+
+    async fn get_api_address_source(&self, _: Request<()>) -> ServiceResult<types::ApiAddressInfo> {
+        log::debug!("get_api_address_source");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetApiAddressInfo(tx))?;
+        let info = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::ApiAddressInfo::from(info)))
+    }
+
+    async fn reset_api_connections(&self, _: Request<()>) -> ServiceResult<()> {
+        log::debug!("reset_api_connections");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ResetApiConnections(tx))?;
+        self.wait_for_result(rx).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn handle_network_change(&self, _: Request<()>) -> ServiceResult<()> {
+        log::debug!("handle_network_change");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::HandleNetworkChange(tx))?;
+        self.wait_for_result(rx).await?.map_err(map_daemon_error)?;
+        Ok(Response::new(()))
+    }
+
+    async fn check_system_clock(&self, _: Request<()>) -> ServiceResult<types::ClockCheck> {
+        log::debug!("check_system_clock");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::CheckSystemClock(tx))?;
+        let check = self
+            .wait_for_result(rx)
+            .await?
+            .map_err(|error: RestError| {
+                log::error!(
+                    "Unable to get the time from the API: {}",
+                    error.display_chain()
+                );
+                map_rest_error(&error)
+            })?;
+        Ok(Response::new(types::ClockCheck::from(check)))
+    }
+
+    async fn clear_cache(&self, request: Request<types::CacheKinds>) -> ServiceResult<()> {
+        let kinds = Vec::<CacheKind>::try_from(request.into_inner())?;
+        log::debug!("clear_cache({kinds:?})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ClearCache(tx, kinds))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
     async fn get_current_version(&self, _: Request<()>) -> ServiceResult<String> {
         log::debug!("get_current_version");
         let (tx, rx) = oneshot::channel();
@@ -155,6 +488,48 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(version))
     }
 
+    async fn get_supported_features(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::SupportedFeatures> {
+        log::debug!("get_supported_features");
+        Ok(Response::new(types::SupportedFeatures::from(
+            supported_features(),
+        )))
+    }
+
+    async fn list_network_interfaces(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::NetworkInterfaceList> {
+        log::debug!("list_network_interfaces");
+        let interfaces = network_interfaces::list_network_interfaces().map_err(|error| {
+            Status::internal(error.display_chain_with_msg("Failed to list network interfaces"))
+        })?;
+        Ok(Response::new(types::NetworkInterfaceList {
+            interfaces: interfaces
+                .into_iter()
+                .map(types::NetworkInterface::from)
+                .collect(),
+        }))
+    }
+
+    async fn get_installed_routes(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::InstalledRouteList> {
+        log::debug!("get_installed_routes");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetInstalledRoutes(tx))?;
+        let routes = self.wait_for_result(rx).await?.map_err(map_daemon_error)?;
+        Ok(Response::new(types::InstalledRouteList {
+            routes: routes
+                .into_iter()
+                .map(types::InstalledRoute::from)
+                .collect(),
+        }))
+    }
+
     async fn get_version_info(&self, _: Request<()>) -> ServiceResult<types::AppVersionInfo> {
         log::debug!("get_version_info");
 
@@ -167,6 +542,21 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
     }
 
+    async fn get_upgrade_download_size(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::UpgradeDownloadSize> {
+        log::debug!("get_upgrade_download_size");
+
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetUpgradeDownloadSize(tx))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(|size| types::UpgradeDownloadSize { size })
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
     async fn is_performing_post_upgrade(&self, _: Request<()>) -> ServiceResult<bool> {
         log::debug!("is_performing_post_upgrade");
         let (tx, rx) = oneshot::channel();
@@ -208,6 +598,143 @@ impl ManagementService for ManagementServiceImpl {
             .map(|relays| Response::new(types::RelayList::from(relays)))
     }
 
+    async fn get_relay_locations_summary(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::RelayLocationsSummary> {
+        log::debug!("get_relay_locations_summary");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetRelayLocationsSummary(tx))?;
+        self.wait_for_result(rx)
+            .await
+            .map(|summary| Response::new(types::RelayLocationsSummary::from(summary)))
+    }
+
+    async fn get_relays_in_city(
+        &self,
+        request: Request<types::CityLocation>,
+    ) -> ServiceResult<types::CityRelays> {
+        let location = request.into_inner();
+        log::debug!(
+            "get_relays_in_city({}, {})",
+            location.country_code,
+            location.city_code
+        );
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetRelaysInCity(
+            tx,
+            location.country_code,
+            location.city_code,
+        ))?;
+        match self.wait_for_result(rx).await? {
+            Some(relays) => Ok(Response::new(types::CityRelays::from(relays))),
+            None => Err(Status::not_found("there is no such city in the relay list")),
+        }
+    }
+
+    async fn check_relay_feature_support(
+        &self,
+        request: Request<types::RelaySettings>,
+    ) -> ServiceResult<types::RelayFeatureSupport> {
+        log::debug!("check_relay_feature_support");
+        let relay_settings =
+            RelaySettings::try_from(request.into_inner()).map_err(map_protobuf_type_err)?;
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::CheckRelayFeatureSupport(tx, relay_settings))?;
+        self.wait_for_result(rx)
+            .await
+            .map(|support| Response::new(types::RelayFeatureSupport::from(support)))
+    }
+
+    async fn relay_list_self_test(&self, _: Request<()>) -> ServiceResult<types::RelayListReport> {
+        log::debug!("relay_list_self_test");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::RelayListSelfTest(tx))?;
+        self.wait_for_result(rx)
+            .await
+            .map(types::RelayListReport::from)
+            .map(Response::new)
+    }
+
+    async fn get_relay_list_update_info(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::RelayListUpdateInfo> {
+        log::debug!("get_relay_list_update_info");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetRelayListUpdateInfo(tx))?;
+        self.wait_for_result(rx)
+            .await
+            .map(types::RelayListUpdateInfo::from)
+            .map(Response::new)
+    }
+
+    async fn test_relay_port(
+        &self,
+        request: Request<types::RelayPortTest>,
+    ) -> ServiceResult<types::RelayPortTestResult> {
+        let request = request.into_inner();
+        let port =
+            u16::try_from(request.port).map_err(|_| Status::invalid_argument("Invalid port"))?;
+        let protocol = types::TransportProtocol::try_from(request.protocol)
+            .map(TransportProtocol::from)
+            .map_err(|_| Status::invalid_argument("Invalid transport protocol"))?;
+        log::debug!("test_relay_port({}, {port}, {protocol})", request.hostname);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::TestRelayPort(
+            tx,
+            request.hostname,
+            port,
+            protocol,
+        ))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(types::RelayPortTestResult::from)
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn get_relay_transparency_info(
+        &self,
+        request: Request<String>,
+    ) -> ServiceResult<types::RelayTransparencyInfo> {
+        let hostname = request.into_inner();
+        log::debug!("get_relay_transparency_info({hostname})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetRelayTransparencyInfo(tx, hostname))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(types::RelayTransparencyInfo::from)
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn benchmark_obfuscation(
+        &self,
+        request: Request<types::ObfuscationBenchmarkRequest>,
+    ) -> ServiceResult<types::ObfuscationBenchmarkResults> {
+        let request = request.into_inner();
+        let methods = request
+            .methods
+            .into_iter()
+            .map(types::relay_constraints::try_selected_obfuscation_from_i32)
+            .collect::<Result<Vec<_>, _>>()?;
+        log::debug!("benchmark_obfuscation({}, {methods:?})", request.hostname);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::BenchmarkObfuscation(
+            tx,
+            request.hostname,
+            methods,
+        ))?;
+        let results = self.wait_for_result(rx).await?.map_err(map_daemon_error)?;
+        Ok(Response::new(types::ObfuscationBenchmarkResults {
+            results: results
+                .into_iter()
+                .map(types::ObfuscationBenchmarkResult::from)
+                .collect(),
+        }))
+    }
+
     async fn get_bridges(&self, _: Request<()>) -> ServiceResult<types::BridgeList> {
         log::debug!("get_bridges");
 
@@ -226,12 +753,76 @@ impl ManagementService for ManagementServiceImpl {
         let settings =
             ObfuscationSettings::try_from(request.into_inner()).map_err(map_protobuf_type_err)?;
         log::debug!("set_obfuscation_settings({:?})", settings);
+        if let Constraint::Only(server) = &settings.shadowsocks.custom_server
+            && !SHADOWSOCKS_CIPHERS.contains(&server.cipher.as_str())
+        {
+            return Err(Status::invalid_argument(format!(
+                "Unsupported Shadowsocks cipher \"{}\". Supported ciphers: {}",
+                server.cipher,
+                SHADOWSOCKS_CIPHERS.join(", ")
+            )));
+        }
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::SetObfuscationSettings(tx, settings))?;
         self.wait_for_result(rx).await??;
         Ok(Response::new(()))
     }
 
+    async fn set_obfuscation_auto_sensitivity(
+        &self,
+        request: Request<types::ObfuscationAutoSensitivity>,
+    ) -> ServiceResult<()> {
+        let sensitivity = ObfuscationSensitivity::try_from(request.into_inner())
+            .map_err(map_protobuf_type_err)?;
+        log::debug!("set_obfuscation_auto_sensitivity({sensitivity})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetObfuscationAutoSensitivity(
+            tx,
+            sensitivity,
+        ))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn get_obfuscation_state(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::ObfuscationState> {
+        log::debug!("get_obfuscation_state");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetObfuscationState(tx))?;
+        self.wait_for_result(rx)
+            .await
+            .map(|state| Response::new(types::ObfuscationState::from(state)))
+    }
+
+    async fn get_connection_attempt_history(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::ConnectionAttemptHistory> {
+        log::debug!("get_connection_attempt_history");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetConnectionAttemptHistory(tx))?;
+        let attempts = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::ConnectionAttemptHistory {
+            attempts: attempts
+                .into_iter()
+                .map(types::ConnectionAttempt::from)
+                .collect(),
+        }))
+    }
+
+    async fn get_network_probe_results(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::NetworkProbeResults> {
+        log::debug!("get_network_probe_results");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetNetworkProbeResults(tx))?;
+        let results = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::NetworkProbeResults::from(results)))
+    }
+
     // Settings
     //
 
@@ -252,6 +843,135 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    async fn verify_settings_integrity(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::SettingsIntegrity> {
+        log::debug!("verify_settings_integrity");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::VerifySettingsIntegrity(tx))?;
+        self.wait_for_result(rx)
+            .await
+            .map(types::SettingsIntegrity::from)
+            .map(Response::new)
+    }
+
+    async fn save_profile(&self, request: Request<String>) -> ServiceResult<()> {
+        let name = request.into_inner();
+        log::debug!("save_profile({name})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SaveProfile(tx, name))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn apply_profile(
+        &self,
+        request: Request<types::ApplyProfileRequest>,
+    ) -> ServiceResult<()> {
+        let request = request.into_inner();
+        log::debug!("apply_profile({}, {})", request.name, request.reconnect);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ApplyProfile(
+            tx,
+            request.name,
+            request.reconnect,
+        ))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn list_profiles(&self, _: Request<()>) -> ServiceResult<types::ConnectionProfiles> {
+        log::debug!("list_profiles");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ListProfiles(tx))?;
+        let profiles = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::ConnectionProfiles::from(&profiles)))
+    }
+
+    async fn delete_profile(&self, request: Request<String>) -> ServiceResult<()> {
+        let name = request.into_inner();
+        log::debug!("delete_profile({name})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::DeleteProfile(tx, name))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn export_relay_link(&self, _: Request<()>) -> ServiceResult<String> {
+        log::debug!("export_relay_link");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ExportRelayLink(tx))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn import_relay_link(&self, request: Request<String>) -> ServiceResult<()> {
+        log::debug!("import_relay_link");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ImportRelayLink(tx, request.into_inner()))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn get_applied_settings_migrations(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::SettingsMigrations> {
+        log::debug!("get_applied_settings_migrations");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetAppliedSettingsMigrations(tx))?;
+        let migrations = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::SettingsMigrations {
+            migrations: migrations
+                .into_iter()
+                .map(types::SettingsMigration::from)
+                .collect(),
+        }))
+    }
+
+    async fn lock_settings(
+        &self,
+        request: Request<types::LockSettingsRequest>,
+    ) -> ServiceResult<()> {
+        let request = request.into_inner();
+        let settings = Vec::<LockableSetting>::try_from(types::LockedSettings {
+            settings: request.settings,
+        })?;
+        log::debug!("lock_settings({:?})", settings);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::LockSettings(tx, settings, request.password))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn unlock_settings(&self, request: Request<String>) -> ServiceResult<()> {
+        log::debug!("unlock_settings");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::UnlockSettings(tx, request.into_inner()))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn get_locked_settings(&self, _: Request<()>) -> ServiceResult<types::LockedSettings> {
+        log::debug!("get_locked_settings");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetSettingsLock(tx))?;
+        let lock = self.wait_for_result(rx).await?;
+        let locked: Vec<_> = lock.locked().collect();
+        Ok(Response::new(types::LockedSettings::from(&locked[..])))
+    }
+
     async fn set_allow_lan(&self, request: Request<bool>) -> ServiceResult<()> {
         let allow_lan = request.into_inner();
         log::debug!("set_allow_lan({})", allow_lan);
@@ -270,6 +990,63 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    async fn set_randomize_relay_each_connect(&self, request: Request<bool>) -> ServiceResult<()> {
+        let enabled = request.into_inner();
+        log::debug!("set_randomize_relay_each_connect({})", enabled);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetRandomizeRelayEachConnect(tx, enabled))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn set_low_power_mode(&self, request: Request<bool>) -> ServiceResult<()> {
+        let enabled = request.into_inner();
+        log::debug!("set_low_power_mode({})", enabled);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetLowPowerMode(tx, enabled))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn set_captive_portal_check(&self, request: Request<bool>) -> ServiceResult<()> {
+        let enabled = request.into_inner();
+        log::debug!("set_captive_portal_check({})", enabled);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetCaptivePortalCheck(tx, enabled))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn set_connect_timeout(&self, request: Request<types::Duration>) -> ServiceResult<()> {
+        let timeout = Duration::try_from(request.into_inner())
+            .map_err(|_| Status::invalid_argument("unexpected negative connect timeout"))?;
+        log::debug!("set_connect_timeout({:?})", timeout);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetConnectTimeout(tx, timeout))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn set_max_reconnect_attempts(
+        &self,
+        request: Request<types::MaxReconnectAttempts>,
+    ) -> ServiceResult<()> {
+        let max_attempts = request.into_inner().max_attempts;
+        log::debug!("set_max_reconnect_attempts({:?})", max_attempts);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetMaxReconnectAttempts(tx, max_attempts))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn dismiss_captive_portal(&self, _: Request<()>) -> ServiceResult<()> {
+        log::debug!("dismiss_captive_portal");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::DismissCaptivePortal(tx))?;
+        self.wait_for_result(rx).await?;
+        Ok(Response::new(()))
+    }
+
     #[cfg(not(target_os = "android"))]
     async fn set_lockdown_mode(&self, request: Request<bool>) -> ServiceResult<()> {
         let lockdown_mode = request.into_inner();
@@ -298,6 +1075,44 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    async fn set_connect_schedule(
+        &self,
+        request: Request<types::ConnectSchedule>,
+    ) -> ServiceResult<()> {
+        let schedule = ConnectSchedule::try_from(request.into_inner())?;
+        log::debug!("set_connect_schedule({:?})", schedule);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetConnectSchedule(tx, schedule))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn set_trusted_networks(
+        &self,
+        request: Request<types::TrustedNetworks>,
+    ) -> ServiceResult<()> {
+        let trusted_networks = TrustedNetworksSettings::try_from(request.into_inner())?;
+        // The networks themselves are not logged, since they identify where the user has been
+        log::debug!(
+            "set_trusted_networks({} networks, disconnect: {})",
+            trusted_networks.networks.len(),
+            trusted_networks.disconnect
+        );
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetTrustedNetworks(tx, trusted_networks))?;
+        self.wait_for_result(rx).await?.map_err(map_daemon_error)?;
+        Ok(Response::new(()))
+    }
+
+    async fn add_trusted_ssid(&self, request: Request<Vec<u8>>) -> ServiceResult<()> {
+        // The SSID is not logged, since it identifies where the user has been
+        log::debug!("add_trusted_ssid");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::AddTrustedSsid(tx, request.into_inner()))?;
+        self.wait_for_result(rx).await?.map_err(map_daemon_error)?;
+        Ok(Response::new(()))
+    }
+
     async fn set_wireguard_mtu(&self, request: Request<u32>) -> ServiceResult<()> {
         let mtu = request.into_inner();
         let mtu = if mtu != 0 { Some(mtu as u16) } else { None };
@@ -308,6 +1123,49 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    async fn set_wireguard_keepalive(&self, request: Request<u32>) -> ServiceResult<()> {
+        let interval = request.into_inner();
+        let interval = if interval != 0 {
+            let interval = u16::try_from(interval)
+                .ok()
+                .filter(|interval| KEEPALIVE_INTERVAL_RANGE.contains(interval))
+                .ok_or_else(|| {
+                    Status::invalid_argument(format!(
+                        "The keepalive interval must be between {} and {} seconds",
+                        KEEPALIVE_INTERVAL_RANGE.start(),
+                        KEEPALIVE_INTERVAL_RANGE.end(),
+                    ))
+                })?;
+            Some(interval)
+        } else {
+            None
+        };
+        log::debug!("set_wireguard_keepalive({:?})", interval);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetWireguardKeepalive(tx, interval))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn set_tunnel_bind_address(&self, request: Request<String>) -> ServiceResult<()> {
+        let address = request.into_inner();
+        let address = if address.is_empty() {
+            None
+        } else {
+            Some(parse_bind_address(&address)?)
+        };
+        log::debug!("set_tunnel_bind_address({:?})", address);
+        if cfg!(target_os = "android") && address.is_some() {
+            return Err(Status::unimplemented(
+                "Binding the tunnel socket to a local address is not supported on Android",
+            ));
+        }
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetTunnelBindAddress(tx, address))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
     async fn set_enable_ipv6(&self, request: Request<bool>) -> ServiceResult<()> {
         let enable_ipv6 = request.into_inner();
         log::debug!("set_enable_ipv6({})", enable_ipv6);
@@ -331,6 +1189,20 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    async fn set_on_account_expiry(
+        &self,
+        request: Request<types::AccountExpiryAction>,
+    ) -> ServiceResult<()> {
+        let action = mullvad_types::settings::AccountExpiryAction::try_from(request.into_inner())
+            .map_err(map_protobuf_type_err)?;
+
+        log::debug!("set_on_account_expiry({action:?})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetOnAccountExpiry(tx, action))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
     #[cfg(daita)]
     async fn set_enable_daita(&self, request: Request<bool>) -> ServiceResult<()> {
         let daita_enabled = request.into_inner();
@@ -354,6 +1226,16 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    #[cfg(daita)]
+    async fn set_daita_required(&self, request: Request<bool>) -> ServiceResult<()> {
+        let daita_required = request.into_inner();
+        log::debug!("set_daita_required({daita_required})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetDaitaRequired(tx, daita_required))?;
+        self.wait_for_result(rx).await?.map(Response::new)?;
+        Ok(Response::new(()))
+    }
+
     #[cfg(daita)]
     async fn set_daita_settings(
         &self,
@@ -378,6 +1260,11 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    #[cfg(not(daita))]
+    async fn set_daita_required(&self, _: Request<bool>) -> ServiceResult<()> {
+        Ok(Response::new(()))
+    }
+
     #[cfg(not(daita))]
     async fn set_daita_settings(&self, _: Request<types::DaitaSettings>) -> ServiceResult<()> {
         Ok(Response::new(()))
@@ -386,13 +1273,76 @@ impl ManagementService for ManagementServiceImpl {
     async fn set_dns_options(&self, request: Request<types::DnsOptions>) -> ServiceResult<()> {
         let options = DnsOptions::try_from(request.into_inner()).map_err(map_protobuf_type_err)?;
         log::debug!("set_dns_options({:?})", options);
-
+        // The options are validated when the settings are updated
         let (tx, rx) = oneshot::channel();
         self.send_command_to_daemon(DaemonCommand::SetDnsOptions(tx, options))?;
         self.wait_for_result(rx).await??;
         Ok(Response::new(()))
     }
 
+    async fn get_effective_dns_servers(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::EffectiveDnsServers> {
+        log::debug!("get_effective_dns_servers");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetEffectiveDnsServers(tx))?;
+        let servers = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::EffectiveDnsServers {
+            servers: servers
+                .into_iter()
+                .map(types::EffectiveDnsServer::from)
+                .collect(),
+        }))
+    }
+
+    async fn get_effective_connection_config(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::EffectiveConnectionConfig> {
+        log::debug!("get_effective_connection_config");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetEffectiveConnectionConfig(tx))?;
+        let config = self.wait_for_result(rx).await?.map_err(map_daemon_error)?;
+        Ok(Response::new(types::EffectiveConnectionConfig::from(
+            config,
+        )))
+    }
+
+    async fn test_custom_dns(
+        &self,
+        request: Request<types::CustomDnsOptions>,
+    ) -> ServiceResult<types::DnsServerTestResults> {
+        let servers = request
+            .into_inner()
+            .addresses
+            .iter()
+            .map(|address| IpAddr::from_str(address))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Status::invalid_argument("invalid DNS server address"))?;
+        log::debug!("test_custom_dns({:?})", servers);
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::TestCustomDns(tx, servers))?;
+        let results = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::DnsServerTestResults {
+            results: results
+                .into_iter()
+                .map(types::DnsServerTestResult::from)
+                .collect(),
+        }))
+    }
+
+    async fn check_dns_leak(&self, _: Request<()>) -> ServiceResult<types::DnsLeakResult> {
+        log::debug!("check_dns_leak");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::CheckDnsLeak(tx))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(types::DnsLeakResult::from)
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
     async fn set_relay_override(
         &self,
         request: Request<types::RelayOverride>,
@@ -438,6 +1388,27 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
     }
 
+    async fn switch_account(&self, request: Request<AccountNumber>) -> ServiceResult<()> {
+        log::debug!("switch_account");
+        let account_number = request.into_inner();
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SwitchAccount(tx, account_number))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn get_saved_accounts(&self, _: Request<()>) -> ServiceResult<types::SavedAccounts> {
+        log::debug!("get_saved_accounts");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetSavedAccounts(tx))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(|numbers| Response::new(types::SavedAccounts { numbers }))
+            .map_err(map_daemon_error)
+    }
+
     async fn logout_account(&self, _: Request<()>) -> ServiceResult<()> {
         log::debug!("logout_account");
         let (tx, rx) = oneshot::channel();
@@ -448,6 +1419,34 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
     }
 
+    async fn reauthenticate(&self, _: Request<()>) -> ServiceResult<()> {
+        log::debug!("reauthenticate");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::Reauthenticate(tx))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn get_login_backoff(&self, _: Request<()>) -> ServiceResult<types::LoginBackoff> {
+        log::debug!("get_login_backoff");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetLoginBackoff(tx))?;
+        let backoff = self.wait_for_result(rx).await?.map_err(map_daemon_error)?;
+        Ok(Response::new(types::LoginBackoff::from(backoff)))
+    }
+
+    async fn clear_login_backoff(&self, _: Request<()>) -> ServiceResult<()> {
+        log::debug!("clear_login_backoff");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ClearLoginBackoff(tx))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
     async fn get_account_data(
         &self,
         request: Request<AccountNumber>,
@@ -612,6 +1611,80 @@ impl ManagementService for ManagementServiceImpl {
         }
     }
 
+    async fn get_wireguard_key_age(&self, _: Request<()>) -> ServiceResult<types::WireguardKeyAge> {
+        log::debug!("get_wireguard_key_age");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetWireguardKeyAge(tx))?;
+        match self.wait_for_result(rx).await? {
+            Some(key_age) => Ok(Response::new(types::WireguardKeyAge::from(key_age))),
+            None => Err(Status::not_found("no WireGuard key was found")),
+        }
+    }
+
+    async fn get_last_handshake_diagnostics(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::HandshakeDiagnostics> {
+        log::debug!("get_last_handshake_diagnostics");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetLastHandshakeDiagnostics(tx))?;
+        match self.wait_for_result(rx).await? {
+            Some(diagnostics) => Ok(Response::new(types::HandshakeDiagnostics::from(
+                diagnostics,
+            ))),
+            None => Err(Status::not_found("no failed handshake has been recorded")),
+        }
+    }
+
+    async fn get_tunnel_mtu_info(&self, _: Request<()>) -> ServiceResult<types::TunnelMtuInfo> {
+        log::debug!("get_tunnel_mtu_info");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetTunnelMtuInfo(tx))?;
+        match self.wait_for_result(rx).await? {
+            Some(info) => Ok(Response::new(types::TunnelMtuInfo::from(info))),
+            None => Err(Status::not_found("the tunnel is not connected")),
+        }
+    }
+
+    async fn get_tunnel_crypto_info(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::TunnelCryptoInfo> {
+        log::debug!("get_tunnel_crypto_info");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetTunnelCryptoInfo(tx))?;
+        match self.wait_for_result(rx).await? {
+            Some(info) => Ok(Response::new(types::TunnelCryptoInfo::from(info))),
+            None => Err(Status::not_found("the tunnel is not connected")),
+        }
+    }
+
+    async fn get_applied_allowed_ips(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::AllowedIpsList> {
+        log::debug!("get_applied_allowed_ips");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetAppliedAllowedIps(tx))?;
+        match self.wait_for_result(rx).await? {
+            Some(allowed_ips) => Ok(Response::new(types::AllowedIpsList {
+                values: allowed_ips.iter().map(ToString::to_string).collect(),
+            })),
+            None => Err(Status::not_found("the tunnel is not connected")),
+        }
+    }
+
+    async fn collect_logs(&self, request: Request<bool>) -> ServiceResult<String> {
+        let redact = request.into_inner();
+        log::debug!("collect_logs({redact})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::CollectLogs(tx, redact))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
     async fn set_wireguard_allowed_ips(
         &self,
         request: Request<types::AllowedIpsList>,
@@ -632,6 +1705,32 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    async fn validate_wireguard_allowed_ips(
+        &self,
+        request: Request<types::AllowedIpsList>,
+    ) -> ServiceResult<types::AllowedIpsWarnings> {
+        let allowed_ips_str = request.into_inner().values;
+        log::debug!("validate_wireguard_allowed_ips({:?})", allowed_ips_str);
+
+        let allowed_ips = AllowedIps::parse(&allowed_ips_str)
+            .map_err(|e| Status::invalid_argument(format!("Invalid allowed IPs: {e}")))?;
+        let allowed_ips = if allowed_ips.0.is_empty() {
+            AllowedIps::allow_all()
+        } else {
+            allowed_ips
+        };
+
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ValidateWireguardAllowedIps(tx, allowed_ips))?;
+        let warnings = self.wait_for_result(rx).await?;
+        Ok(Response::new(types::AllowedIpsWarnings {
+            warnings: warnings
+                .into_iter()
+                .map(types::AllowedIpsWarning::from)
+                .collect(),
+        }))
+    }
+
     // Custom lists
     //
 
@@ -780,6 +1879,24 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
     }
 
+    /// Return the access method which carried the most recent successful API request.
+    async fn get_last_successful_api_access_method(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::LastSuccessfulAccessMethod> {
+        log::debug!("get_last_successful_api_access_method");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::GetLastSuccessfulAccessMethod(tx))?;
+        match self.wait_for_result(rx).await?.map_err(map_daemon_error)? {
+            Some(last_success) => Ok(Response::new(types::LastSuccessfulAccessMethod::from(
+                last_success,
+            ))),
+            None => Err(Status::not_found(
+                "no API request has succeeded since the daemon started",
+            )),
+        }
+    }
+
     async fn test_custom_api_access_method(
         &self,
         config: Request<types::CustomProxy>,
@@ -811,6 +1928,64 @@ impl ManagementService for ManagementServiceImpl {
             .map_err(map_daemon_error)
     }
 
+    async fn test_all_api_access_methods(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::AccessMethodTestResults> {
+        log::debug!("test_all_api_access_methods");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::TestAllApiAccessMethods(tx))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(|results| types::AccessMethodTestResults {
+                results: results
+                    .into_iter()
+                    .map(types::AccessMethodTestResult::from)
+                    .collect(),
+            })
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn find_working_api_access_method(
+        &self,
+        request: Request<bool>,
+    ) -> ServiceResult<types::AccessMethodSearch> {
+        log::debug!("find_working_api_access_method");
+        let set_active = request.into_inner();
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::FindWorkingApiAccessMethod(tx, set_active))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(types::AccessMethodSearch::from)
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn export_api_access_methods(&self, request: Request<bool>) -> ServiceResult<String> {
+        log::debug!("export_api_access_methods");
+        let include_secrets = request.into_inner();
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ExportApiAccessMethods(tx, include_secrets))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
+    async fn import_api_access_methods(&self, request: Request<String>) -> ServiceResult<()> {
+        log::debug!("import_api_access_methods");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ImportApiAccessMethods(
+            tx,
+            request.into_inner(),
+        ))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
+    }
+
     // Split tunneling
     //
 
@@ -914,7 +2089,7 @@ impl ManagementService for ManagementServiceImpl {
         log::debug!("add_split_tunnel_app");
         let path = SplitApp::from(request.into_inner());
         let (tx, rx) = oneshot::channel();
-        self.send_command_to_daemon(DaemonCommand::AddSplitTunnelApp(tx, path))?;
+        self.send_command_to_daemon(DaemonCommand::AddSplitTunnelApp(tx, path, vec![]))?;
         self.wait_for_result(rx)
             .await?
             .map_err(map_daemon_error)
@@ -926,6 +2101,40 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    #[cfg(any(windows, target_os = "android", target_os = "macos"))]
+    async fn add_split_tunnel_app_with_subnets(
+        &self,
+        request: Request<types::SplitTunnelAppSubnets>,
+    ) -> ServiceResult<()> {
+        use mullvad_types::settings::SplitApp;
+        log::debug!("add_split_tunnel_app_with_subnets");
+        let (path, subnets) =
+            <(SplitApp, Vec<ipnetwork::IpNetwork>)>::try_from(request.into_inner())
+                .map_err(map_protobuf_type_err)?;
+        if !cfg!(target_os = "macos") && !subnets.is_empty() {
+            return Err(Status::unimplemented(
+                "Allowed subnets for split tunnel apps are only supported on macOS",
+            ));
+        }
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::AddSplitTunnelApp(tx, path, subnets))?;
+        self.wait_for_result(rx)
+            .await?
+            .map_err(map_daemon_error)
+            .map(Response::new)
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn add_split_tunnel_app_with_subnets(
+        &self,
+        _: Request<types::SplitTunnelAppSubnets>,
+    ) -> ServiceResult<()> {
+        log::debug!("add_split_tunnel_app_with_subnets");
+        Err(Status::unimplemented(
+            "Split tunnel apps are not supported on Linux",
+        ))
+    }
+
     #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     async fn remove_split_tunnel_app(&self, request: Request<String>) -> ServiceResult<()> {
         use mullvad_types::settings::SplitApp;
@@ -1049,6 +2258,47 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(blob))
     }
 
+    async fn create_settings_snapshot(
+        &self,
+        label: Request<String>,
+    ) -> ServiceResult<types::SettingsSnapshot> {
+        log::debug!("create_settings_snapshot");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::CreateSettingsSnapshot(
+            tx,
+            label.into_inner(),
+        ))?;
+        let snapshot = self.wait_for_result(rx).await??;
+        Ok(Response::new(types::SettingsSnapshot::from(snapshot)))
+    }
+
+    async fn restore_settings_snapshot(&self, label: Request<String>) -> ServiceResult<()> {
+        log::debug!("restore_settings_snapshot");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::RestoreSettingsSnapshot(
+            tx,
+            label.into_inner(),
+        ))?;
+        self.wait_for_result(rx).await??;
+        Ok(Response::new(()))
+    }
+
+    async fn list_settings_snapshots(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::SettingsSnapshots> {
+        log::debug!("list_settings_snapshots");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ListSettingsSnapshots(tx))?;
+        let snapshots = self.wait_for_result(rx).await??;
+        Ok(Response::new(types::SettingsSnapshots {
+            snapshots: snapshots
+                .into_iter()
+                .map(types::SettingsSnapshot::from)
+                .collect(),
+        }))
+    }
+
     #[cfg(target_os = "android")]
     async fn init_play_purchase(
         &self,
@@ -1121,10 +2371,14 @@ impl ManagementService for ManagementServiceImpl {
     }
 
     async fn set_log_filter(&self, request: Request<types::LogFilter>) -> ServiceResult<()> {
-        self.log_reload_handle
-            .set_log_filter(request.into_inner().log_filter)
-            .map_err(|error| Status::invalid_argument(error.to_string()))?;
-        Ok(Response::new(()))
+        let log_filter = request.into_inner().log_filter;
+        log::debug!("set_log_filter({log_filter})");
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::SetLogFilter(tx, log_filter))?;
+        self.wait_for_result(rx)
+            .await?
+            .map(Response::new)
+            .map_err(map_daemon_error)
     }
 
     async fn log_listen(&self, _request: Request<()>) -> ServiceResult<Self::LogListenStream> {
@@ -1149,6 +2403,49 @@ impl ManagementService for ManagementServiceImpl {
 
         Ok(Response::new(UnboundedReceiverStream::new(rx)))
     }
+
+    async fn stream_logs(
+        &self,
+        request: Request<types::StreamLogsRequest>,
+    ) -> ServiceResult<Self::StreamLogsStream> {
+        let min_level = match types::LogLevel::try_from(request.into_inner().min_level) {
+            // Stream all records that pass the log filter
+            Ok(types::LogLevel::Unspecified) => None,
+            Ok(level) => Some(LogLevel::try_from(level).map_err(map_protobuf_type_err)?),
+            Err(_) => return Err(Status::invalid_argument("invalid log level")),
+        };
+        let mut record_stream = self.log_reload_handle.get_log_record_stream();
+
+        // The channel is bounded, so that a slow client does not cause records to pile up. Once
+        // it is full, the client falls behind on `record_stream`, which drops the oldest records.
+        // The client is told how many records it lost.
+        let (tx, rx) = tokio::sync::mpsc::channel(LOG_RECORD_STREAM_BUFFER_SIZE);
+        tokio::spawn(async move {
+            loop {
+                let event = match record_stream.recv().await {
+                    Ok(record)
+                        if min_level.is_none_or(|min_level| record.level.is_enabled(min_level)) =>
+                    {
+                        LogStreamEvent::Record(record)
+                    }
+                    Ok(_) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(count)) => {
+                        LogStreamEvent::Dropped(count)
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                if tx
+                    .send(Ok(types::LogStreamEvent::from(event)))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
     // Debug features
 
     async fn disable_relay(&self, relay: Request<String>) -> ServiceResult<()> {
@@ -1229,6 +2526,19 @@ impl ManagementService for ManagementServiceImpl {
         Ok(Response::new(()))
     }
 
+    async fn prefetch_update(&self, _: Request<()>) -> ServiceResult<()> {
+        log::debug!("prefetch_update");
+
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::PrefetchUpdate(tx))?;
+
+        self.wait_for_result(rx)
+            .await?
+            .map_err(map_version_check_error)?;
+
+        Ok(Response::new(()))
+    }
+
     async fn app_upgrade_abort(&self, _: Request<()>) -> ServiceResult<()> {
         log::debug!("app_upgrade_abort");
 
@@ -1261,6 +2571,40 @@ impl ManagementService for ManagementServiceImpl {
         ))
     }
 
+    async fn list_active_downloads(
+        &self,
+        _: Request<()>,
+    ) -> ServiceResult<types::ActiveDownloadList> {
+        log::debug!("list_active_downloads");
+
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::ListActiveDownloads(tx))?;
+
+        let downloads = self
+            .wait_for_result(rx)
+            .await?
+            .map_err(map_version_check_error)?;
+        Ok(Response::new(types::ActiveDownloadList {
+            downloads: downloads
+                .into_iter()
+                .map(types::ActiveDownload::from)
+                .collect(),
+        }))
+    }
+
+    async fn cancel_download(&self, request: Request<u32>) -> ServiceResult<()> {
+        let id = request.into_inner();
+        log::debug!("cancel_download({id})");
+
+        let (tx, rx) = oneshot::channel();
+        self.send_command_to_daemon(DaemonCommand::CancelDownload(tx, id))?;
+
+        self.wait_for_result(rx)
+            .await?
+            .map_err(map_version_check_error)?;
+        Ok(Response::new(()))
+    }
+
     async fn get_app_upgrade_cache_dir(&self, _: Request<()>) -> ServiceResult<String> {
         log::debug!("get_app_upgrade_cache_dir");
 
@@ -1322,7 +2666,10 @@ impl ManagementInterfaceServer {
         app_upgrade_broadcast: AppUpgradeBroadcast,
         log_reload_handle: crate::logging::LogHandle,
     ) -> Result<ManagementInterfaceServer, Error> {
-        let subscriptions = Arc::<Mutex<Vec<EventsListenerSender>>>::default();
+        #[cfg(unix)]
+        check_socket_path_len(&rpc_socket_path)?;
+
+        let subscriptions = Arc::<Mutex<EventSubscriptions>>::default();
 
         // NOTE: It is important that the channel buffer size is kept at 0. When sending a signal
         // to abort the gRPC server, the sender can be awaited to know when the gRPC server has
@@ -1385,16 +2732,82 @@ impl ManagementInterfaceServer {
     }
 }
 
+/// The set of clients subscribed to daemon events.
+///
+/// Senders whose receivers have been dropped are pruned whenever a new subscriber is added, and
+/// their slots are reused. This keeps the set bounded even if events are rarely broadcast.
+#[derive(Default)]
+struct EventSubscriptions {
+    slots: Vec<Option<EventsListenerSender>>,
+}
+
+impl EventSubscriptions {
+    /// Add a new subscriber, reusing the slot of a dead subscription if there is one.
+    fn insert(&mut self, tx: EventsListenerSender) {
+        self.prune();
+        match self.slots.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => *slot = Some(tx),
+            None => self.slots.push(Some(tx)),
+        }
+    }
+
+    /// Send `value` to all subscribers. Subscribers that have gone away are removed.
+    fn send(&mut self, value: &types::DaemonEvent) {
+        for slot in &mut self.slots {
+            if let Some(tx) = slot
+                && tx.send(Ok(value.clone())).is_err()
+            {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Remove all senders whose receivers have been dropped, and release trailing empty slots.
+    fn prune(&mut self) {
+        for slot in &mut self.slots {
+            if slot.as_ref().is_some_and(|tx| tx.is_closed()) {
+                *slot = None;
+            }
+        }
+        while let Some(None) = self.slots.last() {
+            self.slots.pop();
+        }
+    }
+
+    /// Return the number of live subscriptions.
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.slots.iter().flatten().count()
+    }
+}
+
+/// Fail if `path` is too long to bind a unix socket to. Binding would otherwise fail with an
+/// unhelpful error.
+#[cfg(unix)]
+fn check_socket_path_len(path: &std::path::Path) -> Result<(), Error> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let len = path.as_os_str().as_bytes().len();
+    if len > MAX_SOCKET_PATH_LEN {
+        return Err(Error::SocketPathTooLong {
+            path: path.to_owned(),
+            len,
+            max_len: MAX_SOCKET_PATH_LEN,
+        });
+    }
+    Ok(())
+}
+
 /// A handle that allows broadcasting messages to all subscribers of the management interface.
 #[derive(Clone)]
 pub struct ManagementInterfaceEventBroadcaster {
-    subscriptions: Arc<Mutex<Vec<EventsListenerSender>>>,
+    subscriptions: Arc<Mutex<EventSubscriptions>>,
 }
 
 impl ManagementInterfaceEventBroadcaster {
     fn notify(&self, value: types::DaemonEvent) {
         let mut subscriptions = self.subscriptions.lock().unwrap();
-        subscriptions.retain(|tx| tx.send(Ok(value.clone())).is_ok());
+        subscriptions.send(&value);
     }
 
     /// Notify that the tunnel state changed.
@@ -1485,6 +2898,52 @@ impl ManagementInterfaceEventBroadcaster {
         })
     }
 
+    /// Notify that the account expired while connected, and what action was taken.
+    pub(crate) fn notify_account_expired(
+        &self,
+        action: mullvad_types::settings::AccountExpiryAction,
+    ) {
+        log::debug!("Broadcasting account expired event");
+        self.notify(types::DaemonEvent {
+            event: Some(daemon_event::Event::AccountExpired(
+                types::AccountExpiryAction::from(action),
+            )),
+        })
+    }
+
+    /// Notify that a hostname pattern was ignored since it did not match any relay.
+    pub(crate) fn notify_relay_selection_fallback(
+        &self,
+        fallback: mullvad_types::relay_constraints::RelaySelectionFallback,
+    ) {
+        log::debug!("Broadcasting relay selection fallback event");
+        self.notify(types::DaemonEvent {
+            event: Some(daemon_event::Event::RelaySelectionFallback(
+                types::RelaySelectionFallback::from(fallback),
+            )),
+        })
+    }
+
+    /// Notify that a captive portal was detected before connecting.
+    pub(crate) fn notify_captive_portal_detected(&self) {
+        log::debug!("Broadcasting captive portal event");
+        self.notify(types::DaemonEvent {
+            event: Some(daemon_event::Event::CaptivePortalDetected(
+                types::CaptivePortalDetected {},
+            )),
+        })
+    }
+
+    /// Notify that the device joined or left a trusted network.
+    pub(crate) fn notify_trusted_network_changed(&self, trusted: bool) {
+        log::debug!("Broadcasting trusted network event");
+        self.notify(types::DaemonEvent {
+            event: Some(daemon_event::Event::TrustedNetworkChanged(
+                types::TrustedNetworkChanged { trusted },
+            )),
+        })
+    }
+
     /// Notify that device changed (login, logout, or key rotation).
     pub(crate) fn notify_device_event(&self, device: mullvad_types::device::DeviceEvent) {
         log::debug!("Broadcasting device event");
@@ -1522,12 +2981,56 @@ impl ManagementInterfaceEventBroadcaster {
     }
 }
 
+/// Parse an address to bind the tunnel socket to. The address must be assigned to this machine,
+/// which is checked by binding a socket to it.
+fn parse_bind_address(address: &str) -> Result<IpAddr, Status> {
+    let address =
+        IpAddr::from_str(address).map_err(|_| Status::invalid_argument("invalid IP address"))?;
+    if address.is_unspecified() || address.is_loopback() || address.is_multicast() {
+        return Err(Status::invalid_argument(format!(
+            "{address} cannot be used as a bind address"
+        )));
+    }
+    UdpSocket::bind((address, 0)).map_err(|error| {
+        Status::invalid_argument(format!("{address} is not a local address: {error}"))
+    })?;
+    Ok(address)
+}
+
+/// Functionality supported by this build of the daemon on the current platform.
+fn supported_features() -> SupportedFeatures {
+    let split_tunnel = if cfg!(target_os = "linux") {
+        Some(SplitTunnelKind::ProcessIds)
+    } else if cfg!(any(target_os = "windows", target_os = "macos")) {
+        Some(SplitTunnelKind::AppPaths)
+    } else if cfg!(target_os = "android") {
+        Some(SplitTunnelKind::AppPackages)
+    } else {
+        None
+    };
+
+    SupportedFeatures {
+        daita: cfg!(daita),
+        split_tunnel,
+        obfuscation: vec![
+            ObfuscationType::Udp2Tcp,
+            ObfuscationType::Shadowsocks,
+            ObfuscationType::Quic,
+            ObfuscationType::Lwo,
+        ],
+        quantum_resistant: true,
+    }
+}
+
 /// Converts [`crate::Error`] into a tonic status.
 fn map_daemon_error(error: crate::Error) -> Status {
     use crate::Error as DaemonError;
 
     match error {
         DaemonError::RestError(error) => map_rest_error(&error),
+        DaemonError::RefreshApiAddress(mullvad_api::Error::RestError(error)) => {
+            map_rest_error(&error)
+        }
         DaemonError::SettingsError(error) => Status::from(error),
         DaemonError::AlreadyLoggedIn => Status::already_exists(error.to_string()),
         DaemonError::LoginError(error) => map_device_error(&error),
@@ -1539,15 +3042,55 @@ fn map_daemon_error(error: crate::Error) -> Status {
         DaemonError::VoucherSubmission(error) => map_device_error(&error),
         #[cfg(any(target_os = "windows", target_os = "macos"))]
         DaemonError::SplitTunnelError(error) => map_split_tunnel_error(error),
+        #[cfg(target_os = "linux")]
+        DaemonError::SplitTunnelError(error) => Status::failed_precondition(error.to_string()),
         DaemonError::AccountHistory(error) => map_account_history_error(error),
         DaemonError::NoAccountNumber | DaemonError::NoAccountNumberHistory => {
             Status::unauthenticated(error.to_string())
         }
         DaemonError::VersionCheckError(error) => map_version_check_error(error),
+        DaemonError::RelayNotFound(_) | DaemonError::NotificationNotFound(_) => {
+            Status::not_found(error.to_string())
+        }
+        DaemonError::ConnectionConfig(ref inner) => map_connection_config_error(inner),
+        DaemonError::Offline => Status::unavailable(error.to_string()),
+        DaemonError::UnknownSsidHash => Status::invalid_argument(error.to_string()),
+        DaemonError::TrustedNetworkUnsupported => Status::unimplemented(error.to_string()),
+        DaemonError::NoLogDirectory
+        | DaemonError::PauseNotSecured
+        | DaemonError::ObfuscationBenchmarkInProgress
+        | DaemonError::RelayPortTestNotDisconnected => {
+            Status::failed_precondition(error.to_string())
+        }
+        DaemonError::InvalidPauseDuration
+        | DaemonError::InvalidObfuscationBenchmark
+        | DaemonError::SetLogFilter(_) => Status::invalid_argument(error.to_string()),
+        DaemonError::ClearInstallers(error @ crate::version::Error::UpgradeInProgress) => {
+            Status::failed_precondition(error.to_string())
+        }
+        DaemonError::AccessMethodError(
+            error @ (crate::access_method::Error::Serialization(_)
+            | crate::access_method::Error::InvalidImport { .. }),
+        ) => Status::invalid_argument(error.to_string()),
+        DaemonError::RelayLinkError(error @ relay_link::Error::CustomTunnelEndpoint) => {
+            Status::failed_precondition(error.to_string())
+        }
+        DaemonError::RelayLinkError(error) => Status::invalid_argument(error.to_string()),
         error => Status::unknown(error.to_string()),
     }
 }
 
+/// Converts [`crate::tunnel::Error`] into a tonic status.
+fn map_connection_config_error(error: &crate::tunnel::Error) -> Status {
+    use crate::tunnel::Error;
+
+    match error {
+        Error::NoAuthDetails => Status::unauthenticated(error.to_string()),
+        Error::SelectRelay(_) => Status::not_found(error.to_string()),
+        Error::ResolveCustomHostname | Error::Device(_) => Status::unknown(error.to_string()),
+    }
+}
+
 #[cfg(windows)]
 /// Converts [`talpid_core::split_tunnel::Error`] into a tonic status.
 fn map_split_tunnel_error(error: talpid_core::split_tunnel::Error) -> Status {
@@ -1574,16 +3117,16 @@ fn map_split_tunnel_error(error: talpid_core::split_tunnel::Error) -> Status {
 /// Converts a REST API error into a tonic status.
 fn map_rest_error(error: &RestError) -> Status {
     match error {
-        RestError::ApiError(status, message)
+        RestError::ApiError(status, message, _)
             if *status == StatusCode::UNAUTHORIZED || *status == StatusCode::FORBIDDEN =>
         {
             Status::new(Code::Unauthenticated, message)
         }
-        RestError::ApiError(status, message) if *status == StatusCode::BAD_REQUEST => {
+        RestError::ApiError(status, message, _) if *status == StatusCode::BAD_REQUEST => {
             Status::new(Code::InvalidArgument, message)
         }
         // FIXME: do not use Code for this
-        RestError::ApiError(status, _) if *status == StatusCode::TOO_MANY_REQUESTS => Status::new(
+        RestError::ApiError(status, ..) if *status == StatusCode::TOO_MANY_REQUESTS => Status::new(
             Code::ResourceExhausted,
             StatusCode::TOO_MANY_REQUESTS.to_string(),
         ),
@@ -1606,6 +3149,7 @@ fn map_device_error(error: &device::Error) -> Status {
         device::Error::UsedVoucher => Status::new(Code::ResourceExhausted, USED_VOUCHER_MESSAGE),
         device::Error::DeviceIoError(_error) => Status::new(Code::Unavailable, error.to_string()),
         device::Error::OtherRestError(error) => map_rest_error(error),
+        device::Error::LoginBackoff(_) => Status::unavailable(error.to_string()),
         _ => Status::new(Code::Unknown, error.to_string()),
     }
 }
@@ -1627,6 +3171,10 @@ fn map_version_check_error(error: crate::version::Error) -> Status {
         crate::version::Error::Download(..)
         | crate::version::Error::ReadVersionCache(..)
         | crate::version::Error::ApiCheck(..) => Status::unavailable(error.to_string()),
+        crate::version::Error::LowPowerMode | crate::version::Error::MeteredNetwork => {
+            Status::failed_precondition(error.to_string())
+        }
+        crate::version::Error::NoSuchDownload => Status::not_found(error.to_string()),
         _ => Status::unknown(error.to_string()),
     }
 }
@@ -1636,3 +3184,68 @@ fn map_protobuf_type_err(err: types::FromProtobufTypeError) -> Status {
         types::FromProtobufTypeError::InvalidArgument(err) => Status::invalid_argument(err),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket_path_too_long() {
+        use std::path::Path;
+
+        let path = Path::new("/tmp").join("a".repeat(MAX_SOCKET_PATH_LEN));
+        assert!(matches!(
+            check_socket_path_len(&path),
+            Err(Error::SocketPathTooLong { len, .. }) if len == MAX_SOCKET_PATH_LEN + 5
+        ));
+
+        let path = Path::new("/").join("a".repeat(MAX_SOCKET_PATH_LEN - 1));
+        assert!(check_socket_path_len(&path).is_ok());
+    }
+
+    /// Dropped subscribers should be pruned when a new subscriber is added, even if no events
+    /// have been broadcast in the meantime.
+    #[test]
+    fn test_prune_dead_subscriptions() {
+        let mut subscriptions = EventSubscriptions::default();
+
+        let receivers: Vec<_> = (0..100)
+            .map(|_| {
+                let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+                subscriptions.insert(tx);
+                rx
+            })
+            .collect();
+        assert_eq!(subscriptions.slots.len(), 100);
+
+        drop(receivers);
+
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        subscriptions.insert(tx);
+
+        assert_eq!(subscriptions.len(), 1);
+        assert_eq!(subscriptions.slots.len(), 1);
+    }
+
+    /// Slots of dead subscribers should be reused by new subscribers.
+    #[test]
+    fn test_reuse_subscription_slots() {
+        let mut subscriptions = EventSubscriptions::default();
+
+        let (tx, _rx_first) = tokio::sync::mpsc::unbounded_channel();
+        subscriptions.insert(tx);
+        let (tx, rx_second) = tokio::sync::mpsc::unbounded_channel();
+        subscriptions.insert(tx);
+        let (tx, _rx_third) = tokio::sync::mpsc::unbounded_channel();
+        subscriptions.insert(tx);
+
+        drop(rx_second);
+
+        let (tx, _rx_fourth) = tokio::sync::mpsc::unbounded_channel();
+        subscriptions.insert(tx);
+
+        assert_eq!(subscriptions.len(), 3);
+        assert_eq!(subscriptions.slots.len(), 3);
+    }
+}
@@ -31,6 +31,7 @@
 //! 1. Implement the migration and add adequate tests.
 //! 1. Add to the changelog: "Settings format updated to `vY`"
 
+use mullvad_types::settings::SettingsMigration;
 use std::{
     path::Path,
     sync::{
@@ -125,13 +126,56 @@ impl MigrationComplete {
 /// Contains discarded data that may be useful for later work.
 pub type MigrationData = v5::MigrationData;
 
+/// The result of migrating the settings
+#[derive(Default)]
+pub struct MigrationOutcome {
+    pub data: Option<MigrationData>,
+    /// Steps that changed the settings version, in the order they were applied
+    pub applied: Vec<SettingsMigration>,
+}
+
+/// Records every step that changes the settings version
+struct MigrationRecorder {
+    version: u32,
+    applied: Vec<SettingsMigration>,
+}
+
+impl MigrationRecorder {
+    fn new(settings: &serde_json::Value) -> Self {
+        MigrationRecorder {
+            version: settings_version(settings),
+            applied: vec![],
+        }
+    }
+
+    fn record(&mut self, settings: &serde_json::Value) {
+        let version = settings_version(settings);
+        if version != self.version {
+            self.applied.push(SettingsMigration {
+                from_version: self.version,
+                to_version: version,
+            });
+            self.version = version;
+        }
+    }
+}
+
+/// The first settings format has no version
+fn settings_version(settings: &serde_json::Value) -> u32 {
+    settings
+        .get("settings_version")
+        .and_then(|version| version.as_u64())
+        .map(|version| version as u32)
+        .unwrap_or(1)
+}
+
 /// Directories that may be passed to the migration logic.
 pub struct Directories<'path> {
     cache_dir: &'path Path,
     settings_dir: &'path Path,
 }
 
-pub async fn migrate_all(cache_dir: &Path, settings_dir: &Path) -> Result<Option<MigrationData>> {
+pub async fn migrate_all(cache_dir: &Path, settings_dir: &Path) -> Result<MigrationOutcome> {
     #[cfg(windows)]
     windows::migrate_after_windows_update(settings_dir)
         .await
@@ -140,7 +184,7 @@ pub async fn migrate_all(cache_dir: &Path, settings_dir: &Path) -> Result<Option
     let path = settings_dir.join(SETTINGS_FILE);
 
     if !path.is_file() {
-        return Ok(None);
+        return Ok(MigrationOutcome::default());
     }
 
     let settings_bytes = fs::read(&path).await.map_err(Error::Read)?;
@@ -154,11 +198,11 @@ pub async fn migrate_all(cache_dir: &Path, settings_dir: &Path) -> Result<Option
         settings_dir,
     };
 
-    let migration_data = migrate_settings(Some(directories), &mut settings).await?;
+    let outcome = migrate_settings(Some(directories), &mut settings).await?;
 
     if settings == old_settings {
         // Nothing changed
-        return Ok(migration_data);
+        return Ok(outcome);
     }
 
     let buffer = serde_json::to_string_pretty(&settings).map_err(Error::Serialize)?;
@@ -177,21 +221,27 @@ pub async fn migrate_all(cache_dir: &Path, settings_dir: &Path) -> Result<Option
 
     log::debug!("Migrated settings. Wrote settings to {}", path.display());
 
-    Ok(migration_data)
+    Ok(outcome)
 }
 
 async fn migrate_settings(
     directories: Option<Directories<'_>>,
     settings: &mut serde_json::Value,
-) -> Result<Option<MigrationData>> {
+) -> Result<MigrationOutcome> {
     if !settings.is_object() {
         return Err(Error::InvalidSettingsContent);
     }
 
+    let mut recorder = MigrationRecorder::new(settings);
+
     v1::migrate(settings)?;
+    recorder.record(settings);
     v2::migrate(settings)?;
+    recorder.record(settings);
     v3::migrate(settings)?;
+    recorder.record(settings);
     v4::migrate(settings)?;
+    recorder.record(settings);
 
     if let Some(Directories {
         cache_dir,
@@ -203,9 +253,13 @@ async fn migrate_settings(
     }
 
     let migration_data = v5::migrate(settings)?;
+    recorder.record(settings);
     v6::migrate(settings)?;
+    recorder.record(settings);
     v7::migrate(settings)?;
+    recorder.record(settings);
     v8::migrate(settings)?;
+    recorder.record(settings);
 
     v9::migrate(
         settings,
@@ -214,14 +268,23 @@ async fn migrate_settings(
             settings: directories.settings_dir,
         }),
     )?;
+    recorder.record(settings);
 
     v10::migrate(settings)?;
+    recorder.record(settings);
     v11::migrate(settings)?;
+    recorder.record(settings);
     v12::migrate(settings)?;
+    recorder.record(settings);
     v13::migrate(settings)?;
+    recorder.record(settings);
     v14::migrate(settings)?;
+    recorder.record(settings);
 
-    Ok(migration_data)
+    Ok(MigrationOutcome {
+        data: migration_data,
+        applied: recorder.applied,
+    })
 }
 
 pub(crate) fn migrate_device(
@@ -464,11 +527,12 @@ mod test {
         let default_settings = serde_json::to_value(Settings::default()).unwrap();
         let mut migrated_settings = default_settings.clone();
 
-        migrate_settings(None, &mut migrated_settings)
+        let outcome = migrate_settings(None, &mut migrated_settings)
             .await
             .unwrap();
 
         assert_eq!(default_settings, migrated_settings);
+        assert!(outcome.applied.is_empty());
     }
 
     /// Ensure that the settings version is correct after running all migration code
@@ -476,8 +540,19 @@ mod test {
     async fn test_all_migrations() {
         const V1_SETTINGS: &str = include_str!("v1_settings.json");
         let mut settings = serde_json::from_str(V1_SETTINGS).unwrap();
-        migrate_settings(None, &mut settings).await.unwrap();
+        let outcome = migrate_settings(None, &mut settings).await.unwrap();
         let deserialized: Settings = serde_json::from_value(settings).unwrap();
         assert_eq!(deserialized.settings_version, CURRENT_SETTINGS_VERSION);
+
+        // Every step is recorded, from the unversioned format to the current one
+        let steps: Vec<_> = outcome
+            .applied
+            .iter()
+            .map(|step| (step.from_version, step.to_version))
+            .collect();
+        let expected: Vec<_> = (1..CURRENT_SETTINGS_VERSION as u32)
+            .map(|version| (version, version + 1))
+            .collect();
+        assert_eq!(steps, expected);
     }
 }
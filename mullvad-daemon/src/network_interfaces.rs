@@ -0,0 +1,124 @@
+//! Lists the network interfaces on the host.
+
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+};
+use talpid_types::net::NetworkInterface;
+
+/// Any non-local address will do. No traffic is sent to it.
+const ROUTE_PROBE_V4: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+const ROUTE_PROBE_V6: Ipv6Addr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+
+/// Return all network interfaces, sorted by name. An interface is considered to be the default
+/// route if it holds the source address that the OS picks for traffic to the internet. While
+/// connected, this is the tunnel interface.
+pub fn list_network_interfaces() -> io::Result<Vec<NetworkInterface>> {
+    let default_addresses: Vec<IpAddr> = [IpAddr::V4(ROUTE_PROBE_V4), IpAddr::V6(ROUTE_PROBE_V6)]
+        .into_iter()
+        .filter_map(source_address)
+        .collect();
+
+    let mut interfaces: Vec<_> = imp::list_interfaces()?
+        .into_iter()
+        .map(|mut interface| {
+            interface.default_route = interface
+                .addresses
+                .iter()
+                .any(|address| default_addresses.contains(address));
+            interface
+        })
+        .collect();
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(interfaces)
+}
+
+/// Return the local address that the OS would use to reach `destination`. Connecting a UDP socket
+/// only performs a route lookup, so nothing is sent.
+fn source_address(destination: IpAddr) -> Option<IpAddr> {
+    let bind_address = match destination {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    let socket = UdpSocket::bind((bind_address, 0)).ok()?;
+    socket.connect(SocketAddr::new(destination, 53)).ok()?;
+    Some(socket.local_addr().ok()?.ip())
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod imp {
+    use std::io;
+    use talpid_types::net::NetworkInterface;
+
+    pub fn list_interfaces() -> io::Result<Vec<NetworkInterface>> {
+        let interfaces = talpid_net::unix::list_interfaces().map_err(io::Error::from)?;
+        Ok(interfaces
+            .into_iter()
+            .map(|interface| NetworkInterface {
+                name: interface.name,
+                addresses: interface.addresses,
+                up: interface.up,
+                default_route: false,
+            })
+            .collect())
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::io;
+    use talpid_types::net::NetworkInterface;
+    use talpid_windows::net::{
+        AddressFamily, alias_from_luid, get_ip_interface_entry, get_unicast_table,
+        try_socketaddr_from_inet_sockaddr,
+    };
+
+    /// Only interfaces with at least one unicast address are returned.
+    pub fn list_interfaces() -> io::Result<Vec<NetworkInterface>> {
+        let mut interfaces: Vec<NetworkInterface> = vec![];
+        for row in get_unicast_table(None)? {
+            let Ok(address) = try_socketaddr_from_inet_sockaddr(row.Address) else {
+                continue;
+            };
+            let address = address.ip();
+            let name = alias_from_luid(&row.InterfaceLuid)?
+                .to_string_lossy()
+                .into_owned();
+
+            match interfaces
+                .iter_mut()
+                .find(|interface| interface.name == name)
+            {
+                Some(interface) => interface.addresses.push(address),
+                None => {
+                    let family = if address.is_ipv4() {
+                        AddressFamily::Ipv4
+                    } else {
+                        AddressFamily::Ipv6
+                    };
+                    let up = get_ip_interface_entry(family, &row.InterfaceLuid)
+                        .map(|entry| entry.Connected != 0)
+                        .unwrap_or(false);
+                    interfaces.push(NetworkInterface {
+                        name,
+                        addresses: vec![address],
+                        up,
+                        default_route: false,
+                    });
+                }
+            }
+        }
+        Ok(interfaces)
+    }
+}
+
+#[cfg(target_os = "android")]
+mod imp {
+    use std::io;
+    use talpid_types::net::NetworkInterface;
+
+    /// Interfaces are managed by the Android app, so none are listed.
+    pub fn list_interfaces() -> io::Result<Vec<NetworkInterface>> {
+        Ok(vec![])
+    }
+}
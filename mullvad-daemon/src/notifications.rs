@@ -0,0 +1,383 @@
+//! Keeps track of conditions that the user should be notified about, and which notifications the
+//! user has acknowledged. Acknowledgements are persisted in the settings directory, so that
+//! dismissed notifications do not reappear after a restart.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mullvad_types::{
+    notification::{Notification, NotificationKind, NotificationSeverity},
+    version::AppVersionInfo,
+};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+use talpid_types::ErrorExt;
+use tokio::{fs, io};
+
+use crate::device::PrivateDeviceEvent;
+
+const ACKNOWLEDGED_NOTIFICATIONS_FILE: &str = "acknowledged-notifications.json";
+
+/// Notify the user this long before the account runs out of time.
+const ACCOUNT_EXPIRY_WARNING: TimeDelta = TimeDelta::days(3);
+
+const DEVICE_REVOKED_ID: &str = "device-revoked";
+const ACCOUNT_EXPIRED_PREFIX: &str = "account-expired:";
+const ACCOUNT_EXPIRING_SOON_PREFIX: &str = "account-expiring-soon:";
+const UNSUPPORTED_VERSION_PREFIX: &str = "unsupported-version:";
+const UPDATE_AVAILABLE_PREFIX: &str = "update-available:";
+
+/// Keeps track of active notifications and the IDs of acknowledged ones.
+pub struct NotificationTracker {
+    path: PathBuf,
+    acknowledged: BTreeSet<String>,
+    account_expiry: Option<DateTime<Utc>>,
+    device_revoked: bool,
+    app_version_info: Option<AppVersionInfo>,
+}
+
+impl NotificationTracker {
+    pub async fn new(settings_dir: &Path, device_revoked: bool) -> Self {
+        let path = settings_dir.join(ACKNOWLEDGED_NOTIFICATIONS_FILE);
+        let acknowledged = Self::read_acknowledged(&path).await;
+        let mut tracker = NotificationTracker {
+            path,
+            acknowledged,
+            account_expiry: None,
+            device_revoked,
+            app_version_info: None,
+        };
+        if tracker.prune() {
+            tracker.save().await;
+        }
+        tracker
+    }
+
+    async fn read_acknowledged(path: &Path) -> BTreeSet<String> {
+        match fs::read_to_string(path).await {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|error| {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to parse acknowledged notifications")
+                );
+                BTreeSet::new()
+            }),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => BTreeSet::new(),
+            Err(error) => {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to read acknowledged notifications")
+                );
+                BTreeSet::new()
+            }
+        }
+    }
+
+    pub async fn on_account_expiry(&mut self, expiry: DateTime<Utc>) {
+        self.account_expiry = Some(expiry);
+        if self.prune() {
+            self.save().await;
+        }
+    }
+
+    pub async fn on_device_event(&mut self, event: &PrivateDeviceEvent) {
+        match event {
+            PrivateDeviceEvent::Login(_) | PrivateDeviceEvent::Logout => {
+                self.account_expiry = None;
+                self.device_revoked = false;
+                // Notify again the next time the device is revoked, and about the expiry of the
+                // next account
+                let len = self.acknowledged.len();
+                self.acknowledged.retain(|id| {
+                    id != DEVICE_REVOKED_ID
+                        && !id.starts_with(ACCOUNT_EXPIRED_PREFIX)
+                        && !id.starts_with(ACCOUNT_EXPIRING_SOON_PREFIX)
+                });
+                if self.acknowledged.len() != len {
+                    self.save().await;
+                }
+            }
+            PrivateDeviceEvent::Revoked => {
+                self.account_expiry = None;
+                self.device_revoked = true;
+            }
+            _ => (),
+        }
+    }
+
+    pub async fn on_app_version_info(&mut self, app_version_info: AppVersionInfo) {
+        self.app_version_info = Some(app_version_info);
+        if self.prune() {
+            self.save().await;
+        }
+    }
+
+    /// Return where the acknowledged notifications are saved.
+    #[cfg(not(target_os = "android"))]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Delete the acknowledged notifications from memory and disk.
+    pub async fn remove(&mut self) -> io::Result<()> {
+        self.acknowledged.clear();
+        match fs::remove_file(&self.path).await {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    /// Forget acknowledged notifications that can no longer become active. Returns whether any
+    /// were removed.
+    fn prune(&mut self) -> bool {
+        let len = self.acknowledged.len();
+        let stale: Vec<_> = self
+            .acknowledged
+            .iter()
+            .filter(|id| self.is_stale(id))
+            .cloned()
+            .collect();
+        for id in stale {
+            self.acknowledged.remove(&id);
+        }
+        self.acknowledged.len() != len
+    }
+
+    /// Whether the notification with the given ID belongs to an account expiry or app version
+    /// other than the current one. Only what is known so far is taken into account.
+    fn is_stale(&self, id: &str) -> bool {
+        if let Some(version) = id.strip_prefix(UNSUPPORTED_VERSION_PREFIX) {
+            return version != mullvad_version::VERSION;
+        }
+        #[cfg(not(target_os = "android"))]
+        if let Some(version) = id.strip_prefix(UPDATE_AVAILABLE_PREFIX) {
+            return self.app_version_info.as_ref().is_some_and(|info| {
+                info.suggested_upgrade
+                    .as_ref()
+                    .is_none_or(|upgrade| upgrade.version.to_string() != version)
+            });
+        }
+        let expiry = id
+            .strip_prefix(ACCOUNT_EXPIRED_PREFIX)
+            .or_else(|| id.strip_prefix(ACCOUNT_EXPIRING_SOON_PREFIX));
+        if let (Some(timestamp), Some(account_expiry)) = (expiry, self.account_expiry) {
+            return timestamp != account_expiry.timestamp().to_string();
+        }
+        false
+    }
+
+    /// Return all notifications that apply now and have not been acknowledged, most severe first.
+    pub fn notifications(&self) -> Vec<Notification> {
+        let mut notifications: Vec<_> = self
+            .active_notifications(Utc::now())
+            .into_iter()
+            .filter(|notification| !self.acknowledged.contains(&notification.id))
+            .collect();
+        notifications.sort_by(|a, b| b.severity.cmp(&a.severity));
+        notifications
+    }
+
+    /// Dismiss the notification with the given ID. Returns `false` if there is no such
+    /// notification.
+    pub async fn acknowledge(&mut self, id: &str) -> bool {
+        let is_active = self
+            .active_notifications(Utc::now())
+            .iter()
+            .any(|notification| notification.id == id);
+        if !is_active {
+            return false;
+        }
+        if self.acknowledged.insert(id.to_owned()) {
+            self.save().await;
+        }
+        true
+    }
+
+    fn active_notifications(&self, now: DateTime<Utc>) -> Vec<Notification> {
+        let mut notifications = vec![];
+
+        if self.device_revoked {
+            notifications.push(Notification {
+                id: DEVICE_REVOKED_ID.to_owned(),
+                kind: NotificationKind::DeviceRevoked,
+                severity: NotificationSeverity::Critical,
+            });
+        }
+
+        if let Some(expiry) = self.account_expiry {
+            if expiry <= now {
+                notifications.push(Notification {
+                    id: format!("{ACCOUNT_EXPIRED_PREFIX}{}", expiry.timestamp()),
+                    kind: NotificationKind::AccountExpired { expiry },
+                    severity: NotificationSeverity::Critical,
+                });
+            } else if expiry - now <= ACCOUNT_EXPIRY_WARNING {
+                notifications.push(Notification {
+                    id: format!("{ACCOUNT_EXPIRING_SOON_PREFIX}{}", expiry.timestamp()),
+                    kind: NotificationKind::AccountExpiringSoon { expiry },
+                    severity: NotificationSeverity::Warning,
+                });
+            }
+        }
+
+        if let Some(app_version_info) = &self.app_version_info {
+            if !app_version_info.current_version_supported {
+                notifications.push(Notification {
+                    id: format!("{UNSUPPORTED_VERSION_PREFIX}{}", mullvad_version::VERSION),
+                    kind: NotificationKind::UnsupportedVersion,
+                    severity: NotificationSeverity::Critical,
+                });
+            }
+            #[cfg(not(target_os = "android"))]
+            if let Some(upgrade) = &app_version_info.suggested_upgrade {
+                let version = upgrade.version.to_string();
+                notifications.push(Notification {
+                    id: format!("{UPDATE_AVAILABLE_PREFIX}{version}"),
+                    kind: NotificationKind::UpdateAvailable { version },
+                    severity: NotificationSeverity::Info,
+                });
+            }
+        }
+
+        notifications
+    }
+
+    async fn save(&self) {
+        let data = match serde_json::to_string(&self.acknowledged) {
+            Ok(data) => data,
+            Err(error) => {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to serialize acknowledged notifications")
+                );
+                return;
+            }
+        };
+        if let Err(error) = fs::write(&self.path, data).await {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to write acknowledged notifications")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tracker() -> NotificationTracker {
+        NotificationTracker {
+            path: PathBuf::new(),
+            acknowledged: BTreeSet::new(),
+            account_expiry: None,
+            device_revoked: false,
+            app_version_info: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_account_expiry_notifications() {
+        let now = Utc::now();
+        let mut tracker = tracker();
+
+        tracker.on_account_expiry(now + TimeDelta::days(30)).await;
+        assert!(tracker.active_notifications(now).is_empty());
+
+        let expiry = now + TimeDelta::days(1);
+        tracker.on_account_expiry(expiry).await;
+        assert_eq!(
+            tracker.active_notifications(now)[0].kind,
+            NotificationKind::AccountExpiringSoon { expiry }
+        );
+
+        let expired = tracker.active_notifications(expiry + TimeDelta::seconds(1));
+        assert_eq!(expired[0].kind, NotificationKind::AccountExpired { expiry });
+        assert_eq!(expired[0].severity, NotificationSeverity::Critical);
+    }
+
+    #[tokio::test]
+    async fn test_acknowledged_notifications_are_hidden() {
+        let mut tracker = tracker();
+        tracker.device_revoked = true;
+        tracker
+            .on_account_expiry(Utc::now() - TimeDelta::days(1))
+            .await;
+
+        let notifications = tracker.notifications();
+        assert_eq!(notifications.len(), 2);
+
+        tracker.acknowledged.insert(DEVICE_REVOKED_ID.to_owned());
+        let notifications = tracker.notifications();
+        assert_eq!(notifications.len(), 1);
+        assert!(matches!(
+            notifications[0].kind,
+            NotificationKind::AccountExpired { .. }
+        ));
+    }
+
+    /// Acknowledgements of other account expiries and app versions should be forgotten
+    #[tokio::test]
+    async fn test_prune_acknowledged_notifications() {
+        let expiry = Utc::now() + TimeDelta::days(1);
+        let current_expiry = format!("{ACCOUNT_EXPIRING_SOON_PREFIX}{}", expiry.timestamp());
+        let current_version = format!("{UNSUPPORTED_VERSION_PREFIX}{}", mullvad_version::VERSION);
+        let mut tracker = tracker();
+        tracker.acknowledged = BTreeSet::from([
+            DEVICE_REVOKED_ID.to_owned(),
+            format!("{ACCOUNT_EXPIRED_PREFIX}0"),
+            current_expiry.clone(),
+            format!("{UNSUPPORTED_VERSION_PREFIX}2000.1"),
+            current_version.clone(),
+        ]);
+
+        // The account expiry is not known yet
+        assert!(tracker.prune());
+        assert_eq!(tracker.acknowledged.len(), 4);
+
+        tracker.on_account_expiry(expiry).await;
+        assert_eq!(
+            tracker.acknowledged,
+            BTreeSet::from([
+                DEVICE_REVOKED_ID.to_owned(),
+                current_expiry,
+                current_version
+            ])
+        );
+        assert!(!tracker.prune());
+    }
+
+    /// Logging in or out should forget acknowledgements that belong to the previous account
+    #[tokio::test]
+    async fn test_prune_on_logout() {
+        let mut tracker = tracker();
+        tracker.device_revoked = true;
+        tracker
+            .on_account_expiry(Utc::now() - TimeDelta::days(1))
+            .await;
+        for notification in tracker.notifications() {
+            assert!(tracker.acknowledge(&notification.id).await);
+        }
+        assert_eq!(tracker.acknowledged.len(), 2);
+
+        tracker.on_device_event(&PrivateDeviceEvent::Logout).await;
+        assert!(tracker.acknowledged.is_empty());
+    }
+
+    /// Removing the tracker should delete the file, and succeed if there is no file
+    #[tokio::test]
+    async fn test_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tracker = NotificationTracker::new(dir.path(), true).await;
+        assert!(tracker.acknowledge(DEVICE_REVOKED_ID).await);
+        assert!(tracker.path.exists());
+
+        tracker.remove().await.unwrap();
+        assert!(tracker.acknowledged.is_empty());
+        assert!(!tracker.path.exists());
+        tracker.remove().await.unwrap();
+
+        let tracker = NotificationTracker::new(dir.path(), true).await;
+        assert_eq!(tracker.notifications().len(), 1);
+    }
+}
@@ -0,0 +1,419 @@
+//! Benchmarks obfuscation methods against a relay.
+//!
+//! The methods are tried one at a time, so that they do not interfere with each other. For each
+//! method, the tunnel is connected to the relay using that method, and the time until it is
+//! connected is measured. Once connected, the throughput is measured by downloading from
+//! am.i.mullvad.net through the tunnel. A method fails if the tunnel enters the error state, has to
+//! retry connecting, or does not connect in time. Once all methods have been tried, or the benchmark has
+//! run out of time, the daemon returns to the target state that it had before the benchmark.
+
+use crate::{DaemonEventSender, Error, InternalDaemonEvent, ResponseTx, geoip};
+use futures::future::{AbortHandle, abortable};
+use mullvad_api::rest::RequestServiceHandle;
+use mullvad_types::{
+    relay_constraints::{GeographicLocationConstraint, SelectedObfuscation},
+    relay_list::ObfuscationBenchmarkResult,
+    states::TargetState,
+};
+use std::{collections::VecDeque, future::Future, time::Duration};
+use talpid_core::mpsc::Sender;
+use talpid_types::ErrorExt;
+use tokio::time::Instant;
+
+/// Maximum time to spend on a single method
+const METHOD_TIMEOUT: Duration = Duration::from_secs(30);
+/// Maximum time to spend on the whole benchmark. Methods that have not been tried by then are
+/// reported as failed.
+const MAX_BENCHMARK_DURATION: Duration = Duration::from_secs(120);
+/// Maximum number of bytes to download when measuring the throughput
+const PROBE_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug)]
+pub enum BenchmarkEvent {
+    /// The given attempt ran out of time.
+    TimedOut { attempt: usize },
+    /// The throughput of the given attempt has been measured.
+    Measured {
+        attempt: usize,
+        throughput: Option<u64>,
+    },
+}
+
+impl From<BenchmarkEvent> for InternalDaemonEvent {
+    fn from(event: BenchmarkEvent) -> Self {
+        InternalDaemonEvent::ObfuscationBenchmarkEvent(event)
+    }
+}
+
+pub struct Benchmark {
+    /// The relay that is benchmarked
+    pub location: GeographicLocationConstraint,
+    /// The target state to return to once the benchmark is done
+    prior_target_state: TargetState,
+    tx: ResponseTx<Vec<ObfuscationBenchmarkResult>, Error>,
+    pending: VecDeque<SelectedObfuscation>,
+    results: Vec<ObfuscationBenchmarkResult>,
+    current: Option<Attempt>,
+    deadline: Instant,
+}
+
+/// The result of a finished benchmark
+pub struct FinishedBenchmark {
+    pub tx: ResponseTx<Vec<ObfuscationBenchmarkResult>, Error>,
+    pub results: Vec<ObfuscationBenchmarkResult>,
+    /// The target state to enter now that the benchmark is done
+    pub target_state: TargetState,
+}
+
+struct Attempt {
+    method: SelectedObfuscation,
+    started: Instant,
+    timeout: AbortHandle,
+    /// Set once the tunnel is connected
+    handshake_time: Option<Duration>,
+    /// Measures the throughput once the tunnel is connected
+    probe: Option<AbortHandle>,
+}
+
+impl Benchmark {
+    pub fn new(
+        location: GeographicLocationConstraint,
+        methods: Vec<SelectedObfuscation>,
+        prior_target_state: TargetState,
+        tx: ResponseTx<Vec<ObfuscationBenchmarkResult>, Error>,
+    ) -> Self {
+        Benchmark {
+            location,
+            prior_target_state,
+            tx,
+            pending: VecDeque::from(methods),
+            results: vec![],
+            current: None,
+            deadline: Instant::now() + MAX_BENCHMARK_DURATION,
+        }
+    }
+
+    /// Whether `methods` can be benchmarked. Each method must be a specific one, and only occur
+    /// once.
+    pub fn is_valid(methods: &[SelectedObfuscation]) -> bool {
+        !methods.is_empty()
+            && methods.iter().enumerate().all(|(i, method)| {
+                *method != SelectedObfuscation::Auto && !methods[..i].contains(method)
+            })
+    }
+
+    /// Fail the current attempt, if any, and start the next one. Returns the method to connect
+    /// with, or `None` if the benchmark is done.
+    pub fn start_next(
+        &mut self,
+        event_tx: DaemonEventSender<BenchmarkEvent>,
+    ) -> Option<SelectedObfuscation> {
+        self.finish_attempt(None);
+
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            log::warn!("Obfuscation benchmark ran out of time");
+            return None;
+        }
+        let method = self.pending.pop_front()?;
+
+        let attempt = self.results.len();
+        let (timeout, abort_handle) = abortable(async move {
+            tokio::time::sleep(remaining.min(METHOD_TIMEOUT)).await;
+            let _ = event_tx.send(BenchmarkEvent::TimedOut { attempt });
+        });
+        tokio::spawn(timeout);
+
+        self.current = Some(Attempt {
+            method,
+            started: Instant::now(),
+            timeout: abort_handle,
+            handshake_time: None,
+            probe: None,
+        });
+        Some(method)
+    }
+
+    /// Record the handshake time of the current attempt, and start measuring its throughput using
+    /// `probe`. The attempt is finished once the throughput has been measured.
+    pub fn on_connected(
+        &mut self,
+        probe: impl Future<Output = Option<u64>> + Send + 'static,
+        event_tx: DaemonEventSender<BenchmarkEvent>,
+    ) {
+        let attempt = self.results.len();
+        let Some(current) = &mut self.current else {
+            return;
+        };
+        if current.handshake_time.is_some() {
+            return;
+        }
+        current.handshake_time = Some(current.started.elapsed());
+
+        let (probe, abort_handle) = abortable(async move {
+            let throughput = probe.await;
+            let _ = event_tx.send(BenchmarkEvent::Measured {
+                attempt,
+                throughput,
+            });
+        });
+        tokio::spawn(probe);
+        current.probe = Some(abort_handle);
+    }
+
+    /// Fail the current attempt. Returns whether the next method should be started.
+    pub fn on_failed(&mut self) -> bool {
+        let failed = self.current.is_some();
+        self.finish_attempt(None);
+        failed
+    }
+
+    /// Handle an event from an attempt. Returns whether the next method should be started.
+    pub fn handle_event(&mut self, event: BenchmarkEvent) -> bool {
+        match event {
+            BenchmarkEvent::TimedOut { attempt }
+                if attempt == self.results.len() && self.current.is_some() =>
+            {
+                self.finish_attempt(None);
+                true
+            }
+            BenchmarkEvent::Measured {
+                attempt,
+                throughput,
+            } if attempt == self.results.len() && self.current.is_some() => {
+                self.finish_attempt(throughput);
+                true
+            }
+            // The event belongs to an attempt that has already finished
+            _ => false,
+        }
+    }
+
+    /// Stop the benchmark. Methods that were not tried are reported as failed. The target state
+    /// to enter is `target_state`, or the one from before the benchmark if it is `None`.
+    pub fn finish(mut self, target_state: Option<TargetState>) -> FinishedBenchmark {
+        self.finish_attempt(None);
+        self.results.extend(
+            self.pending
+                .drain(..)
+                .map(|method| ObfuscationBenchmarkResult {
+                    method,
+                    handshake_time: None,
+                    throughput: None,
+                }),
+        );
+        FinishedBenchmark {
+            tx: self.tx,
+            results: self.results,
+            target_state: target_state.unwrap_or(self.prior_target_state),
+        }
+    }
+
+    /// Finish the current attempt. Its handshake time is kept if it connected, even if the
+    /// throughput could not be measured.
+    fn finish_attempt(&mut self, throughput: Option<u64>) {
+        let Some(attempt) = self.current.take() else {
+            return;
+        };
+        attempt.timeout.abort();
+        if let Some(probe) = attempt.probe {
+            probe.abort();
+        }
+        log::debug!(
+            "Obfuscation benchmark of {}: handshake time {:?}, throughput {:?}",
+            attempt.method,
+            attempt.handshake_time,
+            throughput,
+        );
+        self.results.push(ObfuscationBenchmarkResult {
+            method: attempt.method,
+            handshake_time: attempt.handshake_time,
+            throughput,
+        });
+    }
+}
+
+/// Download up to [`PROBE_SIZE`] bytes from am.i.mullvad.net and return the download speed in
+/// bytes per second. The time includes setting up the connection, so this is a lower bound.
+pub fn measure_throughput(
+    rest_service: RequestServiceHandle,
+) -> impl Future<Output = Option<u64>> + Send + 'static {
+    async move {
+        let uri = format!("https://ipv4.{}/", *geoip::MULLVAD_CONNCHECK_HOST);
+        let started = Instant::now();
+        let result = async {
+            let request = mullvad_api::rest::get(&uri)?;
+            rest_service
+                .request(request)
+                .await?
+                .body_prefix(PROBE_SIZE)
+                .await
+        }
+        .await;
+        match result {
+            Ok(body) => {
+                let elapsed = started.elapsed().as_secs_f64();
+                (elapsed > 0.0).then(|| (body.len() as f64 / elapsed) as u64)
+            }
+            Err(error) => {
+                log::debug!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to measure throughput")
+                );
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use SelectedObfuscation::*;
+    use futures::channel::{mpsc, oneshot};
+    use std::sync::Arc;
+
+    fn benchmark(
+        methods: Vec<SelectedObfuscation>,
+    ) -> (
+        Benchmark,
+        oneshot::Receiver<Result<Vec<ObfuscationBenchmarkResult>, Error>>,
+    ) {
+        let (tx, rx) = oneshot::channel();
+        let location = GeographicLocationConstraint::hostname("se", "got", "se-got-wg-001");
+        let benchmark = Benchmark::new(location, methods, TargetState::Unsecured, tx);
+        (benchmark, rx)
+    }
+
+    /// Returns a sender for benchmark events, and the receiving end of the daemon event channel.
+    /// The sender only works while the returned [Arc] is kept.
+    fn event_channel() -> (
+        DaemonEventSender<BenchmarkEvent>,
+        Arc<mpsc::UnboundedSender<InternalDaemonEvent>>,
+        mpsc::UnboundedReceiver<InternalDaemonEvent>,
+    ) {
+        let (tx, rx) = mpsc::unbounded();
+        let tx = Arc::new(tx);
+        let sender = DaemonEventSender::new(Arc::downgrade(&tx)).to_specialized_sender();
+        (sender, tx, rx)
+    }
+
+    async fn next_event(rx: &mut mpsc::UnboundedReceiver<InternalDaemonEvent>) -> BenchmarkEvent {
+        use futures::StreamExt;
+        match rx.next().await {
+            Some(InternalDaemonEvent::ObfuscationBenchmarkEvent(event)) => event,
+            _ => panic!("expected a benchmark event"),
+        }
+    }
+
+    /// Connected attempts record their handshake time and throughput, and failed ones do not
+    #[tokio::test(start_paused = true)]
+    async fn test_connected_and_failed() {
+        let (mut benchmark, _rx) = benchmark(vec![Udp2Tcp, Shadowsocks]);
+        let (event_tx, _daemon_tx, mut events) = event_channel();
+
+        assert_eq!(benchmark.start_next(event_tx.clone()), Some(Udp2Tcp));
+        tokio::time::advance(Duration::from_secs(2)).await;
+        benchmark.on_connected(async { Some(1000) }, event_tx.clone());
+        // A second connected state does not restart the measurement
+        benchmark.on_connected(async { Some(1) }, event_tx.clone());
+        let event = next_event(&mut events).await;
+        assert!(matches!(
+            event,
+            BenchmarkEvent::Measured {
+                attempt: 0,
+                throughput: Some(1000)
+            }
+        ));
+        assert!(benchmark.handle_event(event));
+
+        assert_eq!(benchmark.start_next(event_tx.clone()), Some(Shadowsocks));
+        assert!(benchmark.on_failed());
+        assert!(!benchmark.on_failed());
+
+        assert_eq!(benchmark.start_next(event_tx), None);
+        let results = benchmark.finish(None).results;
+        assert_eq!(
+            results,
+            [
+                ObfuscationBenchmarkResult {
+                    method: Udp2Tcp,
+                    handshake_time: Some(Duration::from_secs(2)),
+                    throughput: Some(1000),
+                },
+                ObfuscationBenchmarkResult {
+                    method: Shadowsocks,
+                    handshake_time: None,
+                    throughput: None,
+                },
+            ]
+        );
+    }
+
+    /// An attempt that does not connect in time fails, and timeouts of finished attempts are
+    /// ignored
+    #[tokio::test(start_paused = true)]
+    async fn test_method_timeout() {
+        let (mut benchmark, _rx) = benchmark(vec![Udp2Tcp, Shadowsocks]);
+        let (event_tx, _daemon_tx, mut events) = event_channel();
+
+        assert_eq!(benchmark.start_next(event_tx.clone()), Some(Udp2Tcp));
+        let event = next_event(&mut events).await;
+        assert!(matches!(event, BenchmarkEvent::TimedOut { attempt: 0 }));
+        assert!(benchmark.handle_event(event));
+
+        assert_eq!(benchmark.start_next(event_tx.clone()), Some(Shadowsocks));
+        assert!(!benchmark.handle_event(BenchmarkEvent::TimedOut { attempt: 0 }));
+        // The measurement does not finish before the attempt times out
+        benchmark.on_connected(std::future::pending(), event_tx);
+        let event = next_event(&mut events).await;
+        assert!(matches!(event, BenchmarkEvent::TimedOut { attempt: 1 }));
+        assert!(benchmark.handle_event(event));
+        assert!(!benchmark.handle_event(BenchmarkEvent::Measured {
+            attempt: 1,
+            throughput: Some(1000)
+        }));
+
+        let results = benchmark.finish(None).results;
+        assert_eq!(results[0].handshake_time, None);
+        assert!(results[1].handshake_time.is_some());
+        assert_eq!(results[1].throughput, None);
+    }
+
+    /// Methods that are not tried before the benchmark runs out of time are reported as failed
+    #[tokio::test(start_paused = true)]
+    async fn test_finish() {
+        let (mut benchmark, _rx) = benchmark(vec![Udp2Tcp, Shadowsocks, Off]);
+        let (event_tx, _daemon_tx, _events) = event_channel();
+
+        assert_eq!(benchmark.start_next(event_tx.clone()), Some(Udp2Tcp));
+        tokio::time::advance(MAX_BENCHMARK_DURATION).await;
+        assert_eq!(benchmark.start_next(event_tx), None);
+
+        let results = benchmark.finish(None).results;
+        let methods: Vec<_> = results.iter().map(|result| result.method).collect();
+        assert_eq!(methods, [Udp2Tcp, Shadowsocks, Off]);
+        assert!(results.iter().all(|result| result.handshake_time.is_none()));
+    }
+
+    /// The target state from before the benchmark is restored, unless another one is given
+    #[test]
+    fn test_finish_target_state() {
+        let (benchmark, _rx) = benchmark(vec![Udp2Tcp]);
+        assert_eq!(benchmark.finish(None).target_state, TargetState::Unsecured);
+
+        let (benchmark, _rx) = benchmark(vec![Udp2Tcp]);
+        assert_eq!(
+            benchmark.finish(Some(TargetState::Secured)).target_state,
+            TargetState::Secured
+        );
+    }
+
+    #[test]
+    fn test_valid_methods() {
+        assert!(Benchmark::is_valid(&[Udp2Tcp, Shadowsocks, Off]));
+        assert!(!Benchmark::is_valid(&[]));
+        assert!(!Benchmark::is_valid(&[Udp2Tcp, Auto]));
+        assert!(!Benchmark::is_valid(&[Udp2Tcp, Shadowsocks, Udp2Tcp]));
+    }
+}
@@ -0,0 +1,224 @@
+//! Pausing the tunnel, i.e. disconnecting for a limited time and reconnecting once the time is up.
+//!
+//! A pause is tracked by the disconnect reason of the daemon, so that a client which changes the
+//! target state, or anything else which disconnects the daemon, ends it.
+
+use crate::{DaemonEventSender, Error, InternalDaemonEvent};
+use futures::future::{AbortHandle, abortable};
+use mullvad_types::states::{DisconnectReason, TargetState};
+use std::time::Duration;
+use talpid_core::mpsc::Sender;
+
+/// Longest time that the tunnel can be paused for
+pub const MAX_PAUSE_DURATION: Duration = Duration::from_hours(24);
+
+/// Timer which ends the current pause of the tunnel.
+#[derive(Default)]
+pub struct TunnelPause {
+    job: Option<AbortHandle>,
+}
+
+impl TunnelPause {
+    /// Pause the tunnel for `duration`, or restart the current pause with the new duration.
+    /// [InternalDaemonEvent::PauseEnded] is sent on `event_tx` once the duration has passed.
+    /// Returns the reason that the daemon should disconnect with.
+    pub fn start(
+        &mut self,
+        target_state: TargetState,
+        disconnect_reason: Option<DisconnectReason>,
+        duration: Duration,
+        event_tx: DaemonEventSender,
+    ) -> Result<DisconnectReason, Error> {
+        let until = pause_end(chrono::Utc::now(), duration)?;
+        if !is_paused(disconnect_reason) && target_state != TargetState::Secured {
+            return Err(Error::PauseNotSecured);
+        }
+
+        self.cancel();
+        let (future, abort_handle) = abortable(Box::pin(async move {
+            // Include time spent in suspend, so that the pause does not outlast `until`
+            talpid_time::sleep(duration).await;
+            let _ = event_tx.send(InternalDaemonEvent::PauseEnded);
+        }));
+        tokio::spawn(future);
+        self.job = Some(abort_handle);
+
+        Ok(DisconnectReason::Paused { until })
+    }
+
+    /// End the pause. Returns the target state to restore, or `None` if the tunnel is not
+    /// paused.
+    pub fn end(&mut self, disconnect_reason: Option<DisconnectReason>) -> Option<TargetState> {
+        if !is_paused(disconnect_reason) {
+            return None;
+        }
+        self.cancel();
+        Some(TargetState::Secured)
+    }
+
+    /// Stop the timer, so that the daemon does not reconnect when the pause would have ended.
+    pub fn cancel(&mut self) {
+        if let Some(job) = self.job.take() {
+            job.abort();
+        }
+    }
+}
+
+/// Return whether the daemon is disconnected because the tunnel is paused.
+pub fn is_paused(disconnect_reason: Option<DisconnectReason>) -> bool {
+    matches!(disconnect_reason, Some(DisconnectReason::Paused { .. }))
+}
+
+/// Return when a pause of `duration` that starts at `now` ends
+fn pause_end(
+    now: chrono::DateTime<chrono::Utc>,
+    duration: Duration,
+) -> Result<chrono::DateTime<chrono::Utc>, Error> {
+    if duration.is_zero() || duration > MAX_PAUSE_DURATION {
+        return Err(Error::InvalidPauseDuration);
+    }
+    chrono::TimeDelta::from_std(duration)
+        .ok()
+        .and_then(|duration| now.checked_add_signed(duration))
+        .ok_or(Error::InvalidPauseDuration)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::{StreamExt, channel::mpsc};
+    use std::sync::Arc;
+
+    /// The target state and disconnect reason of a daemon, and the pause timer which changes them
+    struct Daemon {
+        target_state: TargetState,
+        disconnect_reason: Option<DisconnectReason>,
+        pause: TunnelPause,
+        event_tx: Arc<mpsc::UnboundedSender<InternalDaemonEvent>>,
+        event_rx: mpsc::UnboundedReceiver<InternalDaemonEvent>,
+    }
+
+    impl Daemon {
+        fn new() -> Self {
+            let (event_tx, event_rx) = mpsc::unbounded();
+            Self {
+                target_state: TargetState::Secured,
+                disconnect_reason: None,
+                pause: TunnelPause::default(),
+                event_tx: Arc::new(event_tx),
+                event_rx,
+            }
+        }
+
+        fn pause(&mut self, duration: Duration) -> Result<(), Error> {
+            let event_tx = DaemonEventSender::new(Arc::downgrade(&self.event_tx));
+            let reason = self.pause.start(
+                self.target_state,
+                self.disconnect_reason,
+                duration,
+                event_tx,
+            )?;
+            self.target_state = TargetState::Unsecured;
+            self.disconnect_reason = Some(reason);
+            Ok(())
+        }
+
+        fn end_pause(&mut self) -> bool {
+            match self.pause.end(self.disconnect_reason) {
+                Some(target_state) => {
+                    self.target_state = target_state;
+                    self.disconnect_reason = None;
+                    true
+                }
+                None => false,
+            }
+        }
+
+        /// Wait for the pause timer, and handle the event like the daemon does
+        async fn handle_next_event(&mut self) {
+            let event = self.event_rx.next().await;
+            assert!(matches!(event, Some(InternalDaemonEvent::PauseEnded)));
+            self.end_pause();
+        }
+    }
+
+    /// Pausing disconnects the tunnel, and it is secured again once the pause timer has fired.
+    #[tokio::test(start_paused = true)]
+    async fn test_pause_timer_restores_secured() {
+        let mut daemon = Daemon::new();
+        let duration = Duration::from_secs(5 * 60);
+        let start = tokio::time::Instant::now();
+
+        daemon.pause(duration).unwrap();
+        assert_eq!(daemon.target_state, TargetState::Unsecured);
+        assert!(is_paused(daemon.disconnect_reason));
+
+        daemon.handle_next_event().await;
+        assert_eq!(start.elapsed(), duration);
+        assert_eq!(daemon.target_state, TargetState::Secured);
+        assert_eq!(daemon.disconnect_reason, None);
+    }
+
+    /// Pausing again restarts the pause, so only the last duration counts.
+    #[tokio::test(start_paused = true)]
+    async fn test_pause_restart() {
+        let mut daemon = Daemon::new();
+        let start = tokio::time::Instant::now();
+
+        daemon.pause(Duration::from_secs(60)).unwrap();
+        tokio::time::advance(Duration::from_secs(30)).await;
+        daemon.pause(Duration::from_secs(60)).unwrap();
+
+        daemon.handle_next_event().await;
+        assert_eq!(start.elapsed(), Duration::from_secs(90));
+        assert_eq!(daemon.target_state, TargetState::Secured);
+        assert!(daemon.event_rx.try_next().is_err());
+    }
+
+    /// Resuming early reconnects, and the pause timer no longer fires.
+    #[tokio::test(start_paused = true)]
+    async fn test_resume() {
+        let mut daemon = Daemon::new();
+        daemon.pause(Duration::from_secs(60)).unwrap();
+
+        assert!(daemon.end_pause());
+        assert_eq!(daemon.target_state, TargetState::Secured);
+        assert!(!daemon.end_pause());
+
+        tokio::time::sleep(Duration::from_secs(120)).await;
+        assert!(daemon.event_rx.try_next().is_err());
+    }
+
+    /// Only a secured tunnel can be paused.
+    #[tokio::test(start_paused = true)]
+    async fn test_pause_unsecured() {
+        let mut daemon = Daemon::new();
+        daemon.target_state = TargetState::Unsecured;
+        assert!(matches!(
+            daemon.pause(Duration::from_secs(60)),
+            Err(Error::PauseNotSecured)
+        ));
+        assert_eq!(daemon.disconnect_reason, None);
+    }
+
+    #[test]
+    fn test_pause_end() {
+        let now = chrono::Utc::now();
+        assert_eq!(
+            pause_end(now, Duration::from_secs(90)).unwrap(),
+            now + chrono::TimeDelta::seconds(90)
+        );
+        assert_eq!(
+            pause_end(now, MAX_PAUSE_DURATION).unwrap(),
+            now + chrono::TimeDelta::hours(24)
+        );
+        assert!(matches!(
+            pause_end(now, Duration::ZERO),
+            Err(Error::InvalidPauseDuration)
+        ));
+        assert!(matches!(
+            pause_end(now, MAX_PAUSE_DURATION + Duration::from_secs(1)),
+            Err(Error::InvalidPauseDuration)
+        ));
+    }
+}
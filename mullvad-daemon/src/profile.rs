@@ -0,0 +1,83 @@
+use crate::{Daemon, Error, dns};
+use futures::channel::oneshot;
+use mullvad_types::profile::{self, ConnectionProfile, ProfilesSettings};
+use talpid_core::tunnel_state_machine::TunnelCommand;
+use talpid_types::ErrorExt;
+
+impl Daemon {
+    /// Save the current relay, obfuscation, and tunnel settings under `name`, replacing any
+    /// profile with the same name.
+    pub async fn save_profile(&mut self, name: String) -> Result<(), Error> {
+        self.settings
+            .try_update(|settings| {
+                let profile = ConnectionProfile::from_settings(name, settings)?;
+                settings.profiles.save(profile);
+                Ok::<(), profile::Error>(())
+            })
+            .await
+            .map_err(Error::SettingsError)?;
+        Ok(())
+    }
+
+    /// Replace all settings contained in the profile `name` in a single update. If `reconnect`
+    /// is set, the tunnel is reconnected so that the settings take effect immediately.
+    ///
+    /// Returns an error if there is no profile with the given name.
+    pub async fn apply_profile(&mut self, name: String, reconnect: bool) -> Result<(), Error> {
+        let settings_changed = self
+            .settings
+            .try_update(|settings| {
+                let profile = settings
+                    .profiles
+                    .get(&name)
+                    .cloned()
+                    .ok_or(profile::Error::ProfileNotFound)?;
+                profile.apply_to(settings);
+                Ok::<(), profile::Error>(())
+            })
+            .await
+            .map_err(Error::SettingsError)?;
+
+        if settings_changed {
+            log::info!("Applied connection profile \"{name}\"");
+
+            let (tx, _rx) = oneshot::channel();
+            let dns = dns::addresses_from_options(&self.settings.tunnel_options.dns_options);
+            self.send_tunnel_command(TunnelCommand::Dns(dns, tx));
+
+            let interval = self.settings.tunnel_options.wireguard.rotation_interval;
+            let account_manager = self.account_manager.clone();
+            tokio::spawn(async move {
+                if let Err(error) = account_manager
+                    .set_rotation_interval(interval.unwrap_or_default())
+                    .await
+                {
+                    log::error!(
+                        "{}",
+                        error.display_chain_with_msg("Failed to update rotation interval")
+                    );
+                }
+            });
+
+            if reconnect {
+                self.reconnect_tunnel();
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete the profile `name`.
+    ///
+    /// Returns an error if there is no profile with the given name.
+    pub async fn delete_profile(&mut self, name: String) -> Result<(), Error> {
+        self.settings
+            .try_update(|settings| settings.profiles.remove(&name))
+            .await
+            .map_err(Error::SettingsError)?;
+        Ok(())
+    }
+
+    pub fn list_profiles(&self) -> ProfilesSettings {
+        self.settings.profiles.clone()
+    }
+}
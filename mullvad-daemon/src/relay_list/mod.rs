@@ -2,14 +2,15 @@
 
 pub mod error;
 pub(crate) mod parsed_relays;
+pub(crate) mod self_test;
 
 use error::Error;
-use mullvad_types::relay_constraints::RelayOverride;
+use mullvad_types::{relay_constraints::RelayOverride, relay_list::RelayListUpdateInfo};
 
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use futures::channel::mpsc;
+use futures::channel::{mpsc, oneshot};
 use futures::future::{Fuse, FusedFuture};
 use futures::{Future, FutureExt, SinkExt, StreamExt};
 use tokio::fs::File;
@@ -28,13 +29,15 @@ use talpid_types::ErrorExt;
 const UPDATE_CHECK_INTERVAL: Duration = Duration::from_mins(15);
 /// How old the cached relays need to be to trigger an update
 const UPDATE_INTERVAL: Duration = Duration::from_hours(1);
+/// How old the cached relays need to be to trigger an update in low-power mode
+const LOW_POWER_UPDATE_INTERVAL: Duration = Duration::from_hours(6);
 
 const DOWNLOAD_RETRY_STRATEGY: Jittered<ExponentialBackoff> = Jittered::jitter(
     ExponentialBackoff::new(Duration::from_secs(16), 8).max_delay(Some(Duration::from_hours(2))),
 );
 
 /// Where the relay list is cached on disk.
-const RELAYS_FILENAME: &str = "relays.json";
+pub(crate) const RELAYS_FILENAME: &str = "relays.json";
 
 #[derive(Clone)]
 pub struct RelayListUpdaterHandle {
@@ -48,6 +51,10 @@ enum Event {
     Update,
     /// Register new relay IP overrides.
     Override(Vec<RelayOverride>),
+    /// Set whether the current network is metered.
+    SetMeteredNetwork(bool),
+    /// Return the update schedule.
+    GetUpdateInfo(oneshot::Sender<RelayListUpdateInfo>),
 }
 
 impl RelayListUpdaterHandle {
@@ -71,13 +78,33 @@ impl RelayListUpdaterHandle {
             log::error!("Failed to apply new relay overrides");
         };
     }
+
+    /// Set whether the current network is metered. Automatic updates are skipped on metered
+    /// networks, but [Self::update] still fetches the relay list.
+    pub async fn set_metered_network(&mut self, metered: bool) {
+        if let Err(_err) = self.tx.send(Event::SetMeteredNetwork(metered)).await {
+            log::error!("Failed to set whether the network is metered");
+        }
+    }
+
+    /// Return when the relay list was last fetched and when it will next be fetched.
+    pub async fn update_info(&mut self) -> Result<RelayListUpdateInfo, Error> {
+        let (tx, rx) = oneshot::channel();
+        self.tx
+            .send(Event::GetUpdateInfo(tx))
+            .await
+            .map_err(|_| Error::DownloaderShutdown)?;
+        rx.await.map_err(|_| Error::DownloaderShutdown)
+    }
 }
 
 pub(crate) struct RelayListUpdater {
     api_client: RelayListProxy,
     cache_path: PathBuf,
     on_update: Box<dyn Fn(&RelayList) + Send + 'static>,
-    last_check: SystemTime,
+    schedule: UpdateSchedule,
+    /// When the relay list was last fetched successfully
+    last_updated: Option<SystemTime>,
     api_availability: ApiAvailability,
     etag: Option<ETag>,
     // Keep tabs on the up-to-date relay list.
@@ -98,6 +125,7 @@ impl RelayListUpdater {
         overrides: Vec<RelayOverride>,
         on_update: impl Fn(&RelayList) + Send + 'static,
         cached_relay_list: Option<CachedRelayList>,
+        metered_network: bool,
     ) -> RelayListUpdaterHandle {
         let (tx, cmd_rx) = mpsc::channel(1);
         let api_availability = api_handle.availability.clone();
@@ -115,7 +143,8 @@ impl RelayListUpdater {
             cache_path: cache_dir.join(RELAYS_FILENAME),
             relay_selector: selector,
             on_update: Box::new(on_update),
-            last_check: UNIX_EPOCH,
+            schedule: UpdateSchedule::new(metered_network),
+            last_updated: None,
             etag,
             overrides,
             api_availability,
@@ -130,18 +159,22 @@ impl RelayListUpdater {
 
     async fn run(mut self, mut internal_events: mpsc::Receiver<Event>) {
         let mut download_future = Box::pin(Fuse::terminated());
-        loop {
-            let next_check = tokio::time::sleep(UPDATE_CHECK_INTERVAL).fuse();
-            tokio::pin!(next_check);
+        // The deadline is only pushed forward when it fires, so that commands do not postpone it
+        let next_check = tokio::time::sleep(UPDATE_CHECK_INTERVAL);
+        tokio::pin!(next_check);
+        self.schedule.next_check = SystemTime::now() + UPDATE_CHECK_INTERVAL;
 
+        loop {
             let etag = self.etag.clone();
 
             futures::select! {
-                _check_update = next_check => {
+                _check_update = (&mut next_check).fuse() => {
                     log::trace!("Received `next_check` event");
-                    if download_future.is_terminated() && self.should_update() {
+                    next_check.as_mut().reset(tokio::time::Instant::now() + UPDATE_CHECK_INTERVAL);
+                    let now = SystemTime::now();
+                    self.schedule.next_check = now + UPDATE_CHECK_INTERVAL;
+                    if download_future.is_terminated() && self.schedule.should_update(now, self.update_interval(), UpdateTrigger::Scheduled) {
                         download_future = Box::pin(Self::download_relay_list(self.api_availability.clone(), self.api_client.clone(), etag).fuse());
-                        self.last_check = SystemTime::now();
                     }
                 },
 
@@ -158,8 +191,9 @@ impl RelayListUpdater {
                     };
                     match event {
                         Event::Update => {
-                            download_future = Box::pin(Self::download_relay_list(self.api_availability.clone(), self.api_client.clone(), etag).fuse());
-                            self.last_check = SystemTime::now();
+                            if self.schedule.should_update(SystemTime::now(), self.update_interval(), UpdateTrigger::Manual) {
+                                download_future = Box::pin(Self::download_relay_list(self.api_availability.clone(), self.api_client.clone(), etag).fuse());
+                            }
                         },
                         // Only update the relay list with new overrides if they are actually new.
                         Event::Override(overrides) if self.overrides != overrides => {
@@ -170,6 +204,12 @@ impl RelayListUpdater {
                             log::trace!("New overrides match the old overrides.");
                             log::trace!("{overrides:#?}");
                         }
+                        Event::SetMeteredNetwork(metered) => {
+                            self.schedule.metered_network = metered;
+                        }
+                        Event::GetUpdateInfo(tx) => {
+                            let _ = tx.send(self.update_info());
+                        }
                     }
                 }
 
@@ -181,6 +221,9 @@ impl RelayListUpdater {
         &mut self,
         result: Result<Option<CachedRelayList>, mullvad_api::Error>,
     ) {
+        if result.is_ok() {
+            self.last_updated = Some(SystemTime::now());
+        }
         match result {
             Ok(Some(relay_list)) => {
                 log::trace!("Updating relay list cache");
@@ -194,14 +237,21 @@ impl RelayListUpdater {
         }
     }
 
-    /// Returns true if the current relay list is older than [`UPDATE_INTERVAL`].
-    fn should_update(&mut self) -> bool {
-        match SystemTime::now().duration_since(self.last_check) {
-            Ok(duration) => duration >= UPDATE_INTERVAL,
-            // If the clock is skewed we have no idea by how much or when the last update
-            // actually was, better download again to get in sync and get a `last_updated`
-            // timestamp corresponding to the new time.
-            Err(_) => true,
+    /// Returns [`UPDATE_INTERVAL`], or [`LOW_POWER_UPDATE_INTERVAL`] in low-power mode.
+    fn update_interval(&self) -> Duration {
+        if self.api_availability.is_low_power() {
+            LOW_POWER_UPDATE_INTERVAL
+        } else {
+            UPDATE_INTERVAL
+        }
+    }
+
+    fn update_info(&self) -> RelayListUpdateInfo {
+        let update_interval = self.update_interval();
+        RelayListUpdateInfo {
+            update_interval,
+            last_updated: self.last_updated.map(Into::into),
+            next_update: self.schedule.next_update(update_interval).map(Into::into),
         }
     }
 
@@ -281,3 +331,158 @@ impl RelayListUpdater {
             .apply_overrides(self.overrides.clone())
     }
 }
+
+/// What caused the updater to consider fetching the relay list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UpdateTrigger {
+    /// The periodic check fired
+    Scheduled,
+    /// An update was requested using [RelayListUpdaterHandle::update]
+    Manual,
+}
+
+/// Keeps track of when the relay list is due to be updated automatically.
+struct UpdateSchedule {
+    /// When an update was last started
+    last_check: SystemTime,
+    /// When the updater next wakes up to check whether to update
+    next_check: SystemTime,
+    /// Whether to skip automatic updates because the network is metered
+    metered_network: bool,
+}
+
+impl UpdateSchedule {
+    fn new(metered_network: bool) -> Self {
+        Self {
+            last_check: UNIX_EPOCH,
+            next_check: SystemTime::now(),
+            metered_network,
+        }
+    }
+
+    /// Returns true if an update should be started at `now`. Manual updates always are, whereas
+    /// scheduled updates are only started if the network is not metered and the last update is
+    /// older than `update_interval`.
+    fn should_update(
+        &mut self,
+        now: SystemTime,
+        update_interval: Duration,
+        trigger: UpdateTrigger,
+    ) -> bool {
+        if trigger == UpdateTrigger::Manual {
+            self.last_check = now;
+            return true;
+        }
+        if self.metered_network {
+            log::trace!("Skipping automatic relay list update on metered network");
+            return false;
+        }
+        let should_update = match now.duration_since(self.last_check) {
+            Ok(duration) => duration >= update_interval,
+            // If the clock is skewed we have no idea by how much or when the last update
+            // actually was, better download again to get in sync and get a `last_updated`
+            // timestamp corresponding to the new time.
+            Err(_) => true,
+        };
+        if should_update {
+            self.last_check = now;
+        }
+        should_update
+    }
+
+    /// Returns when the next automatic update will happen, or `None` if automatic updates are
+    /// skipped because the network is metered.
+    fn next_update(&self, update_interval: Duration) -> Option<SystemTime> {
+        if self.metered_network {
+            return None;
+        }
+        Some(next_update(
+            self.last_check,
+            self.next_check,
+            update_interval,
+        ))
+    }
+}
+
+/// Return the first check, starting at `next_check` and repeating every [`UPDATE_CHECK_INTERVAL`],
+/// at which `update_interval` has passed since `last_check`.
+fn next_update(
+    last_check: SystemTime,
+    next_check: SystemTime,
+    update_interval: Duration,
+) -> SystemTime {
+    let Ok(remaining) = (last_check + update_interval).duration_since(next_check) else {
+        return next_check;
+    };
+    let checks = remaining
+        .as_secs()
+        .div_ceil(UPDATE_CHECK_INTERVAL.as_secs());
+    next_check + UPDATE_CHECK_INTERVAL * u32::try_from(checks).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_next_update() {
+        let next_check = UNIX_EPOCH + Duration::from_hours(100);
+
+        // An update is overdue, so it happens at the next check
+        assert_eq!(
+            next_update(UNIX_EPOCH, next_check, UPDATE_INTERVAL),
+            next_check
+        );
+
+        // The update is due exactly at a check
+        let last_check = next_check - UPDATE_INTERVAL + UPDATE_CHECK_INTERVAL;
+        assert_eq!(
+            next_update(last_check, next_check, UPDATE_INTERVAL),
+            next_check + UPDATE_CHECK_INTERVAL
+        );
+
+        // The update is due between two checks, so it happens at the later one
+        let last_check = next_check - Duration::from_mins(5);
+        assert_eq!(
+            next_update(last_check, next_check, UPDATE_INTERVAL),
+            next_check + UPDATE_CHECK_INTERVAL * 4
+        );
+    }
+
+    /// Scheduled updates are skipped on metered networks, but manual updates are not
+    #[test]
+    fn test_metered_network() {
+        let now = UNIX_EPOCH + Duration::from_hours(100);
+        let mut schedule = UpdateSchedule::new(true);
+
+        assert!(!schedule.should_update(now, UPDATE_INTERVAL, UpdateTrigger::Scheduled));
+        assert_eq!(schedule.last_check, UNIX_EPOCH);
+
+        assert!(schedule.should_update(now, UPDATE_INTERVAL, UpdateTrigger::Manual));
+        assert_eq!(schedule.last_check, now);
+
+        // Once the network is no longer metered, scheduled updates resume
+        schedule.metered_network = false;
+        let later = now + UPDATE_INTERVAL;
+        assert!(!schedule.should_update(
+            later - UPDATE_CHECK_INTERVAL,
+            UPDATE_INTERVAL,
+            UpdateTrigger::Scheduled
+        ));
+        assert!(schedule.should_update(later, UPDATE_INTERVAL, UpdateTrigger::Scheduled));
+        assert_eq!(schedule.last_check, later);
+    }
+
+    /// The next update is only reported while automatic updates are enabled
+    #[test]
+    fn test_next_update_metered() {
+        let mut schedule = UpdateSchedule::new(true);
+        assert_eq!(schedule.next_update(UPDATE_INTERVAL), None);
+
+        schedule.metered_network = false;
+        assert_eq!(
+            schedule.next_update(UPDATE_INTERVAL),
+            Some(schedule.next_check)
+        );
+    }
+}
@@ -39,6 +39,17 @@ pub fn parse_relays_from_file(
     Ok(relay_list)
 }
 
+/// Try to read the relays cached in `cache_dir`. Returns `None` if there is no cached relay list.
+pub fn parse_relays_from_cache(
+    cache_dir: impl AsRef<Path>,
+) -> Result<Option<CachedRelayList>, Error> {
+    match from_file_inner(cache_dir.as_ref().join(RELAYS_FILENAME)) {
+        Ok((cached_relays, _)) => Ok(Some(cached_relays)),
+        Err(Error::OpenRelayCache(error)) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
 fn from_file_inner(path: impl AsRef<Path>) -> Result<(CachedRelayList, SystemTime), Error> {
     log::trace!("Reading relays from {}", path.as_ref().display());
     let (file, last_modified) = open_file(path).map_err(Error::OpenRelayCache)?;
@@ -0,0 +1,201 @@
+//! Sanity checks for the relay list, used to detect an empty or malformed list after a bad fetch.
+
+use super::parsed_relays::parse_relays_from_cache;
+use mullvad_types::relay_list::{BridgeList, Relay, RelayList, RelayListReport};
+use std::{collections::HashSet, path::Path};
+use talpid_types::ErrorExt;
+
+/// Validate the relay list currently in use, as well as the one cached in `cache_dir`, if any.
+pub fn self_test(cache_dir: &Path, relays: &RelayList, bridges: &BridgeList) -> RelayListReport {
+    let mut report = validate(relays, bridges);
+
+    if let Err(error) = parse_relays_from_cache(cache_dir) {
+        report
+            .problems
+            .push(error.display_chain_with_msg("The cached relay list could not be parsed"));
+    }
+
+    report
+}
+
+/// Check that the relay list is non-empty, that every relay has the required fields, and that
+/// there is at least one relay for each advertised protocol.
+fn validate(relays: &RelayList, bridges: &BridgeList) -> RelayListReport {
+    let mut problems = vec![];
+    let mut hostnames = HashSet::new();
+
+    let mut relay_count = 0;
+    let mut active_relays = 0;
+    for relay in relays.relays() {
+        relay_count += 1;
+        if relay.active {
+            active_relays += 1;
+        }
+        check_relay(
+            &relay.inner,
+            "WireGuard relay",
+            &mut hostnames,
+            &mut problems,
+        );
+    }
+
+    let mut bridge_count = 0;
+    let mut active_bridges = 0;
+    for bridge in &bridges.bridges {
+        bridge_count += 1;
+        if bridge.active {
+            active_bridges += 1;
+        }
+        check_relay(&bridge.0, "Bridge", &mut hostnames, &mut problems);
+    }
+
+    if relay_count == 0 {
+        problems.push("The relay list contains no WireGuard relays".to_owned());
+    } else if active_relays == 0 {
+        problems.push("The relay list contains no active WireGuard relays".to_owned());
+    }
+    if relays.wireguard.port_ranges.is_empty() {
+        problems.push("The relay list contains no WireGuard port ranges".to_owned());
+    }
+    if !bridges.bridge_endpoint.shadowsocks.is_empty() && active_bridges == 0 {
+        problems
+            .push("Shadowsocks bridges are advertised, but there are no active bridges".to_owned());
+    }
+
+    RelayListReport {
+        relay_count,
+        bridge_count,
+        problems,
+    }
+}
+
+fn check_relay<'a>(
+    relay: &'a Relay,
+    kind: &str,
+    hostnames: &mut HashSet<&'a str>,
+    problems: &mut Vec<String>,
+) {
+    if relay.hostname.is_empty() {
+        problems.push(format!(
+            "{kind} with address {} has no hostname",
+            relay.ipv4_addr_in
+        ));
+        return;
+    }
+    if !hostnames.insert(&relay.hostname) {
+        problems.push(format!(
+            "{kind} {} is listed more than once",
+            relay.hostname
+        ));
+    }
+    if relay.ipv4_addr_in.is_unspecified() {
+        problems.push(format!("{kind} {} has no IPv4 address", relay.hostname));
+    }
+    if relay.location.country_code.is_empty() || relay.location.city_code.is_empty() {
+        problems.push(format!(
+            "{kind} {} has an incomplete location",
+            relay.hostname
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mullvad_types::{
+        location::Location,
+        relay_list::{
+            EndpointData, RelayListCity, RelayListCountry, WireguardRelay,
+            WireguardRelayEndpointData,
+        },
+    };
+    use talpid_types::net::wireguard::PublicKey;
+
+    fn relay_list(relays: Vec<WireguardRelay>) -> RelayList {
+        RelayList {
+            countries: vec![RelayListCountry {
+                name: "Sweden".to_owned(),
+                code: "se".to_owned(),
+                cities: vec![RelayListCity {
+                    name: "Gothenburg".to_owned(),
+                    code: "got".to_owned(),
+                    latitude: 57.70887,
+                    longitude: 11.97456,
+                    relays,
+                }],
+            }],
+            wireguard: EndpointData {
+                port_ranges: vec![53..=53, 4000..=33433],
+                ..Default::default()
+            },
+        }
+    }
+
+    fn relay(hostname: &str) -> WireguardRelay {
+        WireguardRelay::new(
+            false,
+            false,
+            true,
+            true,
+            "provider".to_owned(),
+            WireguardRelayEndpointData::new(
+                PublicKey::from_base64("BLNHNoGO88LjV/wDBa7CUUwUzPq/fO2UwcGLy56hKy4=").unwrap(),
+            ),
+            Relay {
+                hostname: hostname.to_owned(),
+                ipv4_addr_in: "185.213.154.68".parse().unwrap(),
+                ipv6_addr_in: None,
+                active: true,
+                weight: 1,
+                location: Location {
+                    country: "Sweden".to_owned(),
+                    country_code: "se".to_owned(),
+                    city: "Gothenburg".to_owned(),
+                    city_code: "got".to_owned(),
+                    latitude: 57.71,
+                    longitude: 11.97,
+                },
+            },
+        )
+    }
+
+    #[test]
+    fn test_valid_relay_list() {
+        let relays = relay_list(vec![relay("se-got-wg-001"), relay("se-got-wg-002")]);
+        let report = validate(&relays, &BridgeList::default());
+        assert!(report.is_ok(), "unexpected problems: {:?}", report.problems);
+        assert_eq!(report.relay_count, 2);
+    }
+
+    #[test]
+    fn test_empty_relay_list() {
+        let report = validate(&RelayList::empty(), &BridgeList::default());
+        assert!(!report.is_ok());
+        assert_eq!(report.relay_count, 0);
+    }
+
+    #[test]
+    fn test_malformed_relays() {
+        let mut inactive = relay("se-got-wg-002");
+        inactive.active = false;
+        let mut no_location = relay("se-got-wg-003");
+        no_location.location.city_code.clear();
+
+        let relays = relay_list(vec![
+            relay("se-got-wg-001"),
+            relay("se-got-wg-001"),
+            inactive,
+            no_location,
+            relay(""),
+        ]);
+        let report = validate(&relays, &BridgeList::default());
+
+        // Expect: Duplicate hostname, incomplete location, and missing hostname
+        assert_eq!(
+            report.problems.len(),
+            3,
+            "unexpected problems: {:?}",
+            report.problems
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Tests whether a port on a relay is reachable.
+//!
+//! TCP ports are tested by establishing a connection. Relays do not respond to UDP datagrams that
+//! are not part of a WireGuard handshake, so a UDP port can only be found to be blocked, if the
+//! probe is refused. Otherwise, the result is unknown.
+//!
+//! Probes are sent like any other traffic from the daemon, so they would go through the tunnel
+//! while connected, and be blocked by the firewall when the daemon blocks traffic. The daemon
+//! therefore only tests ports while disconnected.
+
+use mullvad_types::relay_list::RelayPortReachability;
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::{Duration, Instant},
+};
+use talpid_types::{ErrorExt, net::TransportProtocol};
+use tokio::net::{TcpStream, UdpSocket};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Payload of UDP probes. This is not a valid WireGuard message, so relays discard it without
+/// responding. Only an ICMP error in response to it can be detected.
+const UDP_PROBE: &[u8] = &[0; 32];
+
+/// Test whether `port` on `address` is reachable using `protocol`.
+pub async fn test_port(
+    address: IpAddr,
+    port: u16,
+    protocol: TransportProtocol,
+) -> RelayPortReachability {
+    let address = SocketAddr::new(address, port);
+    let probe = async {
+        match protocol {
+            TransportProtocol::Tcp => probe_tcp(address).await,
+            TransportProtocol::Udp => probe_udp(address).await,
+        }
+    };
+    let timed_out = match protocol {
+        TransportProtocol::Tcp => RelayPortReachability::NoResponse,
+        TransportProtocol::Udp => RelayPortReachability::Unknown,
+    };
+    match tokio::time::timeout(PROBE_TIMEOUT, probe).await {
+        Ok(Ok(latency)) => RelayPortReachability::Reachable { latency },
+        Ok(Err(error)) if error.kind() == io::ErrorKind::ConnectionRefused => {
+            RelayPortReachability::Refused
+        }
+        Ok(Err(error)) => {
+            log::debug!(
+                "{}",
+                error.display_chain_with_msg(&format!("Failed to probe {address}/{protocol}"))
+            );
+            timed_out
+        }
+        Err(_) => timed_out,
+    }
+}
+
+/// Return the time it takes to establish a connection.
+async fn probe_tcp(address: SocketAddr) -> io::Result<Duration> {
+    let start = Instant::now();
+    TcpStream::connect(address).await?;
+    Ok(start.elapsed())
+}
+
+/// Return the time until a response to a probe is received. A refused probe is reported as
+/// [io::ErrorKind::ConnectionRefused].
+async fn probe_udp(address: SocketAddr) -> io::Result<Duration> {
+    let bind_address = match address {
+        SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    let socket = UdpSocket::bind((bind_address, 0)).await?;
+    socket.connect(address).await?;
+
+    let start = Instant::now();
+    socket.send(UDP_PROBE).await?;
+    let mut response = [0u8; 1];
+    socket.recv(&mut response).await?;
+    Ok(start.elapsed())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_tcp_port() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(matches!(
+            test_port(Ipv4Addr::LOCALHOST.into(), port, TransportProtocol::Tcp).await,
+            RelayPortReachability::Reachable { .. }
+        ));
+
+        drop(listener);
+        assert_eq!(
+            test_port(Ipv4Addr::LOCALHOST.into(), port, TransportProtocol::Tcp).await,
+            RelayPortReachability::Refused
+        );
+    }
+
+    /// A UDP port that silently discards the probe is unknown, not unreachable
+    #[tokio::test]
+    async fn test_udp_port() {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        assert_eq!(
+            test_port(Ipv4Addr::LOCALHOST.into(), port, TransportProtocol::Udp).await,
+            RelayPortReachability::Unknown
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! Reads the memory and handle usage of the daemon process.
+
+use mullvad_types::resource_usage::ResourceUsage;
+
+/// Return the current resource usage of the daemon. Must be called from within the tokio runtime.
+pub fn resource_usage() -> ResourceUsage {
+    ResourceUsage {
+        resident_memory: resident_memory(),
+        open_handles: open_handles(),
+        active_tasks: tokio::runtime::Handle::current()
+            .metrics()
+            .num_alive_tasks() as u64,
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn resident_memory() -> Option<u64> {
+    // The second field is the resident set size, in pages
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    // SAFETY: `sysconf` has no preconditions
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    Some(pages * u64::try_from(page_size).ok()?)
+}
+
+#[cfg(target_os = "macos")]
+fn resident_memory() -> Option<u64> {
+    // SAFETY: `proc_taskinfo` is plain old data, for which all zeroes is a valid value
+    let mut info: libc::proc_taskinfo = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::proc_taskinfo>() as libc::c_int;
+    // SAFETY: `info` is a valid buffer of `size` bytes
+    let written = unsafe {
+        libc::proc_pidinfo(
+            std::process::id() as libc::c_int,
+            libc::PROC_PIDTASKINFO,
+            0,
+            (&raw mut info).cast(),
+            size,
+        )
+    };
+    (written == size).then_some(info.pti_resident_size)
+}
+
+#[cfg(unix)]
+fn open_handles() -> Option<u64> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const FD_DIR: &str = "/proc/self/fd";
+    #[cfg(target_os = "macos")]
+    const FD_DIR: &str = "/dev/fd";
+
+    let entries = std::fs::read_dir(FD_DIR).ok()?.count();
+    // Do not count the descriptor used to read the directory
+    Some(entries.saturating_sub(1) as u64)
+}
+
+#[cfg(windows)]
+fn resident_memory() -> Option<u64> {
+    use windows_sys::Win32::System::{
+        ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS},
+        Threading::GetCurrentProcess,
+    };
+
+    // SAFETY: `PROCESS_MEMORY_COUNTERS` is plain old data, for which all zeroes is a valid value
+    let mut counters: PROCESS_MEMORY_COUNTERS = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+    // SAFETY: `counters` is a valid buffer of `size` bytes
+    let result = unsafe { GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, size) };
+    (result != 0).then_some(counters.WorkingSetSize as u64)
+}
+
+#[cfg(windows)]
+fn open_handles() -> Option<u64> {
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, GetProcessHandleCount};
+
+    let mut count = 0;
+    // SAFETY: `count` is a valid pointer to a `u32`
+    let result = unsafe { GetProcessHandleCount(GetCurrentProcess(), &mut count) };
+    (result != 0).then_some(u64::from(count))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resource_usage() {
+        let usage = resource_usage();
+        assert!(usage.resident_memory.is_some_and(|memory| memory > 0));
+        assert!(usage.open_handles.is_some_and(|handles| handles > 0));
+    }
+}
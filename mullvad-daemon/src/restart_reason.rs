@@ -0,0 +1,174 @@
+use mullvad_types::states::RestartReason;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use talpid_types::ErrorExt;
+use tokio::{fs, io};
+
+const SHUTDOWN_RECORD_FILE: &str = "last-shutdown-reason.json";
+
+/// State of the daemon as last recorded on disk.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ShutdownState {
+    /// The daemon is running. If this is read on startup, the previous instance did not shut down
+    /// cleanly.
+    Running,
+    CleanShutdown,
+    PrepareRestart,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ShutdownRecord {
+    state: ShutdownState,
+    /// Version of the daemon that wrote the record
+    version: String,
+}
+
+/// Persists why the daemon stopped, so that the next instance can tell why it was restarted.
+pub struct ShutdownRecorder {
+    cache_path: PathBuf,
+    last_restart_reason: Option<RestartReason>,
+    prepared_restart: bool,
+}
+
+impl ShutdownRecorder {
+    /// Read the record left behind by the previous instance, and mark the current instance as
+    /// running.
+    pub async fn new(cache_dir: &Path) -> Self {
+        let cache_path = cache_dir.join(SHUTDOWN_RECORD_FILE);
+        let last_restart_reason = Self::read_restart_reason(&cache_path).await;
+        if let Some(reason) = last_restart_reason {
+            log::info!("Last restart reason: {reason}");
+        }
+
+        let recorder = ShutdownRecorder {
+            cache_path,
+            last_restart_reason,
+            prepared_restart: false,
+        };
+        recorder.save(ShutdownState::Running).await;
+        recorder
+    }
+
+    async fn read_restart_reason(cache_path: &Path) -> Option<RestartReason> {
+        match fs::read_to_string(cache_path).await {
+            Ok(content) => match serde_json::from_str::<ShutdownRecord>(&content) {
+                Ok(record) => Some(restart_reason(&record, mullvad_version::VERSION)),
+                Err(error) => {
+                    log::error!(
+                        "{}",
+                        error.display_chain_with_msg("Failed to parse shutdown reason")
+                    );
+                    None
+                }
+            },
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                log::debug!("No shutdown reason to load");
+                None
+            }
+            Err(error) => {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to read shutdown reason")
+                );
+                None
+            }
+        }
+    }
+
+    /// Return why the daemon was restarted, if known.
+    pub fn last_restart_reason(&self) -> Option<RestartReason> {
+        self.last_restart_reason
+    }
+
+    /// Record that the daemon is being prepared for a restart.
+    pub async fn prepare_restart(&mut self) {
+        self.prepared_restart = true;
+        self.save(ShutdownState::PrepareRestart).await;
+    }
+
+    /// Record a clean shutdown, unless the daemon has been prepared for a restart.
+    pub async fn finalize(self) {
+        if !self.prepared_restart {
+            self.save(ShutdownState::CleanShutdown).await;
+        }
+    }
+
+    async fn save(&self, state: ShutdownState) {
+        log::trace!(
+            "Saving shutdown state {state:?} to {}",
+            self.cache_path.display()
+        );
+        let record = ShutdownRecord {
+            state,
+            version: mullvad_version::VERSION.to_owned(),
+        };
+        match serde_json::to_string(&record) {
+            Ok(data) => {
+                if let Err(error) = fs::write(&self.cache_path, data).await {
+                    log::error!(
+                        "{}",
+                        error.display_chain_with_msg("Failed to write shutdown reason")
+                    );
+                }
+            }
+            Err(error) => {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to serialize shutdown reason")
+                );
+            }
+        }
+    }
+}
+
+/// Determine why the daemon was restarted, given the record left behind by the previous instance.
+/// A change of version takes precedence over how the previous instance stopped.
+fn restart_reason(record: &ShutdownRecord, current_version: &str) -> RestartReason {
+    if record.version != current_version {
+        return RestartReason::Upgrade;
+    }
+    match record.state {
+        ShutdownState::Running => RestartReason::Crash,
+        ShutdownState::CleanShutdown => RestartReason::CleanShutdown,
+        ShutdownState::PrepareRestart => RestartReason::PrepareRestart,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record(state: ShutdownState, version: &str) -> ShutdownRecord {
+        ShutdownRecord {
+            state,
+            version: version.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_restart_reason() {
+        let cases = [
+            (ShutdownState::Running, RestartReason::Crash),
+            (ShutdownState::CleanShutdown, RestartReason::CleanShutdown),
+            (ShutdownState::PrepareRestart, RestartReason::PrepareRestart),
+        ];
+        for (state, expected) in cases {
+            assert_eq!(restart_reason(&record(state, "2025.1"), "2025.1"), expected);
+        }
+    }
+
+    #[test]
+    fn test_restart_reason_upgrade() {
+        for state in [
+            ShutdownState::Running,
+            ShutdownState::CleanShutdown,
+            ShutdownState::PrepareRestart,
+        ] {
+            assert_eq!(
+                restart_reason(&record(state, "2025.1"), "2025.2"),
+                RestartReason::Upgrade
+            );
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Locking and unlocking of settings with a password.
+//!
+//! Passwords are hashed with a random salt. Hashing is slow by design, so it is done on a blocking
+//! thread. Unlock attempts are delayed by [UnlockBackoffTracker] after too many incorrect
+//! passwords in a row.
+
+use super::{Error, MadeChanges, SettingsPersister};
+use chrono::{DateTime, Utc};
+use mullvad_types::settings::{LockableSetting, PasswordHash, SettingsLock, SettingsLockError};
+use std::time::Duration;
+
+/// Number of unlock attempts in a row that may fail before further attempts are delayed
+const UNLOCK_FAILURES_BEFORE_BACKOFF: u32 = 3;
+/// Delay after the first failure past [UNLOCK_FAILURES_BEFORE_BACKOFF]. It doubles with every
+/// further failure.
+const UNLOCK_BACKOFF_INITIAL_DELAY: Duration = Duration::from_secs(5);
+const UNLOCK_BACKOFF_MAX_DELAY: Duration = Duration::from_secs(10 * 60);
+
+/// Lock `locked` with `password`
+pub async fn lock(
+    settings: &mut SettingsPersister,
+    locked: Vec<LockableSetting>,
+    password: String,
+) -> Result<MadeChanges, Error> {
+    // Fail before spending time on hashing the password
+    if !settings.settings_lock.is_empty() {
+        return Err(SettingsLockError::AlreadyLocked.into());
+    }
+    let salt = rand::random();
+    let password_hash = tokio::task::spawn_blocking(move || PasswordHash::new(&password, salt))
+        .await
+        .map_err(|error| Error::UpdateFailed(Box::new(error)))??;
+
+    let mut lock = settings.settings_lock.clone();
+    lock.lock(locked, password_hash)?;
+    settings.set_settings_lock(lock).await
+}
+
+/// Unlock all settings if `password` is correct. Unlocking settings that are not locked always
+/// succeeds.
+pub async fn unlock(
+    settings: &mut SettingsPersister,
+    backoff: &mut UnlockBackoffTracker,
+    password: String,
+) -> Result<MadeChanges, Error> {
+    if let Some(password_hash) = settings.settings_lock.password_hash().cloned() {
+        backoff.check()?;
+        let correct = tokio::task::spawn_blocking(move || password_hash.verify(&password))
+            .await
+            .map_err(|error| Error::UpdateFailed(Box::new(error)))?;
+        if !correct {
+            backoff.record_failure();
+            return Err(SettingsLockError::IncorrectPassword.into());
+        }
+        backoff.reset();
+    }
+    settings.set_settings_lock(SettingsLock::default()).await
+}
+
+/// Delays unlock attempts after attempts in a row have failed because the password was incorrect.
+/// The state is kept in memory only.
+#[derive(Debug, Default)]
+pub struct UnlockBackoffTracker {
+    consecutive_failures: u32,
+    next_attempt: Option<DateTime<Utc>>,
+}
+
+impl UnlockBackoffTracker {
+    /// Return an error if an unlock attempt is not allowed yet.
+    fn check(&self) -> Result<(), SettingsLockError> {
+        match self.next_attempt {
+            Some(next_attempt) if next_attempt > Utc::now() => {
+                Err(SettingsLockError::UnlockBackoff(next_attempt))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn record_failure(&mut self) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.next_attempt = Self::delay(self.consecutive_failures).map(|delay| {
+            Utc::now() + chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::MAX)
+        });
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Return how long to wait after `failures` failed unlock attempts in a row.
+    fn delay(failures: u32) -> Option<Duration> {
+        let exponent = failures.checked_sub(UNLOCK_FAILURES_BEFORE_BACKOFF)?;
+        let delay = UNLOCK_BACKOFF_INITIAL_DELAY
+            .checked_mul(2u32.saturating_pow(exponent))
+            .unwrap_or(UNLOCK_BACKOFF_MAX_DELAY);
+        Some(delay.min(UNLOCK_BACKOFF_MAX_DELAY))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Unlock attempts should only be delayed once enough attempts in a row have failed
+    #[test]
+    fn test_unlock_backoff() {
+        let mut backoff = UnlockBackoffTracker::default();
+        for _ in 0..UNLOCK_FAILURES_BEFORE_BACKOFF - 1 {
+            backoff.record_failure();
+        }
+        assert!(backoff.check().is_ok());
+
+        backoff.record_failure();
+        assert!(matches!(
+            backoff.check(),
+            Err(SettingsLockError::UnlockBackoff(_))
+        ));
+
+        assert!(
+            UnlockBackoffTracker::delay(UNLOCK_FAILURES_BEFORE_BACKOFF + 1)
+                > UnlockBackoffTracker::delay(UNLOCK_FAILURES_BEFORE_BACKOFF)
+        );
+        assert_eq!(
+            UnlockBackoffTracker::delay(u32::MAX),
+            Some(UNLOCK_BACKOFF_MAX_DELAY)
+        );
+
+        backoff.reset();
+        assert!(backoff.check().is_ok());
+    }
+}
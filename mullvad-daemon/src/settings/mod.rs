@@ -1,9 +1,16 @@
 use futures::TryFutureExt;
 use mullvad_types::{
     access_method::Error as ApiAccessMethodError,
+    constraints::Constraint,
     custom_list::Error as CustomListError,
-    relay_constraints::{RelayConstraints, RelaySettings, WireguardConstraints},
-    settings::{DnsState, Settings},
+    profile::Error as ProfileError,
+    relay_constraints::{
+        LocationConstraint, RelayConstraints, RelaySettings, WireguardConstraints,
+    },
+    settings::{
+        CURRENT_SETTINGS_VERSION, DnsOptionsError, DnsState, LockableSetting, Settings,
+        SettingsIntegrity, SettingsLock, SettingsLockError,
+    },
 };
 use std::{
     fmt::{self, Display},
@@ -17,7 +24,9 @@ use tokio::{
     io::{self, AsyncWriteExt},
 };
 
+pub mod lock;
 pub mod patch;
+pub mod snapshot;
 
 const SETTINGS_FILE: &str = "settings.json";
 
@@ -45,6 +54,12 @@ pub enum Error {
     ParseIp(String),
 }
 
+impl From<SettingsLockError> for Error {
+    fn from(error: SettingsLockError) -> Self {
+        Error::UpdateFailed(Box::new(error))
+    }
+}
+
 /// Converts an [Error] to a management interface status
 impl From<Error> for mullvad_management_interface::Status {
     fn from(error: Error) -> mullvad_management_interface::Status {
@@ -61,6 +76,35 @@ impl From<Error> for mullvad_management_interface::Status {
                 let custom_list_err = *err.downcast::<CustomListError>().unwrap();
                 handle_custom_list_error(custom_list_err)
             }
+            Error::UpdateFailed(err) if err.downcast_ref::<ProfileError>().is_some() => {
+                match *err.downcast::<ProfileError>().unwrap() {
+                    error @ ProfileError::EmptyName => Status::invalid_argument(error.to_string()),
+                    error @ ProfileError::ProfileNotFound => Status::not_found(error.to_string()),
+                }
+            }
+            Error::UpdateFailed(err) if err.downcast_ref::<SettingsLockError>().is_some() => {
+                match *err.downcast::<SettingsLockError>().unwrap() {
+                    error @ (SettingsLockError::NoSettings | SettingsLockError::EmptyPassword) => {
+                        Status::invalid_argument(error.to_string())
+                    }
+                    error @ SettingsLockError::AlreadyLocked => {
+                        Status::failed_precondition(error.to_string())
+                    }
+                    error @ (SettingsLockError::IncorrectPassword
+                    | SettingsLockError::Locked(_)) => Status::permission_denied(error.to_string()),
+                    error @ SettingsLockError::UnlockBackoff(_) => {
+                        Status::unavailable(error.to_string())
+                    }
+                }
+            }
+            Error::UpdateFailed(err) if err.downcast_ref::<DnsOptionsError>().is_some() => {
+                match *err.downcast::<DnsOptionsError>().unwrap() {
+                    error @ DnsOptionsError::SplitDnsUnsupported => {
+                        Status::unimplemented(error.to_string())
+                    }
+                    error => Status::invalid_argument(error.to_string()),
+                }
+            }
             Error::UpdateFailed(err) if err.downcast_ref::<ApiAccessMethodError>().is_some() => {
                 let api_access_method_err = *err.downcast::<ApiAccessMethodError>().unwrap();
                 handle_api_access_method_error(api_access_method_err)
@@ -255,8 +299,9 @@ impl SettingsPersister {
         Ok(())
     }
 
-    /// Resets to default settings
+    /// Resets to default settings. Fails if any setting is locked.
     pub async fn reset(&mut self) -> Result<(), Error> {
+        self.check_no_settings_locked()?;
         self.settings = Self::default_settings();
         let path = self.path.clone();
         self.save()
@@ -279,6 +324,75 @@ impl SettingsPersister {
         Ok(())
     }
 
+    /// Check whether the settings file on disk can be loaded, without applying it. Unlike
+    /// [Self::load], this does not fall back on the default settings.
+    pub async fn verify_integrity(&self) -> SettingsIntegrity {
+        match Self::load_from_file(&self.path).await {
+            Ok(settings) => Self::check_invariants(&settings),
+            Err(Error::ReadError(_, err)) if err.kind() == io::ErrorKind::NotFound => {
+                SettingsIntegrity::Missing
+            }
+            Err(error @ Error::ReadError(..)) => {
+                SettingsIntegrity::Unreadable(error.display_chain())
+            }
+            Err(error) => SettingsIntegrity::Corrupt(error.display_chain()),
+        }
+    }
+
+    /// Check invariants that deserialization alone does not guarantee
+    fn check_invariants(settings: &Settings) -> SettingsIntegrity {
+        let mut problems = vec![];
+
+        if settings.settings_version != CURRENT_SETTINGS_VERSION {
+            problems.push(format!(
+                "Unexpected settings version: {}",
+                settings.settings_version as u32
+            ));
+        }
+
+        let custom_lists = &settings.custom_lists;
+        for (i, list) in custom_lists.iter().enumerate() {
+            let previous = &custom_lists[..i];
+            if previous.iter().any(|other| other.id() == list.id()) {
+                problems.push(format!("Duplicate custom list ID: {}", *list.id()));
+            }
+            if previous.iter().any(|other| other.name == list.name) {
+                problems.push(format!("Duplicate custom list name: {}", list.name));
+            }
+        }
+
+        if let RelaySettings::Normal(constraints) = &settings.relay_settings {
+            let locations = [
+                &constraints.location,
+                &constraints.wireguard_constraints.entry_location,
+            ];
+            for location in locations {
+                if let Constraint::Only(LocationConstraint::CustomList { list_id }) = location
+                    && !custom_lists.iter().any(|list| list.id() == *list_id)
+                {
+                    problems.push(format!(
+                        "Relay settings refer to a missing custom list: {}",
+                        **list_id
+                    ));
+                }
+            }
+        }
+
+        if settings
+            .api_access_methods
+            .iter()
+            .all(|access_method| access_method.disabled())
+        {
+            problems.push("All API access methods are disabled".to_owned());
+        }
+
+        if problems.is_empty() {
+            SettingsIntegrity::Ok
+        } else {
+            SettingsIntegrity::Invalid(problems)
+        }
+    }
+
     pub const fn settings(&self) -> &Settings {
         &self.settings
     }
@@ -338,12 +452,18 @@ impl SettingsPersister {
     ///
     /// # On failure
     ///
-    /// `try_update` may fail in two scenarios
+    /// `try_update` may fail in three scenarios
     ///
     /// ## The settings could not be written to disk
     ///
     /// In this case, all changes are rolled back and an error is returned.
     ///
+    /// ## A locked setting was changed
+    ///
+    /// All changes are rolled back, and [`SettingsLockError::Locked`] is returned through the
+    /// [`Error::UpdateFailed`] error variant. The settings lock itself is never changed, see
+    /// [`Self::set_settings_lock`].
+    ///
     /// ## `update_fn` failed
     ///
     /// If `update_fn` were to fail the error will be propagated through the
@@ -382,6 +502,33 @@ impl SettingsPersister {
             .map_err(Box::from)
             .map_err(Error::UpdateFailed)?;
 
+        // DNS options are checked here rather than by the setter, since they may also be replaced
+        // by patches and snapshots
+        let dns_options = &new_settings.tunnel_options.dns_options;
+        if *dns_options != self.settings.tunnel_options.dns_options {
+            dns_options
+                .validate()
+                .map_err(|error| Error::UpdateFailed(Box::new(error)))?;
+        }
+
+        // Replacing all settings, e.g. when importing them, must not remove the lock
+        new_settings.settings_lock = self.settings.settings_lock.clone();
+        self.settings
+            .settings_lock
+            .check_update(&self.settings, &new_settings)?;
+
+        self.save_changes(new_settings).await
+    }
+
+    /// Replace the settings lock. This is the only way to change it, since [Self::try_update]
+    /// leaves it as is.
+    pub async fn set_settings_lock(&mut self, lock: SettingsLock) -> Result<MadeChanges, Error> {
+        let mut new_settings = self.settings.clone();
+        new_settings.settings_lock = lock;
+        self.save_changes(new_settings).await
+    }
+
+    async fn save_changes(&mut self, new_settings: Settings) -> Result<MadeChanges, Error> {
         if self.settings == new_settings {
             return Ok(false);
         }
@@ -394,6 +541,17 @@ impl SettingsPersister {
         Ok(true)
     }
 
+    /// Fail if `setting` is locked. Changes made through [Self::try_update] are checked
+    /// automatically, so this is only needed for changes to state outside of the settings.
+    pub fn check_unlocked(&self, setting: LockableSetting) -> Result<(), Error> {
+        Ok(self.settings.settings_lock.check_unlocked(setting)?)
+    }
+
+    /// Fail if any setting is locked
+    pub fn check_no_settings_locked(&self) -> Result<(), Error> {
+        Ok(self.settings.settings_lock.check_no_settings_locked()?)
+    }
+
     /// Return a compact summary of important settings
     pub fn summary(&self) -> SettingsSummary<'_> {
         SettingsSummary {
@@ -550,6 +708,39 @@ mod test {
     use super::*;
     use mullvad_types::settings::SettingsVersion;
 
+    #[test]
+    fn test_integrity_of_default_settings() {
+        let settings = SettingsPersister::default_settings();
+        assert_eq!(
+            SettingsPersister::check_invariants(&settings),
+            SettingsIntegrity::Ok
+        );
+    }
+
+    #[test]
+    fn test_integrity_of_inconsistent_settings() {
+        use mullvad_types::custom_list::CustomList;
+
+        let list = CustomList::new("list".to_owned()).unwrap();
+        let missing_list = CustomList::new("missing".to_owned()).unwrap();
+
+        let mut settings = SettingsPersister::default_settings();
+        settings.custom_lists = vec![list.clone(), list].into();
+        settings.relay_settings = RelaySettings::Normal(RelayConstraints {
+            location: Constraint::Only(LocationConstraint::CustomList {
+                list_id: missing_list.id(),
+            }),
+            ..Default::default()
+        });
+
+        // Expect: Duplicate ID, duplicate name, and missing list
+        let SettingsIntegrity::Invalid(problems) = SettingsPersister::check_invariants(&settings)
+        else {
+            panic!("expected settings to be invalid");
+        };
+        assert_eq!(problems.len(), 3, "unexpected problems: {problems:?}");
+    }
+
     #[test]
     #[should_panic]
     fn test_deserialization_failure_version_too_small() {
@@ -736,4 +927,89 @@ mod test {
             serde_json::from_str(r#"{"recents": null}"#).expect("Failed to deserialize");
         assert_eq!(disabled.recents, None);
     }
+
+    /// Updates must neither change locked settings nor remove the lock
+    #[tokio::test]
+    async fn test_update_locked_settings() {
+        let mut settings = SettingsPersister::default_settings();
+        settings.settings_lock = serde_json::from_str(r#"{"locked": ["allow_lan"]}"#).unwrap();
+        let mut persister = SettingsPersister {
+            settings: settings.clone(),
+            path: PathBuf::new(),
+            on_change_listeners: vec![],
+        };
+
+        let result = persister
+            .update(|settings| settings.allow_lan = !settings.allow_lan)
+            .await;
+        assert!(matches!(
+            result,
+            Err(Error::UpdateFailed(error))
+                if *error.downcast::<SettingsLockError>().unwrap()
+                    == SettingsLockError::Locked(LockableSetting::AllowLan)
+        ));
+
+        let made_changes = persister
+            .update(|settings| settings.settings_lock = SettingsLock::default())
+            .await
+            .unwrap();
+        assert!(!made_changes);
+        assert_eq!(*persister, settings);
+
+        assert!(persister.reset().await.is_err());
+        assert_eq!(*persister, settings);
+    }
+
+    /// Invalid DNS options are rejected even when all settings are replaced at once
+    #[tokio::test]
+    async fn test_update_invalid_dns_options() {
+        use mullvad_types::settings::SplitDnsRule;
+
+        let settings = SettingsPersister::default_settings();
+        let mut persister = SettingsPersister {
+            settings: settings.clone(),
+            path: PathBuf::new(),
+            on_change_listeners: vec![],
+        };
+
+        let rule =
+            SplitDnsRule::new("corp.example.com", vec!["10.0.0.1".parse().unwrap()]).unwrap();
+        let mut new_settings = settings.clone();
+        new_settings.tunnel_options.dns_options.split_dns = vec![rule.clone(), rule];
+
+        let result = persister
+            .update(move |settings| *settings = new_settings)
+            .await;
+        assert!(matches!(
+            result,
+            Err(Error::UpdateFailed(error)) if error.downcast_ref::<DnsOptionsError>().is_some()
+        ));
+        assert_eq!(*persister, settings);
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[tokio::test]
+    async fn test_update_unsupported_split_dns() {
+        use mullvad_types::settings::SplitDnsRule;
+
+        let settings = SettingsPersister::default_settings();
+        let mut persister = SettingsPersister {
+            settings: settings.clone(),
+            path: PathBuf::new(),
+            on_change_listeners: vec![],
+        };
+
+        let rule =
+            SplitDnsRule::new("corp.example.com", vec!["10.0.0.1".parse().unwrap()]).unwrap();
+        let result = persister
+            .update(move |settings| settings.tunnel_options.dns_options.split_dns = vec![rule])
+            .await;
+        assert!(matches!(
+            result,
+            Err(Error::UpdateFailed(error))
+                if *error.downcast::<DnsOptionsError>().unwrap()
+                    == DnsOptionsError::SplitDnsUnsupported
+        ));
+        assert_eq!(*persister, settings);
+    }
 }
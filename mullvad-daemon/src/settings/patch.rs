@@ -124,6 +124,8 @@ const PERMITTED_SUBKEYS: &PermittedKey = &PermittedKey::object(&[(
         ("hostname", PermittedKey::any()),
         ("ipv4_addr_in", PermittedKey::any()),
         ("ipv6_addr_in", PermittedKey::any()),
+        ("wireguard_ipv4_addr_in", PermittedKey::any()),
+        ("wireguard_ipv6_addr_in", PermittedKey::any()),
     ]))
     .merge_strategy(MergeStrategy::Custom(merge_relay_overrides)),
 )]);
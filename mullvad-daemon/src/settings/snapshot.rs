@@ -0,0 +1,293 @@
+//! Named restore points for the settings.
+//!
+//! A snapshot is a copy of all settings, stored together with its creation time in the cache
+//! directory. The settings lock is not included, and restoring a snapshot fails if it would change
+//! a locked setting. Snapshots created by a daemon with a different settings version cannot be
+//! restored.
+//!
+//! At most [MAX_SNAPSHOTS] are kept. When a new snapshot is created, the oldest ones are removed.
+
+use super::SettingsPersister;
+use chrono::{DateTime, Utc};
+use mullvad_types::settings::{Settings, SettingsLock, SettingsSnapshot};
+use serde::{Deserialize, Serialize};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+use talpid_types::ErrorExt;
+use tokio::fs;
+
+const SNAPSHOT_DIR: &str = "settings-snapshots";
+const SNAPSHOT_EXTENSION: &str = "json";
+
+/// Maximum number of snapshots to keep
+const MAX_SNAPSHOTS: usize = 10;
+/// Maximum number of characters in a snapshot label
+const MAX_LABEL_LEN: usize = 64;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// The label is empty, too long, or contains characters other than alphanumerics, '-' and '_'
+    #[error("Invalid snapshot label: {0}")]
+    InvalidLabel(String),
+    #[error("There is no snapshot with the label {0}")]
+    NotFound(String),
+    #[error("Unable to read snapshot {0}")]
+    ReadError(String, #[source] io::Error),
+    #[error("Unable to write snapshot {0}")]
+    WriteError(String, #[source] io::Error),
+    #[error("Malformed snapshot {0}")]
+    ParseError(String, #[source] serde_json::Error),
+    #[error("Unable to serialize snapshot")]
+    SerializeError(#[source] serde_json::Error),
+    #[error("Failed to apply settings")]
+    Apply(#[source] super::Error),
+}
+
+/// Converts an [Error] to a management interface status
+impl From<Error> for mullvad_management_interface::Status {
+    fn from(error: Error) -> mullvad_management_interface::Status {
+        use mullvad_management_interface::Status;
+
+        match error {
+            Error::InvalidLabel(_) => Status::invalid_argument(error.to_string()),
+            Error::NotFound(_) => Status::not_found(error.to_string()),
+            Error::Apply(error) => Status::from(error),
+            Error::ReadError(..)
+            | Error::WriteError(..)
+            | Error::ParseError(..)
+            | Error::SerializeError(_) => Status::internal(error.display_chain()),
+        }
+    }
+}
+
+/// Format of a snapshot file
+#[derive(Serialize, Deserialize)]
+struct SnapshotFile {
+    created: DateTime<Utc>,
+    settings: Settings,
+}
+
+/// Store `settings` in a snapshot named `label`. An existing snapshot with the same label is
+/// replaced.
+pub async fn create(
+    cache_dir: &Path,
+    settings: &SettingsPersister,
+    label: &str,
+) -> Result<SettingsSnapshot, Error> {
+    validate_label(label)?;
+
+    let snapshot = SnapshotFile {
+        created: Utc::now(),
+        settings: Settings {
+            // The lock cannot be restored, and the password hash should not be copied around
+            settings_lock: SettingsLock::default(),
+            ..settings.to_settings()
+        },
+    };
+    let buffer = serde_json::to_vec_pretty(&snapshot).map_err(Error::SerializeError)?;
+
+    let dir = cache_dir.join(SNAPSHOT_DIR);
+    fs::create_dir_all(&dir)
+        .await
+        .map_err(|error| Error::WriteError(label.to_owned(), error))?;
+    fs::write(snapshot_path(&dir, label), buffer)
+        .await
+        .map_err(|error| Error::WriteError(label.to_owned(), error))?;
+
+    prune(&dir).await;
+
+    Ok(SettingsSnapshot {
+        label: label.to_owned(),
+        created: snapshot.created,
+    })
+}
+
+/// Replace `settings` with the snapshot named `label`.
+pub async fn restore(
+    cache_dir: &Path,
+    settings: &mut SettingsPersister,
+    label: &str,
+) -> Result<(), Error> {
+    validate_label(label)?;
+
+    let path = snapshot_path(&cache_dir.join(SNAPSHOT_DIR), label);
+    let snapshot = read_snapshot(&path, label).await?;
+
+    settings
+        .update(move |settings| *settings = snapshot.settings)
+        .await
+        .map_err(Error::Apply)?;
+    Ok(())
+}
+
+/// Return all stored snapshots, newest first.
+pub async fn list(cache_dir: &Path) -> Result<Vec<SettingsSnapshot>, Error> {
+    list_in(&cache_dir.join(SNAPSHOT_DIR)).await
+}
+
+async fn list_in(dir: &Path) -> Result<Vec<SettingsSnapshot>, Error> {
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(Error::ReadError(dir.display().to_string(), error)),
+    };
+
+    let mut snapshots = vec![];
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|error| Error::ReadError(dir.display().to_string(), error))?
+    {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(SNAPSHOT_EXTENSION) {
+            continue;
+        }
+        let Some(label) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        match read_snapshot(&path, label).await {
+            Ok(snapshot) => snapshots.push(SettingsSnapshot {
+                label: label.to_owned(),
+                created: snapshot.created,
+            }),
+            Err(error) => log::warn!("{}", error.display_chain()),
+        }
+    }
+
+    sort_newest_first(&mut snapshots);
+    Ok(snapshots)
+}
+
+/// Remove the oldest snapshots in `dir` until at most [MAX_SNAPSHOTS] remain.
+async fn prune(dir: &Path) {
+    let snapshots = match list_in(dir).await {
+        Ok(snapshots) => snapshots,
+        Err(error) => {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to list settings snapshots")
+            );
+            return;
+        }
+    };
+
+    for snapshot in snapshots.into_iter().skip(MAX_SNAPSHOTS) {
+        log::debug!("Removing old settings snapshot {}", snapshot.label);
+        if let Err(error) = fs::remove_file(snapshot_path(dir, &snapshot.label)).await {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to remove settings snapshot")
+            );
+        }
+    }
+}
+
+async fn read_snapshot(path: &Path, label: &str) -> Result<SnapshotFile, Error> {
+    let buffer = fs::read(path).await.map_err(|error| {
+        if error.kind() == io::ErrorKind::NotFound {
+            Error::NotFound(label.to_owned())
+        } else {
+            Error::ReadError(label.to_owned(), error)
+        }
+    })?;
+    serde_json::from_slice(&buffer).map_err(|error| Error::ParseError(label.to_owned(), error))
+}
+
+fn snapshot_path(dir: &Path, label: &str) -> PathBuf {
+    dir.join(label).with_extension(SNAPSHOT_EXTENSION)
+}
+
+fn sort_newest_first(snapshots: &mut [SettingsSnapshot]) {
+    snapshots.sort_by(|a, b| b.created.cmp(&a.created));
+}
+
+/// The label is used as the file name, so only a small set of characters is allowed.
+fn validate_label(label: &str) -> Result<(), Error> {
+    let valid = !label.is_empty()
+        && label.chars().count() <= MAX_LABEL_LEN
+        && label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidLabel(label.to_owned()))
+    }
+}
+
+#[test]
+fn test_validate_label() {
+    assert!(validate_label("before-upgrade_2").is_ok());
+
+    assert!(validate_label("").is_err());
+    assert!(validate_label("../settings").is_err());
+    assert!(validate_label("a/b").is_err());
+    assert!(validate_label("label.json").is_err());
+    assert!(validate_label(&"a".repeat(MAX_LABEL_LEN + 1)).is_err());
+}
+
+#[test]
+fn test_sort_newest_first() {
+    let oldest = SettingsSnapshot {
+        label: "oldest".to_owned(),
+        created: DateTime::from_timestamp(1, 0).unwrap(),
+    };
+    let newest = SettingsSnapshot {
+        label: "newest".to_owned(),
+        created: DateTime::from_timestamp(3, 0).unwrap(),
+    };
+    let middle = SettingsSnapshot {
+        label: "middle".to_owned(),
+        created: DateTime::from_timestamp(2, 0).unwrap(),
+    };
+
+    let mut snapshots = vec![oldest.clone(), newest.clone(), middle.clone()];
+    sort_newest_first(&mut snapshots);
+    assert_eq!(snapshots, vec![newest, middle, oldest]);
+}
+
+/// Restoring a snapshot brings back all settings as they were when it was created
+#[tokio::test]
+async fn test_snapshot_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let (settings_dir, cache_dir) = (dir.path().join("settings"), dir.path().join("cache"));
+    std::fs::create_dir_all(&settings_dir).unwrap();
+
+    let mut settings = SettingsPersister::load(&settings_dir).await;
+    settings
+        .update(|settings| {
+            settings.allow_lan = true;
+            settings.tunnel_options.generic.enable_ipv6 = true;
+        })
+        .await
+        .unwrap();
+    let expected = settings.to_settings();
+
+    let snapshot = create(&cache_dir, &settings, "before-change")
+        .await
+        .unwrap();
+    assert_eq!(list(&cache_dir).await.unwrap(), vec![snapshot]);
+
+    settings
+        .update(|settings| *settings = Settings::default())
+        .await
+        .unwrap();
+    assert_ne!(settings.to_settings(), expected);
+
+    restore(&cache_dir, &mut settings, "before-change")
+        .await
+        .unwrap();
+    assert_eq!(settings.to_settings(), expected);
+    // The restored settings are also persisted
+    assert_eq!(
+        SettingsPersister::load(&settings_dir).await.to_settings(),
+        expected
+    );
+
+    assert!(matches!(
+        restore(&cache_dir, &mut settings, "missing").await,
+        Err(Error::NotFound(_))
+    ));
+}
@@ -16,6 +16,8 @@ pub struct PersistentTargetState {
     state: TargetState,
     cache_path: PathBuf,
     locked: bool,
+    /// Whether `state` differs from the saved target state
+    unsaved: bool,
 }
 
 impl PersistentTargetState {
@@ -30,6 +32,7 @@ impl PersistentTargetState {
             state,
             cache_path,
             locked: false,
+            unsaved: false,
         };
         if update_cache {
             state.save().await;
@@ -101,18 +104,29 @@ impl PersistentTargetState {
             state: TargetState::Secured,
             cache_path,
             locked: false,
+            unsaved: false,
         };
         state.save().await;
         state
     }
 
     pub async fn set(&mut self, new_state: TargetState) {
-        if new_state != self.state {
+        if new_state != self.state || self.unsaved {
             self.state = new_state;
+            self.unsaved = false;
             self.save().await;
         }
     }
 
+    /// Change the target state without saving it. If the daemon is restarted before the next call
+    /// to [Self::set], the previously saved target state is used.
+    pub fn set_unsaved(&mut self, new_state: TargetState) {
+        if new_state != self.state {
+            self.state = new_state;
+            self.unsaved = true;
+        }
+    }
+
     /// Prevent the file from being removed when the instance is dropped.
     pub fn lock(&mut self) {
         self.locked = true;
@@ -248,4 +262,28 @@ mod test {
         // Reading back a corrupt target state cache should yield `TargetState::Secured`.
         assert_eq!(*target_state, TargetState::Secured);
     }
+
+    /// A target state set with [PersistentTargetState::set_unsaved] is only saved by the next call
+    /// to [PersistentTargetState::set], even if the state does not change.
+    #[tokio::test]
+    async fn test_unsaved_target_state() {
+        let cache_dir = std::env::temp_dir().join(DUMMY_CACHE_DIR);
+        fs::create_dir_all(&cache_dir).await.unwrap();
+        let mut target_state = PersistentTargetState::new_secured(&cache_dir).await;
+        let read_saved_state = || async {
+            let content = fs::read_to_string(cache_dir.join(TARGET_START_STATE_FILE))
+                .await
+                .unwrap();
+            serde_json::from_str::<TargetState>(&content).unwrap()
+        };
+
+        target_state.set_unsaved(TargetState::Unsecured);
+        assert_eq!(*target_state, TargetState::Unsecured);
+        assert_eq!(read_saved_state().await, TargetState::Secured);
+
+        target_state.set(TargetState::Unsecured).await;
+        assert_eq!(read_saved_state().await, TargetState::Unsecured);
+
+        target_state.finalize().await;
+    }
 }
@@ -0,0 +1,283 @@
+//! Detects the network that the device is connected to, so that the daemon can refrain from
+//! connecting on trusted networks.
+//!
+//! The network is polled, since there is no common way to be notified when the SSID changes.
+//! SSIDs are hashed as soon as they are read, and neither they nor their hashes are logged. They
+//! are salted with a key that is created on first use and stored in the settings directory.
+//!
+//! Detection is not supported on Android.
+
+#[cfg(not(target_os = "android"))]
+use crate::DaemonEventSender;
+use crate::InternalDaemonEvent;
+use mullvad_types::trusted_network::{
+    CurrentNetwork, SSID_HASH_KEY_LEN, SsidHashKey, TrustedNetwork,
+};
+use std::path::{Path, PathBuf};
+#[cfg(not(target_os = "android"))]
+use std::time::Duration;
+#[cfg(not(target_os = "android"))]
+use talpid_core::mpsc::Sender;
+#[cfg(not(target_os = "android"))]
+use talpid_routing::RouteManagerHandle;
+use talpid_types::ErrorExt;
+use tokio::{fs, io};
+
+#[cfg(not(target_os = "android"))]
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+const SSID_HASH_KEY_FILE: &str = "ssid-hash-key";
+
+impl From<CurrentNetwork> for InternalDaemonEvent {
+    fn from(network: CurrentNetwork) -> Self {
+        InternalDaemonEvent::CurrentNetworkChanged(network)
+    }
+}
+
+/// The key that SSIDs are salted with, and the file in the settings directory that it is stored
+/// in.
+pub struct SsidHashKeyFile {
+    path: PathBuf,
+    key: SsidHashKey,
+}
+
+impl SsidHashKeyFile {
+    /// Load the key from `settings_dir`, or create it if it does not exist. If it cannot be
+    /// loaded, a temporary key is used instead, so that the stored key is not replaced. SSIDs that
+    /// were trusted using the stored key are then not detected.
+    pub async fn load(settings_dir: &Path) -> Self {
+        let path = settings_dir.join(SSID_HASH_KEY_FILE);
+        let key = load_ssid_hash_key(&path).await.unwrap_or_else(|error| {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg(
+                    "Failed to load the SSID hash key. Using a temporary key"
+                )
+            );
+            SsidHashKey::new(rand::random())
+        });
+        SsidHashKeyFile { path, key }
+    }
+
+    pub fn key(&self) -> &SsidHashKey {
+        &self.key
+    }
+
+    /// Return where the key is stored.
+    #[cfg(not(target_os = "android"))]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Delete the key from disk.
+    pub async fn remove(&self) -> io::Result<()> {
+        match fs::remove_file(&self.path).await {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Read the key from `path`, or create it if it does not exist. The key is only created if the
+/// file is missing, since replacing it would invalidate all trusted SSIDs.
+async fn load_ssid_hash_key(path: &Path) -> io::Result<SsidHashKey> {
+    match fs::read(path).await {
+        Ok(key) => {
+            let key = <[u8; SSID_HASH_KEY_LEN]>::try_from(key).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "The SSID hash key is invalid")
+            })?;
+            return Ok(SsidHashKey::new(key));
+        }
+        Err(error) if error.kind() == io::ErrorKind::NotFound => (),
+        Err(error) => return Err(error),
+    }
+
+    let key = SsidHashKey::new(rand::random());
+    fs::write(path, key.as_bytes()).await?;
+    Ok(key)
+}
+
+/// Return whether `network` can be detected on this platform. Trusting a network that can never
+/// be detected would silently have no effect.
+pub fn is_detectable(network: &TrustedNetwork) -> bool {
+    match network {
+        TrustedNetwork::SsidHash(_) => cfg!(target_os = "linux"),
+        TrustedNetwork::GatewayMac(_) => cfg!(target_os = "macos"),
+        TrustedNetwork::GatewaySubnet(_) => cfg!(not(target_os = "android")),
+    }
+}
+
+/// Spawn a task that sends the [CurrentNetwork] once it has been detected, and then whenever it
+/// changes. The task stops once the daemon stops receiving events.
+#[cfg(not(target_os = "android"))]
+pub fn spawn(
+    route_manager: RouteManagerHandle,
+    ssid_hash_key: SsidHashKey,
+    event_tx: DaemonEventSender<CurrentNetwork>,
+) {
+    let detector = NetworkDetector {
+        route_manager,
+        ssid_hash_key,
+        #[cfg(target_os = "linux")]
+        network_manager: None,
+    };
+    tokio::spawn(detector.run(event_tx));
+}
+
+#[cfg(not(target_os = "android"))]
+struct NetworkDetector {
+    route_manager: RouteManagerHandle,
+    ssid_hash_key: SsidHashKey,
+    /// Connection to NetworkManager, kept between polls. It is `None` until NetworkManager has
+    /// been reached.
+    #[cfg(target_os = "linux")]
+    network_manager: Option<talpid_dbus::network_manager::NetworkManager>,
+}
+
+#[cfg(not(target_os = "android"))]
+impl NetworkDetector {
+    async fn run(mut self, event_tx: DaemonEventSender<CurrentNetwork>) {
+        let mut last_network = None;
+        loop {
+            let network = self.current_network().await;
+            if last_network.as_ref() != Some(&network) {
+                if event_tx.send(network.clone()).is_err() {
+                    return;
+                }
+                last_network = Some(network);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn current_network(&mut self) -> CurrentNetwork {
+        let (gateway, gateway_mac) = default_gateway(&self.route_manager).await;
+        let ssid_hash = self.ssid().await.map(|ssid| self.ssid_hash_key.hash(&ssid));
+        CurrentNetwork {
+            ssid_hash,
+            gateway,
+            gateway_mac,
+        }
+    }
+
+    /// Return the SSID of the primary connection, if NetworkManager is running and the primary
+    /// connection is a Wi-Fi connection.
+    #[cfg(target_os = "linux")]
+    async fn ssid(&mut self) -> Option<Vec<u8>> {
+        use talpid_dbus::network_manager::NetworkManager;
+
+        let network_manager = self.network_manager.take();
+        let (network_manager, ssid) = tokio::task::spawn_blocking(move || {
+            let network_manager = match network_manager {
+                Some(network_manager) => network_manager,
+                None => NetworkManager::new()?,
+            };
+            let ssid = network_manager.primary_connection_ssid();
+            Ok::<_, talpid_dbus::network_manager::Error>((network_manager, ssid))
+        })
+        .await
+        .ok()?
+        .ok()?;
+        self.network_manager = Some(network_manager);
+        ssid.ok()?
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn ssid(&mut self) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+/// Return the IP address of the default gateway outside of the tunnel, and its MAC address if
+/// it is known.
+#[cfg(target_os = "linux")]
+async fn default_gateway(
+    route_manager: &RouteManagerHandle,
+) -> (Option<std::net::IpAddr>, Option<[u8; 6]>) {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    /// Any public address is routed through the default gateway
+    const PUBLIC_INTERNET_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::new(193, 138, 218, 78));
+
+    // By setting FWMARK, the route that is used outside of the tunnel is returned
+    let route = route_manager
+        .get_destination_route(PUBLIC_INTERNET_ADDRESS, Some(mullvad_types::TUNNEL_FWMARK))
+        .await;
+    let gateway = match route {
+        Ok(route) => route.and_then(|route| route.get_node().get_address()),
+        Err(_) => None,
+    };
+    (gateway, None)
+}
+
+#[cfg(target_os = "macos")]
+async fn default_gateway(
+    route_manager: &RouteManagerHandle,
+) -> (Option<std::net::IpAddr>, Option<[u8; 6]>) {
+    match route_manager.get_default_gateway().await {
+        Ok((v4_gateway, v6_gateway)) => v4_gateway
+            .or(v6_gateway)
+            .map(|gateway| (Some(gateway.ip_address), Some(gateway.mac_address.0)))
+            .unwrap_or_default(),
+        Err(_) => (None, None),
+    }
+}
+
+#[cfg(target_os = "windows")]
+async fn default_gateway(
+    _route_manager: &RouteManagerHandle,
+) -> (Option<std::net::IpAddr>, Option<[u8; 6]>) {
+    use talpid_windows::net::AddressFamily;
+
+    let gateway = talpid_routing::get_best_default_route(AddressFamily::Ipv4)
+        .ok()
+        .flatten()
+        .map(|route| route.gateway.ip());
+    (gateway, None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_detectable() {
+        let subnet = TrustedNetwork::GatewaySubnet("192.168.1.0/24".parse().unwrap());
+        assert_eq!(is_detectable(&subnet), cfg!(not(target_os = "android")));
+        assert_eq!(
+            is_detectable(&TrustedNetwork::GatewayMac([0; 6])),
+            cfg!(target_os = "macos")
+        );
+        let ssid = TrustedNetwork::ssid(b"home", &SsidHashKey::new([0; SSID_HASH_KEY_LEN]));
+        assert_eq!(is_detectable(&ssid), cfg!(target_os = "linux"));
+    }
+
+    /// The key is created once and then read back, so that hashes stay valid across restarts
+    #[tokio::test]
+    async fn test_load_ssid_hash_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        let key_file = SsidHashKeyFile::load(dir).await;
+        assert_eq!(SsidHashKeyFile::load(dir).await.key(), key_file.key());
+
+        key_file.remove().await.unwrap();
+        assert!(!key_file.path.exists());
+        key_file.remove().await.unwrap();
+    }
+
+    /// An unreadable key is not replaced, since that would invalidate all trusted SSIDs
+    #[tokio::test]
+    async fn test_invalid_ssid_hash_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SSID_HASH_KEY_FILE);
+        fs::write(&path, b"invalid").await.unwrap();
+
+        let key_file = SsidHashKeyFile::load(dir.path()).await;
+        assert_ne!(
+            key_file.key(),
+            SsidHashKeyFile::load(dir.path()).await.key()
+        );
+        assert_eq!(fs::read(&path).await.unwrap(), b"invalid");
+    }
+}
@@ -1,20 +1,30 @@
-use std::{future::Future, net::IpAddr, pin::Pin, sync::Arc};
+use std::{collections::VecDeque, future::Future, net::IpAddr, pin::Pin, sync::Arc};
 
 use talpid_types::net::wireguard::TunnelParameters;
 use tokio::sync::Mutex;
 
 use mullvad_relay_selector::{GetRelay, RelaySelector, WireguardConfig};
 use mullvad_types::{
-    endpoint::MullvadEndpoint, location::GeoIpLocation, relay_list::WireguardRelay,
+    connection_config::EffectiveConnectionConfig,
+    endpoint::MullvadEndpoint,
+    location::GeoIpLocation,
+    relay_constraints::{GeographicLocationConstraint, SelectedObfuscation},
+    relay_list::WireguardRelay,
     settings::TunnelOptions,
 };
 use talpid_core::tunnel_state_machine::TunnelParametersGenerator;
-use talpid_types::net::{obfuscation::Obfuscators, wireguard};
+use talpid_types::net::{
+    ObfuscationEndpoint, ObfuscationType, obfuscation::Obfuscators, wireguard,
+};
 
 use talpid_types::{ErrorExt, net::IpAvailability, tunnel::ParameterGenerationError};
 
 use crate::device::{AccountManagerHandle, Error as DeviceError, PrivateAccountAndDevice};
 
+/// Maximum number of attempts to remember the obfuscation methods of. The tunnel may retry
+/// indefinitely, so older attempts are forgotten.
+const MAX_OBFUSCATION_ATTEMPTS: usize = 32;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("Not logged in on a valid device")]
@@ -31,14 +41,42 @@ pub enum Error {
 }
 
 #[derive(Clone)]
-pub(crate) struct ParametersGenerator(Arc<Mutex<InnerParametersGenerator>>);
+pub(crate) struct ParametersGenerator {
+    inner: Arc<Mutex<InnerParametersGenerator>>,
+    account_manager: AccountManagerHandle,
+}
 
 struct InnerParametersGenerator {
     relay_selector: RelaySelector,
     tunnel_options: TunnelOptions,
-    account_manager: AccountManagerHandle,
 
     last_generated_relays: Option<LastSelectedRelays>,
+    last_gateways: Vec<IpAddr>,
+    /// Obfuscation methods used by the most recent attempts since the tunnel was last started. At
+    /// most [MAX_OBFUSCATION_ATTEMPTS] are kept.
+    obfuscation_attempts: VecDeque<Vec<ObfuscationType>>,
+
+    relay_override: Option<RelayOverride>,
+}
+
+/// Relay to use instead of the normal relay selection.
+#[derive(Clone)]
+struct RelayOverride {
+    location: GeographicLocationConstraint,
+    /// Obfuscation method to use instead of the obfuscation settings
+    obfuscation: Option<SelectedObfuscation>,
+    /// Whether the override is dropped once the tunnel is restarted. Otherwise, it is used until
+    /// it is cleared.
+    once: bool,
+    /// The retry attempt that the override was last used for, if it has been used.
+    last_attempt: Option<u32>,
+}
+
+impl RelayOverride {
+    /// Whether the override should be dropped if a new tunnel is started.
+    fn is_dropped_on_restart(&self) -> bool {
+        self.once && self.last_attempt.is_some()
+    }
 }
 
 impl ParametersGenerator {
@@ -48,68 +86,229 @@ impl ParametersGenerator {
         relay_selector: RelaySelector,
         tunnel_options: TunnelOptions,
     ) -> Self {
-        Self(Arc::new(Mutex::new(InnerParametersGenerator {
-            tunnel_options,
-            relay_selector,
-
+        Self {
+            inner: Arc::new(Mutex::new(InnerParametersGenerator::new(
+                relay_selector,
+                tunnel_options,
+            ))),
             account_manager,
-
-            last_generated_relays: None,
-        })))
+        }
     }
 
     /// Sets the tunnel options to use when generating new tunnel parameters.
     pub async fn set_tunnel_options(&self, tunnel_options: &TunnelOptions) {
-        self.0.lock().await.tunnel_options = tunnel_options.clone();
+        self.inner.lock().await.tunnel_options = tunnel_options.clone();
+    }
+
+    /// Use the relay at `location` for the next tunnel, including its retry attempts. Normal
+    /// relay selection is used again once the tunnel is restarted from the first attempt.
+    pub async fn set_relay_override(&self, location: GeographicLocationConstraint) {
+        self.inner.lock().await.relay_override = Some(RelayOverride {
+            location,
+            obfuscation: None,
+            once: true,
+            last_attempt: None,
+        });
+    }
+
+    /// Use the relay at `location` with the obfuscation method `obfuscation` for all tunnels
+    /// until [`Self::clear_relay_override`] is called, also if the tunnel is restarted.
+    pub async fn hold_relay_override(
+        &self,
+        location: GeographicLocationConstraint,
+        obfuscation: SelectedObfuscation,
+    ) {
+        self.inner.lock().await.relay_override = Some(RelayOverride {
+            location,
+            obfuscation: Some(obfuscation),
+            once: false,
+            last_attempt: None,
+        });
+    }
+
+    /// Remove any relay override set by [`Self::set_relay_override`] or
+    /// [`Self::hold_relay_override`].
+    pub async fn clear_relay_override(&self) {
+        self.inner.lock().await.relay_override = None;
+    }
+
+    /// Gets the retry attempt that the current relay override was last used for. `None` if there
+    /// is no relay override, or if no tunnel parameters have been generated using it yet.
+    pub async fn relay_override_attempt(&self) -> Option<u32> {
+        self.inner
+            .lock()
+            .await
+            .relay_override
+            .as_ref()?
+            .last_attempt
     }
 
     pub async fn last_relay_was_overridden(&self) -> bool {
-        let inner = self.0.lock().await;
+        let inner = self.inner.lock().await;
         let Some(relays) = inner.last_generated_relays.as_ref() else {
             return false;
         };
         relays.server_override
     }
 
+    /// Gets the tunnel gateways of the last generated tunnel parameters.
+    pub async fn get_last_gateways(&self) -> Vec<IpAddr> {
+        self.inner.lock().await.last_gateways.clone()
+    }
+
+    /// Gets the obfuscation methods used by the most recent attempts since the tunnel was last
+    /// started, oldest first. The last entry belongs to the last generated tunnel parameters.
+    pub async fn get_obfuscation_attempts(&self) -> Vec<Vec<ObfuscationType>> {
+        self.inner
+            .lock()
+            .await
+            .obfuscation_attempts
+            .iter()
+            .cloned()
+            .collect()
+    }
+
     /// Gets the location associated with the last generated tunnel parameters.
     pub async fn get_last_location(&self) -> Option<GeoIpLocation> {
-        let inner = self.0.lock().await;
+        let inner = self.inner.lock().await;
+        inner
+            .last_generated_relays
+            .as_ref()
+            .map(LastSelectedRelays::location)
+    }
 
-        let relays = inner.last_generated_relays.as_ref()?;
+    /// Returns the configuration that a new tunnel would use if it was started now. Unlike
+    /// [`TunnelParametersGenerator::generate`], this does not affect the state of the generator.
+    pub async fn effective_config(
+        &self,
+        ip_availability: IpAvailability,
+    ) -> Result<EffectiveConnectionConfig, Error> {
+        let data = device(&self.account_manager).await?;
+        self.inner
+            .lock()
+            .await
+            .effective_config(data, ip_availability)
+    }
+}
 
-        let take_hostname =
-            |relay: &Option<WireguardRelay>| relay.as_ref().map(|relay| relay.hostname.clone());
+impl InnerParametersGenerator {
+    fn new(relay_selector: RelaySelector, tunnel_options: TunnelOptions) -> Self {
+        InnerParametersGenerator {
+            tunnel_options,
+            relay_selector,
 
-        let entry_hostname = take_hostname(&relays.entry);
-        let hostname = relays.exit.hostname.clone();
-        let obfuscator_hostname = take_hostname(&relays.obfuscator);
-        let location = relays.exit.location.clone();
+            last_generated_relays: None,
+            last_gateways: vec![],
+            obfuscation_attempts: VecDeque::new(),
 
-        Some(GeoIpLocation {
-            ipv4: None,
-            ipv6: None,
-            country: location.country,
-            city: Some(location.city),
-            latitude: location.latitude,
-            longitude: location.longitude,
-            mullvad_exit_ip: true,
-            hostname: Some(hostname),
-            entry_hostname,
-            obfuscator_hostname,
-        })
+            relay_override: None,
+        }
     }
-}
 
-impl InnerParametersGenerator {
-    async fn generate(
+    /// Create tunnel parameters for `retry_attempt`, and remember the relays and obfuscation
+    /// methods that they use.
+    fn generate(
         &mut self,
+        data: PrivateAccountAndDevice,
         retry_attempt: u32,
         ip_availability: IpAvailability,
     ) -> Result<TunnelParameters, Error> {
-        let data = self.device().await?;
-        let selected_relay = self
-            .relay_selector
-            .get_relay(retry_attempt as usize, ip_availability)?;
+        if retry_attempt == 0 {
+            self.obfuscation_attempts.clear();
+        }
+        let relay_override = self.use_relay_override(retry_attempt);
+        let (parameters, relays) = self.select(
+            &self.relay_selector,
+            data,
+            retry_attempt,
+            ip_availability,
+            relay_override,
+        )?;
+
+        if self.obfuscation_attempts.len() == MAX_OBFUSCATION_ATTEMPTS {
+            self.obfuscation_attempts.pop_front();
+        }
+        self.obfuscation_attempts.push_back(
+            parameters
+                .obfuscation
+                .as_ref()
+                .map(obfuscation_types)
+                .unwrap_or_default(),
+        );
+        self.last_generated_relays = relays;
+
+        Ok(parameters)
+    }
+
+    /// Returns the configuration that a new tunnel would use. See
+    /// [`ParametersGenerator::effective_config`].
+    fn effective_config(
+        &self,
+        data: PrivateAccountAndDevice,
+        ip_availability: IpAvailability,
+    ) -> Result<EffectiveConnectionConfig, Error> {
+        let relay_override = self
+            .relay_override
+            .clone()
+            .filter(|relay_override| !relay_override.is_dropped_on_restart());
+        // Peek, so that the selection does not affect the next tunnel
+        let (parameters, relays) = self.select(
+            &self.relay_selector.peek(),
+            data,
+            0,
+            ip_availability,
+            relay_override,
+        )?;
+
+        let dns_servers = crate::dns::effective_dns_servers(
+            &self.tunnel_options.dns_options,
+            &tunnel_gateways(&parameters),
+        );
+        let connection = &parameters.connection;
+        let allowed_ips = connection
+            .exit_peer
+            .as_ref()
+            .unwrap_or(&connection.peer)
+            .allowed_ips
+            .clone();
+
+        Ok(EffectiveConnectionConfig {
+            endpoint: parameters.get_tunnel_endpoint(),
+            location: relays.as_ref().map(LastSelectedRelays::location),
+            allowed_ips,
+            mtu: parameters.options.mtu,
+            dns_servers,
+        })
+    }
+
+    /// Select relays using `relay_selector` and create tunnel parameters for `retry_attempt`,
+    /// using `relay_override` instead of the relay constraints if it is set. The relays are `None`
+    /// for custom tunnel endpoints.
+    fn select(
+        &self,
+        relay_selector: &RelaySelector,
+        data: PrivateAccountAndDevice,
+        retry_attempt: u32,
+        ip_availability: IpAvailability,
+        relay_override: Option<RelayOverride>,
+    ) -> Result<(TunnelParameters, Option<LastSelectedRelays>), Error> {
+        let retry_attempt = retry_attempt as usize;
+        let selected_relay = match relay_override {
+            Some(relay_override) => match relay_override.obfuscation {
+                Some(obfuscation) => relay_selector.get_relay_with_location_and_obfuscation(
+                    relay_override.location,
+                    obfuscation,
+                    retry_attempt,
+                    ip_availability,
+                )?,
+                None => relay_selector.get_relay_with_location(
+                    relay_override.location,
+                    retry_attempt,
+                    ip_availability,
+                )?,
+            },
+            None => relay_selector.get_relay(retry_attempt, ip_availability)?,
+        };
 
         match selected_relay {
             GetRelay::Mullvad {
@@ -131,34 +330,56 @@ impl InnerParametersGenerator {
                     (first_relay.overridden_ipv4 && endpoint.peer.endpoint.is_ipv4())
                         || (first_relay.overridden_ipv6 && endpoint.peer.endpoint.is_ipv6())
                 };
+                #[cfg(daita)]
+                let daita_supported = entry.as_ref().unwrap_or(&exit).endpoint_data.daita;
 
-                self.last_generated_relays = Some(LastSelectedRelays {
+                let relays = LastSelectedRelays {
                     entry,
                     exit,
                     obfuscator: obfuscator_relay,
                     server_override,
-                });
-
-                Ok(self.create_wireguard_tunnel_parameters(endpoint, data, obfuscator_config))
+                };
+                let parameters = self.create_wireguard_tunnel_parameters(
+                    endpoint,
+                    data,
+                    obfuscator_config,
+                    #[cfg(daita)]
+                    daita_supported,
+                );
+                Ok((parameters, Some(relays)))
             }
             GetRelay::Custom(custom_relay) => {
-                self.last_generated_relays = None;
-                custom_relay
+                let parameters = custom_relay
                     // TODO: generate proxy settings for custom tunnels
                     .to_tunnel_parameters(self.tunnel_options.clone())
                     .map_err(|e| {
                         log::error!("Failed to resolve hostname for custom tunnel config: {}", e);
                         Error::ResolveCustomHostname
-                    })
+                    })?;
+                Ok((parameters, None))
             }
         }
     }
 
+    /// Return the relay override to use for `retry_attempt`, if any. A one-time override is
+    /// dropped when a new tunnel is started after the one it was used for.
+    fn use_relay_override(&mut self, retry_attempt: u32) -> Option<RelayOverride> {
+        let relay_override = self.relay_override.as_mut()?;
+        if retry_attempt == 0 && relay_override.is_dropped_on_restart() {
+            log::debug!("Dropping one-time relay override");
+            self.relay_override = None;
+            return None;
+        }
+        relay_override.last_attempt = Some(retry_attempt);
+        Some(relay_override.clone())
+    }
+
     fn create_wireguard_tunnel_parameters(
         &self,
         endpoint: MullvadEndpoint,
         data: PrivateAccountAndDevice,
         obfuscator_config: Option<Obfuscators>,
+        #[cfg(daita)] daita_supported: bool,
     ) -> TunnelParameters {
         let tunnel_ipv4 = data.device.wg_data.addresses.ipv4_address.ip();
         let tunnel_ipv6 = data.device.wg_data.addresses.ipv6_address.ip();
@@ -167,6 +388,18 @@ impl InnerParametersGenerator {
             addresses: vec![IpAddr::from(tunnel_ipv4), IpAddr::from(tunnel_ipv6)],
         };
 
+        #[cfg_attr(not(daita), allow(unused_mut))]
+        let mut options = self
+            .tunnel_options
+            .wireguard
+            .clone()
+            .into_talpid_tunnel_options();
+        // The relay selector only picks a relay without DAITA if DAITA is not required
+        #[cfg(daita)]
+        {
+            options.daita &= daita_supported;
+        }
+
         wireguard::TunnelParameters {
             connection: wireguard::ConnectionConfig {
                 tunnel,
@@ -177,20 +410,17 @@ impl InnerParametersGenerator {
                 #[cfg(target_os = "linux")]
                 fwmark: Some(mullvad_types::TUNNEL_FWMARK),
             },
-            options: self
-                .tunnel_options
-                .wireguard
-                .clone()
-                .into_talpid_tunnel_options(),
+            options,
             generic_options: self.tunnel_options.generic.clone(),
             obfuscation: obfuscator_config,
         }
     }
+}
 
-    async fn device(&self) -> Result<PrivateAccountAndDevice, Error> {
-        let device_state = self.account_manager.data().await?;
-        device_state.into_device().ok_or(Error::NoAuthDetails)
-    }
+/// Return the device that tunnels are created for.
+async fn device(account_manager: &AccountManagerHandle) -> Result<PrivateAccountAndDevice, Error> {
+    let device_state = account_manager.data().await?;
+    device_state.into_device().ok_or(Error::NoAuthDetails)
 }
 
 impl TunnelParametersGenerator for ParametersGenerator {
@@ -199,23 +429,51 @@ impl TunnelParametersGenerator for ParametersGenerator {
         retry_attempt: u32,
         ip_availability: IpAvailability,
     ) -> Pin<Box<dyn Future<Output = Result<TunnelParameters, ParameterGenerationError>>>> {
-        let generator = self.0.clone();
+        let generator = self.inner.clone();
+        let account_manager = self.account_manager.clone();
         Box::pin(async move {
+            // Don't hold the lock while waiting for the account manager
+            let data = device(&account_manager).await;
             let mut inner = generator.lock().await;
-            inner
-                .generate(retry_attempt, ip_availability)
-                .await
+            let parameters = data
+                .and_then(|data| inner.generate(data, retry_attempt, ip_availability))
                 .inspect_err(|error| {
                     log::error!(
                         "{}",
                         error.display_chain_with_msg("Failed to generate tunnel parameters")
                     );
                 })
-                .map_err(ParameterGenerationError::from)
+                .map_err(ParameterGenerationError::from)?;
+            inner.last_gateways = tunnel_gateways(&parameters);
+            Ok(parameters)
         })
     }
 }
 
+/// Return the obfuscation methods that `obfuscators` may use, in the order they are tried.
+fn obfuscation_types(obfuscators: &Obfuscators) -> Vec<ObfuscationType> {
+    let configs = match obfuscators {
+        Obfuscators::Single(config) => vec![config],
+        Obfuscators::Multiplexer {
+            configs: (first, remaining),
+            ..
+        } => std::iter::once(first).chain(remaining).collect(),
+    };
+    configs
+        .into_iter()
+        .map(|config| ObfuscationEndpoint::from(config).obfuscation_type)
+        .collect()
+}
+
+/// Return the gateways which will be configured on the tunnel interface for `parameters`.
+fn tunnel_gateways(parameters: &TunnelParameters) -> Vec<IpAddr> {
+    let mut gateways = vec![IpAddr::from(parameters.connection.ipv4_gateway)];
+    if parameters.generic_options.enable_ipv6 {
+        gateways.extend(parameters.connection.ipv6_gateway.map(IpAddr::from));
+    }
+    gateways
+}
+
 impl From<Error> for ParameterGenerationError {
     fn from(error: Error) -> Self {
         match error {
@@ -234,6 +492,9 @@ impl From<Error> for ParameterGenerationError {
             Error::SelectRelay(mullvad_relay_selector::Error::NoRelayExit(_)) => {
                 ParameterGenerationError::NoMatchingRelayExit
             }
+            Error::SelectRelay(mullvad_relay_selector::Error::NoDaitaRelay(_)) => {
+                ParameterGenerationError::NoMatchingDaitaRelay
+            }
             Error::NoAuthDetails | Error::SelectRelay(_) | Error::Device(_) => {
                 ParameterGenerationError::NoMatchingRelay
             }
@@ -254,3 +515,237 @@ struct LastSelectedRelays {
     obfuscator: Option<WireguardRelay>,
     server_override: bool,
 }
+
+impl LastSelectedRelays {
+    /// Return the location of the exit relay, and the hostnames of all relays.
+    fn location(&self) -> GeoIpLocation {
+        let take_hostname =
+            |relay: &Option<WireguardRelay>| relay.as_ref().map(|relay| relay.hostname.clone());
+
+        let entry_hostname = take_hostname(&self.entry);
+        let hostname = self.exit.hostname.clone();
+        let obfuscator_hostname = take_hostname(&self.obfuscator);
+        let location = self.exit.location.clone();
+
+        GeoIpLocation {
+            ipv4: None,
+            ipv6: None,
+            country: location.country,
+            city: Some(location.city),
+            latitude: location.latitude,
+            longitude: location.longitude,
+            mullvad_exit_ip: true,
+            hostname: Some(hostname),
+            entry_hostname,
+            obfuscator_hostname,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::device::PrivateDevice;
+    use mullvad_relay_selector::SelectorConfig;
+    use mullvad_types::{
+        location::Location,
+        relay_list::{
+            BridgeList, EndpointData, Relay, RelayList, RelayListCity, RelayListCountry,
+            WireguardRelayEndpointData,
+        },
+        wireguard::{AssociatedAddresses, WireguardData},
+    };
+    use talpid_types::net::wireguard::{PrivateKey, PublicKey};
+
+    fn relay(hostname: &str) -> WireguardRelay {
+        WireguardRelay::new(
+            false,
+            false,
+            true,
+            true,
+            "provider".to_owned(),
+            WireguardRelayEndpointData::new(
+                PublicKey::from_base64("BLNHNoGO88LjV/wDBa7CUUwUzPq/fO2UwcGLy56hKy4=").unwrap(),
+            ),
+            Relay {
+                hostname: hostname.to_owned(),
+                ipv4_addr_in: "185.213.154.68".parse().unwrap(),
+                ipv6_addr_in: None,
+                active: true,
+                weight: 1,
+                location: Location {
+                    country: "Sweden".to_owned(),
+                    country_code: "se".to_owned(),
+                    city: "Gothenburg".to_owned(),
+                    city_code: "got".to_owned(),
+                    latitude: 57.71,
+                    longitude: 11.97,
+                },
+            },
+        )
+    }
+
+    fn generator() -> InnerParametersGenerator {
+        let relays = RelayList {
+            countries: vec![RelayListCountry {
+                name: "Sweden".to_owned(),
+                code: "se".to_owned(),
+                cities: vec![RelayListCity {
+                    name: "Gothenburg".to_owned(),
+                    code: "got".to_owned(),
+                    latitude: 57.70887,
+                    longitude: 11.97456,
+                    relays: vec![relay("se-got-wg-001"), relay("se-got-wg-002")],
+                }],
+            }],
+            wireguard: EndpointData {
+                port_ranges: vec![53..=53, 4000..=33433],
+                ..Default::default()
+            },
+        };
+        let relay_selector =
+            RelaySelector::new(SelectorConfig::default(), relays, BridgeList::default());
+        InnerParametersGenerator::new(relay_selector, TunnelOptions::default())
+    }
+
+    fn device() -> PrivateAccountAndDevice {
+        PrivateAccountAndDevice {
+            account_number: "1234123412341234".to_owned(),
+            device: PrivateDevice {
+                id: "device".to_owned(),
+                name: "happy seagull".to_owned(),
+                wg_data: WireguardData {
+                    private_key: PrivateKey::new_from_random(),
+                    addresses: AssociatedAddresses {
+                        ipv4_address: "10.64.0.1/32".parse().unwrap(),
+                        ipv6_address: "fc00:bbbb:bbbb:bb01::1/128".parse().unwrap(),
+                    },
+                    created: chrono::Utc::now(),
+                },
+                hijack_dns: false,
+                created: chrono::Utc::now(),
+            },
+        }
+    }
+
+    fn last_hostname(generator: &InnerParametersGenerator) -> Option<String> {
+        let relays = generator.last_generated_relays.as_ref()?;
+        Some(relays.exit.hostname.clone())
+    }
+
+    /// Generating parameters should remember the selected relays and the obfuscation methods of
+    /// each attempt since the tunnel was started
+    #[test]
+    fn test_generate() {
+        let mut generator = generator();
+
+        generator
+            .generate(device(), 0, IpAvailability::Ipv4)
+            .unwrap();
+        assert!(last_hostname(&generator).is_some());
+        generator
+            .generate(device(), 1, IpAvailability::Ipv4)
+            .unwrap();
+        assert_eq!(generator.obfuscation_attempts.len(), 2);
+
+        // Starting a new tunnel forgets the previous attempts
+        generator
+            .generate(device(), 0, IpAvailability::Ipv4)
+            .unwrap();
+        assert_eq!(generator.obfuscation_attempts.len(), 1);
+
+        for attempt in 1..=MAX_OBFUSCATION_ATTEMPTS as u32 {
+            generator
+                .generate(device(), attempt, IpAvailability::Ipv4)
+                .unwrap();
+        }
+        assert_eq!(
+            generator.obfuscation_attempts.len(),
+            MAX_OBFUSCATION_ATTEMPTS
+        );
+    }
+
+    /// A one-time relay override should be used for all attempts of the next tunnel, and dropped
+    /// once another tunnel is started
+    #[test]
+    fn test_generate_with_relay_override() {
+        let mut generator = generator();
+        generator.relay_override = Some(RelayOverride {
+            location: GeographicLocationConstraint::hostname("se", "got", "se-got-wg-002"),
+            obfuscation: None,
+            once: true,
+            last_attempt: None,
+        });
+
+        for attempt in 0..3 {
+            generator
+                .generate(device(), attempt, IpAvailability::Ipv4)
+                .unwrap();
+            assert_eq!(last_hostname(&generator).as_deref(), Some("se-got-wg-002"));
+            assert_eq!(
+                generator.relay_override.as_ref().unwrap().last_attempt,
+                Some(attempt)
+            );
+        }
+
+        generator
+            .generate(device(), 0, IpAvailability::Ipv4)
+            .unwrap();
+        assert!(generator.relay_override.is_none());
+    }
+
+    /// The effective configuration should use the relay override that a new tunnel would use,
+    /// without affecting the state of the generator
+    #[test]
+    fn test_effective_config() {
+        let mut generator = generator();
+        generator.relay_override = Some(RelayOverride {
+            location: GeographicLocationConstraint::hostname("se", "got", "se-got-wg-002"),
+            obfuscation: None,
+            once: true,
+            last_attempt: None,
+        });
+
+        let config = generator
+            .effective_config(device(), IpAvailability::Ipv4)
+            .unwrap();
+        let location = config.location.unwrap();
+        assert_eq!(location.hostname.as_deref(), Some("se-got-wg-002"));
+        assert!(config.endpoint.endpoint.address.is_ipv4());
+        assert!(generator.last_generated_relays.is_none());
+        assert!(generator.obfuscation_attempts.is_empty());
+        assert_eq!(
+            generator.relay_override.as_ref().unwrap().last_attempt,
+            None
+        );
+
+        // An override that has been used is dropped when a new tunnel is started, so it is ignored
+        // even if it would fail
+        let relay_override = generator.relay_override.as_mut().unwrap();
+        relay_override.location = GeographicLocationConstraint::hostname("se", "got", "missing");
+        assert!(
+            generator
+                .effective_config(device(), IpAvailability::Ipv4)
+                .is_err()
+        );
+        generator.relay_override.as_mut().unwrap().last_attempt = Some(0);
+        generator
+            .effective_config(device(), IpAvailability::Ipv4)
+            .unwrap();
+        assert!(generator.relay_override.is_some());
+    }
+
+    /// Selection should fail if no relay satisfies the IP availability
+    #[test]
+    fn test_select_unavailable_ip_version() {
+        let generator = generator();
+        let result = generator.select(
+            &generator.relay_selector,
+            device(),
+            0,
+            IpAvailability::Ipv6,
+            None,
+        );
+        assert!(matches!(result, Err(Error::SelectRelay(_))));
+    }
+}
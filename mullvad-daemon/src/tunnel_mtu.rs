@@ -0,0 +1,128 @@
+//! Computes the MTU and per-packet overhead of the active tunnel.
+
+use mullvad_types::wireguard::TunnelMtuInfo;
+use std::net::{IpAddr, SocketAddr};
+use talpid_tunnel::{IPV4_HEADER_SIZE, IPV6_HEADER_SIZE, WIREGUARD_HEADER_SIZE};
+use talpid_types::net::{ObfuscationInfo, ObfuscationType, TunnelEndpoint};
+use tunnel_obfuscation::{lwo, multiplexer, quic, shadowsocks, udp2tcp};
+
+/// Describe the MTU of a connected tunnel. `configured_mtu` is the MTU set in the settings, if any.
+///
+/// Only the overhead of the entry hop is included, since traffic to the exit relay in multihop is
+/// routed back through the tunnel interface.
+pub fn tunnel_mtu_info(endpoint: &TunnelEndpoint, configured_mtu: Option<u16>) -> TunnelMtuInfo {
+    let entry_endpoint = endpoint.entry_endpoint.unwrap_or(endpoint.endpoint);
+    let tunnel_mtu = endpoint
+        .tunnel_interface
+        .as_deref()
+        .and_then(interface_mtu)
+        .or(configured_mtu);
+
+    TunnelMtuInfo {
+        configured_mtu,
+        tunnel_mtu,
+        wireguard_overhead: wireguard_overhead(entry_endpoint.address.ip()),
+        obfuscation_overhead: endpoint
+            .obfuscation
+            .as_ref()
+            .map(|obfuscation| obfuscation_overhead(obfuscation, entry_endpoint.address))
+            .unwrap_or(0),
+    }
+}
+
+/// Calculates WireGuard per-packet overhead
+const fn wireguard_overhead(ip: IpAddr) -> u16 {
+    match ip {
+        IpAddr::V4(..) => IPV4_HEADER_SIZE + WIREGUARD_HEADER_SIZE,
+        IpAddr::V6(..) => IPV6_HEADER_SIZE + WIREGUARD_HEADER_SIZE,
+    }
+}
+
+/// Calculates the per-packet overhead of `obfuscation` when forwarding to `wireguard_endpoint`
+fn obfuscation_overhead(obfuscation: &ObfuscationInfo, wireguard_endpoint: SocketAddr) -> u16 {
+    match obfuscation {
+        ObfuscationInfo::Single(obfuscator) => match obfuscator.obfuscation_type {
+            ObfuscationType::Udp2Tcp => udp2tcp::PACKET_OVERHEAD,
+            ObfuscationType::Shadowsocks => shadowsocks::packet_overhead(wireguard_endpoint),
+            // The tunnel MTU is not lowered for QUIC, since the proxy fragments packets that are
+            // too large. This is the overhead of each packet sent through it.
+            ObfuscationType::Quic => quic::MAX_PACKET_OVERHEAD,
+            ObfuscationType::Lwo => lwo::PACKET_OVERHEAD,
+        },
+        ObfuscationInfo::Multiplexer { .. } => multiplexer::PACKET_OVERHEAD,
+    }
+}
+
+#[cfg(unix)]
+fn interface_mtu(interface: &str) -> Option<u16> {
+    talpid_net::unix::get_mtu(interface).ok()
+}
+
+#[cfg(windows)]
+fn interface_mtu(_interface: &str) -> Option<u16> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use talpid_types::net::{Endpoint, ObfuscationEndpoint, TransportProtocol};
+
+    fn endpoint(address: &str, obfuscation: Option<ObfuscationInfo>) -> TunnelEndpoint {
+        TunnelEndpoint {
+            endpoint: Endpoint {
+                address: address.parse().unwrap(),
+                protocol: TransportProtocol::Udp,
+            },
+            quantum_resistant: false,
+            obfuscation,
+            entry_endpoint: None,
+            tunnel_interface: None,
+            #[cfg(daita)]
+            daita: false,
+        }
+    }
+
+    fn obfuscator(obfuscation_type: ObfuscationType, address: &str) -> ObfuscationInfo {
+        ObfuscationInfo::Single(ObfuscationEndpoint {
+            endpoint: Endpoint {
+                address: address.parse().unwrap(),
+                protocol: TransportProtocol::Tcp,
+            },
+            obfuscation_type,
+        })
+    }
+
+    #[test]
+    fn test_plain_wireguard_overhead() {
+        let info = tunnel_mtu_info(&endpoint("1.2.3.4:51820", None), Some(1380));
+        assert_eq!(info.configured_mtu, Some(1380));
+        assert_eq!(info.tunnel_mtu, Some(1380));
+        assert_eq!(info.wireguard_overhead, 60);
+        assert_eq!(info.obfuscation_overhead, 0);
+        assert_eq!(info.link_packet_size(), Some(1440));
+
+        let info = tunnel_mtu_info(&endpoint("[::1]:51820", None), None);
+        assert_eq!(info.tunnel_mtu, None);
+        assert_eq!(info.overhead(), 80);
+    }
+
+    #[test]
+    fn test_obfuscation_overhead() {
+        let udp2tcp = obfuscator(ObfuscationType::Udp2Tcp, "1.2.3.4:443");
+        let info = tunnel_mtu_info(&endpoint("1.2.3.4:51820", Some(udp2tcp)), None);
+        assert_eq!(info.obfuscation_overhead, 54);
+
+        let shadowsocks = obfuscator(ObfuscationType::Shadowsocks, "1.2.3.4:443");
+        let info = tunnel_mtu_info(&endpoint("1.2.3.4:51820", Some(shadowsocks)), None);
+        assert_eq!(info.obfuscation_overhead, 55);
+
+        let quic = obfuscator(ObfuscationType::Quic, "1.2.3.4:443");
+        let info = tunnel_mtu_info(&endpoint("1.2.3.4:51820", Some(quic)), None);
+        assert_eq!(info.obfuscation_overhead, 47);
+
+        let lwo = obfuscator(ObfuscationType::Lwo, "1.2.3.4:51820");
+        let info = tunnel_mtu_info(&endpoint("1.2.3.4:51820", Some(lwo)), None);
+        assert_eq!(info.obfuscation_overhead, 0);
+    }
+}
@@ -0,0 +1,341 @@
+//! Accumulates usage statistics across tunnel sessions and persists them in the settings
+//! directory. The statistics are only used locally, and are never sent to the API.
+//!
+//! The statistics of the current session are saved periodically, so that little is lost if the
+//! daemon does not shut down cleanly.
+
+use crate::{DaemonEventSender, InternalDaemonEvent};
+use mullvad_types::{states::TunnelState, usage_stats::UsageStats};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use talpid_core::mpsc::Sender;
+use talpid_types::{ErrorExt, net::TunnelTraffic};
+use tokio::{fs, io, time::Instant};
+
+const USAGE_STATS_FILE: &str = "usage-stats.json";
+
+/// How often the statistics of the current session are saved
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Sent when the statistics should be saved.
+#[derive(Debug)]
+pub struct FlushUsageStats;
+
+impl From<FlushUsageStats> for InternalDaemonEvent {
+    fn from(_: FlushUsageStats) -> Self {
+        InternalDaemonEvent::FlushUsageStats
+    }
+}
+
+/// Spawn a task that sends [FlushUsageStats] every [FLUSH_INTERVAL]. The task stops once the
+/// daemon stops receiving events.
+pub fn spawn_flush_timer(event_tx: DaemonEventSender<FlushUsageStats>) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval_at(Instant::now() + FLUSH_INTERVAL, FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if event_tx.send(FlushUsageStats).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+/// Statistics as stored on disk.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct UsageRecord {
+    connected_time: Duration,
+    tx_bytes: u64,
+    rx_bytes: u64,
+    sessions: u64,
+    /// Number of sessions per relay hostname
+    relay_sessions: BTreeMap<String, u64>,
+}
+
+impl UsageRecord {
+    fn add_session(&mut self, hostname: Option<&str>) {
+        self.sessions += 1;
+        if let Some(hostname) = hostname {
+            *self.relay_sessions.entry(hostname.to_owned()).or_default() += 1;
+        }
+    }
+
+    fn add_traffic(&mut self, traffic: TunnelTraffic) {
+        self.tx_bytes = self.tx_bytes.saturating_add(traffic.tx_bytes);
+        self.rx_bytes = self.rx_bytes.saturating_add(traffic.rx_bytes);
+    }
+
+    /// Return the relay with the most sessions. Ties are broken by the hostname.
+    fn favorite_relay(&self) -> Option<&str> {
+        self.relay_sessions
+            .iter()
+            .max_by(|(a_host, a_count), (b_host, b_count)| {
+                a_count.cmp(b_count).then(b_host.cmp(a_host))
+            })
+            .map(|(hostname, _)| hostname.as_str())
+    }
+}
+
+/// Keeps track of tunnel sessions and updates the statistics on disk.
+pub struct UsageStatsTracker {
+    /// Where the statistics are saved. This is `None` once they have been removed, since they
+    /// must not be saved again before the daemon shuts down after a factory reset.
+    path: Option<PathBuf>,
+    record: UsageRecord,
+    /// When the current session was connected
+    connected_since: Option<Instant>,
+    /// Total tunnel traffic when it was last added to the statistics
+    counted_traffic: TunnelTraffic,
+}
+
+impl UsageStatsTracker {
+    pub async fn new(settings_dir: &Path) -> Self {
+        let path = settings_dir.join(USAGE_STATS_FILE);
+        let record = Self::read_record(&path).await;
+        UsageStatsTracker {
+            path: Some(path),
+            record,
+            connected_since: None,
+            counted_traffic: TunnelTraffic::default(),
+        }
+    }
+
+    async fn read_record(path: &Path) -> UsageRecord {
+        match fs::read_to_string(path).await {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|error| {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to parse usage stats")
+                );
+                UsageRecord::default()
+            }),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => UsageRecord::default(),
+            Err(error) => {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to read usage stats")
+                );
+                UsageRecord::default()
+            }
+        }
+    }
+
+    /// Update the statistics after a tunnel state transition. `traffic` is the total traffic
+    /// reported by the tunnel state machine.
+    pub async fn on_tunnel_state(&mut self, state: &TunnelState, traffic: TunnelTraffic) {
+        self.count_traffic(traffic);
+        match state {
+            TunnelState::Connected { location, .. } => {
+                if self.connected_since.is_none() {
+                    let hostname = location
+                        .as_ref()
+                        .and_then(|location| location.hostname.as_deref());
+                    self.record.add_session(hostname);
+                    self.connected_since = Some(Instant::now());
+                }
+            }
+            _ => self.end_session(),
+        }
+        self.save().await;
+    }
+
+    /// Return where the statistics are saved, unless they have been removed.
+    #[cfg(not(target_os = "android"))]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Return the statistics, including the time spent in the current session.
+    pub fn stats(&self, traffic: TunnelTraffic) -> UsageStats {
+        let mut record = self.record.clone();
+        record.add_traffic(traffic.since(self.counted_traffic));
+        if let Some(since) = self.connected_since {
+            record.connected_time += since.elapsed();
+        }
+        UsageStats {
+            connected_time: record.connected_time,
+            tx_bytes: record.tx_bytes,
+            rx_bytes: record.rx_bytes,
+            sessions: record.sessions,
+            favorite_relay: record.favorite_relay().map(str::to_owned),
+        }
+    }
+
+    /// Forget all statistics. A connected session is counted from now on.
+    pub async fn reset(&mut self, traffic: TunnelTraffic) {
+        self.record = UsageRecord::default();
+        self.counted_traffic = traffic;
+        if self.connected_since.is_some() {
+            self.connected_since = Some(Instant::now());
+        }
+        self.save().await;
+    }
+
+    /// Save the statistics, including the traffic and time of the current session so far.
+    pub async fn flush(&mut self, traffic: TunnelTraffic) {
+        self.count_traffic(traffic);
+        if let Some(since) = self.connected_since.as_mut() {
+            let now = Instant::now();
+            self.record.connected_time += now - *since;
+            *since = now;
+        }
+        self.save().await;
+    }
+
+    /// Delete the statistics from disk, and stop saving them.
+    pub async fn remove(&mut self) -> io::Result<()> {
+        self.record = UsageRecord::default();
+        let Some(path) = self.path.take() else {
+            return Ok(());
+        };
+        match fs::remove_file(path).await {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    /// Save the statistics before the daemon stops.
+    pub async fn finalize(mut self, traffic: TunnelTraffic) {
+        self.count_traffic(traffic);
+        self.end_session();
+        self.save().await;
+    }
+
+    fn count_traffic(&mut self, traffic: TunnelTraffic) {
+        self.record.add_traffic(traffic.since(self.counted_traffic));
+        self.counted_traffic = traffic;
+    }
+
+    fn end_session(&mut self) {
+        if let Some(since) = self.connected_since.take() {
+            self.record.connected_time += since.elapsed();
+        }
+    }
+
+    async fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let data = match serde_json::to_string(&self.record) {
+            Ok(data) => data,
+            Err(error) => {
+                log::error!(
+                    "{}",
+                    error.display_chain_with_msg("Failed to serialize usage stats")
+                );
+                return;
+            }
+        };
+        if let Err(error) = fs::write(path, data).await {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to write usage stats")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_favorite_relay() {
+        let mut record = UsageRecord::default();
+        assert_eq!(record.favorite_relay(), None);
+
+        record.add_session(Some("se-got-wg-001"));
+        record.add_session(Some("de-fra-wg-002"));
+        record.add_session(None);
+        assert_eq!(record.sessions, 3);
+        assert_eq!(record.favorite_relay(), Some("de-fra-wg-002"));
+
+        record.add_session(Some("se-got-wg-001"));
+        assert_eq!(record.favorite_relay(), Some("se-got-wg-001"));
+    }
+
+    #[test]
+    fn test_count_traffic() {
+        let mut tracker = UsageStatsTracker {
+            path: None,
+            record: UsageRecord::default(),
+            connected_since: None,
+            counted_traffic: TunnelTraffic::default(),
+        };
+        let traffic = |tx_bytes, rx_bytes| TunnelTraffic { tx_bytes, rx_bytes };
+
+        tracker.count_traffic(traffic(10, 20));
+        tracker.count_traffic(traffic(15, 20));
+        assert_eq!(tracker.record.tx_bytes, 15);
+        assert_eq!(tracker.record.rx_bytes, 20);
+
+        let stats = tracker.stats(traffic(20, 30));
+        assert_eq!((stats.tx_bytes, stats.rx_bytes), (20, 30));
+        assert_eq!(
+            tracker.record.tx_bytes, 15,
+            "stats() must not count traffic"
+        );
+    }
+
+    /// The current session is included in queries before it has been flushed
+    #[tokio::test(start_paused = true)]
+    async fn test_stats_include_current_session() {
+        let mut tracker = UsageStatsTracker {
+            path: None,
+            record: UsageRecord::default(),
+            connected_since: Some(Instant::now()),
+            counted_traffic: TunnelTraffic::default(),
+        };
+        tracker.record.add_session(Some("se-got-wg-001"));
+        tokio::time::advance(Duration::from_secs(60)).await;
+
+        let stats = tracker.stats(TunnelTraffic {
+            tx_bytes: 10,
+            rx_bytes: 20,
+        });
+        assert_eq!(stats.connected_time, Duration::from_secs(60));
+        assert_eq!((stats.tx_bytes, stats.rx_bytes), (10, 20));
+        assert_eq!(stats.sessions, 1);
+        assert_eq!(stats.favorite_relay.as_deref(), Some("se-got-wg-001"));
+        assert_eq!(tracker.record.connected_time, Duration::ZERO);
+    }
+
+    /// Flushed statistics survive a restart without counting the session twice, and nothing is
+    /// saved once the statistics have been removed
+    #[tokio::test(start_paused = true)]
+    async fn test_flush_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let traffic = |tx_bytes, rx_bytes| TunnelTraffic { tx_bytes, rx_bytes };
+
+        let mut tracker = UsageStatsTracker::new(dir).await;
+        tracker.connected_since = Some(Instant::now());
+        tokio::time::advance(Duration::from_secs(60)).await;
+        tracker.flush(traffic(10, 20)).await;
+
+        let restored = UsageStatsTracker::new(dir).await;
+        assert_eq!(restored.record.connected_time, Duration::from_secs(60));
+        assert_eq!(
+            (restored.record.tx_bytes, restored.record.rx_bytes),
+            (10, 20)
+        );
+
+        tokio::time::advance(Duration::from_secs(30)).await;
+        tracker.flush(traffic(15, 20)).await;
+        let restored = UsageStatsTracker::new(dir).await;
+        assert_eq!(restored.record.connected_time, Duration::from_secs(90));
+        assert_eq!(restored.record.tx_bytes, 15);
+
+        tracker.remove().await.unwrap();
+        assert!(!dir.join(USAGE_STATS_FILE).exists());
+        tracker.flush(traffic(20, 20)).await;
+        tracker.finalize(traffic(20, 20)).await;
+        assert!(!dir.join(USAGE_STATS_FILE).exists());
+    }
+}
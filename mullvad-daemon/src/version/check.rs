@@ -338,13 +338,13 @@ fn do_version_check(
 
 /// Query the API for the latest [AppVersionInfo] once, without retrying.
 ///
-/// This function waits until background calls are enabled in
+/// This function waits until non-essential background calls are enabled in
 /// [ApiAvailability](mullvad_api::availability::ApiAvailability).
 fn do_version_check_in_background(
     api: ApiContext,
     cache: Option<VersionCache>,
 ) -> BoxFuture<'static, Result<VersionCache, Error>> {
-    let when_available = api.api_handle.wait_background();
+    let when_available = api.api_handle.wait_non_essential();
     let version_cache = version_check_inner(api, cache);
     Box::pin(async move {
         when_available.await.map_err(Error::ApiCheck)?;
@@ -8,6 +8,8 @@ use mullvad_update::version::Metadata;
 use rand::seq::IndexedRandom;
 use std::io;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::{Duration, Instant};
 use talpid_types::ErrorExt;
 use tokio::fs;
@@ -42,6 +44,21 @@ pub struct DownloaderHandle {
     task: tokio::task::JoinHandle<std::result::Result<PathBuf, Error>>,
     /// Handle to send `AppUpgradeEvent::Aborted` when the downloader is dropped
     dropped_tx: Option<broadcast::Sender<AppUpgradeEvent>>,
+    /// URL that the installer is downloaded from, if one could be selected
+    url: Option<String>,
+    /// Percentage of the installer that has been downloaded
+    progress: Arc<AtomicU32>,
+}
+
+impl DownloaderHandle {
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    /// Return the percentage of the installer that has been downloaded
+    pub fn progress(&self) -> u32 {
+        self.progress.load(Ordering::Relaxed)
+    }
 }
 
 impl Drop for DownloaderHandle {
@@ -76,24 +93,33 @@ where
     D: AppDownloader + Send + 'static,
     D: From<AppDownloaderParameters<ProgressUpdater>>,
 {
+    let url = select_cdn_url(&metadata.urls).map(str::to_owned);
+    let progress = Arc::new(AtomicU32::new(0));
     DownloaderHandle {
-        task: tokio::spawn(start::<D>(metadata, event_tx.clone())),
+        task: tokio::spawn(start::<D>(
+            metadata,
+            url.clone(),
+            progress.clone(),
+            event_tx.clone(),
+        )),
         dropped_tx: Some(event_tx),
+        url,
+        progress,
     }
 }
 
-/// Begin or resume download of `metadata`
+/// Begin or resume download of `metadata` from `url`
 async fn start<D>(
     metadata: Metadata,
+    url: Option<String>,
+    progress: Arc<AtomicU32>,
     event_tx: broadcast::Sender<AppUpgradeEvent>,
 ) -> Result<PathBuf>
 where
     D: AppDownloader + Send + 'static,
     D: From<AppDownloaderParameters<ProgressUpdater>>,
 {
-    let url = select_cdn_url(&metadata.urls)
-        .ok_or(Error::NoUrlFound)?
-        .to_owned();
+    let url = url.ok_or(Error::NoUrlFound)?;
 
     log::info!("Downloading app version '{}' from {url}", metadata.version);
 
@@ -111,9 +137,10 @@ where
         app_version: metadata.version,
         app_url: url.clone(),
         app_size: metadata.size,
-        app_progress: ProgressUpdater::new(server_from_url(&url), event_tx.clone()),
+        app_progress: ProgressUpdater::new(server_from_url(&url), event_tx.clone(), progress),
         app_sha256: metadata.sha256,
         cache_dir: download_dir,
+        rate_limit: None,
     };
     let downloader = D::from(params);
 
@@ -122,7 +149,7 @@ where
         let _ = event_tx.send(AppUpgradeEvent::Error(AppUpgradeError::DownloadFailed));
     })?;
     let _ = event_tx.send(AppUpgradeEvent::VerifyingInstaller);
-    installer.verify().await.inspect_err(|_| {
+    installer.verify(()).await.inspect_err(|_| {
         let _ = event_tx.send(AppUpgradeEvent::Error(AppUpgradeError::VerificationFailed));
     })?;
     let _ = event_tx.send(AppUpgradeEvent::VerifiedInstaller);
@@ -134,7 +161,7 @@ where
 }
 
 async fn create_download_dir() -> Result<PathBuf> {
-    let download_dir = mullvad_paths::cache_dir()?.join("mullvad-update");
+    let download_dir = mullvad_paths::cache_dir()?.join(super::DOWNLOAD_DIRNAME);
     log::trace!("Download directory: {download_dir:?}");
     fs::create_dir_all(&download_dir)
         .await
@@ -144,7 +171,12 @@ async fn create_download_dir() -> Result<PathBuf> {
 
 /// Remove the download directory
 pub async fn clear_download_dir() -> Result<PathBuf> {
-    let download_dir = mullvad_paths::get_cache_dir()?.join("mullvad-update");
+    let download_dir = mullvad_paths::get_cache_dir()?.join(super::DOWNLOAD_DIRNAME);
+    remove_download_dir(download_dir).await
+}
+
+/// Remove `download_dir` along with any installers in it
+pub async fn remove_download_dir(download_dir: PathBuf) -> Result<PathBuf> {
     log::info!("Cleaning up download directory: {}", download_dir.display());
     match fs::remove_dir_all(&download_dir).await {
         Ok(()) => Ok(download_dir),
@@ -159,16 +191,23 @@ pub struct ProgressUpdater {
     complete_frac: f32,
     start_time: Instant,
     complete_frac_at_start: Option<f32>,
+    /// Shared with the [DownloaderHandle], so that progress can be queried
+    progress: Arc<AtomicU32>,
 }
 
 impl ProgressUpdater {
-    fn new(server: String, event_tx: broadcast::Sender<AppUpgradeEvent>) -> Self {
+    fn new(
+        server: String,
+        event_tx: broadcast::Sender<AppUpgradeEvent>,
+        progress: Arc<AtomicU32>,
+    ) -> Self {
         Self {
             server,
             event_tx,
             complete_frac: 0.,
             start_time: Instant::now(),
             complete_frac_at_start: None,
+            progress,
         }
     }
 }
@@ -185,11 +224,13 @@ impl mullvad_update::fetch::ProgressUpdater for ProgressUpdater {
         let complete_frac_at_start = self.complete_frac_at_start.get_or_insert(fraction_complete);
 
         self.complete_frac = fraction_complete;
+        let progress = (fraction_complete * 100.0) as u32;
+        self.progress.store(progress, Ordering::Relaxed);
 
         let _ = self.event_tx.send(AppUpgradeEvent::DownloadProgress(
             AppUpgradeDownloadProgress {
                 server: self.server.clone(),
-                progress: (fraction_complete * 100.0) as u32,
+                progress,
                 time_left: estimate_time_left(
                     self.start_time,
                     fraction_complete,
@@ -201,6 +242,7 @@ impl mullvad_update::fetch::ProgressUpdater for ProgressUpdater {
 
     fn clear_progress(&mut self) {
         self.complete_frac = 0.;
+        self.progress.store(0, Ordering::Relaxed);
 
         let _ = self.event_tx.send(AppUpgradeEvent::DownloadProgress(
             AppUpgradeDownloadProgress {
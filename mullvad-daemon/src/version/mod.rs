@@ -38,8 +38,27 @@ pub enum Error {
 
     #[error("Version cache update was aborted")]
     UpdateAborted,
+
+    #[error("Cannot remove installers while an upgrade is in progress")]
+    UpgradeInProgress,
+
+    #[error("Updates are not prefetched in low power mode")]
+    LowPowerMode,
+
+    #[error("Updates are not prefetched on metered networks")]
+    MeteredNetwork,
+
+    #[error("No download with the given ID is in progress")]
+    NoSuchDownload,
+
+    #[cfg(in_app_upgrade)]
+    #[error("Failed to remove downloaded installers")]
+    ClearDownloads(#[source] downloader::Error),
 }
 
+/// Directory within the cache directory that installers are downloaded to
+pub const DOWNLOAD_DIRNAME: &str = "mullvad-update";
+
 /// Contains the date of the git commit this was built from
 pub const COMMIT_DATE: &str = include_str!(concat!(env!("OUT_DIR"), "/git-commit-date.txt"));
 
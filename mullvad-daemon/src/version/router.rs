@@ -4,6 +4,8 @@ use std::path::PathBuf;
 use futures::channel::{mpsc, oneshot};
 use futures::stream::StreamExt;
 use mullvad_api::{availability::ApiAvailability, rest::MullvadRestHandle};
+#[cfg(in_app_upgrade)]
+use mullvad_types::version::ActiveDownload;
 use mullvad_types::version::AppVersionInfo;
 #[cfg(not(target_os = "android"))]
 use mullvad_types::version::SuggestedUpgrade;
@@ -53,6 +55,16 @@ impl VersionRouterHandle {
         result_rx.await.map_err(|_| Error::VersionRouterClosed)?
     }
 
+    /// Return the size of the installer for the suggested upgrade, if there is one.
+    /// This uses the last fetched version info and does not trigger a version check.
+    pub async fn get_upgrade_download_size(&self) -> Result<Option<u64>> {
+        let (result_tx, result_rx) = oneshot::channel();
+        self.tx
+            .send(Message::GetUpgradeDownloadSize { result_tx })
+            .map_err(|_| Error::VersionRouterClosed)?;
+        result_rx.await.map_err(|_| Error::VersionRouterClosed)
+    }
+
     #[cfg(in_app_upgrade)]
     pub async fn update_application(&self) -> Result<()> {
         let (result_tx, result_rx) = oneshot::channel();
@@ -62,6 +74,27 @@ impl VersionRouterHandle {
         result_rx.await.map_err(|_| Error::VersionRouterClosed)
     }
 
+    /// Download and verify the suggested upgrade in the background, without notifying frontends
+    /// that it can be installed. A later call to [Self::update_application] uses the prefetched
+    /// installer instead of downloading it again. Fails with [Error::LowPowerMode] in low power
+    /// mode and with [Error::MeteredNetwork] on metered networks.
+    #[cfg(in_app_upgrade)]
+    pub async fn prefetch_update(&self) -> Result<()> {
+        let (result_tx, result_rx) = oneshot::channel();
+        self.tx
+            .send(Message::PrefetchUpdate { result_tx })
+            .map_err(|_| Error::VersionRouterClosed)?;
+        result_rx.await.map_err(|_| Error::VersionRouterClosed)?
+    }
+
+    /// Set whether the current network is metered, in which case updates are not prefetched.
+    #[cfg(in_app_upgrade)]
+    pub fn set_metered_network(&self, metered: bool) -> Result<()> {
+        self.tx
+            .send(Message::SetMeteredNetwork(metered))
+            .map_err(|_| Error::VersionRouterClosed)
+    }
+
     #[cfg(in_app_upgrade)]
     pub async fn cancel_update(&self) -> Result<()> {
         let (result_tx, result_rx) = oneshot::channel();
@@ -79,6 +112,38 @@ impl VersionRouterHandle {
             .map_err(|_| Error::VersionRouterClosed)?;
         result_rx.await.map_err(|_| Error::VersionRouterClosed)
     }
+
+    /// Remove downloaded installers. Fails with [Error::UpgradeInProgress] while an installer is
+    /// being downloaded or waiting to be installed.
+    #[cfg(in_app_upgrade)]
+    pub async fn clear_downloads(&self) -> Result<()> {
+        let (result_tx, result_rx) = oneshot::channel();
+        self.tx
+            .send(Message::ClearDownloads { result_tx })
+            .map_err(|_| Error::VersionRouterClosed)?;
+        result_rx.await.map_err(|_| Error::VersionRouterClosed)?
+    }
+
+    /// Return the installer downloads that are in progress
+    #[cfg(in_app_upgrade)]
+    pub async fn list_active_downloads(&self) -> Result<Vec<ActiveDownload>> {
+        let (result_tx, result_rx) = oneshot::channel();
+        self.tx
+            .send(Message::ListActiveDownloads { result_tx })
+            .map_err(|_| Error::VersionRouterClosed)?;
+        result_rx.await.map_err(|_| Error::VersionRouterClosed)
+    }
+
+    /// Abort the download identified by `id`. Fails with [Error::NoSuchDownload] if it is not in
+    /// progress.
+    #[cfg(in_app_upgrade)]
+    pub async fn cancel_download(&self, id: u32) -> Result<()> {
+        let (result_tx, result_rx) = oneshot::channel();
+        self.tx
+            .send(Message::CancelDownload { id, result_tx })
+            .map_err(|_| Error::VersionRouterClosed)?;
+        result_rx.await.map_err(|_| Error::VersionRouterClosed)?
+    }
 }
 
 // These wrapper traits and type aliases exist to help feature gate the module
@@ -120,6 +185,15 @@ struct VersionRouter<S = DaemonEventSender<AppVersionInfo>, D = DefaultDownloade
     app_upgrade_broadcast: AppUpgradeBroadcast,
     #[cfg(in_app_upgrade)]
     cache_dir: PathBuf,
+    /// Used to avoid prefetching updates in low power mode
+    #[cfg(in_app_upgrade)]
+    api_availability: ApiAvailability,
+    /// Used to avoid prefetching updates on metered networks
+    #[cfg(in_app_upgrade)]
+    metered_network: bool,
+    /// ID to assign to the next download
+    #[cfg(in_app_upgrade)]
+    next_download_id: u32,
     /// Type used to spawn the downloader task, replaced when testing
     _phantom: std::marker::PhantomData<D>,
 }
@@ -132,15 +206,43 @@ enum Message {
     },
     /// Check for updates
     GetLatestVersion(oneshot::Sender<Result<AppVersionInfo>>),
+    /// Get the installer size of the suggested upgrade from the cached version info
+    GetUpgradeDownloadSize {
+        result_tx: oneshot::Sender<Option<u64>>,
+    },
     /// Update the application
     #[cfg(in_app_upgrade)]
     UpdateApplication { result_tx: oneshot::Sender<()> },
+    /// Download the update without installing it
+    #[cfg(in_app_upgrade)]
+    PrefetchUpdate {
+        result_tx: oneshot::Sender<Result<()>>,
+    },
+    /// Set whether the current network is metered
+    #[cfg(in_app_upgrade)]
+    SetMeteredNetwork(bool),
     /// Cancel the ongoing update
     #[cfg(in_app_upgrade)]
     CancelUpdate { result_tx: oneshot::Sender<()> },
     /// Get the cache dir
     #[cfg(in_app_upgrade)]
     GetCacheDir { result_tx: oneshot::Sender<PathBuf> },
+    /// Remove downloaded installers, unless an upgrade is in progress
+    #[cfg(in_app_upgrade)]
+    ClearDownloads {
+        result_tx: oneshot::Sender<Result<()>>,
+    },
+    /// List the downloads that are in progress
+    #[cfg(in_app_upgrade)]
+    ListActiveDownloads {
+        result_tx: oneshot::Sender<Vec<ActiveDownload>>,
+    },
+    /// Abort a download that is in progress
+    #[cfg(in_app_upgrade)]
+    CancelDownload {
+        id: u32,
+        result_tx: oneshot::Sender<Result<()>>,
+    },
 }
 
 #[derive(Debug)]
@@ -158,6 +260,11 @@ enum State {
         upgrading_to_version: mullvad_update::version::Metadata,
         /// Tokio task for the downloader handle
         downloader_handle: downloader::DownloaderHandle,
+        /// Identifies the download to users
+        download_id: u32,
+        /// Whether the download was started by a prefetch, in which case it should not be
+        /// announced as ready to install when complete
+        prefetch: bool,
     },
     /// Download is complete. We have a verified binary
     #[cfg(in_app_upgrade)]
@@ -167,6 +274,17 @@ enum State {
         /// Path to verified installer
         verified_installer_path: PathBuf,
     },
+    /// A prefetched download is complete. We have a verified binary, but frontends have not been
+    /// told about it yet
+    #[cfg(in_app_upgrade)]
+    Prefetched {
+        /// Version info received from `HasVersion`
+        version_cache: VersionCache,
+        /// The version that was downloaded
+        upgrading_to_version: mullvad_update::version::Metadata,
+        /// Path to verified installer
+        verified_installer_path: PathBuf,
+    },
 }
 
 struct AppVersionInfoEvent {
@@ -189,6 +307,11 @@ impl std::fmt::Display for State {
                 verified_installer_path,
                 ..
             } => write!(f, "Downloaded '{}'", verified_installer_path.display()),
+            #[cfg(in_app_upgrade)]
+            State::Prefetched {
+                verified_installer_path,
+                ..
+            } => write!(f, "Prefetched '{}'", verified_installer_path.display()),
         }
     }
 }
@@ -199,9 +322,9 @@ impl State {
             State::NoVersion => None,
             State::HasVersion { version_cache, .. } => Some(version_cache),
             #[cfg(in_app_upgrade)]
-            State::Downloading { version_cache, .. } | State::Downloaded { version_cache, .. } => {
-                Some(version_cache)
-            }
+            State::Downloading { version_cache, .. }
+            | State::Downloaded { version_cache, .. }
+            | State::Prefetched { version_cache, .. } => Some(version_cache),
         }
     }
 }
@@ -213,12 +336,15 @@ pub(crate) fn spawn_version_router(
     cache_dir: PathBuf,
     version_event_sender: DaemonEventSender<AppVersionInfo>,
     beta_program: bool,
+    metered_network: bool,
     #[cfg(not(target_os = "android"))] rollout: Rollout,
     app_upgrade_broadcast: AppUpgradeBroadcast,
 ) -> VersionRouterHandle {
     let (tx, rx) = mpsc::unbounded();
 
     tokio::spawn(async move {
+        #[cfg(in_app_upgrade)]
+        let api_availability = availability_handle.clone();
         let (new_version_tx, new_version_rx) = mpsc::unbounded();
         let (refresh_version_check_tx, refresh_version_check_rx) = mpsc::unbounded();
 
@@ -252,6 +378,12 @@ pub(crate) fn spawn_version_router(
             app_upgrade_broadcast,
             #[cfg(in_app_upgrade)]
             cache_dir,
+            #[cfg(in_app_upgrade)]
+            api_availability,
+            #[cfg(in_app_upgrade)]
+            metered_network,
+            #[cfg(in_app_upgrade)]
+            next_download_id: 0,
             refresh_version_check_tx,
             _phantom: std::marker::PhantomData::<DefaultDownloader>,
         }
@@ -292,14 +424,15 @@ where
                     let _ = self.version_event_sender.send(app_update_info);
                 }
             },
-            Some(message) = self.daemon_rx.next() => self.handle_message(message),
+            Some(message) = self.daemon_rx.next() => self.handle_message(message).await,
             else => return ControlFlow::Break(()),
         }
         ControlFlow::Continue(())
     }
 
     /// Handle [Message] sent by user
-    fn handle_message(&mut self, message: Message) {
+    #[cfg_attr(not(in_app_upgrade), expect(clippy::unused_async))]
+    async fn handle_message(&mut self, message: Message) {
         match message {
             Message::SetBetaProgram { state, result_tx } => {
                 self.set_beta_program(state);
@@ -310,12 +443,23 @@ where
             Message::GetLatestVersion(result_tx) => {
                 self.get_latest_version(result_tx);
             }
+            Message::GetUpgradeDownloadSize { result_tx } => {
+                let _ = result_tx.send(self.upgrade_download_size());
+            }
             #[cfg(in_app_upgrade)]
             Message::UpdateApplication { result_tx } => {
                 self.update_application();
                 let _ = result_tx.send(());
             }
             #[cfg(in_app_upgrade)]
+            Message::PrefetchUpdate { result_tx } => {
+                let _ = result_tx.send(self.prefetch_update());
+            }
+            #[cfg(in_app_upgrade)]
+            Message::SetMeteredNetwork(metered) => {
+                self.metered_network = metered;
+            }
+            #[cfg(in_app_upgrade)]
             Message::CancelUpdate { result_tx } => {
                 self.cancel_upgrade();
                 let _ = result_tx.send(());
@@ -324,6 +468,44 @@ where
             Message::GetCacheDir { result_tx } => {
                 let _ = result_tx.send(self.cache_dir.clone());
             }
+            #[cfg(in_app_upgrade)]
+            Message::ClearDownloads { result_tx } => {
+                // This is handled by the router so that no download can start while removing
+                // A prefetched installer is removed along with the others
+                if let State::Prefetched { .. } = self.state {
+                    self.cancel_upgrade();
+                }
+                let result = match self.state {
+                    State::Downloading { .. } | State::Downloaded { .. } => {
+                        Err(Error::UpgradeInProgress)
+                    }
+                    State::NoVersion | State::HasVersion { .. } | State::Prefetched { .. } => {
+                        downloader::remove_download_dir(
+                            self.cache_dir.join(super::DOWNLOAD_DIRNAME),
+                        )
+                        .await
+                        .map(|_| ())
+                        .map_err(Error::ClearDownloads)
+                    }
+                };
+                let _ = result_tx.send(result);
+            }
+            #[cfg(in_app_upgrade)]
+            Message::ListActiveDownloads { result_tx } => {
+                let _ = result_tx.send(self.active_downloads());
+            }
+            #[cfg(in_app_upgrade)]
+            Message::CancelDownload { id, result_tx } => {
+                let result = match self.state {
+                    State::Downloading { download_id, .. } if download_id == id => {
+                        log::info!("Cancelling download {id}");
+                        self.cancel_upgrade();
+                        Ok(())
+                    }
+                    _ => Err(Error::NoSuchDownload),
+                };
+                let _ = result_tx.send(result);
+            }
         }
     }
 
@@ -414,6 +596,30 @@ where
 
                 log::warn!("Received new version in downloaded state. Aborting download");
 
+                event
+            }
+            #[cfg(in_app_upgrade)]
+            State::Prefetched {
+                version_cache: prev_cache,
+                ..
+            } => {
+                let prev_app_version_info =
+                    to_app_version_info(prev_cache, self.beta_program, None);
+                let app_version_info = to_app_version_info(&version_cache, self.beta_program, None);
+
+                let event = AppVersionInfoEvent {
+                    is_new: prev_app_version_info != app_version_info,
+                    app_version_info,
+                };
+
+                if !event.is_new {
+                    log::trace!("Ignoring same version in prefetched state");
+                    *prev_cache = version_cache.clone();
+                    return event;
+                }
+
+                log::debug!("Received new version in prefetched state. Discarding download");
+
                 event
             }
         };
@@ -446,7 +652,9 @@ where
         // Always cancel download if the suggested upgrade changes
         let version_cache = match mem::replace(&mut self.state, State::NoVersion) {
             #[cfg(in_app_upgrade)]
-            State::Downloaded { version_cache, .. } | State::Downloading { version_cache, .. } => {
+            State::Downloaded { version_cache, .. }
+            | State::Downloading { version_cache, .. }
+            | State::Prefetched { version_cache, .. } => {
                 log::warn!(
                     "Switching beta after updating resulted in new suggested upgrade: {:?}, aborting",
                     new_app_version.suggested_upgrade
@@ -483,6 +691,19 @@ where
         }
     }
 
+    /// Installer size of the suggested upgrade, based on the cached version info
+    #[cfg(not(target_os = "android"))]
+    fn upgrade_download_size(&self) -> Option<u64> {
+        let version_cache = self.state.get_version_cache()?;
+        recommended_version_upgrade(&version_cache.version_info, self.beta_program)
+            .map(|version| version.size as u64)
+    }
+
+    #[cfg(target_os = "android")]
+    fn upgrade_download_size(&self) -> Option<u64> {
+        None
+    }
+
     #[cfg(in_app_upgrade)]
     fn update_application(&mut self) {
         use crate::version::downloader::spawn_downloader;
@@ -511,8 +732,54 @@ where
                     version_cache,
                     upgrading_to_version,
                     downloader_handle,
+                    download_id: self.new_download_id(),
+                    prefetch: false,
                 };
             }
+            // Announce the downloaded installer once the prefetch completes
+            State::Downloading {
+                version_cache,
+                upgrading_to_version,
+                downloader_handle,
+                download_id,
+                prefetch: true,
+            } => {
+                log::info!(
+                    "Continuing prefetch of version {} as an upgrade",
+                    upgrading_to_version.version
+                );
+                self.state = State::Downloading {
+                    version_cache,
+                    upgrading_to_version,
+                    downloader_handle,
+                    download_id,
+                    prefetch: false,
+                };
+            }
+            State::Prefetched {
+                version_cache,
+                upgrading_to_version,
+                verified_installer_path,
+            } => {
+                log::info!(
+                    "Using prefetched installer for version {}",
+                    upgrading_to_version.version
+                );
+                let _ = self
+                    .app_upgrade_broadcast
+                    .send(mullvad_types::version::AppUpgradeEvent::VerifiedInstaller);
+                let app_version_info = downloaded_version_info(
+                    &version_cache,
+                    &upgrading_to_version,
+                    verified_installer_path.clone(),
+                );
+                self.state = State::Downloaded {
+                    version_cache,
+                    verified_installer_path,
+                };
+                self.notify_version_requesters(app_version_info.clone());
+                let _ = self.version_event_sender.send(app_version_info);
+            }
             state => {
                 log::debug!("Ignoring update request while in state {:?}", state);
                 self.state = state;
@@ -520,6 +787,76 @@ where
         }
     }
 
+    #[cfg(in_app_upgrade)]
+    fn prefetch_update(&mut self) -> Result<()> {
+        use crate::version::downloader::spawn_downloader;
+
+        if self.api_availability.is_low_power() {
+            return Err(Error::LowPowerMode);
+        }
+        if self.metered_network {
+            return Err(Error::MeteredNetwork);
+        }
+
+        match mem::replace(&mut self.state, State::NoVersion) {
+            State::HasVersion { version_cache } => {
+                let Some(upgrading_to_version) =
+                    recommended_version_upgrade(&version_cache.version_info, self.beta_program)
+                else {
+                    log::debug!("Received prefetch request without suggested upgrade");
+                    self.state = State::HasVersion { version_cache };
+                    return Ok(());
+                };
+                log::info!("Prefetching version {}", upgrading_to_version.version);
+
+                let downloader_handle = spawn_downloader::<D>(
+                    upgrading_to_version.clone(),
+                    self.app_upgrade_broadcast.clone(),
+                );
+
+                self.state = State::Downloading {
+                    version_cache,
+                    upgrading_to_version,
+                    downloader_handle,
+                    download_id: self.new_download_id(),
+                    prefetch: true,
+                };
+            }
+            state => {
+                log::debug!("Ignoring prefetch request while in state {:?}", state);
+                self.state = state;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(in_app_upgrade)]
+    fn new_download_id(&mut self) -> u32 {
+        let id = self.next_download_id;
+        self.next_download_id = self.next_download_id.wrapping_add(1);
+        id
+    }
+
+    #[cfg(in_app_upgrade)]
+    fn active_downloads(&self) -> Vec<ActiveDownload> {
+        match &self.state {
+            State::Downloading {
+                upgrading_to_version,
+                downloader_handle,
+                download_id,
+                prefetch,
+                ..
+            } => vec![ActiveDownload {
+                id: *download_id,
+                version: upgrading_to_version.version.clone(),
+                url: downloader_handle.url().unwrap_or_default().to_owned(),
+                progress: downloader_handle.progress(),
+                prefetch: *prefetch,
+            }],
+            _ => vec![],
+        }
+    }
+
     #[cfg(in_app_upgrade)]
     fn cancel_upgrade(&mut self) {
         use mullvad_types::version::AppUpgradeEvent;
@@ -527,7 +864,7 @@ where
         match mem::replace(&mut self.state, State::NoVersion) {
             // If we're upgrading, emit an event if a version was received during the upgrade
             // Otherwise, just reset upgrade info to last known state
-            State::Downloading { version_cache, .. } => {
+            State::Downloading { version_cache, .. } | State::Prefetched { version_cache, .. } => {
                 self.state = State::HasVersion { version_cache };
             }
             State::Downloaded { version_cache, .. } => {
@@ -563,19 +900,28 @@ async fn wait_for_update(state: &mut State) -> Option<AppVersionInfo> {
             version_cache,
             downloader_handle,
             upgrading_to_version,
+            prefetch,
             ..
         } => match downloader_handle.await {
-            Ok(verified_installer_path) => {
-                let app_update_info = AppVersionInfo {
-                    current_version_supported: version_cache.current_version_supported,
-                    suggested_upgrade: Some({
-                        SuggestedUpgrade {
-                            version: upgrading_to_version.version.clone(),
-                            changelog: upgrading_to_version.changelog.clone(),
-                            verified_installer_path: Some(verified_installer_path.clone()),
-                        }
-                    }),
+            Ok(verified_installer_path) if *prefetch => {
+                log::info!(
+                    "Prefetched version {} to {}",
+                    upgrading_to_version.version,
+                    verified_installer_path.display()
+                );
+                *state = State::Prefetched {
+                    version_cache: version_cache.clone(),
+                    upgrading_to_version: upgrading_to_version.clone(),
+                    verified_installer_path,
                 };
+                None
+            }
+            Ok(verified_installer_path) => {
+                let app_update_info = downloaded_version_info(
+                    version_cache,
+                    upgrading_to_version,
+                    verified_installer_path.clone(),
+                );
                 *state = State::Downloaded {
                     version_cache: version_cache.clone(),
                     verified_installer_path,
@@ -602,6 +948,24 @@ async fn wait_for_update(state: &mut State) -> Option<AppVersionInfo> {
     }
 }
 
+/// [`AppVersionInfo`] announcing that the installer for `upgrading_to_version` has been
+/// downloaded and verified
+#[cfg(in_app_upgrade)]
+fn downloaded_version_info(
+    version_cache: &VersionCache,
+    upgrading_to_version: &mullvad_update::version::Metadata,
+    verified_installer_path: PathBuf,
+) -> AppVersionInfo {
+    AppVersionInfo {
+        current_version_supported: version_cache.current_version_supported,
+        suggested_upgrade: Some(SuggestedUpgrade {
+            version: upgrading_to_version.version.clone(),
+            changelog: upgrading_to_version.changelog.clone(),
+            verified_installer_path: Some(verified_installer_path),
+        }),
+    }
+}
+
 /// Extract [`AppVersionInfo`], containing upgrade version and `current_version_supported`
 /// from [VersionCache] and beta program state.
 #[cfg_attr(target_os = "android", expect(unused_variables))]
@@ -655,6 +1019,7 @@ mod test {
     use mullvad_update::{
         app::{DownloadError, DownloadedInstaller, VerifiedInstaller},
         fetch::ProgressUpdater as _,
+        verify::VerifyProgress,
     };
     use tokio::sync::broadcast::error::TryRecvError;
 
@@ -683,7 +1048,10 @@ mod test {
             &self.0.app_version
         }
 
-        async fn verify(self) -> std::result::Result<impl VerifiedInstaller, DownloadError> {
+        async fn verify(
+            self,
+            _progress: impl VerifyProgress,
+        ) -> std::result::Result<impl VerifiedInstaller, DownloadError> {
             Ok(self)
         }
     }
@@ -718,7 +1086,10 @@ mod test {
             unreachable!()
         }
 
-        async fn verify(self) -> std::result::Result<impl VerifiedInstaller, DownloadError> {
+        async fn verify(
+            self,
+            _progress: impl VerifyProgress,
+        ) -> std::result::Result<impl VerifiedInstaller, DownloadError> {
             Ok(self)
         }
     }
@@ -758,7 +1129,10 @@ mod test {
             }
         }
 
-        async fn verify(self) -> std::result::Result<impl VerifiedInstaller, DownloadError> {
+        async fn verify(
+            self,
+            _progress: impl VerifyProgress,
+        ) -> std::result::Result<impl VerifiedInstaller, DownloadError> {
             Err::<Self, _>(DownloadError::Verification(anyhow::anyhow!(
                 "Verification failed"
             )))
@@ -808,6 +1182,9 @@ mod test {
                 app_upgrade_broadcast,
                 refresh_version_check_tx,
                 cache_dir: PathBuf::new(),
+                api_availability: ApiAvailability::default(),
+                metered_network: false,
+                next_download_id: 0,
                 _phantom: std::marker::PhantomData::<D>,
             },
             VersionRouterChannels {
@@ -1099,6 +1476,156 @@ mod test {
         );
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn test_prefetch() {
+        let (mut version_router, mut channels) = make_version_router::<SuccessfulAppDownloader>();
+        version_router.on_new_version(get_new_stable_version_cache());
+
+        version_router.api_availability.set_low_power(true);
+        assert!(matches!(
+            version_router.prefetch_update(),
+            Err(Error::LowPowerMode)
+        ));
+        assert!(matches!(version_router.state, State::HasVersion { .. }));
+        version_router.api_availability.set_low_power(false);
+
+        version_router.metered_network = true;
+        assert!(matches!(
+            version_router.prefetch_update(),
+            Err(Error::MeteredNetwork)
+        ));
+        assert!(matches!(version_router.state, State::HasVersion { .. }));
+        version_router.metered_network = false;
+
+        // Prefetching reports progress, but does not announce the installer
+        let mut app_upgrade_listener = version_router.app_upgrade_broadcast.subscribe();
+        version_router.prefetch_update().unwrap();
+        assert!(matches!(
+            version_router.state,
+            State::Downloading { prefetch: true, .. }
+        ));
+        assert_eq!(version_router.run_step().await, ControlFlow::Continue(()));
+        let verified_installer_path = match &version_router.state {
+            State::Prefetched {
+                verified_installer_path,
+                ..
+            } => verified_installer_path.clone(),
+            other => panic!("State should be Prefetched, was {other:?}"),
+        };
+        assert_eq!(
+            app_upgrade_listener.try_recv(),
+            Ok(AppUpgradeEvent::DownloadStarting)
+        );
+        while app_upgrade_listener.try_recv().is_ok() {}
+        channels
+            .version_event_receiver
+            .try_next()
+            .expect_err("No version event should be sent after prefetching");
+
+        // Upgrading uses the prefetched installer without downloading it again
+        version_router.update_application();
+        assert!(matches!(version_router.state, State::Downloaded { .. }));
+        assert_eq!(
+            app_upgrade_listener.try_recv(),
+            Ok(AppUpgradeEvent::VerifiedInstaller)
+        );
+        assert_eq!(app_upgrade_listener.try_recv(), Err(TryRecvError::Empty));
+        let version_info = channels
+            .version_event_receiver
+            .try_next()
+            .expect("Version event channel should contain message")
+            .expect("Version event should be sent");
+        assert_eq!(
+            version_info
+                .suggested_upgrade
+                .unwrap()
+                .verified_installer_path,
+            Some(verified_installer_path)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_list_and_cancel_downloads() {
+        let (mut version_router, _channels) = make_version_router::<SuccessfulAppDownloader>();
+        let version_cache = get_new_stable_version_cache();
+        version_router.on_new_version(version_cache.clone());
+        assert!(version_router.active_downloads().is_empty());
+
+        version_router.prefetch_update().unwrap();
+        let downloads = version_router.active_downloads();
+        assert_eq!(
+            downloads,
+            vec![ActiveDownload {
+                id: 0,
+                version: version_cache.version_info.stable.version,
+                url: "https://example.com".to_string(),
+                progress: 0,
+                prefetch: true,
+            }]
+        );
+
+        let (result_tx, result_rx) = oneshot::channel();
+        version_router
+            .handle_message(Message::CancelDownload { id: 1, result_tx })
+            .await;
+        assert!(matches!(result_rx.await, Ok(Err(Error::NoSuchDownload))));
+        assert!(matches!(version_router.state, State::Downloading { .. }));
+
+        let (result_tx, result_rx) = oneshot::channel();
+        version_router
+            .handle_message(Message::CancelDownload { id: 0, result_tx })
+            .await;
+        assert!(matches!(result_rx.await, Ok(Ok(()))));
+        assert!(matches!(version_router.state, State::HasVersion { .. }));
+        assert!(version_router.active_downloads().is_empty());
+
+        // New downloads are given new IDs
+        version_router.update_application();
+        assert_eq!(version_router.active_downloads()[0].id, 1);
+    }
+
+    /// An installer which is being downloaded, or which is waiting to be installed, must not be
+    /// removed when clearing downloads.
+    #[tokio::test(start_paused = true)]
+    async fn test_clear_downloads_in_use() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let download_dir = cache_dir.path().join(crate::version::DOWNLOAD_DIRNAME);
+        let installer = download_dir.join("installer");
+        std::fs::create_dir_all(&download_dir).unwrap();
+        std::fs::write(&installer, b"installer").unwrap();
+
+        let (mut version_router, _channels) = make_version_router::<SuccessfulAppDownloader>();
+        version_router.cache_dir = cache_dir.path().to_owned();
+        version_router.on_new_version(get_new_stable_version_cache());
+
+        version_router.update_application();
+        assert!(matches!(version_router.state, State::Downloading { .. }));
+        let (result_tx, result_rx) = oneshot::channel();
+        version_router
+            .handle_message(Message::ClearDownloads { result_tx })
+            .await;
+        assert!(matches!(result_rx.await, Ok(Err(Error::UpgradeInProgress))));
+        assert!(installer.exists());
+
+        assert_eq!(version_router.run_step().await, ControlFlow::Continue(()));
+        assert!(matches!(version_router.state, State::Downloaded { .. }));
+        let (result_tx, result_rx) = oneshot::channel();
+        version_router
+            .handle_message(Message::ClearDownloads { result_tx })
+            .await;
+        assert!(matches!(result_rx.await, Ok(Err(Error::UpgradeInProgress))));
+        assert!(installer.exists());
+
+        // Once the upgrade is cancelled, the installer is no longer in use
+        version_router.cancel_upgrade();
+        let (result_tx, result_rx) = oneshot::channel();
+        version_router
+            .handle_message(Message::ClearDownloads { result_tx })
+            .await;
+        assert!(matches!(result_rx.await, Ok(Ok(()))));
+        assert!(!download_dir.exists());
+    }
+
     /// Test that the update is aborted if a new version is received while downloading
     #[tokio::test(start_paused = true)]
     async fn test_abort_on_new_version() {
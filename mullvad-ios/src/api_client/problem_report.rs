@@ -61,6 +61,7 @@ pub unsafe extern "C" fn mullvad_ios_send_problem_report(
         let err = Error::ApiError(
             rest::StatusCode::BAD_REQUEST,
             "Failed to send problem report: invalid address, message, or log data.".to_string(),
+            None,
         );
         log::error!("{err:?}");
         completion.finish(SwiftMullvadApiResponse::rest_error(err));
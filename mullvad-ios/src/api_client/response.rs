@@ -93,7 +93,7 @@ impl SwiftMullvadApiResponse {
 
         let error_description = to_cstr_pointer(err.to_string());
         let (status_code, server_response_code): (u16, _) =
-            if let rest::Error::ApiError(status_code, error_code) = err {
+            if let rest::Error::ApiError(status_code, error_code, _) = err {
                 (status_code.into(), to_cstr_pointer(error_code))
             } else {
                 (0, null_mut())
@@ -23,7 +23,7 @@ pub fn api_endpoint_from_java(
 
     Some(mullvad_api::ApiEndpoint {
         host: Some(hostname),
-        address: Some(socket_addr),
+        address: vec![socket_addr],
         disable_tls: disable_tls_from_java(env, endpoint_override),
         force_direct: force_direct_from_java(env, endpoint_override),
     })
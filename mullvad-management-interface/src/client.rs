@@ -8,26 +8,63 @@ use mullvad_types::wireguard::DaitaSettings;
 use mullvad_types::{
     access_method::AccessMethodSetting,
     device::{DeviceEvent, RemoveDeviceEvent},
+    relay_constraints::RelaySelectionFallback,
     relay_list::RelayList,
-    settings::Settings,
+    settings::{AccountExpiryAction, Settings},
     states::TunnelState,
     version::AppVersionInfo,
 };
 #[cfg(not(target_os = "android"))]
 use mullvad_types::{
-    access_method::{self, AccessMethod},
-    account::{AccountData, AccountNumber, VoucherSubmission},
+    access_method::{self, AccessMethod, LastSuccessfulAccessMethod},
+    account::{AccountData, AccountNumber, LoginBackoff, VoucherSubmission},
+    api::{ApiAddressInfo, ApiErrorResponse, ApiRequestLimit, InflightApiRequest},
+    cache::{CacheKind, CacheUsage},
+    clock::ClockCheck,
+    connection_config::EffectiveConnectionConfig,
+    connection_history::{ConnectionAttempt, NetworkProbeResults},
     custom_list::{CustomList, Id},
     device::{Device, DeviceId, DeviceState},
-    features::FeatureIndicators,
-    relay_constraints::{AllowedIps, ObfuscationSettings, RelayOverride, RelaySettings},
-    relay_list::BridgeList,
-    settings::DnsOptions,
-    wireguard::{PublicKey, QuantumResistantState, RotationInterval},
+    features::{FeatureIndicators, SupportedFeatures},
+    location::DnsLeakResult,
+    logging::{LogLevel, LogStreamEvent},
+    network_simulation::SimulatedNetworkConditions,
+    notification::Notification,
+    profile::ConnectionProfile,
+    relay_constraints::{
+        AllowedIps, ObfuscationSensitivity, ObfuscationSettings, ObfuscationState,
+        RelayConstraints, RelayOverride, RelaySettings, SelectedObfuscation,
+        allowed_ip::AllowedIpsWarning,
+    },
+    relay_list::{
+        BridgeList, ObfuscationBenchmarkResult, RelayFeatureSupport, RelayListReport,
+        RelayListUpdateInfo, RelayLocationsSummary, RelayPortReachability, RelayTransparencyInfo,
+        WireguardRelay,
+    },
+    resource_usage::ResourceUsage,
+    schedule::ConnectSchedule,
+    settings::{
+        DnsOptions, DnsServerTestResult, EffectiveDnsServer, FactoryResetPreview, LockableSetting,
+        RelayListDownloadSettings, SettingsIntegrity, SettingsMigration, SettingsSnapshot,
+    },
+    states::RestartReason,
+    trusted_network::TrustedNetworksSettings,
+    usage_stats::UsageStats,
+    version::ActiveDownload,
+    wireguard::{
+        PublicKey, QuantumResistantState, RotationInterval, TunnelCryptoInfo, TunnelMtuInfo,
+        WireguardKeyAge,
+    },
 };
 use std::net::IpAddr;
 #[cfg(not(target_os = "android"))]
-use std::{path::Path, str::FromStr};
+use std::{path::Path, str::FromStr, time::Duration};
+#[cfg(not(target_os = "android"))]
+use talpid_types::firewall::FirewallInfo;
+#[cfg(not(target_os = "android"))]
+use talpid_types::net::wireguard::HandshakeDiagnostics;
+#[cfg(not(target_os = "android"))]
+use talpid_types::net::{InstalledRoute, NetworkInterface, TransportProtocol};
 #[cfg(target_os = "windows")]
 use talpid_types::split_tunnel::ExcludedProcess;
 #[cfg(not(target_os = "android"))]
@@ -52,6 +89,15 @@ pub enum DaemonEvent {
     RemoveDevice(RemoveDeviceEvent),
     NewAccessMethod(AccessMethodSetting),
     LeakDetected(LeakInfo),
+    /// The account expired while connected, and the daemon took the given action.
+    AccountExpired(AccountExpiryAction),
+    /// A hostname pattern did not match any relay, and was ignored when selecting a relay.
+    RelaySelectionFallback(RelaySelectionFallback),
+    /// A captive portal was detected. Connecting is deferred until it has been passed, or until
+    /// [`MullvadProxyClient::dismiss_captive_portal`] is called.
+    CaptivePortalDetected,
+    /// The device joined (`true`) or left (`false`) a trusted network.
+    TrustedNetworkChanged(bool),
 }
 
 impl TryFrom<types::daemon_event::Event> for DaemonEvent {
@@ -85,6 +131,22 @@ impl TryFrom<types::daemon_event::Event> for DaemonEvent {
             types::daemon_event::Event::LeakInfo(leak) => {
                 LeakInfo::try_from(leak).map(DaemonEvent::LeakDetected)
             }
+            types::daemon_event::Event::AccountExpired(action) => {
+                AccountExpiryAction::try_from(action)
+                    .map(DaemonEvent::AccountExpired)
+                    .map_err(Error::InvalidResponse)
+            }
+            types::daemon_event::Event::RelaySelectionFallback(fallback) => {
+                RelaySelectionFallback::try_from(fallback)
+                    .map(DaemonEvent::RelaySelectionFallback)
+                    .map_err(Error::InvalidResponse)
+            }
+            types::daemon_event::Event::CaptivePortalDetected(_) => {
+                Ok(DaemonEvent::CaptivePortalDetected)
+            }
+            types::daemon_event::Event::TrustedNetworkChanged(event) => {
+                Ok(DaemonEvent::TrustedNetworkChanged(event.trusted))
+            }
         }
     }
 }
@@ -112,11 +174,39 @@ impl MullvadProxyClient {
         Ok(self.0.reconnect_tunnel(()).await?.into_inner())
     }
 
+    /// Disconnect, and reconnect once `duration` has passed. The target state is left secured.
+    pub async fn pause_tunnel(&mut self, duration: Duration) -> Result<()> {
+        let duration = prost_types::Duration::try_from(duration)
+            .expect("Failed to convert std::time::Duration to prost_types::Duration");
+        self.0.pause_tunnel(duration).await?;
+        Ok(())
+    }
+
+    /// End a pause early and reconnect. Returns whether the tunnel was paused.
+    pub async fn resume_tunnel(&mut self) -> Result<bool> {
+        Ok(self.0.resume_tunnel(()).await?.into_inner())
+    }
+
+    pub async fn connect_to_relay_once(&mut self, hostname: String) -> Result<()> {
+        self.0
+            .connect_to_relay_once(hostname)
+            .await
+            .map_err(|status| match status.code() {
+                Code::NotFound => Error::RelayNotFound,
+                _other => Error::Rpc(Box::new(status)),
+            })?;
+        Ok(())
+    }
+
     pub async fn get_tunnel_state(&mut self) -> Result<TunnelState> {
         let state = self.0.get_tunnel_state(()).await?.into_inner();
         TunnelState::try_from(state).map_err(Error::InvalidResponse)
     }
 
+    pub async fn is_kill_switch_active(&mut self) -> Result<bool> {
+        Ok(self.0.is_kill_switch_active(()).await?.into_inner())
+    }
+
     pub async fn events_listen<'a>(
         &mut self,
     ) -> Result<impl Stream<Item = Result<DaemonEvent>> + 'a> {
@@ -144,25 +234,398 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    /// Return why the daemon was last restarted, or `None` if this is not known.
+    pub async fn get_last_restart_reason(&mut self) -> Result<Option<RestartReason>> {
+        let reason = match self.0.get_last_restart_reason(()).await {
+            Ok(reason) => reason.into_inner(),
+            Err(status) if status.code() == Code::NotFound => return Ok(None),
+            Err(status) => return Err(status.into()),
+        };
+        RestartReason::try_from(reason)
+            .map(Some)
+            .map_err(Error::InvalidResponse)
+    }
+
+    /// Return usage statistics accumulated across tunnel sessions. These are only stored locally
+    /// by the daemon and are never sent to the API.
+    pub async fn get_usage_stats(&mut self) -> Result<UsageStats> {
+        let stats = self.0.get_usage_stats(()).await?.into_inner();
+        UsageStats::try_from(stats).map_err(Error::InvalidResponse)
+    }
+
+    /// Return the conditions that the user should be notified about and has not acknowledged yet,
+    /// most severe first.
+    pub async fn get_notifications(&mut self) -> Result<Vec<Notification>> {
+        let notifications = self.0.get_notifications(()).await?.into_inner();
+        notifications
+            .notifications
+            .into_iter()
+            .map(Notification::try_from)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(Error::InvalidResponse)
+    }
+
+    /// Dismiss the notification with the given ID, so that it is not returned again.
+    pub async fn acknowledge_notification(&mut self, id: String) -> Result<()> {
+        self.0.acknowledge_notification(id).await?;
+        Ok(())
+    }
+
+    /// Forget the accumulated usage statistics.
+    pub async fn reset_usage_stats(&mut self) -> Result<()> {
+        self.0.reset_usage_stats(()).await?;
+        Ok(())
+    }
+
+    /// Return the firewall backend used by the daemon and any known problems with it.
+    pub async fn get_firewall_info(&mut self) -> Result<FirewallInfo> {
+        let info = self.0.get_firewall_info(()).await?.into_inner();
+        FirewallInfo::try_from(info).map_err(Error::InvalidResponse)
+    }
+
+    /// Simulate lost and delayed API connection attempts. The conditions apply once per
+    /// connection attempt, not per packet. This fails unless the daemon was built with the
+    /// `network-simulation` feature.
+    pub async fn set_simulated_network_conditions(
+        &mut self,
+        conditions: SimulatedNetworkConditions,
+    ) -> Result<()> {
+        self.0
+            .set_simulated_network_conditions(types::SimulatedNetworkConditions::from(conditions))
+            .await?;
+        Ok(())
+    }
+
+    /// Return the memory and handle usage of the daemon. This fails unless the daemon was built
+    /// with the `diagnostics` feature.
+    pub async fn get_resource_usage(&mut self) -> Result<ResourceUsage> {
+        let usage = self.0.get_resource_usage(()).await?.into_inner();
+        Ok(ResourceUsage::from(usage))
+    }
+
     pub async fn factory_reset(&mut self) -> Result<()> {
         self.0.factory_reset(()).await?;
         Ok(())
     }
 
+    /// Return what [`Self::factory_reset`] would remove, without removing anything.
+    pub async fn factory_reset_preview(&mut self) -> Result<FactoryResetPreview> {
+        let preview = self.0.get_factory_reset_preview(()).await?.into_inner();
+        Ok(FactoryResetPreview::from(preview))
+    }
+
+    /// Return the disk usage of the files cached by the daemon
+    pub async fn get_cache_usage(&mut self) -> Result<CacheUsage> {
+        let usage = self.0.get_cache_usage(()).await?.into_inner();
+        Ok(CacheUsage::from(usage))
+    }
+
+    /// Return the response to the most recent API request that failed with an unexpected status
+    /// code, or `None` if no request has failed that way since the daemon started
+    pub async fn get_last_api_error(&mut self) -> Result<Option<ApiErrorResponse>> {
+        let error = match self.0.get_last_api_error(()).await {
+            Ok(error) => error.into_inner(),
+            Err(status) if status.code() == Code::NotFound => return Ok(None),
+            Err(status) => return Err(status.into()),
+        };
+        ApiErrorResponse::try_from(error)
+            .map(Some)
+            .map_err(Error::InvalidResponse)
+    }
+
+    /// Set the maximum number of API requests that may be in flight at once. Zero means unbounded
+    pub async fn set_max_concurrent_api_requests(&mut self, max_requests: u32) -> Result<()> {
+        self.0.set_max_concurrent_api_requests(max_requests).await?;
+        Ok(())
+    }
+
+    pub async fn get_api_sni_hostname(&mut self) -> Result<Option<String>> {
+        Ok(self.get_settings().await?.api_sni_hostname)
+    }
+
+    /// Set the server name to send when connecting to the API. `None` means that the API
+    /// hostname is sent
+    pub async fn set_api_sni_hostname(&mut self, hostname: Option<String>) -> Result<()> {
+        self.0
+            .set_api_sni_hostname(types::ApiSniHostname { hostname })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_relay_list_download_settings(&mut self) -> Result<RelayListDownloadSettings> {
+        Ok(self.get_settings().await?.relay_list_download)
+    }
+
+    /// Set the bandwidth limit for relay list and API address downloads, and whether the network
+    /// is metered. Automatic relay list updates are skipped on metered networks, but
+    /// [Self::update_relay_locations] still fetches the relay list.
+    pub async fn set_relay_list_download_settings(
+        &mut self,
+        settings: RelayListDownloadSettings,
+    ) -> Result<()> {
+        self.0
+            .set_relay_list_download_settings(types::RelayListDownloadSettings::from(settings))
+            .await?;
+        Ok(())
+    }
+
+    /// Return the maximum number of concurrent API requests, and the number of requests waiting
+    /// because of it
+    pub async fn get_api_request_limit(&mut self) -> Result<ApiRequestLimit> {
+        let limit = self.0.get_api_request_limit(()).await?.into_inner();
+        Ok(ApiRequestLimit::from(limit))
+    }
+
+    /// Return the address that is used to reach the API, and where it comes from
+    pub async fn get_api_address_source(&mut self) -> Result<ApiAddressInfo> {
+        let info = self.0.get_api_address_source(()).await?.into_inner();
+        ApiAddressInfo::try_from(info).map_err(Error::InvalidResponse)
+    }
+
+    /// Return the API requests that have been sent but have not completed yet, oldest first
+    pub async fn get_inflight_api_requests(&mut self) -> Result<Vec<InflightApiRequest>> {
+        let requests = self.0.get_inflight_api_requests(()).await?.into_inner();
+        requests
+            .requests
+            .into_iter()
+            .map(InflightApiRequest::try_from)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(Error::InvalidResponse)
+    }
+
+    /// Drop all pooled API connections, so that subsequent requests establish new connections
+    pub async fn reset_api_connections(&mut self) -> Result<()> {
+        self.0.reset_api_connections(()).await?;
+        Ok(())
+    }
+
+    /// Re-resolve the API address, drop API connections, refresh the relay list, and reconnect
+    /// the tunnel if it is connected. Useful after moving to another network.
+    pub async fn handle_network_change(&mut self) -> Result<()> {
+        self.0.handle_network_change(()).await?;
+        Ok(())
+    }
+
+    /// Compare the system clock with the time reported by the API
+    pub async fn check_system_clock(&mut self) -> Result<ClockCheck> {
+        let check = self.0.check_system_clock(()).await?.into_inner();
+        ClockCheck::try_from(check).map_err(Error::InvalidResponse)
+    }
+
+    /// Remove the given kinds of cached files. Fails without removing anything if installers are
+    /// included while an upgrade is in progress.
+    pub async fn clear_cache(&mut self, kinds: &[CacheKind]) -> Result<()> {
+        self.0.clear_cache(types::CacheKinds::from(kinds)).await?;
+        Ok(())
+    }
+
     pub async fn get_current_version(&mut self) -> Result<String> {
         Ok(self.0.get_current_version(()).await?.into_inner())
     }
 
+    /// Return the functionality supported by the daemon. Use this to detect whether a feature is
+    /// available before using it.
+    pub async fn get_supported_features(&mut self) -> Result<SupportedFeatures> {
+        let features = self.0.get_supported_features(()).await?.into_inner();
+        Ok(SupportedFeatures::from(features))
+    }
+
+    /// Return the network interfaces on the host, as seen by the daemon.
+    pub async fn list_network_interfaces(&mut self) -> Result<Vec<NetworkInterface>> {
+        self.0
+            .list_network_interfaces(())
+            .await?
+            .into_inner()
+            .interfaces
+            .into_iter()
+            .map(|interface| NetworkInterface::try_from(interface).map_err(Error::InvalidResponse))
+            .collect()
+    }
+
+    /// Return the routes that the daemon has added to the routing table.
+    pub async fn get_installed_routes(&mut self) -> Result<Vec<InstalledRoute>> {
+        self.0
+            .get_installed_routes(())
+            .await?
+            .into_inner()
+            .routes
+            .into_iter()
+            .map(|route| InstalledRoute::try_from(route).map_err(Error::InvalidResponse))
+            .collect()
+    }
+
     pub async fn get_version_info(&mut self) -> Result<AppVersionInfo> {
         let version_info = self.0.get_version_info(()).await?.into_inner();
         AppVersionInfo::try_from(version_info).map_err(Error::InvalidResponse)
     }
 
+    /// Return the download size in bytes of the suggested upgrade, or `None` if no upgrade is
+    /// available.
+    pub async fn get_upgrade_download_size(&mut self) -> Result<Option<u64>> {
+        let download_size = self.0.get_upgrade_download_size(()).await?.into_inner();
+        Ok(download_size.size)
+    }
+
+    /// Return whether the daemon is performing post-upgrade tasks
+    pub async fn is_performing_post_upgrade(&mut self) -> Result<bool> {
+        Ok(self.0.is_performing_post_upgrade(()).await?.into_inner())
+    }
+
+    /// Download and verify the suggested upgrade in the background. Progress is reported as app
+    /// upgrade events, but the installer is not announced until the app is upgraded.
+    pub async fn prefetch_update(&mut self) -> Result<()> {
+        self.0.prefetch_update(()).await?;
+        Ok(())
+    }
+
+    /// Return the installer downloads that the daemon is performing
+    pub async fn list_active_downloads(&mut self) -> Result<Vec<ActiveDownload>> {
+        let downloads = self.0.list_active_downloads(()).await?.into_inner();
+        downloads
+            .downloads
+            .into_iter()
+            .map(|download| ActiveDownload::try_from(download).map_err(Error::InvalidResponse))
+            .collect()
+    }
+
+    /// Abort the installer download identified by `id`
+    pub async fn cancel_download(&mut self, id: u32) -> Result<()> {
+        self.0.cancel_download(id).await?;
+        Ok(())
+    }
+
+    /// Return the relay list, including the coordinates of each city and relay. See
+    /// [mullvad_types::relay_list::RelayListCity::coordinates].
     pub async fn get_relay_locations(&mut self) -> Result<RelayList> {
         let list = self.0.get_relay_locations(()).await?.into_inner();
         mullvad_types::relay_list::RelayList::try_from(list).map_err(Error::InvalidResponse)
     }
 
+    /// Return the countries and cities of the relay list, without the relays.
+    pub async fn get_relay_locations_summary(&mut self) -> Result<RelayLocationsSummary> {
+        let summary = self.0.get_relay_locations_summary(()).await?.into_inner();
+        Ok(RelayLocationsSummary::from(summary))
+    }
+
+    /// Return the relays in a city of the relay list, or `None` if there is no such city.
+    pub async fn get_relays_in_city(
+        &mut self,
+        country_code: String,
+        city_code: String,
+    ) -> Result<Option<Vec<WireguardRelay>>> {
+        let location = types::CityLocation {
+            country_code,
+            city_code,
+        };
+        let relays = match self.0.get_relays_in_city(location).await {
+            Ok(relays) => relays.into_inner(),
+            Err(status) if status.code() == Code::NotFound => return Ok(None),
+            Err(status) => return Err(status.into()),
+        };
+        Vec::<WireguardRelay>::try_from(relays)
+            .map(Some)
+            .map_err(Error::InvalidResponse)
+    }
+
+    /// Return which of the features required by the current settings are supported by each relay
+    /// matching `relay_settings`. The relay settings in use are not changed.
+    pub async fn check_relay_feature_support(
+        &mut self,
+        relay_settings: RelaySettings,
+    ) -> Result<RelayFeatureSupport> {
+        let support = self
+            .0
+            .check_relay_feature_support(types::RelaySettings::from(relay_settings))
+            .await?
+            .into_inner();
+        RelayFeatureSupport::try_from(support).map_err(Error::InvalidResponse)
+    }
+
+    /// Validate the relay list in use by the daemon. If problems are found, the relay list should
+    /// be refreshed using [Self::update_relay_locations].
+    pub async fn relay_list_self_test(&mut self) -> Result<RelayListReport> {
+        let report = self.0.relay_list_self_test(()).await?.into_inner();
+        Ok(RelayListReport::from(report))
+    }
+
+    /// Return how often the relay list is fetched automatically, when it was last fetched, and when
+    /// it will next be fetched. Use [Self::update_relay_locations] to fetch it immediately.
+    pub async fn get_relay_list_update_info(&mut self) -> Result<RelayListUpdateInfo> {
+        let info = self.0.get_relay_list_update_info(()).await?.into_inner();
+        RelayListUpdateInfo::try_from(info).map_err(Error::InvalidResponse)
+    }
+
+    /// Test whether `port` on the relay with the given hostname is reachable using `protocol`
+    pub async fn test_relay_port(
+        &mut self,
+        hostname: String,
+        port: u16,
+        protocol: TransportProtocol,
+    ) -> Result<RelayPortReachability> {
+        let result = self
+            .0
+            .test_relay_port(types::RelayPortTest {
+                hostname,
+                port: u32::from(port),
+                protocol: i32::from(types::TransportProtocol::from(protocol)),
+            })
+            .await
+            .map_err(|status| match status.code() {
+                Code::NotFound => Error::RelayNotFound,
+                _other => Error::Rpc(Box::new(status)),
+            })?
+            .into_inner();
+        RelayPortReachability::try_from(result).map_err(Error::InvalidResponse)
+    }
+
+    /// Return who owns the relay with the given hostname and who hosts it
+    pub async fn get_relay_transparency_info(
+        &mut self,
+        hostname: String,
+    ) -> Result<RelayTransparencyInfo> {
+        let info = self
+            .0
+            .get_relay_transparency_info(hostname)
+            .await
+            .map_err(|status| match status.code() {
+                Code::NotFound => Error::RelayNotFound,
+                _other => Error::Rpc(Box::new(status)),
+            })?
+            .into_inner();
+        RelayTransparencyInfo::try_from(info).map_err(Error::InvalidResponse)
+    }
+
+    /// Connect to the relay with the given hostname using each of `methods` in turn, and return
+    /// how well each method performed. This may take a couple of minutes.
+    pub async fn benchmark_obfuscation(
+        &mut self,
+        hostname: String,
+        methods: Vec<SelectedObfuscation>,
+    ) -> Result<Vec<ObfuscationBenchmarkResult>> {
+        let methods = methods
+            .into_iter()
+            .map(|method| {
+                i32::from(types::obfuscation_settings::SelectedObfuscation::from(
+                    method,
+                ))
+            })
+            .collect();
+        self.0
+            .benchmark_obfuscation(types::ObfuscationBenchmarkRequest { hostname, methods })
+            .await
+            .map_err(|status| match status.code() {
+                Code::NotFound => Error::RelayNotFound,
+                _other => Error::Rpc(Box::new(status)),
+            })?
+            .into_inner()
+            .results
+            .into_iter()
+            .map(|result| {
+                ObfuscationBenchmarkResult::try_from(result).map_err(Error::InvalidResponse)
+            })
+            .collect()
+    }
+
     pub async fn get_bridges(&mut self) -> Result<BridgeList> {
         let list = self.0.get_bridges(()).await?.into_inner();
         mullvad_types::relay_list::BridgeList::try_from(list).map_err(Error::InvalidResponse)
@@ -206,6 +669,21 @@ impl MullvadProxyClient {
             })
     }
 
+    /// Return the access method which carried the most recent successful API request, or
+    /// `None` if no request has succeeded since the daemon started.
+    pub async fn get_last_successful_api_access_method(
+        &mut self,
+    ) -> Result<Option<LastSuccessfulAccessMethod>> {
+        let last_success = match self.0.get_last_successful_api_access_method(()).await {
+            Ok(last_success) => last_success.into_inner(),
+            Err(status) if status.code() == Code::NotFound => return Ok(None),
+            Err(status) => return Err(status.into()),
+        };
+        LastSuccessfulAccessMethod::try_from(last_success)
+            .map(Some)
+            .map_err(Error::InvalidResponse)
+    }
+
     pub async fn test_api_access_method(&mut self, id: access_method::Id) -> Result<bool> {
         let result = self
             .0
@@ -214,6 +692,56 @@ impl MullvadProxyClient {
         Ok(result.into_inner())
     }
 
+    /// Test all enabled access methods, one at a time, returning whether each
+    /// one could reach the API and how long its test took.
+    pub async fn test_all_api_access_methods(
+        &mut self,
+    ) -> Result<Vec<access_method::AccessMethodTestResult>> {
+        self.0
+            .test_all_api_access_methods(())
+            .await?
+            .into_inner()
+            .results
+            .into_iter()
+            .map(access_method::AccessMethodTestResult::try_from)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(Error::InvalidResponse)
+    }
+
+    /// Test enabled access methods one at a time, in priority order, until one
+    /// can reach the API. If `set_active` is `true`, the first working access
+    /// method is set as the active one.
+    pub async fn find_working_api_access_method(
+        &mut self,
+        set_active: bool,
+    ) -> Result<access_method::AccessMethodSearch> {
+        let search = self
+            .0
+            .find_working_api_access_method(set_active)
+            .await?
+            .into_inner();
+        access_method::AccessMethodSearch::try_from(search).map_err(Error::InvalidResponse)
+    }
+
+    /// Export all custom access methods as JSON.
+    ///
+    /// If `include_secrets` is `false`, proxy credentials are left out, and the affected
+    /// access methods cannot be imported again.
+    pub async fn export_api_access_methods(&mut self, include_secrets: bool) -> Result<String> {
+        Ok(self
+            .0
+            .export_api_access_methods(include_secrets)
+            .await?
+            .into_inner())
+    }
+
+    /// Import custom access methods from JSON produced by
+    /// [`MullvadProxyClient::export_api_access_methods`].
+    pub async fn import_api_access_methods(&mut self, json: String) -> Result<()> {
+        self.0.import_api_access_methods(json).await?;
+        Ok(())
+    }
+
     pub async fn test_custom_api_access_method(
         &mut self,
         config: talpid_types::net::proxy::CustomProxy,
@@ -236,12 +764,79 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    /// Only select relays whose hostname matches the glob `pattern`, such as `se-got-wg-*`. Custom
+    /// relay settings are replaced by the default constraints. The daemon rejects patterns with
+    /// invalid syntax.
+    pub async fn set_relay_hostname_pattern(&mut self, pattern: &str) -> Result<()> {
+        let constraints = match self.get_settings().await?.relay_settings {
+            RelaySettings::Normal(constraints) => constraints,
+            RelaySettings::CustomTunnelEndpoint(_) => RelayConstraints::default(),
+        };
+        // The pattern is validated by the daemon
+        let mut update = types::RelaySettings::from(RelaySettings::Normal(constraints));
+        if let Some(types::relay_settings::Endpoint::Normal(normal)) = &mut update.endpoint {
+            normal.location = Some(types::LocationConstraint {
+                r#type: Some(types::location_constraint::Type::HostnamePattern(
+                    pattern.to_owned(),
+                )),
+            });
+        }
+        self.0.set_relay_settings(update).await?;
+        Ok(())
+    }
+
     pub async fn set_obfuscation_settings(&mut self, settings: ObfuscationSettings) -> Result<()> {
         let settings = types::ObfuscationSettings::from(&settings);
         self.0.set_obfuscation_settings(settings).await?;
         Ok(())
     }
 
+    /// Return how eagerly automatic obfuscation is used
+    pub async fn get_obfuscation_auto_sensitivity(&mut self) -> Result<ObfuscationSensitivity> {
+        Ok(self
+            .get_settings()
+            .await?
+            .obfuscation_settings
+            .auto_sensitivity)
+    }
+
+    pub async fn set_obfuscation_auto_sensitivity(
+        &mut self,
+        sensitivity: ObfuscationSensitivity,
+    ) -> Result<()> {
+        let sensitivity = types::ObfuscationAutoSensitivity::from(sensitivity);
+        self.0.set_obfuscation_auto_sensitivity(sensitivity).await?;
+        Ok(())
+    }
+
+    /// Return the obfuscation used by the most recent tunnel, including the methods that were
+    /// tried before it when obfuscation is automatic
+    pub async fn get_obfuscation_state(&mut self) -> Result<ObfuscationState> {
+        let state = self.0.get_obfuscation_state(()).await?.into_inner();
+        ObfuscationState::try_from(state).map_err(Error::InvalidResponse)
+    }
+
+    /// Return the most recent attempts to establish a tunnel, oldest first
+    pub async fn get_connection_attempt_history(&mut self) -> Result<Vec<ConnectionAttempt>> {
+        let history = self
+            .0
+            .get_connection_attempt_history(())
+            .await?
+            .into_inner();
+        history
+            .attempts
+            .into_iter()
+            .map(ConnectionAttempt::try_from)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(Error::InvalidResponse)
+    }
+
+    /// Return whether UDP and TCP appear to be reachable, judging by recent connection attempts
+    pub async fn get_network_probe_results(&mut self) -> Result<NetworkProbeResults> {
+        let results = self.0.get_network_probe_results(()).await?.into_inner();
+        NetworkProbeResults::try_from(results).map_err(Error::InvalidResponse)
+    }
+
     pub async fn get_settings(&mut self) -> Result<Settings> {
         let settings = self.0.get_settings(()).await?.into_inner();
         Settings::try_from(settings).map_err(Error::InvalidResponse)
@@ -252,6 +847,50 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    /// Check whether the settings file on disk can be loaded, without applying it
+    pub async fn verify_settings_integrity(&mut self) -> Result<SettingsIntegrity> {
+        let integrity = self.0.verify_settings_integrity(()).await?.into_inner();
+        SettingsIntegrity::try_from(integrity).map_err(Error::InvalidResponse)
+    }
+
+    /// Return the settings migration steps that were applied when the daemon started
+    pub async fn get_applied_settings_migrations(&mut self) -> Result<Vec<SettingsMigration>> {
+        let migrations = self
+            .0
+            .get_applied_settings_migrations(())
+            .await?
+            .into_inner()
+            .migrations
+            .into_iter()
+            .map(SettingsMigration::from)
+            .collect();
+        Ok(migrations)
+    }
+
+    /// Lock `settings` against changes until they are unlocked with `password`
+    pub async fn lock_settings(
+        &mut self,
+        settings: &[LockableSetting],
+        password: String,
+    ) -> Result<()> {
+        let settings = types::LockedSettings::from(settings).settings;
+        self.0
+            .lock_settings(types::LockSettingsRequest { settings, password })
+            .await?;
+        Ok(())
+    }
+
+    /// Unlock all settings
+    pub async fn unlock_settings(&mut self, password: String) -> Result<()> {
+        self.0.unlock_settings(password).await?;
+        Ok(())
+    }
+
+    pub async fn get_locked_settings(&mut self) -> Result<Vec<LockableSetting>> {
+        let settings = self.0.get_locked_settings(()).await?.into_inner();
+        Vec::try_from(settings).map_err(Error::InvalidResponse)
+    }
+
     pub async fn set_allow_lan(&mut self, state: bool) -> Result<()> {
         self.0.set_allow_lan(state).await?;
         Ok(())
@@ -262,6 +901,58 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    /// Pick a uniformly random relay on every connection attempt, avoiding the previous exit
+    /// relay.
+    pub async fn set_randomize_relay_each_connect(&mut self, state: bool) -> Result<()> {
+        self.0.set_randomize_relay_each_connect(state).await?;
+        Ok(())
+    }
+
+    /// Pause non-essential background API requests, such as version checks, and check for relay
+    /// list updates less often. Connecting is not affected.
+    pub async fn set_low_power_mode(&mut self, state: bool) -> Result<()> {
+        self.0.set_low_power_mode(state).await?;
+        Ok(())
+    }
+
+    /// Check for a captive portal before connecting, and defer connecting while one is detected.
+    pub async fn set_captive_portal_check(&mut self, state: bool) -> Result<()> {
+        self.0.set_captive_portal_check(state).await?;
+        Ok(())
+    }
+
+    pub async fn get_connect_timeout(&mut self) -> Result<Duration> {
+        Ok(self.get_settings().await?.connect_timeout)
+    }
+
+    /// Set how long to keep retrying to connect before giving up and blocking traffic. A zero
+    /// `timeout` means that connecting is retried indefinitely.
+    pub async fn set_connect_timeout(&mut self, timeout: Duration) -> Result<()> {
+        let timeout = prost_types::Duration::try_from(timeout)
+            .expect("Failed to convert std::time::Duration to prost_types::Duration");
+        self.0.set_connect_timeout(timeout).await?;
+        Ok(())
+    }
+
+    pub async fn get_max_reconnect_attempts(&mut self) -> Result<Option<u32>> {
+        Ok(self.get_settings().await?.max_reconnect_attempts)
+    }
+
+    /// Set how many times to automatically retry connecting before giving up and blocking
+    /// traffic. `None` means that connecting is retried indefinitely.
+    pub async fn set_max_reconnect_attempts(&mut self, max_attempts: Option<u32>) -> Result<()> {
+        self.0
+            .set_max_reconnect_attempts(types::MaxReconnectAttempts { max_attempts })
+            .await?;
+        Ok(())
+    }
+
+    /// Connect immediately instead of waiting for a detected captive portal to be passed.
+    pub async fn dismiss_captive_portal(&mut self) -> Result<()> {
+        self.0.dismiss_captive_portal(()).await?;
+        Ok(())
+    }
+
     pub async fn set_lockdown_mode(&mut self, state: bool) -> Result<()> {
         self.0.set_lockdown_mode(state).await?;
         Ok(())
@@ -272,6 +963,80 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    pub async fn get_connect_schedule(&mut self) -> Result<ConnectSchedule> {
+        Ok(self.get_settings().await?.connect_schedule)
+    }
+
+    pub async fn set_connect_schedule(&mut self, schedule: &ConnectSchedule) -> Result<()> {
+        self.0
+            .set_connect_schedule(types::ConnectSchedule::from(schedule))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_trusted_networks(&mut self) -> Result<TrustedNetworksSettings> {
+        Ok(self.get_settings().await?.trusted_networks)
+    }
+
+    pub async fn set_trusted_networks(
+        &mut self,
+        trusted_networks: &TrustedNetworksSettings,
+    ) -> Result<()> {
+        self.0
+            .set_trusted_networks(types::TrustedNetworks::from(trusted_networks))
+            .await?;
+        Ok(())
+    }
+
+    /// Trust the Wi-Fi network with the given SSID. The daemon only stores a salted hash of it.
+    pub async fn add_trusted_ssid(&mut self, ssid: Vec<u8>) -> Result<()> {
+        self.0.add_trusted_ssid(ssid).await?;
+        Ok(())
+    }
+
+    /// Save the current relay, obfuscation, and tunnel settings as a profile named `name`,
+    /// replacing any profile with the same name
+    pub async fn save_profile(&mut self, name: String) -> Result<()> {
+        self.0.save_profile(name).await?;
+        Ok(())
+    }
+
+    /// Replace the current settings with those in the profile `name`. If `reconnect` is set, the
+    /// tunnel is reconnected so that they take effect immediately.
+    pub async fn apply_profile(&mut self, name: String, reconnect: bool) -> Result<()> {
+        self.0
+            .apply_profile(types::ApplyProfileRequest { name, reconnect })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_profiles(&mut self) -> Result<Vec<ConnectionProfile>> {
+        let profiles = self.0.list_profiles(()).await?.into_inner();
+        profiles
+            .profiles
+            .into_iter()
+            .map(ConnectionProfile::try_from)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(Error::InvalidResponse)
+    }
+
+    pub async fn delete_profile(&mut self, name: String) -> Result<()> {
+        self.0.delete_profile(name).await?;
+        Ok(())
+    }
+
+    /// Encode the current relay and obfuscation settings as a link that can be shared.
+    pub async fn export_relay_link(&mut self) -> Result<String> {
+        Ok(self.0.export_relay_link(()).await?.into_inner())
+    }
+
+    /// Apply the relay and obfuscation settings in a link produced by
+    /// [Self::export_relay_link].
+    pub async fn import_relay_link(&mut self, link: String) -> Result<()> {
+        self.0.import_relay_link(link).await?;
+        Ok(())
+    }
+
     pub async fn set_wireguard_mtu(&mut self, mtu: Option<u16>) -> Result<()> {
         self.0
             .set_wireguard_mtu(mtu.map(u32::from).unwrap_or(0))
@@ -279,6 +1044,40 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    /// Return the persistent keepalive interval in seconds, or `None` if keepalive is disabled.
+    pub async fn get_wireguard_keepalive(&mut self) -> Result<Option<u16>> {
+        Ok(self
+            .get_settings()
+            .await?
+            .tunnel_options
+            .wireguard
+            .keepalive)
+    }
+
+    /// Set the persistent keepalive interval in seconds. `None` disables keepalive. The daemon
+    /// rejects intervals outside of [`KEEPALIVE_INTERVAL_RANGE`].
+    ///
+    /// [`KEEPALIVE_INTERVAL_RANGE`]: mullvad_types::wireguard::KEEPALIVE_INTERVAL_RANGE
+    pub async fn set_wireguard_keepalive(&mut self, interval: Option<u16>) -> Result<()> {
+        self.0
+            .set_wireguard_keepalive(interval.map(u32::from).unwrap_or(0))
+            .await?;
+        Ok(())
+    }
+
+    /// Bind the tunnel socket to a local address. `None` lets the OS choose the address based on
+    /// the routing table. The daemon rejects addresses that are not assigned to this machine.
+    pub async fn set_tunnel_bind_address(&mut self, address: Option<IpAddr>) -> Result<()> {
+        self.0
+            .set_tunnel_bind_address(
+                address
+                    .map(|address| address.to_string())
+                    .unwrap_or_default(),
+            )
+            .await?;
+        Ok(())
+    }
+
     pub async fn set_enable_ipv6(&mut self, state: bool) -> Result<()> {
         self.0.set_enable_ipv6(state).await?;
         Ok(())
@@ -293,6 +1092,16 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    pub async fn get_on_account_expiry(&mut self) -> Result<AccountExpiryAction> {
+        Ok(self.get_settings().await?.on_account_expiry)
+    }
+
+    pub async fn set_on_account_expiry(&mut self, action: AccountExpiryAction) -> Result<()> {
+        let action = types::AccountExpiryAction::from(action);
+        self.0.set_on_account_expiry(action).await?;
+        Ok(())
+    }
+
     #[cfg(daita)]
     pub async fn set_enable_daita(&mut self, value: bool) -> Result<()> {
         self.0.set_enable_daita(value).await?;
@@ -305,6 +1114,12 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    #[cfg(daita)]
+    pub async fn set_daita_required(&mut self, value: bool) -> Result<()> {
+        self.0.set_daita_required(value).await?;
+        Ok(())
+    }
+
     #[cfg(daita)]
     pub async fn set_daita_settings(&mut self, settings: DaitaSettings) -> Result<()> {
         let settings = types::DaitaSettings::from(settings);
@@ -318,6 +1133,58 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    /// Get the DNS servers currently used by the tunnel, along with where they come from.
+    ///
+    /// This is empty unless the tunnel is connected.
+    pub async fn get_effective_dns_servers(&mut self) -> Result<Vec<EffectiveDnsServer>> {
+        self.0
+            .get_effective_dns_servers(())
+            .await?
+            .into_inner()
+            .servers
+            .into_iter()
+            .map(EffectiveDnsServer::try_from)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(Error::InvalidResponse)
+    }
+
+    /// Return the configuration that a tunnel would use if it was started now, without
+    /// connecting. Relays are picked at random among the matching ones, so an actual connection
+    /// may use different relays.
+    pub async fn get_effective_connection_config(&mut self) -> Result<EffectiveConnectionConfig> {
+        let config = self
+            .0
+            .get_effective_connection_config(())
+            .await?
+            .into_inner();
+        EffectiveConnectionConfig::try_from(config).map_err(Error::InvalidResponse)
+    }
+
+    /// Send a test query to each of `servers`, and return whether they answered. The queries are
+    /// subject to the current tunnel state, so servers that the firewall blocks are not queried.
+    pub async fn test_custom_dns(
+        &mut self,
+        servers: &[IpAddr],
+    ) -> Result<Vec<DnsServerTestResult>> {
+        self.0
+            .test_custom_dns(types::CustomDnsOptions {
+                addresses: servers.iter().map(IpAddr::to_string).collect(),
+            })
+            .await?
+            .into_inner()
+            .results
+            .into_iter()
+            .map(DnsServerTestResult::try_from)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(Error::InvalidResponse)
+    }
+
+    /// Check whether DNS queries leak outside of Mullvad.
+    pub async fn check_dns_leak(&mut self) -> Result<DnsLeakResult> {
+        let result = self.0.check_dns_leak(()).await?.into_inner();
+        DnsLeakResult::try_from(result).map_err(Error::InvalidResponse)
+    }
+
     pub async fn set_relay_override(&mut self, relay_override: RelayOverride) -> Result<()> {
         let r#override = types::RelayOverride::from(relay_override);
         self.0.set_relay_override(r#override).await?;
@@ -346,11 +1213,45 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    pub async fn switch_account(&mut self, account: AccountNumber) -> Result<()> {
+        self.0
+            .switch_account(account)
+            .await
+            .map_err(map_device_error)?;
+        Ok(())
+    }
+
+    pub async fn get_saved_accounts(&mut self) -> Result<Vec<AccountNumber>> {
+        let accounts = self.0.get_saved_accounts(()).await?.into_inner();
+        Ok(accounts.numbers)
+    }
+
     pub async fn logout_account(&mut self) -> Result<()> {
         self.0.logout_account(()).await?;
         Ok(())
     }
 
+    /// Request a new access token for the current account and validate the current device,
+    /// keeping its WireGuard key. If this fails because the device or account is no longer
+    /// valid, the device is revoked and a new login is required.
+    pub async fn reauthenticate(&mut self) -> Result<()> {
+        self.0.reauthenticate(()).await.map_err(map_device_error)?;
+        Ok(())
+    }
+
+    /// Return whether logins are delayed, and until when, after too many logins in a row failed
+    /// due to an invalid account number.
+    pub async fn get_login_backoff(&mut self) -> Result<LoginBackoff> {
+        let backoff = self.0.get_login_backoff(()).await?.into_inner();
+        LoginBackoff::try_from(backoff).map_err(Error::InvalidResponse)
+    }
+
+    /// Forget failed logins, so that the next login is allowed immediately.
+    pub async fn clear_login_backoff(&mut self) -> Result<()> {
+        self.0.clear_login_backoff(()).await?;
+        Ok(())
+    }
+
     pub async fn get_account_data(&mut self, account: AccountNumber) -> Result<AccountData> {
         let data = self.0.get_account_data(account).await?.into_inner();
         AccountData::try_from(data).map_err(Error::InvalidResponse)
@@ -450,6 +1351,83 @@ impl MullvadProxyClient {
         PublicKey::try_from(key).map_err(Error::InvalidResponse)
     }
 
+    /// Return when the current WireGuard key was created and how old it is, or `None` if logged
+    /// out
+    pub async fn get_wireguard_key_age(&mut self) -> Result<Option<WireguardKeyAge>> {
+        let key_age = match self.0.get_wireguard_key_age(()).await {
+            Ok(key_age) => key_age.into_inner(),
+            Err(status) if status.code() == Code::NotFound => return Ok(None),
+            Err(status) => return Err(status.into()),
+        };
+        WireguardKeyAge::try_from(key_age)
+            .map(Some)
+            .map_err(Error::InvalidResponse)
+    }
+
+    /// Return diagnostics for the last tunnel that timed out before becoming functional, if the
+    /// tunnel has not come up since.
+    pub async fn get_last_handshake_diagnostics(&mut self) -> Result<Option<HandshakeDiagnostics>> {
+        let diagnostics = match self.0.get_last_handshake_diagnostics(()).await {
+            Ok(diagnostics) => diagnostics.into_inner(),
+            Err(status) if status.code() == Code::NotFound => return Ok(None),
+            Err(status) => return Err(status.into()),
+        };
+        HandshakeDiagnostics::try_from(diagnostics)
+            .map(Some)
+            .map_err(Error::InvalidResponse)
+    }
+
+    /// Return the MTU and per-packet overhead of the tunnel, or `None` if it is not connected
+    pub async fn get_tunnel_mtu_info(&mut self) -> Result<Option<TunnelMtuInfo>> {
+        let info = match self.0.get_tunnel_mtu_info(()).await {
+            Ok(info) => info.into_inner(),
+            Err(status) if status.code() == Code::NotFound => return Ok(None),
+            Err(status) => return Err(status.into()),
+        };
+        TunnelMtuInfo::try_from(info)
+            .map(Some)
+            .map_err(Error::InvalidResponse)
+    }
+
+    /// Return the cryptographic details of the tunnel, or `None` if it is not connected
+    pub async fn get_tunnel_crypto_info(&mut self) -> Result<Option<TunnelCryptoInfo>> {
+        match self.0.get_tunnel_crypto_info(()).await {
+            Ok(info) => Ok(Some(TunnelCryptoInfo::from(info.into_inner()))),
+            Err(status) if status.code() == Code::NotFound => Ok(None),
+            Err(status) => Err(status.into()),
+        }
+    }
+
+    /// Return the allowed IPs that are configured on the tunnel device, or `None` if the tunnel is
+    /// not connected. Changes to the allowed IPs setting are not applied until the tunnel
+    /// reconnects.
+    pub async fn get_applied_allowed_ips(&mut self) -> Result<Option<Vec<ipnetwork::IpNetwork>>> {
+        let allowed_ips = match self.0.get_applied_allowed_ips(()).await {
+            Ok(allowed_ips) => allowed_ips.into_inner(),
+            Err(status) if status.code() == Code::NotFound => return Ok(None),
+            Err(status) => return Err(status.into()),
+        };
+        allowed_ips
+            .values
+            .iter()
+            .map(|ip| {
+                ip.parse().map_err(|_| {
+                    Error::InvalidResponse(types::FromProtobufTypeError::InvalidArgument(
+                        "invalid allowed IP",
+                    ))
+                })
+            })
+            .collect::<Result<_>>()
+            .map(Some)
+    }
+
+    /// Return the most recent daemon log output. If `redact` is set, account numbers, keys, and
+    /// network information are removed.
+    pub async fn collect_logs(&mut self, redact: bool) -> Result<String> {
+        let logs = self.0.collect_logs(redact).await?.into_inner();
+        Ok(logs)
+    }
+
     pub async fn create_custom_list(&mut self, name: String) -> Result<Id> {
         let request = types::NewCustomList {
             name,
@@ -570,6 +1548,23 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    /// Exclude an app from the tunnel, but only for traffic to `subnets`. If `subnets` is empty,
+    /// this is equivalent to [Self::add_split_tunnel_app].
+    pub async fn add_split_tunnel_app_with_subnets<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        subnets: &[ipnetwork::IpNetwork],
+    ) -> Result<()> {
+        let path = path.as_ref().to_str().ok_or(Error::PathMustBeUtf8)?;
+        self.0
+            .add_split_tunnel_app_with_subnets(types::SplitTunnelAppSubnets {
+                app: path.to_owned(),
+                subnets: subnets.iter().map(ToString::to_string).collect(),
+            })
+            .await?;
+        Ok(())
+    }
+
     pub async fn remove_split_tunnel_app<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
         let path = path.as_ref().to_str().ok_or(Error::PathMustBeUtf8)?;
         self.0.remove_split_tunnel_app(path.to_owned()).await?;
@@ -609,6 +1604,28 @@ impl MullvadProxyClient {
         Ok(blob.into_inner())
     }
 
+    /// Store a copy of the settings in a daemon-managed snapshot named `label`
+    pub async fn create_settings_snapshot(&mut self, label: String) -> Result<SettingsSnapshot> {
+        let snapshot = self.0.create_settings_snapshot(label).await?.into_inner();
+        SettingsSnapshot::try_from(snapshot).map_err(Error::InvalidResponse)
+    }
+
+    pub async fn restore_settings_snapshot(&mut self, label: String) -> Result<()> {
+        self.0.restore_settings_snapshot(label).await?;
+        Ok(())
+    }
+
+    pub async fn list_settings_snapshots(&mut self) -> Result<Vec<SettingsSnapshot>> {
+        self.0
+            .list_settings_snapshots(())
+            .await?
+            .into_inner()
+            .snapshots
+            .into_iter()
+            .map(|snapshot| SettingsSnapshot::try_from(snapshot).map_err(Error::InvalidResponse))
+            .collect()
+    }
+
     pub async fn get_feature_indicators(&mut self) -> Result<FeatureIndicators> {
         Ok(FeatureIndicators::from(
             self.0.get_feature_indicators(()).await?.into_inner(),
@@ -654,6 +1671,27 @@ impl MullvadProxyClient {
         Ok(())
     }
 
+    /// Check `allowed_ips` for conflicts with the local network and DNS settings, without
+    /// applying them.
+    pub async fn validate_wireguard_allowed_ips(
+        &mut self,
+        allowed_ips: AllowedIps,
+    ) -> Result<Vec<AllowedIpsWarning>> {
+        let warnings = self
+            .0
+            .validate_wireguard_allowed_ips(types::AllowedIpsList {
+                values: allowed_ips.0.iter().map(ToString::to_string).collect(),
+            })
+            .await?
+            .into_inner();
+        warnings
+            .warnings
+            .into_iter()
+            .map(AllowedIpsWarning::try_from)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(Error::InvalidResponse)
+    }
+
     pub async fn set_log_filter(&mut self, level: String) -> Result<()> {
         self.0.set_log_filter(types::LogFilter::from(level)).await?;
         Ok(())
@@ -664,6 +1702,24 @@ impl MullvadProxyClient {
 
         Ok(listener.map(|item| Ok(item?.message)))
     }
+
+    /// Stream log records at least as severe as `min_level`, or all records if it is `None`, as
+    /// they are emitted by the daemon. Records that are dropped because the client does not keep
+    /// up are reported as [`LogStreamEvent::Dropped`].
+    pub async fn stream_logs(
+        &mut self,
+        min_level: Option<LogLevel>,
+    ) -> Result<impl Stream<Item = Result<LogStreamEvent>>> {
+        let min_level = min_level
+            .map(types::LogLevel::from)
+            .unwrap_or(types::LogLevel::Unspecified);
+        let request = types::StreamLogsRequest {
+            min_level: i32::from(min_level),
+        };
+        let listener = self.0.stream_logs(request).await?.into_inner();
+
+        Ok(listener.map(|item| LogStreamEvent::try_from(item?).map_err(Error::InvalidResponse)))
+    }
 }
 
 #[cfg(not(target_os = "android"))]
@@ -96,6 +96,9 @@ pub enum Error {
     #[error("There is no such device")]
     DeviceNotFound,
 
+    #[error("There is no relay with that hostname")]
+    RelayNotFound,
+
     #[error("Location data is unavailable")]
     NoLocationData,
 
@@ -260,3 +260,124 @@ mod data {
         }
     }
 }
+
+/// Implements conversions for the auxiliary
+/// [`crate::types::proto::AccessMethodTestResult`] type, which carries the
+/// outcome of testing a single access method.
+mod test_result {
+    use crate::types::{FromProtobufTypeError, proto};
+    use mullvad_types::access_method::{AccessMethodTestResult, Id};
+    use std::time::Duration;
+
+    impl From<AccessMethodTestResult> for proto::AccessMethodTestResult {
+        fn from(result: AccessMethodTestResult) -> Self {
+            Self {
+                id: Some(proto::Uuid::from(result.id)),
+                reachable: result.reachable,
+                duration: Some(
+                    prost_types::Duration::try_from(result.duration)
+                        .expect("Failed to convert std::time::Duration to prost_types::Duration"),
+                ),
+                error: result.error,
+            }
+        }
+    }
+
+    impl TryFrom<proto::AccessMethodTestResult> for AccessMethodTestResult {
+        type Error = FromProtobufTypeError;
+
+        fn try_from(value: proto::AccessMethodTestResult) -> Result<Self, Self::Error> {
+            let id = value
+                .id
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "Could not deserialize access method test result from protobuf",
+                ))
+                .and_then(Id::try_from)?;
+            let duration = value
+                .duration
+                .map(Duration::try_from)
+                .transpose()
+                .map_err(|_| FromProtobufTypeError::InvalidArgument("Invalid test duration"))?
+                .unwrap_or_default();
+            Ok(AccessMethodTestResult {
+                id,
+                reachable: value.reachable,
+                duration,
+                error: value.error,
+            })
+        }
+    }
+}
+
+/// Implements conversions for the auxiliary
+/// [`crate::types::proto::AccessMethodSearch`] type.
+mod search {
+    use crate::types::{FromProtobufTypeError, proto};
+    use mullvad_types::access_method::{AccessMethodSearch, AccessMethodTestResult, Id};
+
+    impl From<AccessMethodSearch> for proto::AccessMethodSearch {
+        fn from(search: AccessMethodSearch) -> Self {
+            Self {
+                working: search.working.map(proto::Uuid::from),
+                results: search
+                    .results
+                    .into_iter()
+                    .map(proto::AccessMethodTestResult::from)
+                    .collect(),
+            }
+        }
+    }
+
+    impl TryFrom<proto::AccessMethodSearch> for AccessMethodSearch {
+        type Error = FromProtobufTypeError;
+
+        fn try_from(value: proto::AccessMethodSearch) -> Result<Self, Self::Error> {
+            let working = value.working.map(Id::try_from).transpose()?;
+            let results = value
+                .results
+                .into_iter()
+                .map(AccessMethodTestResult::try_from)
+                .collect::<Result<_, _>>()?;
+            Ok(AccessMethodSearch { working, results })
+        }
+    }
+}
+
+/// Implements conversions for the auxiliary
+/// [`crate::types::proto::LastSuccessfulAccessMethod`] type.
+mod last_success {
+    use crate::types::{FromProtobufTypeError, proto};
+    use chrono::DateTime;
+    use mullvad_types::access_method::{Id, LastSuccessfulAccessMethod};
+
+    impl From<LastSuccessfulAccessMethod> for proto::LastSuccessfulAccessMethod {
+        fn from(value: LastSuccessfulAccessMethod) -> Self {
+            Self {
+                id: Some(proto::Uuid::from(value.id)),
+                timestamp: Some(prost_types::Timestamp {
+                    seconds: value.timestamp.timestamp(),
+                    nanos: 0,
+                }),
+            }
+        }
+    }
+
+    impl TryFrom<proto::LastSuccessfulAccessMethod> for LastSuccessfulAccessMethod {
+        type Error = FromProtobufTypeError;
+
+        fn try_from(value: proto::LastSuccessfulAccessMethod) -> Result<Self, Self::Error> {
+            let id = value
+                .id
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "missing access method id",
+                ))
+                .and_then(Id::try_from)?;
+            let timestamp = value
+                .timestamp
+                .ok_or(FromProtobufTypeError::InvalidArgument("missing timestamp"))?;
+            let timestamp = DateTime::from_timestamp(timestamp.seconds, timestamp.nanos as u32)
+                .ok_or(FromProtobufTypeError::InvalidArgument("invalid timestamp"))?;
+            Ok(LastSuccessfulAccessMethod { id, timestamp })
+        }
+    }
+}
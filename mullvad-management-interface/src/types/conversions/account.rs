@@ -1,6 +1,6 @@
 use crate::types;
 use chrono::DateTime;
-use mullvad_types::account::{AccountData, VoucherSubmission};
+use mullvad_types::account::{AccountData, LoginBackoff, VoucherSubmission};
 #[cfg(target_os = "android")]
 use mullvad_types::account::{PlayPurchase, PlayPurchasePaymentToken};
 
@@ -36,6 +36,37 @@ impl TryFrom<types::VoucherSubmission> for VoucherSubmission {
     }
 }
 
+impl From<LoginBackoff> for types::LoginBackoff {
+    fn from(backoff: LoginBackoff) -> Self {
+        types::LoginBackoff {
+            consecutive_failures: backoff.consecutive_failures,
+            next_attempt: backoff.next_attempt.map(|next_attempt| types::Timestamp {
+                seconds: next_attempt.timestamp(),
+                nanos: 0,
+            }),
+        }
+    }
+}
+
+impl TryFrom<types::LoginBackoff> for LoginBackoff {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(backoff: types::LoginBackoff) -> Result<Self, FromProtobufTypeError> {
+        let next_attempt = backoff
+            .next_attempt
+            .map(|next_attempt| {
+                DateTime::from_timestamp(next_attempt.seconds, next_attempt.nanos as u32)
+                    .ok_or(FromProtobufTypeError::InvalidArgument("invalid timestamp"))
+            })
+            .transpose()?;
+
+        Ok(LoginBackoff {
+            consecutive_failures: backoff.consecutive_failures,
+            next_attempt,
+        })
+    }
+}
+
 impl From<AccountData> for types::AccountData {
     fn from(data: AccountData) -> Self {
         types::AccountData {
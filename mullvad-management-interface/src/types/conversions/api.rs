@@ -0,0 +1,119 @@
+use crate::types::{FromProtobufTypeError, proto};
+use mullvad_types::api::{
+    ApiAddressInfo, ApiAddressSource, ApiErrorResponse, ApiRequestLimit, InflightApiRequest,
+};
+
+impl From<InflightApiRequest> for proto::InflightApiRequest {
+    fn from(request: InflightApiRequest) -> Self {
+        proto::InflightApiRequest {
+            method: request.method,
+            path: request.path,
+            elapsed: Some(
+                prost_types::Duration::try_from(request.elapsed)
+                    .expect("Failed to convert std::time::Duration to prost_types::Duration"),
+            ),
+        }
+    }
+}
+
+impl TryFrom<proto::InflightApiRequest> for InflightApiRequest {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(request: proto::InflightApiRequest) -> Result<Self, Self::Error> {
+        let elapsed = request
+            .elapsed
+            .ok_or(FromProtobufTypeError::InvalidArgument(
+                "missing elapsed time",
+            ))
+            .and_then(|elapsed| {
+                std::time::Duration::try_from(elapsed)
+                    .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid elapsed time"))
+            })?;
+
+        Ok(InflightApiRequest {
+            method: request.method,
+            path: request.path,
+            elapsed,
+        })
+    }
+}
+
+impl From<ApiErrorResponse> for proto::ApiErrorResponse {
+    fn from(error: ApiErrorResponse) -> Self {
+        proto::ApiErrorResponse {
+            method: error.method,
+            path: error.path,
+            status: u32::from(error.status),
+            body: error.body,
+        }
+    }
+}
+
+impl TryFrom<proto::ApiErrorResponse> for ApiErrorResponse {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(error: proto::ApiErrorResponse) -> Result<Self, Self::Error> {
+        let status = u16::try_from(error.status)
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid HTTP status code"))?;
+        Ok(ApiErrorResponse {
+            method: error.method,
+            path: error.path,
+            status,
+            body: error.body,
+        })
+    }
+}
+
+impl From<ApiRequestLimit> for proto::ApiRequestLimit {
+    fn from(limit: ApiRequestLimit) -> Self {
+        proto::ApiRequestLimit {
+            max_concurrent_requests: limit.max_concurrent_requests,
+            queued_requests: limit.queued_requests,
+        }
+    }
+}
+
+impl From<proto::ApiRequestLimit> for ApiRequestLimit {
+    fn from(limit: proto::ApiRequestLimit) -> Self {
+        ApiRequestLimit {
+            max_concurrent_requests: limit.max_concurrent_requests,
+            queued_requests: limit.queued_requests,
+        }
+    }
+}
+
+impl From<ApiAddressInfo> for proto::ApiAddressInfo {
+    fn from(info: ApiAddressInfo) -> Self {
+        let source = match info.source {
+            ApiAddressSource::Bundled => proto::api_address_info::Source::Bundled,
+            ApiAddressSource::Cached => proto::api_address_info::Source::Cached,
+            ApiAddressSource::Override => proto::api_address_info::Source::Override,
+        };
+        proto::ApiAddressInfo {
+            address: info.address.to_string(),
+            source: i32::from(source),
+        }
+    }
+}
+
+impl TryFrom<proto::ApiAddressInfo> for ApiAddressInfo {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(info: proto::ApiAddressInfo) -> Result<Self, Self::Error> {
+        let address = info
+            .address
+            .parse()
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid API address"))?;
+        let source = match proto::api_address_info::Source::try_from(info.source) {
+            Ok(proto::api_address_info::Source::Bundled) => ApiAddressSource::Bundled,
+            Ok(proto::api_address_info::Source::Cached) => ApiAddressSource::Cached,
+            Ok(proto::api_address_info::Source::Override) => ApiAddressSource::Override,
+            Err(_) => {
+                return Err(FromProtobufTypeError::InvalidArgument(
+                    "invalid API address source",
+                ));
+            }
+        };
+        Ok(ApiAddressInfo { address, source })
+    }
+}
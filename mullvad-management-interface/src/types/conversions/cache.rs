@@ -0,0 +1,62 @@
+use crate::types::{FromProtobufTypeError, proto};
+use mullvad_types::cache::{CacheKind, CacheUsage};
+
+impl From<CacheUsage> for proto::CacheUsage {
+    fn from(usage: CacheUsage) -> Self {
+        proto::CacheUsage {
+            relay_list: usage.relay_list,
+            api_address: usage.api_address,
+            installers: usage.installers,
+            logs: usage.logs,
+        }
+    }
+}
+
+impl From<proto::CacheUsage> for CacheUsage {
+    fn from(usage: proto::CacheUsage) -> Self {
+        CacheUsage {
+            relay_list: usage.relay_list,
+            api_address: usage.api_address,
+            installers: usage.installers,
+            logs: usage.logs,
+        }
+    }
+}
+
+impl From<&[CacheKind]> for proto::CacheKinds {
+    fn from(kinds: &[CacheKind]) -> Self {
+        use proto::cache_kinds::CacheKind as ProtoKind;
+
+        let kinds = kinds
+            .iter()
+            .map(|kind| match kind {
+                CacheKind::RelayList => ProtoKind::RelayList,
+                CacheKind::ApiAddress => ProtoKind::ApiAddress,
+                CacheKind::Installers => ProtoKind::Installers,
+                CacheKind::Logs => ProtoKind::Logs,
+            })
+            .map(i32::from)
+            .collect();
+        proto::CacheKinds { kinds }
+    }
+}
+
+impl TryFrom<proto::CacheKinds> for Vec<CacheKind> {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(kinds: proto::CacheKinds) -> Result<Self, Self::Error> {
+        use proto::cache_kinds::CacheKind as ProtoKind;
+
+        kinds
+            .kinds
+            .into_iter()
+            .map(|kind| match ProtoKind::try_from(kind) {
+                Ok(ProtoKind::RelayList) => Ok(CacheKind::RelayList),
+                Ok(ProtoKind::ApiAddress) => Ok(CacheKind::ApiAddress),
+                Ok(ProtoKind::Installers) => Ok(CacheKind::Installers),
+                Ok(ProtoKind::Logs) => Ok(CacheKind::Logs),
+                Err(_) => Err(FromProtobufTypeError::InvalidArgument("invalid cache kind")),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,33 @@
+use crate::types::{FromProtobufTypeError, proto};
+use chrono::TimeDelta;
+use mullvad_types::clock::ClockCheck;
+
+impl From<ClockCheck> for proto::ClockCheck {
+    fn from(check: ClockCheck) -> Self {
+        proto::ClockCheck {
+            offset: Some(prost_types::Duration {
+                seconds: check.offset.num_seconds(),
+                nanos: 0,
+            }),
+            within_tolerance: check.within_tolerance,
+        }
+    }
+}
+
+impl TryFrom<proto::ClockCheck> for ClockCheck {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(check: proto::ClockCheck) -> Result<Self, Self::Error> {
+        let offset = check
+            .offset
+            .and_then(|offset| TimeDelta::try_seconds(offset.seconds))
+            .ok_or(FromProtobufTypeError::InvalidArgument(
+                "invalid clock offset",
+            ))?;
+
+        Ok(ClockCheck {
+            offset,
+            within_tolerance: check.within_tolerance,
+        })
+    }
+}
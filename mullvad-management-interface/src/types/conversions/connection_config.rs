@@ -0,0 +1,56 @@
+use crate::types::{FromProtobufTypeError, conversions::arg_from_str, proto};
+use mullvad_types::{connection_config::EffectiveConnectionConfig, settings::EffectiveDnsServer};
+
+impl From<EffectiveConnectionConfig> for proto::EffectiveConnectionConfig {
+    fn from(config: EffectiveConnectionConfig) -> Self {
+        proto::EffectiveConnectionConfig {
+            endpoint: Some(proto::TunnelEndpoint::from(config.endpoint)),
+            location: config.location.map(proto::GeoIpLocation::from),
+            allowed_ips: config.allowed_ips.iter().map(ToString::to_string).collect(),
+            mtu: config.mtu.map(u32::from),
+            dns_servers: config
+                .dns_servers
+                .into_iter()
+                .map(proto::EffectiveDnsServer::from)
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::EffectiveConnectionConfig> for EffectiveConnectionConfig {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(config: proto::EffectiveConnectionConfig) -> Result<Self, Self::Error> {
+        let endpoint = config
+            .endpoint
+            .ok_or(FromProtobufTypeError::InvalidArgument(
+                "missing tunnel endpoint",
+            ))?;
+        let mtu = config
+            .mtu
+            .map(|mtu| {
+                u16::try_from(mtu)
+                    .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid MTU"))
+            })
+            .transpose()?;
+
+        Ok(EffectiveConnectionConfig {
+            endpoint: talpid_types::net::TunnelEndpoint::try_from(endpoint)?,
+            location: config
+                .location
+                .map(mullvad_types::location::GeoIpLocation::try_from)
+                .transpose()?,
+            allowed_ips: config
+                .allowed_ips
+                .iter()
+                .map(|network| arg_from_str(network, "invalid allowed IP"))
+                .collect::<Result<_, _>>()?,
+            mtu,
+            dns_servers: config
+                .dns_servers
+                .into_iter()
+                .map(EffectiveDnsServer::try_from)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
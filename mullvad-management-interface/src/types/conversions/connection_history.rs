@@ -0,0 +1,168 @@
+use crate::types::{FromProtobufTypeError, proto};
+use chrono::DateTime;
+use mullvad_types::connection_history::{
+    ConnectionAttempt, ConnectionAttemptOutcome, NetworkProbeResults, ProtocolReachability,
+};
+
+impl From<ConnectionAttempt> for proto::ConnectionAttempt {
+    fn from(attempt: ConnectionAttempt) -> Self {
+        use proto::connection_attempt::Outcome;
+
+        let (outcome, failure_reason) = match attempt.outcome {
+            ConnectionAttemptOutcome::Pending => (Outcome::Pending, None),
+            ConnectionAttemptOutcome::Connected => (Outcome::Connected, None),
+            ConnectionAttemptOutcome::Superseded => (Outcome::Superseded, None),
+            ConnectionAttemptOutcome::Aborted => (Outcome::Aborted, None),
+            ConnectionAttemptOutcome::Failed(reason) => (Outcome::Failed, Some(reason)),
+        };
+        proto::ConnectionAttempt {
+            started: Some(prost_types::Timestamp {
+                seconds: attempt.started.timestamp(),
+                nanos: 0,
+            }),
+            relay_info: Some(proto::TunnelStateRelayInfo {
+                tunnel_endpoint: Some(proto::TunnelEndpoint::from(attempt.endpoint)),
+                location: attempt.location.map(proto::GeoIpLocation::from),
+            }),
+            outcome: i32::from(outcome),
+            failure_reason,
+        }
+    }
+}
+
+impl TryFrom<proto::ConnectionAttempt> for ConnectionAttempt {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(attempt: proto::ConnectionAttempt) -> Result<Self, Self::Error> {
+        use proto::connection_attempt::Outcome;
+
+        let started = attempt
+            .started
+            .ok_or(FromProtobufTypeError::InvalidArgument(
+                "missing 'started' timestamp",
+            ))?;
+        let started = DateTime::from_timestamp(started.seconds, started.nanos as u32)
+            .ok_or(FromProtobufTypeError::InvalidArgument("invalid timestamp"))?;
+
+        let Some(proto::TunnelStateRelayInfo {
+            tunnel_endpoint: Some(tunnel_endpoint),
+            location,
+        }) = attempt.relay_info
+        else {
+            return Err(FromProtobufTypeError::InvalidArgument(
+                "missing tunnel endpoint",
+            ));
+        };
+
+        let outcome = match Outcome::try_from(attempt.outcome) {
+            Ok(Outcome::Pending) => ConnectionAttemptOutcome::Pending,
+            Ok(Outcome::Connected) => ConnectionAttemptOutcome::Connected,
+            Ok(Outcome::Superseded) => ConnectionAttemptOutcome::Superseded,
+            Ok(Outcome::Aborted) => ConnectionAttemptOutcome::Aborted,
+            Ok(Outcome::Failed) => {
+                ConnectionAttemptOutcome::Failed(attempt.failure_reason.unwrap_or_default())
+            }
+            Err(_) => {
+                return Err(FromProtobufTypeError::InvalidArgument(
+                    "invalid connection attempt outcome",
+                ));
+            }
+        };
+
+        Ok(ConnectionAttempt {
+            started,
+            endpoint: talpid_types::net::TunnelEndpoint::try_from(tunnel_endpoint)?,
+            location: location
+                .map(mullvad_types::location::GeoIpLocation::try_from)
+                .transpose()?,
+            outcome,
+        })
+    }
+}
+
+impl From<ProtocolReachability> for proto::ProtocolReachability {
+    fn from(reachability: ProtocolReachability) -> Self {
+        proto::ProtocolReachability {
+            reachable: reachability.reachable,
+            reachable_ports: reachability
+                .reachable_ports
+                .into_iter()
+                .map(u32::from)
+                .collect(),
+            unreachable_ports: reachability
+                .unreachable_ports
+                .into_iter()
+                .map(u32::from)
+                .collect(),
+            connected: reachability.connected,
+            superseded: reachability.superseded,
+            failed: reachability.failed,
+        }
+    }
+}
+
+impl TryFrom<proto::ProtocolReachability> for ProtocolReachability {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(reachability: proto::ProtocolReachability) -> Result<Self, Self::Error> {
+        let ports = |ports: Vec<u32>| {
+            ports
+                .into_iter()
+                .map(|port| {
+                    u16::try_from(port)
+                        .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid port"))
+                })
+                .collect()
+        };
+        Ok(ProtocolReachability {
+            reachable: reachability.reachable,
+            reachable_ports: ports(reachability.reachable_ports)?,
+            unreachable_ports: ports(reachability.unreachable_ports)?,
+            connected: reachability.connected,
+            superseded: reachability.superseded,
+            failed: reachability.failed,
+        })
+    }
+}
+
+impl From<NetworkProbeResults> for proto::NetworkProbeResults {
+    fn from(results: NetworkProbeResults) -> Self {
+        proto::NetworkProbeResults {
+            udp: Some(proto::ProtocolReachability::from(results.udp)),
+            tcp: Some(proto::ProtocolReachability::from(results.tcp)),
+            quic: Some(proto::ProtocolReachability::from(results.quic)),
+            lwo: Some(proto::ProtocolReachability::from(results.lwo)),
+            last_probe: results.last_probe.map(|last_probe| prost_types::Timestamp {
+                seconds: last_probe.timestamp(),
+                nanos: 0,
+            }),
+        }
+    }
+}
+
+impl TryFrom<proto::NetworkProbeResults> for NetworkProbeResults {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(results: proto::NetworkProbeResults) -> Result<Self, Self::Error> {
+        let last_probe = results
+            .last_probe
+            .map(|last_probe| {
+                DateTime::from_timestamp(last_probe.seconds, last_probe.nanos as u32)
+                    .ok_or(FromProtobufTypeError::InvalidArgument("invalid timestamp"))
+            })
+            .transpose()?;
+        let reachability = |reachability: Option<proto::ProtocolReachability>| {
+            reachability
+                .map(ProtocolReachability::try_from)
+                .transpose()
+                .map(Option::unwrap_or_default)
+        };
+        Ok(NetworkProbeResults {
+            udp: reachability(results.udp)?,
+            tcp: reachability(results.tcp)?,
+            quic: reachability(results.quic)?,
+            lwo: reachability(results.lwo)?,
+            last_probe,
+        })
+    }
+}
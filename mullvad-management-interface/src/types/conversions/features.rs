@@ -1,5 +1,84 @@
 use crate::types::proto;
 
+impl From<mullvad_types::features::SupportedFeatures> for proto::SupportedFeatures {
+    fn from(features: mullvad_types::features::SupportedFeatures) -> Self {
+        use mullvad_types::features::SplitTunnelKind;
+        use proto::obfuscation_endpoint::ObfuscationType;
+        use proto::supported_features::SplitTunnelKind as ProtoSplitTunnelKind;
+        use talpid_types::net::ObfuscationType as TalpidObfuscationType;
+
+        let split_tunnel = match features.split_tunnel {
+            None => ProtoSplitTunnelKind::None,
+            Some(SplitTunnelKind::ProcessIds) => ProtoSplitTunnelKind::ProcessIds,
+            Some(SplitTunnelKind::AppPaths) => ProtoSplitTunnelKind::AppPaths,
+            Some(SplitTunnelKind::AppPackages) => ProtoSplitTunnelKind::AppPackages,
+        };
+        let obfuscation = features
+            .obfuscation
+            .into_iter()
+            .map(|obfuscation| match obfuscation {
+                TalpidObfuscationType::Udp2Tcp => ObfuscationType::Udp2tcp,
+                TalpidObfuscationType::Shadowsocks => ObfuscationType::Shadowsocks,
+                TalpidObfuscationType::Quic => ObfuscationType::Quic,
+                TalpidObfuscationType::Lwo => ObfuscationType::Lwo,
+            })
+            .map(i32::from)
+            .collect();
+
+        proto::SupportedFeatures {
+            daita: features.daita,
+            split_tunnel: i32::from(split_tunnel),
+            obfuscation,
+            quantum_resistant: features.quantum_resistant,
+        }
+    }
+}
+
+/// Unknown values, e.g. ones added in a newer daemon, are logged and treated as unsupported.
+impl From<proto::SupportedFeatures> for mullvad_types::features::SupportedFeatures {
+    fn from(features: proto::SupportedFeatures) -> Self {
+        use mullvad_types::features::SplitTunnelKind;
+        use proto::obfuscation_endpoint::ObfuscationType;
+        use proto::supported_features::SplitTunnelKind as ProtoSplitTunnelKind;
+        use talpid_types::net::ObfuscationType as TalpidObfuscationType;
+
+        let split_tunnel = match ProtoSplitTunnelKind::try_from(features.split_tunnel) {
+            Ok(ProtoSplitTunnelKind::None) => None,
+            Ok(ProtoSplitTunnelKind::ProcessIds) => Some(SplitTunnelKind::ProcessIds),
+            Ok(ProtoSplitTunnelKind::AppPaths) => Some(SplitTunnelKind::AppPaths),
+            Ok(ProtoSplitTunnelKind::AppPackages) => Some(SplitTunnelKind::AppPackages),
+            Err(_) => {
+                log::warn!(
+                    "Unknown split tunnel kind {}, assuming split tunneling is unsupported",
+                    features.split_tunnel
+                );
+                None
+            }
+        };
+        let obfuscation = features
+            .obfuscation
+            .into_iter()
+            .filter_map(|obfuscation| match ObfuscationType::try_from(obfuscation) {
+                Ok(ObfuscationType::Udp2tcp) => Some(TalpidObfuscationType::Udp2Tcp),
+                Ok(ObfuscationType::Shadowsocks) => Some(TalpidObfuscationType::Shadowsocks),
+                Ok(ObfuscationType::Quic) => Some(TalpidObfuscationType::Quic),
+                Ok(ObfuscationType::Lwo) => Some(TalpidObfuscationType::Lwo),
+                Err(_) => {
+                    log::warn!("Ignoring unknown obfuscation type {obfuscation}");
+                    None
+                }
+            })
+            .collect();
+
+        mullvad_types::features::SupportedFeatures {
+            daita: features.daita,
+            split_tunnel,
+            obfuscation,
+            quantum_resistant: features.quantum_resistant,
+        }
+    }
+}
+
 impl From<mullvad_types::features::FeatureIndicator> for proto::FeatureIndicator {
     fn from(feature: mullvad_types::features::FeatureIndicator) -> Self {
         use proto::FeatureIndicator::*;
@@ -68,3 +147,44 @@ impl From<mullvad_types::features::FeatureIndicators> for proto::FeatureIndicato
         proto_features
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mullvad_types::features::{SplitTunnelKind, SupportedFeatures};
+    use talpid_types::net::ObfuscationType;
+
+    /// Values unknown to an older client must not make the conversion fail
+    #[test]
+    fn test_unknown_supported_features() {
+        let features = proto::SupportedFeatures {
+            daita: true,
+            split_tunnel: i32::MAX,
+            obfuscation: vec![
+                i32::from(proto::obfuscation_endpoint::ObfuscationType::Quic),
+                i32::MAX,
+            ],
+            quantum_resistant: true,
+        };
+        assert_eq!(
+            SupportedFeatures::from(features),
+            SupportedFeatures {
+                daita: true,
+                split_tunnel: None,
+                obfuscation: vec![ObfuscationType::Quic],
+                quantum_resistant: true,
+            }
+        );
+
+        let features = SupportedFeatures {
+            daita: false,
+            split_tunnel: Some(SplitTunnelKind::AppPaths),
+            obfuscation: vec![ObfuscationType::Udp2Tcp, ObfuscationType::Lwo],
+            quantum_resistant: false,
+        };
+        assert_eq!(
+            SupportedFeatures::from(proto::SupportedFeatures::from(features.clone())),
+            features
+        );
+    }
+}
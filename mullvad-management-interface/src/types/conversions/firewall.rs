@@ -0,0 +1,43 @@
+use crate::types::{FromProtobufTypeError, proto};
+use talpid_types::firewall::{FirewallBackend, FirewallInfo};
+
+impl From<FirewallInfo> for proto::FirewallInfo {
+    fn from(info: FirewallInfo) -> Self {
+        use proto::firewall_info::Backend;
+
+        let backend = match info.backend {
+            FirewallBackend::Nftables => Backend::Nftables,
+            FirewallBackend::Pf => Backend::Pf,
+            FirewallBackend::Wfp => Backend::Wfp,
+            FirewallBackend::None => Backend::None,
+        };
+        proto::FirewallInfo {
+            backend: i32::from(backend),
+            degraded: info.degraded,
+        }
+    }
+}
+
+impl TryFrom<proto::FirewallInfo> for FirewallInfo {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(info: proto::FirewallInfo) -> Result<Self, Self::Error> {
+        use proto::firewall_info::Backend;
+
+        let backend = match Backend::try_from(info.backend) {
+            Ok(Backend::Nftables) => FirewallBackend::Nftables,
+            Ok(Backend::Pf) => FirewallBackend::Pf,
+            Ok(Backend::Wfp) => FirewallBackend::Wfp,
+            Ok(Backend::None) => FirewallBackend::None,
+            Err(_) => {
+                return Err(FromProtobufTypeError::InvalidArgument(
+                    "invalid firewall backend",
+                ));
+            }
+        };
+        Ok(FirewallInfo {
+            backend,
+            degraded: info.degraded,
+        })
+    }
+}
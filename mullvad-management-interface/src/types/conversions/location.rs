@@ -41,3 +41,31 @@ impl TryFrom<proto::GeoIpLocation> for mullvad_types::location::GeoIpLocation {
         })
     }
 }
+
+impl From<mullvad_types::location::DnsLeakResult> for proto::DnsLeakResult {
+    fn from(result: mullvad_types::location::DnsLeakResult) -> Self {
+        proto::DnsLeakResult {
+            leaking: result.leaking,
+            resolvers: result
+                .resolvers
+                .iter()
+                .map(|resolver| resolver.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::DnsLeakResult> for mullvad_types::location::DnsLeakResult {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(result: proto::DnsLeakResult) -> Result<Self, Self::Error> {
+        Ok(mullvad_types::location::DnsLeakResult {
+            leaking: result.leaking,
+            resolvers: result
+                .resolvers
+                .iter()
+                .map(|resolver| arg_from_str(resolver, "invalid resolver address"))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
@@ -1,4 +1,6 @@
-use crate::types::proto;
+use crate::types::{FromProtobufTypeError, proto};
+use chrono::DateTime;
+use mullvad_types::logging::{LogLevel, LogRecord, LogStreamEvent};
 
 impl From<String> for proto::LogFilter {
     fn from(log_filter: String) -> proto::LogFilter {
@@ -11,3 +13,129 @@ impl From<proto::LogFilter> for String {
         value.log_filter
     }
 }
+
+impl From<LogLevel> for proto::LogLevel {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => proto::LogLevel::Error,
+            LogLevel::Warn => proto::LogLevel::Warn,
+            LogLevel::Info => proto::LogLevel::Info,
+            LogLevel::Debug => proto::LogLevel::Debug,
+            LogLevel::Trace => proto::LogLevel::Trace,
+        }
+    }
+}
+
+impl TryFrom<proto::LogLevel> for LogLevel {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(level: proto::LogLevel) -> Result<Self, Self::Error> {
+        match level {
+            proto::LogLevel::Unspecified => Err(FromProtobufTypeError::InvalidArgument(
+                "unspecified log level",
+            )),
+            proto::LogLevel::Error => Ok(LogLevel::Error),
+            proto::LogLevel::Warn => Ok(LogLevel::Warn),
+            proto::LogLevel::Info => Ok(LogLevel::Info),
+            proto::LogLevel::Debug => Ok(LogLevel::Debug),
+            proto::LogLevel::Trace => Ok(LogLevel::Trace),
+        }
+    }
+}
+
+impl From<LogRecord> for proto::LogRecord {
+    fn from(record: LogRecord) -> Self {
+        proto::LogRecord {
+            level: i32::from(proto::LogLevel::from(record.level)),
+            target: record.target,
+            message: record.message,
+            timestamp: Some(prost_types::Timestamp {
+                seconds: record.timestamp.timestamp(),
+                nanos: record.timestamp.timestamp_subsec_nanos() as i32,
+            }),
+        }
+    }
+}
+
+impl TryFrom<proto::LogRecord> for LogRecord {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(record: proto::LogRecord) -> Result<Self, Self::Error> {
+        let timestamp = record
+            .timestamp
+            .ok_or(FromProtobufTypeError::InvalidArgument("missing timestamp"))?;
+        let timestamp = DateTime::from_timestamp(timestamp.seconds, timestamp.nanos as u32)
+            .ok_or(FromProtobufTypeError::InvalidArgument("invalid timestamp"))?;
+
+        let level = proto::LogLevel::try_from(record.level)
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid log level"))?;
+
+        Ok(LogRecord {
+            level: LogLevel::try_from(level)?,
+            target: record.target,
+            message: record.message,
+            timestamp,
+        })
+    }
+}
+
+impl From<LogStreamEvent> for proto::LogStreamEvent {
+    fn from(event: LogStreamEvent) -> Self {
+        type ProtoEvent = proto::log_stream_event::Event;
+
+        let event = match event {
+            LogStreamEvent::Record(record) => ProtoEvent::Record(record.into()),
+            LogStreamEvent::Dropped(count) => ProtoEvent::DroppedRecords(count),
+        };
+        Self { event: Some(event) }
+    }
+}
+
+impl TryFrom<proto::LogStreamEvent> for LogStreamEvent {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(event: proto::LogStreamEvent) -> Result<Self, Self::Error> {
+        type ProtoEvent = proto::log_stream_event::Event;
+
+        let event = event.event.ok_or(FromProtobufTypeError::InvalidArgument(
+            "Non-existent LogStreamEvent",
+        ))?;
+        match event {
+            ProtoEvent::Record(record) => Ok(LogStreamEvent::Record(record.try_into()?)),
+            ProtoEvent::DroppedRecords(count) => Ok(LogStreamEvent::Dropped(count)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record() -> LogRecord {
+        LogRecord {
+            level: LogLevel::Warn,
+            target: "mullvad_daemon".to_owned(),
+            message: "message".to_owned(),
+            timestamp: DateTime::from_timestamp(1_700_000_000, 123_000_000).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_log_stream_event_round_trip() {
+        for event in [LogStreamEvent::Record(record()), LogStreamEvent::Dropped(3)] {
+            let converted =
+                LogStreamEvent::try_from(proto::LogStreamEvent::from(event.clone())).unwrap();
+            assert_eq!(converted, event);
+        }
+    }
+
+    /// Records must have a known level
+    #[test]
+    fn test_record_without_level() {
+        let mut record = proto::LogRecord::from(record());
+        record.level = i32::from(proto::LogLevel::Unspecified);
+        assert!(LogRecord::try_from(record.clone()).is_err());
+        record.level = i32::MAX;
+        assert!(LogRecord::try_from(record).is_err());
+    }
+}
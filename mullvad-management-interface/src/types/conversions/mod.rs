@@ -2,19 +2,32 @@ use std::str::FromStr;
 
 mod access_method;
 mod account;
+mod api;
+mod cache;
+mod clock;
+mod connection_config;
+mod connection_history;
 mod custom_list;
 mod custom_tunnel;
 mod device;
 mod features;
+mod firewall;
 mod location;
 mod logging;
 mod net;
+mod network_simulation;
+mod notification;
+mod profile;
 pub mod relay_constraints;
 mod relay_list;
+mod resource_usage;
+mod schedule;
 mod settings;
 #[cfg(target_os = "windows")]
 mod split_tunnel;
 mod states;
+mod trusted_network;
+mod usage_stats;
 mod version;
 mod wireguard;
 
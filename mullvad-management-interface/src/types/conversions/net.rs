@@ -70,24 +70,43 @@ impl From<talpid_types::net::ObfuscationEndpoint> for proto::ObfuscationEndpoint
     fn from(endpoint: talpid_types::net::ObfuscationEndpoint) -> Self {
         proto::ObfuscationEndpoint {
             endpoint: Some(proto::Endpoint::from(endpoint.endpoint)),
-            obfuscation_type: match endpoint.obfuscation_type {
-                talpid_types::net::ObfuscationType::Udp2Tcp => {
-                    i32::from(proto::obfuscation_endpoint::ObfuscationType::Udp2tcp)
-                }
-                talpid_types::net::ObfuscationType::Shadowsocks => {
-                    i32::from(proto::obfuscation_endpoint::ObfuscationType::Shadowsocks)
-                }
-                talpid_types::net::ObfuscationType::Quic => {
-                    i32::from(proto::obfuscation_endpoint::ObfuscationType::Quic)
-                }
-                talpid_types::net::ObfuscationType::Lwo => {
-                    i32::from(proto::obfuscation_endpoint::ObfuscationType::Lwo)
-                }
-            },
+            obfuscation_type: i32::from(proto::obfuscation_endpoint::ObfuscationType::from(
+                endpoint.obfuscation_type,
+            )),
+        }
+    }
+}
+
+impl From<talpid_types::net::ObfuscationType> for proto::obfuscation_endpoint::ObfuscationType {
+    fn from(obfuscation_type: talpid_types::net::ObfuscationType) -> Self {
+        match obfuscation_type {
+            talpid_types::net::ObfuscationType::Udp2Tcp => Self::Udp2tcp,
+            talpid_types::net::ObfuscationType::Shadowsocks => Self::Shadowsocks,
+            talpid_types::net::ObfuscationType::Quic => Self::Quic,
+            talpid_types::net::ObfuscationType::Lwo => Self::Lwo,
         }
     }
 }
 
+/// Convert an `i32` representation of [proto::obfuscation_endpoint::ObfuscationType]
+pub(super) fn try_obfuscation_type_from_i32(
+    obfuscation_type: i32,
+) -> Result<talpid_types::net::ObfuscationType, FromProtobufTypeError> {
+    use talpid_types::net::ObfuscationType;
+
+    match proto::obfuscation_endpoint::ObfuscationType::try_from(obfuscation_type) {
+        Ok(proto::obfuscation_endpoint::ObfuscationType::Udp2tcp) => Ok(ObfuscationType::Udp2Tcp),
+        Ok(proto::obfuscation_endpoint::ObfuscationType::Shadowsocks) => {
+            Ok(ObfuscationType::Shadowsocks)
+        }
+        Ok(proto::obfuscation_endpoint::ObfuscationType::Quic) => Ok(ObfuscationType::Quic),
+        Ok(proto::obfuscation_endpoint::ObfuscationType::Lwo) => Ok(ObfuscationType::Lwo),
+        Err(_) => Err(FromProtobufTypeError::InvalidArgument(
+            "unknown obfuscation type",
+        )),
+    }
+}
+
 impl TryFrom<proto::ObfuscationEndpoint> for talpid_types::net::ObfuscationEndpoint {
     type Error = FromProtobufTypeError;
 
@@ -98,27 +117,7 @@ impl TryFrom<proto::ObfuscationEndpoint> for talpid_types::net::ObfuscationEndpo
             endpoint: talpid_net::Endpoint::try_from(endpoint.endpoint.ok_or(
                 FromProtobufTypeError::InvalidArgument("missing obfuscation endpoint"),
             )?)?,
-            obfuscation_type: match proto::obfuscation_endpoint::ObfuscationType::try_from(
-                endpoint.obfuscation_type,
-            ) {
-                Ok(proto::obfuscation_endpoint::ObfuscationType::Udp2tcp) => {
-                    talpid_net::ObfuscationType::Udp2Tcp
-                }
-                Ok(proto::obfuscation_endpoint::ObfuscationType::Shadowsocks) => {
-                    talpid_net::ObfuscationType::Shadowsocks
-                }
-                Ok(proto::obfuscation_endpoint::ObfuscationType::Quic) => {
-                    talpid_net::ObfuscationType::Quic
-                }
-                Ok(proto::obfuscation_endpoint::ObfuscationType::Lwo) => {
-                    talpid_net::ObfuscationType::Lwo
-                }
-                Err(_) => {
-                    return Err(FromProtobufTypeError::InvalidArgument(
-                        "unknown obfuscation type",
-                    ));
-                }
-            },
+            obfuscation_type: try_obfuscation_type_from_i32(endpoint.obfuscation_type)?,
         })
     }
 }
@@ -394,3 +393,103 @@ mod proxy {
         }
     }
 }
+
+impl From<talpid_types::net::NetworkInterface> for proto::NetworkInterface {
+    fn from(interface: talpid_types::net::NetworkInterface) -> Self {
+        proto::NetworkInterface {
+            name: interface.name,
+            addresses: interface
+                .addresses
+                .iter()
+                .map(|address| address.to_string())
+                .collect(),
+            up: interface.up,
+            default_route: interface.default_route,
+        }
+    }
+}
+
+impl TryFrom<proto::NetworkInterface> for talpid_types::net::NetworkInterface {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(interface: proto::NetworkInterface) -> Result<Self, Self::Error> {
+        Ok(talpid_types::net::NetworkInterface {
+            name: interface.name,
+            addresses: interface
+                .addresses
+                .iter()
+                .map(|address| arg_from_str(address, "invalid interface address"))
+                .collect::<Result<_, _>>()?,
+            up: interface.up,
+            default_route: interface.default_route,
+        })
+    }
+}
+
+impl From<talpid_types::net::InstalledRoute> for proto::InstalledRoute {
+    fn from(route: talpid_types::net::InstalledRoute) -> Self {
+        proto::InstalledRoute {
+            destination: route.destination.to_string(),
+            gateway: route.gateway.map(|gateway| gateway.to_string()),
+            interface: route.interface,
+            metric: route.metric,
+        }
+    }
+}
+
+impl TryFrom<proto::InstalledRoute> for talpid_types::net::InstalledRoute {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(route: proto::InstalledRoute) -> Result<Self, Self::Error> {
+        Ok(talpid_types::net::InstalledRoute {
+            destination: arg_from_str(&route.destination, "invalid route destination")?,
+            gateway: route
+                .gateway
+                .map(|gateway| arg_from_str(&gateway, "invalid route gateway"))
+                .transpose()?,
+            interface: route.interface,
+            metric: route.metric,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_installed_route_round_trip() {
+        let routes = [
+            talpid_types::net::InstalledRoute {
+                destination: "0.0.0.0/0".parse().unwrap(),
+                gateway: None,
+                interface: Some("wg0-mullvad".to_owned()),
+                metric: None,
+            },
+            talpid_types::net::InstalledRoute {
+                destination: "2001:db8::/32".parse().unwrap(),
+                gateway: Some("fe80::1".parse().unwrap()),
+                interface: None,
+                metric: Some(100),
+            },
+        ];
+        for route in routes {
+            let proto_route = proto::InstalledRoute::from(route.clone());
+            assert_eq!(
+                talpid_types::net::InstalledRoute::try_from(proto_route).unwrap(),
+                route
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_installed_route() {
+        let route = proto::InstalledRoute {
+            destination: "not a network".to_owned(),
+            gateway: None,
+            interface: None,
+            metric: None,
+        };
+        assert!(talpid_types::net::InstalledRoute::try_from(route).is_err());
+    }
+}
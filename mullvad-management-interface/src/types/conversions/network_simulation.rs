@@ -0,0 +1,37 @@
+use crate::types::{FromProtobufTypeError, proto};
+use mullvad_types::network_simulation::SimulatedNetworkConditions;
+
+impl From<SimulatedNetworkConditions> for proto::SimulatedNetworkConditions {
+    fn from(conditions: SimulatedNetworkConditions) -> Self {
+        proto::SimulatedNetworkConditions {
+            loss_percent: u32::from(conditions.loss_percent()),
+            added_latency: Some(
+                prost_types::Duration::try_from(conditions.added_latency)
+                    .expect("Failed to convert std::time::Duration to prost_types::Duration"),
+            ),
+            seed: conditions.seed,
+        }
+    }
+}
+
+impl TryFrom<proto::SimulatedNetworkConditions> for SimulatedNetworkConditions {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(conditions: proto::SimulatedNetworkConditions) -> Result<Self, Self::Error> {
+        let added_latency = conditions
+            .added_latency
+            .map(|latency| {
+                std::time::Duration::try_from(latency)
+                    .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid added latency"))
+            })
+            .transpose()?
+            .unwrap_or_default();
+        let loss_percent = u8::try_from(conditions.loss_percent)
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid loss percentage"))?;
+
+        let mut simulated = SimulatedNetworkConditions::new(loss_percent, added_latency)
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid loss percentage"))?;
+        simulated.seed = conditions.seed;
+        Ok(simulated)
+    }
+}
@@ -0,0 +1,94 @@
+use crate::types::{FromProtobufTypeError, proto};
+use chrono::DateTime;
+use mullvad_types::notification::{Notification, NotificationKind, NotificationSeverity};
+
+impl From<Notification> for proto::Notification {
+    fn from(notification: Notification) -> Self {
+        use proto::notification::{Kind, Severity};
+
+        let (kind, account_expiry, version) = match notification.kind {
+            NotificationKind::AccountExpiringSoon { expiry } => {
+                (Kind::AccountExpiringSoon, Some(expiry), None)
+            }
+            NotificationKind::AccountExpired { expiry } => {
+                (Kind::AccountExpired, Some(expiry), None)
+            }
+            NotificationKind::UpdateAvailable { version } => {
+                (Kind::UpdateAvailable, None, Some(version))
+            }
+            NotificationKind::UnsupportedVersion => (Kind::UnsupportedVersion, None, None),
+            NotificationKind::DeviceRevoked => (Kind::DeviceRevoked, None, None),
+        };
+        let severity = match notification.severity {
+            NotificationSeverity::Info => Severity::Info,
+            NotificationSeverity::Warning => Severity::Warning,
+            NotificationSeverity::Critical => Severity::Critical,
+        };
+        proto::Notification {
+            id: notification.id,
+            kind: i32::from(kind),
+            severity: i32::from(severity),
+            account_expiry: account_expiry.map(|expiry| prost_types::Timestamp {
+                seconds: expiry.timestamp(),
+                nanos: 0,
+            }),
+            version,
+        }
+    }
+}
+
+impl TryFrom<proto::Notification> for Notification {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(notification: proto::Notification) -> Result<Self, Self::Error> {
+        use proto::notification::{Kind, Severity};
+
+        let account_expiry = || {
+            let expiry =
+                notification
+                    .account_expiry
+                    .ok_or(FromProtobufTypeError::InvalidArgument(
+                        "missing account expiry",
+                    ))?;
+            DateTime::from_timestamp(expiry.seconds, expiry.nanos as u32)
+                .ok_or(FromProtobufTypeError::InvalidArgument("invalid timestamp"))
+        };
+        let kind = match Kind::try_from(notification.kind) {
+            Ok(Kind::AccountExpiringSoon) => NotificationKind::AccountExpiringSoon {
+                expiry: account_expiry()?,
+            },
+            Ok(Kind::AccountExpired) => NotificationKind::AccountExpired {
+                expiry: account_expiry()?,
+            },
+            Ok(Kind::UpdateAvailable) => NotificationKind::UpdateAvailable {
+                version: notification
+                    .version
+                    .clone()
+                    .ok_or(FromProtobufTypeError::InvalidArgument("missing version"))?,
+            },
+            Ok(Kind::UnsupportedVersion) => NotificationKind::UnsupportedVersion,
+            Ok(Kind::DeviceRevoked) => NotificationKind::DeviceRevoked,
+            Err(_) => {
+                return Err(FromProtobufTypeError::InvalidArgument(
+                    "invalid notification kind",
+                ));
+            }
+        };
+        let severity = match Severity::try_from(notification.severity) {
+            Ok(Severity::Info) => NotificationSeverity::Info,
+            Ok(Severity::Warning) => NotificationSeverity::Warning,
+            Ok(Severity::Critical) => NotificationSeverity::Critical,
+            Err(_) => {
+                return Err(FromProtobufTypeError::InvalidArgument(
+                    "invalid notification severity",
+                ));
+            }
+        };
+
+        Ok(Notification {
+            id: notification.id,
+            kind,
+            severity,
+        })
+    }
+}
@@ -0,0 +1,75 @@
+use crate::types::{FromProtobufTypeError, proto};
+use mullvad_types::{
+    profile::{ConnectionProfile, ProfilesSettings},
+    relay_constraints::{ObfuscationSettings, RelaySettings},
+    settings::TunnelOptions,
+};
+
+impl From<&ConnectionProfile> for proto::ConnectionProfile {
+    fn from(profile: &ConnectionProfile) -> Self {
+        proto::ConnectionProfile {
+            name: profile.name.clone(),
+            relay_settings: Some(proto::RelaySettings::from(profile.relay_settings.clone())),
+            obfuscation_settings: Some(proto::ObfuscationSettings::from(
+                &profile.obfuscation_settings,
+            )),
+            tunnel_options: Some(proto::TunnelOptions::from(&profile.tunnel_options)),
+        }
+    }
+}
+
+impl TryFrom<proto::ConnectionProfile> for ConnectionProfile {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(profile: proto::ConnectionProfile) -> Result<Self, Self::Error> {
+        let relay_settings =
+            profile
+                .relay_settings
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "missing relay settings",
+                ))?;
+        let obfuscation_settings =
+            profile
+                .obfuscation_settings
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "missing obfuscation settings",
+                ))?;
+        let tunnel_options =
+            profile
+                .tunnel_options
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "missing tunnel options",
+                ))?;
+
+        Ok(ConnectionProfile {
+            name: profile.name,
+            relay_settings: RelaySettings::try_from(relay_settings)?,
+            obfuscation_settings: ObfuscationSettings::try_from(obfuscation_settings)?,
+            tunnel_options: TunnelOptions::try_from(tunnel_options)?,
+        })
+    }
+}
+
+impl From<&ProfilesSettings> for proto::ConnectionProfiles {
+    fn from(profiles: &ProfilesSettings) -> Self {
+        proto::ConnectionProfiles {
+            profiles: profiles
+                .iter()
+                .map(proto::ConnectionProfile::from)
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::ConnectionProfiles> for ProfilesSettings {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(profiles: proto::ConnectionProfiles) -> Result<Self, Self::Error> {
+        profiles
+            .profiles
+            .into_iter()
+            .map(ConnectionProfile::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map(ProfilesSettings::from)
+    }
+}
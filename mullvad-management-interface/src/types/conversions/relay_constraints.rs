@@ -3,7 +3,7 @@ use mullvad_types::{
     constraints::Constraint,
     custom_list::Id,
     relay_constraints::{
-        GeographicLocationConstraint,
+        GeographicLocationConstraint, HostnamePattern, RelaySelectionFallback,
         allowed_ip::{self, AllowedIps},
     },
 };
@@ -123,24 +123,48 @@ impl TryFrom<proto::RelaySettings> for mullvad_types::relay_constraints::RelaySe
     }
 }
 
+impl From<mullvad_types::relay_constraints::SelectedObfuscation>
+    for proto::obfuscation_settings::SelectedObfuscation
+{
+    fn from(selected_obfuscation: mullvad_types::relay_constraints::SelectedObfuscation) -> Self {
+        use mullvad_types::relay_constraints::SelectedObfuscation;
+        match selected_obfuscation {
+            SelectedObfuscation::Auto => Self::Auto,
+            SelectedObfuscation::Off => Self::Off,
+            SelectedObfuscation::Udp2Tcp => Self::Udp2tcp,
+            SelectedObfuscation::Shadowsocks => Self::Shadowsocks,
+            SelectedObfuscation::Quic => Self::Quic,
+            SelectedObfuscation::Lwo => Self::Lwo,
+            SelectedObfuscation::WireguardPort => Self::WireguardPort,
+        }
+    }
+}
+
+/// Convert an `i32` representation of [proto::obfuscation_settings::SelectedObfuscation]
+pub fn try_selected_obfuscation_from_i32(
+    selected_obfuscation: i32,
+) -> Result<mullvad_types::relay_constraints::SelectedObfuscation, FromProtobufTypeError> {
+    use mullvad_types::relay_constraints::SelectedObfuscation;
+    use proto::obfuscation_settings::SelectedObfuscation as IpcSelectedObfuscation;
+    match IpcSelectedObfuscation::try_from(selected_obfuscation) {
+        Ok(IpcSelectedObfuscation::Auto) => Ok(SelectedObfuscation::Auto),
+        Ok(IpcSelectedObfuscation::Off) => Ok(SelectedObfuscation::Off),
+        Ok(IpcSelectedObfuscation::Udp2tcp) => Ok(SelectedObfuscation::Udp2Tcp),
+        Ok(IpcSelectedObfuscation::Shadowsocks) => Ok(SelectedObfuscation::Shadowsocks),
+        Ok(IpcSelectedObfuscation::Quic) => Ok(SelectedObfuscation::Quic),
+        Ok(IpcSelectedObfuscation::Lwo) => Ok(SelectedObfuscation::Lwo),
+        Ok(IpcSelectedObfuscation::WireguardPort) => Ok(SelectedObfuscation::WireguardPort),
+        Err(_) => Err(FromProtobufTypeError::InvalidArgument(
+            "invalid obfuscation settings",
+        )),
+    }
+}
+
 impl From<&mullvad_types::relay_constraints::ObfuscationSettings> for proto::ObfuscationSettings {
     fn from(settings: &mullvad_types::relay_constraints::ObfuscationSettings) -> Self {
-        use mullvad_types::relay_constraints::SelectedObfuscation;
-        let selected_obfuscation = i32::from(match settings.selected_obfuscation {
-            SelectedObfuscation::Auto => proto::obfuscation_settings::SelectedObfuscation::Auto,
-            SelectedObfuscation::Off => proto::obfuscation_settings::SelectedObfuscation::Off,
-            SelectedObfuscation::Udp2Tcp => {
-                proto::obfuscation_settings::SelectedObfuscation::Udp2tcp
-            }
-            SelectedObfuscation::Shadowsocks => {
-                proto::obfuscation_settings::SelectedObfuscation::Shadowsocks
-            }
-            SelectedObfuscation::Quic => proto::obfuscation_settings::SelectedObfuscation::Quic,
-            SelectedObfuscation::Lwo => proto::obfuscation_settings::SelectedObfuscation::Lwo,
-            SelectedObfuscation::WireguardPort => {
-                proto::obfuscation_settings::SelectedObfuscation::WireguardPort
-            }
-        });
+        let selected_obfuscation = i32::from(
+            proto::obfuscation_settings::SelectedObfuscation::from(settings.selected_obfuscation),
+        );
         Self {
             selected_obfuscation,
             udp2tcp: Some(proto::obfuscation_settings::Udp2TcpObfuscation::from(
@@ -152,6 +176,9 @@ impl From<&mullvad_types::relay_constraints::ObfuscationSettings> for proto::Obf
             wireguard_port: Some(proto::obfuscation_settings::WireguardPort::from(
                 &settings.wireguard_port,
             )),
+            auto_sensitivity: i32::from(proto::obfuscation_settings::AutoSensitivity::from(
+                settings.auto_sensitivity,
+            )),
         }
     }
 }
@@ -178,6 +205,11 @@ impl From<&mullvad_types::relay_constraints::ShadowsocksSettings>
     fn from(settings: &mullvad_types::relay_constraints::ShadowsocksSettings) -> Self {
         Self {
             port: settings.port.map(u32::from).option(),
+            custom_server: settings
+                .custom_server
+                .clone()
+                .map(proto::Shadowsocks::from)
+                .option(),
         }
     }
 }
@@ -273,6 +305,11 @@ impl From<mullvad_types::relay_constraints::LocationConstraint> for proto::Locat
                     list_id.to_string(),
                 )),
             },
+            LocationConstraint::HostnamePattern(pattern) => Self {
+                r#type: Some(proto::location_constraint::Type::HostnamePattern(
+                    String::from(pattern),
+                )),
+            },
         }
     }
 }
@@ -296,6 +333,13 @@ impl TryFrom<proto::LocationConstraint>
                 };
                 Ok(Constraint::Only(location))
             }
+            Some(proto::location_constraint::Type::HostnamePattern(pattern)) => {
+                Ok(Constraint::Only(LocationConstraint::HostnamePattern(
+                    HostnamePattern::from_str(&pattern).map_err(|_| {
+                        FromProtobufTypeError::InvalidArgument("invalid hostname pattern")
+                    })?,
+                )))
+            }
             None => Ok(Constraint::Any),
         }
     }
@@ -347,23 +391,8 @@ impl TryFrom<proto::ObfuscationSettings> for mullvad_types::relay_constraints::O
     type Error = FromProtobufTypeError;
 
     fn try_from(settings: proto::ObfuscationSettings) -> Result<Self, Self::Error> {
-        use mullvad_types::relay_constraints::SelectedObfuscation;
-        use proto::obfuscation_settings::SelectedObfuscation as IpcSelectedObfuscation;
         let selected_obfuscation =
-            match IpcSelectedObfuscation::try_from(settings.selected_obfuscation) {
-                Ok(IpcSelectedObfuscation::Auto) => SelectedObfuscation::Auto,
-                Ok(IpcSelectedObfuscation::Off) => SelectedObfuscation::Off,
-                Ok(IpcSelectedObfuscation::Udp2tcp) => SelectedObfuscation::Udp2Tcp,
-                Ok(IpcSelectedObfuscation::Shadowsocks) => SelectedObfuscation::Shadowsocks,
-                Ok(IpcSelectedObfuscation::Quic) => SelectedObfuscation::Quic,
-                Ok(IpcSelectedObfuscation::Lwo) => SelectedObfuscation::Lwo,
-                Ok(IpcSelectedObfuscation::WireguardPort) => SelectedObfuscation::WireguardPort,
-                Err(_) => {
-                    return Err(FromProtobufTypeError::InvalidArgument(
-                        "invalid obfuscation settings",
-                    ));
-                }
-            };
+            try_selected_obfuscation_from_i32(settings.selected_obfuscation)?;
 
         let udp2tcp = match settings.udp2tcp {
             Some(settings) => {
@@ -397,15 +426,73 @@ impl TryFrom<proto::ObfuscationSettings> for mullvad_types::relay_constraints::O
             }
         };
 
+        let auto_sensitivity =
+            proto::obfuscation_settings::AutoSensitivity::try_from(settings.auto_sensitivity)
+                .map(mullvad_types::relay_constraints::ObfuscationSensitivity::from)
+                .map_err(|_| {
+                    FromProtobufTypeError::InvalidArgument("invalid obfuscation sensitivity")
+                })?;
+
         Ok(Self {
             selected_obfuscation,
             udp2tcp,
             shadowsocks,
             wireguard_port,
+            auto_sensitivity,
         })
     }
 }
 
+impl From<mullvad_types::relay_constraints::ObfuscationSensitivity>
+    for proto::obfuscation_settings::AutoSensitivity
+{
+    fn from(sensitivity: mullvad_types::relay_constraints::ObfuscationSensitivity) -> Self {
+        use mullvad_types::relay_constraints::ObfuscationSensitivity;
+        match sensitivity {
+            ObfuscationSensitivity::Low => Self::Low,
+            ObfuscationSensitivity::Normal => Self::Normal,
+            ObfuscationSensitivity::High => Self::High,
+        }
+    }
+}
+
+impl From<proto::obfuscation_settings::AutoSensitivity>
+    for mullvad_types::relay_constraints::ObfuscationSensitivity
+{
+    fn from(sensitivity: proto::obfuscation_settings::AutoSensitivity) -> Self {
+        use proto::obfuscation_settings::AutoSensitivity;
+        match sensitivity {
+            AutoSensitivity::Low => Self::Low,
+            AutoSensitivity::Normal => Self::Normal,
+            AutoSensitivity::High => Self::High,
+        }
+    }
+}
+
+impl From<mullvad_types::relay_constraints::ObfuscationSensitivity>
+    for proto::ObfuscationAutoSensitivity
+{
+    fn from(sensitivity: mullvad_types::relay_constraints::ObfuscationSensitivity) -> Self {
+        Self {
+            sensitivity: i32::from(proto::obfuscation_settings::AutoSensitivity::from(
+                sensitivity,
+            )),
+        }
+    }
+}
+
+impl TryFrom<proto::ObfuscationAutoSensitivity>
+    for mullvad_types::relay_constraints::ObfuscationSensitivity
+{
+    type Error = FromProtobufTypeError;
+
+    fn try_from(sensitivity: proto::ObfuscationAutoSensitivity) -> Result<Self, Self::Error> {
+        proto::obfuscation_settings::AutoSensitivity::try_from(sensitivity.sensitivity)
+            .map(Self::from)
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid obfuscation sensitivity"))
+    }
+}
+
 impl TryFrom<&proto::obfuscation_settings::Udp2TcpObfuscation>
     for mullvad_types::relay_constraints::Udp2TcpObfuscationSettings
 {
@@ -426,8 +513,14 @@ impl TryFrom<&proto::obfuscation_settings::Shadowsocks>
     type Error = FromProtobufTypeError;
 
     fn try_from(settings: &proto::obfuscation_settings::Shadowsocks) -> Result<Self, Self::Error> {
+        let custom_server = settings
+            .custom_server
+            .clone()
+            .map(talpid_types::net::proxy::Shadowsocks::try_from)
+            .transpose()?;
         Ok(Self {
             port: Constraint::from(settings.port.map(|port| port as u16)),
+            custom_server: Constraint::from(custom_server),
         })
     }
 }
@@ -462,6 +555,12 @@ impl From<mullvad_types::relay_constraints::RelayOverride> for proto::RelayOverr
             hostname: r#override.hostname,
             ipv4_addr_in: r#override.ipv4_addr_in.map(|addr| addr.to_string()),
             ipv6_addr_in: r#override.ipv6_addr_in.map(|addr| addr.to_string()),
+            wireguard_ipv4_addr_in: r#override
+                .wireguard_ipv4_addr_in
+                .map(|addr| addr.to_string()),
+            wireguard_ipv6_addr_in: r#override
+                .wireguard_ipv6_addr_in
+                .map(|addr| addr.to_string()),
         }
     }
 }
@@ -488,6 +587,20 @@ impl TryFrom<proto::RelayOverride> for mullvad_types::relay_constraints::RelayOv
                         .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid IPv6 address"))
                 })
                 .transpose()?,
+            wireguard_ipv4_addr_in: r#override
+                .wireguard_ipv4_addr_in
+                .map(|addr| {
+                    addr.parse()
+                        .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid IPv4 address"))
+                })
+                .transpose()?,
+            wireguard_ipv6_addr_in: r#override
+                .wireguard_ipv6_addr_in
+                .map(|addr| {
+                    addr.parse()
+                        .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid IPv6 address"))
+                })
+                .transpose()?,
         })
     }
 }
@@ -548,3 +661,137 @@ fn convert_ownership_constraint(
         },
     }
 }
+
+impl From<RelaySelectionFallback> for proto::RelaySelectionFallback {
+    fn from(fallback: RelaySelectionFallback) -> Self {
+        proto::RelaySelectionFallback {
+            hostname_pattern: String::from(fallback.pattern),
+            entry: fallback.entry,
+        }
+    }
+}
+
+impl TryFrom<proto::RelaySelectionFallback> for RelaySelectionFallback {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(fallback: proto::RelaySelectionFallback) -> Result<Self, Self::Error> {
+        Ok(RelaySelectionFallback {
+            pattern: HostnamePattern::from_str(&fallback.hostname_pattern)
+                .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid hostname pattern"))?,
+            entry: fallback.entry,
+        })
+    }
+}
+
+impl From<mullvad_types::relay_constraints::ObfuscationState> for proto::ObfuscationState {
+    fn from(state: mullvad_types::relay_constraints::ObfuscationState) -> Self {
+        let attempt = |obfuscation: Vec<talpid_types::net::ObfuscationType>| {
+            proto::obfuscation_state::Attempt {
+                obfuscation: obfuscation
+                    .into_iter()
+                    .map(|obfuscation_type| {
+                        i32::from(proto::obfuscation_endpoint::ObfuscationType::from(
+                            obfuscation_type,
+                        ))
+                    })
+                    .collect(),
+            }
+        };
+        proto::ObfuscationState {
+            requested: i32::from(proto::obfuscation_settings::SelectedObfuscation::from(
+                state.requested,
+            )),
+            active: state.active.map(attempt),
+            attempts: state.attempts.into_iter().map(attempt).collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::ObfuscationState> for mullvad_types::relay_constraints::ObfuscationState {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(state: proto::ObfuscationState) -> Result<Self, Self::Error> {
+        let attempt = |attempt: proto::obfuscation_state::Attempt| {
+            attempt
+                .obfuscation
+                .into_iter()
+                .map(super::net::try_obfuscation_type_from_i32)
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(mullvad_types::relay_constraints::ObfuscationState {
+            requested: try_selected_obfuscation_from_i32(state.requested)?,
+            active: state.active.map(attempt).transpose()?,
+            attempts: state
+                .attempts
+                .into_iter()
+                .map(attempt)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<allowed_ip::AllowedIpsWarning> for proto::AllowedIpsWarning {
+    fn from(warning: allowed_ip::AllowedIpsWarning) -> Self {
+        use allowed_ip::AllowedIpsWarning;
+        use proto::allowed_ips_warning::Kind;
+
+        let (kind, network, conflict) = match warning {
+            AllowedIpsWarning::CapturesLocalNetwork {
+                network,
+                local_network,
+            } => (
+                Kind::CapturesLocalNetwork,
+                Some(network),
+                local_network.to_string(),
+            ),
+            AllowedIpsWarning::ExcludesDnsServer(server) => {
+                (Kind::ExcludesDnsServer, None, server.to_string())
+            }
+            AllowedIpsWarning::Redundant {
+                network,
+                containing,
+            } => (Kind::Redundant, Some(network), containing.to_string()),
+        };
+        proto::AllowedIpsWarning {
+            kind: i32::from(kind),
+            network: network.map(|network| network.to_string()),
+            conflict,
+        }
+    }
+}
+
+impl TryFrom<proto::AllowedIpsWarning> for allowed_ip::AllowedIpsWarning {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(warning: proto::AllowedIpsWarning) -> Result<Self, Self::Error> {
+        use allowed_ip::AllowedIpsWarning;
+        use proto::allowed_ips_warning::Kind;
+
+        let network = || {
+            warning
+                .network
+                .as_deref()
+                .ok_or(FromProtobufTypeError::InvalidArgument("missing network"))?
+                .parse()
+                .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid network"))
+        };
+        let invalid_conflict = FromProtobufTypeError::InvalidArgument("invalid conflict");
+
+        match Kind::try_from(warning.kind) {
+            Ok(Kind::CapturesLocalNetwork) => Ok(AllowedIpsWarning::CapturesLocalNetwork {
+                network: network()?,
+                local_network: warning.conflict.parse().map_err(|_| invalid_conflict)?,
+            }),
+            Ok(Kind::ExcludesDnsServer) => Ok(AllowedIpsWarning::ExcludesDnsServer(
+                warning.conflict.parse().map_err(|_| invalid_conflict)?,
+            )),
+            Ok(Kind::Redundant) => Ok(AllowedIpsWarning::Redundant {
+                network: network()?,
+                containing: warning.conflict.parse().map_err(|_| invalid_conflict)?,
+            }),
+            Err(_) => Err(FromProtobufTypeError::InvalidArgument(
+                "invalid allowed IPs warning kind",
+            )),
+        }
+    }
+}
@@ -5,18 +5,343 @@ use std::{
     str::FromStr,
 };
 
+use chrono::{DateTime, Utc};
 use mullvad_types::{
+    constraints::Constraint,
     location::Location,
+    relay_constraints::Ownership,
     relay_list::{
-        Bridge, BridgeEndpointData, BridgeList, EndpointData, Relay, RelayList, RelayListCountry,
-        WireguardRelay,
+        Bridge, BridgeEndpointData, BridgeList, CitySummary, CountrySummary, EndpointData,
+        ObfuscationBenchmarkResult, Relay, RelayFeature, RelayFeatureSupport, RelayFeatures,
+        RelayList, RelayListCity, RelayListCountry, RelayListReport, RelayListUpdateInfo,
+        RelayLocationsSummary, RelayPortReachability, RelayTransparencyInfo, WireguardRelay,
     },
 };
 use vec1::Vec1;
 
-use super::net::try_transport_protocol_from_i32;
+use super::{
+    net::try_transport_protocol_from_i32,
+    relay_constraints::{try_ownership_constraint_from_i32, try_selected_obfuscation_from_i32},
+};
 use crate::types::{FromProtobufTypeError, conversions::bytes_to_pubkey, proto};
 
+impl From<RelayListReport> for proto::RelayListReport {
+    fn from(report: RelayListReport) -> Self {
+        proto::RelayListReport {
+            relay_count: u32::try_from(report.relay_count).unwrap_or(u32::MAX),
+            bridge_count: u32::try_from(report.bridge_count).unwrap_or(u32::MAX),
+            problems: report.problems,
+        }
+    }
+}
+
+impl From<proto::RelayListReport> for RelayListReport {
+    fn from(report: proto::RelayListReport) -> Self {
+        RelayListReport {
+            relay_count: report.relay_count as usize,
+            bridge_count: report.bridge_count as usize,
+            problems: report.problems,
+        }
+    }
+}
+
+impl From<RelayLocationsSummary> for proto::RelayLocationsSummary {
+    fn from(summary: RelayLocationsSummary) -> Self {
+        let city_from = |city: CitySummary| proto::relay_locations_summary::City {
+            name: city.name,
+            code: city.code,
+            latitude: city.latitude,
+            longitude: city.longitude,
+            relay_count: u32::try_from(city.relay_count).unwrap_or(u32::MAX),
+            active_relay_count: u32::try_from(city.active_relay_count).unwrap_or(u32::MAX),
+        };
+        proto::RelayLocationsSummary {
+            countries: summary
+                .countries
+                .into_iter()
+                .map(|country| proto::relay_locations_summary::Country {
+                    name: country.name,
+                    code: country.code,
+                    cities: country.cities.into_iter().map(city_from).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<proto::RelayLocationsSummary> for RelayLocationsSummary {
+    fn from(summary: proto::RelayLocationsSummary) -> Self {
+        let city_from = |city: proto::relay_locations_summary::City| CitySummary {
+            name: city.name,
+            code: city.code,
+            latitude: city.latitude,
+            longitude: city.longitude,
+            relay_count: city.relay_count as usize,
+            active_relay_count: city.active_relay_count as usize,
+        };
+        RelayLocationsSummary {
+            countries: summary
+                .countries
+                .into_iter()
+                .map(|country| CountrySummary {
+                    name: country.name,
+                    code: country.code,
+                    cities: country.cities.into_iter().map(city_from).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<Vec<WireguardRelay>> for proto::CityRelays {
+    fn from(relays: Vec<WireguardRelay>) -> Self {
+        proto::CityRelays {
+            relays: relays.into_iter().map(proto::Relay::from).collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::CityRelays> for Vec<WireguardRelay> {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(relays: proto::CityRelays) -> Result<Self, Self::Error> {
+        relays
+            .relays
+            .into_iter()
+            .map(WireguardRelay::try_from)
+            .collect()
+    }
+}
+
+impl From<RelayListUpdateInfo> for proto::RelayListUpdateInfo {
+    fn from(info: RelayListUpdateInfo) -> Self {
+        let to_timestamp = |time: DateTime<Utc>| prost_types::Timestamp {
+            seconds: time.timestamp(),
+            nanos: 0,
+        };
+        proto::RelayListUpdateInfo {
+            update_interval: Some(
+                prost_types::Duration::try_from(info.update_interval)
+                    .expect("Failed to convert std::time::Duration to prost_types::Duration"),
+            ),
+            last_updated: info.last_updated.map(to_timestamp),
+            next_update: info.next_update.map(to_timestamp),
+        }
+    }
+}
+
+impl TryFrom<proto::RelayListUpdateInfo> for RelayListUpdateInfo {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(info: proto::RelayListUpdateInfo) -> Result<Self, Self::Error> {
+        let from_timestamp = |time: prost_types::Timestamp| {
+            DateTime::from_timestamp(time.seconds, time.nanos as u32)
+                .ok_or(FromProtobufTypeError::InvalidArgument("invalid timestamp"))
+        };
+        let update_interval = info
+            .update_interval
+            .ok_or(FromProtobufTypeError::InvalidArgument(
+                "missing update interval",
+            ))
+            .and_then(|interval| {
+                std::time::Duration::try_from(interval)
+                    .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid update interval"))
+            })?;
+        Ok(RelayListUpdateInfo {
+            update_interval,
+            last_updated: info.last_updated.map(from_timestamp).transpose()?,
+            next_update: info.next_update.map(from_timestamp).transpose()?,
+        })
+    }
+}
+
+impl From<RelayTransparencyInfo> for proto::RelayTransparencyInfo {
+    fn from(info: RelayTransparencyInfo) -> Self {
+        let ownership = match info.ownership {
+            Ownership::MullvadOwned => proto::Ownership::MullvadOwned,
+            Ownership::Rented => proto::Ownership::Rented,
+        };
+        proto::RelayTransparencyInfo {
+            hostname: info.hostname,
+            ownership: i32::from(ownership),
+            provider: info.provider,
+            active: info.active,
+            in_listed_country: info.in_listed_country,
+            matches_filters: info.matches_filters,
+        }
+    }
+}
+
+impl TryFrom<proto::RelayTransparencyInfo> for RelayTransparencyInfo {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(info: proto::RelayTransparencyInfo) -> Result<Self, Self::Error> {
+        let Constraint::Only(ownership) = try_ownership_constraint_from_i32(info.ownership)? else {
+            return Err(FromProtobufTypeError::InvalidArgument(
+                "relay ownership must be specified",
+            ));
+        };
+        Ok(RelayTransparencyInfo {
+            hostname: info.hostname,
+            ownership,
+            provider: info.provider,
+            active: info.active,
+            in_listed_country: info.in_listed_country,
+            matches_filters: info.matches_filters,
+        })
+    }
+}
+
+impl From<RelayPortReachability> for proto::RelayPortTestResult {
+    fn from(reachability: RelayPortReachability) -> Self {
+        use proto::relay_port_test_result::Reachability;
+
+        let (reachability, latency) = match reachability {
+            RelayPortReachability::Reachable { latency } => (
+                Reachability::Reachable,
+                Some(
+                    prost_types::Duration::try_from(latency)
+                        .expect("Failed to convert std::time::Duration to prost_types::Duration"),
+                ),
+            ),
+            RelayPortReachability::Refused => (Reachability::Refused, None),
+            RelayPortReachability::NoResponse => (Reachability::NoResponse, None),
+            RelayPortReachability::Unknown => (Reachability::Unknown, None),
+        };
+        proto::RelayPortTestResult {
+            reachability: i32::from(reachability),
+            latency,
+        }
+    }
+}
+
+impl TryFrom<proto::RelayPortTestResult> for RelayPortReachability {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(result: proto::RelayPortTestResult) -> Result<Self, Self::Error> {
+        use proto::relay_port_test_result::Reachability;
+
+        match Reachability::try_from(result.reachability) {
+            Ok(Reachability::Reachable) => {
+                let latency = result
+                    .latency
+                    .map(std::time::Duration::try_from)
+                    .transpose()
+                    .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid latency"))?
+                    .unwrap_or_default();
+                Ok(RelayPortReachability::Reachable { latency })
+            }
+            Ok(Reachability::Refused) => Ok(RelayPortReachability::Refused),
+            Ok(Reachability::NoResponse) => Ok(RelayPortReachability::NoResponse),
+            Ok(Reachability::Unknown) => Ok(RelayPortReachability::Unknown),
+            Err(_) => Err(FromProtobufTypeError::InvalidArgument(
+                "invalid relay port reachability",
+            )),
+        }
+    }
+}
+
+impl From<ObfuscationBenchmarkResult> for proto::ObfuscationBenchmarkResult {
+    fn from(result: ObfuscationBenchmarkResult) -> Self {
+        proto::ObfuscationBenchmarkResult {
+            method: i32::from(proto::obfuscation_settings::SelectedObfuscation::from(
+                result.method,
+            )),
+            handshake_time: result.handshake_time.map(|handshake_time| {
+                prost_types::Duration::try_from(handshake_time)
+                    .expect("Failed to convert std::time::Duration to prost_types::Duration")
+            }),
+            throughput: result.throughput,
+        }
+    }
+}
+
+impl TryFrom<proto::ObfuscationBenchmarkResult> for ObfuscationBenchmarkResult {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(result: proto::ObfuscationBenchmarkResult) -> Result<Self, Self::Error> {
+        Ok(ObfuscationBenchmarkResult {
+            method: try_selected_obfuscation_from_i32(result.method)?,
+            handshake_time: result
+                .handshake_time
+                .map(std::time::Duration::try_from)
+                .transpose()
+                .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid handshake time"))?,
+            throughput: result.throughput,
+        })
+    }
+}
+
+impl From<RelayFeature> for proto::relay_feature_support::Feature {
+    fn from(feature: RelayFeature) -> Self {
+        match feature {
+            RelayFeature::Daita => proto::relay_feature_support::Feature::Daita,
+            RelayFeature::Quic => proto::relay_feature_support::Feature::Quic,
+            RelayFeature::Lwo => proto::relay_feature_support::Feature::Lwo,
+        }
+    }
+}
+
+fn try_relay_feature_from_i32(feature: i32) -> Result<RelayFeature, FromProtobufTypeError> {
+    match proto::relay_feature_support::Feature::try_from(feature) {
+        Ok(proto::relay_feature_support::Feature::Daita) => Ok(RelayFeature::Daita),
+        Ok(proto::relay_feature_support::Feature::Quic) => Ok(RelayFeature::Quic),
+        Ok(proto::relay_feature_support::Feature::Lwo) => Ok(RelayFeature::Lwo),
+        Err(_) => Err(FromProtobufTypeError::InvalidArgument(
+            "invalid relay feature",
+        )),
+    }
+}
+
+impl From<RelayFeatureSupport> for proto::RelayFeatureSupport {
+    fn from(support: RelayFeatureSupport) -> Self {
+        let features_to_proto = |features: Vec<RelayFeature>| {
+            features
+                .into_iter()
+                .map(|feature| i32::from(proto::relay_feature_support::Feature::from(feature)))
+                .collect()
+        };
+        proto::RelayFeatureSupport {
+            requested: features_to_proto(support.requested),
+            relays: support
+                .relays
+                .into_iter()
+                .map(|relay| proto::relay_feature_support::Relay {
+                    hostname: relay.hostname,
+                    features: features_to_proto(relay.features),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::RelayFeatureSupport> for RelayFeatureSupport {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(support: proto::RelayFeatureSupport) -> Result<Self, Self::Error> {
+        let features_from_proto = |features: Vec<i32>| {
+            features
+                .into_iter()
+                .map(try_relay_feature_from_i32)
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let relays = support
+            .relays
+            .into_iter()
+            .map(|relay| {
+                Ok(RelayFeatures {
+                    hostname: relay.hostname,
+                    features: features_from_proto(relay.features)?,
+                })
+            })
+            .collect::<Result<_, FromProtobufTypeError>>()?;
+        Ok(RelayFeatureSupport {
+            requested: features_from_proto(support.requested)?,
+            relays,
+        })
+    }
+}
+
 impl From<RelayList> for proto::RelayList {
     fn from(relay_list: mullvad_types::relay_list::RelayList) -> Self {
         let RelayList {
@@ -215,23 +540,27 @@ impl From<RangeInclusive<u16>> for proto::PortRange {
 
 impl From<mullvad_types::relay_list::RelayListCountry> for proto::RelayListCountry {
     fn from(country: mullvad_types::relay_list::RelayListCountry) -> Self {
-        let mut proto_country = proto::RelayListCountry {
+        proto::RelayListCountry {
             name: country.name,
             code: country.code,
-            cities: Vec::with_capacity(country.cities.len()),
-        };
-
-        for city in country.cities.into_iter() {
-            proto_country.cities.push(proto::RelayListCity {
-                name: city.name,
-                code: city.code,
-                latitude: city.latitude,
-                longitude: city.longitude,
-                relays: city.relays.into_iter().map(proto::Relay::from).collect(),
-            });
+            cities: country
+                .cities
+                .into_iter()
+                .map(proto::RelayListCity::from)
+                .collect(),
         }
+    }
+}
 
-        proto_country
+impl From<RelayListCity> for proto::RelayListCity {
+    fn from(city: RelayListCity) -> Self {
+        proto::RelayListCity {
+            name: city.name,
+            code: city.code,
+            latitude: city.latitude,
+            longitude: city.longitude,
+            relays: city.relays.into_iter().map(proto::Relay::from).collect(),
+        }
     }
 }
 
@@ -250,6 +579,8 @@ impl From<mullvad_types::relay_list::WireguardRelay> for proto::Relay {
                     quic,
                     lwo,
                     shadowsocks_extra_addr_in,
+                    ipv4_addr_in: _,
+                    ipv6_addr_in: _,
                 },
             inner:
                 mullvad_types::relay_list::Relay {
@@ -394,6 +725,8 @@ impl TryFrom<proto::Relay> for mullvad_types::relay_list::WireguardRelay {
                     .map(|addr| addr.parse())
                     .collect::<Result<HashSet<IpAddr>, _>>()
                     .map_err(|_err| FromProtobufTypeError::InvalidArgument("Invalid IP address"))?,
+                ipv4_addr_in: None,
+                ipv6_addr_in: None,
             }
         };
 
@@ -0,0 +1,22 @@
+use crate::types::proto;
+use mullvad_types::resource_usage::ResourceUsage;
+
+impl From<ResourceUsage> for proto::ResourceUsage {
+    fn from(usage: ResourceUsage) -> Self {
+        proto::ResourceUsage {
+            resident_memory: usage.resident_memory,
+            open_handles: usage.open_handles,
+            active_tasks: usage.active_tasks,
+        }
+    }
+}
+
+impl From<proto::ResourceUsage> for ResourceUsage {
+    fn from(usage: proto::ResourceUsage) -> Self {
+        ResourceUsage {
+            resident_memory: usage.resident_memory,
+            open_handles: usage.open_handles,
+            active_tasks: usage.active_tasks,
+        }
+    }
+}
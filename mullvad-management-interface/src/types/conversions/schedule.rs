@@ -0,0 +1,81 @@
+use crate::types::{FromProtobufTypeError, proto};
+use chrono::Weekday;
+use mullvad_types::schedule::{ConnectSchedule, ScheduleRange, WeekTime};
+
+impl From<&ConnectSchedule> for proto::ConnectSchedule {
+    fn from(schedule: &ConnectSchedule) -> Self {
+        proto::ConnectSchedule {
+            ranges: schedule
+                .ranges()
+                .iter()
+                .map(proto::ScheduleRange::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<&ScheduleRange> for proto::ScheduleRange {
+    fn from(range: &ScheduleRange) -> Self {
+        proto::ScheduleRange {
+            start: Some(proto::WeekTime::from(range.start)),
+            end: Some(proto::WeekTime::from(range.end)),
+        }
+    }
+}
+
+impl From<WeekTime> for proto::WeekTime {
+    fn from(time: WeekTime) -> Self {
+        proto::WeekTime {
+            weekday: time.weekday.num_days_from_monday(),
+            hour: u32::from(time.hour),
+            minute: u32::from(time.minute),
+        }
+    }
+}
+
+impl TryFrom<proto::ConnectSchedule> for ConnectSchedule {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(schedule: proto::ConnectSchedule) -> Result<Self, Self::Error> {
+        let ranges = schedule
+            .ranges
+            .into_iter()
+            .map(ScheduleRange::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ConnectSchedule::new(ranges))
+    }
+}
+
+impl TryFrom<proto::ScheduleRange> for ScheduleRange {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(range: proto::ScheduleRange) -> Result<Self, Self::Error> {
+        let start = range.start.ok_or(FromProtobufTypeError::InvalidArgument(
+            "missing schedule range start",
+        ))?;
+        let end = range.end.ok_or(FromProtobufTypeError::InvalidArgument(
+            "missing schedule range end",
+        ))?;
+        Ok(ScheduleRange {
+            start: WeekTime::try_from(start)?,
+            end: WeekTime::try_from(end)?,
+        })
+    }
+}
+
+impl TryFrom<proto::WeekTime> for WeekTime {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(time: proto::WeekTime) -> Result<Self, Self::Error> {
+        let weekday = u8::try_from(time.weekday)
+            .ok()
+            .and_then(|day| Weekday::try_from(day).ok())
+            .ok_or(FromProtobufTypeError::InvalidArgument("invalid weekday"))?;
+        let hour = u8::try_from(time.hour)
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid hour"))?;
+        let minute = u8::try_from(time.minute)
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid minute"))?;
+        WeekTime::new(weekday, hour, minute)
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid time of day"))
+    }
+}
@@ -1,5 +1,7 @@
-use crate::types::{FromProtobufTypeError, proto};
+use crate::types::{FromProtobufTypeError, conversions::arg_from_str, proto};
+use chrono::DateTime;
 use mullvad_types::settings::CURRENT_SETTINGS_VERSION;
+use prost_types::Timestamp;
 use talpid_types::ErrorExt;
 
 impl From<&mullvad_types::settings::Settings> for proto::Settings {
@@ -19,9 +21,22 @@ impl From<&mullvad_types::settings::Settings> for proto::Settings {
                 })
                 .collect();
 
+            let allowed_subnets = settings
+                .split_tunnel
+                .allowed_subnets
+                .iter()
+                .filter_map(|(app, subnets)| {
+                    Some(proto::SplitTunnelAppSubnets {
+                        app: app.clone().to_string()?,
+                        subnets: subnets.iter().map(ToString::to_string).collect(),
+                    })
+                })
+                .collect();
+
             Some(proto::SplitTunnelSettings {
                 enable_exclusions: settings.split_tunnel.enable_exclusions,
                 apps,
+                allowed_subnets,
             })
         };
         #[cfg(target_os = "linux")]
@@ -35,8 +50,24 @@ impl From<&mullvad_types::settings::Settings> for proto::Settings {
             #[cfg(target_os = "android")]
             lockdown_mode: false,
             auto_connect: settings.auto_connect,
+            on_account_expiry: Some(proto::AccountExpiryAction::from(settings.on_account_expiry)),
             tunnel_options: Some(proto::TunnelOptions::from(&settings.tunnel_options)),
             show_beta_releases: settings.show_beta_releases,
+            low_power_mode: settings.low_power_mode,
+            captive_portal_check: settings.captive_portal_check,
+            connect_timeout: Some(
+                prost_types::Duration::try_from(settings.connect_timeout)
+                    .expect("Failed to convert std::time::Duration to prost_types::Duration"),
+            ),
+            max_reconnect_attempts: settings.max_reconnect_attempts,
+            max_concurrent_api_requests: settings.max_concurrent_api_requests,
+            api_sni_hostname: settings.api_sni_hostname.clone(),
+            relay_list_download: Some(proto::RelayListDownloadSettings::from(
+                settings.relay_list_download,
+            )),
+            connect_schedule: Some(proto::ConnectSchedule::from(&settings.connect_schedule)),
+            trusted_networks: Some(proto::TrustedNetworks::from(&settings.trusted_networks)),
+            profiles: Some(proto::ConnectionProfiles::from(&settings.profiles)),
             obfuscation_settings: Some(proto::ObfuscationSettings::from(
                 &settings.obfuscation_settings,
             )),
@@ -53,6 +84,7 @@ impl From<&mullvad_types::settings::Settings> for proto::Settings {
                 .cloned()
                 .map(proto::RelayOverride::from)
                 .collect(),
+            randomize_relay_each_connect: settings.randomize_relay_each_connect,
             recents: settings.recents.clone().map(proto::Recents::from),
             update_default_location: settings.update_default_location,
         }
@@ -84,6 +116,26 @@ impl From<&mullvad_types::settings::DnsOptions> for proto::DnsOptions {
                     .map(|addr| addr.to_string())
                     .collect(),
             }),
+            split_dns: options
+                .split_dns
+                .iter()
+                .map(|rule| proto::SplitDnsRule {
+                    domain: rule.domain.clone(),
+                    servers: rule.servers.iter().map(|addr| addr.to_string()).collect(),
+                })
+                .collect(),
+            resolver_order: options
+                .resolver_order
+                .iter()
+                .map(|source| match source {
+                    mullvad_types::settings::DnsResolverSource::Custom => {
+                        dns_options::ResolverSource::CustomServers as i32
+                    }
+                    mullvad_types::settings::DnsResolverSource::Mullvad => {
+                        dns_options::ResolverSource::Mullvad as i32
+                    }
+                })
+                .collect(),
         }
     }
 }
@@ -92,6 +144,11 @@ impl From<&mullvad_types::settings::TunnelOptions> for proto::TunnelOptions {
     fn from(options: &mullvad_types::settings::TunnelOptions) -> Self {
         proto::TunnelOptions {
             mtu: options.wireguard.mtu.map(u32::from),
+            keepalive: options.wireguard.keepalive.map(u32::from),
+            bind_address: options
+                .wireguard
+                .bind_address
+                .map(|address| address.to_string()),
             rotation_interval: options.wireguard.rotation_interval.map(|ivl| {
                 prost_types::Duration::try_from(std::time::Duration::from(ivl))
                     .expect("Failed to convert std::time::Duration to prost_types::Duration for tunnel_options.rotation_interval")
@@ -141,6 +198,33 @@ impl TryFrom<proto::Settings> for mullvad_types::settings::Settings {
                 .ok_or(FromProtobufTypeError::InvalidArgument(
                     "missing api access methods settings",
                 ))?;
+        let on_account_expiry =
+            settings
+                .on_account_expiry
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "missing account expiry action",
+                ))?;
+        let connect_schedule =
+            settings
+                .connect_schedule
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "missing connect schedule",
+                ))?;
+        let trusted_networks =
+            settings
+                .trusted_networks
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "missing trusted networks",
+                ))?;
+        let relay_list_download =
+            settings
+                .relay_list_download
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "missing relay list download settings",
+                ))?;
+        let profiles = settings
+            .profiles
+            .ok_or(FromProtobufTypeError::InvalidArgument("missing profiles"))?;
         #[cfg(any(windows, target_os = "android", target_os = "macos"))]
         let split_tunnel = settings
             .split_tunnel
@@ -156,15 +240,38 @@ impl TryFrom<proto::Settings> for mullvad_types::settings::Settings {
             #[cfg(not(target_os = "android"))]
             lockdown_mode: settings.lockdown_mode,
             auto_connect: settings.auto_connect,
+            on_account_expiry: mullvad_types::settings::AccountExpiryAction::try_from(
+                on_account_expiry,
+            )?,
             tunnel_options: mullvad_types::settings::TunnelOptions::try_from(tunnel_options)?,
             relay_overrides: settings
                 .relay_overrides
                 .into_iter()
                 .map(mullvad_types::relay_constraints::RelayOverride::try_from)
                 .collect::<Result<Vec<_>, _>>()?,
+            randomize_relay_each_connect: settings.randomize_relay_each_connect,
             show_beta_releases: settings.show_beta_releases,
+            low_power_mode: settings.low_power_mode,
+            captive_portal_check: settings.captive_portal_check,
+            connect_timeout: settings
+                .connect_timeout
+                .map(std::time::Duration::try_from)
+                .transpose()
+                .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid connect timeout"))?
+                .unwrap_or_default(),
+            max_reconnect_attempts: settings.max_reconnect_attempts,
+            max_concurrent_api_requests: settings.max_concurrent_api_requests,
+            api_sni_hostname: settings.api_sni_hostname,
+            relay_list_download: mullvad_types::settings::RelayListDownloadSettings::from(
+                relay_list_download,
+            ),
+            connect_schedule: mullvad_types::schedule::ConnectSchedule::try_from(connect_schedule)?,
+            trusted_networks: mullvad_types::trusted_network::TrustedNetworksSettings::try_from(
+                trusted_networks,
+            )?,
+            profiles: mullvad_types::profile::ProfilesSettings::try_from(profiles)?,
             #[cfg(any(windows, target_os = "android", target_os = "macos"))]
-            split_tunnel: mullvad_types::settings::SplitTunnelSettings::from(split_tunnel),
+            split_tunnel: mullvad_types::settings::SplitTunnelSettings::try_from(split_tunnel)?,
             obfuscation_settings: mullvad_types::relay_constraints::ObfuscationSettings::try_from(
                 obfuscation_settings,
             )?,
@@ -189,14 +296,273 @@ impl TryFrom<proto::Settings> for mullvad_types::settings::Settings {
     }
 }
 
+impl From<mullvad_types::settings::AccountExpiryAction> for proto::AccountExpiryAction {
+    fn from(action: mullvad_types::settings::AccountExpiryAction) -> Self {
+        use mullvad_types::settings::AccountExpiryAction;
+        use proto::account_expiry_action::Action;
+
+        let action = match action {
+            AccountExpiryAction::Block => Action::Block,
+            AccountExpiryAction::Disconnect => Action::Disconnect,
+            AccountExpiryAction::StayConnectedUntilDrop => Action::StayConnectedUntilDrop,
+        };
+        proto::AccountExpiryAction {
+            action: i32::from(action),
+        }
+    }
+}
+
+impl TryFrom<proto::AccountExpiryAction> for mullvad_types::settings::AccountExpiryAction {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(action: proto::AccountExpiryAction) -> Result<Self, Self::Error> {
+        use proto::account_expiry_action::Action;
+
+        match Action::try_from(action.action) {
+            Ok(Action::Block) => Ok(Self::Block),
+            Ok(Action::Disconnect) => Ok(Self::Disconnect),
+            Ok(Action::StayConnectedUntilDrop) => Ok(Self::StayConnectedUntilDrop),
+            Err(_) => Err(FromProtobufTypeError::InvalidArgument(
+                "invalid account expiry action",
+            )),
+        }
+    }
+}
+
+impl From<mullvad_types::settings::FactoryResetPreview> for proto::FactoryResetPreview {
+    fn from(preview: mullvad_types::settings::FactoryResetPreview) -> Self {
+        let paths_to_strings = |paths: Vec<std::path::PathBuf>| {
+            paths
+                .into_iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect()
+        };
+        proto::FactoryResetPreview {
+            settings: preview.settings,
+            account_history: preview.account_history,
+            device: preview.device,
+            cache_files: paths_to_strings(preview.cache_files),
+            log_files: paths_to_strings(preview.log_files),
+            settings_files: paths_to_strings(preview.settings_files),
+        }
+    }
+}
+
+impl From<proto::FactoryResetPreview> for mullvad_types::settings::FactoryResetPreview {
+    fn from(preview: proto::FactoryResetPreview) -> Self {
+        mullvad_types::settings::FactoryResetPreview {
+            settings: preview.settings,
+            account_history: preview.account_history,
+            device: preview.device,
+            cache_files: preview.cache_files.into_iter().map(Into::into).collect(),
+            log_files: preview.log_files.into_iter().map(Into::into).collect(),
+            settings_files: preview.settings_files.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<mullvad_types::settings::SettingsIntegrity> for proto::SettingsIntegrity {
+    fn from(integrity: mullvad_types::settings::SettingsIntegrity) -> Self {
+        use mullvad_types::settings::SettingsIntegrity;
+        use proto::settings_integrity::Status;
+
+        let (status, problems) = match integrity {
+            SettingsIntegrity::Ok => (Status::Ok, vec![]),
+            SettingsIntegrity::Missing => (Status::Missing, vec![]),
+            SettingsIntegrity::Unreadable(problem) => (Status::Unreadable, vec![problem]),
+            SettingsIntegrity::Corrupt(problem) => (Status::Corrupt, vec![problem]),
+            SettingsIntegrity::Invalid(problems) => (Status::Invalid, problems),
+        };
+        proto::SettingsIntegrity {
+            status: i32::from(status),
+            problems,
+        }
+    }
+}
+
+impl TryFrom<proto::SettingsIntegrity> for mullvad_types::settings::SettingsIntegrity {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(integrity: proto::SettingsIntegrity) -> Result<Self, Self::Error> {
+        use proto::settings_integrity::Status;
+
+        let mut problems = integrity.problems;
+        let problem = problems.pop().unwrap_or_default();
+
+        match Status::try_from(integrity.status) {
+            Ok(Status::Ok) => Ok(Self::Ok),
+            Ok(Status::Missing) => Ok(Self::Missing),
+            Ok(Status::Unreadable) => Ok(Self::Unreadable(problem)),
+            Ok(Status::Corrupt) => Ok(Self::Corrupt(problem)),
+            Ok(Status::Invalid) => {
+                problems.push(problem);
+                Ok(Self::Invalid(problems))
+            }
+            Err(_) => Err(FromProtobufTypeError::InvalidArgument(
+                "invalid settings integrity status",
+            )),
+        }
+    }
+}
+
+impl From<mullvad_types::settings::RelayListDownloadSettings> for proto::RelayListDownloadSettings {
+    fn from(settings: mullvad_types::settings::RelayListDownloadSettings) -> Self {
+        proto::RelayListDownloadSettings {
+            bandwidth_limit: settings.bandwidth_limit,
+            metered_network: settings.metered_network,
+        }
+    }
+}
+
+impl From<proto::RelayListDownloadSettings> for mullvad_types::settings::RelayListDownloadSettings {
+    fn from(settings: proto::RelayListDownloadSettings) -> Self {
+        mullvad_types::settings::RelayListDownloadSettings {
+            bandwidth_limit: settings.bandwidth_limit,
+            metered_network: settings.metered_network,
+        }
+    }
+}
+
+impl From<mullvad_types::settings::SettingsMigration> for proto::SettingsMigration {
+    fn from(migration: mullvad_types::settings::SettingsMigration) -> Self {
+        proto::SettingsMigration {
+            from_version: migration.from_version,
+            to_version: migration.to_version,
+        }
+    }
+}
+
+impl From<proto::SettingsMigration> for mullvad_types::settings::SettingsMigration {
+    fn from(migration: proto::SettingsMigration) -> Self {
+        mullvad_types::settings::SettingsMigration {
+            from_version: migration.from_version,
+            to_version: migration.to_version,
+        }
+    }
+}
+
+impl From<&[mullvad_types::settings::LockableSetting]> for proto::LockedSettings {
+    fn from(settings: &[mullvad_types::settings::LockableSetting]) -> Self {
+        use mullvad_types::settings::LockableSetting;
+        use proto::locked_settings::Setting;
+
+        let settings = settings
+            .iter()
+            .map(|setting| match setting {
+                LockableSetting::AllowLan => Setting::AllowLan,
+                LockableSetting::LockdownMode => Setting::LockdownMode,
+                LockableSetting::AutoConnect => Setting::AutoConnect,
+                LockableSetting::RelaySettings => Setting::RelaySettings,
+                LockableSetting::ObfuscationSettings => Setting::ObfuscationSettings,
+                LockableSetting::DnsOptions => Setting::DnsOptions,
+                LockableSetting::TunnelOptions => Setting::TunnelOptions,
+                LockableSetting::CustomLists => Setting::CustomLists,
+                LockableSetting::ApiAccessMethods => Setting::ApiAccessMethods,
+                LockableSetting::SplitTunnel => Setting::SplitTunnel,
+                LockableSetting::General => Setting::General,
+            })
+            .map(i32::from)
+            .collect();
+        proto::LockedSettings { settings }
+    }
+}
+
+impl TryFrom<proto::LockedSettings> for Vec<mullvad_types::settings::LockableSetting> {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(settings: proto::LockedSettings) -> Result<Self, Self::Error> {
+        use mullvad_types::settings::LockableSetting;
+        use proto::locked_settings::Setting;
+
+        settings
+            .settings
+            .into_iter()
+            .map(|setting| match Setting::try_from(setting) {
+                Ok(Setting::AllowLan) => Ok(LockableSetting::AllowLan),
+                Ok(Setting::LockdownMode) => Ok(LockableSetting::LockdownMode),
+                Ok(Setting::AutoConnect) => Ok(LockableSetting::AutoConnect),
+                Ok(Setting::RelaySettings) => Ok(LockableSetting::RelaySettings),
+                Ok(Setting::ObfuscationSettings) => Ok(LockableSetting::ObfuscationSettings),
+                Ok(Setting::DnsOptions) => Ok(LockableSetting::DnsOptions),
+                Ok(Setting::TunnelOptions) => Ok(LockableSetting::TunnelOptions),
+                Ok(Setting::CustomLists) => Ok(LockableSetting::CustomLists),
+                Ok(Setting::ApiAccessMethods) => Ok(LockableSetting::ApiAccessMethods),
+                Ok(Setting::SplitTunnel) => Ok(LockableSetting::SplitTunnel),
+                Ok(Setting::General) => Ok(LockableSetting::General),
+                Err(_) => Err(FromProtobufTypeError::InvalidArgument(
+                    "invalid lockable setting",
+                )),
+            })
+            .collect()
+    }
+}
+
+impl From<mullvad_types::settings::SettingsSnapshot> for proto::SettingsSnapshot {
+    fn from(snapshot: mullvad_types::settings::SettingsSnapshot) -> Self {
+        proto::SettingsSnapshot {
+            label: snapshot.label,
+            created: Some(Timestamp {
+                seconds: snapshot.created.timestamp(),
+                nanos: 0,
+            }),
+        }
+    }
+}
+
+impl TryFrom<proto::SettingsSnapshot> for mullvad_types::settings::SettingsSnapshot {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(snapshot: proto::SettingsSnapshot) -> Result<Self, Self::Error> {
+        let created = snapshot
+            .created
+            .ok_or(FromProtobufTypeError::InvalidArgument(
+                "missing 'created' timestamp",
+            ))?;
+        let created = DateTime::from_timestamp(created.seconds, created.nanos as u32)
+            .ok_or(FromProtobufTypeError::InvalidArgument("invalid timestamp"))?;
+
+        Ok(mullvad_types::settings::SettingsSnapshot {
+            label: snapshot.label,
+            created,
+        })
+    }
+}
+
 #[cfg(any(windows, target_os = "android", target_os = "macos"))]
-impl From<proto::SplitTunnelSettings> for mullvad_types::settings::SplitTunnelSettings {
-    fn from(value: proto::SplitTunnelSettings) -> Self {
+impl TryFrom<proto::SplitTunnelSettings> for mullvad_types::settings::SplitTunnelSettings {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(value: proto::SplitTunnelSettings) -> Result<Self, Self::Error> {
         use mullvad_types::settings::{SplitApp, SplitTunnelSettings};
-        SplitTunnelSettings {
+        let allowed_subnets = value
+            .allowed_subnets
+            .into_iter()
+            .map(<(SplitApp, Vec<_>)>::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(SplitTunnelSettings {
             enable_exclusions: value.enable_exclusions,
             apps: value.apps.into_iter().map(SplitApp::from).collect(),
-        }
+            allowed_subnets,
+        })
+    }
+}
+
+#[cfg(any(windows, target_os = "android", target_os = "macos"))]
+impl TryFrom<proto::SplitTunnelAppSubnets>
+    for (mullvad_types::settings::SplitApp, Vec<ipnetwork::IpNetwork>)
+{
+    type Error = FromProtobufTypeError;
+
+    fn try_from(value: proto::SplitTunnelAppSubnets) -> Result<Self, Self::Error> {
+        let subnets = value
+            .subnets
+            .iter()
+            .map(|subnet| subnet.parse())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid subnet"))?;
+        mullvad_types::settings::SplitTunnelSettings::validate_allowed_subnets(&subnets)
+            .map_err(|_| FromProtobufTypeError::InvalidArgument("subnet has host bits set"))?;
+        Ok((mullvad_types::settings::SplitApp::from(value.app), subnets))
     }
 }
 
@@ -215,6 +581,17 @@ impl TryFrom<proto::TunnelOptions> for mullvad_types::settings::TunnelOptions {
         Ok(Self {
             wireguard: mullvad_types::wireguard::TunnelOptions {
                 mtu: options.mtu.map(|mtu| mtu as u16),
+                keepalive: options
+                    .keepalive
+                    .map(u16::try_from)
+                    .transpose()
+                    .map_err(|_| {
+                        FromProtobufTypeError::InvalidArgument("invalid keepalive interval")
+                    })?,
+                bind_address: options
+                    .bind_address
+                    .map(|address| arg_from_str(&address, "invalid bind address"))
+                    .transpose()?,
                 rotation_interval: options
                     .rotation_interval
                     .map(std::time::Duration::try_from)
@@ -251,6 +628,94 @@ impl TryFrom<proto::TunnelOptions> for mullvad_types::settings::TunnelOptions {
     }
 }
 
+impl From<mullvad_types::settings::EffectiveDnsServer> for proto::EffectiveDnsServer {
+    fn from(server: mullvad_types::settings::EffectiveDnsServer) -> Self {
+        use mullvad_types::settings::DnsServerSource;
+        use proto::effective_dns_server::Source;
+
+        let source = match server.source {
+            DnsServerSource::Relay => Source::Relay,
+            DnsServerSource::ContentBlocker => Source::ContentBlocker,
+            DnsServerSource::Custom => Source::Custom,
+            DnsServerSource::SplitDns => Source::SplitDns,
+        };
+        proto::EffectiveDnsServer {
+            address: server.address.to_string(),
+            source: source as i32,
+        }
+    }
+}
+
+impl TryFrom<proto::EffectiveDnsServer> for mullvad_types::settings::EffectiveDnsServer {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(server: proto::EffectiveDnsServer) -> Result<Self, Self::Error> {
+        use mullvad_types::settings::DnsServerSource;
+        use proto::effective_dns_server::Source;
+
+        let source = match Source::try_from(server.source) {
+            Ok(Source::Relay) => DnsServerSource::Relay,
+            Ok(Source::ContentBlocker) => DnsServerSource::ContentBlocker,
+            Ok(Source::Custom) => DnsServerSource::Custom,
+            Ok(Source::SplitDns) => DnsServerSource::SplitDns,
+            Err(_) => {
+                return Err(FromProtobufTypeError::InvalidArgument(
+                    "invalid DNS server source",
+                ));
+            }
+        };
+        Ok(mullvad_types::settings::EffectiveDnsServer {
+            address: arg_from_str(&server.address, "invalid DNS server address")?,
+            source,
+        })
+    }
+}
+
+impl From<mullvad_types::settings::DnsServerTestResult> for proto::DnsServerTestResult {
+    fn from(result: mullvad_types::settings::DnsServerTestResult) -> Self {
+        use mullvad_types::settings::DnsServerReachability;
+        use proto::dns_server_test_result::Reachability;
+
+        let reachability = match result.reachability {
+            DnsServerReachability::Answered => Reachability::Answered,
+            DnsServerReachability::RecursionRefused => Reachability::RecursionRefused,
+            DnsServerReachability::Failed => Reachability::Failed,
+            DnsServerReachability::NoResponse => Reachability::NoResponse,
+            DnsServerReachability::Blocked => Reachability::Blocked,
+        };
+        proto::DnsServerTestResult {
+            address: result.address.to_string(),
+            reachability: reachability as i32,
+        }
+    }
+}
+
+impl TryFrom<proto::DnsServerTestResult> for mullvad_types::settings::DnsServerTestResult {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(result: proto::DnsServerTestResult) -> Result<Self, Self::Error> {
+        use mullvad_types::settings::DnsServerReachability;
+        use proto::dns_server_test_result::Reachability;
+
+        let reachability = match Reachability::try_from(result.reachability) {
+            Ok(Reachability::Answered) => DnsServerReachability::Answered,
+            Ok(Reachability::RecursionRefused) => DnsServerReachability::RecursionRefused,
+            Ok(Reachability::Failed) => DnsServerReachability::Failed,
+            Ok(Reachability::NoResponse) => DnsServerReachability::NoResponse,
+            Ok(Reachability::Blocked) => DnsServerReachability::Blocked,
+            Err(_) => {
+                return Err(FromProtobufTypeError::InvalidArgument(
+                    "invalid DNS server reachability",
+                ));
+            }
+        };
+        Ok(mullvad_types::settings::DnsServerTestResult {
+            address: arg_from_str(&result.address, "invalid DNS server address")?,
+            reachability,
+        })
+    }
+}
+
 impl TryFrom<proto::DnsOptions> for mullvad_types::settings::DnsOptions {
     type Error = FromProtobufTypeError;
 
@@ -258,7 +723,8 @@ impl TryFrom<proto::DnsOptions> for mullvad_types::settings::DnsOptions {
         use mullvad_types::settings::{
             CustomDnsOptions as MullvadCustomDnsOptions,
             DefaultDnsOptions as MullvadDefaultDnsOptions, DnsOptions as MullvadDnsOptions,
-            DnsState as MullvadDnsState,
+            DnsResolverSource as MullvadDnsResolverSource, DnsState as MullvadDnsState,
+            SplitDnsRule as MullvadSplitDnsRule,
         };
 
         let state = match proto::dns_options::DnsState::try_from(options.state) {
@@ -284,7 +750,7 @@ impl TryFrom<proto::DnsOptions> for mullvad_types::settings::DnsOptions {
                     "missing default DNS options",
                 ))?;
 
-        Ok(MullvadDnsOptions {
+        let mut options = MullvadDnsOptions {
             state,
             default_options: MullvadDefaultDnsOptions {
                 block_ads: default_options.block_ads,
@@ -305,7 +771,47 @@ impl TryFrom<proto::DnsOptions> for mullvad_types::settings::DnsOptions {
                     })
                     .collect::<Result<Vec<_>, _>>()?,
             },
-        })
+            split_dns: options
+                .split_dns
+                .into_iter()
+                .map(|rule| {
+                    Ok(MullvadSplitDnsRule {
+                        domain: rule.domain,
+                        servers: rule
+                            .servers
+                            .into_iter()
+                            .map(|addr| {
+                                addr.parse().map_err(|_| {
+                                    FromProtobufTypeError::InvalidArgument("invalid IP address")
+                                })
+                            })
+                            .collect::<Result<Vec<_>, _>>()?,
+                    })
+                })
+                .collect::<Result<Vec<_>, FromProtobufTypeError>>()?,
+            resolver_order: options
+                .resolver_order
+                .into_iter()
+                .map(
+                    |source| match proto::dns_options::ResolverSource::try_from(source) {
+                        Ok(proto::dns_options::ResolverSource::CustomServers) => {
+                            Ok(MullvadDnsResolverSource::Custom)
+                        }
+                        Ok(proto::dns_options::ResolverSource::Mullvad) => {
+                            Ok(MullvadDnsResolverSource::Mullvad)
+                        }
+                        Err(_) => Err(FromProtobufTypeError::InvalidArgument(
+                            "invalid DNS resolver source",
+                        )),
+                    },
+                )
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        // Clients that predate `resolver_order` never set it
+        if options.resolver_order.is_empty() {
+            options.resolver_order = MullvadDnsOptions::default().resolver_order;
+        }
+        Ok(options)
     }
 }
 
@@ -332,3 +838,20 @@ impl From<mullvad_types::settings::Recent> for proto::Recent {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mullvad_types::settings::{DnsOptions, DnsResolverSource};
+
+    /// Clients that predate `resolver_order` must still be able to set DNS options
+    #[test]
+    fn test_dns_options_without_resolver_order() {
+        let mut options = proto::DnsOptions::from(&DnsOptions::default());
+        options.resolver_order.clear();
+
+        let options = DnsOptions::try_from(options).unwrap();
+        assert_eq!(options.resolver_order, DnsOptions::default().resolver_order);
+        assert!(DnsResolverSource::validate_order(&options.resolver_order).is_ok());
+    }
+}
@@ -1,6 +1,42 @@
 use crate::types::{FromProtobufTypeError, proto};
 use talpid_types::net::IpVersion;
 
+impl From<mullvad_types::states::RestartReason> for proto::RestartReason {
+    fn from(reason: mullvad_types::states::RestartReason) -> Self {
+        use mullvad_types::states::RestartReason;
+        use proto::restart_reason::Reason;
+
+        let reason = match reason {
+            RestartReason::CleanShutdown => Reason::CleanShutdown,
+            RestartReason::Crash => Reason::Crash,
+            RestartReason::Upgrade => Reason::Upgrade,
+            RestartReason::PrepareRestart => Reason::PrepareRestart,
+        };
+        proto::RestartReason {
+            reason: i32::from(reason),
+        }
+    }
+}
+
+impl TryFrom<proto::RestartReason> for mullvad_types::states::RestartReason {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(reason: proto::RestartReason) -> Result<Self, Self::Error> {
+        use mullvad_types::states::RestartReason;
+        use proto::restart_reason::Reason;
+
+        match Reason::try_from(reason.reason) {
+            Ok(Reason::CleanShutdown) => Ok(RestartReason::CleanShutdown),
+            Ok(Reason::Crash) => Ok(RestartReason::Crash),
+            Ok(Reason::Upgrade) => Ok(RestartReason::Upgrade),
+            Ok(Reason::PrepareRestart) => Ok(RestartReason::PrepareRestart),
+            Err(_) => Err(FromProtobufTypeError::InvalidArgument(
+                "invalid restart reason",
+            )),
+        }
+    }
+}
+
 impl From<mullvad_types::states::TunnelState> for proto::TunnelState {
     fn from(state: mullvad_types::states::TunnelState) -> Self {
         use mullvad_types::states::TunnelState as MullvadTunnelState;
@@ -37,12 +73,24 @@ impl From<mullvad_types::states::TunnelState> for proto::TunnelState {
                 location: disconnected_location,
                 #[cfg(not(target_os = "android"))]
                 locked_down,
+                disconnect_reason,
             } => proto::tunnel_state::State::Disconnected(proto::tunnel_state::Disconnected {
                 disconnected_location: disconnected_location.map(proto::GeoIpLocation::from),
                 #[cfg(not(target_os = "android"))]
                 locked_down,
                 #[cfg(target_os = "android")]
                 locked_down: false,
+                disconnect_reason: disconnect_reason
+                    .map(|reason| i32::from(proto::DisconnectReason::from(reason))),
+                paused_until: match disconnect_reason {
+                    Some(mullvad_types::states::DisconnectReason::Paused { until }) => {
+                        Some(prost_types::Timestamp {
+                            seconds: until.timestamp(),
+                            nanos: 0,
+                        })
+                    }
+                    _ => None,
+                },
             }),
             MullvadTunnelState::Connecting {
                 endpoint,
@@ -110,6 +158,12 @@ impl From<mullvad_types::states::TunnelState> for proto::TunnelState {
                             talpid_tunnel::ErrorStateCause::IsOffline => {
                                 i32::from(Cause::IsOffline)
                             }
+                            talpid_tunnel::ErrorStateCause::ConnectTimeout => {
+                                i32::from(Cause::ConnectTimeout)
+                            }
+                            talpid_tunnel::ErrorStateCause::ReconnectLimitReached => {
+                                i32::from(Cause::ReconnectLimitReached)
+                            }
                             #[cfg(target_os = "android")]
                             talpid_tunnel::ErrorStateCause::NotPrepared => {
                                 i32::from(Cause::NotPrepared)
@@ -188,6 +242,9 @@ impl From<mullvad_types::states::TunnelState> for proto::TunnelState {
                                     talpid_tunnel::ParameterGenerationError::NoMatchingRelay => {
                                         i32::from(GenerationError::NoMatchingRelay)
                                     }
+                                talpid_tunnel::ParameterGenerationError::NoMatchingDaitaRelay => {
+                                    i32::from(GenerationError::NoMatchingDaitaRelay)
+                                }
                                 talpid_tunnel::ParameterGenerationError::NoMatchingBridgeRelay => {
                                     i32::from(GenerationError::NoMatchingBridgeRelay)
                                 }
@@ -276,12 +333,18 @@ impl TryFrom<proto::TunnelState> for mullvad_types::states::TunnelState {
             Some(proto::tunnel_state::State::Disconnected(proto::tunnel_state::Disconnected {
                 disconnected_location,
                 locked_down,
+                disconnect_reason,
+                paused_until,
             })) => MullvadState::Disconnected {
                 location: disconnected_location
                     .map(mullvad_types::location::GeoIpLocation::try_from)
                     .transpose()?,
                 #[cfg(not(target_os = "android"))]
                 locked_down,
+                disconnect_reason: match disconnect_reason {
+                    Some(reason) => try_disconnect_reason_from_i32(reason, paused_until)?,
+                    None => None,
+                },
             },
             Some(proto::tunnel_state::State::Connecting(proto::tunnel_state::Connecting {
                 relay_info:
@@ -367,6 +430,12 @@ impl TryFrom<proto::TunnelState> for mullvad_types::states::TunnelState {
                     Ok(proto::error_state::Cause::IsOffline) => {
                         talpid_tunnel::ErrorStateCause::IsOffline
                     }
+                    Ok(proto::error_state::Cause::ConnectTimeout) => {
+                        talpid_tunnel::ErrorStateCause::ConnectTimeout
+                    }
+                    Ok(proto::error_state::Cause::ReconnectLimitReached) => {
+                        talpid_tunnel::ErrorStateCause::ReconnectLimitReached
+                    }
                     Ok(proto::error_state::Cause::SetDnsError) => {
                         talpid_tunnel::ErrorStateCause::SetDnsError
                     }
@@ -399,6 +468,7 @@ impl TryFrom<proto::TunnelState> for mullvad_types::states::TunnelState {
                             Ok(proto::error_state::GenerationError::NetworkIpv4Unavailable) => talpid_tunnel::ParameterGenerationError::IpVersionUnavailable { family: IpVersion::V4 },
                             Ok(proto::error_state::GenerationError::NetworkIpv6Unavailable) => talpid_tunnel::ParameterGenerationError::IpVersionUnavailable { family: IpVersion::V6 },
                             Ok(proto::error_state::GenerationError::NoMatchingRelay) => talpid_tunnel::ParameterGenerationError::NoMatchingRelay,
+                            Ok(proto::error_state::GenerationError::NoMatchingDaitaRelay) => talpid_tunnel::ParameterGenerationError::NoMatchingDaitaRelay,
                             _ => return Err(FromProtobufTypeError::InvalidArgument(
                                 "invalid parameter error",
                             )),
@@ -468,3 +538,98 @@ fn try_firewall_policy_error_from_i32(
         )),
     }
 }
+
+impl From<mullvad_types::states::DisconnectReason> for proto::DisconnectReason {
+    fn from(reason: mullvad_types::states::DisconnectReason) -> Self {
+        use mullvad_types::states::DisconnectReason;
+        match reason {
+            DisconnectReason::AccountExpired => proto::DisconnectReason::AccountExpired,
+            DisconnectReason::LoggedOut => proto::DisconnectReason::LoggedOut,
+            DisconnectReason::Schedule => proto::DisconnectReason::Schedule,
+            DisconnectReason::TrustedNetwork => proto::DisconnectReason::TrustedNetwork,
+            DisconnectReason::Paused { .. } => proto::DisconnectReason::Paused,
+        }
+    }
+}
+
+/// Unspecified and unknown reasons, e.g. ones added in a newer daemon, are treated as no known
+/// reason.
+fn try_disconnect_reason_from_i32(
+    reason: i32,
+    paused_until: Option<prost_types::Timestamp>,
+) -> Result<Option<mullvad_types::states::DisconnectReason>, FromProtobufTypeError> {
+    use mullvad_types::states::DisconnectReason;
+    let reason = match proto::DisconnectReason::try_from(reason) {
+        Ok(proto::DisconnectReason::Unspecified) => return Ok(None),
+        Ok(proto::DisconnectReason::AccountExpired) => DisconnectReason::AccountExpired,
+        Ok(proto::DisconnectReason::LoggedOut) => DisconnectReason::LoggedOut,
+        Ok(proto::DisconnectReason::Schedule) => DisconnectReason::Schedule,
+        Ok(proto::DisconnectReason::TrustedNetwork) => DisconnectReason::TrustedNetwork,
+        Ok(proto::DisconnectReason::Paused) => {
+            let until = paused_until
+                .and_then(|until| chrono::DateTime::from_timestamp(until.seconds, 0))
+                .ok_or(FromProtobufTypeError::InvalidArgument(
+                    "missing or invalid pause end time",
+                ))?;
+            DisconnectReason::Paused { until }
+        }
+        Err(_) => {
+            log::warn!("Ignoring unknown disconnect reason: {reason}");
+            return Ok(None);
+        }
+    };
+    Ok(Some(reason))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mullvad_types::states::DisconnectReason;
+
+    /// The end of a pause must survive a round trip through the proto type
+    #[test]
+    fn test_paused_round_trip() {
+        let until = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let state = mullvad_types::states::TunnelState::Disconnected {
+            location: None,
+            #[cfg(not(target_os = "android"))]
+            locked_down: false,
+            disconnect_reason: Some(DisconnectReason::Paused { until }),
+        };
+
+        let state =
+            mullvad_types::states::TunnelState::try_from(proto::TunnelState::from(state)).unwrap();
+        assert!(matches!(
+            state,
+            mullvad_types::states::TunnelState::Disconnected {
+                disconnect_reason: Some(DisconnectReason::Paused { until: converted }),
+                ..
+            } if converted == until
+        ));
+    }
+
+    #[test]
+    fn test_paused_without_end() {
+        let reason = i32::from(proto::DisconnectReason::Paused);
+        assert!(try_disconnect_reason_from_i32(reason, None).is_err());
+    }
+
+    /// Unspecified and unknown reasons must not make the tunnel state fail to convert
+    #[test]
+    fn test_unknown_disconnect_reason() {
+        let unspecified = i32::from(proto::DisconnectReason::Unspecified);
+        assert_eq!(
+            try_disconnect_reason_from_i32(unspecified, None).unwrap(),
+            None
+        );
+        assert_eq!(
+            try_disconnect_reason_from_i32(i32::MAX, None).unwrap(),
+            None
+        );
+        assert_eq!(
+            try_disconnect_reason_from_i32(i32::from(proto::DisconnectReason::Schedule), None)
+                .unwrap(),
+            Some(DisconnectReason::Schedule)
+        );
+    }
+}
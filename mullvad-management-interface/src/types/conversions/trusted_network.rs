@@ -0,0 +1,69 @@
+use crate::types::{FromProtobufTypeError, proto};
+use mullvad_types::trusted_network::{TrustedNetwork, TrustedNetworksSettings};
+
+impl From<&TrustedNetworksSettings> for proto::TrustedNetworks {
+    fn from(settings: &TrustedNetworksSettings) -> Self {
+        proto::TrustedNetworks {
+            networks: settings
+                .networks
+                .iter()
+                .map(proto::TrustedNetwork::from)
+                .collect(),
+            disconnect: settings.disconnect,
+        }
+    }
+}
+
+impl From<&TrustedNetwork> for proto::TrustedNetwork {
+    fn from(network: &TrustedNetwork) -> Self {
+        use proto::trusted_network::Network;
+
+        let network = match network {
+            TrustedNetwork::SsidHash(hash) => Network::SsidHash(hash.clone()),
+            TrustedNetwork::GatewayMac(mac) => Network::GatewayMac(mac.to_vec()),
+            TrustedNetwork::GatewaySubnet(subnet) => Network::GatewaySubnet(subnet.to_string()),
+        };
+        proto::TrustedNetwork {
+            network: Some(network),
+        }
+    }
+}
+
+impl TryFrom<proto::TrustedNetworks> for TrustedNetworksSettings {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(settings: proto::TrustedNetworks) -> Result<Self, Self::Error> {
+        let networks = settings
+            .networks
+            .into_iter()
+            .map(TrustedNetwork::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(TrustedNetworksSettings {
+            networks,
+            disconnect: settings.disconnect,
+        })
+    }
+}
+
+impl TryFrom<proto::TrustedNetwork> for TrustedNetwork {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(network: proto::TrustedNetwork) -> Result<Self, Self::Error> {
+        use proto::trusted_network::Network;
+
+        match network.network {
+            Some(Network::SsidHash(hash)) => TrustedNetwork::ssid_hash(hash)
+                .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid SSID hash")),
+            Some(Network::GatewayMac(mac)) => <[u8; 6]>::try_from(mac)
+                .map(TrustedNetwork::GatewayMac)
+                .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid gateway MAC address")),
+            Some(Network::GatewaySubnet(subnet)) => subnet
+                .parse()
+                .map(TrustedNetwork::GatewaySubnet)
+                .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid gateway subnet")),
+            None => Err(FromProtobufTypeError::InvalidArgument(
+                "missing trusted network",
+            )),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use crate::types::{FromProtobufTypeError, proto};
+use mullvad_types::usage_stats::UsageStats;
+
+impl From<UsageStats> for proto::UsageStats {
+    fn from(stats: UsageStats) -> Self {
+        proto::UsageStats {
+            connected_time: Some(
+                prost_types::Duration::try_from(stats.connected_time)
+                    .expect("Failed to convert std::time::Duration to prost_types::Duration"),
+            ),
+            tx_bytes: stats.tx_bytes,
+            rx_bytes: stats.rx_bytes,
+            sessions: stats.sessions,
+            favorite_relay: stats.favorite_relay,
+        }
+    }
+}
+
+impl TryFrom<proto::UsageStats> for UsageStats {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(stats: proto::UsageStats) -> Result<Self, Self::Error> {
+        let connected_time = stats
+            .connected_time
+            .ok_or(FromProtobufTypeError::InvalidArgument(
+                "missing connected time",
+            ))
+            .and_then(|time| {
+                std::time::Duration::try_from(time)
+                    .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid connected time"))
+            })?;
+
+        Ok(UsageStats {
+            connected_time,
+            tx_bytes: stats.tx_bytes,
+            rx_bytes: stats.rx_bytes,
+            sessions: stats.sessions,
+            favorite_relay: stats.favorite_relay,
+        })
+    }
+}
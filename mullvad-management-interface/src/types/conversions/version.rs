@@ -66,6 +66,35 @@ impl TryFrom<proto::SuggestedUpgrade> for SuggestedUpgrade {
     }
 }
 
+impl From<ActiveDownload> for proto::ActiveDownload {
+    fn from(download: ActiveDownload) -> Self {
+        Self {
+            id: download.id,
+            version: download.version.to_string(),
+            url: download.url,
+            progress: download.progress,
+            prefetch: download.prefetch,
+        }
+    }
+}
+
+impl TryFrom<proto::ActiveDownload> for ActiveDownload {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(download: proto::ActiveDownload) -> Result<Self, Self::Error> {
+        let version = download.version.parse().map_err(|_err| {
+            FromProtobufTypeError::InvalidArgument("invalid Mullvad app version")
+        })?;
+        Ok(Self {
+            id: download.id,
+            version,
+            url: download.url,
+            progress: download.progress,
+            prefetch: download.prefetch,
+        })
+    }
+}
+
 impl From<AppUpgradeEvent> for proto::AppUpgradeEvent {
     fn from(upgrade_event: AppUpgradeEvent) -> Self {
         type ProtoEvent = proto::app_upgrade_event::Event;
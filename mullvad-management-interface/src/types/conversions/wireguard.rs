@@ -36,6 +36,132 @@ impl TryFrom<proto::PublicKey> for mullvad_types::wireguard::PublicKey {
     }
 }
 
+impl From<mullvad_types::wireguard::WireguardKeyAge> for proto::WireguardKeyAge {
+    fn from(key_age: mullvad_types::wireguard::WireguardKeyAge) -> Self {
+        proto::WireguardKeyAge {
+            created: Some(Timestamp {
+                seconds: key_age.created.timestamp(),
+                nanos: 0,
+            }),
+            age: Some(
+                prost_types::Duration::try_from(key_age.age)
+                    .expect("Failed to convert std::time::Duration to prost_types::Duration"),
+            ),
+        }
+    }
+}
+
+impl TryFrom<proto::WireguardKeyAge> for mullvad_types::wireguard::WireguardKeyAge {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(key_age: proto::WireguardKeyAge) -> Result<Self, Self::Error> {
+        let created = key_age
+            .created
+            .ok_or(FromProtobufTypeError::InvalidArgument(
+                "missing 'created' timestamp",
+            ))?;
+        let created = DateTime::from_timestamp(created.seconds, created.nanos as u32)
+            .ok_or(FromProtobufTypeError::InvalidArgument("invalid timestamp"))?;
+        let age = key_age
+            .age
+            .ok_or(FromProtobufTypeError::InvalidArgument("missing key age"))
+            .and_then(|age| {
+                std::time::Duration::try_from(age)
+                    .map_err(|_| FromProtobufTypeError::InvalidArgument("invalid key age"))
+            })?;
+
+        Ok(mullvad_types::wireguard::WireguardKeyAge { created, age })
+    }
+}
+
+impl From<talpid_types::net::wireguard::HandshakeDiagnostics> for proto::HandshakeDiagnostics {
+    fn from(diagnostics: talpid_types::net::wireguard::HandshakeDiagnostics) -> Self {
+        use proto::handshake_diagnostics::Outcome;
+        use talpid_types::net::wireguard::HandshakeOutcome;
+
+        let outcome = match diagnostics.outcome {
+            HandshakeOutcome::Timeout => Outcome::Timeout,
+            HandshakeOutcome::Rejected => Outcome::Rejected,
+            HandshakeOutcome::Completed => Outcome::Completed,
+        };
+        proto::HandshakeDiagnostics {
+            initiation_sent: diagnostics.initiation_sent,
+            response_received: diagnostics.response_received,
+            outcome: i32::from(outcome),
+        }
+    }
+}
+
+impl TryFrom<proto::HandshakeDiagnostics> for talpid_types::net::wireguard::HandshakeDiagnostics {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(diagnostics: proto::HandshakeDiagnostics) -> Result<Self, Self::Error> {
+        use proto::handshake_diagnostics::Outcome;
+        use talpid_types::net::wireguard::HandshakeOutcome;
+
+        let outcome = match Outcome::try_from(diagnostics.outcome) {
+            Ok(Outcome::Timeout) => HandshakeOutcome::Timeout,
+            Ok(Outcome::Rejected) => HandshakeOutcome::Rejected,
+            Ok(Outcome::Completed) => HandshakeOutcome::Completed,
+            Err(_) => {
+                return Err(FromProtobufTypeError::InvalidArgument(
+                    "invalid handshake outcome",
+                ));
+            }
+        };
+        Ok(talpid_types::net::wireguard::HandshakeDiagnostics {
+            initiation_sent: diagnostics.initiation_sent,
+            response_received: diagnostics.response_received,
+            outcome,
+        })
+    }
+}
+
+impl From<mullvad_types::wireguard::TunnelMtuInfo> for proto::TunnelMtuInfo {
+    fn from(info: mullvad_types::wireguard::TunnelMtuInfo) -> Self {
+        proto::TunnelMtuInfo {
+            configured_mtu: info.configured_mtu.map(u32::from),
+            tunnel_mtu: info.tunnel_mtu.map(u32::from),
+            wireguard_overhead: u32::from(info.wireguard_overhead),
+            obfuscation_overhead: u32::from(info.obfuscation_overhead),
+        }
+    }
+}
+
+impl TryFrom<proto::TunnelMtuInfo> for mullvad_types::wireguard::TunnelMtuInfo {
+    type Error = FromProtobufTypeError;
+
+    fn try_from(info: proto::TunnelMtuInfo) -> Result<Self, Self::Error> {
+        let to_u16 = |value: u32| {
+            u16::try_from(value).map_err(|_| FromProtobufTypeError::InvalidArgument("invalid MTU"))
+        };
+        Ok(mullvad_types::wireguard::TunnelMtuInfo {
+            configured_mtu: info.configured_mtu.map(to_u16).transpose()?,
+            tunnel_mtu: info.tunnel_mtu.map(to_u16).transpose()?,
+            wireguard_overhead: to_u16(info.wireguard_overhead)?,
+            obfuscation_overhead: to_u16(info.obfuscation_overhead)?,
+        })
+    }
+}
+
+impl From<mullvad_types::wireguard::TunnelCryptoInfo> for proto::TunnelCryptoInfo {
+    fn from(info: mullvad_types::wireguard::TunnelCryptoInfo) -> Self {
+        proto::TunnelCryptoInfo {
+            handshake_protocol: info.handshake_protocol,
+            quantum_resistant: info.quantum_resistant,
+        }
+    }
+}
+
+impl From<proto::TunnelCryptoInfo> for mullvad_types::wireguard::TunnelCryptoInfo {
+    fn from(info: proto::TunnelCryptoInfo) -> Self {
+        mullvad_types::wireguard::TunnelCryptoInfo {
+            handshake_protocol: info.handshake_protocol,
+            quantum_resistant: info.quantum_resistant,
+        }
+    }
+}
+
 impl From<mullvad_types::wireguard::QuantumResistantState> for proto::QuantumResistantState {
     fn from(state: mullvad_types::wireguard::QuantumResistantState) -> Self {
         match state {
@@ -73,6 +199,7 @@ impl From<mullvad_types::wireguard::DaitaSettings> for proto::DaitaSettings {
         proto::DaitaSettings {
             enabled: settings.enabled,
             direct_only: !settings.use_multihop_if_necessary,
+            required: settings.required,
         }
     }
 }
@@ -83,6 +210,7 @@ impl From<proto::DaitaSettings> for mullvad_types::wireguard::DaitaSettings {
         mullvad_types::wireguard::DaitaSettings {
             enabled: settings.enabled,
             use_multihop_if_necessary: !settings.direct_only,
+            required: settings.required,
         }
     }
 }
@@ -20,6 +20,9 @@ const EXTRA_BYTES: usize = 32 * 1024;
 /// Fit five logs plus some system information in the report.
 const REPORT_MAX_SIZE: usize = (5 * LOG_MAX_READ_BYTES) + EXTRA_BYTES;
 
+/// Maximum size of the logs returned by [collect_daemon_logs]
+pub const DAEMON_LOGS_MAX_SIZE: usize = (4 * LOG_MAX_READ_BYTES) + EXTRA_BYTES;
+
 /// Field delimiter in generated problem report
 const LOG_DELIMITER: &str = "====================";
 
@@ -213,6 +216,39 @@ impl ProblemReportCollector {
     }
 }
 
+/// Collect the most recent output of the daemon logs in `log_dir`, without any system
+/// information. If `redact` is set, account numbers, network information, and all strings in
+/// `redact_custom_strings` are removed. The result is truncated to [DAEMON_LOGS_MAX_SIZE] bytes.
+pub fn collect_daemon_logs(
+    log_dir: &Path,
+    redact: bool,
+    redact_custom_strings: Vec<String>,
+) -> String {
+    let mut logs = ProblemReport::without_metadata(redact, redact_custom_strings);
+    match list_logs(log_dir) {
+        Ok(daemon_logs) => {
+            let mut paths = vec![];
+            for log in daemon_logs {
+                match log {
+                    Ok(path) => paths.push(path),
+                    Err(error) => logs.add_error("Unable to get log path", &error),
+                }
+            }
+            // Put the current log before the older ones
+            paths.sort();
+            logs.add_logs(paths);
+        }
+        Err(error) => logs.add_error("Failed to list logs in daemon log directory", &error),
+    }
+
+    let mut output = vec![];
+    logs.write_logs_to(&mut output)
+        .expect("writing to a Vec cannot fail");
+    let mut output = String::from_utf8_lossy(&output).into_owned();
+    truncate_at_char_boundary(&mut output, DAEMON_LOGS_MAX_SIZE);
+    output
+}
+
 /// A [Write] with a named source.
 pub struct WriteSource<W: Write> {
     pub write: W,
@@ -420,6 +456,7 @@ struct ProblemReport {
     metadata: BTreeMap<String, String>,
     logs: Vec<(String, String)>,
     log_paths: HashSet<PathBuf>,
+    redact_logs: bool,
     redact_custom_strings: Vec<String>,
 }
 
@@ -433,6 +470,20 @@ impl ProblemReport {
             metadata: metadata::collect(),
             logs: Vec::new(),
             log_paths: HashSet::new(),
+            redact_logs: true,
+            redact_custom_strings,
+        }
+    }
+
+    /// Creates a report that only contains logs. Nothing is redacted unless `redact_logs` is set.
+    fn without_metadata(redact_logs: bool, mut redact_custom_strings: Vec<String>) -> Self {
+        redact_custom_strings.retain(|redact| !redact.is_empty());
+
+        ProblemReport {
+            metadata: BTreeMap::new(),
+            logs: Vec::new(),
+            log_paths: HashSet::new(),
+            redact_logs,
             redact_custom_strings,
         }
     }
@@ -482,6 +533,9 @@ impl ProblemReport {
     }
 
     fn redact(&self, input: &str) -> String {
+        if !self.redact_logs {
+            return input.to_owned();
+        }
         let out1 = Self::redact_account_number(input);
         let out2 = Self::redact_home_dir(&out1);
         let out3 = Self::redact_network_info(&out2);
@@ -542,6 +596,10 @@ impl ProblemReport {
         }
         // Write empty line to separate metadata from first log
         write_line!(output)?;
+        self.write_logs_to(output)
+    }
+
+    fn write_logs_to<W: Write>(&self, mut output: W) -> io::Result<()> {
         for (label, content) in &self.logs {
             write_line!(output, "{}", LOG_DELIMITER)?;
             write_line!(output, "Log: {}", label)?;
@@ -674,6 +732,17 @@ fn read_file_lossy(path: &Path, max_bytes: usize) -> io::Result<String> {
     Ok(String::from_utf8_lossy(&buffer).into_owned())
 }
 
+/// Truncate `text` to at most `max_bytes` bytes without splitting a character.
+fn truncate_at_char_boundary(text: &mut String, max_bytes: usize) {
+    if text.len() > max_bytes {
+        let mut end = max_bytes;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+}
+
 #[cfg(not(windows))]
 fn normalize_newlines(text: String) -> String {
     text
@@ -772,6 +841,30 @@ mod tests {
         assert_eq!(input, res);
     }
 
+    #[test]
+    fn does_not_redact_when_disabled() {
+        let report = ProblemReport::without_metadata(false, vec!["secret".to_owned()]);
+        let input = "1234123412341234 10.0.0.1 secret";
+        assert_eq!(input, report.redact(input));
+
+        let report = ProblemReport::without_metadata(true, vec!["secret".to_owned()]);
+        assert_eq!(
+            "[REDACTED ACCOUNT NUMBER] [REDACTED] [REDACTED]",
+            report.redact(input)
+        );
+    }
+
+    #[test]
+    fn truncates_at_char_boundary() {
+        let mut text = "aåb".to_owned();
+        truncate_at_char_boundary(&mut text, 2);
+        assert_eq!(text, "a");
+
+        let mut text = "aåb".to_owned();
+        truncate_at_char_boundary(&mut text, 10);
+        assert_eq!(text, "aåb");
+    }
+
     #[test]
     fn parse_metadata() {
         let report = ProblemReport::new(Vec::new());
@@ -18,6 +18,11 @@ pub enum Error {
     #[error("No relays matching current constraints: {0:?}")]
     NoRelay(Box<RelayQuery>),
 
+    /// Relays match the constraints, but none of them support DAITA, and DAITA may not fall back
+    /// on multihop.
+    #[error("No DAITA-compatible relays matching current constraints: {0:?}")]
+    NoDaitaRelay(Box<RelayQuery>),
+
     #[error("No bridges matching current constraints")]
     NoBridge,
 
@@ -7,7 +7,8 @@ mod relay_selector;
 // Re-exports
 pub use error::Error;
 pub use relay_selector::{
-    AdditionalRelayConstraints, AdditionalWireguardConstraints, GetRelay, RETRY_ORDER,
-    RelaySelector, SelectedObfuscator, SelectorConfig, detailer, matcher,
-    matcher::filter_matching_relay_list, query, relays::WireguardConfig,
+    AdditionalRelayConstraints, AdditionalWireguardConstraints, GetRelay,
+    HIGH_SENSITIVITY_RETRY_ORDER, LOW_SENSITIVITY_RETRY_ORDER, RETRY_ORDER, RelaySelector,
+    SelectedObfuscator, SelectorConfig, detailer, matcher, matcher::filter_matching_relay_list,
+    query, relays::WireguardConfig,
 };
@@ -109,7 +109,7 @@ fn wireguard_multihop_endpoint(
     /// multihop circuit.
     const WIREGUARD_EXIT_PORT: u16 = 51820;
     let exit_endpoint = {
-        let ip = exit.ipv4_addr_in;
+        let ip = exit.wireguard_ipv4_addr_in();
         // The port that the exit relay listens for incoming connections from entry
         // relays is *not* derived from the original query / user settings.
         let port = WIREGUARD_EXIT_PORT;
@@ -164,9 +164,9 @@ fn get_address_for_wireguard_relay(
     relay: &WireguardRelay,
 ) -> Result<IpAddr, Error> {
     match resolve_ip_version(query.ip_version) {
-        IpVersion::V4 => Ok(relay.ipv4_addr_in.into()),
+        IpVersion::V4 => Ok(relay.wireguard_ipv4_addr_in().into()),
         IpVersion::V6 => relay
-            .ipv6_addr_in
+            .wireguard_ipv6_addr_in()
             .map(|addr| addr.into())
             .ok_or(Error::NoIPv6(Box::new(relay.clone()))),
     }
@@ -179,6 +179,11 @@ pub fn get_shadowsocks_obfuscator(
     relay: WireguardRelay,
     endpoint: &MullvadEndpoint,
 ) -> Result<(ObfuscatorConfig, WireguardRelay), Error> {
+    if let Constraint::Only(server) = &settings.custom_server {
+        let server = server.clone();
+        return Ok((ObfuscatorConfig::CustomShadowsocks { server }, relay));
+    }
+
     let port = settings.port;
     let extra_addrs = relay.endpoint_data.shadowsocks_extra_in_addrs();
 
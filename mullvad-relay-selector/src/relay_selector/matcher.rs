@@ -5,7 +5,8 @@ use mullvad_types::{
     constraints::{Constraint, Match},
     custom_list::CustomListsSettings,
     relay_constraints::{
-        GeographicLocationConstraint, LocationConstraint, Ownership, Providers, ShadowsocksSettings,
+        GeographicLocationConstraint, HostnamePattern, LocationConstraint, Ownership, Providers,
+        ShadowsocksSettings,
     },
     relay_list::{RelayList, WireguardRelay, WireguardRelayEndpointData},
 };
@@ -131,11 +132,17 @@ fn filter_on_shadowsocks(
     let ip_version = super::detailer::resolve_ip_version(*ip_version);
 
     match settings {
+        // A custom server forwards traffic to any relay
+        ShadowsocksSettings {
+            custom_server: Constraint::Only(_),
+            ..
+        } => true,
         // If Shadowsocks is specifically asked for, we must check if the specific relay supports
         // our port. If there are extra addresses, then all ports are available, so we do
         // not need to do this.
         ShadowsocksSettings {
             port: Constraint::Only(desired_port),
+            ..
         } => {
             let filtered_extra_addrs = endpoint_data
                 .shadowsocks_extra_addr_in
@@ -160,11 +167,13 @@ fn filter_on_include_in_country(
     relays: Vec<WireguardRelay>,
 ) -> Vec<WireguardRelay> {
     match locations {
-        Constraint::Any => relays,
-        Constraint::Only(locations) => {
+        Constraint::Any | Constraint::Only(ResolvedLocationConstraint::HostnamePattern(_)) => {
+            relays
+        }
+        Constraint::Only(ResolvedLocationConstraint::Locations(locations)) => {
             let mut included = HashSet::new();
             let mut excluded = HashSet::new();
-            for location in &locations {
+            for location in locations {
                 let (included_in_country, not_included_in_country): (Vec<_>, Vec<_>) = relays
                     .iter()
                     .partition(|relay| location.is_country() && relay.include_in_country);
@@ -182,9 +191,12 @@ fn filter_on_include_in_country(
 
 /// Wrapper around [`GeographicLocationConstraint`].
 /// Useful for iterating over a set of [`GeographicLocationConstraint`] where custom lists
-/// are considered.
+/// are considered. Hostname patterns are matched directly against relay hostnames.
 #[derive(Debug, Clone)]
-pub struct ResolvedLocationConstraint<'a>(Vec<&'a GeographicLocationConstraint>);
+pub enum ResolvedLocationConstraint<'a> {
+    Locations(Vec<&'a GeographicLocationConstraint>),
+    HostnamePattern(&'a HostnamePattern),
+}
 
 impl<'a> ResolvedLocationConstraint<'a> {
     /// Define the mapping from a [location][`LocationConstraint`] and a set of
@@ -197,34 +209,35 @@ impl<'a> ResolvedLocationConstraint<'a> {
             Constraint::Any => Constraint::Any,
             Constraint::Only(location) => Constraint::Only(match location {
                 LocationConstraint::Location(location) => {
-                    ResolvedLocationConstraint(vec![location])
+                    ResolvedLocationConstraint::Locations(vec![location])
                 }
                 LocationConstraint::CustomList { list_id } => custom_lists
                     .iter()
                     .find(|list| list.id() == *list_id)
                     .map(|custom_list| {
-                        ResolvedLocationConstraint(custom_list.locations.iter().collect())
+                        ResolvedLocationConstraint::Locations(
+                            custom_list.locations.iter().collect(),
+                        )
                     })
                     .unwrap_or_else(|| {
                         log::warn!("Resolved non-existent custom list with id {list_id:?}");
-                        ResolvedLocationConstraint(vec![])
+                        ResolvedLocationConstraint::Locations(vec![])
                     }),
+                LocationConstraint::HostnamePattern(pattern) => {
+                    ResolvedLocationConstraint::HostnamePattern(pattern)
+                }
             }),
         }
     }
 }
 
-impl<'a> IntoIterator for &'a ResolvedLocationConstraint<'a> {
-    type Item = &'a GeographicLocationConstraint;
-    type IntoIter = std::iter::Copied<std::slice::Iter<'a, &'a GeographicLocationConstraint>>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        self.0.iter().copied()
-    }
-}
-
 impl Match<WireguardRelay> for ResolvedLocationConstraint<'_> {
     fn matches(&self, relay: &WireguardRelay) -> bool {
-        self.into_iter().any(|location| location.matches(relay))
+        match self {
+            ResolvedLocationConstraint::Locations(locations) => {
+                locations.iter().any(|location| location.matches(relay))
+            }
+            ResolvedLocationConstraint::HostnamePattern(pattern) => pattern.matches(relay),
+        }
     }
 }
@@ -24,7 +24,9 @@ use mullvad_types::{
     endpoint::MullvadEndpoint,
     location::Coordinates,
     relay_constraints::{
-        ObfuscationSettings, RelayConstraints, RelaySettings, WireguardConstraints,
+        GeographicLocationConstraint, LocationConstraint, ObfuscationSensitivity,
+        ObfuscationSettings, RelayConstraints, RelaySelectionFallback, RelaySettings,
+        SelectedObfuscation, WireguardConstraints,
     },
     relay_list::{Bridge, BridgeList, Relay, RelayList, WireguardRelay},
     settings::Settings,
@@ -66,6 +68,43 @@ pub static RETRY_ORDER: LazyLock<Vec<RelayQuery>> = LazyLock::new(|| {
     ]
 });
 
+/// Like [`RETRY_ORDER`], but each attempt without obfuscation is made twice before obfuscation is
+/// used. Used when the sensitivity of automatic obfuscation is [`ObfuscationSensitivity::Low`].
+pub static LOW_SENSITIVITY_RETRY_ORDER: LazyLock<Vec<RelayQuery>> = LazyLock::new(|| {
+    let (unobfuscated, obfuscated) = RETRY_ORDER.split_at(2);
+    [unobfuscated, unobfuscated, obfuscated].concat()
+});
+
+/// Like [`RETRY_ORDER`], but obfuscation is used after a single attempt without it. Used when the
+/// sensitivity of automatic obfuscation is [`ObfuscationSensitivity::High`].
+pub static HIGH_SENSITIVITY_RETRY_ORDER: LazyLock<Vec<RelayQuery>> = LazyLock::new(|| {
+    use query::builder::{IpVersion, RelayQueryBuilder};
+    vec![
+        RelayQueryBuilder::new().build(),
+        RelayQueryBuilder::new().shadowsocks().build(),
+        RelayQueryBuilder::new().quic().build(),
+        RelayQueryBuilder::new().udp2tcp().build(),
+        RelayQueryBuilder::new().ip_version(IpVersion::V6).build(),
+        RelayQueryBuilder::new()
+            .udp2tcp()
+            .ip_version(IpVersion::V6)
+            .build(),
+        RelayQueryBuilder::new().lwo().build(),
+    ]
+});
+
+/// Return the retry order to use with `obfuscation_settings`.
+fn retry_order(obfuscation_settings: &ObfuscationSettings) -> &'static [RelayQuery] {
+    if obfuscation_settings.selected_obfuscation != SelectedObfuscation::Auto {
+        return &RETRY_ORDER;
+    }
+    match obfuscation_settings.auto_sensitivity {
+        ObfuscationSensitivity::Low => &LOW_SENSITIVITY_RETRY_ORDER,
+        ObfuscationSensitivity::Normal => &RETRY_ORDER,
+        ObfuscationSensitivity::High => &HIGH_SENSITIVITY_RETRY_ORDER,
+    }
+}
+
 #[derive(Clone)]
 pub struct RelaySelector {
     config: Arc<Mutex<SelectorConfig>>,
@@ -73,6 +112,19 @@ pub struct RelaySelector {
     // the same time.
     relays: Arc<RwLock<RelayList>>,
     bridges: Arc<RwLock<BridgeList>>,
+    fallbacks: Arc<Mutex<FallbackState>>,
+    /// Hostname of the most recently selected exit relay. Only kept track of if
+    /// [`SelectorConfig::randomize_relay_each_connect`] is enabled.
+    last_exit: Arc<Mutex<Option<String>>>,
+}
+
+/// Keeps track of which constraints are ignored, so that the listener is only notified when this
+/// changes.
+#[derive(Default)]
+struct FallbackState {
+    listener: Option<Box<dyn Fn(RelaySelectionFallback) + Send>>,
+    /// Fallbacks applied by the most recent relay selection
+    active: Vec<RelaySelectionFallback>,
 }
 
 // TODO: Rename to simply `Config`
@@ -84,6 +136,9 @@ pub struct SelectorConfig {
     pub custom_lists: CustomListsSettings,
     // Wireguard specific data
     pub obfuscation_settings: ObfuscationSettings,
+    /// If true, pick relays uniformly at random, regardless of their weights, and avoid picking
+    /// the same exit relay as last time.
+    pub randomize_relay_each_connect: bool,
 }
 
 impl SelectorConfig {
@@ -98,11 +153,15 @@ impl SelectorConfig {
                     .wireguard
                     .daita
                     .use_multihop_if_necessary,
+                #[cfg(daita)]
+                daita_required: settings.tunnel_options.wireguard.daita.required,
 
                 #[cfg(not(daita))]
                 daita: false,
                 #[cfg(not(daita))]
                 daita_use_multihop_if_necessary: false,
+                #[cfg(not(daita))]
+                daita_required: false,
 
                 quantum_resistant: settings.tunnel_options.wireguard.quantum_resistant,
             },
@@ -113,6 +172,7 @@ impl SelectorConfig {
             additional_constraints,
             obfuscation_settings: settings.obfuscation_settings.clone(),
             custom_lists: settings.custom_lists.clone(),
+            randomize_relay_each_connect: settings.randomize_relay_each_connect,
         }
     }
 }
@@ -134,6 +194,10 @@ pub struct AdditionalWireguardConstraints {
     /// DAITA is enabled.
     pub daita_use_multihop_if_necessary: bool,
 
+    /// If true, fail rather than select relays without DAITA if no relays that support DAITA
+    /// match the constraints.
+    pub daita_required: bool,
+
     /// If enabled, select relays that support PQ.
     pub quantum_resistant: QuantumResistantState,
 }
@@ -209,6 +273,7 @@ impl Default for SelectorConfig {
             additional_constraints: AdditionalRelayConstraints::default(),
             obfuscation_settings: default_settings.obfuscation_settings,
             custom_lists: default_settings.custom_lists,
+            randomize_relay_each_connect: default_settings.randomize_relay_each_connect,
         }
     }
 }
@@ -269,6 +334,7 @@ impl<'a> TryFrom<NormalSelectorConfig<'a>> for RelayQuery {
             let AdditionalWireguardConstraints {
                 daita,
                 daita_use_multihop_if_necessary,
+                daita_required,
                 quantum_resistant,
             } = additional_constraints;
             WireguardRelayQuery {
@@ -281,6 +347,7 @@ impl<'a> TryFrom<NormalSelectorConfig<'a>> for RelayQuery {
                 obfuscation: ObfuscationQuery::from(obfuscation_settings),
                 daita: Constraint::Only(daita),
                 daita_use_multihop_if_necessary: Constraint::Only(daita_use_multihop_if_necessary),
+                daita_required: Constraint::Only(daita_required),
                 quantum_resistant: Constraint::Only(quantum_resistant),
             }
         }
@@ -306,6 +373,36 @@ impl RelaySelector {
             config: Arc::new(Mutex::new(config)),
             relays: Arc::new(RwLock::new(relays)),
             bridges: Arc::new(RwLock::new(bridges)),
+            fallbacks: Arc::new(Mutex::new(FallbackState::default())),
+            last_exit: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Register a function that is called when a constraint starts being ignored because no relay
+    /// satisfies it. See [`RelaySelectionFallback`].
+    pub fn set_fallback_listener(
+        &self,
+        listener: impl Fn(RelaySelectionFallback) + Send + 'static,
+    ) {
+        self.fallbacks.lock().unwrap().listener = Some(Box::new(listener));
+    }
+
+    /// Returns a selector that shares the config, relays and bridges of this one, but whose
+    /// selections do not affect this one. Use it to preview which relay would be selected without
+    /// notifying the fallback listener or changing which exit relay
+    /// [`SelectorConfig::randomize_relay_each_connect`] avoids next.
+    pub fn peek(&self) -> RelaySelector {
+        let active = self.fallbacks.lock().unwrap().active.clone();
+        let last_exit = self.last_exit.lock().unwrap().clone();
+        RelaySelector {
+            config: self.config.clone(),
+            relays: self.relays.clone(),
+            bridges: self.bridges.clone(),
+            fallbacks: Arc::new(Mutex::new(FallbackState {
+                listener: None,
+                active,
+            })),
+            last_exit: Arc::new(Mutex::new(last_exit)),
         }
     }
 
@@ -388,16 +485,83 @@ impl RelaySelector {
                 Ok(GetRelay::Custom(custom_config.clone()))
             }
             SpecializedSelectorConfig::Normal(_normal_config) => {
+                let retry_order = retry_order(&config_guard.obfuscation_settings);
                 drop(config_guard);
                 self.get_relay_with_custom_params(
                     retry_attempt,
-                    &RETRY_ORDER,
+                    retry_order,
                     runtime_ip_availability,
                 )
             }
         }
     }
 
+    /// Same as [`Self::get_relay`], except that the exit location constraint is replaced by
+    /// `location`. The saved constraints are not modified.
+    pub fn get_relay_with_location(
+        &self,
+        location: GeographicLocationConstraint,
+        retry_attempt: usize,
+        runtime_ip_availability: IpAvailability,
+    ) -> Result<GetRelay, Error> {
+        let mut config = self.config.lock().unwrap().clone();
+        if let RelaySettings::Normal(constraints) = &mut config.relay_settings {
+            constraints.location = Constraint::Only(LocationConstraint::Location(location));
+        }
+        self.get_relay_with_config(
+            &config,
+            retry_attempt,
+            retry_order(&config.obfuscation_settings),
+            runtime_ip_availability,
+        )
+    }
+
+    /// Same as [`Self::get_relay_with_location`], except that `obfuscation` is used instead of
+    /// the selected obfuscation method. The saved settings are not modified.
+    pub fn get_relay_with_location_and_obfuscation(
+        &self,
+        location: GeographicLocationConstraint,
+        obfuscation: SelectedObfuscation,
+        retry_attempt: usize,
+        runtime_ip_availability: IpAvailability,
+    ) -> Result<GetRelay, Error> {
+        let mut config = self.config.lock().unwrap().clone();
+        if let RelaySettings::Normal(constraints) = &mut config.relay_settings {
+            constraints.location = Constraint::Only(LocationConstraint::Location(location));
+        }
+        config.obfuscation_settings.selected_obfuscation = obfuscation;
+        self.get_relay_with_config(
+            &config,
+            retry_attempt,
+            retry_order(&config.obfuscation_settings),
+            runtime_ip_availability,
+        )
+    }
+
+    /// Returns the active exit relays matching the location, provider and ownership constraints
+    /// in `relay_settings`, regardless of which features they support. The saved constraints are
+    /// not modified. Returns no relays if `relay_settings` is a custom tunnel endpoint.
+    pub fn get_relays_matching_settings(
+        &self,
+        relay_settings: RelaySettings,
+    ) -> Vec<WireguardRelay> {
+        let mut config = self.config.lock().unwrap().clone();
+        config.relay_settings = relay_settings;
+        config.additional_constraints = AdditionalRelayConstraints::default();
+        config.obfuscation_settings.selected_obfuscation = SelectedObfuscation::Off;
+
+        let SpecializedSelectorConfig::Normal(normal_config) =
+            SpecializedSelectorConfig::from(&config)
+        else {
+            return vec![];
+        };
+        let custom_lists = normal_config.custom_lists;
+        let Ok(query) = RelayQuery::try_from(normal_config) else {
+            return vec![];
+        };
+        self.relay_list(|relay_list| filter_matching_relay_list(&query, relay_list, custom_lists))
+    }
+
     /// Returns a random relay and relay endpoint matching the current constraints defined by
     /// `retry_order` corresponding to `retry_attempt`.
     pub fn get_relay_with_custom_params(
@@ -407,7 +571,23 @@ impl RelaySelector {
         runtime_ip_availability: IpAvailability,
     ) -> Result<GetRelay, Error> {
         let config_guard = self.config.lock().unwrap();
-        let config = SpecializedSelectorConfig::from(&*config_guard);
+        self.get_relay_with_config(
+            &config_guard,
+            retry_attempt,
+            retry_order,
+            runtime_ip_availability,
+        )
+    }
+
+    fn get_relay_with_config(
+        &self,
+        config: &SelectorConfig,
+        retry_attempt: usize,
+        retry_order: &[RelayQuery],
+        runtime_ip_availability: IpAvailability,
+    ) -> Result<GetRelay, Error> {
+        let randomize = config.randomize_relay_each_connect;
+        let config = SpecializedSelectorConfig::from(config);
 
         // Short-circuit if a custom tunnel endpoint is to be used - don't have to involve the
         // relay selector further!
@@ -420,32 +600,127 @@ impl RelaySelector {
                 // Merge user preferences with the relay selector's default preferences.
                 let custom_lists = normal_config.custom_lists;
                 let mut user_query = RelayQuery::try_from(normal_config)?;
+                self.apply_hostname_pattern_fallback(&mut user_query, &parsed_relays);
                 // Runtime parameters may affect which of the default queries that are considered.
                 // For example, queries which rely on IPv6 will not be considered if
                 // working IPv6 is not available at runtime.
                 apply_ip_availability(runtime_ip_availability, &mut user_query)?;
                 log::trace!("Merging user preferences {user_query:?} with default retry strategy");
-                // Select a relay using the user's preferences merged with the nth compatible query
-                // in `retry_order`, looping back to the start of `retry_order` if
-                // necessary.
-                let maybe_relay = retry_order
-                    .iter()
-                    .filter_map(|query| query.clone().intersection(user_query.clone()))
-                    .filter_map(|query| {
-                        Self::get_relay_inner(&query, &parsed_relays, custom_lists).ok()
-                    })
-                    .cycle() // If the above filters remove all relays, cycle will also return an empty iterator
-                    .nth(retry_attempt);
-                match maybe_relay {
-                    Some(v) => Ok(v),
-                    // If none of the queries in `retry_order` merged with `user_preferences` yield any relays,
-                    // attempt to only consider the user's preferences.
-                    None => Self::get_relay_inner(&user_query, &parsed_relays, custom_lists),
+                if !randomize {
+                    return Self::get_relay_with_retry_order(
+                        &user_query,
+                        &parsed_relays,
+                        custom_lists,
+                        retry_attempt,
+                        retry_order,
+                    );
+                }
+
+                // Avoid the previous exit relay, unless it is the only one that matches
+                let mut last_exit = self.last_exit.lock().unwrap();
+                let result = Self::get_relay_with_retry_order(
+                    &user_query,
+                    &randomized_relay_list(&parsed_relays, last_exit.as_deref()),
+                    custom_lists,
+                    retry_attempt,
+                    retry_order,
+                )
+                .or_else(|_| {
+                    Self::get_relay_with_retry_order(
+                        &user_query,
+                        &randomized_relay_list(&parsed_relays, None),
+                        custom_lists,
+                        retry_attempt,
+                        retry_order,
+                    )
+                });
+                if let Ok(GetRelay::Mullvad {
+                    inner:
+                        WireguardConfig::Singlehop { exit } | WireguardConfig::Multihop { exit, .. },
+                    ..
+                }) = &result
+                {
+                    *last_exit = Some(exit.hostname.clone());
                 }
+                result
             }
         }
     }
 
+    /// Select a relay using `user_query` merged with the nth compatible query in `retry_order`,
+    /// where n is `retry_attempt`, looping back to the start of `retry_order` if necessary.
+    fn get_relay_with_retry_order(
+        user_query: &RelayQuery,
+        parsed_relays: &RelayList,
+        custom_lists: &CustomListsSettings,
+        retry_attempt: usize,
+        retry_order: &[RelayQuery],
+    ) -> Result<GetRelay, Error> {
+        let maybe_relay = retry_order
+            .iter()
+            .filter_map(|query| query.clone().intersection(user_query.clone()))
+            .filter_map(|query| Self::get_relay_inner(&query, parsed_relays, custom_lists).ok())
+            .cycle() // If the above filters remove all relays, cycle will also return an empty iterator
+            .nth(retry_attempt);
+        match maybe_relay {
+            Some(v) => Ok(v),
+            // If none of the queries in `retry_order` merged with `user_preferences` yield any relays,
+            // attempt to only consider the user's preferences.
+            None => Self::get_relay_inner(user_query, parsed_relays, custom_lists),
+        }
+    }
+
+    /// Ignore hostname patterns in `query` that do not match any active relay, rather than failing
+    /// to select a relay. The fallback listener is notified of patterns that were not ignored by
+    /// the previous selection.
+    fn apply_hostname_pattern_fallback(&self, query: &mut RelayQuery, parsed_relays: &RelayList) {
+        let unmatched_pattern = |location: &Constraint<LocationConstraint>| match location {
+            Constraint::Only(LocationConstraint::HostnamePattern(pattern))
+                if !parsed_relays
+                    .relays()
+                    .any(|relay| relay.active && pattern.is_match(&relay.hostname)) =>
+            {
+                Some(pattern.clone())
+            }
+            _ => None,
+        };
+
+        let mut fallbacks = vec![];
+        if let Some(pattern) = unmatched_pattern(query.location()) {
+            query.set_location(Constraint::Any);
+            fallbacks.push(RelaySelectionFallback {
+                pattern,
+                entry: false,
+            });
+        }
+        let mut wireguard_constraints = query.wireguard_constraints().clone();
+        if wireguard_constraints.use_multihop == Constraint::Only(true)
+            && let Some(pattern) = unmatched_pattern(&wireguard_constraints.entry_location)
+        {
+            wireguard_constraints.entry_location = Constraint::Any;
+            query.set_wireguard_constraints(wireguard_constraints);
+            fallbacks.push(RelaySelectionFallback {
+                pattern,
+                entry: true,
+            });
+        }
+
+        let mut state = self.fallbacks.lock().unwrap();
+        for fallback in &fallbacks {
+            if state.active.contains(fallback) {
+                continue;
+            }
+            log::warn!(
+                "No relay matches the hostname pattern {}, ignoring it",
+                fallback.pattern
+            );
+            if let Some(listener) = state.listener.as_ref() {
+                listener(fallback.clone());
+            }
+        }
+        state.active = fallbacks;
+    }
+
     /// "Execute" the given query, yielding a final set of relays and/or bridges which the VPN
     /// traffic shall be routed through.
     ///
@@ -487,7 +762,19 @@ impl RelaySelector {
         custom_lists: &CustomListsSettings,
         parsed_relays: &RelayList,
     ) -> Result<GetRelay, Error> {
-        let inner = Self::get_wireguard_relay_config(query, custom_lists, parsed_relays)?;
+        let inner = match Self::get_wireguard_relay_config(query, custom_lists, parsed_relays) {
+            // Unless DAITA is required, fall back on a relay without DAITA. The tunnel only enables
+            // DAITA if the relay supports it.
+            Err(error) if query.using_daita() && !query.daita_required() => {
+                log::warn!("Selecting a relay without DAITA: {error}");
+                Self::get_wireguard_relay_config(
+                    &without_daita(query),
+                    custom_lists,
+                    parsed_relays,
+                )?
+            }
+            result => result?,
+        };
         let endpoint = Self::get_wireguard_endpoint(query, parsed_relays, &inner)?;
         let obfuscator =
             Self::get_wireguard_obfuscator(query, inner.clone(), &endpoint, parsed_relays)?;
@@ -526,7 +813,11 @@ impl RelaySelector {
                         )?;
                         WireguardConfig::from(multihop)
                     } else {
-                        return Err(Error::NoRelay(Box::new(query.clone())));
+                        return Err(Self::no_singlehop_relay_error(
+                            query,
+                            custom_lists,
+                            parsed_relays,
+                        ));
                     }
                 }
             }
@@ -562,6 +853,22 @@ impl RelaySelector {
             .map(Singlehop::new)
     }
 
+    /// Return [`Error::NoDaitaRelay`] if `query` would have matched a relay if it were not for
+    /// DAITA, so that the user can be told to change either, and [`Error::NoRelay`] otherwise.
+    fn no_singlehop_relay_error(
+        query: &RelayQuery,
+        custom_lists: &CustomListsSettings,
+        parsed_relays: &RelayList,
+    ) -> Error {
+        if query.using_daita()
+            && !filter_matching_relay_list(&without_daita(query), parsed_relays, custom_lists)
+                .is_empty()
+        {
+            return Error::NoDaitaRelay(Box::new(query.clone()));
+        }
+        Error::NoRelay(Box::new(query.clone()))
+    }
+
     /// Select a valid Wireguard exit relay, together with with an automatically chosen entry relay.
     ///
     /// # Returns
@@ -813,6 +1120,34 @@ impl RelaySelector {
     }
 }
 
+/// Return a copy of `relays` where every relay is equally likely to be picked, since
+/// [`helpers::pick_random_relay_weighted`] ignores weights when they are all 0. The relay with the
+/// hostname `exclude` is removed.
+fn randomized_relay_list(relays: &RelayList, exclude: Option<&str>) -> RelayList {
+    let mut relays = relays.clone();
+    for city in relays
+        .countries
+        .iter_mut()
+        .flat_map(|country| country.cities.iter_mut())
+    {
+        city.relays
+            .retain(|relay| Some(relay.hostname.as_str()) != exclude);
+        for relay in &mut city.relays {
+            relay.weight = 0;
+        }
+    }
+    relays
+}
+
+/// Return a copy of `query` which matches relays regardless of whether they support DAITA.
+fn without_daita(query: &RelayQuery) -> RelayQuery {
+    let mut query = query.clone();
+    let mut wireguard_constraints = query.wireguard_constraints().clone();
+    wireguard_constraints.daita = Constraint::Only(false);
+    query.set_wireguard_constraints(wireguard_constraints);
+    query
+}
+
 fn apply_ip_availability(
     runtime_ip_availability: IpAvailability,
     user_query: &mut RelayQuery,
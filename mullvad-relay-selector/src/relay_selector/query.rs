@@ -205,6 +205,7 @@ pub struct WireguardRelayQuery {
     pub obfuscation: ObfuscationQuery,
     pub daita: Constraint<bool>,
     pub daita_use_multihop_if_necessary: Constraint<bool>,
+    pub daita_required: Constraint<bool>,
     pub quantum_resistant: Constraint<QuantumResistantState>,
 }
 
@@ -309,6 +310,7 @@ impl WireguardRelayQuery {
             obfuscation: ObfuscationQuery::Auto,
             daita: Constraint::Any,
             daita_use_multihop_if_necessary: Constraint::Any,
+            daita_required: Constraint::Any,
             quantum_resistant: Constraint::Any,
         }
     }
@@ -518,6 +520,12 @@ pub mod builder {
                 .daita_use_multihop_if_necessary = constraint.into();
             self
         }
+
+        /// Enable DAITA 'required'.
+        pub fn daita_required(mut self, constraint: impl Into<Constraint<bool>>) -> Self {
+            self.query.wireguard_constraints.daita_required = constraint.into();
+            self
+        }
     }
 
     impl<Multihop, Obfuscation, Daita> RelayQueryBuilder<Multihop, Obfuscation, Daita, Any> {
@@ -632,9 +640,7 @@ pub mod builder {
         pub fn shadowsocks(
             mut self,
         ) -> RelayQueryBuilder<Multihop, ShadowsocksSettings, Daita, QuantumResistant> {
-            let obfuscation = ShadowsocksSettings {
-                port: Constraint::Any,
-            };
+            let obfuscation = ShadowsocksSettings::default();
             let protocol = Settings {
                 multihop: self.settings.multihop,
                 obfuscation: obfuscation.clone(),
@@ -698,6 +704,8 @@ pub trait RelayQueryExt {
     fn using_daita(&self) -> bool;
     /// is `use_multihop_if_necessary` enabled? In other words, is `Direct only` disabled?
     fn use_multihop_if_necessary(&self) -> bool;
+    /// Must DAITA be used? In other words, may we not fall back on a relay without DAITA?
+    fn daita_required(&self) -> bool;
     /// Are we using singlehop? I.e. is multihop *not* explicitly enabled?
     fn singlehop(&self) -> bool;
 }
@@ -715,6 +723,13 @@ impl RelayQueryExt for RelayQuery {
             .intersection(Constraint::Only(true))
             .is_some()
     }
+    fn daita_required(&self) -> bool {
+        self.wireguard_constraints()
+            .daita_required
+            // The default value is `Any`, which means that we need to check the intersection.
+            .intersection(Constraint::Only(true))
+            .is_some()
+    }
     fn singlehop(&self) -> bool {
         !self.wireguard_constraints().multihop()
     }
@@ -820,8 +835,10 @@ mod test {
                 },
                 shadowsocks: ShadowsocksSettings {
                     port: port2,
+                    ..Default::default()
                 },
                 wireguard_port: port1.into(),
+                auto_sensitivity: Default::default(),
             });
             assert_eq!(query, ObfuscationQuery::Auto);
         }
@@ -3,24 +3,30 @@
 use std::{
     collections::HashSet,
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
-    sync::LazyLock,
+    sync::{Arc, LazyLock, Mutex},
 };
 use talpid_types::net::{
-    IpVersion,
+    IpAvailability, IpVersion,
     TransportProtocol::{Tcp, Udp},
     obfuscation::{ObfuscatorConfig, Obfuscators},
+    proxy::Shadowsocks,
     wireguard::PublicKey,
 };
 
 use mullvad_relay_selector::{
-    Error, GetRelay, RETRY_ORDER, RelaySelector, SelectedObfuscator, SelectorConfig,
-    WireguardConfig,
+    Error, GetRelay, HIGH_SENSITIVITY_RETRY_ORDER, LOW_SENSITIVITY_RETRY_ORDER, RETRY_ORDER,
+    RelaySelector, SelectedObfuscator, SelectorConfig, WireguardConfig,
     query::{ObfuscationQuery, builder::RelayQueryBuilder},
 };
 use mullvad_types::{
+    constraints::Constraint,
     endpoint::MullvadEndpoint,
     location::Location,
-    relay_constraints::{GeographicLocationConstraint, Ownership, Providers, RelayOverride},
+    relay_constraints::{
+        GeographicLocationConstraint, HostnamePattern, LocationConstraint, ObfuscationSensitivity,
+        ObfuscationSettings, Ownership, Providers, RelayConstraints, RelayOverride,
+        SelectedObfuscation, ShadowsocksSettings,
+    },
     relay_list::{
         Bridge, BridgeEndpointData, BridgeList, EndpointData, Quic, Relay, RelayList,
         RelayListCity, RelayListCountry, ShadowsocksEndpointData, WireguardRelay,
@@ -517,6 +523,237 @@ fn test_entry_hostname_collision() {
     )
 }
 
+/// Assert that a location override selects the given relay, without changing which relays are
+/// selected afterwards.
+#[test]
+fn test_get_relay_with_location() {
+    let relay_selector = default_relay_selector();
+    let location = GeographicLocationConstraint::hostname("se", "got", "se10-wireguard");
+
+    for retry_attempt in 0..10 {
+        let relay = relay_selector
+            .get_relay_with_location(location.clone(), retry_attempt, IpAvailability::Ipv4)
+            .map(unwrap_relay)
+            .unwrap();
+        assert_eq!(relay.hostname, "se10-wireguard");
+    }
+
+    let selected_hostnames: HashSet<_> = (0..100)
+        .map(|_| {
+            let relay = relay_selector.get_relay(0, IpAvailability::Ipv4).unwrap();
+            unwrap_relay(relay).hostname.clone()
+        })
+        .collect();
+    assert!(selected_hostnames.len() > 1);
+}
+
+/// Assert that an obfuscation override is used instead of the obfuscation settings.
+#[test]
+fn test_get_relay_with_location_and_obfuscation() {
+    let relay_selector = default_relay_selector();
+    let location = GeographicLocationConstraint::hostname("se", "got", "se10-wireguard");
+
+    let relay = relay_selector
+        .get_relay_with_location_and_obfuscation(
+            location,
+            SelectedObfuscation::Shadowsocks,
+            0,
+            IpAvailability::Ipv4,
+        )
+        .unwrap();
+    match relay {
+        GetRelay::Mullvad {
+            obfuscator,
+            inner: WireguardConfig::Singlehop { exit },
+            ..
+        } => {
+            assert_eq!(exit.hostname, "se10-wireguard");
+            assert!(obfuscator.is_some_and(|obfuscator| matches!(
+                obfuscator.config,
+                Obfuscators::Single(ObfuscatorConfig::Shadowsocks { .. })
+            )));
+        }
+        wrong_relay => panic!(
+            "Relay selector should have picked se10-wireguard with Shadowsocks, instead chose {wrong_relay:?}"
+        ),
+    }
+}
+
+/// Assert that relays are matched against relay settings regardless of the features they support,
+/// even if the saved settings require DAITA and QUIC.
+#[test]
+fn test_get_relays_matching_settings() {
+    let mut config = SelectorConfig::default();
+    config.additional_constraints.wireguard.daita = true;
+    config.obfuscation_settings.selected_obfuscation = SelectedObfuscation::Quic;
+    let relay_selector = RelaySelector::new(config, RELAYS.clone(), BRIDGES.clone());
+
+    let constraints = RelayConstraints {
+        providers: Constraint::Only(Providers::new(["provider1"]).unwrap()),
+        ..RelayConstraints::default()
+    };
+    let relays = relay_selector.get_relays_matching_settings(constraints.into());
+    assert_eq!(relays.len(), 1);
+    assert_eq!(relays[0].hostname, "se10-wireguard");
+    assert!(!supports_daita(&relays[0]));
+
+    let relays = relay_selector.get_relays_matching_settings(RelayConstraints::default().into());
+    assert_eq!(relays.len(), RELAYS.relays().count());
+}
+
+fn hostname_pattern_config(pattern: &str) -> SelectorConfig {
+    let constraints = RelayConstraints {
+        location: Constraint::Only(LocationConstraint::HostnamePattern(
+            pattern.parse().unwrap(),
+        )),
+        ..RelayConstraints::default()
+    };
+    SelectorConfig {
+        relay_settings: constraints.into(),
+        ..SelectorConfig::default()
+    }
+}
+
+fn relay_selector_with_hostname_pattern(pattern: &str) -> RelaySelector {
+    RelaySelector::new(
+        hostname_pattern_config(pattern),
+        RELAYS.clone(),
+        BRIDGES.clone(),
+    )
+}
+
+/// Assert that only relays matching a hostname pattern are selected.
+#[test]
+fn test_hostname_pattern() {
+    let relay_selector = relay_selector_with_hostname_pattern("se1?-wireguard");
+    let fallbacks = Arc::new(Mutex::new(vec![]));
+    let fallbacks_clone = fallbacks.clone();
+    relay_selector.set_fallback_listener(move |fallback| {
+        fallbacks_clone.lock().unwrap().push(fallback);
+    });
+
+    let selected_hostnames: HashSet<_> = (0..100)
+        .map(|_| {
+            let relay = relay_selector.get_relay(0, IpAvailability::Ipv4).unwrap();
+            unwrap_relay(relay).hostname.clone()
+        })
+        .collect();
+    assert!(!selected_hostnames.contains("se9-wireguard"));
+    assert!(selected_hostnames.contains("se10-wireguard"));
+    assert!(fallbacks.lock().unwrap().is_empty());
+}
+
+/// Assert that a hostname pattern which does not match any relay is ignored, and that the
+/// fallback listener is notified once, when the pattern starts being ignored.
+#[test]
+fn test_hostname_pattern_fallback() {
+    let relay_selector = relay_selector_with_hostname_pattern("no-*");
+    let fallbacks = Arc::new(Mutex::new(vec![]));
+    let fallbacks_clone = fallbacks.clone();
+    relay_selector.set_fallback_listener(move |fallback| {
+        fallbacks_clone.lock().unwrap().push(fallback);
+    });
+
+    for _ in 0..3 {
+        relay_selector
+            .get_relay(0, IpAvailability::Ipv4)
+            .expect("an unmatched hostname pattern should be ignored");
+    }
+
+    {
+        let fallbacks = fallbacks.lock().unwrap();
+        assert_eq!(fallbacks.len(), 1);
+        assert_eq!(
+            fallbacks[0].pattern,
+            "no-*".parse::<HostnamePattern>().unwrap()
+        );
+        assert!(!fallbacks[0].entry);
+    }
+
+    // A pattern that is ignored again after a selection that did not ignore it is reported again
+    relay_selector.set_config(hostname_pattern_config("se*"));
+    relay_selector.get_relay(0, IpAvailability::Ipv4).unwrap();
+    assert_eq!(fallbacks.lock().unwrap().len(), 1);
+
+    relay_selector.set_config(hostname_pattern_config("no-*"));
+    relay_selector.get_relay(0, IpAvailability::Ipv4).unwrap();
+    assert_eq!(fallbacks.lock().unwrap().len(), 2);
+}
+
+/// Assert that the previous exit relay is avoided when relays are randomized on each connection,
+/// unless it is the only matching relay.
+#[test]
+fn test_randomize_relay_each_connect() {
+    let config = SelectorConfig {
+        randomize_relay_each_connect: true,
+        ..SelectorConfig::default()
+    };
+    let relay_selector = RelaySelector::new(config, RELAYS.clone(), BRIDGES.clone());
+
+    let mut previous_hostname = None;
+    for _ in 0..100 {
+        let relay = relay_selector.get_relay(0, IpAvailability::Ipv4).unwrap();
+        let hostname = unwrap_relay(relay).hostname.clone();
+        assert_ne!(previous_hostname.as_ref(), Some(&hostname));
+        previous_hostname = Some(hostname);
+    }
+
+    let (relay_constraints, ..) = RelayQueryBuilder::new()
+        .location(NON_DAITA_RELAY_LOCATION.clone())
+        .build()
+        .into_settings();
+    relay_selector.set_config(SelectorConfig {
+        relay_settings: relay_constraints.into(),
+        randomize_relay_each_connect: true,
+        ..SelectorConfig::default()
+    });
+    for _ in 0..2 {
+        let relay = relay_selector.get_relay(0, IpAvailability::Ipv4).unwrap();
+        assert_eq!(unwrap_relay(relay).hostname, "se10-wireguard");
+    }
+}
+
+/// Assert that selecting relays using [`RelaySelector::peek`] neither notifies the fallback
+/// listener nor changes which exit relay is avoided next, so that previews followed by a
+/// selection behave like a single selection.
+#[test]
+fn test_peek() {
+    let relay_selector = relay_selector_with_hostname_pattern("no-*");
+    let fallbacks = Arc::new(Mutex::new(vec![]));
+    let fallbacks_clone = fallbacks.clone();
+    relay_selector.set_fallback_listener(move |fallback| {
+        fallbacks_clone.lock().unwrap().push(fallback);
+    });
+
+    for _ in 0..2 {
+        relay_selector
+            .peek()
+            .get_relay(0, IpAvailability::Ipv4)
+            .unwrap();
+    }
+    assert!(fallbacks.lock().unwrap().is_empty());
+    relay_selector.get_relay(0, IpAvailability::Ipv4).unwrap();
+    assert_eq!(fallbacks.lock().unwrap().len(), 1);
+
+    // Only se10-wireguard and se11-wireguard match, so every selection must pick the relay that
+    // was not selected last
+    relay_selector.set_config(SelectorConfig {
+        randomize_relay_each_connect: true,
+        ..hostname_pattern_config("se1?-wireguard")
+    });
+    let relay = relay_selector.get_relay(0, IpAvailability::Ipv4).unwrap();
+    let first_hostname = unwrap_relay(relay).hostname;
+    for _ in 0..2 {
+        let relay = relay_selector
+            .peek()
+            .get_relay(0, IpAvailability::Ipv4)
+            .unwrap();
+        assert_ne!(unwrap_relay(relay).hostname, first_hostname);
+    }
+    let relay = relay_selector.get_relay(0, IpAvailability::Ipv4).unwrap();
+    assert_ne!(unwrap_relay(relay).hostname, first_hostname);
+}
+
 /// Construct a query for multihop configuration and assert that the relay selector picks an
 /// accompanying entry relay.
 #[test]
@@ -564,6 +801,44 @@ fn test_selecting_over_shadowsocks() {
     }
 }
 
+/// Test whether a custom Shadowsocks server is used instead of the ones run by relays
+#[test]
+fn test_selecting_custom_shadowsocks_server() {
+    let relay_selector =
+        RelaySelector::new(SelectorConfig::default(), RELAYS.clone(), BRIDGES.clone());
+
+    let server = Shadowsocks {
+        endpoint: "192.0.2.10:8388".parse().unwrap(),
+        password: "mullvad".to_owned(),
+        cipher: "aes-256-gcm".to_owned(),
+    };
+    let mut query = RelayQueryBuilder::new().build();
+    let mut wireguard_constraints = query.wireguard_constraints().clone();
+    wireguard_constraints.obfuscation = ObfuscationQuery::Shadowsocks(ShadowsocksSettings {
+        custom_server: Constraint::Only(server.clone()),
+        ..Default::default()
+    });
+    query.set_wireguard_constraints(wireguard_constraints);
+
+    let relay = relay_selector.get_relay_by_query(query).unwrap();
+    match relay {
+        GetRelay::Mullvad {
+            obfuscator:
+                Some(SelectedObfuscator {
+                    config:
+                        Obfuscators::Single(ObfuscatorConfig::CustomShadowsocks {
+                            server: selected_server,
+                        }),
+                    ..
+                }),
+            ..
+        } => assert_eq!(selected_server, server),
+        wrong_relay => panic!(
+            "Relay selector should have picked the custom Shadowsocks server, instead chose {wrong_relay:?}"
+        ),
+    }
+}
+
 /// Test whether extra Shadowsocks IPs are selected when available
 #[test]
 fn test_selecting_over_shadowsocks_extra_ips() {
@@ -663,6 +938,8 @@ fn test_selecting_ignore_extra_ips_override_v4() {
         hostname: SHADOWSOCKS_RELAY_LOCATION.get_hostname().unwrap().clone(),
         ipv4_addr_in: Some(OVERRIDE_IPV4),
         ipv6_addr_in: None,
+        wireguard_ipv4_addr_in: None,
+        wireguard_ipv6_addr_in: None,
     }]);
 
     let relay_selector = RelaySelector::new(SelectorConfig::default(), relay_list, BRIDGES.clone());
@@ -695,6 +972,54 @@ fn test_selecting_ignore_extra_ips_override_v4() {
     }
 }
 
+/// WireGuard endpoint overrides should only affect the WireGuard endpoint
+#[test]
+fn test_selecting_wireguard_endpoint_override() {
+    const OVERRIDE_IPV4: Ipv4Addr = Ipv4Addr::new(1, 3, 3, 7);
+
+    let relay_list = RELAYS.clone().apply_overrides(vec![RelayOverride {
+        wireguard_ipv4_addr_in: Some(OVERRIDE_IPV4),
+        ..RelayOverride::empty(SHADOWSOCKS_RELAY_LOCATION.get_hostname().unwrap().clone())
+    }]);
+    let relay_selector = RelaySelector::new(SelectorConfig::default(), relay_list, BRIDGES.clone());
+
+    let query = RelayQueryBuilder::new()
+        .location(SHADOWSOCKS_RELAY_LOCATION.clone())
+        .ip_version(IpVersion::V4)
+        .build();
+    let relay = relay_selector.get_relay_by_query(query).unwrap();
+    let entry = unwrap_entry_relay(relay.clone());
+    assert!(entry.overridden_ipv4);
+    assert_ne!(
+        entry.ipv4_addr_in, OVERRIDE_IPV4,
+        "the relay address should not be overridden"
+    );
+    assert_eq!(
+        unwrap_endpoint(relay).peer.endpoint.ip(),
+        IpAddr::from(OVERRIDE_IPV4)
+    );
+
+    // Obfuscation should still use the relay address
+    let query = RelayQueryBuilder::new()
+        .location(SHADOWSOCKS_RELAY_LOCATION.clone())
+        .ip_version(IpVersion::V4)
+        .shadowsocks()
+        .build();
+    match relay_selector.get_relay_by_query(query).unwrap() {
+        GetRelay::Mullvad {
+            obfuscator:
+                Some(SelectedObfuscator {
+                    config: Obfuscators::Single(ObfuscatorConfig::Shadowsocks { endpoint }),
+                    ..
+                }),
+            ..
+        } => assert_ne!(endpoint.ip(), IpAddr::from(OVERRIDE_IPV4)),
+        wrong_relay => panic!(
+            "Relay selector should have picked a Mullvad relay with Shadowsocks, instead chose {wrong_relay:?}"
+        ),
+    }
+}
+
 /// Ignore extra IPv6 addresses when overrides are set
 #[test]
 fn test_selecting_ignore_extra_ips_override_v6() {
@@ -704,6 +1029,8 @@ fn test_selecting_ignore_extra_ips_override_v6() {
         hostname: SHADOWSOCKS_RELAY_LOCATION.get_hostname().unwrap().clone(),
         ipv4_addr_in: None,
         ipv6_addr_in: Some(OVERRIDE_IPV6),
+        wireguard_ipv4_addr_in: None,
+        wireguard_ipv6_addr_in: None,
     }]);
 
     let relay_selector = RelaySelector::new(SelectorConfig::default(), relay_list, BRIDGES.clone());
@@ -781,6 +1108,46 @@ fn test_selecting_endpoint_with_udp2tcp_obfuscation() {
     }
 }
 
+/// Check that the sensitivity of automatic obfuscation determines how many attempts are made
+/// without obfuscation.
+#[cfg(not(feature = "staggered-obfuscation"))]
+#[test]
+fn test_auto_obfuscation_sensitivity() {
+    let relay_selector_with_sensitivity = |auto_sensitivity| {
+        let config = SelectorConfig {
+            obfuscation_settings: ObfuscationSettings {
+                auto_sensitivity,
+                ..Default::default()
+            },
+            ..SelectorConfig::default()
+        };
+        RelaySelector::new(config, RELAYS.clone(), BRIDGES.clone())
+    };
+    let is_obfuscated = |relay_selector: &RelaySelector, retry_attempt| match relay_selector
+        .get_relay(retry_attempt, IpAvailability::Ipv4AndIpv6)
+        .unwrap()
+    {
+        GetRelay::Mullvad { obfuscator, .. } => obfuscator.is_some(),
+        wrong_relay => panic!(
+            "Relay selector should have picked a Mullvad relay, instead chose {wrong_relay:?}"
+        ),
+    };
+
+    let relay_selector = relay_selector_with_sensitivity(ObfuscationSensitivity::Low);
+    assert_eq!(LOW_SENSITIVITY_RETRY_ORDER.len(), RETRY_ORDER.len() + 2);
+    assert!((0..4).all(|attempt| !is_obfuscated(&relay_selector, attempt)));
+    assert!(is_obfuscated(&relay_selector, 4));
+
+    let relay_selector = relay_selector_with_sensitivity(ObfuscationSensitivity::Normal);
+    assert!(!is_obfuscated(&relay_selector, 1));
+    assert!(is_obfuscated(&relay_selector, 2));
+
+    let relay_selector = relay_selector_with_sensitivity(ObfuscationSensitivity::High);
+    assert_eq!(HIGH_SENSITIVITY_RETRY_ORDER.len(), RETRY_ORDER.len());
+    assert!(!is_obfuscated(&relay_selector, 0));
+    assert!(is_obfuscated(&relay_selector, 1));
+}
+
 /// Construct a query for a Wireguard configuration where obfuscation is set to "Auto" and
 /// multihop is explicitly turned off. Assert that the relay selector does *not* return an
 /// obfuscator config.
@@ -1155,9 +1522,13 @@ fn test_daita() {
         .daita_use_multihop_if_necessary(false)
         .location(NON_DAITA_RELAY_LOCATION.clone())
         .build();
-    relay_selector
+    let error = relay_selector
         .get_relay_by_query(query)
         .expect_err("Expected to find no matching relay");
+    assert!(
+        matches!(error, Error::NoDaitaRelay(_)),
+        "Expected failure due to DAITA, got {error:?}"
+    );
 
     // Should be able to connect to non-DAITA relay with use_multihop_if_necessary
     let query = RelayQueryBuilder::new()
@@ -1261,6 +1632,41 @@ fn test_daita() {
     }
 }
 
+/// Fall back on a relay without DAITA when no relay that supports DAITA matches the constraints,
+/// unless DAITA is required.
+#[test]
+fn test_daita_required() {
+    let relay_selector =
+        RelaySelector::new(SelectorConfig::default(), RELAYS.clone(), BRIDGES.clone());
+
+    let query = RelayQueryBuilder::new()
+        .daita()
+        .daita_use_multihop_if_necessary(false)
+        .daita_required(false)
+        .location(NON_DAITA_RELAY_LOCATION.clone())
+        .build();
+    let relay = unwrap_entry_relay(
+        relay_selector
+            .get_relay_by_query(query)
+            .expect("Expected to fall back on a relay without DAITA"),
+    );
+    assert!(!supports_daita(&relay));
+
+    let query = RelayQueryBuilder::new()
+        .daita()
+        .daita_use_multihop_if_necessary(false)
+        .daita_required(true)
+        .location(NON_DAITA_RELAY_LOCATION.clone())
+        .build();
+    let error = relay_selector
+        .get_relay_by_query(query)
+        .expect_err("Expected DAITA to be required");
+    assert!(
+        matches!(error, Error::NoDaitaRelay(_)),
+        "Expected failure due to DAITA, got {error:?}"
+    );
+}
+
 /// Check that if the original user query would yield a relay, the result of running the query
 /// which is the intersection between the user query and any of the default queries shall never
 /// fail.
@@ -210,7 +210,9 @@ async fn remove_device() -> Result<(), Error> {
         // `DEVICE_NOT_FOUND` is not considered to be an error in this context.
         match device_removal {
             Ok(_) => Ok(()),
-            Err(mullvad_api::rest::Error::ApiError(_status, code)) if code == DEVICE_NOT_FOUND => {
+            Err(mullvad_api::rest::Error::ApiError(_status, code, _))
+                if code == DEVICE_NOT_FOUND =>
+            {
                 Ok(())
             }
             Err(e) => Err(Error::RemoveDeviceError(e)),
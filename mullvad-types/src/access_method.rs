@@ -1,4 +1,6 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use talpid_types::net::proxy::{CustomProxy, Shadowsocks, Socks5Local, Socks5Remote};
 
 /// Settings for API access methods.
@@ -235,6 +237,35 @@ pub struct AccessMethodSetting {
     pub access_method: AccessMethod,
 }
 
+/// The access method which carried the most recent successful API request
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LastSuccessfulAccessMethod {
+    pub id: Id,
+    /// When the response to the request was received
+    pub timestamp: DateTime<Utc>,
+}
+
+/// The outcome of testing whether a single access method can reach the API
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessMethodTestResult {
+    pub id: Id,
+    pub reachable: bool,
+    /// How long the test took
+    pub duration: Duration,
+    /// Why the access method could not be tested or could not reach the API, if known
+    pub error: Option<String>,
+}
+
+/// The outcome of testing enabled access methods one at a time, in priority order, until one
+/// of them could reach the API
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessMethodSearch {
+    /// The first access method which could reach the API, if any
+    pub working: Option<Id>,
+    /// The tested access methods, in the order they were tested
+    pub results: Vec<AccessMethodTestResult>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Id(uuid::Uuid);
 
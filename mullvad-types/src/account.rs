@@ -39,6 +39,16 @@ pub struct VoucherSubmission {
     pub new_expiry: DateTime<Utc>,
 }
 
+/// How long the daemon waits before allowing another login, after logins have failed because the
+/// account number was invalid.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LoginBackoff {
+    /// Number of logins in a row that failed because the account number was invalid
+    pub consecutive_failures: u32,
+    /// When the next login is allowed, or `None` if logging in is allowed now
+    pub next_attempt: Option<DateTime<Utc>>,
+}
+
 /// `PlayPurchase` is provided to google in order to verify that a google play purchase was
 /// acknowledged.
 #[derive(Deserialize, Serialize, Debug, Clone)]
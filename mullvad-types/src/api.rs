@@ -0,0 +1,56 @@
+//! Types describing requests made to the Mullvad API.
+
+use serde::{Deserialize, Serialize};
+use std::{net::SocketAddr, time::Duration};
+
+/// An API request that has been submitted but has not completed yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InflightApiRequest {
+    /// HTTP method, such as `GET`
+    pub method: String,
+    /// Path of the request URI, without the query
+    pub path: String,
+    /// Time since the request was submitted
+    pub elapsed: Duration,
+}
+
+/// The response to the most recent API request which failed with an unexpected status code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiErrorResponse {
+    /// HTTP method of the request, such as `GET`
+    pub method: String,
+    /// Path of the request, without the host
+    pub path: String,
+    /// HTTP status code of the response
+    pub status: u16,
+    /// The raw response body. Large bodies are truncated.
+    pub body: Vec<u8>,
+}
+
+/// The limit on the number of concurrent API requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiRequestLimit {
+    /// Maximum number of requests that may be in flight at once. Zero means unbounded.
+    pub max_concurrent_requests: u32,
+    /// Number of requests that are waiting to be sent because of the limit
+    pub queued_requests: u32,
+}
+
+/// Where the address used to reach the API comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiAddressSource {
+    /// The address that is bundled with the app
+    Bundled,
+    /// An address that was read from the cache on disk or fetched from the API
+    Cached,
+    /// An address set using the `MULLVAD_API_ADDR` or `MULLVAD_API_HOST` environment variables
+    Override,
+}
+
+/// The address currently used to reach the API, and where it comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiAddressInfo {
+    pub address: SocketAddr,
+    pub source: ApiAddressSource,
+}
@@ -0,0 +1,25 @@
+//! Files that the daemon caches on disk.
+
+use serde::{Deserialize, Serialize};
+
+/// A kind of cached file that can be cleared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CacheKind {
+    /// The cached relay list
+    RelayList,
+    /// The cached API address
+    ApiAddress,
+    /// Installers downloaded for in-app upgrades
+    Installers,
+    /// Rotated daemon logs. Logs that are currently being written to are never removed.
+    Logs,
+}
+
+/// Size in bytes of each kind of cached file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheUsage {
+    pub relay_list: u64,
+    pub api_address: u64,
+    pub installers: u64,
+    pub logs: u64,
+}
@@ -0,0 +1,63 @@
+//! Checks of the system clock against the time reported by the Mullvad API.
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+/// Largest offset from the time reported by the API that is not considered a problem. Larger
+/// offsets may cause TLS certificates or account expiry to be misjudged.
+pub const MAX_CLOCK_OFFSET: TimeDelta = TimeDelta::minutes(2);
+
+/// Result of comparing the system clock with the time reported by the API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockCheck {
+    /// Estimated amount of time that the system clock is ahead of the API. This is negative if
+    /// the system clock is behind. The API only reports whole seconds.
+    pub offset: TimeDelta,
+    /// Whether the offset is within [MAX_CLOCK_OFFSET]
+    pub within_tolerance: bool,
+}
+
+impl ClockCheck {
+    /// Compare the time reported by the API with the system time. `request_sent` and
+    /// `response_received` are the system times when the request was sent and when the response
+    /// was received, and the time of the API is assumed to have been read in between.
+    pub fn new(
+        request_sent: DateTime<Utc>,
+        response_received: DateTime<Utc>,
+        api_time: DateTime<Utc>,
+    ) -> Self {
+        let local_time = request_sent + (response_received - request_sent) / 2;
+        let offset = TimeDelta::seconds((local_time - api_time).num_seconds());
+        Self {
+            offset,
+            within_tolerance: offset.abs() <= MAX_CLOCK_OFFSET,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_clock_check() {
+        let api_time = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+
+        let check = ClockCheck::new(
+            api_time - TimeDelta::milliseconds(200),
+            api_time + TimeDelta::milliseconds(400),
+            api_time,
+        );
+        assert_eq!(check.offset, TimeDelta::zero());
+        assert!(check.within_tolerance);
+
+        let request_sent = api_time + TimeDelta::minutes(5);
+        let check = ClockCheck::new(request_sent, request_sent + TimeDelta::seconds(2), api_time);
+        assert_eq!(check.offset, TimeDelta::seconds(301));
+        assert!(!check.within_tolerance);
+
+        let request_sent = api_time - TimeDelta::minutes(2);
+        let check = ClockCheck::new(request_sent, request_sent, api_time);
+        assert_eq!(check.offset, TimeDelta::minutes(-2));
+        assert!(check.within_tolerance);
+    }
+}
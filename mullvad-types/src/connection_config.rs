@@ -0,0 +1,22 @@
+//! The configuration that a tunnel would use if it was started now.
+
+use crate::{location::GeoIpLocation, settings::EffectiveDnsServer};
+use ipnetwork::IpNetwork;
+use serde::{Deserialize, Serialize};
+use talpid_types::net::TunnelEndpoint;
+
+/// The configuration that a tunnel would use if it was started now. Relays are picked at random
+/// among the ones matching the constraints, so an actual connection may use different relays.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectiveConnectionConfig {
+    /// Protocol, entry endpoint, obfuscation, and whether quantum resistance and DAITA are used
+    pub endpoint: TunnelEndpoint,
+    /// Location and hostnames of the relays. This is `None` for custom relays
+    pub location: Option<GeoIpLocation>,
+    /// Networks that are routed through the tunnel
+    pub allowed_ips: Vec<IpNetwork>,
+    /// MTU of the tunnel interface. This is `None` if the MTU is chosen automatically
+    pub mtu: Option<u16>,
+    /// DNS servers that would be used, in order of priority
+    pub dns_servers: Vec<EffectiveDnsServer>,
+}
@@ -0,0 +1,344 @@
+//! A bounded record of recent attempts to establish a tunnel.
+
+use crate::location::GeoIpLocation;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, VecDeque};
+use talpid_types::net::{
+    Endpoint, ObfuscationInfo, ObfuscationType, TransportProtocol, TunnelEndpoint,
+};
+
+/// Number of attempts to remember. Older attempts are forgotten first.
+pub const MAX_CONNECTION_ATTEMPTS: usize = 50;
+
+/// An attempt to establish a tunnel to a relay.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionAttempt {
+    pub started: DateTime<Utc>,
+    /// Protocol, entry endpoint, and obfuscation used
+    pub endpoint: TunnelEndpoint,
+    /// Location and hostnames of the relays used. This is `None` for custom relays
+    pub location: Option<GeoIpLocation>,
+    pub outcome: ConnectionAttemptOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConnectionAttemptOutcome {
+    /// The tunnel is still being established
+    Pending,
+    Connected,
+    /// A new attempt was started before this one connected, e.g. because it timed out
+    Superseded,
+    /// The tunnel was disconnected before it connected
+    Aborted,
+    /// The tunnel entered the error state for the given reason
+    Failed(String),
+}
+
+/// Whether traffic to relays appears to get through over each transport, judging by the outcome
+/// of recent connection attempts. Automatic obfuscation is tried when attempts over plain UDP fail.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NetworkProbeResults {
+    /// Plain UDP, and obfuscation other than QUIC and LWO that is carried over UDP
+    pub udp: ProtocolReachability,
+    pub tcp: ProtocolReachability,
+    /// QUIC obfuscation. This is kept apart from UDP, since it can get through where plain UDP is
+    /// blocked.
+    pub quic: ProtocolReachability,
+    /// LWO obfuscation. This is kept apart from UDP, since it can get through where plain UDP is
+    /// blocked.
+    pub lwo: ProtocolReachability,
+    /// When the most recent attempt that the results are based on was started
+    pub last_probe: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProtocolReachability {
+    /// Whether the most recent finished attempt over the protocol connected, or `None` if there has
+    /// been no such attempt
+    pub reachable: Option<bool>,
+    /// Ports for which the most recent finished attempt connected
+    pub reachable_ports: BTreeSet<u16>,
+    /// Ports for which the most recent finished attempt did not connect
+    pub unreachable_ports: BTreeSet<u16>,
+    /// Number of attempts that connected
+    pub connected: u32,
+    /// Number of attempts that were superseded by a new attempt before they connected
+    pub superseded: u32,
+    /// Number of attempts that ended in the error state. These do not affect reachability, since
+    /// the error need not have anything to do with the network.
+    pub failed: u32,
+}
+
+impl ProtocolReachability {
+    fn record_outcome(&mut self, port: u16, outcome: &ConnectionAttemptOutcome) {
+        match outcome {
+            ConnectionAttemptOutcome::Connected => {
+                self.connected += 1;
+                self.record(port, true);
+            }
+            ConnectionAttemptOutcome::Superseded => {
+                self.superseded += 1;
+                self.record(port, false);
+            }
+            ConnectionAttemptOutcome::Failed(_) => self.failed += 1,
+            ConnectionAttemptOutcome::Pending | ConnectionAttemptOutcome::Aborted => (),
+        }
+    }
+
+    fn record(&mut self, port: u16, reachable: bool) {
+        self.reachable = Some(reachable);
+        if reachable {
+            self.unreachable_ports.remove(&port);
+            self.reachable_ports.insert(port);
+        } else {
+            self.reachable_ports.remove(&port);
+            self.unreachable_ports.insert(port);
+        }
+    }
+}
+
+/// The most recent connection attempts, oldest first.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ConnectionAttemptHistory {
+    attempts: VecDeque<ConnectionAttempt>,
+}
+
+impl ConnectionAttemptHistory {
+    /// Record a new pending attempt. Any attempt that is still pending is superseded by it.
+    pub fn start(&mut self, endpoint: TunnelEndpoint, location: Option<GeoIpLocation>) {
+        self.finish(ConnectionAttemptOutcome::Superseded);
+        if self.attempts.len() >= MAX_CONNECTION_ATTEMPTS {
+            self.attempts.pop_front();
+        }
+        self.attempts.push_back(ConnectionAttempt {
+            started: Utc::now(),
+            endpoint,
+            location,
+            outcome: ConnectionAttemptOutcome::Pending,
+        });
+    }
+
+    /// Set the outcome of the last attempt, if it is still pending.
+    pub fn finish(&mut self, outcome: ConnectionAttemptOutcome) {
+        if let Some(attempt) = self.attempts.back_mut()
+            && attempt.outcome == ConnectionAttemptOutcome::Pending
+        {
+            attempt.outcome = outcome;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ConnectionAttempt> {
+        self.attempts.iter()
+    }
+
+    /// Assess which transports are reachable from the attempts that have finished. Attempts
+    /// that were aborted are ignored, as are attempts through a multiplexer, since it is not known
+    /// which of its obfuscators was used.
+    pub fn probe_results(&self) -> NetworkProbeResults {
+        let mut results = NetworkProbeResults::default();
+        for attempt in &self.attempts {
+            if matches!(
+                attempt.outcome,
+                ConnectionAttemptOutcome::Pending | ConnectionAttemptOutcome::Aborted
+            ) {
+                continue;
+            }
+            let Some((first_hop, obfuscation)) = first_hop(&attempt.endpoint) else {
+                continue;
+            };
+            let transport = match (obfuscation, first_hop.protocol) {
+                (Some(ObfuscationType::Quic), _) => &mut results.quic,
+                (Some(ObfuscationType::Lwo), _) => &mut results.lwo,
+                (_, TransportProtocol::Udp) => &mut results.udp,
+                (_, TransportProtocol::Tcp) => &mut results.tcp,
+            };
+            transport.record_outcome(first_hop.address.port(), &attempt.outcome);
+            results.last_probe = Some(attempt.started);
+        }
+        results
+    }
+}
+
+/// Return the endpoint that tunnel traffic is sent to directly and the obfuscation used, or `None`
+/// if it depends on which obfuscator a multiplexer picked.
+fn first_hop(endpoint: &TunnelEndpoint) -> Option<(Endpoint, Option<ObfuscationType>)> {
+    match &endpoint.obfuscation {
+        Some(ObfuscationInfo::Single(obfuscation)) => {
+            Some((obfuscation.endpoint, Some(obfuscation.obfuscation_type)))
+        }
+        Some(ObfuscationInfo::Multiplexer { .. }) => None,
+        None => Some((endpoint.entry_endpoint.unwrap_or(endpoint.endpoint), None)),
+    }
+}
+
+impl From<Vec<ConnectionAttempt>> for ConnectionAttemptHistory {
+    fn from(attempts: Vec<ConnectionAttempt>) -> Self {
+        Self {
+            attempts: attempts.into(),
+        }
+    }
+}
+
+impl From<ConnectionAttemptHistory> for Vec<ConnectionAttempt> {
+    fn from(history: ConnectionAttemptHistory) -> Self {
+        history.attempts.into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use talpid_types::net::ObfuscationEndpoint;
+
+    fn obfuscated_endpoint(
+        port: u16,
+        protocol: TransportProtocol,
+        obfuscation_type: ObfuscationType,
+    ) -> TunnelEndpoint {
+        let mut endpoint = endpoint(1);
+        endpoint.obfuscation = Some(ObfuscationInfo::Single(ObfuscationEndpoint {
+            endpoint: Endpoint::new([10, 0, 0, 1], port, protocol),
+            obfuscation_type,
+        }));
+        endpoint
+    }
+
+    fn endpoint(port: u16) -> TunnelEndpoint {
+        TunnelEndpoint {
+            endpoint: Endpoint::new([10, 0, 0, 1], port, TransportProtocol::Udp),
+            quantum_resistant: false,
+            obfuscation: None,
+            entry_endpoint: None,
+            tunnel_interface: None,
+            #[cfg(daita)]
+            daita: false,
+        }
+    }
+
+    #[test]
+    fn test_connection_attempt_history() {
+        let mut history = ConnectionAttemptHistory::default();
+        history.start(endpoint(1), None);
+        history.start(endpoint(2), None);
+        history.finish(ConnectionAttemptOutcome::Connected);
+        // Only pending attempts are finished
+        history.finish(ConnectionAttemptOutcome::Aborted);
+
+        let outcomes: Vec<_> = history.iter().map(|attempt| &attempt.outcome).collect();
+        assert_eq!(
+            outcomes,
+            [
+                &ConnectionAttemptOutcome::Superseded,
+                &ConnectionAttemptOutcome::Connected
+            ]
+        );
+
+        for port in 0..MAX_CONNECTION_ATTEMPTS as u16 {
+            history.start(endpoint(100 + port), None);
+        }
+        assert_eq!(history.iter().count(), MAX_CONNECTION_ATTEMPTS);
+        assert_eq!(history.iter().next().unwrap().endpoint, endpoint(100));
+    }
+
+    #[test]
+    fn test_probe_results() {
+        let mut history = ConnectionAttemptHistory::default();
+        assert_eq!(history.probe_results(), NetworkProbeResults::default());
+
+        // UDP times out on port 1, so the next attempt is obfuscated over TCP
+        history.start(endpoint(1), None);
+        history.start(
+            obfuscated_endpoint(80, TransportProtocol::Tcp, ObfuscationType::Udp2Tcp),
+            None,
+        );
+        history.finish(ConnectionAttemptOutcome::Connected);
+
+        // Aborted attempts say nothing about reachability
+        history.start(endpoint(2), None);
+        history.finish(ConnectionAttemptOutcome::Aborted);
+
+        let results = history.probe_results();
+        assert_eq!(results.udp.reachable, Some(false));
+        assert_eq!(results.udp.unreachable_ports, BTreeSet::from([1]));
+        assert_eq!(results.tcp.reachable, Some(true));
+        assert_eq!(results.tcp.reachable_ports, BTreeSet::from([80]));
+        assert_eq!(
+            results.last_probe,
+            Some(history.iter().nth(1).unwrap().started)
+        );
+
+        // A later successful attempt on the same port replaces the failure
+        history.start(endpoint(1), None);
+        history.finish(ConnectionAttemptOutcome::Connected);
+        let results = history.probe_results();
+        assert_eq!(results.udp.reachable, Some(true));
+        assert_eq!(results.udp.reachable_ports, BTreeSet::from([1]));
+        assert!(results.udp.unreachable_ports.is_empty());
+    }
+
+    /// Failed attempts are counted but do not make a transport unreachable, and QUIC and LWO are
+    /// not counted as UDP
+    #[test]
+    fn test_probe_results_per_outcome_and_transport() {
+        let mut history = ConnectionAttemptHistory::default();
+
+        history.start(endpoint(1), None);
+        history.finish(ConnectionAttemptOutcome::Connected);
+        history.start(endpoint(1), None);
+        history.finish(ConnectionAttemptOutcome::Failed("error".to_owned()));
+
+        history.start(
+            obfuscated_endpoint(443, TransportProtocol::Udp, ObfuscationType::Quic),
+            None,
+        );
+        history.start(
+            obfuscated_endpoint(443, TransportProtocol::Udp, ObfuscationType::Quic),
+            None,
+        );
+        history.finish(ConnectionAttemptOutcome::Connected);
+
+        history.start(
+            obfuscated_endpoint(2, TransportProtocol::Udp, ObfuscationType::Lwo),
+            None,
+        );
+        history.finish(ConnectionAttemptOutcome::Failed("error".to_owned()));
+
+        let results = history.probe_results();
+        assert_eq!(
+            results.udp,
+            ProtocolReachability {
+                reachable: Some(true),
+                reachable_ports: BTreeSet::from([1]),
+                unreachable_ports: BTreeSet::new(),
+                connected: 1,
+                superseded: 0,
+                failed: 1,
+            }
+        );
+        assert_eq!(
+            results.quic,
+            ProtocolReachability {
+                reachable: Some(true),
+                reachable_ports: BTreeSet::from([443]),
+                unreachable_ports: BTreeSet::new(),
+                connected: 1,
+                superseded: 1,
+                failed: 0,
+            }
+        );
+        assert_eq!(
+            results.lwo,
+            ProtocolReachability {
+                reachable: None,
+                failed: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(results.tcp, ProtocolReachability::default());
+        assert_eq!(
+            results.last_probe,
+            Some(history.iter().last().unwrap().started)
+        );
+    }
+}
@@ -132,4 +132,5 @@ impl_intersection_partialeq!(relay_constraints::LocationConstraint);
 impl_intersection_partialeq!(relay_constraints::Ownership);
 impl_intersection_partialeq!(talpid_types::net::TransportProtocol);
 impl_intersection_partialeq!(talpid_types::net::IpVersion);
+impl_intersection_partialeq!(talpid_types::net::proxy::Shadowsocks);
 impl_intersection_partialeq!(relay_constraints::AllowedIps);
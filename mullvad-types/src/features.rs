@@ -7,6 +7,28 @@ use crate::settings::{DnsState, Settings};
 use serde::{Deserialize, Serialize};
 use talpid_types::net::{ObfuscationInfo, ObfuscationType, TunnelEndpoint};
 
+/// Functionality supported by the running daemon. Clients should use this to detect whether a
+/// feature is available, rather than guessing based on the platform or version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupportedFeatures {
+    pub daita: bool,
+    /// How applications are excluded from the tunnel, if split tunneling is supported at all
+    pub split_tunnel: Option<SplitTunnelKind>,
+    pub obfuscation: Vec<ObfuscationType>,
+    pub quantum_resistant: bool,
+}
+
+/// The kind of split tunneling supported by the daemon
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SplitTunnelKind {
+    /// Processes are excluded by process ID
+    ProcessIds,
+    /// Applications are excluded by path
+    AppPaths,
+    /// Applications are excluded by package name
+    AppPackages,
+}
+
 /// Feature indicators are active settings that should be shown to the user to make them aware of
 /// what is affecting their connection at any given time.
 ///
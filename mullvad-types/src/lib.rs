@@ -1,16 +1,30 @@
 pub mod access_method;
 pub mod account;
+pub mod api;
 pub mod auth_failed;
+pub mod cache;
+pub mod clock;
+pub mod connection_config;
+pub mod connection_history;
 pub mod constraints;
 pub mod custom_list;
 pub mod device;
 pub mod endpoint;
 pub mod features;
 pub mod location;
+pub mod logging;
+pub mod network_simulation;
+pub mod notification;
+pub mod profile;
 pub mod relay_constraints;
+pub mod relay_link;
 pub mod relay_list;
+pub mod resource_usage;
+pub mod schedule;
 pub mod settings;
 pub mod states;
+pub mod trusted_network;
+pub mod usage_stats;
 pub mod version;
 pub mod wireguard;
 
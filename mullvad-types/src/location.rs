@@ -148,6 +148,33 @@ pub struct AmIMullvad {
     pub mullvad_exit_ip: bool,
 }
 
+/// A DNS resolver reported by the am.i.mullvad.net DNS leak service.
+#[derive(Debug, Deserialize)]
+pub struct AmIMullvadDnsResolver {
+    pub ip: IpAddr,
+    pub mullvad_dns: bool,
+}
+
+/// The outcome of checking whether DNS queries leak outside of Mullvad.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsLeakResult {
+    /// Whether any DNS query was answered by a resolver not operated by Mullvad. This is `None`
+    /// if no resolver was reported, since the leak status is then unknown.
+    pub leaking: Option<bool>,
+    /// The resolvers which performed the DNS queries.
+    pub resolvers: Vec<IpAddr>,
+}
+
+impl DnsLeakResult {
+    pub fn from_resolvers(resolvers: Vec<AmIMullvadDnsResolver>) -> Self {
+        DnsLeakResult {
+            leaking: (!resolvers.is_empty())
+                .then(|| resolvers.iter().any(|resolver| !resolver.mullvad_dns)),
+            resolvers: resolvers.into_iter().map(|resolver| resolver.ip).collect(),
+        }
+    }
+}
+
 /// GeoIP information exposed from the daemon to frontends.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GeoIpLocation {
@@ -194,7 +221,8 @@ pub struct LocationEventData {
 
 #[cfg(test)]
 mod tests {
-    use super::Coordinates;
+    use super::{AmIMullvadDnsResolver, Coordinates, DnsLeakResult};
+    use std::net::{IpAddr, Ipv4Addr};
 
     impl Coordinates {
         fn equal(&self, other: Coordinates) -> bool {
@@ -204,6 +232,49 @@ mod tests {
         }
     }
 
+    fn resolver(ip: [u8; 4], mullvad_dns: bool) -> AmIMullvadDnsResolver {
+        AmIMullvadDnsResolver {
+            ip: IpAddr::V4(Ipv4Addr::from(ip)),
+            mullvad_dns,
+        }
+    }
+
+    /// DNS leaks if any resolver is not operated by Mullvad, regardless of where it is listed.
+    #[test]
+    fn test_dns_leak_decision() {
+        let result = DnsLeakResult::from_resolvers(vec![
+            resolver([10, 64, 0, 1], true),
+            resolver([192, 0, 2, 53], false),
+            resolver([10, 64, 0, 2], true),
+        ]);
+        assert_eq!(result.leaking, Some(true));
+        assert_eq!(
+            result.resolvers,
+            [
+                IpAddr::V4(Ipv4Addr::new(10, 64, 0, 1)),
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 53)),
+                IpAddr::V4(Ipv4Addr::new(10, 64, 0, 2)),
+            ]
+        );
+
+        let result = DnsLeakResult::from_resolvers(vec![resolver([192, 0, 2, 53], false)]);
+        assert_eq!(result.leaking, Some(true));
+
+        let result = DnsLeakResult::from_resolvers(vec![
+            resolver([10, 64, 0, 1], true),
+            resolver([10, 64, 0, 2], true),
+        ]);
+        assert_eq!(result.leaking, Some(false));
+    }
+
+    /// Without any resolvers, it is unknown whether DNS leaks.
+    #[test]
+    fn test_dns_leak_decision_no_resolvers() {
+        let result = DnsLeakResult::from_resolvers(vec![]);
+        assert_eq!(result.leaking, None);
+        assert!(result.resolvers.is_empty());
+    }
+
     #[test]
     fn test_haversine_dist_deg() {
         use super::haversine_dist_deg;
@@ -0,0 +1,53 @@
+//! Structured log records, streamed from the daemon to clients as they are emitted.
+
+use chrono::{DateTime, Utc};
+
+/// Severity of a log record, from most to least severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// Whether a record with this level passes a `min_level` filter, i.e. whether it is at least
+    /// as severe as `min_level`.
+    pub fn is_enabled(&self, min_level: LogLevel) -> bool {
+        *self <= min_level
+    }
+}
+
+/// A single log record emitted by the daemon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    pub level: LogLevel,
+    /// Module path or target that emitted the record
+    pub target: String,
+    pub message: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// An item of the stream of log records
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogStreamEvent {
+    Record(LogRecord),
+    /// This many records were dropped since the previous item, because the client did not keep
+    /// up
+    Dropped(u64),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_min_level() {
+        assert!(LogLevel::Error.is_enabled(LogLevel::Info));
+        assert!(LogLevel::Info.is_enabled(LogLevel::Info));
+        assert!(!LogLevel::Debug.is_enabled(LogLevel::Info));
+        assert!(!LogLevel::Trace.is_enabled(LogLevel::Error));
+    }
+}
@@ -0,0 +1,75 @@
+//! Simulated network conditions, for testing how the daemon handles poor connectivity. These are
+//! only applied by daemons built with the `network-simulation` feature.
+//!
+//! The conditions are applied to every read and write on API connections. Lost data is
+//! retransmitted after a timeout, like TCP does, and the read or write fails if too many
+//! retransmissions are lost. Only API connections are affected. Tunnel traffic, including API
+//! requests sent through the tunnel, is not.
+
+use std::time::Duration;
+
+/// Largest allowed [`SimulatedNetworkConditions::loss_percent`]
+pub const MAX_LOSS_PERCENT: u8 = 100;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("The loss percentage must be at most 100")]
+    InvalidLossPercent,
+}
+
+/// Conditions applied to new API connections.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SimulatedNetworkConditions {
+    /// Percentage of reads and writes that are lost, and have to be retransmitted
+    loss_percent: u8,
+    /// Time added to each read and write
+    pub added_latency: Duration,
+    /// Seed for deciding what is lost, so that a simulation can be reproduced. A random seed is
+    /// used if this is `None`.
+    pub seed: Option<u64>,
+}
+
+impl SimulatedNetworkConditions {
+    pub fn new(loss_percent: u8, added_latency: Duration) -> Result<Self, Error> {
+        if loss_percent > MAX_LOSS_PERCENT {
+            return Err(Error::InvalidLossPercent);
+        }
+        Ok(Self {
+            loss_percent,
+            added_latency,
+            seed: None,
+        })
+    }
+
+    pub fn loss_percent(&self) -> u8 {
+        self.loss_percent
+    }
+
+    /// Return whether no conditions are simulated
+    pub fn is_empty(&self) -> bool {
+        self.loss_percent == 0 && self.added_latency.is_zero()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_loss_percent() {
+        let latency = Duration::from_millis(100);
+        assert_eq!(
+            SimulatedNetworkConditions::new(100, latency).map(|c| c.loss_percent()),
+            Ok(100)
+        );
+        assert_eq!(
+            SimulatedNetworkConditions::new(101, latency),
+            Err(Error::InvalidLossPercent)
+        );
+        assert!(
+            SimulatedNetworkConditions::new(0, Duration::ZERO)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,38 @@
+//! Conditions that the user should be notified about.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A condition that the user should be notified about, until it is acknowledged or no longer
+/// applies.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notification {
+    /// Identifies the notification. The same condition always results in the same ID, so an
+    /// acknowledged notification does not reappear.
+    pub id: String,
+    pub kind: NotificationKind,
+    pub severity: NotificationSeverity,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// The account runs out of time soon
+    AccountExpiringSoon { expiry: DateTime<Utc> },
+    /// The account has run out of time
+    AccountExpired { expiry: DateTime<Utc> },
+    /// A newer version of the app is available
+    UpdateAvailable { version: String },
+    /// The running version of the app is no longer supported
+    UnsupportedVersion,
+    /// The device was removed from the account by someone else
+    DeviceRevoked,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationSeverity {
+    Info,
+    Warning,
+    Critical,
+}
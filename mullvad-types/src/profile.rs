@@ -0,0 +1,125 @@
+//! Named sets of relay, obfuscation, and tunnel settings that can be applied together.
+
+use crate::{
+    relay_constraints::{ObfuscationSettings, RelaySettings},
+    settings::{Settings, TunnelOptions},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Profile name must not be empty")]
+    EmptyName,
+    #[error("Profile not found")]
+    ProfileNotFound,
+}
+
+/// Settings that are saved in and applied from a profile. DNS options are part of
+/// `tunnel_options`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConnectionProfile {
+    pub name: String,
+    pub relay_settings: RelaySettings,
+    pub obfuscation_settings: ObfuscationSettings,
+    pub tunnel_options: TunnelOptions,
+}
+
+impl ConnectionProfile {
+    /// Create a profile from the current `settings`.
+    pub fn from_settings(name: String, settings: &Settings) -> Result<Self, Error> {
+        if name.is_empty() {
+            return Err(Error::EmptyName);
+        }
+        Ok(ConnectionProfile {
+            name,
+            relay_settings: settings.relay_settings.clone(),
+            obfuscation_settings: settings.obfuscation_settings.clone(),
+            tunnel_options: settings.tunnel_options.clone(),
+        })
+    }
+
+    /// Replace all settings contained in the profile.
+    pub fn apply_to(&self, settings: &mut Settings) {
+        settings.relay_settings = self.relay_settings.clone();
+        settings.obfuscation_settings = self.obfuscation_settings.clone();
+        settings.tunnel_options = self.tunnel_options.clone();
+    }
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProfilesSettings {
+    profiles: Vec<ConnectionProfile>,
+}
+
+impl From<Vec<ConnectionProfile>> for ProfilesSettings {
+    fn from(profiles: Vec<ConnectionProfile>) -> Self {
+        Self { profiles }
+    }
+}
+
+impl ProfilesSettings {
+    /// Add a profile, replacing any existing profile with the same name.
+    pub fn save(&mut self, profile: ConnectionProfile) {
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ConnectionProfile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), Error> {
+        let index = self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == name)
+            .ok_or(Error::ProfileNotFound)?;
+        self.profiles.remove(index);
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ConnectionProfile> {
+        self.profiles.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::relay_constraints::SelectedObfuscation;
+
+    #[test]
+    fn test_save_and_apply() {
+        let mut settings = Settings::default();
+        assert!(ConnectionProfile::from_settings(String::new(), &settings).is_err());
+
+        settings.obfuscation_settings.selected_obfuscation = SelectedObfuscation::Shadowsocks;
+        let work = ConnectionProfile::from_settings("work".to_owned(), &settings).unwrap();
+
+        let mut profiles = ProfilesSettings::default();
+        profiles.save(work.clone());
+        settings.obfuscation_settings.selected_obfuscation = SelectedObfuscation::Off;
+        profiles.save(ConnectionProfile::from_settings("work".to_owned(), &settings).unwrap());
+        assert_eq!(
+            profiles.iter().count(),
+            1,
+            "saving should replace the profile"
+        );
+
+        profiles.get("work").unwrap().apply_to(&mut settings);
+        assert_eq!(
+            settings.obfuscation_settings.selected_obfuscation,
+            SelectedObfuscation::Off
+        );
+        work.apply_to(&mut settings);
+        assert_eq!(
+            settings.obfuscation_settings.selected_obfuscation,
+            SelectedObfuscation::Shadowsocks
+        );
+
+        profiles.remove("work").unwrap();
+        assert!(profiles.remove("work").is_err());
+    }
+}
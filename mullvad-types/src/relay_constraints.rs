@@ -15,7 +15,7 @@ use std::{
     net::{Ipv4Addr, Ipv6Addr},
     str::FromStr,
 };
-use talpid_types::net::{IpVersion, TransportProtocol};
+use talpid_types::net::{IpVersion, ObfuscationType, TransportProtocol, proxy::Shadowsocks};
 
 /// Specifies a specific endpoint or [`RelayConstraints`] to use when `mullvad-daemon` selects a
 /// relay.
@@ -67,7 +67,11 @@ impl fmt::Display for RelaySettingsFormatter<'_> {
 #[serde(rename_all = "snake_case")]
 pub enum LocationConstraint {
     Location(GeographicLocationConstraint),
-    CustomList { list_id: Id },
+    CustomList {
+        list_id: Id,
+    },
+    /// Any relay whose hostname matches a glob pattern, such as `se-got-wg-*`.
+    HostnamePattern(HostnamePattern),
 }
 
 pub struct LocationConstraintFormatter<'a> {
@@ -91,6 +95,7 @@ impl fmt::Display for LocationConstraintFormatter<'_> {
                 .find(|list| list.id() == *list_id)
                 .map(|custom_list| write!(f, "{}", custom_list.name))
                 .unwrap_or_else(|| write!(f, "invalid custom list")),
+            LocationConstraint::HostnamePattern(pattern) => write!(f, "{pattern}"),
         }
     }
 }
@@ -229,6 +234,187 @@ impl FromStr for GeographicLocationConstraint {
     }
 }
 
+/// A glob pattern that is matched against relay hostnames.
+///
+/// `*` matches any sequence of characters, `?` matches any single character, and `[...]` matches
+/// one of the characters or ranges in the brackets, such as `[0-9]`. A bracket expression beginning
+/// with `!` matches any character not in it. Matching is case-insensitive.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HostnamePattern {
+    pattern: String,
+    tokens: Vec<PatternToken>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+enum PatternToken {
+    Char(char),
+    AnyChar,
+    AnySequence,
+    Set {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Invalid hostname pattern \"{pattern}\": {reason}")]
+pub struct ParseHostnamePatternError {
+    pattern: String,
+    reason: &'static str,
+}
+
+impl HostnamePattern {
+    /// Return whether `hostname` matches the pattern.
+    pub fn is_match(&self, hostname: &str) -> bool {
+        let hostname: Vec<char> = hostname.to_ascii_lowercase().chars().collect();
+
+        // Position to resume from if the current attempt fails: The token following the last `*`,
+        // and the next character of the hostname for the `*` to consume.
+        let mut backtrack = None;
+        let (mut token, mut character) = (0, 0);
+        while character < hostname.len() {
+            match self.tokens.get(token) {
+                Some(PatternToken::AnySequence) => {
+                    token += 1;
+                    backtrack = Some((token, character));
+                    continue;
+                }
+                Some(next_token) if next_token.matches(hostname[character]) => {
+                    token += 1;
+                    character += 1;
+                    continue;
+                }
+                _ => (),
+            }
+            let Some((resume_token, resume_character)) = backtrack else {
+                return false;
+            };
+            token = resume_token;
+            character = resume_character + 1;
+            backtrack = Some((resume_token, character));
+        }
+        self.tokens[token..]
+            .iter()
+            .all(|token| *token == PatternToken::AnySequence)
+    }
+
+    fn parse_set(
+        chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    ) -> Result<PatternToken, &'static str> {
+        let negated = chars.next_if_eq(&'!').is_some();
+        let mut ranges = vec![];
+        loop {
+            let start = match chars.next() {
+                Some(']') if !ranges.is_empty() => break,
+                Some(']') => return Err("empty bracket expression"),
+                Some(c) if is_hostname_char(c) => c,
+                Some(_) => return Err("invalid character in bracket expression"),
+                None => return Err("unterminated bracket expression"),
+            };
+            if chars.next_if_eq(&'-').is_some() {
+                let end = match chars.next() {
+                    Some(c) if is_hostname_char(c) => c,
+                    Some(_) => return Err("invalid range in bracket expression"),
+                    None => return Err("unterminated bracket expression"),
+                };
+                if start > end {
+                    return Err("invalid range in bracket expression");
+                }
+                ranges.push((start, end));
+            } else {
+                ranges.push((start, start));
+            }
+        }
+        Ok(PatternToken::Set { negated, ranges })
+    }
+}
+
+impl PatternToken {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            PatternToken::Char(expected) => *expected == c,
+            PatternToken::AnyChar => true,
+            PatternToken::AnySequence => false,
+            PatternToken::Set { negated, ranges } => {
+                ranges
+                    .iter()
+                    .any(|(start, end)| (*start..=*end).contains(&c))
+                    != *negated
+            }
+        }
+    }
+}
+
+const fn is_hostname_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'
+}
+
+impl FromStr for HostnamePattern {
+    type Err = ParseHostnamePatternError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let pattern = input.to_ascii_lowercase();
+        let error = |reason| ParseHostnamePatternError {
+            pattern: input.to_owned(),
+            reason,
+        };
+        if pattern.is_empty() {
+            return Err(error("the pattern is empty"));
+        }
+
+        let mut tokens = vec![];
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '*' => PatternToken::AnySequence,
+                '?' => PatternToken::AnyChar,
+                '[' => Self::parse_set(&mut chars).map_err(error)?,
+                c if is_hostname_char(c) => PatternToken::Char(c),
+                _ => return Err(error("invalid character")),
+            };
+            tokens.push(token);
+        }
+
+        Ok(HostnamePattern { pattern, tokens })
+    }
+}
+
+impl TryFrom<String> for HostnamePattern {
+    type Error = ParseHostnamePatternError;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        pattern.parse()
+    }
+}
+
+impl From<HostnamePattern> for String {
+    fn from(pattern: HostnamePattern) -> Self {
+        pattern.pattern
+    }
+}
+
+impl fmt::Display for HostnamePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+impl Match<WireguardRelay> for HostnamePattern {
+    fn matches(&self, relay: &WireguardRelay) -> bool {
+        self.is_match(&relay.hostname)
+    }
+}
+
+/// A location constraint that was ignored by the relay selector because no relay satisfied it.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RelaySelectionFallback {
+    /// The hostname pattern that did not match any active relay.
+    pub pattern: HostnamePattern,
+    /// Whether the pattern was used for the entry relay, rather than the exit relay.
+    pub entry: bool,
+}
+
 /// Limits the set of servers to choose based on ownership.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
@@ -369,11 +555,12 @@ pub struct WireguardConstraints {
 
 pub use allowed_ip::AllowedIps;
 pub mod allowed_ip {
-    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
     use crate::constraints::Constraint;
     use ipnetwork::IpNetwork;
     use serde::{Deserialize, Serialize};
+    use talpid_types::net::ALLOWED_LAN_NETS;
 
     #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
     pub struct AllowedIps(pub Vec<IpNetwork>);
@@ -479,6 +666,124 @@ pub mod allowed_ip {
         }
     }
 
+    /// The state of the host that a set of allowed IPs is validated against.
+    #[derive(Debug, Default, Clone)]
+    pub struct ValidationContext {
+        pub allow_lan: bool,
+        /// Custom DNS servers, if custom DNS is enabled
+        pub dns_servers: Vec<IpAddr>,
+        /// Destinations of the routes in the routing table of the host, excluding routes through
+        /// the tunnel interface
+        pub local_routes: Vec<IpNetwork>,
+    }
+
+    /// A potential problem with a set of allowed IPs. See [AllowedIps::validate].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum AllowedIpsWarning {
+        /// `network` overlaps `local_network`, a private network that the host has a route to
+        CapturesLocalNetwork {
+            network: IpNetwork,
+            local_network: IpNetwork,
+        },
+        /// The DNS server is not in any allowed network, so queries to it will be dropped
+        ExcludesDnsServer(IpAddr),
+        /// `network` is already contained in `containing`
+        Redundant {
+            network: IpNetwork,
+            containing: IpNetwork,
+        },
+    }
+
+    impl std::fmt::Display for AllowedIpsWarning {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                AllowedIpsWarning::CapturesLocalNetwork {
+                    network,
+                    local_network,
+                } => write!(
+                    f,
+                    "{network} will capture your local network ({local_network}) unless allow-LAN is on"
+                ),
+                AllowedIpsWarning::ExcludesDnsServer(server) => write!(
+                    f,
+                    "The DNS server {server} is not allowed, so DNS queries to it will fail"
+                ),
+                AllowedIpsWarning::Redundant {
+                    network,
+                    containing,
+                } => write!(f, "{network} is redundant since {containing} is allowed"),
+            }
+        }
+    }
+
+    impl AllowedIps {
+        /// Check the allowed IPs for conflicts with the local network and DNS settings. This only
+        /// analyzes the networks and does not change anything.
+        pub fn validate(&self, context: &ValidationContext) -> Vec<AllowedIpsWarning> {
+            let mut warnings = vec![];
+
+            for (index, network) in self.0.iter().enumerate() {
+                let containing = self.0.iter().enumerate().find(|(other_index, other)| {
+                    *other_index != index
+                        && contains_network(other, network)
+                        // Only report one of two identical networks
+                        && (*other != network || *other_index < index)
+                });
+                if let Some((_, containing)) = containing {
+                    warnings.push(AllowedIpsWarning::Redundant {
+                        network: *network,
+                        containing: *containing,
+                    });
+                    continue;
+                }
+
+                if !context.allow_lan {
+                    let local_network = context.local_routes.iter().find(|route| {
+                        is_lan_network(route)
+                            && (contains_network(network, route)
+                                || contains_network(route, network))
+                    });
+                    if let Some(local_network) = local_network {
+                        warnings.push(AllowedIpsWarning::CapturesLocalNetwork {
+                            network: *network,
+                            local_network: *local_network,
+                        });
+                    }
+                }
+            }
+
+            for server in &context.dns_servers {
+                // Loopback and, with allow-LAN, local servers are not reached through the tunnel
+                if server.is_loopback() || (context.allow_lan && is_lan_address(*server)) {
+                    continue;
+                }
+                if !self.0.iter().any(|network| network.contains(*server)) {
+                    warnings.push(AllowedIpsWarning::ExcludesDnsServer(*server));
+                }
+            }
+
+            warnings
+        }
+    }
+
+    /// Return whether `inner` is a subset of `outer`.
+    fn contains_network(outer: &IpNetwork, inner: &IpNetwork) -> bool {
+        outer.is_ipv4() == inner.is_ipv4()
+            && outer.prefix() <= inner.prefix()
+            && outer.contains(inner.network())
+    }
+
+    fn is_lan_address(address: IpAddr) -> bool {
+        ALLOWED_LAN_NETS.iter().any(|net| net.contains(address))
+    }
+
+    /// Return whether `network` is a private network. Default routes are not.
+    fn is_lan_network(network: &IpNetwork) -> bool {
+        ALLOWED_LAN_NETS
+            .iter()
+            .any(|net| contains_network(net, network))
+    }
+
     /// Resolves the allowed IPs from a `Constraint<AllowedIps>`, adding the host IPv4 and IPv6 addresses if provided.
     /// If the constraint is `Constraint::Any` or `Constraint::Only` with an empty list, it allows all IPs.
     /// Returns a vector of `IpNetwork` containing the resolved allowed IPs.
@@ -590,14 +895,39 @@ impl fmt::Display for Udp2TcpObfuscationSettings {
     }
 }
 
-#[derive(Default, Debug, Clone, Eq, PartialEq, Deserialize, Serialize, Intersection)]
+#[derive(Default, Clone, Eq, PartialEq, Deserialize, Serialize, Intersection)]
 #[serde(rename_all = "snake_case")]
 pub struct ShadowsocksSettings {
     pub port: Constraint<u16>,
+    /// Shadowsocks server to use instead of the ones run by relays. The server forwards traffic
+    /// to the selected relay, so `port` does not apply to it.
+    #[serde(default)]
+    pub custom_server: Constraint<Shadowsocks>,
+}
+
+impl fmt::Debug for ShadowsocksSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Leave out the password of the custom server
+        let custom_server = self
+            .custom_server
+            .as_ref()
+            .map(|server| (server.endpoint, &server.cipher));
+        f.debug_struct("ShadowsocksSettings")
+            .field("port", &self.port)
+            .field("custom_server", &custom_server)
+            .finish()
+    }
 }
 
 impl fmt::Display for ShadowsocksSettings {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Constraint::Only(server) = &self.custom_server {
+            return write!(
+                f,
+                "custom server {} using {}",
+                server.endpoint, server.cipher
+            );
+        }
         match self.port {
             Constraint::Any => write!(f, "any port"),
             Constraint::Only(port) => write!(f, "port {port}"),
@@ -653,6 +983,48 @@ pub struct ObfuscationSettings {
     pub udp2tcp: Udp2TcpObfuscationSettings,
     pub shadowsocks: ShadowsocksSettings,
     pub wireguard_port: WireguardPortSettings,
+    /// How eagerly obfuscation is used when it is automatic
+    pub auto_sensitivity: ObfuscationSensitivity,
+}
+
+/// How many failed connection attempts without obfuscation it takes before automatic obfuscation
+/// is used. This only has an effect when [SelectedObfuscation::Auto] is selected.
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum ObfuscationSensitivity {
+    /// Make each attempt without obfuscation twice before obfuscating. Suitable for networks where
+    /// UDP is unreliable, but not blocked.
+    Low,
+    /// Make one attempt over IPv4 and one over IPv6 without obfuscation before obfuscating.
+    #[default]
+    Normal,
+    /// Obfuscate after a single failed attempt without obfuscation. Suitable for networks where
+    /// UDP is usually blocked.
+    High,
+}
+
+impl fmt::Display for ObfuscationSensitivity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObfuscationSensitivity::Low => f.write_str("low"),
+            ObfuscationSensitivity::Normal => f.write_str("normal"),
+            ObfuscationSensitivity::High => f.write_str("high"),
+        }
+    }
+}
+
+/// Obfuscation used by the most recent tunnel, which may differ from the selected obfuscation
+/// when it is automatic.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ObfuscationState {
+    /// Obfuscation selected in the settings
+    pub requested: SelectedObfuscation,
+    /// Obfuscation methods used by the tunnel, or `None` if it is not connecting or connected. No
+    /// methods means that the tunnel is not obfuscated.
+    pub active: Option<Vec<ObfuscationType>>,
+    /// Obfuscation methods used by the most recent connection attempts of the tunnel, oldest first
+    pub attempts: Vec<Vec<ObfuscationType>>,
 }
 
 /// Options to override for a particular relay to use instead of the ones specified in the relay
@@ -665,6 +1037,14 @@ pub struct RelayOverride {
     pub ipv4_addr_in: Option<Ipv4Addr>,
     /// IPv6 address to use instead of the default
     pub ipv6_addr_in: Option<Ipv6Addr>,
+    /// IPv4 address to use for the WireGuard endpoint only. Unlike `ipv4_addr_in`, this does not
+    /// affect the address used by obfuscation, or the address that is shown for the relay.
+    #[serde(default)]
+    pub wireguard_ipv4_addr_in: Option<Ipv4Addr>,
+    /// IPv6 address to use for the WireGuard endpoint only. Unlike `ipv6_addr_in`, this does not
+    /// affect the address used by obfuscation, or the address that is shown for the relay.
+    #[serde(default)]
+    pub wireguard_ipv6_addr_in: Option<Ipv6Addr>,
 }
 
 impl RelayOverride {
@@ -673,6 +1053,8 @@ impl RelayOverride {
             hostname,
             ipv4_addr_in: None,
             ipv6_addr_in: None,
+            wireguard_ipv4_addr_in: None,
+            wireguard_ipv6_addr_in: None,
         }
     }
 
@@ -695,6 +1077,20 @@ impl RelayOverride {
             );
             relay.override_ipv6(ipv6_addr_in);
         }
+        if let Some(wireguard_ipv4_addr_in) = self.wireguard_ipv4_addr_in {
+            log::debug!(
+                "Overriding WireGuard ipv4_addr_in for {}: {wireguard_ipv4_addr_in}",
+                relay.hostname
+            );
+            relay.override_wireguard_ipv4(wireguard_ipv4_addr_in);
+        }
+        if let Some(wireguard_ipv6_addr_in) = self.wireguard_ipv6_addr_in {
+            log::debug!(
+                "Overriding WireGuard ipv6_addr_in for {}: {wireguard_ipv6_addr_in}",
+                relay.hostname
+            );
+            relay.override_wireguard_ipv6(wireguard_ipv6_addr_in);
+        }
 
         // Additional IPs should be ignored when overrides are present
         relay
@@ -734,4 +1130,99 @@ mod tests {
             GeographicLocationConstraint::hostname("se", "got", "se-got-wg-101")
         );
     }
+
+    #[test]
+    fn hostname_pattern_matches() {
+        let pattern: HostnamePattern = "se-got-wg-*".parse().unwrap();
+        assert!(pattern.is_match("se-got-wg-001"));
+        assert!(pattern.is_match("SE-GOT-WG-101"));
+        assert!(!pattern.is_match("se-sto-wg-001"));
+        assert!(!pattern.is_match("se-got-wg"));
+
+        let pattern: HostnamePattern = "*-wg-00?".parse().unwrap();
+        assert!(pattern.is_match("se-got-wg-001"));
+        assert!(pattern.is_match("de-ber-wg-009"));
+        assert!(!pattern.is_match("se-got-wg-010"));
+
+        let pattern: HostnamePattern = "se-*-wg-[!0][0-4]*".parse().unwrap();
+        assert!(pattern.is_match("se-got-wg-101"));
+        assert!(pattern.is_match("se-mma-wg-14"));
+        assert!(!pattern.is_match("se-got-wg-001"));
+        assert!(!pattern.is_match("se-got-wg-151"));
+    }
+
+    #[test]
+    fn hostname_pattern_invalid() {
+        for pattern in [
+            "",
+            "se-got-[",
+            "se-[]",
+            "se-[9-0]",
+            "se got",
+            "se-got-wg-[a-]",
+        ] {
+            assert!(
+                pattern.parse::<HostnamePattern>().is_err(),
+                "{pattern} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_clear_wireguard_override() {
+        let mut relay_override = RelayOverride::empty("se-got-wg-001".to_owned());
+        relay_override.wireguard_ipv6_addr_in = Some(Ipv6Addr::LOCALHOST);
+        assert!(!relay_override.is_empty());
+
+        let mut settings = crate::settings::Settings::default();
+        settings.set_relay_override(relay_override.clone());
+        assert_eq!(settings.relay_overrides, vec![relay_override]);
+
+        // Unsetting the only overridden address removes the override
+        settings.set_relay_override(RelayOverride::empty("se-got-wg-001".to_owned()));
+        assert!(settings.relay_overrides.is_empty());
+    }
+
+    #[test]
+    fn test_validate_allowed_ips() {
+        use allowed_ip::{AllowedIps, AllowedIpsWarning, ValidationContext};
+
+        let mut context = ValidationContext {
+            allow_lan: false,
+            dns_servers: vec!["192.168.1.1".parse().unwrap(), "9.9.9.9".parse().unwrap()],
+            local_routes: vec![
+                "0.0.0.0/0".parse().unwrap(),
+                "192.168.1.0/24".parse().unwrap(),
+                "1.2.3.0/24".parse().unwrap(),
+            ],
+        };
+        let allowed_ips = AllowedIps::parse(["0.0.0.0/0", "10.0.0.0/8"]).unwrap();
+
+        assert_eq!(
+            allowed_ips.validate(&context),
+            vec![
+                AllowedIpsWarning::CapturesLocalNetwork {
+                    network: "0.0.0.0/0".parse().unwrap(),
+                    local_network: "192.168.1.0/24".parse().unwrap(),
+                },
+                AllowedIpsWarning::Redundant {
+                    network: "10.0.0.0/8".parse().unwrap(),
+                    containing: "0.0.0.0/0".parse().unwrap(),
+                },
+            ]
+        );
+
+        context.allow_lan = true;
+        let allowed_ips = AllowedIps::parse(["10.0.0.0/8", "10.0.0.0/8"]).unwrap();
+        assert_eq!(
+            allowed_ips.validate(&context),
+            vec![
+                AllowedIpsWarning::Redundant {
+                    network: "10.0.0.0/8".parse().unwrap(),
+                    containing: "10.0.0.0/8".parse().unwrap(),
+                },
+                AllowedIpsWarning::ExcludesDnsServer("9.9.9.9".parse().unwrap()),
+            ]
+        );
+    }
 }
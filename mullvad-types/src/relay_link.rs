@@ -0,0 +1,556 @@
+//! Shareable links that encode relay and obfuscation settings.
+//!
+//! A link has the form `mullvad-relay:<version>:<payload>`, where the payload is a [`LinkV1`]
+//! serialized as JSON and encoded as unpadded URL-safe base64. The payload is defined separately
+//! from the settings types, so that the link format only changes when [LINK_VERSION] does.
+//!
+//! Custom tunnel endpoints and custom Shadowsocks servers are never included in a link, since
+//! they contain secrets.
+
+use crate::{
+    constraints::Constraint,
+    custom_list::{self, CustomListsSettings},
+    relay_constraints::{
+        GeographicLocationConstraint, HostnamePattern, LocationConstraint, ObfuscationSensitivity,
+        ObfuscationSettings, Ownership, Providers, RelayConstraints, RelaySettings,
+        SelectedObfuscation, ShadowsocksSettings, Udp2TcpObfuscationSettings, WireguardConstraints,
+        WireguardPortSettings, allowed_ip::AllowedIps,
+    },
+    settings::Settings,
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use ipnetwork::IpNetwork;
+use serde::{Deserialize, Serialize};
+use talpid_types::net::IpVersion;
+
+const LINK_PREFIX: &str = "mullvad-relay";
+/// Version of the link format. Links with any other version are rejected.
+pub const LINK_VERSION: u32 = 1;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Not a relay link")]
+    InvalidPrefix,
+    #[error("Relay link version {0} is not supported")]
+    UnsupportedVersion(String),
+    #[error("The relay link is not validly encoded")]
+    InvalidEncoding(#[source] base64::DecodeError),
+    #[error("The relay link does not contain valid settings")]
+    InvalidSettings(#[source] serde_json::Error),
+    #[error("The relay link contains an invalid {0}")]
+    InvalidValue(&'static str),
+    #[error("Failed to serialize the relay link")]
+    Serialize(#[source] serde_json::Error),
+    #[error("Custom tunnel endpoints cannot be shared as a relay link")]
+    CustomTunnelEndpoint,
+    #[error("The relay link refers to a custom list that does not exist")]
+    MissingCustomList,
+}
+
+/// Relay and obfuscation settings that can be shared as a link.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelayLink {
+    pub relay_constraints: RelayConstraints,
+    /// Obfuscation settings without a custom Shadowsocks server
+    pub obfuscation_settings: ObfuscationSettings,
+}
+
+impl RelayLink {
+    /// Create a link from the current `settings`. Fails if a custom tunnel endpoint is used.
+    pub fn from_settings(settings: &Settings) -> Result<Self, Error> {
+        let RelaySettings::Normal(relay_constraints) = &settings.relay_settings else {
+            return Err(Error::CustomTunnelEndpoint);
+        };
+        let mut obfuscation_settings = settings.obfuscation_settings.clone();
+        obfuscation_settings.shadowsocks.custom_server = Constraint::Any;
+        Ok(RelayLink {
+            relay_constraints: relay_constraints.clone(),
+            obfuscation_settings,
+        })
+    }
+
+    pub fn encode(&self) -> Result<String, Error> {
+        let json = serde_json::to_vec(&LinkV1::from(self)).map_err(Error::Serialize)?;
+        Ok(format!(
+            "{LINK_PREFIX}:{LINK_VERSION}:{}",
+            URL_SAFE_NO_PAD.encode(json)
+        ))
+    }
+
+    /// Parse a link produced by [RelayLink::encode].
+    pub fn decode(link: &str) -> Result<Self, Error> {
+        let (prefix, rest) = link.trim().split_once(':').ok_or(Error::InvalidPrefix)?;
+        if prefix != LINK_PREFIX {
+            return Err(Error::InvalidPrefix);
+        }
+        let (version, payload) = rest.split_once(':').ok_or(Error::InvalidPrefix)?;
+        if version.parse::<u32>().ok() != Some(LINK_VERSION) {
+            return Err(Error::UnsupportedVersion(version.to_owned()));
+        }
+        let json = URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(Error::InvalidEncoding)?;
+        let link: LinkV1 = serde_json::from_slice(&json).map_err(Error::InvalidSettings)?;
+        RelayLink::try_from(link)
+    }
+
+    /// Check that all custom lists that the relay settings refer to exist in `custom_lists`.
+    /// Custom lists are not included in the link, so they are usually missing on other devices.
+    pub fn validate(&self, custom_lists: &CustomListsSettings) -> Result<(), Error> {
+        let locations = [
+            &self.relay_constraints.location,
+            &self.relay_constraints.wireguard_constraints.entry_location,
+        ];
+        for location in locations {
+            if let Constraint::Only(LocationConstraint::CustomList { list_id }) = location
+                && !custom_lists.iter().any(|list| list.id() == *list_id)
+            {
+                return Err(Error::MissingCustomList);
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the relay and obfuscation settings. The custom Shadowsocks server in `settings`,
+    /// if any, is kept.
+    pub fn apply_to(&self, settings: &mut Settings) {
+        let custom_server = settings
+            .obfuscation_settings
+            .shadowsocks
+            .custom_server
+            .clone();
+        settings.relay_settings = RelaySettings::Normal(self.relay_constraints.clone());
+        settings.obfuscation_settings = self.obfuscation_settings.clone();
+        settings.obfuscation_settings.shadowsocks.custom_server = custom_server;
+    }
+}
+
+/// Version 1 of the link payload. Absent optional fields mean "any".
+#[derive(Debug, Serialize, Deserialize)]
+struct LinkV1 {
+    location: Option<LinkLocation>,
+    #[serde(default)]
+    providers: Vec<String>,
+    ownership: Option<LinkOwnership>,
+    ip_version: Option<LinkIpVersion>,
+    allowed_ips: Option<Vec<IpNetwork>>,
+    use_multihop: bool,
+    entry_location: Option<LinkLocation>,
+    #[serde(default)]
+    entry_providers: Vec<String>,
+    entry_ownership: Option<LinkOwnership>,
+    obfuscation: LinkObfuscation,
+    udp2tcp_port: Option<u16>,
+    shadowsocks_port: Option<u16>,
+    wireguard_port: Option<u16>,
+    auto_sensitivity: LinkSensitivity,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+enum LinkLocation {
+    Country {
+        country: String,
+    },
+    City {
+        country: String,
+        city: String,
+    },
+    Hostname {
+        country: String,
+        city: String,
+        hostname: String,
+    },
+    CustomList {
+        id: String,
+    },
+    HostnamePattern {
+        pattern: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum LinkOwnership {
+    MullvadOwned,
+    Rented,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum LinkIpVersion {
+    V4,
+    V6,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum LinkObfuscation {
+    Auto,
+    Off,
+    WireguardPort,
+    Udp2tcp,
+    Shadowsocks,
+    Quic,
+    Lwo,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum LinkSensitivity {
+    Low,
+    Normal,
+    High,
+}
+
+impl From<&RelayLink> for LinkV1 {
+    fn from(link: &RelayLink) -> Self {
+        let constraints = &link.relay_constraints;
+        let wireguard_constraints = &constraints.wireguard_constraints;
+        let obfuscation = &link.obfuscation_settings;
+        LinkV1 {
+            location: constraints
+                .location
+                .as_ref()
+                .option()
+                .map(LinkLocation::from),
+            providers: providers_to_link(&constraints.providers),
+            ownership: constraints.ownership.option().map(LinkOwnership::from),
+            ip_version: wireguard_constraints
+                .ip_version
+                .option()
+                .map(LinkIpVersion::from),
+            allowed_ips: wireguard_constraints
+                .allowed_ips
+                .as_ref()
+                .option()
+                .map(|allowed_ips| allowed_ips.0.clone()),
+            use_multihop: wireguard_constraints.use_multihop,
+            entry_location: wireguard_constraints
+                .entry_location
+                .as_ref()
+                .option()
+                .map(LinkLocation::from),
+            entry_providers: providers_to_link(&wireguard_constraints.entry_providers),
+            entry_ownership: wireguard_constraints
+                .entry_ownership
+                .option()
+                .map(LinkOwnership::from),
+            obfuscation: LinkObfuscation::from(obfuscation.selected_obfuscation),
+            udp2tcp_port: obfuscation.udp2tcp.port.option(),
+            shadowsocks_port: obfuscation.shadowsocks.port.option(),
+            wireguard_port: obfuscation.wireguard_port.get().option(),
+            auto_sensitivity: LinkSensitivity::from(obfuscation.auto_sensitivity),
+        }
+    }
+}
+
+impl TryFrom<LinkV1> for RelayLink {
+    type Error = Error;
+
+    fn try_from(link: LinkV1) -> Result<Self, Self::Error> {
+        let location = link
+            .location
+            .map(LocationConstraint::try_from)
+            .transpose()?;
+        let entry_location = link
+            .entry_location
+            .map(LocationConstraint::try_from)
+            .transpose()?;
+        let relay_constraints = RelayConstraints {
+            location: Constraint::from(location),
+            providers: providers_from_link(link.providers),
+            ownership: Constraint::from(link.ownership.map(Ownership::from)),
+            wireguard_constraints: WireguardConstraints {
+                ip_version: Constraint::from(link.ip_version.map(IpVersion::from)),
+                allowed_ips: Constraint::from(link.allowed_ips.map(AllowedIps)),
+                use_multihop: link.use_multihop,
+                entry_location: Constraint::from(entry_location),
+                entry_providers: providers_from_link(link.entry_providers),
+                entry_ownership: Constraint::from(link.entry_ownership.map(Ownership::from)),
+            },
+        };
+        let obfuscation_settings = ObfuscationSettings {
+            selected_obfuscation: SelectedObfuscation::from(link.obfuscation),
+            udp2tcp: Udp2TcpObfuscationSettings {
+                port: Constraint::from(link.udp2tcp_port),
+            },
+            shadowsocks: ShadowsocksSettings {
+                port: Constraint::from(link.shadowsocks_port),
+                custom_server: Constraint::Any,
+            },
+            wireguard_port: WireguardPortSettings::from(link.wireguard_port),
+            auto_sensitivity: ObfuscationSensitivity::from(link.auto_sensitivity),
+        };
+        Ok(RelayLink {
+            relay_constraints,
+            obfuscation_settings,
+        })
+    }
+}
+
+/// An empty list of providers means "any".
+fn providers_to_link(providers: &Constraint<Providers>) -> Vec<String> {
+    match providers {
+        Constraint::Any => vec![],
+        Constraint::Only(providers) => {
+            let mut providers = providers.clone().into_vec();
+            providers.sort();
+            providers
+        }
+    }
+}
+
+fn providers_from_link(providers: Vec<String>) -> Constraint<Providers> {
+    Constraint::from(Providers::new(providers).ok())
+}
+
+impl From<&LocationConstraint> for LinkLocation {
+    fn from(location: &LocationConstraint) -> Self {
+        match location.clone() {
+            LocationConstraint::Location(GeographicLocationConstraint::Country(country)) => {
+                LinkLocation::Country { country }
+            }
+            LocationConstraint::Location(GeographicLocationConstraint::City(country, city)) => {
+                LinkLocation::City { country, city }
+            }
+            LocationConstraint::Location(GeographicLocationConstraint::Hostname(
+                country,
+                city,
+                hostname,
+            )) => LinkLocation::Hostname {
+                country,
+                city,
+                hostname,
+            },
+            LocationConstraint::CustomList { list_id } => LinkLocation::CustomList {
+                id: list_id.to_string(),
+            },
+            LocationConstraint::HostnamePattern(pattern) => LinkLocation::HostnamePattern {
+                pattern: String::from(pattern),
+            },
+        }
+    }
+}
+
+impl TryFrom<LinkLocation> for LocationConstraint {
+    type Error = Error;
+
+    fn try_from(location: LinkLocation) -> Result<Self, Self::Error> {
+        let location = match location {
+            LinkLocation::Country { country } => GeographicLocationConstraint::Country(country),
+            LinkLocation::City { country, city } => {
+                GeographicLocationConstraint::City(country, city)
+            }
+            LinkLocation::Hostname {
+                country,
+                city,
+                hostname,
+            } => GeographicLocationConstraint::Hostname(country, city, hostname),
+            LinkLocation::CustomList { id } => {
+                let list_id = id
+                    .parse::<custom_list::Id>()
+                    .map_err(|_| Error::InvalidValue("custom list ID"))?;
+                return Ok(LocationConstraint::CustomList { list_id });
+            }
+            LinkLocation::HostnamePattern { pattern } => {
+                let pattern = HostnamePattern::try_from(pattern)
+                    .map_err(|_| Error::InvalidValue("hostname pattern"))?;
+                return Ok(LocationConstraint::HostnamePattern(pattern));
+            }
+        };
+        Ok(LocationConstraint::Location(location))
+    }
+}
+
+impl From<Ownership> for LinkOwnership {
+    fn from(ownership: Ownership) -> Self {
+        match ownership {
+            Ownership::MullvadOwned => LinkOwnership::MullvadOwned,
+            Ownership::Rented => LinkOwnership::Rented,
+        }
+    }
+}
+
+impl From<LinkOwnership> for Ownership {
+    fn from(ownership: LinkOwnership) -> Self {
+        match ownership {
+            LinkOwnership::MullvadOwned => Ownership::MullvadOwned,
+            LinkOwnership::Rented => Ownership::Rented,
+        }
+    }
+}
+
+impl From<IpVersion> for LinkIpVersion {
+    fn from(ip_version: IpVersion) -> Self {
+        match ip_version {
+            IpVersion::V4 => LinkIpVersion::V4,
+            IpVersion::V6 => LinkIpVersion::V6,
+        }
+    }
+}
+
+impl From<LinkIpVersion> for IpVersion {
+    fn from(ip_version: LinkIpVersion) -> Self {
+        match ip_version {
+            LinkIpVersion::V4 => IpVersion::V4,
+            LinkIpVersion::V6 => IpVersion::V6,
+        }
+    }
+}
+
+impl From<SelectedObfuscation> for LinkObfuscation {
+    fn from(obfuscation: SelectedObfuscation) -> Self {
+        match obfuscation {
+            SelectedObfuscation::Auto => LinkObfuscation::Auto,
+            SelectedObfuscation::Off => LinkObfuscation::Off,
+            SelectedObfuscation::WireguardPort => LinkObfuscation::WireguardPort,
+            SelectedObfuscation::Udp2Tcp => LinkObfuscation::Udp2tcp,
+            SelectedObfuscation::Shadowsocks => LinkObfuscation::Shadowsocks,
+            SelectedObfuscation::Quic => LinkObfuscation::Quic,
+            SelectedObfuscation::Lwo => LinkObfuscation::Lwo,
+        }
+    }
+}
+
+impl From<LinkObfuscation> for SelectedObfuscation {
+    fn from(obfuscation: LinkObfuscation) -> Self {
+        match obfuscation {
+            LinkObfuscation::Auto => SelectedObfuscation::Auto,
+            LinkObfuscation::Off => SelectedObfuscation::Off,
+            LinkObfuscation::WireguardPort => SelectedObfuscation::WireguardPort,
+            LinkObfuscation::Udp2tcp => SelectedObfuscation::Udp2Tcp,
+            LinkObfuscation::Shadowsocks => SelectedObfuscation::Shadowsocks,
+            LinkObfuscation::Quic => SelectedObfuscation::Quic,
+            LinkObfuscation::Lwo => SelectedObfuscation::Lwo,
+        }
+    }
+}
+
+impl From<ObfuscationSensitivity> for LinkSensitivity {
+    fn from(sensitivity: ObfuscationSensitivity) -> Self {
+        match sensitivity {
+            ObfuscationSensitivity::Low => LinkSensitivity::Low,
+            ObfuscationSensitivity::Normal => LinkSensitivity::Normal,
+            ObfuscationSensitivity::High => LinkSensitivity::High,
+        }
+    }
+}
+
+impl From<LinkSensitivity> for ObfuscationSensitivity {
+    fn from(sensitivity: LinkSensitivity) -> Self {
+        match sensitivity {
+            LinkSensitivity::Low => ObfuscationSensitivity::Low,
+            LinkSensitivity::Normal => ObfuscationSensitivity::Normal,
+            LinkSensitivity::High => ObfuscationSensitivity::High,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use talpid_types::net::proxy::Shadowsocks;
+
+    #[test]
+    fn test_encode_decode() {
+        let mut settings = Settings::default();
+        settings.obfuscation_settings.selected_obfuscation = SelectedObfuscation::Shadowsocks;
+        settings.obfuscation_settings.udp2tcp.port = Constraint::Only(80);
+        if let RelaySettings::Normal(constraints) = &mut settings.relay_settings {
+            constraints.location = Constraint::Only(LocationConstraint::from(
+                GeographicLocationConstraint::city("se", "got"),
+            ));
+            constraints.providers = Constraint::Only(Providers::new(["31173"]).unwrap());
+            constraints.wireguard_constraints.use_multihop = true;
+            constraints.wireguard_constraints.entry_location = Constraint::Only(
+                LocationConstraint::HostnamePattern("se-got-wg-*".parse().unwrap()),
+            );
+        }
+        let link = RelayLink::from_settings(&settings).unwrap();
+
+        let encoded = link.encode().unwrap();
+        assert!(encoded.starts_with("mullvad-relay:1:"));
+        assert_eq!(RelayLink::decode(&encoded).unwrap(), link);
+        assert!(link.validate(&settings.custom_lists).is_ok());
+    }
+
+    /// The payload is an explicit format, not the layout of the settings types.
+    #[test]
+    fn test_payload_format() {
+        let link = RelayLink::from_settings(&Settings::default()).unwrap();
+        let encoded = link.encode().unwrap();
+        let payload = encoded.rsplit(':').next().unwrap();
+        let json: serde_json::Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+
+        assert_eq!(json["obfuscation"], "auto");
+        assert_eq!(json["auto_sensitivity"], "normal");
+        assert_eq!(json["use_multihop"], false);
+        assert!(json["location"].is_null());
+    }
+
+    /// Secrets in the settings are never included in a link.
+    #[test]
+    fn test_secrets_excluded() {
+        let custom_server = |password: &str| {
+            Constraint::Only(Shadowsocks::new(
+                ([1, 2, 3, 4], 443),
+                "aes-256-gcm".to_owned(),
+                password.to_owned(),
+            ))
+        };
+        let mut settings = Settings::default();
+        settings.obfuscation_settings.shadowsocks.custom_server = custom_server("hunter2");
+        let encoded = RelayLink::from_settings(&settings)
+            .unwrap()
+            .encode()
+            .unwrap();
+        let payload = encoded.rsplit(':').next().unwrap();
+        let json = String::from_utf8(URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+        assert!(!json.contains("hunter2"));
+
+        // The importing device keeps its own custom server
+        let mut other_settings = Settings::default();
+        other_settings
+            .obfuscation_settings
+            .shadowsocks
+            .custom_server = custom_server("other");
+        RelayLink::decode(&encoded)
+            .unwrap()
+            .apply_to(&mut other_settings);
+        assert_eq!(
+            other_settings
+                .obfuscation_settings
+                .shadowsocks
+                .custom_server,
+            custom_server("other")
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let payload = RelayLink::from_settings(&Settings::default())
+            .unwrap()
+            .encode()
+            .unwrap();
+        let payload = payload.rsplit(':').next().unwrap();
+
+        assert!(matches!(
+            RelayLink::decode(&format!("mullvad:1:{payload}")),
+            Err(Error::InvalidPrefix)
+        ));
+        assert!(matches!(
+            RelayLink::decode(&format!("mullvad-relay:2:{payload}")),
+            Err(Error::UnsupportedVersion(version)) if version == "2"
+        ));
+        assert!(matches!(
+            RelayLink::decode("mullvad-relay:1:not base64"),
+            Err(Error::InvalidEncoding(_))
+        ));
+        assert!(matches!(
+            RelayLink::decode(&format!("mullvad-relay:1:{}", URL_SAFE_NO_PAD.encode("{}"))),
+            Err(Error::InvalidSettings(_))
+        ));
+    }
+}
@@ -1,12 +1,14 @@
 use crate::{
     location::{CityCode, Coordinates, CountryCode, Location},
-    relay_constraints::RelayOverride,
+    relay_constraints::{Ownership, RelayOverride, SelectedObfuscation},
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     ops::RangeInclusive,
+    time::Duration,
 };
 use talpid_types::net::{TransportProtocol, proxy::Shadowsocks, wireguard};
 use vec1::Vec1;
@@ -20,6 +22,170 @@ pub struct RelayList {
     pub wireguard: EndpointData,
 }
 
+/// The countries and cities of a [RelayList], with the number of relays in each city instead of
+/// the relays themselves
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelayLocationsSummary {
+    pub countries: Vec<CountrySummary>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CountrySummary {
+    pub name: String,
+    pub code: CountryCode,
+    pub cities: Vec<CitySummary>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CitySummary {
+    pub name: String,
+    pub code: CityCode,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub relay_count: usize,
+    /// Number of relays that are currently active
+    pub active_relay_count: usize,
+}
+
+/// Schedule of automatic relay list updates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayListUpdateInfo {
+    /// How old the relay list may get before it is updated. This is longer in low-power mode.
+    pub update_interval: Duration,
+    /// When the relay list was last fetched, or `None` if it has not been fetched since the
+    /// daemon started
+    pub last_updated: Option<DateTime<Utc>>,
+    /// When the relay list is next due to be fetched, or `None` if automatic updates are
+    /// paused because the network is metered
+    pub next_update: Option<DateTime<Utc>>,
+}
+
+/// Result of validating the relay list
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelayListReport {
+    pub relay_count: usize,
+    pub bridge_count: usize,
+    /// Problems found with the relay list. Empty if the relay list is valid.
+    pub problems: Vec<String>,
+}
+
+impl RelayListReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// The outcome of testing whether a port on a relay is reachable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayPortReachability {
+    /// A connection was established or a response was received after `latency`.
+    Reachable { latency: Duration },
+    /// The connection was refused, either by the relay or by something on the path to it.
+    Refused,
+    /// Nothing was received in time.
+    NoResponse,
+    /// The probe was not refused, but relays do not respond to UDP probes, so it is unknown
+    /// whether it arrived. This is the result for UDP ports that are not found to be blocked.
+    Unknown,
+}
+
+/// The performance of an obfuscation method when connecting to a relay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObfuscationBenchmarkResult {
+    pub method: SelectedObfuscation,
+    /// Time until the tunnel was connected. `None` if it failed to connect in time.
+    pub handshake_time: Option<Duration>,
+    /// Download speed through the tunnel, in bytes per second. `None` if it could not be
+    /// measured.
+    pub throughput: Option<u64>,
+}
+
+/// Who runs a relay and where it is hosted, as published in the relay list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayTransparencyInfo {
+    pub hostname: String,
+    pub ownership: Ownership,
+    /// The hosting provider of the relay
+    pub provider: String,
+    /// Whether the relay is in service
+    pub active: bool,
+    /// Whether the relay is physically located in the country that it is listed under
+    pub in_listed_country: bool,
+    /// Whether the relay is allowed by the current ownership and provider constraints
+    pub matches_filters: bool,
+}
+
+/// An optional feature which only some relays support
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RelayFeature {
+    Daita,
+    Quic,
+    Lwo,
+}
+
+/// The optional features supported by a relay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayFeatures {
+    pub hostname: String,
+    pub features: Vec<RelayFeature>,
+}
+
+impl From<&WireguardRelay> for RelayFeatures {
+    fn from(relay: &WireguardRelay) -> Self {
+        let endpoint = relay.endpoint();
+        let features = [
+            (endpoint.daita, RelayFeature::Daita),
+            (endpoint.quic.is_some(), RelayFeature::Quic),
+            (endpoint.lwo, RelayFeature::Lwo),
+        ]
+        .into_iter()
+        .filter_map(|(supported, feature)| supported.then_some(feature))
+        .collect();
+        RelayFeatures {
+            hostname: relay.hostname.clone(),
+            features,
+        }
+    }
+}
+
+/// Whether the relays matching some relay settings support the features requested by the current
+/// settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayFeatureSupport {
+    /// Features that are required by the current settings
+    pub requested: Vec<RelayFeature>,
+    /// The relays matching the relay settings, regardless of which features they support
+    pub relays: Vec<RelayFeatures>,
+}
+
+impl RelayFeatureSupport {
+    /// Return the requested features that are supported by every matching relay. Returns nothing
+    /// if no relay matches.
+    pub fn supported_by_all(&self) -> Vec<RelayFeature> {
+        if self.relays.is_empty() {
+            return vec![];
+        }
+        self.requested
+            .iter()
+            .copied()
+            .filter(|feature| {
+                self.relays
+                    .iter()
+                    .all(|relay| relay.features.contains(feature))
+            })
+            .collect()
+    }
+
+    /// Return whether at least one matching relay supports all requested features
+    pub fn is_supported(&self) -> bool {
+        self.relays.iter().any(|relay| {
+            self.requested
+                .iter()
+                .all(|feature| relay.features.contains(feature))
+        })
+    }
+}
+
 /// Stores a list of bridges for each country obtained from the API using
 /// `mullvad_api::RelayListProxy`.
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
@@ -75,6 +241,40 @@ impl RelayList {
         Some(min_dist_country.code.clone())
     }
 
+    /// Return the city with the given codes, if it is in the relay list
+    pub fn lookup_city(
+        &self,
+        country_code: CountryCode,
+        city_code: CityCode,
+    ) -> Option<&RelayListCity> {
+        self.lookup_country(country_code)?.lookup_city(city_code)
+    }
+
+    /// Return the country and city hierarchy of the relay list, without the relays.
+    pub fn summary(&self) -> RelayLocationsSummary {
+        let countries = self
+            .countries
+            .iter()
+            .map(|country| CountrySummary {
+                name: country.name.clone(),
+                code: country.code.clone(),
+                cities: country
+                    .cities
+                    .iter()
+                    .map(|city| CitySummary {
+                        name: city.name.clone(),
+                        code: city.code.clone(),
+                        latitude: city.latitude,
+                        longitude: city.longitude,
+                        relay_count: city.relays.len(),
+                        active_relay_count: city.relays.iter().filter(|relay| relay.active).count(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        RelayLocationsSummary { countries }
+    }
+
     /// Return a flat iterator of all [`Relay`]s
     pub fn relays(&self) -> impl Iterator<Item = &WireguardRelay> + Clone + '_ {
         self.countries
@@ -158,6 +358,16 @@ pub struct RelayListCity {
     pub relays: Vec<WireguardRelay>,
 }
 
+impl RelayListCity {
+    /// Return the coordinates of the city, e.g. for placing it on a map
+    pub fn coordinates(&self) -> Coordinates {
+        Coordinates {
+            latitude: self.latitude,
+            longitude: self.longitude,
+        }
+    }
+}
+
 /// Stores information for a relay returned by the API at `v1/relays` using
 /// `mullvad_api::RelayListProxy`.
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -208,6 +418,30 @@ impl WireguardRelay {
         self.inner.ipv6_addr_in = Some(new_ipv6);
         self.overridden_ipv6 = true;
     }
+
+    /// Use `new_ipv4` for the WireGuard endpoint, but not for obfuscation
+    pub fn override_wireguard_ipv4(&mut self, new_ipv4: Ipv4Addr) {
+        self.endpoint_data.ipv4_addr_in = Some(new_ipv4);
+        self.overridden_ipv4 = true;
+    }
+
+    /// Use `new_ipv6` for the WireGuard endpoint, but not for obfuscation
+    pub fn override_wireguard_ipv6(&mut self, new_ipv6: Ipv6Addr) {
+        self.endpoint_data.ipv6_addr_in = Some(new_ipv6);
+        self.overridden_ipv6 = true;
+    }
+
+    /// IPv4 address of the WireGuard endpoint
+    pub fn wireguard_ipv4_addr_in(&self) -> Ipv4Addr {
+        self.endpoint_data
+            .ipv4_addr_in
+            .unwrap_or(self.inner.ipv4_addr_in)
+    }
+
+    /// IPv6 address of the WireGuard endpoint, if any
+    pub fn wireguard_ipv6_addr_in(&self) -> Option<Ipv6Addr> {
+        self.endpoint_data.ipv6_addr_in.or(self.inner.ipv6_addr_in)
+    }
 }
 
 impl PartialEq for WireguardRelay {
@@ -431,6 +665,14 @@ pub struct WireguardRelayEndpointData {
     /// Optional IP addresses used by Shadowsocks
     #[serde(default)]
     pub shadowsocks_extra_addr_in: HashSet<IpAddr>,
+    /// IPv4 address of the WireGuard endpoint, if it differs from the relay address. This is only
+    /// set by relay overrides.
+    #[serde(default)]
+    pub ipv4_addr_in: Option<Ipv4Addr>,
+    /// IPv6 address of the WireGuard endpoint, if it differs from the relay address. This is only
+    /// set by relay overrides.
+    #[serde(default)]
+    pub ipv6_addr_in: Option<Ipv6Addr>,
 }
 
 impl WireguardRelayEndpointData {
@@ -441,6 +683,8 @@ impl WireguardRelayEndpointData {
             quic: Default::default(),
             lwo: Default::default(),
             shadowsocks_extra_addr_in: Default::default(),
+            ipv4_addr_in: Default::default(),
+            ipv6_addr_in: Default::default(),
         }
     }
 
@@ -635,4 +879,94 @@ mod test {
             Some("jp".to_string())
         );
     }
+
+    #[test]
+    fn test_summary() {
+        let location = Location {
+            country: "Sweden".to_string(),
+            country_code: "se".to_string(),
+            city: "Gothenburg".to_string(),
+            city_code: "got".to_string(),
+            latitude: 57.71,
+            longitude: 11.97,
+        };
+        let relay = |hostname: &str, active| WireguardRelay {
+            inner: Relay {
+                hostname: hostname.to_string(),
+                ipv4_addr_in: "185.213.154.68".parse().unwrap(),
+                ipv6_addr_in: None,
+                active,
+                weight: 1,
+                location: location.clone(),
+            },
+            overridden_ipv4: false,
+            overridden_ipv6: false,
+            include_in_country: true,
+            owned: true,
+            provider: "provider0".to_string(),
+            endpoint_data: WireguardRelayEndpointData::new(
+                PublicKey::from_base64("BLNHNoGO88LjV/wDBa7CUUwUzPq/fO2UwcGLy56hKy4=").unwrap(),
+            ),
+        };
+        let relay_list = RelayList {
+            countries: vec![RelayListCountry {
+                name: "Sweden".to_string(),
+                code: "se".to_string(),
+                cities: vec![RelayListCity {
+                    name: "Gothenburg".to_string(),
+                    code: "got".to_string(),
+                    latitude: 57.70887,
+                    longitude: 11.97456,
+                    relays: vec![relay("se-got-wg-001", true), relay("se-got-wg-002", false)],
+                }],
+            }],
+            ..Default::default()
+        };
+
+        let summary = relay_list.summary();
+        assert_eq!(summary.countries.len(), 1);
+        assert_eq!(summary.countries[0].code, "se");
+        let city = &summary.countries[0].cities[0];
+        assert_eq!(city.code, "got");
+        assert_eq!(city.relay_count, 2);
+        assert_eq!(city.active_relay_count, 1);
+
+        let relays = &relay_list
+            .lookup_city("se".to_string(), "got".to_string())
+            .unwrap()
+            .relays;
+        assert_eq!(relays.len(), 2);
+        assert!(
+            relay_list
+                .lookup_city("se".to_string(), "sto".to_string())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_relay_feature_support() {
+        let relay = |hostname: &str, features: Vec<RelayFeature>| RelayFeatures {
+            hostname: hostname.to_string(),
+            features,
+        };
+        let mut support = RelayFeatureSupport {
+            requested: vec![RelayFeature::Daita, RelayFeature::Quic],
+            relays: vec![
+                relay(
+                    "se1-wireguard",
+                    vec![RelayFeature::Daita, RelayFeature::Quic],
+                ),
+                relay("se2-wireguard", vec![RelayFeature::Daita]),
+            ],
+        };
+        assert!(support.is_supported());
+        assert_eq!(support.supported_by_all(), vec![RelayFeature::Daita]);
+
+        support.relays.remove(0);
+        assert!(!support.is_supported());
+
+        support.relays.clear();
+        assert!(!support.is_supported());
+        assert!(support.supported_by_all().is_empty());
+    }
 }
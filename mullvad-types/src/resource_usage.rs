@@ -0,0 +1,13 @@
+//! Resource usage of the daemon process, for finding leaks. This is only reported by daemons
+//! built with the `diagnostics` feature.
+
+/// Memory, handles and tasks used by the daemon process
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// Resident set size in bytes, or `None` if it could not be read
+    pub resident_memory: Option<u64>,
+    /// Number of open file descriptors, or handles on Windows. `None` if it could not be read
+    pub open_handles: Option<u64>,
+    /// Number of tasks that are alive in the async runtime
+    pub active_tasks: u64,
+}
@@ -0,0 +1,243 @@
+//! Weekly schedule for when the daemon should be connected.
+
+use chrono::{Datelike, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+const MINUTES_PER_WEEK: u32 = 7 * MINUTES_PER_DAY;
+
+/// A point in time within a week, in local time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WeekTime {
+    pub weekday: Weekday,
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl WeekTime {
+    pub fn new(weekday: Weekday, hour: u8, minute: u8) -> Result<Self, ScheduleError> {
+        if hour >= 24 || minute >= 60 {
+            return Err(ScheduleError::InvalidTime { hour, minute });
+        }
+        Ok(WeekTime {
+            weekday,
+            hour,
+            minute,
+        })
+    }
+
+    /// Return the week time of a date and time, ignoring seconds.
+    pub fn from_datetime<T: Datelike + Timelike>(datetime: &T) -> Self {
+        WeekTime {
+            weekday: datetime.weekday(),
+            hour: datetime.hour() as u8,
+            minute: datetime.minute() as u8,
+        }
+    }
+
+    /// Minutes since Monday 00:00
+    const fn minute_of_week(&self) -> u32 {
+        self.weekday.num_days_from_monday() * MINUTES_PER_DAY
+            + self.hour as u32 * 60
+            + self.minute as u32
+    }
+
+    fn from_minute_of_week(minute: u32) -> Self {
+        let minute = minute % MINUTES_PER_WEEK;
+        let day = minute / MINUTES_PER_DAY;
+        let minute_of_day = minute % MINUTES_PER_DAY;
+        WeekTime {
+            weekday: Weekday::try_from(day as u8).expect("day is less than 7"),
+            hour: (minute_of_day / 60) as u8,
+            minute: (minute_of_day % 60) as u8,
+        }
+    }
+}
+
+impl fmt::Display for WeekTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:02}:{:02}", self.weekday, self.hour, self.minute)
+    }
+}
+
+/// A range of time during which the daemon should be connected. `end` is exclusive. If `end` is
+/// before `start`, the range continues into the following week. If they are equal, the range
+/// covers the entire week.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ScheduleRange {
+    pub start: WeekTime,
+    pub end: WeekTime,
+}
+
+impl ScheduleRange {
+    /// Return the range as one or two intervals of minutes since Monday 00:00, split where the
+    /// range wraps around the end of the week.
+    fn intervals(&self) -> impl Iterator<Item = (u32, u32)> {
+        let start = self.start.minute_of_week();
+        let end = self.end.minute_of_week();
+        let (first, second) = if start < end {
+            ((start, end), None)
+        } else {
+            ((start, MINUTES_PER_WEEK), Some((0, end)))
+        };
+        std::iter::once(first).chain(second.filter(|(start, end)| start < end))
+    }
+}
+
+impl fmt::Display for ScheduleRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} - {}", self.start, self.end)
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleError {
+    #[error("Invalid time of day: {hour:02}:{minute:02}")]
+    InvalidTime { hour: u8, minute: u8 },
+}
+
+/// A weekly schedule of when to be connected. The ranges are normalized, so that they are sorted
+/// and do not overlap. An empty schedule means that the daemon never connects or disconnects on
+/// its own.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "Vec<ScheduleRange>", into = "Vec<ScheduleRange>")]
+pub struct ConnectSchedule {
+    ranges: Vec<ScheduleRange>,
+}
+
+impl ConnectSchedule {
+    /// Create a schedule from possibly overlapping ranges. Overlapping and adjacent ranges are
+    /// merged.
+    pub fn new(ranges: impl IntoIterator<Item = ScheduleRange>) -> Self {
+        let mut intervals: Vec<(u32, u32)> = ranges
+            .into_iter()
+            .flat_map(|range| range.intervals().collect::<Vec<_>>())
+            .collect();
+        intervals.sort_unstable();
+
+        let mut merged: Vec<(u32, u32)> = vec![];
+        for (start, end) in intervals {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+
+        let covers_week = matches!(merged.as_slice(), [(0, MINUTES_PER_WEEK)]);
+        let wraps = merged.len() > 1
+            && merged.first().map(|first| first.0) == Some(0)
+            && merged.last().map(|last| last.1) == Some(MINUTES_PER_WEEK);
+
+        let mut ranges: Vec<ScheduleRange> = merged
+            .iter()
+            .map(|&(start, end)| ScheduleRange {
+                start: WeekTime::from_minute_of_week(start),
+                end: WeekTime::from_minute_of_week(end),
+            })
+            .collect();
+        if covers_week {
+            ranges[0].end = ranges[0].start;
+        } else if wraps {
+            // Join the range at the end of the week with the one at the start of the week
+            let first = ranges.remove(0);
+            let last = ranges.last_mut().expect("more than one range");
+            last.end = first.end;
+        }
+
+        ConnectSchedule { ranges }
+    }
+
+    pub fn ranges(&self) -> &[ScheduleRange] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Return whether `time` is within any of the ranges.
+    pub fn is_active(&self, time: WeekTime) -> bool {
+        let minute = time.minute_of_week();
+        self.ranges
+            .iter()
+            .flat_map(|range| range.intervals().collect::<Vec<_>>())
+            .any(|(start, end)| (start..end).contains(&minute))
+    }
+}
+
+impl From<Vec<ScheduleRange>> for ConnectSchedule {
+    fn from(ranges: Vec<ScheduleRange>) -> Self {
+        ConnectSchedule::new(ranges)
+    }
+}
+
+impl From<ConnectSchedule> for Vec<ScheduleRange> {
+    fn from(schedule: ConnectSchedule) -> Self {
+        schedule.ranges
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn time(weekday: Weekday, hour: u8, minute: u8) -> WeekTime {
+        WeekTime::new(weekday, hour, minute).unwrap()
+    }
+
+    fn range(start: WeekTime, end: WeekTime) -> ScheduleRange {
+        ScheduleRange { start, end }
+    }
+
+    #[test]
+    fn test_invalid_time() {
+        assert!(WeekTime::new(Weekday::Mon, 24, 0).is_err());
+        assert!(WeekTime::new(Weekday::Mon, 0, 60).is_err());
+    }
+
+    #[test]
+    fn test_merge_overlapping() {
+        let schedule = ConnectSchedule::new([
+            range(time(Weekday::Mon, 12, 0), time(Weekday::Mon, 17, 0)),
+            range(time(Weekday::Mon, 9, 0), time(Weekday::Mon, 13, 0)),
+            range(time(Weekday::Mon, 17, 0), time(Weekday::Mon, 18, 0)),
+            range(time(Weekday::Tue, 9, 0), time(Weekday::Tue, 17, 0)),
+        ]);
+        assert_eq!(
+            schedule.ranges(),
+            [
+                range(time(Weekday::Mon, 9, 0), time(Weekday::Mon, 18, 0)),
+                range(time(Weekday::Tue, 9, 0), time(Weekday::Tue, 17, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrap_around_week() {
+        let schedule = ConnectSchedule::new([
+            range(time(Weekday::Sun, 22, 0), time(Weekday::Mon, 2, 0)),
+            range(time(Weekday::Mon, 1, 0), time(Weekday::Mon, 3, 0)),
+        ]);
+        assert_eq!(
+            schedule.ranges(),
+            [range(time(Weekday::Sun, 22, 0), time(Weekday::Mon, 3, 0))]
+        );
+
+        assert!(schedule.is_active(time(Weekday::Sun, 23, 59)));
+        assert!(schedule.is_active(time(Weekday::Mon, 0, 0)));
+        assert!(!schedule.is_active(time(Weekday::Mon, 3, 0)));
+        assert!(!schedule.is_active(time(Weekday::Sun, 21, 59)));
+    }
+
+    #[test]
+    fn test_entire_week() {
+        let schedule = ConnectSchedule::new([
+            range(time(Weekday::Wed, 0, 0), time(Weekday::Wed, 0, 0)),
+            range(time(Weekday::Mon, 9, 0), time(Weekday::Mon, 17, 0)),
+        ]);
+        assert_eq!(schedule.ranges().len(), 1);
+        assert!(schedule.is_active(time(Weekday::Mon, 0, 0)));
+        assert!(schedule.is_active(time(Weekday::Sun, 23, 59)));
+    }
+}
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::net::IpAddr;
+use std::{collections::HashSet, net::IpAddr};
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
 #[serde(rename_all = "snake_case")]
@@ -10,12 +10,86 @@ pub enum DnsState {
 }
 
 /// DNS config
-#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
 #[serde(default)]
 pub struct DnsOptions {
     pub state: DnsState,
     pub default_options: DefaultDnsOptions,
     pub custom_options: CustomDnsOptions,
+    /// Domains to resolve using other servers. All other domains are resolved as determined by
+    /// `state`.
+    pub split_dns: Vec<SplitDnsRule>,
+    /// Where to get DNS servers from when `state` is [`DnsState::Custom`], in order of priority.
+    /// Servers from later sources are only used if the earlier ones cannot be reached.
+    pub resolver_order: Vec<DnsResolverSource>,
+}
+
+impl Default for DnsOptions {
+    fn default() -> Self {
+        Self {
+            state: DnsState::default(),
+            default_options: DefaultDnsOptions::default(),
+            custom_options: CustomDnsOptions::default(),
+            split_dns: vec![],
+            resolver_order: vec![DnsResolverSource::Custom],
+        }
+    }
+}
+
+/// A source of DNS servers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum DnsResolverSource {
+    /// The DNS servers in [`CustomDnsOptions`].
+    Custom,
+    /// Mullvad DNS. This is the content blocking server selected by [`DefaultDnsOptions`], or the
+    /// DNS server on the relay if no content blockers are enabled.
+    Mullvad,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ResolverOrderError {
+    #[error("At least one DNS resolver source is required")]
+    Empty,
+    #[error("DNS resolver source {0:?} is listed more than once")]
+    Duplicate(DnsResolverSource),
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum DnsOptionsError {
+    #[error("Split DNS is only supported on macOS")]
+    SplitDnsUnsupported,
+    #[error(transparent)]
+    SplitDns(#[from] SplitDnsError),
+    #[error(transparent)]
+    ResolverOrder(#[from] ResolverOrderError),
+}
+
+impl DnsOptions {
+    /// Check that the options are valid and supported on this platform.
+    pub fn validate(&self) -> Result<(), DnsOptionsError> {
+        if !cfg!(target_os = "macos") && !self.split_dns.is_empty() {
+            return Err(DnsOptionsError::SplitDnsUnsupported);
+        }
+        SplitDnsRule::validate(&self.split_dns)?;
+        DnsResolverSource::validate_order(&self.resolver_order)?;
+        Ok(())
+    }
+}
+
+impl DnsResolverSource {
+    /// Check that `order` contains at least one source, and no source more than once.
+    pub fn validate_order(order: &[DnsResolverSource]) -> Result<(), ResolverOrderError> {
+        if order.is_empty() {
+            return Err(ResolverOrderError::Empty);
+        }
+        let mut sources = HashSet::new();
+        match order.iter().find(|source| !sources.insert(**source)) {
+            Some(duplicate) => Err(ResolverOrderError::Duplicate(*duplicate)),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Default DNS config
@@ -110,3 +184,177 @@ impl Default for DefaultDnsOptions {
 pub struct CustomDnsOptions {
     pub addresses: Vec<IpAddr>,
 }
+
+/// Longest allowed domain name, excluding the trailing dot
+const MAX_DOMAIN_LEN: usize = 253;
+/// Longest allowed label in a domain name
+const MAX_LABEL_LEN: usize = 63;
+
+/// DNS servers to use for a domain and all of its subdomains.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct SplitDnsRule {
+    /// Domain in lowercase, without a trailing dot
+    pub domain: String,
+    pub servers: Vec<IpAddr>,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum SplitDnsError {
+    #[error("Invalid domain: \"{0}\"")]
+    InvalidDomain(String),
+    #[error("Split DNS may not match all domains, since the remaining DNS servers would be unused")]
+    MatchesAllDomains,
+    #[error("No DNS servers for domain \"{0}\"")]
+    NoServers(String),
+    #[error("Domain \"{0}\" has more than one split DNS rule")]
+    DuplicateDomain(String),
+}
+
+impl SplitDnsRule {
+    /// Create a rule for `domain`, which is normalized to lowercase without a trailing dot.
+    pub fn new(domain: &str, servers: Vec<IpAddr>) -> Result<Self, SplitDnsError> {
+        let normalized = domain
+            .strip_suffix('.')
+            .unwrap_or(domain)
+            .to_ascii_lowercase();
+        if normalized.is_empty() {
+            return Err(SplitDnsError::MatchesAllDomains);
+        }
+        if !is_valid_domain(&normalized) {
+            return Err(SplitDnsError::InvalidDomain(domain.to_owned()));
+        }
+        if servers.is_empty() {
+            return Err(SplitDnsError::NoServers(normalized));
+        }
+        Ok(Self {
+            domain: normalized,
+            servers,
+        })
+    }
+
+    /// Check that every rule is valid, and that no domain has more than one rule.
+    pub fn validate(rules: &[SplitDnsRule]) -> Result<(), SplitDnsError> {
+        let mut domains = HashSet::new();
+        for rule in rules {
+            let valid = SplitDnsRule::new(&rule.domain, rule.servers.clone())?;
+            if valid.domain != rule.domain {
+                return Err(SplitDnsError::InvalidDomain(rule.domain.clone()));
+            }
+            if !domains.insert(valid.domain) {
+                return Err(SplitDnsError::DuplicateDomain(rule.domain.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Return whether `domain` consists of valid hostname labels. `domain` must not have a trailing
+/// dot.
+fn is_valid_domain(domain: &str) -> bool {
+    domain.len() <= MAX_DOMAIN_LEN
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= MAX_LABEL_LEN
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+/// Describes where a DNS server in use comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsServerSource {
+    /// The DNS server on the relay, reached via the tunnel gateway.
+    Relay,
+    /// A Mullvad DNS server which blocks content, as selected by [`DefaultDnsOptions`].
+    ContentBlocker,
+    /// A DNS server set in [`CustomDnsOptions`].
+    Custom,
+    /// A DNS server for specific domains, set in [`DnsOptions::split_dns`].
+    SplitDns,
+}
+
+/// A DNS server which is currently in use by the tunnel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct EffectiveDnsServer {
+    pub address: IpAddr,
+    pub source: DnsServerSource,
+}
+
+/// The outcome of sending a test query to a DNS server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsServerReachability {
+    /// The server resolved the query.
+    Answered,
+    /// The server responded, but refused to resolve the query recursively.
+    RecursionRefused,
+    /// The server responded with an error other than refusing the query.
+    Failed,
+    /// No response was received in time.
+    NoResponse,
+    /// DNS to the server is blocked by the firewall in the current tunnel state, so no query was
+    /// sent.
+    Blocked,
+}
+
+/// The result of testing a single DNS server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct DnsServerTestResult {
+    pub address: IpAddr,
+    pub reachability: DnsServerReachability,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_dns_rule() {
+        let servers = vec!["10.0.0.1".parse().unwrap()];
+
+        let rule = SplitDnsRule::new("Corp.Example.com.", servers.clone()).unwrap();
+        assert_eq!(rule.domain, "corp.example.com");
+        assert!(SplitDnsRule::validate(std::slice::from_ref(&rule)).is_ok());
+
+        assert_eq!(
+            SplitDnsRule::new(".", servers.clone()),
+            Err(SplitDnsError::MatchesAllDomains)
+        );
+        assert!(matches!(
+            SplitDnsRule::new("-corp.example.com", servers.clone()),
+            Err(SplitDnsError::InvalidDomain(_))
+        ));
+        assert!(matches!(
+            SplitDnsRule::new("corp..example.com", servers.clone()),
+            Err(SplitDnsError::InvalidDomain(_))
+        ));
+        assert!(matches!(
+            SplitDnsRule::new("corp.example.com", vec![]),
+            Err(SplitDnsError::NoServers(_))
+        ));
+
+        assert!(matches!(
+            SplitDnsRule::validate(&[rule.clone(), rule]),
+            Err(SplitDnsError::DuplicateDomain(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolver_order() {
+        use DnsResolverSource::*;
+
+        assert!(DnsResolverSource::validate_order(&[Mullvad, Custom]).is_ok());
+        assert_eq!(
+            DnsResolverSource::validate_order(&[]),
+            Err(ResolverOrderError::Empty)
+        );
+        assert_eq!(
+            DnsResolverSource::validate_order(&[Custom, Mullvad, Custom]),
+            Err(ResolverOrderError::Duplicate(Custom))
+        );
+    }
+}
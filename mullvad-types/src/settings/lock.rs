@@ -0,0 +1,340 @@
+use super::Settings;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{DateTime, Utc};
+use pbkdf2::pbkdf2_hmac;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{collections::BTreeSet, fmt};
+
+/// Number of PBKDF2 iterations used when hashing a new password
+const PASSWORD_HASH_ROUNDS: u32 = 600_000;
+/// Length of the salt of a password hash, in bytes
+pub const PASSWORD_SALT_LEN: usize = 16;
+/// Length of a password hash, in bytes
+const PASSWORD_HASH_LEN: usize = 32;
+
+/// Groups of settings that can be locked against changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockableSetting {
+    AllowLan,
+    LockdownMode,
+    /// Auto-connect, the connect schedule, trusted networks, and pausing the tunnel
+    AutoConnect,
+    /// Relay settings, relay overrides, and relay randomization
+    RelaySettings,
+    ObfuscationSettings,
+    DnsOptions,
+    /// WireGuard, IPv6, quantum resistance, and DAITA options, and connection retry limits
+    TunnelOptions,
+    CustomLists,
+    /// API access methods, API request limits, and relay list download settings
+    ApiAccessMethods,
+    SplitTunnel,
+    /// Account expiry behavior, beta releases, low power mode, captive portal checks,
+    /// connection profiles, and the log filter
+    General,
+}
+
+impl LockableSetting {
+    /// Return whether any setting in this group differs between `old` and `new`
+    fn changed(self, old: &Settings, new: &Settings) -> bool {
+        match self {
+            LockableSetting::AllowLan => old.allow_lan != new.allow_lan,
+            #[cfg(not(target_os = "android"))]
+            LockableSetting::LockdownMode => old.lockdown_mode != new.lockdown_mode,
+            #[cfg(target_os = "android")]
+            LockableSetting::LockdownMode => false,
+            LockableSetting::AutoConnect => {
+                old.auto_connect != new.auto_connect
+                    || old.connect_schedule != new.connect_schedule
+                    || old.trusted_networks != new.trusted_networks
+            }
+            LockableSetting::RelaySettings => {
+                old.relay_settings != new.relay_settings
+                    || old.relay_overrides != new.relay_overrides
+                    || old.randomize_relay_each_connect != new.randomize_relay_each_connect
+            }
+            LockableSetting::ObfuscationSettings => {
+                old.obfuscation_settings != new.obfuscation_settings
+            }
+            LockableSetting::DnsOptions => {
+                old.tunnel_options.dns_options != new.tunnel_options.dns_options
+            }
+            LockableSetting::TunnelOptions => {
+                old.tunnel_options.wireguard != new.tunnel_options.wireguard
+                    || old.tunnel_options.generic != new.tunnel_options.generic
+                    || old.connect_timeout != new.connect_timeout
+                    || old.max_reconnect_attempts != new.max_reconnect_attempts
+            }
+            LockableSetting::CustomLists => old.custom_lists != new.custom_lists,
+            LockableSetting::ApiAccessMethods => {
+                old.api_access_methods != new.api_access_methods
+                    || old.max_concurrent_api_requests != new.max_concurrent_api_requests
+                    || old.api_sni_hostname != new.api_sni_hostname
+                    || old.relay_list_download != new.relay_list_download
+            }
+            #[cfg(any(windows, target_os = "android", target_os = "macos"))]
+            LockableSetting::SplitTunnel => old.split_tunnel != new.split_tunnel,
+            // Excluded processes are not stored in the settings on Linux
+            #[cfg(not(any(windows, target_os = "android", target_os = "macos")))]
+            LockableSetting::SplitTunnel => false,
+            LockableSetting::General => {
+                old.on_account_expiry != new.on_account_expiry
+                    || old.show_beta_releases != new.show_beta_releases
+                    || old.low_power_mode != new.low_power_mode
+                    || old.captive_portal_check != new.captive_portal_check
+                    || old.profiles != new.profiles
+            }
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum SettingsLockError {
+    #[error("No settings to lock were given")]
+    NoSettings,
+    #[error("The password must not be empty")]
+    EmptyPassword,
+    #[error("Settings are already locked")]
+    AlreadyLocked,
+    #[error("Incorrect password")]
+    IncorrectPassword,
+    #[error("Too many incorrect passwords. The next attempt is allowed at {0}")]
+    UnlockBackoff(DateTime<Utc>),
+    #[error("The setting {0:?} is locked")]
+    Locked(LockableSetting),
+}
+
+/// Settings that may not be changed until they are unlocked with a password.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SettingsLock {
+    locked: BTreeSet<LockableSetting>,
+    password_hash: Option<PasswordHash>,
+}
+
+impl SettingsLock {
+    /// Lock `settings` until the password that matches `password_hash` is given. Settings that are
+    /// already locked must be unlocked first.
+    pub fn lock(
+        &mut self,
+        settings: impl IntoIterator<Item = LockableSetting>,
+        password_hash: PasswordHash,
+    ) -> Result<(), SettingsLockError> {
+        if !self.is_empty() {
+            return Err(SettingsLockError::AlreadyLocked);
+        }
+        let locked: BTreeSet<_> = settings.into_iter().collect();
+        if locked.is_empty() {
+            return Err(SettingsLockError::NoSettings);
+        }
+        self.locked = locked;
+        self.password_hash = Some(password_hash);
+        Ok(())
+    }
+
+    /// Return the hash of the password that unlocks the settings, if any are locked
+    pub fn password_hash(&self) -> Option<&PasswordHash> {
+        self.password_hash.as_ref()
+    }
+
+    pub fn is_locked(&self, setting: LockableSetting) -> bool {
+        self.locked.contains(&setting)
+    }
+
+    /// Return whether no settings are locked
+    pub fn is_empty(&self) -> bool {
+        self.locked.is_empty()
+    }
+
+    pub fn locked(&self) -> impl Iterator<Item = LockableSetting> + '_ {
+        self.locked.iter().copied()
+    }
+
+    /// Fail if `setting` is locked
+    pub fn check_unlocked(&self, setting: LockableSetting) -> Result<(), SettingsLockError> {
+        if self.is_locked(setting) {
+            return Err(SettingsLockError::Locked(setting));
+        }
+        Ok(())
+    }
+
+    /// Fail if any setting is locked
+    pub fn check_no_settings_locked(&self) -> Result<(), SettingsLockError> {
+        match self.locked().next() {
+            Some(setting) => Err(SettingsLockError::Locked(setting)),
+            None => Ok(()),
+        }
+    }
+
+    /// Fail if going from `old` to `new` changes any locked setting
+    pub fn check_update(&self, old: &Settings, new: &Settings) -> Result<(), SettingsLockError> {
+        match self.locked().find(|setting| setting.changed(old, new)) {
+            Some(setting) => Err(SettingsLockError::Locked(setting)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Salted PBKDF2-HMAC-SHA256 hash of a password
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordHash {
+    /// Base64-encoded salt
+    salt: String,
+    rounds: u32,
+    /// Base64-encoded hash
+    hash: String,
+}
+
+impl PasswordHash {
+    /// Hash `password` with `salt`, which should be randomly generated. This is slow by design,
+    /// so it should not be called on an async runtime thread.
+    pub fn new(password: &str, salt: [u8; PASSWORD_SALT_LEN]) -> Result<Self, SettingsLockError> {
+        Self::with_rounds(password, &salt, PASSWORD_HASH_ROUNDS)
+    }
+
+    fn with_rounds(password: &str, salt: &[u8], rounds: u32) -> Result<Self, SettingsLockError> {
+        if password.is_empty() {
+            return Err(SettingsLockError::EmptyPassword);
+        }
+        Ok(Self {
+            salt: STANDARD.encode(salt),
+            rounds,
+            hash: STANDARD.encode(derive_key(password, salt, rounds)),
+        })
+    }
+
+    /// Return whether `password` matches the hash. This is as slow as [PasswordHash::new].
+    pub fn verify(&self, password: &str) -> bool {
+        let (Ok(salt), Ok(expected)) = (STANDARD.decode(&self.salt), STANDARD.decode(&self.hash))
+        else {
+            return false;
+        };
+        if self.rounds == 0 || expected.len() != PASSWORD_HASH_LEN {
+            return false;
+        }
+        let actual = derive_key(password, &salt, self.rounds);
+        // Compare every byte so that the time taken does not reveal how much of the hash matched
+        expected
+            .iter()
+            .zip(actual)
+            .fold(0, |diff, (expected, actual)| diff | (expected ^ actual))
+            == 0
+    }
+}
+
+impl fmt::Debug for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PasswordHash")
+            .field("rounds", &self.rounds)
+            .finish_non_exhaustive()
+    }
+}
+
+fn derive_key(password: &str, salt: &[u8], rounds: u32) -> [u8; PASSWORD_HASH_LEN] {
+    let mut key = [0; PASSWORD_HASH_LEN];
+    pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, rounds, &mut key);
+    key
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hash(password: &str) -> PasswordHash {
+        PasswordHash::with_rounds(password, b"salt", 1).unwrap()
+    }
+
+    #[test]
+    fn test_lock() {
+        let mut lock = SettingsLock::default();
+        assert_eq!(
+            lock.lock([], hash("secret")),
+            Err(SettingsLockError::NoSettings)
+        );
+
+        lock.lock([LockableSetting::AllowLan], hash("secret"))
+            .unwrap();
+        assert!(lock.is_locked(LockableSetting::AllowLan));
+        assert!(!lock.is_locked(LockableSetting::DnsOptions));
+        assert_eq!(
+            lock.lock([LockableSetting::DnsOptions], hash("secret")),
+            Err(SettingsLockError::AlreadyLocked)
+        );
+        assert_eq!(
+            lock.check_unlocked(LockableSetting::AllowLan),
+            Err(SettingsLockError::Locked(LockableSetting::AllowLan))
+        );
+        assert_eq!(lock.check_unlocked(LockableSetting::DnsOptions), Ok(()));
+        assert_eq!(
+            lock.check_no_settings_locked(),
+            Err(SettingsLockError::Locked(LockableSetting::AllowLan))
+        );
+    }
+
+    /// Only changes to locked settings are rejected
+    #[test]
+    fn test_check_update() {
+        let mut lock = SettingsLock::default();
+        lock.lock(
+            [LockableSetting::AllowLan, LockableSetting::General],
+            hash("secret"),
+        )
+        .unwrap();
+        let old = Settings::default();
+
+        let mut new = old.clone();
+        new.auto_connect = !new.auto_connect;
+        assert_eq!(lock.check_update(&old, &new), Ok(()));
+
+        new.allow_lan = !new.allow_lan;
+        assert_eq!(
+            lock.check_update(&old, &new),
+            Err(SettingsLockError::Locked(LockableSetting::AllowLan))
+        );
+
+        let mut new = old.clone();
+        new.low_power_mode = !new.low_power_mode;
+        assert_eq!(
+            lock.check_update(&old, &new),
+            Err(SettingsLockError::Locked(LockableSetting::General))
+        );
+    }
+
+    #[test]
+    fn test_password_hash() {
+        assert_eq!(
+            PasswordHash::new("", [0; PASSWORD_SALT_LEN]),
+            Err(SettingsLockError::EmptyPassword)
+        );
+
+        let password_hash = hash("secret");
+        assert!(password_hash.verify("secret"));
+        assert!(!password_hash.verify("wrong"));
+        assert!(!password_hash.verify(""));
+
+        // The same password with a different salt gives a different hash
+        let other_hash = PasswordHash::with_rounds("secret", b"pepper", 1).unwrap();
+        assert_ne!(password_hash, other_hash);
+        assert!(other_hash.verify("secret"));
+    }
+
+    /// Test vector from RFC 7914, section 11
+    #[test]
+    fn test_derive_key() {
+        let expected = [
+            0x55, 0xac, 0x04, 0x6e, 0x56, 0xe3, 0x08, 0x9f, 0xec, 0x16, 0x91, 0xc2, 0x25, 0x44,
+            0xb6, 0x05, 0xf9, 0x41, 0x85, 0x21, 0x6d, 0xde, 0x04, 0x65, 0xe6, 0x8b, 0x9d, 0x57,
+            0xc2, 0x0d, 0xac, 0xbc,
+        ];
+        assert_eq!(derive_key("passwd", b"salt", 1), expected);
+    }
+
+    /// The hash must not be included in debug output, which may be logged
+    #[test]
+    fn test_password_hash_debug() {
+        let password_hash = hash("secret");
+        assert!(!format!("{password_hash:?}").contains(&password_hash.hash));
+    }
+}
@@ -2,19 +2,26 @@ use crate::{
     access_method,
     constraints::Constraint,
     custom_list::CustomListsSettings,
+    device::DeviceName,
+    profile::ProfilesSettings,
     relay_constraints::{
         GeographicLocationConstraint, LocationConstraint, ObfuscationSettings, RelayConstraints,
         RelayOverride, RelaySettings, RelaySettingsFormatter, SelectedObfuscation,
         WireguardConstraints,
     },
+    schedule::ConnectSchedule,
+    trusted_network::TrustedNetworksSettings,
     wireguard,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 #[cfg(any(windows, target_os = "android", target_os = "macos"))]
 use std::collections::HashSet;
+use std::{path::PathBuf, time::Duration};
 use talpid_types::net::GenericTunnelOptions;
 
 mod dns;
+mod lock;
 
 /// The version used by the current version of the code. Should always be the
 /// latest version that exists in `SettingsVersion`.
@@ -97,13 +104,47 @@ pub struct Settings {
     pub lockdown_mode: bool,
     /// If the daemon should connect the VPN tunnel directly on start or not.
     pub auto_connect: bool,
+    /// What the daemon should do when the account runs out of time while connected.
+    pub on_account_expiry: AccountExpiryAction,
     /// Options that should be applied to tunnels of a specific type regardless of where the relays
     /// might be located.
     pub tunnel_options: TunnelOptions,
     /// Overrides for relays
     pub relay_overrides: Vec<RelayOverride>,
+    /// Whether to pick a uniformly random relay on every connection attempt, avoiding the previous
+    /// exit relay. This only affects tunnel relays. API access methods are selected independently.
+    pub randomize_relay_each_connect: bool,
     /// Whether to notify users of beta updates.
     pub show_beta_releases: bool,
+    /// Whether to pause non-essential background API requests, such as version checks, and check
+    /// for relay list updates less often.
+    pub low_power_mode: bool,
+    /// Whether to check for a captive portal before connecting, and defer connecting until the
+    /// portal has been passed.
+    pub captive_portal_check: bool,
+    /// How long to keep retrying to connect before giving up and blocking traffic. Zero means
+    /// that connecting is retried indefinitely.
+    pub connect_timeout: Duration,
+    /// How many times to automatically retry connecting before giving up and blocking traffic.
+    /// `None` means that connecting is retried indefinitely.
+    pub max_reconnect_attempts: Option<u32>,
+    /// Maximum number of API requests that may be in flight at once. Excess requests wait until
+    /// an earlier request completes. Zero means unbounded.
+    pub max_concurrent_api_requests: u32,
+    /// Server name to send when connecting to the API, instead of the API hostname. The server
+    /// certificate is still verified against the API hostname.
+    pub api_sni_hostname: Option<String>,
+    /// Bandwidth limit for relay list and API address downloads, and whether to skip automatic
+    /// relay list updates.
+    pub relay_list_download: RelayListDownloadSettings,
+    /// Weekly schedule of when the daemon should connect and disconnect on its own.
+    pub connect_schedule: ConnectSchedule,
+    /// Networks on which the daemon does not connect on its own
+    pub trusted_networks: TrustedNetworksSettings,
+    /// Saved connection profiles
+    pub profiles: ProfilesSettings,
+    /// Settings that may not be changed until they are unlocked
+    pub settings_lock: SettingsLock,
     /// Split tunneling settings
     #[cfg(any(windows, target_os = "android", target_os = "macos"))]
     pub split_tunnel: SplitTunnelSettings,
@@ -120,6 +161,52 @@ pub struct Settings {
     pub rollout_threshold_seed: Option<u32>,
 }
 
+/// Result of checking whether the settings file on disk can be loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingsIntegrity {
+    /// The settings file is valid
+    Ok,
+    /// There is no settings file, so the default settings are used
+    Missing,
+    /// The settings file could not be read
+    Unreadable(String),
+    /// The settings file could not be deserialized
+    Corrupt(String),
+    /// The settings file was deserialized, but is inconsistent. Each entry describes a problem
+    Invalid(Vec<String>),
+}
+
+/// A settings migration step that was applied when the settings were loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SettingsMigration {
+    pub from_version: u32,
+    pub to_version: u32,
+}
+
+/// What a factory reset would remove, if performed now
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct FactoryResetPreview {
+    /// Whether any settings differ from the defaults
+    pub settings: bool,
+    /// Whether an account number is stored in the account history
+    pub account_history: bool,
+    /// Name of the device that would be logged out and removed from the account, if logged in
+    pub device: Option<DeviceName>,
+    /// Files and directories that would be removed from the cache directory
+    pub cache_files: Vec<PathBuf>,
+    /// Files and directories that would be removed from the log directory
+    pub log_files: Vec<PathBuf>,
+    /// Files other than the settings that would be removed from the settings directory
+    pub settings_files: Vec<PathBuf>,
+}
+
+/// A named copy of the settings stored by the daemon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsSnapshot {
+    pub label: String,
+    pub created: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Recent {
     Singlehop(LocationConstraint),
@@ -181,6 +268,32 @@ impl TryFrom<&RelaySettings> for Recent {
     }
 }
 
+/// Action taken by the daemon when it detects that the account has expired while the target
+/// state is secured.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum AccountExpiryAction {
+    /// Disconnect the tunnel and unblock traffic.
+    Disconnect,
+    /// Block all traffic until the account has time added to it.
+    #[default]
+    Block,
+    /// Keep the current tunnel up until it can no longer be used, then block traffic.
+    StayConnectedUntilDrop,
+}
+
+/// Settings for the relay list and API address downloads that run in the background
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RelayListDownloadSettings {
+    /// Maximum download speed in bytes per second. Zero means unlimited.
+    pub bandwidth_limit: u32,
+    /// Whether the current network is metered. If so, the relay list is not updated
+    /// automatically, but it can still be updated on request.
+    pub metered_network: bool,
+}
+
 #[cfg(any(windows, target_os = "android", target_os = "macos"))]
 #[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
 pub struct SplitTunnelSettings {
@@ -188,6 +301,33 @@ pub struct SplitTunnelSettings {
     pub enable_exclusions: bool,
     /// Set of applications to exclude from the tunnel.
     pub apps: HashSet<SplitApp>,
+    /// Destinations that excluded applications may reach outside the tunnel. Traffic to other
+    /// destinations goes through the tunnel. Applications without an entry are excluded for all
+    /// destinations. This is only supported on macOS.
+    #[serde(default)]
+    pub allowed_subnets: std::collections::HashMap<SplitApp, Vec<ipnetwork::IpNetwork>>,
+}
+
+/// Error returned when a subnet cannot be used to restrict a split tunnel app
+#[cfg(any(windows, target_os = "android", target_os = "macos"))]
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("{0} is not a network address, since it has host bits set")]
+pub struct InvalidSubnetError(pub ipnetwork::IpNetwork);
+
+#[cfg(any(windows, target_os = "android", target_os = "macos"))]
+impl SplitTunnelSettings {
+    /// Check that `subnets` may be used as the allowed subnets of an application.
+    pub fn validate_allowed_subnets(
+        subnets: &[ipnetwork::IpNetwork],
+    ) -> Result<(), InvalidSubnetError> {
+        match subnets
+            .iter()
+            .find(|subnet| subnet.network() != subnet.ip())
+        {
+            Some(subnet) => Err(InvalidSubnetError(*subnet)),
+            None => Ok(()),
+        }
+    }
 }
 
 /// An application whose traffic should be excluded from any active tunnel.
@@ -283,9 +423,22 @@ impl Default for Settings {
             #[cfg(not(target_os = "android"))]
             lockdown_mode: false,
             auto_connect: false,
+            on_account_expiry: AccountExpiryAction::default(),
             tunnel_options: TunnelOptions::default(),
             relay_overrides: vec![],
+            randomize_relay_each_connect: false,
             show_beta_releases: false,
+            low_power_mode: false,
+            captive_portal_check: false,
+            connect_timeout: Duration::ZERO,
+            max_reconnect_attempts: None,
+            max_concurrent_api_requests: 0,
+            api_sni_hostname: None,
+            relay_list_download: RelayListDownloadSettings::default(),
+            connect_schedule: ConnectSchedule::default(),
+            trusted_networks: TrustedNetworksSettings::default(),
+            profiles: ProfilesSettings::default(),
+            settings_lock: SettingsLock::default(),
             #[cfg(any(windows, target_os = "android", target_os = "macos"))]
             split_tunnel: SplitTunnelSettings::default(),
             settings_version: CURRENT_SETTINGS_VERSION,
@@ -372,7 +525,12 @@ pub struct TunnelOptions {
     pub dns_options: DnsOptions,
 }
 
-pub use dns::{CustomDnsOptions, DefaultDnsOptions, DnsOptions, DnsState};
+pub use dns::{
+    CustomDnsOptions, DefaultDnsOptions, DnsOptions, DnsOptionsError, DnsResolverSource,
+    DnsServerReachability, DnsServerSource, DnsServerTestResult, DnsState, EffectiveDnsServer,
+    ResolverOrderError, SplitDnsError, SplitDnsRule,
+};
+pub use lock::{LockableSetting, PASSWORD_SALT_LEN, PasswordHash, SettingsLock, SettingsLockError};
 
 impl Default for TunnelOptions {
     fn default() -> Self {
@@ -1,4 +1,5 @@
 use crate::{features::FeatureIndicators, location::GeoIpLocation};
+use chrono::{DateTime, Utc};
 use either::Either;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -64,6 +65,64 @@ impl fmt::Display for TargetState {
     }
 }
 
+/// Why the daemon was last restarted, as recorded by the previous daemon instance.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartReason {
+    /// The previous instance shut down normally.
+    CleanShutdown,
+    /// The previous instance stopped without shutting down.
+    Crash,
+    /// The previous instance was running a different version of the app.
+    Upgrade,
+    /// The previous instance was prepared for a restart using `prepare_restart`.
+    PrepareRestart,
+}
+
+impl fmt::Display for RestartReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RestartReason::CleanShutdown => "Clean shutdown".fmt(f),
+            RestartReason::Crash => "Crash".fmt(f),
+            RestartReason::Upgrade => "Upgrade".fmt(f),
+            RestartReason::PrepareRestart => "Prepared restart".fmt(f),
+        }
+    }
+}
+
+/// Why the daemon disconnected on its own, rather than because a client asked it to, or why it
+/// will reconnect on its own.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisconnectReason {
+    /// The account ran out of time, and the daemon is set to disconnect when that happens.
+    AccountExpired,
+    /// The device was logged out.
+    LoggedOut,
+    /// A range of the connect schedule ended.
+    Schedule,
+    /// The device joined a trusted network.
+    TrustedNetwork,
+    /// A client paused the tunnel. The daemon reconnects at `until`, unless the pause is ended
+    /// earlier.
+    Paused { until: DateTime<Utc> },
+}
+
+impl fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisconnectReason::AccountExpired => "Account expired".fmt(f),
+            DisconnectReason::LoggedOut => "Logged out".fmt(f),
+            DisconnectReason::Schedule => "Connect schedule".fmt(f),
+            DisconnectReason::TrustedNetwork => "Trusted network".fmt(f),
+            // The daemon may not run in the same time zone as the user, so use UTC
+            DisconnectReason::Paused { until } => {
+                write!(f, "Paused until {}", until.format("%Y-%m-%d %H:%M:%S UTC"))
+            }
+        }
+    }
+}
+
 /// Represents the state the client tunnel is in.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -74,6 +133,8 @@ pub enum TunnelState {
         /// Whether internet access is blocked due to lockdown mode
         #[cfg(not(target_os = "android"))]
         locked_down: bool,
+        /// Set if the daemon disconnected on its own
+        disconnect_reason: Option<DisconnectReason>,
     },
     Connecting {
         endpoint: TunnelEndpoint,
@@ -105,6 +166,21 @@ impl TunnelState {
         matches!(self, TunnelState::Disconnected { .. })
     }
 
+    /// Returns true if the firewall is currently blocking traffic that does not go through the
+    /// tunnel, i.e. if the kill switch is active.
+    pub fn is_blocking(&self) -> bool {
+        match self {
+            #[cfg(not(target_os = "android"))]
+            TunnelState::Disconnected { locked_down, .. } => *locked_down,
+            #[cfg(target_os = "android")]
+            TunnelState::Disconnected { .. } => false,
+            TunnelState::Connecting { .. }
+            | TunnelState::Connected { .. }
+            | TunnelState::Disconnecting(_) => true,
+            TunnelState::Error(state) => state.is_blocking(),
+        }
+    }
+
     /// Returns the tunnel endpoint for an active connection.
     /// This value exists in the connecting and connected states.
     pub const fn endpoint(&self) -> Option<&TunnelEndpoint> {
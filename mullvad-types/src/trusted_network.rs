@@ -0,0 +1,187 @@
+//! Networks on which the daemon does not connect on its own.
+//!
+//! Wi-Fi networks are identified by a hash of their SSID, so that the names of networks that the
+//! user has visited are not stored in the settings or sent to clients in plain text. The SSID is
+//! salted with a secret that is unique to the installation, so that the hashes cannot be reversed
+//! by hashing common SSIDs.
+
+use ipnetwork::IpNetwork;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::{self, Write},
+    net::IpAddr,
+};
+
+/// Length of a hex-encoded SHA-256 hash
+const SSID_HASH_LEN: usize = 64;
+/// Length of an [SsidHashKey] in bytes
+pub const SSID_HASH_KEY_LEN: usize = 32;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("The SSID hash must be a hex-encoded SHA-256 hash")]
+    InvalidSsidHash,
+}
+
+/// A network that is trusted if any of its properties match.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustedNetwork {
+    /// Hex-encoded SHA-256 hash of the SSID of a Wi-Fi network. SSIDs can only be detected on
+    /// Linux with NetworkManager.
+    SsidHash(String),
+    /// MAC address of the default gateway. This can only be detected on macOS.
+    GatewayMac([u8; 6]),
+    /// Subnet that contains the IP address of the default gateway
+    GatewaySubnet(IpNetwork),
+}
+
+impl TrustedNetwork {
+    /// Trust the Wi-Fi network with the given SSID. Only a hash of the SSID is kept.
+    pub fn ssid(ssid: &[u8], key: &SsidHashKey) -> Self {
+        TrustedNetwork::SsidHash(key.hash(ssid))
+    }
+
+    /// Trust the Wi-Fi network whose SSID hashes to `ssid_hash`.
+    pub fn ssid_hash(ssid_hash: String) -> Result<Self, Error> {
+        if ssid_hash.len() != SSID_HASH_LEN || !ssid_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error::InvalidSsidHash);
+        }
+        Ok(TrustedNetwork::SsidHash(ssid_hash.to_ascii_lowercase()))
+    }
+
+    fn matches(&self, network: &CurrentNetwork) -> bool {
+        match self {
+            TrustedNetwork::SsidHash(hash) => network.ssid_hash.as_ref() == Some(hash),
+            TrustedNetwork::GatewayMac(mac) => network.gateway_mac.as_ref() == Some(mac),
+            TrustedNetwork::GatewaySubnet(subnet) => network
+                .gateway
+                .is_some_and(|gateway| subnet.contains(gateway)),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrustedNetworksSettings {
+    pub networks: Vec<TrustedNetwork>,
+    /// Whether to disconnect when joining a trusted network. Otherwise, only auto-connect is
+    /// suppressed.
+    pub disconnect: bool,
+}
+
+impl TrustedNetworksSettings {
+    /// Return whether `network` matches any of the trusted networks.
+    pub fn is_trusted(&self, network: &CurrentNetwork) -> bool {
+        self.networks.iter().any(|trusted| trusted.matches(network))
+    }
+}
+
+/// Properties of the network that the device is currently connected to. Each property is `None`
+/// if it could not be detected.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct CurrentNetwork {
+    /// Hex-encoded SHA-256 hash of the SSID, if connected to Wi-Fi
+    pub ssid_hash: Option<String>,
+    /// IP address of the default gateway
+    pub gateway: Option<IpAddr>,
+    /// MAC address of the default gateway
+    pub gateway_mac: Option<[u8; 6]>,
+}
+
+/// Secret that SSIDs are salted with before they are hashed. It is generated once per
+/// installation and is never sent to clients.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SsidHashKey([u8; SSID_HASH_KEY_LEN]);
+
+impl SsidHashKey {
+    pub fn new(key: [u8; SSID_HASH_KEY_LEN]) -> Self {
+        SsidHashKey(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; SSID_HASH_KEY_LEN] {
+        &self.0
+    }
+
+    /// Return the hex-encoded SHA-256 hash of `ssid`, salted with this key.
+    pub fn hash(&self, ssid: &[u8]) -> String {
+        Sha256::new()
+            .chain_update(self.0)
+            .chain_update(ssid)
+            .finalize()
+            .iter()
+            .fold(String::with_capacity(SSID_HASH_LEN), |mut hash, byte| {
+                let _ = write!(hash, "{byte:02x}");
+                hash
+            })
+    }
+}
+
+impl fmt::Debug for SsidHashKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SsidHashKey([redacted])")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(byte: u8) -> SsidHashKey {
+        SsidHashKey::new([byte; SSID_HASH_KEY_LEN])
+    }
+
+    #[test]
+    fn test_is_trusted() {
+        let key = key(1);
+        let settings = TrustedNetworksSettings {
+            networks: vec![
+                TrustedNetwork::ssid(b"home", &key),
+                TrustedNetwork::GatewaySubnet("192.168.1.0/24".parse().unwrap()),
+            ],
+            disconnect: false,
+        };
+
+        assert!(!settings.is_trusted(&CurrentNetwork::default()));
+        assert!(settings.is_trusted(&CurrentNetwork {
+            ssid_hash: Some(key.hash(b"home")),
+            ..Default::default()
+        }));
+        assert!(!settings.is_trusted(&CurrentNetwork {
+            ssid_hash: Some(key.hash(b"cafe")),
+            gateway: Some("192.168.2.1".parse().unwrap()),
+            ..Default::default()
+        }));
+        assert!(settings.is_trusted(&CurrentNetwork {
+            gateway: Some("192.168.1.1".parse().unwrap()),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn test_ssid_hash() {
+        let hash = key(1).hash(b"home");
+        assert_eq!(
+            TrustedNetwork::ssid_hash(hash.to_ascii_uppercase()),
+            Ok(TrustedNetwork::SsidHash(hash))
+        );
+        assert_eq!(
+            TrustedNetwork::ssid_hash("home".to_owned()),
+            Err(Error::InvalidSsidHash)
+        );
+    }
+
+    /// The hash depends on the key, so that it cannot be looked up without it
+    #[test]
+    fn test_ssid_hash_key() {
+        assert_eq!(key(1).hash(b"home"), key(1).hash(b"home"));
+        assert_ne!(key(1).hash(b"home"), key(1).hash(b"cafe"));
+        assert_ne!(key(1).hash(b"home"), key(2).hash(b"home"));
+        assert_eq!(
+            TrustedNetwork::ssid_hash(key(1).hash(b"home")),
+            Ok(TrustedNetwork::ssid(b"home", &key(1)))
+        );
+        assert!(!format!("{:?}", key(1)).contains('1'));
+    }
+}
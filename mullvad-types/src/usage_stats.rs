@@ -0,0 +1,20 @@
+//! Usage statistics accumulated across tunnel sessions. These are only stored locally by the
+//! daemon, and are never sent to the API.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageStats {
+    /// Total time spent in the connected state
+    pub connected_time: Duration,
+    /// Bytes sent through the tunnel. The traffic of a session is included once it has ended.
+    pub tx_bytes: u64,
+    /// Bytes received through the tunnel. The traffic of a session is included once it has
+    /// ended.
+    pub rx_bytes: u64,
+    /// Number of times that a tunnel was connected
+    pub sessions: u64,
+    /// Hostname of the relay that has been connected to the most times
+    pub favorite_relay: Option<String>,
+}
@@ -48,6 +48,21 @@ pub struct SuggestedUpgrade {
     pub verified_installer_path: Option<std::path::PathBuf>,
 }
 
+/// An installer download that the daemon is performing
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ActiveDownload {
+    /// Identifies the download until the daemon is restarted
+    pub id: u32,
+    /// Version that is being downloaded
+    pub version: mullvad_version::Version,
+    /// URL that the installer is downloaded from
+    pub url: String,
+    /// Percentage of the installer that has been downloaded
+    pub progress: u32,
+    /// Whether the download was started by a prefetch
+    pub prefetch: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct AppUpgradeDownloadProgress {
     pub server: String,
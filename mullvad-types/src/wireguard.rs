@@ -1,7 +1,7 @@
 #![allow(clippy::identity_op)]
 use chrono::{DateTime, offset::Utc};
 use serde::{Deserialize, Deserializer, Serialize};
-use std::{fmt, str::FromStr, time::Duration};
+use std::{fmt, net::IpAddr, ops::RangeInclusive, str::FromStr, time::Duration};
 use talpid_types::net::wireguard;
 
 use crate::Intersection;
@@ -10,6 +10,10 @@ pub const MIN_ROTATION_INTERVAL: Duration = Duration::from_hours(24);
 pub const MAX_ROTATION_INTERVAL: Duration = Duration::from_hours(30 * 24);
 pub const DEFAULT_ROTATION_INTERVAL: Duration = MAX_ROTATION_INTERVAL;
 
+/// Allowed persistent keepalive intervals, in seconds. Shorter intervals waste bandwidth, and
+/// longer ones are unlikely to keep NAT mappings alive.
+pub const KEEPALIVE_INTERVAL_RANGE: RangeInclusive<u16> = 10..=600;
+
 #[derive(Serialize, Deserialize, Default, Copy, Clone, Debug, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
@@ -73,6 +77,11 @@ pub struct DaitaSettings {
     /// Whether to use multihop if the selected relay is not DAITA-compatible. Note that this is
     /// the inverse of of "Direct only" in the GUI.
     pub use_multihop_if_necessary: bool,
+
+    #[serde(default)]
+    /// Whether to fail to connect if no relay that supports DAITA matches the constraints. If
+    /// disabled, a relay without DAITA is used instead, and DAITA is not enabled for the tunnel.
+    pub required: bool,
 }
 
 #[cfg(daita)]
@@ -89,6 +98,7 @@ impl Default for DaitaSettings {
         Self {
             enabled: false,
             use_multihop_if_necessary: Self::default_use_multihop_if_necessary(),
+            required: false,
         }
     }
 }
@@ -223,11 +233,57 @@ impl Default for RotationInterval {
     }
 }
 
+/// MTU and per-packet overhead of the active tunnel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TunnelMtuInfo {
+    /// MTU set in the settings. `None` if the MTU is determined automatically.
+    pub configured_mtu: Option<u16>,
+    /// MTU of the tunnel interface, i.e. the largest packet that can be sent through the tunnel
+    /// without being fragmented. `None` if it could not be determined.
+    pub tunnel_mtu: Option<u16>,
+    /// Bytes added to each packet by WireGuard, including the outer IP and UDP headers
+    pub wireguard_overhead: u16,
+    /// Bytes added to each packet by obfuscation, if any
+    pub obfuscation_overhead: u16,
+}
+
+impl TunnelMtuInfo {
+    /// Total number of bytes added to each packet sent through the tunnel
+    pub fn overhead(&self) -> u16 {
+        self.wireguard_overhead + self.obfuscation_overhead
+    }
+
+    /// Size of the largest packet sent on the physical link, if the tunnel MTU is known
+    pub fn link_packet_size(&self) -> Option<u16> {
+        self.tunnel_mtu
+            .map(|mtu| mtu.saturating_add(self.overhead()))
+    }
+}
+
+/// Noise protocol used for the WireGuard handshake. WireGuard does not negotiate ciphers, so this
+/// is the same for every tunnel. Traffic is encrypted using ChaCha20-Poly1305.
+pub const WIREGUARD_HANDSHAKE_PROTOCOL: &str = "Noise_IKpsk2_25519_ChaChaPoly_BLAKE2s";
+
+/// Cryptographic details of the active tunnel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TunnelCryptoInfo {
+    /// Name of the handshake protocol, such as [WIREGUARD_HANDSHAKE_PROTOCOL]
+    pub handshake_protocol: String,
+    /// Whether a pre-shared key from a post-quantum secure key exchange is mixed into the
+    /// handshake. No pre-shared key is used otherwise.
+    pub quantum_resistant: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(default)]
 pub struct TunnelOptions {
     /// MTU for the wireguard tunnel
     pub mtu: Option<u16>,
+    /// Persistent keepalive interval in seconds. If `None`, no keepalive packets are sent.
+    pub keepalive: Option<u16>,
+    /// Local address to bind the tunnel socket to. If `None`, the address is chosen by the OS
+    /// based on the routing table. Only supported by the GotaTun implementation.
+    pub bind_address: Option<IpAddr>,
     /// Obtain a PSK using the relay config client.
     pub quantum_resistant: QuantumResistantState,
     /// Configure DAITA
@@ -242,6 +298,8 @@ impl Default for TunnelOptions {
     fn default() -> Self {
         TunnelOptions {
             mtu: None,
+            keepalive: None,
+            bind_address: None,
             quantum_resistant: QuantumResistantState::default(),
             #[cfg(daita)]
             daita: DaitaSettings::default(),
@@ -254,6 +312,8 @@ impl TunnelOptions {
     pub fn into_talpid_tunnel_options(self) -> wireguard::TunnelOptions {
         wireguard::TunnelOptions {
             mtu: self.mtu,
+            persistent_keepalive: self.keepalive,
+            bind_address: self.bind_address,
             quantum_resistant: self.quantum_resistant.enabled(),
             #[cfg(daita)]
             daita: self.daita.enabled,
@@ -268,6 +328,24 @@ pub struct PublicKey {
     pub created: DateTime<Utc>,
 }
 
+/// Age of the current WireGuard key, which is reset whenever the key is rotated
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WireguardKeyAge {
+    pub created: DateTime<Utc>,
+    pub age: Duration,
+}
+
+impl WireguardKeyAge {
+    /// Return the age at `now` of a key created at `created`. A key created in the future, e.g.
+    /// because the clock was changed, is zero seconds old.
+    pub fn new(created: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        Self {
+            created,
+            age: (now - created).to_std().unwrap_or_default(),
+        }
+    }
+}
+
 /// Contains a pair of local link addresses that are paired with a specific wireguard
 /// public/private keypair.
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
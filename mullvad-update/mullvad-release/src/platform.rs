@@ -246,6 +246,7 @@ impl Platform {
         // Make release
         let new_release = Release {
             changelog: changes.to_owned(),
+            localized_changelogs: Default::default(),
             version: version.clone(),
             installers,
             rollout,
@@ -399,6 +400,7 @@ impl Platform {
                 // NOTE: Empty versions are allowed on Linux
                 allow_empty: self == &Platform::Linux,
                 lowest_metadata_version: MIN_VERIFY_METADATA_VERSION,
+                locale: None,
             };
             version_info.push(VersionInfo::try_from_response(
                 &params,
@@ -65,6 +65,8 @@ pub struct HttpVersionInfoProvider {
 }
 
 impl VersionInfoProvider for HttpVersionInfoProvider {
+    /// Fetch and verify version metadata. Metadata that has passed its `metadata_expiry` is
+    /// rejected and not saved, so that a stale response can never be pinned.
     async fn get_version_info(&self, params: &VersionParameters) -> anyhow::Result<VersionInfo> {
         let response = self.get_versions(params.lowest_metadata_version).await?;
         VersionInfo::try_from_response(params, response.signed)
@@ -117,18 +119,21 @@ impl HttpVersionInfoProvider {
         .await
     }
 
-    /// Download and verify signed data with the given keys
+    /// Download and verify signed data with the given keys, as if the current time were
+    /// `current_time`
     #[cfg(test)]
     async fn get_versions_with_keys(
         &self,
         lowest_metadata_version: usize,
         verifying_keys: &Vec1<crate::format::key::VerifyingKey>,
+        current_time: chrono::DateTime<chrono::Utc>,
     ) -> anyhow::Result<SignedResponse> {
         self.get_versions_inner(|raw_json| {
             SignedResponse::deserialize_and_verify_at_time(
                 verifying_keys,
+                std::num::NonZeroUsize::MIN,
                 raw_json,
-                chrono::DateTime::UNIX_EPOCH,
+                current_time,
                 lowest_metadata_version,
             )
         })
@@ -273,7 +278,7 @@ mod test {
         };
 
         let info = info_provider
-            .get_versions_with_keys(0, &verifying_keys)
+            .get_versions_with_keys(0, &verifying_keys, chrono::DateTime::UNIX_EPOCH)
             .await
             .context("Expected valid version info")?;
 
@@ -284,6 +289,7 @@ mod test {
         let cached_data = fs::read(temp_dump).await.expect("expected dumped info");
         let cached_info = SignedResponse::deserialize_and_verify_at_time(
             &verifying_keys,
+            std::num::NonZeroUsize::MIN,
             &cached_data,
             chrono::DateTime::UNIX_EPOCH,
             0,
@@ -293,4 +299,51 @@ mod test {
 
         Ok(())
     }
+
+    /// Test that the HTTP version info provider rejects expired metadata, and does not cache it
+    #[tokio::test]
+    async fn test_http_version_provider_expired() -> anyhow::Result<()> {
+        let valid_key =
+            crate::format::key::VerifyingKey::from_hex(include_str!("../../test-pubkey"))
+                .expect("valid key");
+        let verifying_keys = vec1![valid_key];
+
+        // Start HTTP server
+        let mut server = mockito::Server::new_async().await;
+        let _mock: mockito::Mock = server
+            .mock("GET", "/version")
+            // Respond with some version response payload
+            .with_body(include_bytes!("../../test-version-response.json"))
+            .create();
+
+        // Resolve some host to our mockito server
+        let host = "fakeurl.biz";
+        let url = format!("http://{host}:{}/version", server.socket_address().port());
+        let resolve = (host, server.socket_address().ip());
+
+        let temp_dump_dir = TempDir::new().await.unwrap();
+        let temp_dump = temp_dump_dir.join("metadata.json");
+
+        let info_provider = HttpVersionInfoProvider {
+            url,
+            pinned_certificate: None,
+            resolve: Some(resolve),
+            dump_to_path: Some(temp_dump.clone()),
+        };
+
+        // The test response expired long before the year 3000
+        let current_time = "3000-01-01T00:00:00Z".parse().unwrap();
+        info_provider
+            .get_versions_with_keys(0, &verifying_keys, current_time)
+            .await
+            .expect_err("Expected expired version info to be rejected");
+
+        // Expect: Expired data should not be dumped
+        assert!(
+            fs::metadata(temp_dump).await.is_err(),
+            "expired metadata should not be cached"
+        );
+
+        Ok(())
+    }
 }
@@ -5,6 +5,7 @@
 use std::{
     ffi::OsString,
     future::Future,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
     time::Duration,
 };
@@ -15,7 +16,7 @@ use tokio::{process::Command, time::timeout};
 use crate::fetch;
 use crate::format::installer::Installer;
 use crate::format::response::SignedResponse;
-use crate::verify::{AppVerifier, Sha256Verifier};
+use crate::verify::{AppVerifier, Sha256Verifier, VerifyProgress};
 use crate::version::VersionParameters;
 
 #[derive(Debug, thiserror::Error)]
@@ -43,6 +44,8 @@ pub struct AppDownloaderParameters<AppProgress> {
     /// Directory to store the installer in.
     /// Ensure that this has proper permissions set.
     pub cache_dir: PathBuf,
+    /// Maximum download rate in bytes per second, if any.
+    pub rate_limit: Option<NonZeroUsize>,
 }
 
 /// See the [module-level documentation](self).
@@ -54,8 +57,11 @@ pub trait AppDownloader: Send + 'static {
 }
 
 pub trait DownloadedInstaller: Send + 'static {
-    /// Verify the app signature.
-    fn verify(self) -> impl Future<Output = Result<impl VerifiedInstaller, DownloadError>> + Send;
+    /// Verify the app signature, reporting the progress to `progress`.
+    fn verify(
+        self,
+        progress: impl VerifyProgress,
+    ) -> impl Future<Output = Result<impl VerifiedInstaller, DownloadError>> + Send;
 
     fn version(&self) -> &mullvad_version::Version;
 }
@@ -80,19 +86,23 @@ const INSTALLER_STARTUP_TIMEOUT: Duration = Duration::from_millis(500);
 /// Download the app and signature, and verify the installer's signature
 pub async fn download_install_and_upgrade(
     downloader: impl AppDownloader,
+    verify_progress: impl VerifyProgress,
 ) -> Result<(), DownloadError> {
     downloader
         .download_executable()
         .await?
-        .verify()
+        .verify(verify_progress)
         .await?
         .install()
         .await
 }
 
 /// Verify and run the installer.
-pub async fn install_and_upgrade(installer: impl DownloadedInstaller) -> Result<(), DownloadError> {
-    installer.verify().await?.install().await
+pub async fn install_and_upgrade(
+    installer: impl DownloadedInstaller,
+    verify_progress: impl VerifyProgress,
+) -> Result<(), DownloadError> {
+    installer.verify(verify_progress).await?.install().await
 }
 
 #[derive(Clone)]
@@ -130,6 +140,7 @@ impl<AppProgress: fetch::ProgressUpdater> AppDownloader for HttpAppDownloader<Ap
             &self.params.app_url,
             &mut self.params.app_progress,
             fetch::SizeHint::Exact(self.params.app_size),
+            self.params.rate_limit,
         )
         .await
         .map_err(DownloadError::FetchApp)?;
@@ -144,8 +155,11 @@ impl<AppProgress: fetch::ProgressUpdater> AppDownloader for HttpAppDownloader<Ap
 }
 
 impl DownloadedInstaller for InstallerFile<false> {
-    async fn verify(self) -> Result<impl VerifiedInstaller, DownloadError> {
-        match Sha256Verifier::verify(&self.path, self.app_sha256)
+    async fn verify(
+        self,
+        progress: impl VerifyProgress,
+    ) -> Result<impl VerifiedInstaller, DownloadError> {
+        match Sha256Verifier::verify(&self.path, self.app_sha256, progress)
             .await
             .map_err(DownloadError::Verification)
         {
@@ -2,6 +2,7 @@
 
 use std::{
     error::Error,
+    num::NonZeroUsize,
     path::Path,
     pin::Pin,
     task::{Poll, ready},
@@ -9,10 +10,14 @@ use std::{
 };
 
 use anyhow::Context;
-use reqwest::header::{CONTENT_LENGTH, HeaderValue, RANGE};
+use reqwest::{
+    StatusCode,
+    header::{CONTENT_LENGTH, CONTENT_RANGE, HeaderMap, RANGE},
+};
 use tokio::{
     fs::{self, File},
     io::{self, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt, BufWriter},
+    time::Instant,
 };
 
 use thiserror::Error;
@@ -46,6 +51,14 @@ pub enum DownloadError {
     #[error("Failed to retrieve range")]
     RangeRequest(#[source] reqwest::Error),
 
+    /// The server ignored the range request and responded with the entire file
+    #[error("Server does not support range requests")]
+    RangeNotSupported,
+
+    /// Invalid content range header
+    #[error("Invalid content range header: {0}")]
+    InvalidContentRange(&'static str),
+
     /// Failed to read chunk
     #[error("Failed to read chunk")]
     ChunkRead(#[source] reqwest::Error),
@@ -167,11 +180,13 @@ impl SizeHint {
 /// # Arguments
 /// - `progress_updater` - This interface is notified of download progress.
 /// - `size_hint` - File size restrictions.
+/// - `rate_limit` - Maximum download rate in bytes per second, if any.
 pub async fn get_to_file(
     file: impl AsRef<Path>,
     url: &str,
     progress_updater: &mut impl ProgressUpdater,
     size_hint: SizeHint,
+    rate_limit: Option<NonZeroUsize>,
 ) -> anyhow::Result<()> {
     let file = create_or_append(file)
         .await
@@ -179,9 +194,28 @@ pub async fn get_to_file(
     let mut file = BufWriter::new(file);
     let mut attempts = 0;
     let mut read_timeout = READ_TIMEOUT;
-    while let Err(err) =
-        get_to_writer(&mut file, url, progress_updater, size_hint, read_timeout).await
+    while let Err(err) = get_to_writer(
+        &mut file,
+        url,
+        progress_updater,
+        size_hint,
+        read_timeout,
+        rate_limit,
+    )
+    .await
     {
+        if matches!(err, DownloadError::RangeNotSupported) {
+            // We cannot resume the download, so start over from the beginning
+            log::warn!("Server does not support range requests. Restarting download");
+            file.get_mut()
+                .set_len(0)
+                .await
+                .map_err(DownloadError::FileOperation)?;
+            file.seek(io::SeekFrom::Start(0))
+                .await
+                .map_err(DownloadError::FileOperation)?;
+            continue;
+        }
         if !err.should_retry() {
             return Err(err.into());
         }
@@ -201,12 +235,15 @@ pub async fn get_to_file(
 /// # Arguments
 /// - `progress_updater` - This interface is notified of download progress.
 /// - `size_hint` - File size restrictions.
+/// - `read_timeout` - Timeout for each read.
+/// - `rate_limit` - Maximum download rate in bytes per second, if any.
 pub async fn get_to_writer(
     mut writer: impl AsyncWrite + AsyncSeek + Unpin,
     url: &str,
     progress_updater: &mut impl ProgressUpdater,
     size_hint: SizeHint,
     read_timeout: Duration,
+    rate_limit: Option<NonZeroUsize>,
 ) -> Result<(), DownloadError> {
     // Create a new client for each download attempt to prevent stale connections
     let client = reqwest::Client::builder()
@@ -268,35 +305,46 @@ pub async fn get_to_writer(
         written_nbytes: already_fetched_bytes,
         total_nbytes: total_size,
     };
+    let mut throttle = rate_limit.map(Throttle::new);
 
-    for range in RangeIter::new(already_fetched_bytes, total_size) {
-        let mut response = client
-            .get(url)
-            .header(RANGE, range)
-            .send()
-            .await
-            .map_err(DownloadError::RangeRequest)?;
+    // Fetch the remaining content
+    let mut response = client
+        .get(url)
+        .header(RANGE, format!("bytes={already_fetched_bytes}-"))
+        .send()
+        .await
+        .map_err(DownloadError::RangeRequest)?;
 
-        let status = response.status();
-        if !status.is_success() {
-            return Err(DownloadError::HttpStatus(status));
+    let status = response.status();
+    match status {
+        StatusCode::PARTIAL_CONTENT => {
+            check_content_range(response.headers(), already_fetched_bytes, total_size)?;
         }
+        // The server ignored the range, but we want the entire file anyway
+        StatusCode::OK if already_fetched_bytes == 0 => (),
+        // The server ignored the range, so the response cannot be appended to what we have
+        StatusCode::OK => return Err(DownloadError::RangeNotSupported),
+        status => return Err(DownloadError::HttpStatus(status)),
+    }
 
-        let mut bytes_read = 0;
+    let mut bytes_read = 0;
 
-        while let Some(chunk) = response.chunk().await.map_err(DownloadError::ChunkRead)? {
-            bytes_read += chunk.len();
-            if bytes_read > total_size - already_fetched_bytes {
-                // Protect against servers responding with more data than expected
-                return Err(DownloadError::SizeValidation(
-                    "Server returned more than requested bytes".to_string(),
-                ));
-            }
+    while let Some(chunk) = response.chunk().await.map_err(DownloadError::ChunkRead)? {
+        bytes_read += chunk.len();
+        if bytes_read > total_size - already_fetched_bytes {
+            // Protect against servers responding with more data than expected
+            return Err(DownloadError::SizeValidation(
+                "Server returned more than requested bytes".to_string(),
+            ));
+        }
 
-            writer
-                .write_all(&chunk)
-                .await
-                .map_err(DownloadError::ChunkWrite)?;
+        writer
+            .write_all(&chunk)
+            .await
+            .map_err(DownloadError::ChunkWrite)?;
+
+        if let Some(throttle) = &mut throttle {
+            throttle.consume(chunk.len()).await;
         }
     }
 
@@ -320,38 +368,66 @@ async fn create_or_append(path: impl AsRef<Path>) -> io::Result<File> {
     }
 }
 
-/// Used to download partial content
-struct RangeIter {
-    current: usize,
-    end: usize,
-}
-
-impl RangeIter {
-    fn new(current: usize, end: usize) -> Self {
-        Self { current, end }
+/// Verify that the `Content-Range` of a partial response begins at `expected_start` and that the
+/// total size matches `expected_total`.
+fn check_content_range(
+    headers: &HeaderMap,
+    expected_start: usize,
+    expected_total: usize,
+) -> Result<(), DownloadError> {
+    let content_range = headers
+        .get(CONTENT_RANGE)
+        .ok_or(DownloadError::InvalidContentRange("Missing content range"))?
+        .to_str()
+        .map_err(|_| DownloadError::InvalidContentRange("Invalid content range header"))?;
+    let (start, total) = parse_content_range(content_range).ok_or(
+        DownloadError::InvalidContentRange("Invalid content range format"),
+    )?;
+
+    if start != expected_start {
+        return Err(DownloadError::InvalidContentRange(
+            "Range does not start at the requested offset",
+        ));
     }
+    if total != expected_total {
+        return Err(DownloadError::SizeValidation(format!(
+            "Total size changed: expected {expected_total} bytes, served {total}"
+        )));
+    }
+    Ok(())
 }
 
-impl Iterator for RangeIter {
-    type Item = HeaderValue;
+/// Parse the start and total size from a content range value, e.g. "bytes 32-1023/1024"
+fn parse_content_range(val: &str) -> Option<(usize, usize)> {
+    let range = val.strip_prefix("bytes ")?;
+    let (range, total) = range.split_once('/')?;
+    let (start, _end) = range.split_once('-')?;
+    Some((start.parse().ok()?, total.parse().ok()?))
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.current > self.end {
-            return None;
-        }
-        let prev = self.current;
+/// Paces reads so that the average download rate does not exceed `rate_limit`
+struct Throttle {
+    /// Maximum number of bytes per second
+    rate_limit: NonZeroUsize,
+    start: Instant,
+    consumed_nbytes: usize,
+}
 
-        let read_n = self.end.saturating_sub(self.current);
-        if read_n == 0 {
-            return None;
+impl Throttle {
+    fn new(rate_limit: NonZeroUsize) -> Self {
+        Self {
+            rate_limit,
+            start: Instant::now(),
+            consumed_nbytes: 0,
         }
+    }
 
-        self.current += read_n;
-
-        // NOTE: Subtracting 1 because range includes final byte
-        let end = self.current - 1;
-
-        Some(HeaderValue::from_str(&format!("bytes={prev}-{end}")).expect("valid range/str"))
+    /// Register that `nbytes` have been read, and wait until reading them is within the rate limit
+    async fn consume(&mut self, nbytes: usize) {
+        self.consumed_nbytes += nbytes;
+        let min_elapsed =
+            Duration::from_secs_f64(self.consumed_nbytes as f64 / self.rate_limit.get() as f64);
+        tokio::time::sleep_until(self.start + min_elapsed).await;
     }
 }
 
@@ -488,6 +564,7 @@ mod test {
             &mut progress_updater,
             SizeHint::Exact(file_data.len()),
             READ_TIMEOUT,
+            None,
         )
         .await
         .context("Complete download failed")?;
@@ -523,6 +600,7 @@ mod test {
             &mut progress_updater,
             SizeHint::Exact(file_data.len()),
             READ_TIMEOUT,
+            None,
         )
         .await
         .expect_err("Expected interrupted download");
@@ -554,6 +632,7 @@ mod test {
             &mut progress_updater,
             SizeHint::Exact(file_data.len()),
             READ_TIMEOUT,
+            None,
         )
         .await
         .context("Partial download failed")?;
@@ -565,6 +644,171 @@ mod test {
         Ok(())
     }
 
+    /// Test that [get_to_writer] does not exceed the rate limit
+    #[tokio::test]
+    async fn test_fetch_rate_limited() -> anyhow::Result<()> {
+        const RATE_LIMIT: NonZeroUsize = NonZeroUsize::new(256 * 1024).unwrap();
+
+        // Generate random data
+        let file_data = Box::leak(Box::new(vec![0u8; 128 * 1024]));
+        rand::rng().fill_bytes(file_data);
+
+        // Start server
+        let mut server = mockito::Server::new_async().await;
+        let file_url = format!("{}/my_file", server.url());
+        add_file_server_mock(&mut server, "/my_file", file_data);
+
+        let mut writer = Cursor::new(vec![]);
+        let mut progress_updater = FakeProgressUpdater::default();
+
+        let start = std::time::Instant::now();
+        get_to_writer(
+            &mut writer,
+            &file_url,
+            &mut progress_updater,
+            SizeHint::Exact(file_data.len()),
+            READ_TIMEOUT,
+            Some(RATE_LIMIT),
+        )
+        .await
+        .context("Rate limited download failed")?;
+        let elapsed = start.elapsed();
+
+        // Downloading 128 KiB at 256 KiB/s must take at least half a second
+        let min_elapsed = Duration::from_millis(500);
+        assert!(
+            elapsed >= min_elapsed,
+            "expected download to take at least {min_elapsed:?}, took {elapsed:?}"
+        );
+        assert_eq!(progress_updater.complete, 1.);
+        assert_eq!(&mut writer.into_inner(), file_data);
+
+        Ok(())
+    }
+
+    /// Test that [get_to_file] appends to an existing partial file
+    #[tokio::test]
+    async fn test_resume_partial_content() -> anyhow::Result<()> {
+        // Generate random data
+        let file_data = Box::leak(Box::new(vec![0u8; 1024 * 1024]));
+        rand::rng().fill_bytes(file_data);
+
+        // Start server
+        let mut server = mockito::Server::new_async().await;
+        let file_url = format!("{}/my_file", server.url());
+        add_file_server_mock(&mut server, "/my_file", file_data);
+
+        // Create a partial download
+        let temp_dir = TempDir::new().await?;
+        let file_path = temp_dir.join("my_file");
+        fs::write(&file_path, &file_data[..file_data.len() / 3]).await?;
+
+        get_to_file(
+            &file_path,
+            &file_url,
+            &mut FakeProgressUpdater::default(),
+            SizeHint::Exact(file_data.len()),
+            None,
+        )
+        .await
+        .context("Resumed download failed")?;
+
+        assert_eq!(&fs::read(&file_path).await?, file_data);
+
+        Ok(())
+    }
+
+    /// Test that [get_to_file] restarts the download if the server ignores the range request
+    #[tokio::test]
+    async fn test_resume_range_not_supported() -> anyhow::Result<()> {
+        // Generate random data
+        let file_data = Box::leak(Box::new(vec![0u8; 1024 * 1024]));
+        rand::rng().fill_bytes(file_data);
+
+        // Start a server that always responds with the entire file
+        let mut server = mockito::Server::new_async().await;
+        let file_url = format!("{}/my_file", server.url());
+        server
+            .mock("HEAD", "/my_file")
+            .with_header(CONTENT_LENGTH, &file_data.len().to_string())
+            .create();
+        server
+            .mock("GET", "/my_file")
+            .with_body(&file_data[..])
+            .create();
+
+        // Create a partial download that does not match the served file
+        let temp_dir = TempDir::new().await?;
+        let file_path = temp_dir.join("my_file");
+        fs::write(&file_path, b"stale partial download").await?;
+
+        // Resuming is impossible
+        let mut file = create_or_append(&file_path).await?;
+        let err = get_to_writer(
+            &mut file,
+            &file_url,
+            &mut FakeProgressUpdater::default(),
+            SizeHint::Exact(file_data.len()),
+            READ_TIMEOUT,
+            None,
+        )
+        .await
+        .expect_err("Expected range request to fail");
+        assert!(matches!(err, DownloadError::RangeNotSupported));
+        drop(file);
+
+        // ... so the file should be downloaded from scratch
+        get_to_file(
+            &file_path,
+            &file_url,
+            &mut FakeProgressUpdater::default(),
+            SizeHint::Exact(file_data.len()),
+            None,
+        )
+        .await
+        .context("Restarted download failed")?;
+
+        assert_eq!(&fs::read(&file_path).await?, file_data);
+
+        Ok(())
+    }
+
+    /// Test that partial responses for a differently sized file are rejected
+    #[tokio::test]
+    async fn test_resume_total_size_mismatch() -> anyhow::Result<()> {
+        let file_data = vec![0u8; 1024];
+
+        let mut server = mockito::Server::new_async().await;
+        let file_url = format!("{}/my_file", server.url());
+        server
+            .mock("HEAD", "/my_file")
+            .with_header(CONTENT_LENGTH, &file_data.len().to_string())
+            .create();
+        server
+            .mock("GET", "/my_file")
+            .with_status(206)
+            .with_header(CONTENT_RANGE, "bytes 512-2047/2048")
+            .with_body(&file_data[512..])
+            .create();
+
+        let mut writer = Cursor::new(file_data[..512].to_vec());
+        writer.set_position(512);
+
+        let err = get_to_writer(
+            &mut writer,
+            &file_url,
+            &mut FakeProgressUpdater::default(),
+            SizeHint::Exact(file_data.len()),
+            READ_TIMEOUT,
+            None,
+        )
+        .await
+        .expect_err("Reject mismatching total size");
+        assert!(matches!(err, DownloadError::SizeValidation(_)));
+
+        Ok(())
+    }
+
     /// Create endpoints that serve a file at `url_path` using HTTP range requests
     fn add_file_server_mock(server: &mut mockito::Server, url_path: &str, data: &'static [u8]) {
         // Respond to head requests with file size
@@ -573,27 +817,41 @@ mod test {
             .with_header(CONTENT_LENGTH, &data.len().to_string())
             .create();
 
-        // Respond to HTTP range requests with file
+        // Respond to HTTP range requests with partial content
         server
             .mock("GET", url_path)
+            .with_status(206)
+            .with_header_from_request(CONTENT_RANGE, |request| {
+                let (begin, end) = request_range(request, data.len());
+                format!("bytes {begin}-{end}/{}", data.len())
+            })
             .with_body_from_request(|request| {
-                let range = request.header(RANGE);
-                let range = range[0].to_str().expect("expected str");
-                let (begin, end) = parse_http_range(range).expect("invalid range");
-
+                let (begin, end) = request_range(request, data.len());
                 data[begin..=end].to_vec()
             })
             .create();
     }
 
-    /// Parse a range header value, e.g. "bytes=0-31"
-    fn parse_http_range(val: &str) -> anyhow::Result<(usize, usize)> {
+    /// Return the inclusive byte range requested by `request`
+    fn request_range(request: &mockito::Request, len: usize) -> (usize, usize) {
+        let range = request.header(RANGE);
+        let range = range[0].to_str().expect("expected str");
+        let (begin, end) = parse_http_range(range).expect("invalid range");
+        (begin, end.unwrap_or(len - 1))
+    }
+
+    /// Parse a range header value, e.g. "bytes=0-31" or "bytes=32-"
+    fn parse_http_range(val: &str) -> anyhow::Result<(usize, Option<usize>)> {
         // parse: bytes=0-31
         let (_, val) = val.split_once('=').context("invalid range header")?;
         let (begin, end) = val.split_once('-').context("invalid range")?;
 
         let begin: usize = begin.parse().context("invalid range begin")?;
-        let end: usize = end.parse().context("invalid range end")?;
+        let end: Option<usize> = if end.is_empty() {
+            None
+        } else {
+            Some(end.parse().context("invalid range end")?)
+        };
 
         Ok((begin, end))
     }
@@ -615,6 +873,7 @@ mod test {
             &mut FakeProgressUpdater::default(),
             SizeHint::Exact(1),
             READ_TIMEOUT,
+            None,
         )
         .await
         .expect_err("Reject unexpected content length");
@@ -640,6 +899,7 @@ mod test {
             &mut FakeProgressUpdater::default(),
             SizeHint::Exact(file_data.len()),
             READ_TIMEOUT,
+            None,
         )
         .await
         .expect_err("Reject unexpected chunk sizes");
@@ -3,13 +3,18 @@
 //! This module implements fetching of information about app versions from disk.
 
 use anyhow::{Context, bail};
-use std::{path::PathBuf, vec};
+use std::{
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    vec,
+};
 use tokio::fs;
+use vec1::Vec1;
 
-use crate::format::response::SignedResponse;
+use crate::format::{key::VerifyingKey, response::SignedResponse};
 use crate::version::VersionParameters;
 
-use super::app::{AppCache, InstallerFile};
+use super::app::{AppCache, InstallerFile, bin_path};
 
 pub struct AppCacheDir {
     /// Path to directory containing the metadata file and the downloaded installer.
@@ -80,3 +85,205 @@ impl AppCache for NoopAppCacheDir {
         bail!("NoopAppCacheDir can not present any metadata")
     }
 }
+
+/// Installer and signed metadata provided by the user rather than fetched from the network,
+/// e.g. for offline installation.
+pub struct LocalInstaller {
+    /// Path to the installer
+    pub installer_path: PathBuf,
+    /// Path to the signed metadata describing the installer
+    pub metadata_path: PathBuf,
+}
+
+impl LocalInstaller {
+    /// Verify the metadata and look up the installer in it. The installer is copied to
+    /// `directory`, so that verifying or launching it does not affect the original file.
+    ///
+    /// The returned installer is not yet verified. This must be done using
+    /// [DownloadedInstaller::verify](super::app::DownloadedInstaller::verify).
+    pub async fn import(
+        &self,
+        directory: &Path,
+        version_params: &VersionParameters,
+    ) -> anyhow::Result<InstallerFile<false>> {
+        self.import_with_keys(
+            directory,
+            version_params,
+            &crate::defaults::TRUSTED_METADATA_SIGNING_PUBKEYS,
+            crate::defaults::TRUSTED_METADATA_SIGNATURE_THRESHOLD,
+        )
+        .await
+    }
+
+    /// Same as [Self::import], but the metadata must be signed by `threshold` of `keys`.
+    async fn import_with_keys(
+        &self,
+        directory: &Path,
+        version_params: &VersionParameters,
+        keys: &Vec1<VerifyingKey>,
+        threshold: NonZeroUsize,
+    ) -> anyhow::Result<InstallerFile<false>> {
+        let raw_json = fs::read(&self.metadata_path)
+            .await
+            .context("Failed to read metadata file")?;
+        let metadata = SignedResponse::deserialize_and_verify_with_keys(
+            keys,
+            threshold,
+            &raw_json,
+            version_params.lowest_metadata_version,
+        )
+        .context("Failed to deserialize or verify metadata")?;
+
+        let file = fs::File::open(&self.installer_path)
+            .await
+            .context("Failed to open installer")?;
+        let checksum = crate::hash::checksum(file).await?;
+
+        // Find the release that this installer belongs to
+        let (version, installer) = metadata
+            .get_releases()
+            .into_iter()
+            .flat_map(|release| {
+                release
+                    .installers
+                    .into_iter()
+                    .find(|installer| installer.architecture == version_params.architecture)
+                    .map(|installer| (release.version, installer))
+            })
+            .find(|(_, installer)| {
+                hex::decode(&installer.sha256).is_ok_and(|sha256| sha256 == checksum)
+            })
+            .context("Installer does not match any release in the metadata")?;
+
+        fs::copy(&self.installer_path, bin_path(&version, directory))
+            .await
+            .context("Failed to copy installer")?;
+
+        InstallerFile::<false>::try_from_installer(directory, version, installer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::DownloadedInstaller;
+    use crate::format::Architecture;
+    use crate::version::rollout::Rollout;
+    use async_tempfile::TempDir;
+    use vec1::vec1;
+
+    /// Metadata signed by the key in `test-pubkey`, describing the installer [INSTALLER]
+    const SIGNED_METADATA: &[u8] = include_bytes!("../../test-local-installer-metadata.json");
+    const INSTALLER: &[u8] = b"fake installer";
+
+    fn version_params() -> VersionParameters {
+        VersionParameters {
+            architecture: Architecture::X86,
+            rollout: Rollout::complete(),
+            allow_empty: false,
+            lowest_metadata_version: 0,
+            locale: None,
+        }
+    }
+
+    fn test_keys() -> Vec1<VerifyingKey> {
+        vec1![VerifyingKey::from_hex(include_str!("../../test-pubkey")).expect("valid key")]
+    }
+
+    /// Write `installer` and `metadata` to `dir`, and return them as a [LocalInstaller]
+    async fn local_installer(dir: &Path, installer: &[u8], metadata: &[u8]) -> LocalInstaller {
+        let local = LocalInstaller {
+            installer_path: dir.join("installer"),
+            metadata_path: dir.join("metadata.json"),
+        };
+        fs::write(&local.installer_path, installer).await.unwrap();
+        fs::write(&local.metadata_path, metadata).await.unwrap();
+        local
+    }
+
+    /// Test that a locally provided installer is found in signed metadata and can be verified
+    #[tokio::test]
+    async fn test_import_local_installer() {
+        let source_dir = TempDir::new().await.unwrap();
+        let cache_dir = TempDir::new().await.unwrap();
+        let local = local_installer(&source_dir, INSTALLER, SIGNED_METADATA).await;
+
+        let installer = local
+            .import_with_keys(
+                &cache_dir,
+                &version_params(),
+                &test_keys(),
+                NonZeroUsize::MIN,
+            )
+            .await
+            .expect("installer should be found in metadata");
+
+        assert_eq!(
+            installer.version(),
+            &"2025.1".parse::<mullvad_version::Version>().unwrap()
+        );
+        installer
+            .verify(())
+            .await
+            .expect("expected verification to pass");
+
+        // The original file should be left untouched
+        assert_eq!(fs::read(&local.installer_path).await.unwrap(), INSTALLER);
+    }
+
+    /// Test that an installer that is not described by the metadata is rejected
+    #[tokio::test]
+    async fn test_import_unknown_local_installer() {
+        let source_dir = TempDir::new().await.unwrap();
+        let cache_dir = TempDir::new().await.unwrap();
+        let local = local_installer(&source_dir, b"another installer", SIGNED_METADATA).await;
+
+        let result = local
+            .import_with_keys(
+                &cache_dir,
+                &version_params(),
+                &test_keys(),
+                NonZeroUsize::MIN,
+            )
+            .await;
+        assert!(result.is_err(), "installer should not match metadata");
+    }
+
+    /// Test that metadata which has been changed after it was signed is rejected
+    #[tokio::test]
+    async fn test_import_tampered_metadata() {
+        let source_dir = TempDir::new().await.unwrap();
+        let cache_dir = TempDir::new().await.unwrap();
+        let metadata = String::from_utf8(SIGNED_METADATA.to_vec())
+            .unwrap()
+            .replace("\"2025.1\"", "\"2025.2\"");
+        let local = local_installer(&source_dir, INSTALLER, metadata.as_bytes()).await;
+
+        let result = local
+            .import_with_keys(
+                &cache_dir,
+                &version_params(),
+                &test_keys(),
+                NonZeroUsize::MIN,
+            )
+            .await;
+        assert!(
+            result.is_err(),
+            "tampered metadata should fail verification"
+        );
+    }
+
+    /// Test that metadata which is not signed by the trusted keys is rejected
+    #[tokio::test]
+    async fn test_import_untrusted_metadata() {
+        let source_dir = TempDir::new().await.unwrap();
+        let cache_dir = TempDir::new().await.unwrap();
+        let local = local_installer(&source_dir, INSTALLER, SIGNED_METADATA).await;
+
+        let result = local.import(&cache_dir, &version_params()).await;
+        assert!(
+            result.is_err(),
+            "metadata signed by an untrusted key should be rejected"
+        );
+    }
+}
@@ -6,14 +6,27 @@ use tokio::{
 
 use std::{future::Future, path::Path};
 
+/// Receiver of verification progress
+pub trait VerifyProgress: Send + 'static {
+    /// Progress so far
+    fn set_verify_progress(&mut self, fraction_complete: f32);
+}
+
+/// Ignore verification progress
+impl VerifyProgress for () {
+    fn set_verify_progress(&mut self, _fraction_complete: f32) {}
+}
+
 /// A verifier of digital file signatures or hashes
 pub trait AppVerifier: 'static + Clone {
     type Parameters;
 
     /// Verify `bin_path` using `parameters`, and return an error if this fails for any reason.
+    /// `progress` is notified of how much of the file has been verified.
     fn verify(
         bin_path: impl AsRef<Path>,
         parameters: Self::Parameters,
+        progress: impl VerifyProgress,
     ) -> impl Future<Output = anyhow::Result<()>>;
 }
 
@@ -28,6 +41,7 @@ impl AppVerifier for Sha256Verifier {
     fn verify(
         bin_path: impl AsRef<Path>,
         expected_hash: Self::Parameters,
+        progress: impl VerifyProgress,
     ) -> impl Future<Output = anyhow::Result<()>> {
         let bin_path = bin_path.as_ref().to_owned();
 
@@ -35,9 +49,15 @@ impl AppVerifier for Sha256Verifier {
             let file = fs::File::open(&bin_path)
                 .await
                 .context(format!("Failed to open file at {}", bin_path.display()))?;
+            let file_size = file
+                .metadata()
+                .await
+                .context("Failed to get file metadata")?
+                .len();
+            let file_size = usize::try_from(file_size).context("Invalid file size")?;
             let file = BufReader::new(file);
 
-            Self::verify_inner(file, expected_hash).await
+            Self::verify_inner(file, file_size, expected_hash, progress).await
         }
     }
 }
@@ -45,9 +65,14 @@ impl AppVerifier for Sha256Verifier {
 impl Sha256Verifier {
     async fn verify_inner(
         reader: impl AsyncRead + Unpin,
+        size: usize,
         expected_hash: [u8; 32],
+        mut progress: impl VerifyProgress,
     ) -> anyhow::Result<()> {
-        let actual_hash = crate::hash::checksum(reader).await?;
+        let actual_hash = crate::hash::checksum_with_progress(reader, size, |fraction_complete| {
+            progress.set_verify_progress(fraction_complete)
+        })
+        .await?;
 
         // Verify that hash is correct
         if expected_hash != actual_hash {
@@ -66,6 +91,17 @@ mod test {
 
     use super::*;
 
+    #[derive(Default)]
+    struct FakeVerifyProgress {
+        complete: std::sync::Arc<std::sync::Mutex<f32>>,
+    }
+
+    impl VerifyProgress for FakeVerifyProgress {
+        fn set_verify_progress(&mut self, fraction_complete: f32) {
+            *self.complete.lock().unwrap() = fraction_complete;
+        }
+    }
+
     #[tokio::test]
     async fn test_sha256_checksum() {
         // Generate some random data
@@ -79,13 +115,16 @@ mod test {
         let expected_hash: [u8; 32] = expected_hash[..].try_into().unwrap();
 
         // Same data should be accepted
-        Sha256Verifier::verify_inner(Cursor::new(&data), expected_hash)
+        let progress = FakeVerifyProgress::default();
+        let complete = progress.complete.clone();
+        Sha256Verifier::verify_inner(Cursor::new(&data), data.len(), expected_hash, progress)
             .await
             .expect("expected checksum match");
+        assert_eq!(*complete.lock().unwrap(), 1.);
 
         // Compare the hash against some random data, which should fail
         rand::rng().fill_bytes(&mut data);
-        Sha256Verifier::verify_inner(Cursor::new(&data), expected_hash)
+        Sha256Verifier::verify_inner(Cursor::new(&data), data.len(), expected_hash, ())
             .await
             .expect_err("expected checksum mismatch");
     }
@@ -1,6 +1,7 @@
 //! Default keys and certificates that may be used for verifying data
 
 use crate::format::key::VerifyingKey;
+use std::num::NonZeroUsize;
 use std::sync::LazyLock;
 use vec1::Vec1;
 
@@ -27,6 +28,10 @@ pub static PINNED_CERTIFICATE: LazyLock<reqwest::Certificate> = LazyLock::new(||
 pub static TRUSTED_METADATA_SIGNING_PUBKEYS: LazyLock<Vec1<VerifyingKey>> =
     LazyLock::new(|| parse_keys(include_str!("../trusted-metadata-signing-pubkeys")));
 
+/// Number of distinct keys in [TRUSTED_METADATA_SIGNING_PUBKEYS] that must have signed the
+/// metadata for it to be accepted
+pub const TRUSTED_METADATA_SIGNATURE_THRESHOLD: NonZeroUsize = NonZeroUsize::MIN;
+
 fn parse_keys(keys: &str) -> Vec1<VerifyingKey> {
     let mut v = vec![];
     for key in keys.split('\n') {
@@ -1,5 +1,7 @@
 //! Deserializer and verifier of version metadata
 
+use std::num::NonZeroUsize;
+
 use anyhow::Context;
 use vec1::Vec1;
 
@@ -10,13 +12,15 @@ impl SignedResponse {
     /// Deserialize some bytes to JSON, and verify them, including signature and expiry.
     /// If successful, the deserialized data is returned.
     ///
-    /// This uses the keys in `trusted-metadata-signing-pubkeys`
+    /// This uses the keys in `trusted-metadata-signing-pubkeys`, and requires
+    /// [crate::defaults::TRUSTED_METADATA_SIGNATURE_THRESHOLD] of them to have signed the data.
     pub fn deserialize_and_verify(
         bytes: &[u8],
         min_metadata_version: usize,
     ) -> Result<Self, anyhow::Error> {
         Self::deserialize_and_verify_with_keys(
             &crate::defaults::TRUSTED_METADATA_SIGNING_PUBKEYS,
+            crate::defaults::TRUSTED_METADATA_SIGNATURE_THRESHOLD,
             bytes,
             min_metadata_version,
         )
@@ -28,10 +32,17 @@ impl SignedResponse {
     /// This is typically only used for testing. Prefer [deserialize_and_verify].
     pub(crate) fn deserialize_and_verify_with_keys(
         keys: &Vec1<VerifyingKey>,
+        threshold: NonZeroUsize,
         bytes: &[u8],
         min_metadata_version: usize,
     ) -> Result<Self, anyhow::Error> {
-        Self::deserialize_and_verify_at_time(keys, bytes, chrono::Utc::now(), min_metadata_version)
+        Self::deserialize_and_verify_at_time(
+            keys,
+            threshold,
+            bytes,
+            chrono::Utc::now(),
+            min_metadata_version,
+        )
     }
 
     /// This method is used mostly for testing, and skips all verification.
@@ -53,12 +64,13 @@ impl SignedResponse {
     /// This is typically only used for testing. Prefer [deserialize_and_verify].
     pub(crate) fn deserialize_and_verify_at_time(
         keys: &Vec1<VerifyingKey>,
+        threshold: NonZeroUsize,
         bytes: &[u8],
         current_time: chrono::DateTime<chrono::Utc>,
         min_metadata_version: usize,
     ) -> Result<Self, anyhow::Error> {
-        // Deserialize and verify signature
-        let partial_data = deserialize_and_verify(keys, bytes)?;
+        // Deserialize and verify signatures
+        let partial_data = deserialize_and_verify_with_threshold(keys, threshold, bytes)?;
 
         // Deserialize the canonical JSON to structured representation
         let signed_response: Response = serde_json::from_value(partial_data.signed)
@@ -88,39 +100,69 @@ impl SignedResponse {
     }
 }
 
-/// Deserialize arbitrary JSON object with a signature attached.
+/// Deserialize arbitrary JSON object with a signature attached, requiring a signature by at least
+/// one of `keys`.
 /// WARNING: This only verifies the signature, not expiration.
 ///
 /// On success, this returns verified data and signature
+#[cfg(test)]
 pub(super) fn deserialize_and_verify(
     keys: &Vec1<VerifyingKey>,
     bytes: &[u8],
+) -> anyhow::Result<PartialSignedResponse> {
+    deserialize_and_verify_with_threshold(keys, NonZeroUsize::MIN, bytes)
+}
+
+/// Deserialize arbitrary JSON object with signatures attached, requiring valid signatures by at
+/// least `threshold` distinct keys in `keys`. Signatures by unknown keys or using unrecognized
+/// algorithms are ignored, but an invalid signature by a trusted key is rejected.
+/// WARNING: This only verifies the signatures, not expiration.
+///
+/// On success, this returns verified data and signatures
+pub(super) fn deserialize_and_verify_with_threshold(
+    keys: &Vec1<VerifyingKey>,
+    threshold: NonZeroUsize,
+    bytes: &[u8],
 ) -> anyhow::Result<PartialSignedResponse> {
     let partial_data: PartialSignedResponse =
         serde_json::from_slice(bytes).context("Invalid version JSON")?;
 
     let valid_keys: Vec<_> = keys.into_iter().map(|k| k.0).collect();
 
-    // Check if one of the keys matches
-    let Some((key, sig)) = partial_data.signatures.iter().find_map(|sig| match sig {
-        // Check if ed25519 key matches
-        ResponseSignature::Ed25519 { keyid, sig } if valid_keys.contains(&keyid.0) => {
-            Some((keyid, sig))
-        }
-        // Ignore all non-matching key
-        _ => None,
-    }) else {
-        anyhow::bail!("Unrecognized key");
-    };
-
     // Serialize to canonical json format
     let canon_data = json_canon::to_vec(&partial_data.signed)
         .context("Failed to serialize to canonical JSON")?;
 
-    // Check if the data is signed by our key
-    key.0
-        .verify_strict(&canon_data, &sig.0)
-        .context("Signature verification failed")?;
+    // Distinct trusted keys that have signed the data
+    let mut signed_by = vec![];
+
+    for signature in &partial_data.signatures {
+        match signature {
+            // Check if ed25519 key matches
+            ResponseSignature::Ed25519 { keyid, sig } if valid_keys.contains(&keyid.0) => {
+                // Check if the data is signed by our key
+                keyid
+                    .0
+                    .verify_strict(&canon_data, &sig.0)
+                    .with_context(|| format!("Signature verification failed for key {keyid}"))?;
+                if !signed_by.contains(&keyid.0) {
+                    signed_by.push(keyid.0);
+                }
+            }
+            // Ignore all non-matching keys and unrecognized algorithms
+            _ => (),
+        }
+    }
+
+    if signed_by.is_empty() {
+        anyhow::bail!("Unrecognized key");
+    }
+    if signed_by.len() < threshold.get() {
+        anyhow::bail!(
+            "Signed by {} trusted keys, but at least {threshold} are required",
+            signed_by.len()
+        );
+    }
 
     Ok(PartialSignedResponse {
         signatures: partial_data.signatures,
@@ -134,6 +176,8 @@ pub(super) fn deserialize_and_verify(
 mod test {
     use std::str::FromStr;
 
+    use rand::RngCore;
+    use serde_json::json;
     use vec1::vec1;
 
     use super::*;
@@ -147,6 +191,7 @@ mod test {
 
         SignedResponse::deserialize_and_verify_at_time(
             &vec1![VerifyingKey(verifying_key)],
+            NonZeroUsize::MIN,
             include_bytes!("../../test-version-response.json"),
             // It's 1970 again
             chrono::DateTime::UNIX_EPOCH,
@@ -158,6 +203,7 @@ mod test {
         // Reject expired data
         SignedResponse::deserialize_and_verify_at_time(
             &vec1![VerifyingKey(verifying_key)],
+            NonZeroUsize::MIN,
             include_bytes!("../../test-version-response.json"),
             // In the year 3000
             chrono::DateTime::from_str("3000-01-01T00:00:00Z").unwrap(),
@@ -169,6 +215,7 @@ mod test {
         // Reject expired version number
         SignedResponse::deserialize_and_verify_at_time(
             &vec1![VerifyingKey(verifying_key)],
+            NonZeroUsize::MIN,
             include_bytes!("../../test-version-response.json"),
             chrono::DateTime::UNIX_EPOCH,
             usize::MAX,
@@ -224,4 +271,96 @@ mod test {
             response.signatures[1]
         );
     }
+
+    /// Test that signatures using unrecognized algorithms are ignored, and that the threshold of
+    /// distinct trusted keys is enforced
+    #[test]
+    fn test_mixed_algorithm_signatures() {
+        let key1 = random_secret_key();
+        let key2 = random_secret_key();
+        let untrusted_key = random_secret_key();
+        let trusted = vec1![key1.pubkey(), key2.pubkey()];
+
+        let two = NonZeroUsize::new(2).unwrap();
+        let three = NonZeroUsize::new(3).unwrap();
+
+        // Both trusted keys and an unrecognized algorithm
+        let bytes = signed_bytes(&[&key1, &key2, &untrusted_key]);
+        deserialize_and_verify_with_threshold(&trusted, NonZeroUsize::MIN, &bytes)
+            .expect("one trusted signature is enough");
+        deserialize_and_verify_with_threshold(&trusted, two, &bytes)
+            .expect("two trusted signatures are enough");
+        deserialize_and_verify_with_threshold(&trusted, three, &bytes)
+            .expect_err("untrusted and unrecognized signatures must not count");
+
+        // The same key signing twice only counts once
+        let bytes = signed_bytes(&[&key1, &key1]);
+        deserialize_and_verify_with_threshold(&trusted, NonZeroUsize::MIN, &bytes)
+            .expect("one trusted signature is enough");
+        deserialize_and_verify_with_threshold(&trusted, two, &bytes)
+            .expect_err("duplicate signatures must not count");
+
+        // Only signed using an unrecognized algorithm or by untrusted keys
+        let bytes = signed_bytes(&[&untrusted_key]);
+        deserialize_and_verify_with_threshold(&trusted, NonZeroUsize::MIN, &bytes)
+            .expect_err("expected no trusted signatures");
+    }
+
+    /// Test that an invalid signature by a trusted key is rejected, even if the threshold is met
+    #[test]
+    fn test_invalid_trusted_signature() {
+        let key1 = random_secret_key();
+        let key2 = random_secret_key();
+        let trusted = vec1![key1.pubkey(), key2.pubkey()];
+
+        let mut value: serde_json::Value = serde_json::from_slice(&signed_bytes(&[&key1])).unwrap();
+        value["signatures"].as_array_mut().unwrap().push(json!({
+            "keytype": "ed25519",
+            "keyid": key2.pubkey(),
+            "sig": key2.sign(b"some other data"),
+        }));
+        let bytes = serde_json::to_vec(&value).unwrap();
+
+        deserialize_and_verify_with_threshold(&trusted, NonZeroUsize::MIN, &bytes)
+            .expect_err("expected invalid signature to be rejected");
+    }
+
+    fn random_secret_key() -> SecretKey {
+        let mut secret = [0u8; 32];
+        rand::rng().fill_bytes(&mut secret);
+        SecretKey::from_str(&hex::encode(secret)).unwrap()
+    }
+
+    /// Return a version response signed by `keys`, as well as by a key with an unrecognized
+    /// algorithm
+    fn signed_bytes(keys: &[&SecretKey]) -> Vec<u8> {
+        let signed = json!({
+            "metadata_expiry": "3000-01-01T00:00:00Z",
+            "metadata_version": 0,
+            "releases": []
+        });
+        let canon_data = json_canon::to_vec(&signed).unwrap();
+
+        let mut signatures: Vec<_> = keys
+            .iter()
+            .map(|key| {
+                json!({
+                    "keytype": "ed25519",
+                    "keyid": key.pubkey(),
+                    "sig": key.sign(&canon_data),
+                })
+            })
+            .collect();
+        signatures.push(json!({
+            "keytype": "some post-quantum scheme",
+            "keyid": "future key",
+            "sig": "future signature",
+        }));
+
+        serde_json::to_vec(&json!({
+            "signatures": signatures,
+            "signed": signed,
+        }))
+        .unwrap()
+    }
 }
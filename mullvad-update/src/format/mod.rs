@@ -2,14 +2,16 @@
 //! This includes ensuring authenticity and integrity of version metadata, and rejecting expired
 //! metadata. There are also tools for producing new versions.
 //!
-//! Fundamentally, a version object is a JSON object with a `signed` key and a `signature` key.
-//! `signature` contains a public key and an ed25519 signature of `signed` in canonical JSON form.
+//! Fundamentally, a version object is a JSON object with a `signed` key and a `signatures` key.
+//! `signatures` contains a list of signatures of `signed` in canonical JSON form. Each signature
+//! has a `keytype` identifying the signature algorithm, a public key, and the signature itself.
 //! `signed` also contains an `expires` field, which is a timestamp indicating when the object
 //! expires.
 //!
 //! For the deserializer to succeed in deserializing a file, it must verify that the canonicalized
-//! form of `signed` is in fact signed by key/signature in `signature`. It also reads the `expires`
-//! and rejects the file if it has expired.
+//! form of `signed` is in fact signed by at least a threshold number of trusted keys. Signatures
+//! using unrecognized algorithms are ignored. It also reads the `expires` and rejects the file if
+//! it has expired.
 
 pub mod architecture;
 pub mod deserializer;
@@ -33,6 +35,7 @@ mod test {
         let serialized = serde_json::to_value(Release {
             version: "2024.1".parse().unwrap(),
             changelog: "".to_owned(),
+            localized_changelogs: Default::default(),
             installers: vec![],
             rollout: Rollout::complete(),
         })
@@ -52,6 +55,7 @@ mod test {
         let serialized = serde_json::to_value(Release {
             version: "2024.1".parse().unwrap(),
             changelog: "".to_owned(),
+            localized_changelogs: Default::default(),
             installers: vec![],
             rollout,
         })
@@ -67,4 +71,39 @@ mod test {
             })
         );
     }
+
+    #[test]
+    fn test_localized_changelog() {
+        let release = Release {
+            version: "2024.1".parse().unwrap(),
+            changelog: "default".to_owned(),
+            localized_changelogs: [
+                ("sv".to_owned(), "svenska".to_owned()),
+                ("pt".to_owned(), "português".to_owned()),
+                ("pt-BR".to_owned(), "português do Brasil".to_owned()),
+            ]
+            .into(),
+            installers: vec![],
+            rollout: Rollout::complete(),
+        };
+
+        // Exact matches should be preferred
+        assert_eq!(release.changelog_for_locale(Some("sv")), "svenska");
+        assert_eq!(
+            release.changelog_for_locale(Some("pt-BR")),
+            "português do Brasil"
+        );
+
+        // Fall back on the language if there is no exact match
+        assert_eq!(release.changelog_for_locale(Some("pt-PT")), "português");
+        assert_eq!(release.changelog_for_locale(Some("sv_FI")), "svenska");
+
+        // Fall back on the default changelog if the locale is missing
+        assert_eq!(release.changelog_for_locale(Some("de")), "default");
+        assert_eq!(release.changelog_for_locale(None), "default");
+
+        // Localized changelogs should only be serialized if present
+        let serialized = serde_json::to_value(&release).unwrap();
+        assert_eq!(serialized["localized_changelogs"]["sv"], "svenska");
+    }
 }
@@ -1,5 +1,7 @@
 //! App release
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 use super::installer::Installer;
@@ -12,6 +14,11 @@ pub struct Release {
     pub version: mullvad_version::Version,
     /// Changelog entries
     pub changelog: String,
+    /// Translated changelog entries, keyed by locale (such as `sv` or `pt-BR`).
+    /// [Release::changelog] is used for any locale not present here.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub localized_changelogs: BTreeMap<String, String>,
     /// Installer details for different architectures
     pub installers: Vec<Installer>,
     /// Fraction of users that should receive the new version
@@ -20,6 +27,23 @@ pub struct Release {
     pub rollout: Rollout,
 }
 
+impl Release {
+    /// Return the changelog for `locale`. If there is no changelog for that exact locale, the
+    /// changelog for its language is used (e.g. `pt` for `pt-BR`). If neither exists, or `locale`
+    /// is `None`, the default [Release::changelog] is returned.
+    pub fn changelog_for_locale(&self, locale: Option<&str>) -> &str {
+        let Some(locale) = locale else {
+            return &self.changelog;
+        };
+        let language = locale.split(['-', '_']).next().unwrap_or(locale);
+
+        self.localized_changelogs
+            .get(locale)
+            .or_else(|| self.localized_changelogs.get(language))
+            .unwrap_or(&self.changelog)
+    }
+}
+
 impl PartialEq for Release {
     fn eq(&self, other: &Self) -> bool {
         self.version.eq(&other.version)
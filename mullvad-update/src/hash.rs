@@ -8,11 +8,22 @@ use tokio::io::{AsyncRead, AsyncReadExt};
 const BUF_SIZE: usize = 10 * 1024 * 1024;
 
 /// Generate SHA256 checksum for `reader`
-pub async fn checksum(mut reader: impl AsyncRead + Unpin) -> anyhow::Result<[u8; 32]> {
+pub async fn checksum(reader: impl AsyncRead + Unpin) -> anyhow::Result<[u8; 32]> {
+    checksum_with_progress(reader, 0, |_| ()).await
+}
+
+/// Generate SHA256 checksum for `reader`, where `total_size` is the expected number of bytes.
+/// `on_progress` is called with the fraction of `total_size` that has been hashed so far.
+pub async fn checksum_with_progress(
+    mut reader: impl AsyncRead + Unpin,
+    total_size: usize,
+    mut on_progress: impl FnMut(f32),
+) -> anyhow::Result<[u8; 32]> {
     let mut hasher = sha2::Sha256::new();
 
     // Read data into hasher
     let mut buffer = vec![0u8; BUF_SIZE];
+    let mut hashed_n = 0;
     loop {
         let read_n = reader
             .read(&mut buffer)
@@ -23,6 +34,11 @@ pub async fn checksum(mut reader: impl AsyncRead + Unpin) -> anyhow::Result<[u8;
             break;
         }
         hasher.update(&buffer[..read_n]);
+
+        hashed_n += read_n;
+        if total_size > 0 {
+            on_progress((hashed_n as f32 / total_size as f32).min(1.));
+        }
     }
 
     Ok(hasher.finalize().into())
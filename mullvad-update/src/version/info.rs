@@ -57,7 +57,9 @@ impl VersionInfo {
         .filter(|release| release.rollout >= params.rollout)
         // Filter out dev versions
         .filter(|release| !release.version.is_dev())
-        .flat_map(|Release { version, changelog, installers, .. }| {
+        .flat_map(|release| {
+            let changelog = release.changelog_for_locale(params.locale.as_deref()).to_owned();
+            let Release { version, installers, .. } = release;
             if installers.is_empty() && params.allow_empty {
                 // HACK: If there are no installers (e.g. on Linux), return the version anyway
                 return Some(anyhow::Ok(Metadata {
@@ -147,6 +149,7 @@ mod test {
             rollout: FULLY_ROLLED_OUT,
             allow_empty: false,
             lowest_metadata_version: 0,
+            locale: None,
         };
 
         // Expect: The available latest versions for X86, where the rollout is 1.
@@ -167,6 +170,7 @@ mod test {
             rollout: SUPPORTED_VERSION,
             allow_empty: false,
             lowest_metadata_version: 0,
+            locale: None,
         };
 
         let info = VersionInfo::try_from_response(&params, response.signed)?;
@@ -187,6 +191,7 @@ mod test {
             rollout: SUPPORTED_VERSION,
             allow_empty: true,
             lowest_metadata_version: 0,
+            locale: None,
         };
 
         let info = VersionInfo::try_from_response(&params, response.signed)?;
@@ -207,6 +212,7 @@ mod test {
             rollout: SUPPORTED_VERSION,
             allow_empty: true,
             lowest_metadata_version: 0,
+            locale: None,
         };
 
         let info = VersionInfo::try_from_response(&params, response.signed.clone())?;
@@ -219,6 +225,7 @@ mod test {
             rollout: IGNORE,
             allow_empty: true,
             lowest_metadata_version: 0,
+            locale: None,
         };
 
         let info = VersionInfo::try_from_response(&params, response.signed)?;
@@ -12,6 +12,9 @@ pub struct VersionParameters {
     /// Lowest allowed `metadata_version` in the version data
     /// Typically the current version plus 1
     pub lowest_metadata_version: usize,
+    /// Preferred locale for changelogs, such as `sv` or `pt-BR`. If `None`, or if there is no
+    /// changelog for the locale, the default changelog is used.
+    pub locale: Option<String>,
 }
 
 /// Installer architecture
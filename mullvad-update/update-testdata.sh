@@ -1,6 +1,7 @@
 #!/usr/bin/env bash
 
-# This script updates ./test-version-response.json by signing ./unsigned-response.json.
+# This script updates ./test-version-response.json by signing ./unsigned-response.json, and
+# ./test-local-installer-metadata.json by signing ./unsigned-local-installer-metadata.json.
 # The JSON data is used by several unit tests.
 
 set -eu
@@ -19,4 +20,8 @@ echo "pubkey: $pubkey"
 cargo r --bin mullvad-version-metadata --features sign --features client \
     sign --file ./unsigned-response.json --secret $secret > test-version-response.json
 
+cargo r --bin mullvad-version-metadata --features sign --features client \
+    sign --file ./unsigned-local-installer-metadata.json --secret $secret \
+    > test-local-installer-metadata.json
+
 echo -n "$pubkey" > test-pubkey
@@ -1,4 +1,7 @@
 use super::{FirewallArguments, FirewallPolicy};
+use talpid_types::firewall::FirewallBackend;
+
+pub const BACKEND: FirewallBackend = FirewallBackend::None;
 
 /// Stub error type for Firewall errors on Android.
 #[derive(Debug, thiserror::Error)]
@@ -17,6 +20,10 @@ impl Firewall {
         Ok(Firewall)
     }
 
+    pub fn limitations(&self) -> Vec<String> {
+        vec![]
+    }
+
     pub fn apply_policy(&mut self, _policy: FirewallPolicy) -> Result<(), Error> {
         Ok(())
     }
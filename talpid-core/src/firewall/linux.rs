@@ -15,6 +15,7 @@ use std::{
 };
 use talpid_cgroup::v2::CGroup2;
 use talpid_tunnel::TunnelMetadata;
+use talpid_types::firewall::FirewallBackend;
 use talpid_types::net::{
     ALLOWED_LAN_MULTICAST_NETS, ALLOWED_LAN_NETS, AllowedEndpoint, AllowedTunnelTraffic, Endpoint,
     TransportProtocol,
@@ -29,6 +30,8 @@ const PROC_SYS_NET_IPV4_CONF_ARP_IGNORE: &str = "/proc/sys/net/ipv4/conf/all/arp
 /// Results returned by functions interacting with Linux netfilter.
 pub type Result<T> = std::result::Result<T, Error>;
 
+pub const BACKEND: FirewallBackend = FirewallBackend::Nftables;
+
 /// Errors that can happen when interacting with Linux netfilter.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -141,6 +144,15 @@ impl Firewall {
         })
     }
 
+    /// Return problems with the firewall configuration that prevent it from working as intended.
+    pub fn limitations(&self) -> Vec<String> {
+        if self.excluded_cgroup2.is_none() && self.net_cls.is_none() {
+            vec!["No cgroup is available, so traffic cannot be excluded from the tunnel".to_owned()]
+        } else {
+            vec![]
+        }
+    }
+
     /// Apply a [`FirewallPolicy`] by setting up [`TABLE_NAME`] nftable.
     pub fn apply_policy(&mut self, policy: FirewallPolicy) -> Result<()> {
         let table = Table::new(TABLE_NAME, ProtoFamily::Inet);
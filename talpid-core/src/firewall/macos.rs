@@ -14,6 +14,7 @@ use talpid_types::net::{
 };
 
 use super::{FirewallArguments, FirewallPolicy};
+use talpid_types::firewall::FirewallBackend;
 
 pub use pfctl::Error;
 
@@ -23,6 +24,8 @@ type Result<T> = std::result::Result<T, Error>;
 /// replaced by allowing the anchor name to be configured from the public API of this crate.
 const ANCHOR_NAME: &str = "mullvad";
 
+pub const BACKEND: FirewallBackend = FirewallBackend::Pf;
+
 /// If NAT firewall rules should be applied to force Apple services through the tunnel.
 ///
 /// macOS versions 14.6 <= x < 15.1 were affected by a bug where Apple services tried to bypass the
@@ -56,6 +59,10 @@ impl Firewall {
         Self::new()
     }
 
+    pub fn limitations(&self) -> Vec<String> {
+        vec![]
+    }
+
     pub fn new() -> Result<Self> {
         // Allows controlling whether firewall rules should log to pflog0. Useful for debugging the
         // rules. The firewall rules can be inspected by running `tcpdump -netttti pflog0`.
@@ -428,6 +435,18 @@ impl Firewall {
                         &mut self.get_allow_local_dns_rules_when_connected(tunnel, *server)?,
                     );
                 }
+                for split_config in dns_config.split_config() {
+                    for server in &split_config.tunnel_config {
+                        rules.append(
+                            &mut self.get_allow_tunnel_dns_rules_when_connected(tunnel, *server)?,
+                        );
+                    }
+                    for server in &split_config.non_tunnel_config {
+                        rules.append(
+                            &mut self.get_allow_local_dns_rules_when_connected(tunnel, *server)?,
+                        );
+                    }
+                }
 
                 for peer in peer_endpoints {
                     rules.push(self.get_allow_relay_rule(peer)?);
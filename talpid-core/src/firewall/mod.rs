@@ -2,11 +2,16 @@ use ipnetwork::{IpNetwork, Ipv4Network, Ipv6Network};
 use std::{
     fmt,
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::{Arc, Mutex},
 };
 #[cfg(not(target_os = "android"))]
 use talpid_dns::ResolvedDnsConfig;
 use talpid_tunnel::TunnelMetadata;
-use talpid_types::net::{ALLOWED_LAN_NETS, AllowedEndpoint, AllowedTunnelTraffic};
+use talpid_types::{
+    ErrorExt,
+    firewall::FirewallInfo,
+    net::{ALLOWED_LAN_NETS, AllowedEndpoint, AllowedTunnelTraffic},
+};
 
 cfg_if::cfg_if! {
     if #[cfg(target_os = "windows")] {
@@ -304,6 +309,42 @@ impl fmt::Display for FirewallPolicy {
 /// by manipulating the OS firewall and DNS settings.
 pub struct Firewall {
     inner: imp::Firewall,
+    info: FirewallInfoHandle,
+}
+
+/// Handle for reading the state of a [Firewall] from other threads.
+#[derive(Clone)]
+pub struct FirewallInfoHandle {
+    limitations: Vec<String>,
+    /// Error from the most recent attempt to apply or reset a policy, if it failed
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl FirewallInfoHandle {
+    fn new(firewall: &imp::Firewall) -> Self {
+        Self {
+            limitations: firewall.limitations(),
+            last_error: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn set_result<T>(&self, result: &Result<T, Error>) {
+        *self.last_error.lock().unwrap() = result.as_ref().err().map(|error| error.display_chain());
+    }
+
+    /// Return the backend and any known problems with the firewall.
+    pub fn info(&self) -> FirewallInfo {
+        let mut degraded = self.limitations.clone();
+        if let Some(error) = &*self.last_error.lock().unwrap() {
+            degraded.push(format!(
+                "The last firewall policy could not be applied: {error}"
+            ));
+        }
+        FirewallInfo {
+            backend: imp::BACKEND,
+            degraded,
+        }
+    }
 }
 
 /// Arguments required when first initializing the firewall.
@@ -329,9 +370,7 @@ pub enum InitialFirewallState {
 impl Firewall {
     /// Creates a firewall instance with the given arguments.
     pub fn from_args(args: FirewallArguments) -> Result<Self, Error> {
-        Ok(Firewall {
-            inner: imp::Firewall::from_args(args)?,
-        })
+        Ok(Self::from_inner(imp::Firewall::from_args(args)?))
     }
 
     /// Createsa new firewall instance.
@@ -340,30 +379,42 @@ impl Firewall {
         #[cfg(target_os = "linux")] excluded_cgroup: Option<CGroup2>,
         #[cfg(target_os = "linux")] net_cls: Option<u32>,
     ) -> Result<Self, Error> {
-        Ok(Firewall {
-            inner: imp::Firewall::new(
-                #[cfg(target_os = "linux")]
-                fwmark,
-                #[cfg(target_os = "linux")]
-                excluded_cgroup,
-                #[cfg(target_os = "linux")]
-                net_cls,
-            )?,
-        })
+        Ok(Self::from_inner(imp::Firewall::new(
+            #[cfg(target_os = "linux")]
+            fwmark,
+            #[cfg(target_os = "linux")]
+            excluded_cgroup,
+            #[cfg(target_os = "linux")]
+            net_cls,
+        )?))
+    }
+
+    fn from_inner(inner: imp::Firewall) -> Self {
+        let info = FirewallInfoHandle::new(&inner);
+        Firewall { inner, info }
+    }
+
+    /// Returns a handle for reading the state of the firewall.
+    pub fn info_handle(&self) -> FirewallInfoHandle {
+        self.info.clone()
     }
 
     /// Applies and starts enforcing the given `FirewallPolicy` Makes sure it is being kept in place
     /// until this method is called again with another policy, or until `reset_policy` is called.
     pub fn apply_policy(&mut self, policy: FirewallPolicy) -> Result<(), Error> {
         log::info!("Applying firewall policy: {}", policy);
-        self.inner.apply_policy(policy)
+        let result = self.inner.apply_policy(policy);
+        self.info.set_result(&result);
+        result
     }
 
     /// Resets/removes any currently enforced `FirewallPolicy`. Returns the system to the same state
     /// it had before any policy was applied through this `Firewall` instance.
     pub fn reset_policy(&mut self) -> Result<(), Error> {
         log::info!("Resetting firewall policy");
-        self.inner.reset_policy()
+        let result = self.inner.reset_policy();
+        self.info.set_result(&result);
+        result
     }
 
     /// Sets whether the firewall should persist the blocking rules across a reboot.
@@ -11,6 +11,7 @@ use widestring::WideCString;
 use self::winfw::*;
 use super::{FirewallArguments, FirewallPolicy, InitialFirewallState};
 use talpid_dns::ResolvedDnsConfig;
+use talpid_types::firewall::FirewallBackend;
 
 #[macro_use] // must come before other mod declarations
 mod ffi;
@@ -20,6 +21,8 @@ mod winfw;
 
 const HYPERV_LEAK_WARNING_MSG: &str = "Hyper-V (e.g. WSL machines) may leak in blocked states.";
 
+pub const BACKEND: FirewallBackend = FirewallBackend::Wfp;
+
 // `COMLibrary` must be initialized for per thread, so use TLS
 thread_local! {
     static WMI: Option<wmi::WMIConnection> = {
@@ -105,6 +108,17 @@ impl Firewall {
         Ok(Firewall::default())
     }
 
+    /// Return problems with the firewall configuration that prevent it from working as intended.
+    /// Note that this connects to WMI on the calling thread, if it has not done so already.
+    pub fn limitations(&self) -> Vec<String> {
+        let hyperv_available = *BLOCK_HYPERV && WMI.with(|wmi| wmi.is_some());
+        if hyperv_available {
+            vec![]
+        } else {
+            vec![HYPERV_LEAK_WARNING_MSG.to_owned()]
+        }
+    }
+
     fn initialize_blocked(
         allowed_endpoint: AllowedEndpoint,
         allow_lan: bool,
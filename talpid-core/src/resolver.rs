@@ -192,6 +192,8 @@ enum Config {
     Forwarding {
         /// Remote DNS server to use
         dns_servers: Vec<IpAddr>,
+        /// Remote DNS servers to use for specific domains and their subdomains
+        split_dns_servers: Vec<(String, Vec<IpAddr>)>,
         /// Whether to give an empty response to AAAA queries
         filter_out_aaaa: bool,
     },
@@ -204,6 +206,8 @@ enum Resolver {
     /// Forward DNS queries to a configured server
     Forwarding {
         resolver: Box<TokioResolver>,
+        /// Resolvers to use instead of `resolver` for domains and their subdomains
+        split_resolvers: Vec<(LowerName, TokioResolver)>,
         filter_out_aaaa: bool,
     },
 }
@@ -220,12 +224,20 @@ impl Resolver {
             }
             Resolver::Forwarding {
                 resolver,
+                split_resolvers,
                 filter_out_aaaa,
             } => {
-                let resolver = resolver.clone();
+                // The most specific domain that matches decides the resolver
+                let resolver = split_resolvers
+                    .iter()
+                    .filter(|(domain, _)| domain.zone_of(query.name()))
+                    .max_by_key(|(domain, _)| domain.num_labels())
+                    .map(|(_, resolver)| resolver)
+                    .unwrap_or(resolver.as_ref())
+                    .clone();
                 let filter_out_aaaa = *filter_out_aaaa && !*NEVER_FILTER_AAAA_QUERIES;
                 tokio::spawn(async move {
-                    let lookup = Self::resolve_forward(*resolver, query, filter_out_aaaa);
+                    let lookup = Self::resolve_forward(resolver, query, filter_out_aaaa);
                     let _ = tx.send(lookup.await);
                 });
             }
@@ -311,16 +323,25 @@ impl ResolverHandle {
     ///
     /// # Arguments
     ///
+    /// `split_dns_servers`: Servers to forward queries for a domain and its subdomains to instead
+    ///                      of `dns_servers`. The most specific matching domain is used.
+    ///
     /// `filter_out_aaaa`: This causes the resolver to always return empty responses for AAAA (IPv6)
     ///                    queries. This is useful on macOS when the primary interface has IPv6
     ///                    connectivity, but the VPN tunnel does not. When this is true, and the VPN
     ///                    tunnel lacks IPv6 connectivity, programs like Firefox will resolve IPv6
     ///                    addresses and may attempt to connect to them anyway (but fail).
-    pub async fn enable_forward(&self, dns_servers: Vec<IpAddr>, filter_out_aaaa: bool) {
+    pub async fn enable_forward(
+        &self,
+        dns_servers: Vec<IpAddr>,
+        split_dns_servers: Vec<(String, Vec<IpAddr>)>,
+        filter_out_aaaa: bool,
+    ) {
         let (response_tx, response_rx) = oneshot::channel();
         let _ = self.tx.unbounded_send(ResolverMessage::SetConfig {
             new_config: Config::Forwarding {
                 dns_servers,
+                split_dns_servers,
                 filter_out_aaaa,
             },
             response_tx,
@@ -575,11 +596,15 @@ impl LocalResolver {
             Config::Blocking => self.blocking(),
             Config::Forwarding {
                 mut dns_servers,
+                mut split_dns_servers,
                 filter_out_aaaa,
             } => {
                 // make sure not to accidentally forward queries to ourselves
                 dns_servers.retain(|addr| *addr != self.bound_to.ip());
-                self.forwarding(dns_servers, filter_out_aaaa);
+                for (_, servers) in &mut split_dns_servers {
+                    servers.retain(|addr| *addr != self.bound_to.ip());
+                }
+                self.forwarding(dns_servers, split_dns_servers, filter_out_aaaa);
             }
         }
     }
@@ -589,23 +614,42 @@ impl LocalResolver {
         self.inner_resolver = Resolver::Blocking;
     }
 
-    /// Turn into a forwarding resolver (forward DNS queries to [dns_servers]).
-    fn forwarding(&mut self, dns_servers: Vec<IpAddr>, filter_out_aaaa: bool) {
+    /// Turn into a forwarding resolver (forward DNS queries to [dns_servers], or to the servers
+    /// for the domain in [split_dns_servers]).
+    fn forwarding(
+        &mut self,
+        dns_servers: Vec<IpAddr>,
+        split_dns_servers: Vec<(String, Vec<IpAddr>)>,
+        filter_out_aaaa: bool,
+    ) {
+        let split_resolvers = split_dns_servers
+            .into_iter()
+            .filter_map(|(domain, servers)| match Name::from_str(&domain) {
+                Ok(name) => Some((LowerName::from(name), Self::forwarding_resolver(&servers))),
+                Err(error) => {
+                    log::error!("Ignoring split DNS for invalid domain {domain}: {error}");
+                    None
+                }
+            })
+            .collect();
+
+        self.inner_resolver = Resolver::Forwarding {
+            resolver: Box::new(Self::forwarding_resolver(&dns_servers)),
+            split_resolvers,
+            filter_out_aaaa,
+        };
+    }
+
+    fn forwarding_resolver(dns_servers: &[IpAddr]) -> TokioResolver {
         let forward_server_config =
-            NameServerConfigGroup::from_ips_clear(&dns_servers, DNS_PORT, true);
+            NameServerConfigGroup::from_ips_clear(dns_servers, DNS_PORT, true);
 
         let forward_config = ResolverConfig::from_parts(None, vec![], forward_server_config);
         let resolver_opts = ResolverOpts::default();
 
-        let resolver =
-            TokioResolver::builder_with_config(forward_config, TokioConnectionProvider::default())
-                .with_options(resolver_opts)
-                .build();
-
-        self.inner_resolver = Resolver::Forwarding {
-            resolver: Box::new(resolver),
-            filter_out_aaaa,
-        };
+        TokioResolver::builder_with_config(forward_config, TokioConnectionProvider::default())
+            .with_options(resolver_opts)
+            .build()
     }
 }
 
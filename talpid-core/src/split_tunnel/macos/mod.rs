@@ -1,8 +1,9 @@
 use core::fmt;
+use ipnetwork::IpNetwork;
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     path::PathBuf,
-    sync::{Arc, Weak},
+    sync::{Arc, Mutex, Weak},
 };
 use talpid_routing::RouteManagerHandle;
 use talpid_types::{ErrorExt, tunnel::ErrorStateCause};
@@ -24,6 +25,10 @@ pub use tun::VpnInterface;
 /// This is required by the process monitor.
 pub use process::has_full_disk_access;
 
+/// Destinations that traffic from excluded paths may bypass the tunnel for. Traffic from paths
+/// without an entry bypasses the tunnel regardless of its destination.
+pub type AllowedSubnets = HashMap<PathBuf, Vec<IpNetwork>>;
+
 /// Errors caused by split tunneling
 #[derive(Debug, Clone)]
 pub struct Error {
@@ -98,6 +103,7 @@ pub struct SplitTunnel {
     tunnel_tx: Weak<futures::channel::mpsc::UnboundedSender<TunnelCommand>>,
     rx: mpsc::UnboundedReceiver<Message>,
     shutdown_tx: Option<oneshot::Sender<()>>,
+    allowed_subnets: Arc<Mutex<AllowedSubnets>>,
 }
 
 enum Message {
@@ -128,6 +134,7 @@ enum Message {
 #[derive(Clone)]
 pub struct Handle {
     tx: mpsc::UnboundedSender<Message>,
+    allowed_subnets: Arc<Mutex<AllowedSubnets>>,
 }
 
 impl Handle {
@@ -157,6 +164,12 @@ impl Handle {
         result_rx.await.map_err(|_| Error::unavailable())?
     }
 
+    /// Restrict excluded paths to the given destinations. This takes effect immediately, also for
+    /// connections that are already open.
+    pub fn set_allowed_subnets(&self, allowed_subnets: AllowedSubnets) {
+        *self.allowed_subnets.lock().unwrap() = allowed_subnets;
+    }
+
     /// Set VPN tunnel interface
     pub async fn set_tunnel(&self, vpn_interface: VpnInterface) -> Result<(), Error> {
         let (result_tx, result_rx) = oneshot::channel();
@@ -183,16 +196,21 @@ impl SplitTunnel {
         route_manager: RouteManagerHandle,
     ) -> Handle {
         let (tx, rx) = mpsc::unbounded_channel();
+        let allowed_subnets = Arc::new(Mutex::new(AllowedSubnets::new()));
         let split_tunnel = Self {
             state: State::NoExclusions { route_manager },
             tunnel_tx,
             rx,
             shutdown_tx: None,
+            allowed_subnets: allowed_subnets.clone(),
         };
 
         tokio::spawn(Self::run(split_tunnel));
 
-        Handle { tx }
+        Handle {
+            tx,
+            allowed_subnets,
+        }
     }
 
     async fn run(mut self) {
@@ -268,13 +286,15 @@ impl SplitTunnel {
                 return false;
             }
             Message::SetExcludePaths { result_tx, paths } => {
-                let _ = result_tx.send(self.state.set_exclude_paths(paths).await);
+                let allowed_subnets = self.allowed_subnets.clone();
+                let _ = result_tx.send(self.state.set_exclude_paths(paths, allowed_subnets).await);
             }
             Message::SetTunnel {
                 result_tx,
                 vpn_interface,
             } => {
-                let _ = result_tx.send(self.state.set_tunnel(vpn_interface).await);
+                let allowed_subnets = self.allowed_subnets.clone();
+                let _ = result_tx.send(self.state.set_tunnel(vpn_interface, allowed_subnets).await);
             }
             Message::ResetTunnel { result_tx } => {
                 let _ = result_tx.send(self.state.reset_tunnel().await);
@@ -400,14 +420,19 @@ impl State {
 
     /// Set paths to exclude. For a non-empty path, this will initialize split tunneling if a tunnel
     /// device is also set.
-    async fn set_exclude_paths(&mut self, paths: HashSet<PathBuf>) -> Result<(), Error> {
-        self.transition(move |self_| self_.set_exclude_paths_inner(paths))
+    async fn set_exclude_paths(
+        &mut self,
+        paths: HashSet<PathBuf>,
+        allowed_subnets: Arc<Mutex<AllowedSubnets>>,
+    ) -> Result<(), Error> {
+        self.transition(move |self_| self_.set_exclude_paths_inner(paths, allowed_subnets))
             .await
     }
 
     async fn set_exclude_paths_inner(
         mut self,
         paths: HashSet<PathBuf>,
+        allowed_subnets: Arc<Mutex<AllowedSubnets>>,
     ) -> Result<Self, ErrorWithTransition> {
         match self {
             // If there are currently no paths and no process monitor, initialize it
@@ -436,7 +461,7 @@ impl State {
                     route_manager,
                     process,
                 }
-                .set_tunnel_inner(vpn_interface)
+                .set_tunnel_inner(vpn_interface, allowed_subnets)
                 .await
             }
             // If 'paths' is empty, do nothing
@@ -538,14 +563,19 @@ impl State {
     }
 
     /// Update VPN tunnel interface that non-excluded packets are sent on
-    async fn set_tunnel(&mut self, vpn_interface: VpnInterface) -> Result<(), Error> {
-        self.transition(move |self_| self_.set_tunnel_inner(vpn_interface))
+    async fn set_tunnel(
+        &mut self,
+        vpn_interface: VpnInterface,
+        allowed_subnets: Arc<Mutex<AllowedSubnets>>,
+    ) -> Result<(), Error> {
+        self.transition(move |self_| self_.set_tunnel_inner(vpn_interface, allowed_subnets))
             .await
     }
 
     async fn set_tunnel_inner(
         mut self,
         vpn_interface: VpnInterface,
+        allowed_subnets: Arc<Mutex<AllowedSubnets>>,
     ) -> Result<Self, ErrorWithTransition> {
         match self {
             // If split tunneling is already initialized, just update the interfaces
@@ -618,7 +648,13 @@ impl State {
                     Some(vpn_interface.clone()),
                     route_manager.clone(),
                     Box::new(move |packet| {
-                        match states.get_process_status(packet.header.pth_pid) {
+                        let destination = tun::destination_address(packet);
+                        let allowed_subnets = allowed_subnets.lock().unwrap();
+                        match states.get_process_status(
+                            packet.header.pth_pid,
+                            destination,
+                            &allowed_subnets,
+                        ) {
                             ExclusionStatus::Excluded => tun::RoutingDecision::DefaultInterface,
                             ExclusionStatus::Included => tun::RoutingDecision::VpnTunnel,
                             ExclusionStatus::Unknown => {
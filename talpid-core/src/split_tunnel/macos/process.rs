@@ -5,6 +5,7 @@
 //! The module currently relies on the `eslogger` tool to do so, which in turn relies on the
 //! Endpoint Security framework.
 
+use super::AllowedSubnets;
 use either::Either;
 use futures::channel::oneshot;
 use libc::pid_t;
@@ -12,6 +13,7 @@ use serde::{Deserialize, de::Error as _};
 use std::{
     collections::{HashMap, HashSet},
     io,
+    net::IpAddr,
     path::PathBuf,
     process::Stdio,
     sync::{Arc, LazyLock, Mutex},
@@ -342,10 +344,19 @@ impl ProcessStates {
         inner.exclude_paths = paths;
     }
 
-    pub fn get_process_status(&self, pid: pid_t) -> ExclusionStatus {
+    /// Return the exclusion status of traffic from `pid` to `destination`, which is `None` if the
+    /// destination is unknown.
+    pub fn get_process_status(
+        &self,
+        pid: pid_t,
+        destination: Option<IpAddr>,
+        allowed_subnets: &AllowedSubnets,
+    ) -> ExclusionStatus {
         let inner = self.inner.lock().unwrap();
         match inner.processes.get(&pid) {
-            Some(val) if val.is_excluded() => ExclusionStatus::Excluded,
+            Some(val) if val.is_excluded_for(destination, allowed_subnets) => {
+                ExclusionStatus::Excluded
+            }
             Some(_) => ExclusionStatus::Included,
             None => ExclusionStatus::Unknown,
         }
@@ -449,6 +460,22 @@ impl ProcessInfo {
     fn is_excluded(&self) -> bool {
         !self.excluded_by_paths.is_empty()
     }
+
+    /// Return whether traffic to `destination` is excluded. This is the case if any path that
+    /// excludes the process is either unrestricted or allowed to reach `destination`.
+    fn is_excluded_for(
+        &self,
+        destination: Option<IpAddr>,
+        allowed_subnets: &AllowedSubnets,
+    ) -> bool {
+        self.excluded_by_paths
+            .iter()
+            .any(|path| match allowed_subnets.get(path) {
+                Some(subnets) if !subnets.is_empty() => destination
+                    .is_some_and(|destination| subnets.iter().any(|net| net.contains(destination))),
+                _ => true,
+            })
+    }
 }
 
 /// `fork` event details
@@ -785,4 +812,27 @@ mod test {
             "expected 'NeedFda::No' when nothing was ever printed to stdout or stderr"
         );
     }
+
+    /// Check that traffic from a process is only excluded for destinations that none of the
+    /// paths that exclude it are restricted from.
+    #[test]
+    fn test_is_excluded_for() {
+        let restricted = PathBuf::from("/Applications/Restricted.app");
+        let unrestricted = PathBuf::from("/Applications/Unrestricted.app");
+        let allowed_subnets =
+            AllowedSubnets::from([(restricted.clone(), vec!["10.0.0.0/8".parse().unwrap()])]);
+        let inside = Some("10.1.2.3".parse().unwrap());
+        let outside = Some("192.168.1.1".parse().unwrap());
+
+        let mut info = ProcessInfo::included(restricted.clone());
+        assert!(!info.is_excluded_for(inside, &allowed_subnets));
+
+        info.excluded_by_paths.insert(restricted);
+        assert!(info.is_excluded_for(inside, &allowed_subnets));
+        assert!(!info.is_excluded_for(outside, &allowed_subnets));
+        assert!(!info.is_excluded_for(None, &allowed_subnets));
+
+        info.excluded_by_paths.insert(unrestricted);
+        assert!(info.is_excluded_for(outside, &allowed_subnets));
+    }
 }
@@ -14,8 +14,8 @@ use pnet_packet::{
     MutablePacket, Packet,
     ethernet::{EtherTypes, MutableEthernetPacket},
     ip::IpNextHeaderProtocols,
-    ipv4::MutableIpv4Packet,
-    ipv6::MutableIpv6Packet,
+    ipv4::{Ipv4Packet, MutableIpv4Packet},
+    ipv6::{Ipv6Packet, MutableIpv6Packet},
     tcp::MutableTcpPacket,
     udp::MutableUdpPacket,
 };
@@ -93,6 +93,18 @@ pub enum RoutingDecision {
     Drop,
 }
 
+/// Return the destination of an outgoing packet passed to a [ClassifyFn], or `None` if it is not a
+/// valid IP packet. The IP version is read from the IP header, since the Ethernet header may not be
+/// valid.
+pub fn destination_address(packet: &PktapPacket) -> Option<IpAddr> {
+    let payload = packet.frame.payload();
+    match payload.first()? >> 4 {
+        4 => Ipv4Packet::new(payload).map(|ip| IpAddr::V4(ip.get_destination())),
+        6 => Ipv6Packet::new(payload).map(|ip| IpAddr::V6(ip.get_destination())),
+        _ => None,
+    }
+}
+
 /// VPN tunnel interface details
 #[derive(Debug, Clone)]
 pub struct VpnInterface {
@@ -0,0 +1,182 @@
+use std::time::{Duration, Instant};
+use talpid_types::tunnel::ErrorStateCause;
+
+/// Limits on how long and how many times the tunnel state machine retries connecting before it
+/// gives up and enters the error state.
+pub(super) struct ConnectLimits {
+    /// How long to keep retrying to connect before giving up. Zero means indefinitely.
+    pub timeout: Duration,
+    /// How many times to retry connecting before giving up. `None` means indefinitely.
+    pub max_reconnect_attempts: Option<u32>,
+    /// When the first of the current series of connection attempts was made.
+    connecting_since: Option<Instant>,
+}
+
+impl ConnectLimits {
+    pub fn new(timeout: Duration, max_reconnect_attempts: Option<u32>) -> Self {
+        Self {
+            timeout,
+            max_reconnect_attempts,
+            connecting_since: None,
+        }
+    }
+
+    /// Register that connection attempt `retry_attempt` is about to be made at `now`. Attempt `0`
+    /// starts a new series of attempts. Returns why connecting should be given up on, if a limit
+    /// has been reached.
+    pub fn on_attempt(&mut self, retry_attempt: u32, now: Instant) -> Option<ErrorStateCause> {
+        if retry_attempt == 0 {
+            self.connecting_since = None;
+        }
+        let connecting_since = *self.connecting_since.get_or_insert(now);
+
+        let cause = if self
+            .deadline_since(connecting_since)
+            .is_some_and(|deadline| now >= deadline)
+        {
+            log::warn!("Giving up connecting after {:?}", self.timeout);
+            Some(ErrorStateCause::ConnectTimeout)
+        } else if reconnect_limit_reached(retry_attempt, self.max_reconnect_attempts) {
+            // Attempt `retry_attempt` has not been made yet
+            log::warn!(
+                "Giving up connecting after {} reconnect attempts",
+                retry_attempt - 1
+            );
+            Some(ErrorStateCause::ReconnectLimitReached)
+        } else {
+            None
+        };
+        if cause.is_some() {
+            self.reset();
+        }
+        cause
+    }
+
+    /// When to give up on the current series of connection attempts, if ever.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline_since(self.connecting_since?)
+    }
+
+    /// Forget the current series of connection attempts.
+    pub fn reset(&mut self) {
+        self.connecting_since = None;
+    }
+
+    fn deadline_since(&self, connecting_since: Instant) -> Option<Instant> {
+        if self.timeout.is_zero() {
+            return None;
+        }
+        Some(connecting_since + self.timeout)
+    }
+}
+
+/// Return whether `retry_attempt` exceeds the maximum number of reconnect attempts. The first
+/// attempt, `0`, is not a reconnect attempt. A `max_attempts` of `None` is never exceeded.
+fn reconnect_limit_reached(retry_attempt: u32, max_attempts: Option<u32>) -> bool {
+    max_attempts.is_some_and(|max_attempts| retry_attempt > max_attempts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Attempts made before the timeout are allowed, and the first attempt after it gives up.
+    #[test]
+    fn test_connect_timeout() {
+        let timeout = Duration::from_secs(30);
+        let mut limits = ConnectLimits::new(timeout, None);
+        let start = Instant::now();
+
+        assert!(limits.on_attempt(0, start).is_none());
+        assert_eq!(limits.deadline(), Some(start + timeout));
+        assert!(
+            limits
+                .on_attempt(1, start + Duration::from_secs(29))
+                .is_none()
+        );
+        assert!(matches!(
+            limits.on_attempt(2, start + timeout),
+            Some(ErrorStateCause::ConnectTimeout)
+        ));
+        assert_eq!(limits.deadline(), None);
+    }
+
+    /// A new series of attempts gets the whole timeout, even if the previous one did not finish.
+    #[test]
+    fn test_connect_timeout_restarts() {
+        let timeout = Duration::from_secs(30);
+        let mut limits = ConnectLimits::new(timeout, None);
+        let start = Instant::now();
+
+        assert!(limits.on_attempt(0, start).is_none());
+        assert!(
+            limits
+                .on_attempt(1, start + Duration::from_secs(20))
+                .is_none()
+        );
+
+        let restart = start + Duration::from_secs(40);
+        assert!(limits.on_attempt(0, restart).is_none());
+        assert_eq!(limits.deadline(), Some(restart + timeout));
+    }
+
+    /// The state machine gives up instead of making reconnect attempt `max + 1`, and a new series
+    /// of attempts may again make `max` reconnect attempts.
+    #[test]
+    fn test_reconnect_limit() {
+        let mut limits = ConnectLimits::new(Duration::ZERO, Some(3));
+        let now = Instant::now();
+
+        for _ in 0..2 {
+            for attempt in 0..=3 {
+                assert!(limits.on_attempt(attempt, now).is_none());
+            }
+            assert!(matches!(
+                limits.on_attempt(4, now),
+                Some(ErrorStateCause::ReconnectLimitReached)
+            ));
+        }
+    }
+
+    /// No reconnect attempts are made with a limit of zero
+    #[test]
+    fn test_no_reconnect_attempts() {
+        let mut limits = ConnectLimits::new(Duration::ZERO, Some(0));
+        let now = Instant::now();
+
+        assert!(limits.on_attempt(0, now).is_none());
+        assert!(matches!(
+            limits.on_attempt(1, now),
+            Some(ErrorStateCause::ReconnectLimitReached)
+        ));
+    }
+
+    #[test]
+    fn test_reconnect_limit_reached() {
+        let max_attempts = Some(3);
+
+        // The state machine stops before the fourth reconnect attempt
+        let attempts: Vec<u32> = (0..)
+            .take_while(|&attempt| !reconnect_limit_reached(attempt, max_attempts))
+            .collect();
+        assert_eq!(attempts, [0, 1, 2, 3]);
+
+        assert!(reconnect_limit_reached(1, Some(0)));
+        assert!(!reconnect_limit_reached(0, Some(0)));
+        assert!(!reconnect_limit_reached(u32::MAX, None));
+    }
+
+    #[test]
+    fn test_no_connect_timeout() {
+        let mut limits = ConnectLimits::new(Duration::ZERO, None);
+        let start = Instant::now();
+
+        assert!(limits.on_attempt(0, start).is_none());
+        assert_eq!(limits.deadline(), None);
+        assert!(
+            limits
+                .on_attempt(1, start + Duration::from_secs(60 * 60))
+                .is_none()
+        );
+    }
+}
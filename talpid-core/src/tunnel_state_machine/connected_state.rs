@@ -182,11 +182,18 @@ impl ConnectedState {
 
             // Tell local DNS resolver to start forwarding DNS queries to whatever `dns_config`
             // specifies as DNS.
-            shared_values.runtime.block_on(
-                shared_values
-                    .filtering_resolver
-                    .enable_forward(dns_config.addresses().collect(), filter_out_aaaa),
-            );
+            let split_dns_servers = dns_config
+                .split_config()
+                .iter()
+                .map(|config| (config.domain.clone(), config.addresses().collect()))
+                .collect();
+            shared_values
+                .runtime
+                .block_on(shared_values.filtering_resolver.enable_forward(
+                    dns_config.addresses().collect(),
+                    split_dns_servers,
+                    filter_out_aaaa,
+                ));
         }
 
         Ok(())
@@ -327,6 +334,14 @@ impl ConnectedState {
                 let _ = complete_tx.send(());
                 SameState(self)
             }
+            Some(TunnelCommand::ConnectTimeout(timeout)) => {
+                shared_values.connect_limits.timeout = timeout;
+                SameState(self)
+            }
+            Some(TunnelCommand::MaxReconnectAttempts(max_attempts)) => {
+                shared_values.connect_limits.max_reconnect_attempts = max_attempts;
+                SameState(self)
+            }
             Some(TunnelCommand::Connectivity(connectivity)) => {
                 shared_values.connectivity = connectivity;
                 if connectivity.is_offline() {
@@ -392,6 +407,11 @@ impl ConnectedState {
                 }
                 SameState(self)
             }
+            #[cfg(target_os = "macos")]
+            Some(TunnelCommand::SetExcludedAppSubnets(subnets)) => {
+                shared_values.set_exclude_subnets(subnets);
+                SameState(self)
+            }
         }
     }
 
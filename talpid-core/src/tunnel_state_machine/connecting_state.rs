@@ -1,20 +1,21 @@
-use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+#[cfg(target_os = "windows")]
+use std::path::Path;
+use std::sync::Mutex;
 use std::thread;
 use std::time::{Duration, Instant};
 
 use futures::channel::{mpsc, oneshot};
 use futures::future::Fuse;
 use futures::{FutureExt, StreamExt};
-use talpid_routing::RouteManagerHandle;
-use talpid_tunnel::tun_provider::TunProvider;
 use talpid_tunnel::{EventHook, TunnelArgs, TunnelEvent, TunnelMetadata};
 use talpid_types::ErrorExt;
 use talpid_types::net::{
-    AllowedClients, AllowedEndpoint, AllowedTunnelTraffic, wireguard::TunnelParameters,
+    AllowedClients, AllowedEndpoint, AllowedTunnelTraffic,
+    wireguard::{HandshakeDiagnostics, TunnelParameters},
 };
 use talpid_types::tunnel::{ErrorStateCause, FirewallPolicyError};
 
+use super::connect_limits::ConnectLimits;
 use super::connected_state::TunnelEventsReceiver;
 use super::{
     AfterDisconnect, ConnectedState, DisconnectingState, ErrorState, EventConsequence, EventResult,
@@ -75,6 +76,13 @@ impl ConnectingState {
                 });
         }
 
+        if let Some(cause) = shared_values
+            .connect_limits
+            .on_attempt(retry_attempt, Instant::now())
+        {
+            return ErrorState::enter(shared_values, cause);
+        }
+
         let ip_availability = match shared_values.connectivity.availability() {
             Some(ip_availability) => ip_availability,
             // If we're offline, enter the offline state
@@ -140,15 +148,8 @@ impl ConnectingState {
                         }
                     }
 
-                    let connecting_state = Self::start_tunnel(
-                        shared_values.runtime.clone(),
-                        tunnel_parameters,
-                        &shared_values.log_dir,
-                        &shared_values.resource_dir,
-                        shared_values.tun_provider.clone(),
-                        &shared_values.route_manager,
-                        retry_attempt,
-                    );
+                    let connecting_state =
+                        Self::start_tunnel(shared_values, tunnel_parameters, retry_attempt);
 
                     let params = connecting_state.tunnel_parameters.clone();
                     (
@@ -223,20 +224,21 @@ impl ConnectingState {
     }
 
     fn start_tunnel(
-        runtime: tokio::runtime::Handle,
+        shared_values: &SharedTunnelStateValues,
         parameters: TunnelParameters,
-        log_dir: &Option<PathBuf>,
-        resource_dir: &Path,
-        tun_provider: Arc<Mutex<TunProvider>>,
-        route_manager: &RouteManagerHandle,
         retry_attempt: u32,
     ) -> Self {
         let (event_tx, event_rx) = mpsc::unbounded();
         let event_hook = EventHook::new(event_tx);
 
-        let route_manager = route_manager.clone();
-        let log_dir = log_dir.clone();
-        let resource_dir = resource_dir.to_path_buf();
+        let runtime = shared_values.runtime.clone();
+        let route_manager = shared_values.route_manager.clone();
+        let log_dir = shared_values.log_dir.clone();
+        let resource_dir = shared_values.resource_dir.clone();
+        let tun_provider = shared_values.tun_provider.clone();
+        let last_handshake_diagnostics = shared_values.last_handshake_diagnostics.clone();
+        let tunnel_traffic = shared_values.tunnel_traffic.clone();
+        let applied_allowed_ips = shared_values.applied_allowed_ips.clone();
 
         let (tunnel_close_tx, tunnel_close_rx) = oneshot::channel();
         let (tunnel_close_event_tx, tunnel_close_event_rx) = oneshot::channel();
@@ -257,6 +259,8 @@ impl ConnectingState {
                 tun_provider,
                 retry_attempt,
                 route_manager,
+                tunnel_traffic,
+                applied_allowed_ips,
             };
 
             #[cfg(target_os = "windows")]
@@ -274,7 +278,11 @@ impl ConnectingState {
 
             let block_reason = match TunnelMonitor::start(&tunnel_parameters, &log_dir, args) {
                 Ok(monitor) => {
-                    let reason = Self::wait_for_tunnel_monitor(monitor, retry_attempt);
+                    let reason = Self::wait_for_tunnel_monitor(
+                        monitor,
+                        &last_handshake_diagnostics,
+                        retry_attempt,
+                    );
                     log::debug!("Tunnel monitor exited with block reason: {:?}", reason);
                     reason
                 }
@@ -327,13 +335,19 @@ impl ConnectingState {
 
     fn wait_for_tunnel_monitor(
         tunnel_monitor: TunnelMonitor,
+        last_handshake_diagnostics: &Mutex<Option<HandshakeDiagnostics>>,
         retry_attempt: u32,
     ) -> Option<ErrorStateCause> {
         match tunnel_monitor.wait() {
             Ok(_) => None,
             Err(error) => match error {
-                tunnel_monitor::Error::TunnelMonitoring(talpid_wireguard::Error::TimeoutError) => {
+                tunnel_monitor::Error::TunnelMonitoring(talpid_wireguard::Error::TimeoutError(
+                    diagnostics,
+                )) => {
                     log::debug!("WireGuard tunnel timed out");
+                    if diagnostics.is_some() {
+                        *last_handshake_diagnostics.lock().unwrap() = diagnostics;
+                    }
                     None
                 }
                 error @ tunnel_monitor::Error::TunnelMonitoring(..)
@@ -471,6 +485,14 @@ impl ConnectingState {
                 let _ = complete_tx.send(());
                 SameState(self)
             }
+            Some(TunnelCommand::ConnectTimeout(timeout)) => {
+                shared_values.connect_limits.timeout = timeout;
+                SameState(self)
+            }
+            Some(TunnelCommand::MaxReconnectAttempts(max_attempts)) => {
+                shared_values.connect_limits.max_reconnect_attempts = max_attempts;
+                SameState(self)
+            }
             Some(TunnelCommand::Connectivity(connectivity)) => {
                 shared_values.connectivity = connectivity;
                 if connectivity.is_offline() {
@@ -541,6 +563,11 @@ impl ConnectingState {
                 }
                 SameState(self)
             }
+            #[cfg(target_os = "macos")]
+            Some(TunnelCommand::SetExcludedAppSubnets(subnets)) => {
+                shared_values.set_exclude_subnets(subnets);
+                SameState(self)
+            }
         }
     }
 
@@ -595,14 +622,17 @@ impl ConnectingState {
                     ),
                 }
             }
-            Some((TunnelEvent::Up(metadata), _)) => NewState(ConnectedState::enter(
-                shared_values,
-                metadata,
-                self.tunnel_events,
-                self.tunnel_parameters,
-                self.tunnel_close_event,
-                self.tunnel_close_tx,
-            )),
+            Some((TunnelEvent::Up(metadata), _)) => {
+                *shared_values.last_handshake_diagnostics.lock().unwrap() = None;
+                NewState(ConnectedState::enter(
+                    shared_values,
+                    metadata,
+                    self.tunnel_events,
+                    self.tunnel_parameters,
+                    self.tunnel_close_event,
+                    self.tunnel_close_tx,
+                ))
+            }
             Some((TunnelEvent::Down, _)) => {
                 // It is important to reset this before the tunnel device is down,
                 // or else commands that reapply the firewall rules will fail since
@@ -643,6 +673,41 @@ impl ConnectingState {
             self.retry_attempt + 1,
         ))
     }
+
+    /// Wait for the next command or tunnel event. Returns `None` if `connect_deadline` passes
+    /// first.
+    async fn next_event(
+        commands: &mut TunnelCommandReceiver,
+        tunnel_events: &mut TunnelEventsReceiver,
+        tunnel_close_event: &mut TunnelCloseEvent,
+        connect_deadline: Option<Instant>,
+    ) -> Option<EventResult> {
+        let connect_timeout = async {
+            match connect_deadline {
+                Some(deadline) => {
+                    tokio::time::sleep_until(tokio::time::Instant::from_std(deadline)).await
+                }
+                None => futures::future::pending().await,
+            }
+        }
+        .fuse();
+        futures::pin_mut!(connect_timeout);
+
+        futures::select! {
+            command = commands.next() => Some(EventResult::Command(command)),
+            event = tunnel_events.next() => Some(EventResult::Event(event)),
+            result = tunnel_close_event => Some(EventResult::Close(result)),
+            () = connect_timeout => None,
+        }
+    }
+
+    /// Give up on the current series of connection attempts because the connect timeout has
+    /// passed. Returns what to do once the tunnel has been closed.
+    fn give_up(connect_limits: &mut ConnectLimits) -> AfterDisconnect {
+        log::warn!("Giving up connecting after {:?}", connect_limits.timeout);
+        connect_limits.reset();
+        AfterDisconnect::Block(ErrorStateCause::ConnectTimeout)
+    }
 }
 
 #[cfg_attr(not(target_os = "windows"), expect(unused_variables))]
@@ -661,13 +726,19 @@ impl TunnelState for ConnectingState {
         commands: &mut TunnelCommandReceiver,
         shared_values: &mut SharedTunnelStateValues,
     ) -> EventConsequence {
-        let result = runtime.block_on(async {
-            futures::select! {
-                command = commands.next() => EventResult::Command(command),
-                event = self.tunnel_events.next() => EventResult::Event(event),
-                result = &mut self.tunnel_close_event => EventResult::Close(result),
-            }
-        });
+        let connect_deadline = shared_values.connect_limits.deadline();
+        let result = runtime.block_on(Self::next_event(
+            commands,
+            &mut self.tunnel_events,
+            &mut self.tunnel_close_event,
+            connect_deadline,
+        ));
+
+        // A single attempt may take longer than the connect timeout
+        let Some(result) = result else {
+            let after_disconnect = Self::give_up(&mut shared_values.connect_limits);
+            return self.disconnect(shared_values, after_disconnect);
+        };
 
         match result {
             EventResult::Command(command) => self.handle_commands(command, shared_values),
@@ -682,3 +753,79 @@ impl TunnelState for ConnectingState {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Channels {
+        commands_tx: mpsc::UnboundedSender<TunnelCommand>,
+        commands: TunnelCommandReceiver,
+        _tunnel_events_tx: mpsc::UnboundedSender<(TunnelEvent, oneshot::Sender<()>)>,
+        tunnel_events: TunnelEventsReceiver,
+        _tunnel_close_event_tx: oneshot::Sender<Option<ErrorStateCause>>,
+        tunnel_close_event: TunnelCloseEvent,
+    }
+
+    fn channels() -> Channels {
+        let (commands_tx, commands) = mpsc::unbounded();
+        let (tunnel_events_tx, tunnel_events) = mpsc::unbounded();
+        let (tunnel_close_event_tx, tunnel_close_event) = oneshot::channel();
+        Channels {
+            commands_tx,
+            commands: commands.fuse(),
+            _tunnel_events_tx: tunnel_events_tx,
+            tunnel_events: tunnel_events.fuse(),
+            _tunnel_close_event_tx: tunnel_close_event_tx,
+            tunnel_close_event: tunnel_close_event.fuse(),
+        }
+    }
+
+    /// An attempt that is still in progress when the connect timeout expires is given up on, and
+    /// the state machine enters the error state once the tunnel is closed.
+    #[tokio::test(start_paused = true)]
+    async fn test_connect_timeout_expires() {
+        let mut channels = channels();
+        let mut limits = ConnectLimits::new(Duration::from_secs(30), None);
+        assert!(limits.on_attempt(0, Instant::now()).is_none());
+
+        let result = ConnectingState::next_event(
+            &mut channels.commands,
+            &mut channels.tunnel_events,
+            &mut channels.tunnel_close_event,
+            limits.deadline(),
+        )
+        .await;
+        assert!(result.is_none());
+
+        assert!(matches!(
+            ConnectingState::give_up(&mut limits),
+            AfterDisconnect::Block(ErrorStateCause::ConnectTimeout)
+        ));
+        assert_eq!(limits.deadline(), None);
+    }
+
+    /// Events are handled as usual before the connect timeout expires
+    #[tokio::test(start_paused = true)]
+    async fn test_event_before_connect_timeout() {
+        let mut channels = channels();
+        let mut limits = ConnectLimits::new(Duration::from_secs(30), None);
+        assert!(limits.on_attempt(0, Instant::now()).is_none());
+
+        channels
+            .commands_tx
+            .unbounded_send(TunnelCommand::Connect)
+            .unwrap();
+        let result = ConnectingState::next_event(
+            &mut channels.commands,
+            &mut channels.tunnel_events,
+            &mut channels.tunnel_close_event,
+            limits.deadline(),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Some(EventResult::Command(Some(TunnelCommand::Connect)))
+        ));
+    }
+}
@@ -227,6 +227,14 @@ impl TunnelState for DisconnectedState {
                     SameState(self)
                 }
             }
+            Some(TunnelCommand::ConnectTimeout(timeout)) => {
+                shared_values.connect_limits.timeout = timeout;
+                SameState(self)
+            }
+            Some(TunnelCommand::MaxReconnectAttempts(max_attempts)) => {
+                shared_values.connect_limits.max_reconnect_attempts = max_attempts;
+                SameState(self)
+            }
             Some(TunnelCommand::Connectivity(connectivity)) => {
                 shared_values.connectivity = connectivity;
                 SameState(self)
@@ -254,6 +262,11 @@ impl TunnelState for DisconnectedState {
                 let _ = result_tx.send(shared_values.set_exclude_paths(paths).map(|_| ()));
                 SameState(self)
             }
+            #[cfg(target_os = "macos")]
+            Some(TunnelCommand::SetExcludedAppSubnets(subnets)) => {
+                shared_values.set_exclude_subnets(subnets);
+                SameState(self)
+            }
             None => {
                 Self::reset_dns(shared_values);
                 Finished
@@ -55,6 +55,12 @@ impl DisconnectingState {
                 shared_values.lockdown_mode = lockdown_mode;
                 let _ = complete_tx.send(());
             }
+            Some(TunnelCommand::ConnectTimeout(timeout)) => {
+                shared_values.connect_limits.timeout = timeout;
+            }
+            Some(TunnelCommand::MaxReconnectAttempts(max_attempts)) => {
+                shared_values.connect_limits.max_reconnect_attempts = max_attempts;
+            }
             Some(TunnelCommand::Connectivity(connectivity)) => {
                 shared_values.connectivity = connectivity;
 
@@ -109,6 +115,10 @@ impl DisconnectingState {
             Some(TunnelCommand::SetExcludedApps(result_tx, paths)) => {
                 let _ = result_tx.send(shared_values.set_exclude_paths(paths).map(|_| ()));
             }
+            #[cfg(target_os = "macos")]
+            Some(TunnelCommand::SetExcludedAppSubnets(subnets)) => {
+                shared_values.set_exclude_subnets(subnets);
+            }
         };
 
         EventConsequence::SameState(self)
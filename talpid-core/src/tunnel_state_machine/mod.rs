@@ -1,3 +1,4 @@
+mod connect_limits;
 mod connected_state;
 mod connecting_state;
 mod disconnected_state;
@@ -6,6 +7,7 @@ mod error_state;
 mod tunnel_monitor;
 
 use self::{
+    connect_limits::ConnectLimits,
     connected_state::ConnectedState,
     connecting_state::ConnectingState,
     disconnected_state::DisconnectedState,
@@ -15,10 +17,11 @@ use self::{
 #[cfg(any(windows, target_os = "android", target_os = "macos"))]
 use crate::split_tunnel;
 use crate::{
-    firewall::{Firewall, FirewallArguments, InitialFirewallState},
+    firewall::{Firewall, FirewallArguments, FirewallInfoHandle, InitialFirewallState},
     mpsc::Sender,
     offline,
 };
+use ipnetwork::IpNetwork;
 #[cfg(any(target_os = "windows", target_os = "macos"))]
 use std::ffi::OsString;
 #[cfg(target_os = "linux")]
@@ -49,7 +52,11 @@ use std::{
 #[cfg(target_os = "android")]
 use talpid_types::{ErrorExt, android::AndroidContext};
 use talpid_types::{
-    net::{AllowedEndpoint, Connectivity, IpAvailability, wireguard::TunnelParameters},
+    firewall::FirewallInfo,
+    net::{
+        AllowedEndpoint, Connectivity, IpAvailability, TunnelTraffic,
+        wireguard::{HandshakeDiagnostics, TunnelParameters},
+    },
     tunnel::{ErrorStateCause, ParameterGenerationError, TunnelStateTransition},
 };
 
@@ -112,6 +119,15 @@ pub struct InitialTunnelState {
     /// Apps to exclude from the tunnel.
     #[cfg(target_os = "android")]
     pub exclude_paths: Vec<String>,
+    /// Destinations that excluded programs are restricted to.
+    #[cfg(target_os = "macos")]
+    pub exclude_subnets: split_tunnel::AllowedSubnets,
+    /// How long to keep retrying to connect before entering the error state. Zero means that
+    /// connecting is retried indefinitely.
+    pub connect_timeout: Duration,
+    /// How many times to retry connecting before entering the error state. `None` means that
+    /// connecting is retried indefinitely.
+    pub max_reconnect_attempts: Option<u32>,
 }
 
 /// Identifiers for various network resources that should be unique to a given instance of a tunnel
@@ -185,6 +201,14 @@ pub async fn spawn(
 
     let state_machine = TunnelStateMachine::new(init_args).await?;
 
+    let last_handshake_diagnostics = state_machine
+        .shared_values
+        .last_handshake_diagnostics
+        .clone();
+    let applied_allowed_ips = state_machine.shared_values.applied_allowed_ips.clone();
+    let tunnel_traffic = state_machine.shared_values.tunnel_traffic.clone();
+    let firewall_info = state_machine.shared_values.firewall.info_handle();
+
     #[cfg(windows)]
     let split_tunnel = state_machine.shared_values.split_tunnel.handle();
 
@@ -198,6 +222,10 @@ pub async fn spawn(
     Ok(TunnelStateMachineHandle {
         command_tx,
         shutdown_rx,
+        last_handshake_diagnostics,
+        applied_allowed_ips,
+        tunnel_traffic,
+        firewall_info,
         #[cfg(windows)]
         split_tunnel,
     })
@@ -217,6 +245,10 @@ pub enum TunnelCommand {
     /// Enable or disable the lockdown_mode feature.
     #[cfg(not(target_os = "android"))]
     LockdownMode(LockdownMode, oneshot::Sender<()>),
+    /// Set how long to keep retrying to connect before entering the error state.
+    ConnectTimeout(Duration),
+    /// Set how many times to retry connecting before entering the error state.
+    MaxReconnectAttempts(Option<u32>),
     /// Notify the state machine of the connectivity of the device.
     Connectivity(Connectivity),
     /// Open tunnel connection.
@@ -240,6 +272,9 @@ pub enum TunnelCommand {
         oneshot::Sender<Result<(), split_tunnel::Error>>,
         Vec<String>,
     ),
+    /// Restrict excluded applications to the given destinations.
+    #[cfg(target_os = "macos")]
+    SetExcludedAppSubnets(split_tunnel::AllowedSubnets),
 }
 
 type TunnelCommandReceiver = stream::Fuse<mpsc::UnboundedReceiver<TunnelCommand>>;
@@ -440,6 +475,8 @@ impl TunnelStateMachine {
             &args.settings.exclude_paths,
         );
 
+        #[cfg(target_os = "macos")]
+        split_tunnel.set_allowed_subnets(args.settings.exclude_subnets);
         #[cfg(target_os = "macos")]
         if let Err(error) = split_tunnel
             .set_exclude_paths(
@@ -481,6 +518,13 @@ impl TunnelStateMachine {
             connectivity_check_was_enabled: None,
             #[cfg(target_os = "macos")]
             filtering_resolver,
+            last_handshake_diagnostics: Arc::new(Mutex::new(None)),
+            applied_allowed_ips: Arc::new(Mutex::new(None)),
+            tunnel_traffic: Arc::new(Mutex::new(TunnelTraffic::default())),
+            connect_limits: ConnectLimits::new(
+                args.settings.connect_timeout,
+                args.settings.max_reconnect_attempts,
+            ),
         };
 
         tokio::task::spawn_blocking(move || {
@@ -507,6 +551,10 @@ impl TunnelStateMachine {
                 NewState((state, transition)) => {
                     self.current_state = Some(state);
 
+                    if !matches!(transition, TunnelStateTransition::Connected(_)) {
+                        *self.shared_values.applied_allowed_ips.lock().unwrap() = None;
+                    }
+
                     if let Err(error) = change_listener
                         .send(transition)
                         .map_err(|_| Error::SendStateChange)
@@ -587,6 +635,18 @@ struct SharedTunnelStateValues {
     /// Filtering resolver handle
     #[cfg(target_os = "macos")]
     filtering_resolver: crate::resolver::ResolverHandle,
+
+    /// Diagnostics for the last tunnel that timed out before becoming functional.
+    last_handshake_diagnostics: Arc<Mutex<Option<HandshakeDiagnostics>>>,
+
+    /// Allowed IPs configured on the tunnel device while connected.
+    applied_allowed_ips: Arc<Mutex<Option<Vec<IpNetwork>>>>,
+
+    /// Total traffic of all tunnels that have been torn down.
+    tunnel_traffic: Arc<Mutex<TunnelTraffic>>,
+
+    /// When to give up connecting and enter the error state.
+    connect_limits: ConnectLimits,
 }
 
 impl SharedTunnelStateValues {
@@ -609,6 +669,11 @@ impl SharedTunnelStateValues {
         })
     }
 
+    #[cfg(target_os = "macos")]
+    pub fn set_exclude_subnets(&mut self, subnets: split_tunnel::AllowedSubnets) {
+        self.split_tunnel.set_allowed_subnets(subnets);
+    }
+
     #[cfg(target_os = "macos")]
     pub fn enable_split_tunnel(
         &mut self,
@@ -790,6 +855,10 @@ trait TunnelState: Send {
 pub struct TunnelStateMachineHandle {
     command_tx: Arc<mpsc::UnboundedSender<TunnelCommand>>,
     shutdown_rx: oneshot::Receiver<()>,
+    last_handshake_diagnostics: Arc<Mutex<Option<HandshakeDiagnostics>>>,
+    applied_allowed_ips: Arc<Mutex<Option<Vec<IpNetwork>>>>,
+    tunnel_traffic: Arc<Mutex<TunnelTraffic>>,
+    firewall_info: FirewallInfoHandle,
     #[cfg(windows)]
     split_tunnel: split_tunnel::SplitTunnelHandle,
 }
@@ -811,6 +880,29 @@ impl TunnelStateMachineHandle {
         &self.command_tx
     }
 
+    /// Returns diagnostics for the last tunnel that timed out before it became functional.
+    /// This is cleared once a tunnel comes up.
+    pub fn last_handshake_diagnostics(&self) -> Option<HandshakeDiagnostics> {
+        *self.last_handshake_diagnostics.lock().unwrap()
+    }
+
+    /// Returns the allowed IPs that are configured on the tunnel device, or `None` unless the
+    /// tunnel is connected. These may differ from the settings until the tunnel reconnects.
+    pub fn applied_allowed_ips(&self) -> Option<Vec<IpNetwork>> {
+        self.applied_allowed_ips.lock().unwrap().clone()
+    }
+
+    /// Returns the total traffic of all tunnels that have been torn down since the state machine
+    /// was started. The traffic of a tunnel is only included once it has been torn down.
+    pub fn tunnel_traffic(&self) -> TunnelTraffic {
+        *self.tunnel_traffic.lock().unwrap()
+    }
+
+    /// Returns the firewall backend and any problems that prevent it from working as intended.
+    pub fn firewall_info(&self) -> FirewallInfo {
+        self.firewall_info.info()
+    }
+
     /// Returns split tunnel object handle.
     #[cfg(windows)]
     pub fn split_tunnel(&self) -> &split_tunnel::SplitTunnelHandle {
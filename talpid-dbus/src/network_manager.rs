@@ -43,6 +43,11 @@ const NM_SETTINGS_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings";
 const NM_SETTINGS_CONNECTION_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings.Connection";
 const NM_SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
 const NM_CONNECTION_ACTIVE: &str = "org.freedesktop.NetworkManager.Connection.Active";
+const NM_DEVICE_WIRELESS: &str = "org.freedesktop.NetworkManager.Device.Wireless";
+const NM_ACCESS_POINT: &str = "org.freedesktop.NetworkManager.AccessPoint";
+const NM_WIRELESS_CONNECTION_TYPE: &str = "802-11-wireless";
+/// Object path that NetworkManager uses in place of a missing object
+const NM_NO_OBJECT_PATH: &str = "/";
 
 const NM_ADD_CONNECTION_VOLATILE: u32 = 0x2;
 
@@ -154,6 +159,43 @@ impl NetworkManager {
             .map_err(Error::Dbus)
     }
 
+    /// Return the SSID of the access point that the primary connection uses, or `None` if the
+    /// primary connection is not a Wi-Fi connection. The SSID is an arbitrary byte string.
+    pub fn primary_connection_ssid(&self) -> Result<Option<Vec<u8>>> {
+        let connection_path: dbus::Path<'static> = self
+            .as_manager()
+            .get(NM_MANAGER, "PrimaryConnection")
+            .map_err(Error::Dbus)?;
+        if &*connection_path == NM_NO_OBJECT_PATH {
+            return Ok(None);
+        }
+
+        let connection = self.as_path(&connection_path);
+        let connection_type: String = connection
+            .get(NM_CONNECTION_ACTIVE, "Type")
+            .map_err(Error::Dbus)?;
+        if connection_type != NM_WIRELESS_CONNECTION_TYPE {
+            return Ok(None);
+        }
+        let device_paths: Vec<dbus::Path<'static>> = connection
+            .get(NM_CONNECTION_ACTIVE, "Devices")
+            .map_err(Error::Dbus)?;
+        let device_path = device_paths.into_iter().next().ok_or(Error::NoDevice)?;
+
+        let access_point_path: dbus::Path<'static> = self
+            .as_path(&device_path)
+            .get(NM_DEVICE_WIRELESS, "ActiveAccessPoint")
+            .map_err(Error::Dbus)?;
+        if &*access_point_path == NM_NO_OBJECT_PATH {
+            return Ok(None);
+        }
+        let ssid: Vec<u8> = self
+            .as_path(&access_point_path)
+            .get(NM_ACCESS_POINT, "Ssid")
+            .map_err(Error::Dbus)?;
+        Ok(Some(ssid))
+    }
+
     fn create_wg_tunnel_inner(&self, config: &DeviceConfig) -> Result<WireguardTunnel> {
         let config_path: dbus::Path<'static> = match self.add_connection_2(config) {
             Ok((path, _result)) => path,
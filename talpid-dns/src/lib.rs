@@ -30,12 +30,14 @@ pub use self::imp::Error;
 #[derive(Debug, Clone, PartialEq)]
 pub struct DnsConfig {
     config: InnerDnsConfig,
+    split_config: Vec<SplitDnsConfig>,
 }
 
 impl Default for DnsConfig {
     fn default() -> Self {
         Self {
             config: InnerDnsConfig::Default,
+            split_config: vec![],
         }
     }
 }
@@ -47,9 +49,56 @@ impl DnsConfig {
             config: InnerDnsConfig::Override {
                 tunnel_config: tunnel_config.to_owned(),
                 non_tunnel_config: non_tunnel_config.to_owned(),
+                gateway_position: None,
             },
+            split_config: vec![],
         }
     }
+
+    /// Use the specified addresses for DNS resolution, as well as the gateway addresses from the
+    /// tunnel config. The gateway addresses are inserted at `gateway_position` in
+    /// `tunnel_config`, or last if `gateway_position` is out of range.
+    pub fn from_addresses_with_gateway(
+        tunnel_config: &[IpAddr],
+        non_tunnel_config: &[IpAddr],
+        gateway_position: usize,
+    ) -> Self {
+        DnsConfig {
+            config: InnerDnsConfig::Override {
+                tunnel_config: tunnel_config.to_owned(),
+                non_tunnel_config: non_tunnel_config.to_owned(),
+                gateway_position: Some(gateway_position),
+            },
+            split_config: vec![],
+        }
+    }
+
+    /// Resolve the given domains using other servers than the rest of the config
+    pub fn with_split_config(mut self, split_config: Vec<SplitDnsConfig>) -> Self {
+        self.split_config = split_config;
+        self
+    }
+}
+
+/// DNS servers to use for a domain and its subdomains, instead of those in the rest of the config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitDnsConfig {
+    /// Domain to match, in lowercase and without a trailing dot
+    pub domain: String,
+    /// Addresses to reach through the tunnel
+    pub tunnel_config: Vec<IpAddr>,
+    /// Addresses to allow on non-tunnel interface
+    pub non_tunnel_config: Vec<IpAddr>,
+}
+
+impl SplitDnsConfig {
+    /// Return all addresses for the domain
+    pub fn addresses(&self) -> impl Iterator<Item = IpAddr> + '_ {
+        self.non_tunnel_config
+            .iter()
+            .chain(&self.tunnel_config)
+            .copied()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -64,6 +113,8 @@ enum InnerDnsConfig {
         /// For the most part, the tunnel state machine will not handle any of this configuration
         /// on non-tunnel interface, only allow them in the firewall.
         non_tunnel_config: Vec<IpAddr>,
+        /// Where to insert the gateway addresses in `tunnel_config`, if they should be used
+        gateway_position: Option<usize>,
     },
 }
 
@@ -77,15 +128,27 @@ impl DnsConfig {
             InnerDnsConfig::Default => ResolvedDnsConfig {
                 tunnel_config: default_tun_config.to_owned(),
                 non_tunnel_config: vec![],
+                split_config: self.split_config.clone(),
                 #[cfg(target_os = "macos")]
                 port,
             },
             InnerDnsConfig::Override {
                 tunnel_config,
                 non_tunnel_config,
+                gateway_position,
             } => ResolvedDnsConfig {
-                tunnel_config: tunnel_config.to_owned(),
+                tunnel_config: match gateway_position {
+                    Some(position) => {
+                        let mut tunnel_config = tunnel_config.to_owned();
+                        let position = (*position).min(tunnel_config.len());
+                        tunnel_config
+                            .splice(position..position, default_tun_config.iter().copied());
+                        tunnel_config
+                    }
+                    None => tunnel_config.to_owned(),
+                },
                 non_tunnel_config: non_tunnel_config.to_owned(),
+                split_config: self.split_config.clone(),
                 #[cfg(target_os = "macos")]
                 port,
             },
@@ -102,6 +165,8 @@ pub struct ResolvedDnsConfig {
     /// For the most part, the tunnel state machine will not handle any of this configuration
     /// on non-tunnel interface, only allow them in the firewall.
     non_tunnel_config: Vec<IpAddr>,
+    /// Servers to use for specific domains instead of the ones above
+    split_config: Vec<SplitDnsConfig>,
     /// Port to use
     #[cfg(target_os = "macos")]
     port: u16,
@@ -115,6 +180,11 @@ impl fmt::Display for ResolvedDnsConfig {
         f.write_str(" Non-tunnel DNS: ")?;
         Self::fmt_addr_set(f, &self.non_tunnel_config)?;
 
+        for split_config in &self.split_config {
+            write!(f, " DNS for {}: ", split_config.domain)?;
+            Self::fmt_addr_set(f, &split_config.addresses().collect::<Vec<_>>())?;
+        }
+
         #[cfg(target_os = "macos")]
         write!(f, " Port: {}", self.port)?;
 
@@ -146,6 +216,12 @@ impl ResolvedDnsConfig {
         &self.non_tunnel_config
     }
 
+    /// Servers to use for specific domains. These are not included in [Self::tunnel_config],
+    /// [Self::non_tunnel_config], or [Self::addresses].
+    pub fn split_config(&self) -> &[SplitDnsConfig] {
+        &self.split_config
+    }
+
     /// Consume `self` and return a vector of all addresses
     pub fn addresses(self) -> impl Iterator<Item = IpAddr> {
         self.non_tunnel_config.into_iter().chain(self.tunnel_config)
@@ -189,6 +265,11 @@ impl DnsMonitor {
     /// Set DNS to the given servers. And start monitoring the system for changes.
     pub fn set(&mut self, interface: &str, config: ResolvedDnsConfig) -> Result<(), Error> {
         log::info!("Setting DNS servers: {config}",);
+        if !config.split_config.is_empty() {
+            // Split DNS is applied by the local DNS resolver on macOS, which does not use this
+            // monitor unless the resolver is disabled. Other platforms reject split DNS rules.
+            log::warn!("Split DNS requires the local DNS resolver. Ignoring split DNS rules");
+        }
         self.inner.set(interface, config)
     }
 
@@ -3,12 +3,13 @@
 use std::ffi::c_uint;
 use std::io;
 use std::mem;
+use std::net::IpAddr;
 use std::os::fd::AsRawFd;
 use std::ptr;
 
 use nix::errno::Errno;
 use nix::libc::ifreq;
-use nix::net::if_::if_nametoindex;
+use nix::net::if_::{InterfaceFlags, if_nametoindex};
 use socket2::{Domain, Protocol, Socket, Type};
 use talpid_types::ErrorExt;
 
@@ -27,6 +28,49 @@ pub fn iface_index(name: &str) -> Result<c_uint, IfaceIndexLookupError> {
     })
 }
 
+/// A network interface and the IP addresses assigned to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceAddresses {
+    pub name: String,
+    pub addresses: Vec<IpAddr>,
+    pub up: bool,
+}
+
+/// Return all network interfaces, along with their IP addresses.
+pub fn list_interfaces() -> Result<Vec<InterfaceAddresses>, Errno> {
+    let mut interfaces: Vec<InterfaceAddresses> = vec![];
+    for interface_address in nix::ifaddrs::getifaddrs()? {
+        let address = interface_address.address.and_then(|address| {
+            address
+                .as_sockaddr_in()
+                .map(|address| IpAddr::V4(address.ip()))
+                .or_else(|| {
+                    address
+                        .as_sockaddr_in6()
+                        .map(|address| IpAddr::V6(address.ip()))
+                })
+        });
+        let up = interface_address.flags.contains(InterfaceFlags::IFF_UP);
+
+        // getifaddrs returns one entry per address, so merge the entries of each interface
+        match interfaces
+            .iter_mut()
+            .find(|interface| interface.name == interface_address.interface_name)
+        {
+            Some(interface) => {
+                interface.addresses.extend(address);
+                interface.up |= up;
+            }
+            None => interfaces.push(InterfaceAddresses {
+                name: interface_address.interface_name,
+                addresses: address.into_iter().collect(),
+                up,
+            }),
+        }
+    }
+    Ok(interfaces)
+}
+
 pub fn set_mtu(interface_name: &str, mtu: u16) -> Result<(), io::Error> {
     IfReq::new(interface_name)?.set_mtu(mtu).inspect_err(|e| {
         log::error!("{}", e.display_chain_with_msg("SIOCSIFMTU failed"));
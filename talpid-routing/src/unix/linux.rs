@@ -13,7 +13,7 @@ use netlink_packet_core::{
 };
 use netlink_packet_route::route::RouteFlags;
 use netlink_sys::AsyncSocket;
-use talpid_types::ErrorExt;
+use talpid_types::{ErrorExt, net::InstalledRoute};
 
 use futures::{
     StreamExt, TryStreamExt,
@@ -342,6 +342,10 @@ impl RouteManagerImpl {
         Ok(())
     }
 
+    fn installed_routes(&self) -> Vec<InstalledRoute> {
+        self.added_routes.iter().map(installed_route).collect()
+    }
+
     async fn cleanup_routes(&mut self) {
         for route in self.added_routes.drain().collect::<Vec<_>>().iter() {
             if let Err(e) = self.delete_route_if_exists(route).await {
@@ -401,6 +405,12 @@ impl RouteManagerImpl {
                 log::debug!("Clearing routes");
                 self.cleanup_routes().await;
             }
+            RouteManagerCommand::GetInstalledRoutes(result_tx) => {
+                let _ = result_tx.send(self.installed_routes());
+            }
+            RouteManagerCommand::GetRoutes(result_tx) => {
+                let _ = result_tx.send(self.get_routes().await);
+            }
         }
         Ok(())
     }
@@ -903,6 +913,27 @@ impl RouteManagerImpl {
             Err(err) => Err(Error::GetRoute(err)),
         }
     }
+
+    /// Return the IPv4 and IPv6 routes in the main routing table.
+    async fn get_routes(&self) -> Result<Vec<InstalledRoute>> {
+        let requests = [
+            RouteMessageBuilder::<Ipv4Addr>::new().build(),
+            RouteMessageBuilder::<Ipv6Addr>::new().build(),
+        ];
+        let mut routes = vec![];
+        for request in requests {
+            let mut stream = self.handle.route().get(request).execute();
+            while let Some(route_msg) = stream.try_next().await.map_err(Error::GetRoute)? {
+                // Skip routes that cannot be represented, such as blackhole routes
+                if let Ok(Some(route)) = self.parse_route_message(route_msg)
+                    && route.table_id == u32::from(RT_TABLE_MAIN)
+                {
+                    routes.push(installed_route(&route));
+                }
+            }
+        }
+        Ok(routes)
+    }
 }
 
 fn ip_to_route_address(addr: IpAddr) -> RouteAddress {
@@ -936,6 +967,15 @@ impl NetworkInterface {
     }
 }
 
+fn installed_route(route: &Route) -> InstalledRoute {
+    InstalledRoute {
+        destination: route.prefix,
+        gateway: route.node.get_address(),
+        interface: route.node.get_device().map(str::to_owned),
+        metric: route.metric,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -963,4 +1003,39 @@ mod test {
         });
         std::mem::drop(manager);
     }
+
+    #[test]
+    fn test_installed_route() {
+        let gateway = "192.0.2.1".parse().unwrap();
+        let route = Route {
+            metric: Some(10),
+            ..Route::new(
+                Node::new(gateway, "eth0".to_owned()),
+                "10.0.0.0/8".parse().unwrap(),
+            )
+        };
+        assert_eq!(
+            installed_route(&route),
+            InstalledRoute {
+                destination: "10.0.0.0/8".parse().unwrap(),
+                gateway: Some(gateway),
+                interface: Some("eth0".to_owned()),
+                metric: Some(10),
+            }
+        );
+
+        let route = Route::new(
+            Node::device("wg0-mullvad".to_owned()),
+            "0.0.0.0/0".parse().unwrap(),
+        );
+        assert_eq!(
+            installed_route(&route),
+            InstalledRoute {
+                destination: "0.0.0.0/0".parse().unwrap(),
+                gateway: None,
+                interface: Some("wg0-mullvad".to_owned()),
+                metric: None,
+            }
+        );
+    }
 }
@@ -370,6 +370,11 @@ impl RouteMessage {
         self.route_flags.contains(RouteFlag::RTF_IFSCOPE)
     }
 
+    /// Whether this route is a host route, in which case it has no netmask.
+    pub fn is_host(&self) -> bool {
+        self.route_flags.contains(RouteFlag::RTF_HOST)
+    }
+
     /// Turn this route into a scoped (ifscope) route for the interface index.
     pub fn set_ifscope(mut self) -> Self {
         self.route_flags |= RouteFlag::RTF_IFSCOPE;
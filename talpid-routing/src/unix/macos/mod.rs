@@ -8,7 +8,7 @@ use futures::{
 };
 use ip_map::IpMap;
 use ipnetwork::IpNetwork;
-use nix::net::if_::if_nametoindex;
+use nix::net::if_::{if_indextoname, if_nametoindex};
 use std::{
     collections::{BTreeMap, HashSet},
     net::{IpAddr, SocketAddr},
@@ -16,7 +16,7 @@ use std::{
     sync::Weak,
     time::Duration,
 };
-use talpid_types::ErrorExt;
+use talpid_types::{ErrorExt, net::InstalledRoute};
 
 use super::RouteManagerCommand;
 use data::{Destination, RouteDestination, RouteMessage, RouteSocketMessage};
@@ -63,6 +63,10 @@ pub enum Error {
     /// Received message isn't valid
     #[error("Invalid data")]
     InvalidData(#[source] data::Error),
+
+    /// Failed to read the routing table
+    #[error("Failed to read the routing table")]
+    ReadRoutingTable(#[source] std::io::Error),
 }
 
 /// Route manager can be in 1 of 4 states -
@@ -243,6 +247,14 @@ impl RouteManagerImpl {
                             }
                         },
 
+                        Some(RouteManagerCommand::GetInstalledRoutes(tx)) => {
+                            let _ = tx.send(self.installed_routes());
+                        }
+
+                        Some(RouteManagerCommand::GetRoutes(tx)) => {
+                            let _ = tx.send(get_routes());
+                        }
+
                         Some(RouteManagerCommand::NewInterfaceChangeListener(tx)) => {
                             let (events_tx, events_rx) = mpsc::unbounded();
                             self.interface_change_listeners.push(events_tx);
@@ -610,6 +622,20 @@ impl RouteManagerImpl {
         Ok(())
     }
 
+    fn installed_routes(&self) -> Vec<InstalledRoute> {
+        self.applied_routes
+            .iter()
+            .map(|(destination, route)| InstalledRoute {
+                destination: destination.network,
+                gateway: destination.gateway,
+                interface: if_indextoname(u32::from(route.interface_index()))
+                    .ok()
+                    .map(|name| name.to_string_lossy().into_owned()),
+                metric: None,
+            })
+            .collect()
+    }
+
     async fn cleanup_routes(&mut self) -> Result<()> {
         self.remove_applied_routes(|_| true).await;
 
@@ -751,6 +777,73 @@ impl RouteManagerImpl {
     }
 }
 
+/// Return the routes in the routing table, which is dumped using `sysctl`.
+fn get_routes() -> Result<Vec<InstalledRoute>> {
+    let mut mib = [libc::CTL_NET, libc::PF_ROUTE, 0, 0, libc::NET_RT_DUMP, 0];
+    let mut len = 0;
+    // SAFETY: Passing a null buffer makes sysctl only store the size of the table in `len`
+    let result = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as libc::c_uint,
+            std::ptr::null_mut(),
+            &raw mut len,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if result != 0 {
+        return Err(Error::ReadRoutingTable(std::io::Error::last_os_error()));
+    }
+    let mut buffer = vec![0u8; len];
+    // SAFETY: `buffer` is valid for writes of `len` bytes
+    let result = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as libc::c_uint,
+            buffer.as_mut_ptr().cast(),
+            &raw mut len,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if result != 0 {
+        return Err(Error::ReadRoutingTable(std::io::Error::last_os_error()));
+    }
+    buffer.truncate(len);
+
+    let mut routes = vec![];
+    let mut remaining = buffer.as_slice();
+    while let Some(header) = data::ffi::rt_msghdr_short::from_bytes(remaining) {
+        let message_len = usize::from(header.rtm_msglen);
+        if message_len == 0 || message_len > remaining.len() {
+            break;
+        }
+        let (message, rest) = remaining.split_at(message_len);
+        remaining = rest;
+
+        // Skip routes that cannot be represented, such as link-layer routes
+        if let Ok(RouteSocketMessage::GetRoute(route)) = RouteSocketMessage::parse_message(message)
+            && let Ok(Some(destination)) = route.destination_ip()
+        {
+            let destination = if route.is_host() {
+                IpNetwork::from(destination.ip())
+            } else {
+                destination
+            };
+            routes.push(InstalledRoute {
+                destination,
+                gateway: route.gateway_ip(),
+                interface: if_indextoname(u32::from(route.interface_index()))
+                    .ok()
+                    .map(|name| name.to_string_lossy().into_owned()),
+                metric: None,
+            });
+        }
+    }
+    Ok(routes)
+}
+
 /// Construct a [RouteMessage] that refers to the `0.0.0.0/0` or `::/0` route with the
 /// RTF_GATEAWAY-flag set. Used to reference the default route created by macOS.
 fn default_route_msg(family: interface::Family) -> RouteMessage {
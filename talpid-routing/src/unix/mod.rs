@@ -13,6 +13,7 @@ use futures::channel::{
 use std::sync::Arc;
 #[cfg(target_os = "android")]
 use talpid_types::android::AndroidContext;
+use talpid_types::net::InstalledRoute;
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 use futures::stream::Stream;
@@ -99,6 +100,8 @@ pub(crate) enum RouteManagerCommand {
         Option<Fwmark>,
         oneshot::Sender<Result<Option<Route>, PlatformError>>,
     ),
+    GetInstalledRoutes(oneshot::Sender<Vec<InstalledRoute>>),
+    GetRoutes(oneshot::Sender<Result<Vec<InstalledRoute>, PlatformError>>),
 }
 
 /// Commands for the underlying route manager object.
@@ -126,6 +129,8 @@ pub(crate) enum RouteManagerCommand {
     NewInterfaceChangeListener(oneshot::Sender<mpsc::UnboundedReceiver<InterfaceEvent>>),
     /// Return gateway for V4 and V6
     GetDefaultGateway(oneshot::Sender<(Option<Gateway>, Option<Gateway>)>),
+    GetInstalledRoutes(oneshot::Sender<Vec<InstalledRoute>>),
+    GetRoutes(oneshot::Sender<Result<Vec<InstalledRoute>, PlatformError>>),
 }
 
 /// Event that is sent when interface details may have changed for some interface.
@@ -250,6 +255,44 @@ impl RouteManagerHandle {
         Ok(())
     }
 
+    /// Return the routes that are currently applied by the route manager.
+    #[cfg(not(target_os = "android"))]
+    pub async fn get_installed_routes(&self) -> Result<Vec<InstalledRoute>, Error> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.tx
+            .unbounded_send(RouteManagerCommand::GetInstalledRoutes(response_tx))
+            .map_err(|_| Error::RouteManagerDown)?;
+        response_rx.await.map_err(|_| Error::ManagerChannelDown)
+    }
+
+    /// (Android) We don't directly control the routes on Android, so this is always empty.
+    #[cfg(target_os = "android")]
+    #[expect(clippy::unused_async)]
+    pub async fn get_installed_routes(&self) -> Result<Vec<InstalledRoute>, Error> {
+        Ok(vec![])
+    }
+
+    /// Return the routes in the main routing table, including routes that were not added by the
+    /// route manager.
+    #[cfg(not(target_os = "android"))]
+    pub async fn get_routes(&self) -> Result<Vec<InstalledRoute>, Error> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.tx
+            .unbounded_send(RouteManagerCommand::GetRoutes(response_tx))
+            .map_err(|_| Error::RouteManagerDown)?;
+        response_rx
+            .await
+            .map_err(|_| Error::ManagerChannelDown)?
+            .map_err(Error::PlatformError)
+    }
+
+    /// (Android) The routing table is not accessible on Android, so this is always empty.
+    #[cfg(target_os = "android")]
+    #[expect(clippy::unused_async)]
+    pub async fn get_routes(&self) -> Result<Vec<InstalledRoute>, Error> {
+        Ok(vec![])
+    }
+
     /// (Android) Clear the cached routes
     #[cfg(target_os = "android")]
     pub async fn clear_route_cache(&self) -> Result<(), Error> {
@@ -400,3 +443,49 @@ impl RouteManagerHandle {
             .map_err(Error::PlatformError)
     }
 }
+
+#[cfg(all(test, not(target_os = "android")))]
+mod test {
+    use super::*;
+    use futures::StreamExt;
+
+    #[test]
+    fn test_get_installed_routes() {
+        let runtime = tokio::runtime::Runtime::new().expect("Failed to initialize runtime");
+        let (tx, mut rx) = mpsc::unbounded();
+        let handle = RouteManagerHandle { tx: Arc::new(tx) };
+        let route = InstalledRoute {
+            destination: "10.64.0.1/32".parse().unwrap(),
+            gateway: None,
+            interface: Some("wg0-mullvad".to_owned()),
+            metric: None,
+        };
+
+        let expected = vec![route.clone()];
+        let manager = runtime.spawn(async move {
+            match rx.next().await {
+                Some(RouteManagerCommand::GetInstalledRoutes(tx)) => {
+                    let _ = tx.send(vec![route]);
+                }
+                _ => panic!("unexpected route manager command"),
+            }
+        });
+
+        let routes = runtime.block_on(handle.get_installed_routes()).unwrap();
+        assert_eq!(routes, expected);
+        runtime.block_on(manager).unwrap();
+    }
+
+    #[test]
+    fn test_get_installed_routes_manager_down() {
+        let runtime = tokio::runtime::Runtime::new().expect("Failed to initialize runtime");
+        let (tx, rx) = mpsc::unbounded();
+        let handle = RouteManagerHandle { tx: Arc::new(tx) };
+        drop(rx);
+
+        assert!(matches!(
+            runtime.block_on(handle.get_installed_routes()),
+            Err(Error::RouteManagerDown)
+        ));
+    }
+}
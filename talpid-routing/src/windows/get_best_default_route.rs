@@ -1,8 +1,9 @@
 use super::{Error, Result};
+use ipnetwork::IpNetwork;
 use std::{net::SocketAddr, slice};
-use talpid_types::win32_err;
+use talpid_types::{net::InstalledRoute, win32_err};
 use talpid_windows::net::{
-    AddressFamily, get_ip_interface_entry, try_socketaddr_from_inet_sockaddr,
+    AddressFamily, alias_from_luid, get_ip_interface_entry, try_socketaddr_from_inet_sockaddr,
 };
 use widestring::{WideCStr, widecstr};
 use windows_sys::Win32::NetworkManagement::{
@@ -49,6 +50,32 @@ fn get_ip_forward_table(family: AddressFamily) -> Result<Vec<MIB_IPFORWARD_ROW2>
     Ok(rows)
 }
 
+/// Return the IPv4 and IPv6 routes in the routing table.
+pub fn get_routes() -> Result<Vec<InstalledRoute>> {
+    let mut routes = vec![];
+    for family in [AddressFamily::Ipv4, AddressFamily::Ipv6] {
+        for row in get_ip_forward_table(family)? {
+            let prefix = try_socketaddr_from_inet_sockaddr(row.DestinationPrefix.Prefix)
+                .map_err(|_| Error::InvalidSiFamily)?;
+            let destination = IpNetwork::new(prefix.ip(), row.DestinationPrefix.PrefixLength)
+                .map_err(|_| Error::Conversion)?;
+            let gateway = try_socketaddr_from_inet_sockaddr(row.NextHop)
+                .ok()
+                .map(|next_hop| next_hop.ip())
+                .filter(|next_hop| !next_hop.is_unspecified());
+            routes.push(InstalledRoute {
+                destination,
+                gateway,
+                interface: alias_from_luid(&row.InterfaceLuid)
+                    .ok()
+                    .map(|alias| alias.to_string_lossy().into_owned()),
+                metric: Some(row.Metric),
+            });
+        }
+    }
+    Ok(routes)
+}
+
 /// General type for passing interface and gateway
 pub struct InterfaceAndGateway {
     /// Interface
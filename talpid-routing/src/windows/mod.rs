@@ -11,7 +11,7 @@ pub use get_best_default_route::{InterfaceAndGateway, get_best_default_route};
 use net::AddressFamily;
 pub use route_manager::{Callback, CallbackHandle, Route, RouteManagerInternal};
 use std::{collections::HashSet, io, net::IpAddr};
-use talpid_types::ErrorExt;
+use talpid_types::{ErrorExt, net::InstalledRoute};
 use talpid_windows::net;
 
 mod default_route_monitor;
@@ -107,6 +107,7 @@ pub enum RouteManagerCommand {
     AddRoutes(HashSet<RequiredRoute>, oneshot::Sender<Result<()>>),
     GetMtuForRoute(IpAddr, oneshot::Sender<Result<u16>>),
     ClearRoutes,
+    GetInstalledRoutes(oneshot::Sender<Vec<InstalledRoute>>),
     RegisterDefaultRouteChangeCallback(Callback, oneshot::Sender<CallbackHandle>),
     Shutdown(oneshot::Sender<()>),
 }
@@ -156,6 +157,22 @@ impl RouteManagerHandle {
         response_rx.await.map_err(|_| Error::RouteManagerDown)?
     }
 
+    /// Return the routes that are currently applied by the route manager.
+    pub async fn get_installed_routes(&self) -> Result<Vec<InstalledRoute>> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.tx
+            .unbounded_send(RouteManagerCommand::GetInstalledRoutes(response_tx))
+            .map_err(|_| Error::RouteManagerDown)?;
+        response_rx.await.map_err(|_| Error::RouteManagerDown)
+    }
+
+    /// Return the routes in the routing table, including routes that were not added by the route
+    /// manager.
+    #[expect(clippy::unused_async)]
+    pub async fn get_routes(&self) -> Result<Vec<InstalledRoute>> {
+        get_best_default_route::get_routes()
+    }
+
     /// Stop the routing manager actor and revert all changes to routing
     pub async fn stop(&self) {
         let (result_tx, result_rx) = oneshot::channel();
@@ -211,6 +228,9 @@ impl RouteManagerHandle {
                         log::error!("{}", e.display_chain_with_msg("Could not clear routes"));
                     }
                 }
+                RouteManagerCommand::GetInstalledRoutes(tx) => {
+                    let _ = tx.send(internal.installed_routes());
+                }
                 RouteManagerCommand::RegisterDefaultRouteChangeCallback(callback, tx) => {
                     let _ = tx.send(internal.register_default_route_changed_callback(callback));
                 }
@@ -11,9 +11,10 @@ use std::{
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     sync::{Arc, Mutex},
 };
-use talpid_types::win32_err;
+use talpid_types::{net::InstalledRoute, win32_err};
 use talpid_windows::net::{
-    AddressFamily, inet_sockaddr_from_socketaddr, try_socketaddr_from_inet_sockaddr,
+    AddressFamily, alias_from_luid, inet_sockaddr_from_socketaddr,
+    try_socketaddr_from_inet_sockaddr,
 };
 use widestring::{WideCStr, WideCString};
 use windows_sys::Win32::{
@@ -438,6 +439,25 @@ impl RouteManagerInternal {
         Ok(())
     }
 
+    pub fn installed_routes(&self) -> Vec<InstalledRoute> {
+        let routes = self.routes.lock().unwrap();
+        routes
+            .iter()
+            .map(|record| {
+                let next_hop = record.registered_route.next_hop.ip();
+                InstalledRoute {
+                    destination: record.registered_route.network,
+                    gateway: (!next_hop.is_unspecified()).then_some(next_hop),
+                    interface: alias_from_luid(&record.registered_route.luid)
+                        .ok()
+                        .map(|alias| alias.to_string_lossy().into_owned()),
+                    // Routes are added with the interface metric, which is not known here
+                    metric: None,
+                }
+            })
+            .collect()
+    }
+
     pub fn register_default_route_changed_callback(&self, callback: Callback) -> CallbackHandle {
         let (nonce, callbacks) = &mut *self.callbacks.lock().unwrap();
         let old_nonce = *nonce;
@@ -17,7 +17,7 @@ use futures::{
     },
 };
 use talpid_routing::RouteManagerHandle;
-use talpid_types::net::AllowedTunnelTraffic;
+use talpid_types::net::{AllowedTunnelTraffic, TunnelTraffic};
 use tun_provider::TunProvider;
 
 /// Size of IPv4 header in bytes
@@ -49,6 +49,10 @@ pub struct TunnelArgs<'a> {
     pub retry_attempt: u32,
     /// Route manager handle.
     pub route_manager: RouteManagerHandle,
+    /// Total traffic of all tunnels. The traffic of the tunnel is added when it is torn down.
+    pub tunnel_traffic: Arc<Mutex<TunnelTraffic>>,
+    /// Allowed IPs of the exit peer, as read from the tunnel device once the tunnel is up.
+    pub applied_allowed_ips: Arc<Mutex<Option<Vec<ipnetwork::IpNetwork>>>>,
 }
 
 #[derive(Clone)]
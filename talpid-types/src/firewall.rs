@@ -0,0 +1,37 @@
+//! Information about the firewall integration, for diagnosing problems with blocking traffic.
+
+use std::fmt;
+
+/// The facility of the operating system that enforces the firewall policies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirewallBackend {
+    /// nftables, on Linux
+    Nftables,
+    /// The packet filter, on macOS
+    Pf,
+    /// The Windows Filtering Platform
+    Wfp,
+    /// No firewall is used. On Android, traffic is blocked by the VPN service instead.
+    None,
+}
+
+impl fmt::Display for FirewallBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FirewallBackend::Nftables => f.write_str("nftables"),
+            FirewallBackend::Pf => f.write_str("pf"),
+            FirewallBackend::Wfp => f.write_str("WFP"),
+            FirewallBackend::None => f.write_str("none"),
+        }
+    }
+}
+
+/// The state of the firewall. The tunnel state machine cannot start unless the firewall has
+/// been initialized, so there is no state for a failed initialization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FirewallInfo {
+    pub backend: FirewallBackend,
+    /// Reasons why the firewall may not work as intended, such as the last policy failing to be
+    /// applied. Empty if no problems are known.
+    pub degraded: Vec<String>,
+}
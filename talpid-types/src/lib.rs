@@ -1,5 +1,6 @@
 #[cfg(target_os = "android")]
 pub mod android;
+pub mod firewall;
 pub mod net;
 pub mod tunnel;
 
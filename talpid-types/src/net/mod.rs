@@ -1,5 +1,6 @@
 use crate::net::obfuscation::ObfuscatorConfig;
 
+use ipnetwork::IpNetwork;
 #[cfg(target_os = "android")]
 use jnix::FromJava;
 use obfuscation::Obfuscators;
@@ -162,7 +163,9 @@ impl From<&ObfuscatorConfig> for ObfuscationEndpoint {
     fn from(config: &ObfuscatorConfig) -> ObfuscationEndpoint {
         let obfuscation_type = match config {
             ObfuscatorConfig::Udp2Tcp { .. } => ObfuscationType::Udp2Tcp,
-            ObfuscatorConfig::Shadowsocks { .. } => ObfuscationType::Shadowsocks,
+            ObfuscatorConfig::Shadowsocks { .. } | ObfuscatorConfig::CustomShadowsocks { .. } => {
+                ObfuscationType::Shadowsocks
+            }
             ObfuscatorConfig::Quic { .. } => ObfuscationType::Quic,
             ObfuscatorConfig::Lwo { .. } => ObfuscationType::Lwo,
         };
@@ -351,6 +354,23 @@ impl fmt::Display for AllowedTunnelTraffic {
     }
 }
 
+/// Number of bytes sent and received through tunnels.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct TunnelTraffic {
+    pub tx_bytes: u64,
+    pub rx_bytes: u64,
+}
+
+impl TunnelTraffic {
+    /// Return the traffic that was added since `earlier` was recorded.
+    pub fn since(&self, earlier: TunnelTraffic) -> TunnelTraffic {
+        TunnelTraffic {
+            tx_bytes: self.tx_bytes.saturating_sub(earlier.tx_bytes),
+            rx_bytes: self.rx_bytes.saturating_sub(earlier.rx_bytes),
+        }
+    }
+}
+
 /// IP protocol version.
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -443,6 +463,29 @@ pub struct GenericTunnelOptions {
     pub enable_ipv6: bool,
 }
 
+/// A network interface on the host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkInterface {
+    pub name: String,
+    /// IP addresses assigned to the interface
+    pub addresses: Vec<IpAddr>,
+    /// Whether the interface is up
+    pub up: bool,
+    /// Whether traffic to the internet is routed through this interface
+    pub default_route: bool,
+}
+
+/// A route in the routing table, such as one that has been added by the route manager
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledRoute {
+    pub destination: IpNetwork,
+    /// Address of the gateway, if the route goes via one
+    pub gateway: Option<IpAddr>,
+    /// Name of the interface that the route uses, if known
+    pub interface: Option<String>,
+    pub metric: Option<u32>,
+}
+
 /// Details about the hosts's connectivity.
 ///
 /// Information about the host's connectivity, such as the preesence of
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 
-use super::{Endpoint, TransportProtocol};
+use super::{Endpoint, TransportProtocol, proxy::Shadowsocks};
 
 /// Available obfuscation configuration types.
 #[derive(Clone, Eq, PartialEq, Deserialize, Serialize, Debug)]
@@ -28,6 +28,11 @@ pub enum ObfuscatorConfig {
     Shadowsocks {
         endpoint: SocketAddr,
     },
+    /// A Shadowsocks server that is not run by a relay. It forwards traffic to the WireGuard
+    /// endpoint of the relay.
+    CustomShadowsocks {
+        server: Shadowsocks,
+    },
     Quic {
         hostname: String,
         endpoint: SocketAddr,
@@ -106,6 +111,10 @@ impl ObfuscatorConfig {
                 address: *endpoint,
                 protocol: TransportProtocol::Udp,
             },
+            ObfuscatorConfig::CustomShadowsocks { server } => Endpoint {
+                address: server.endpoint,
+                protocol: TransportProtocol::Udp,
+            },
             ObfuscatorConfig::Quic { endpoint, .. } => Endpoint {
                 address: *endpoint,
                 protocol: TransportProtocol::Udp,
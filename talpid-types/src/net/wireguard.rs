@@ -119,6 +119,10 @@ pub struct TunnelConfig {
 pub struct TunnelOptions {
     /// MTU for the wireguard tunnel
     pub mtu: Option<u16>,
+    /// Persistent keepalive interval in seconds. If `None`, no keepalive packets are sent.
+    pub persistent_keepalive: Option<u16>,
+    /// Local address to bind the tunnel socket to. If `None`, the address is chosen by the OS.
+    pub bind_address: Option<IpAddr>,
     /// Perform PQ-safe PSK exchange when connecting
     pub quantum_resistant: bool,
     /// Enable DAITA during tunnel config
@@ -126,6 +130,48 @@ pub struct TunnelOptions {
     pub daita: bool,
 }
 
+/// Details about a WireGuard tunnel that failed to establish connectivity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HandshakeDiagnostics {
+    /// Whether any data, such as a handshake initiation, was sent to the relay.
+    pub initiation_sent: bool,
+    /// Whether any data was received from the relay.
+    pub response_received: bool,
+    /// How far the handshake got before the attempt was abandoned.
+    pub outcome: HandshakeOutcome,
+}
+
+impl HandshakeDiagnostics {
+    pub fn new(initiation_sent: bool, response_received: bool, handshake_completed: bool) -> Self {
+        let outcome = if handshake_completed {
+            HandshakeOutcome::Completed
+        } else if response_received {
+            HandshakeOutcome::Rejected
+        } else {
+            HandshakeOutcome::Timeout
+        };
+        HandshakeDiagnostics {
+            initiation_sent,
+            response_received,
+            outcome,
+        }
+    }
+}
+
+/// Outcome of a failed attempt to establish a WireGuard tunnel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandshakeOutcome {
+    /// Nothing was received from the relay. Either the relay is unreachable or UDP traffic is
+    /// being dropped on the way.
+    Timeout,
+    /// The relay responded, but no handshake was completed. This usually means that the relay did
+    /// not accept our key.
+    Rejected,
+    /// A handshake was completed, but no traffic made it through the tunnel afterwards.
+    Completed,
+}
+
 /// Wireguard x25519 private key
 #[derive(Clone, Zeroize, ZeroizeOnDrop)]
 pub struct PrivateKey(x25519_dalek::StaticSecret);
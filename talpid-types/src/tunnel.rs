@@ -96,6 +96,10 @@ pub enum ErrorStateCause {
     TunnelParameterError(ParameterGenerationError),
     /// This device is offline, no tunnels can be established.
     IsOffline,
+    /// No tunnel could be established before the connect timeout expired.
+    ConnectTimeout,
+    /// No tunnel could be established within the maximum number of reconnect attempts.
+    ReconnectLimitReached,
     #[cfg(target_os = "android")]
     NotPrepared,
     #[cfg(target_os = "android")]
@@ -130,6 +134,9 @@ pub enum ParameterGenerationError {
     /// Failure to select a matching tunnel relay, but we do not know if it is an entry or an exit
     #[error("Failure to select a matching tunnel relay")]
     NoMatchingRelay,
+    /// Relays match the constraints, but none of them support DAITA
+    #[error("No DAITA-compatible tunnel relay matches the constraints")]
+    NoMatchingDaitaRelay,
     /// Failure to select a matching bridge relay
     #[error("Failure to select a matching bridge relay")]
     NoMatchingBridgeRelay,
@@ -213,6 +220,10 @@ impl fmt::Display for ErrorStateCause {
                 return write!(f, "Failure to generate tunnel parameters: {err}");
             }
             IsOffline => "This device is offline, no tunnels can be established",
+            ConnectTimeout => "Failed to establish a tunnel before the connect timeout expired",
+            ReconnectLimitReached => {
+                "Failed to establish a tunnel within the maximum number of reconnect attempts"
+            }
             #[cfg(any(target_os = "windows", target_os = "macos", target_os = "android"))]
             SplitTunnelError => "The split tunneling module reported an error",
             #[cfg(target_os = "macos")]
@@ -1,7 +1,7 @@
 use std::{
     borrow::Cow,
     ffi::CString,
-    net::{Ipv4Addr, Ipv6Addr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
 };
 use talpid_types::net::wireguard::{PeerConfig, PrivateKey};
 use talpid_types::net::{GenericTunnelOptions, obfuscation::Obfuscators, wireguard};
@@ -25,6 +25,10 @@ pub struct Config {
     pub ipv6_gateway: Option<Ipv6Addr>,
     /// Maximum transmission unit for the tunnel
     pub mtu: u16,
+    /// Persistent keepalive interval for each peer, in seconds
+    pub persistent_keepalive: Option<u16>,
+    /// Local address to bind the tunnel socket to
+    pub bind_address: Option<IpAddr>,
     /// Firewall mark
     #[cfg(target_os = "linux")]
     pub fwmark: Option<u32>,
@@ -96,6 +100,8 @@ impl Config {
             ipv4_gateway: connection.ipv4_gateway,
             ipv6_gateway,
             mtu,
+            persistent_keepalive: wg_options.persistent_keepalive,
+            bind_address: wg_options.bind_address,
             #[cfg(target_os = "linux")]
             fwmark: connection.fwmark,
             #[cfg(target_os = "linux")]
@@ -125,6 +131,7 @@ impl Config {
         userspace_format(
             &self.tunnel.private_key,
             self.peers(),
+            self.persistent_keepalive,
             #[cfg(target_os = "linux")]
             self.fwmark,
         )
@@ -223,6 +230,7 @@ impl WgConfigBuffer {
 pub fn userspace_format<'a>(
     private_key: &PrivateKey,
     peers: impl Iterator<Item = &'a PeerConfig>,
+    persistent_keepalive: Option<u16>,
     #[cfg(target_os = "linux")] fwmark: Option<u32>,
 ) -> CString {
     // the order of insertion matters, public key entry denotes a new peer entry
@@ -239,18 +247,28 @@ pub fn userspace_format<'a>(
     wg_conf.add("replace_peers", "true");
 
     for peer in peers {
-        write_peer_to_config(&mut wg_conf, peer)
+        write_peer_to_config(&mut wg_conf, peer, persistent_keepalive)
     }
 
     let bytes = wg_conf.into_config();
     CString::new(bytes).expect("null bytes inside config")
 }
 
-fn write_peer_to_config(wg_conf: &mut WgConfigBuffer, peer: &PeerConfig) {
+fn write_peer_to_config(
+    wg_conf: &mut WgConfigBuffer,
+    peer: &PeerConfig,
+    persistent_keepalive: Option<u16>,
+) {
     wg_conf
         .add::<&[u8]>("public_key", peer.public_key.as_bytes().as_ref())
         .add("endpoint", peer.endpoint.to_string().as_str())
         .add("replace_allowed_ips", "true");
+    if let Some(interval) = persistent_keepalive {
+        wg_conf.add(
+            "persistent_keepalive_interval",
+            interval.to_string().as_str(),
+        );
+    }
     if let Some(ref psk) = peer.psk {
         wg_conf.add::<&[u8]>("preshared_key", psk.as_bytes().as_ref());
     }
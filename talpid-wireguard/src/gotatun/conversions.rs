@@ -8,8 +8,13 @@ use talpid_types::net::wireguard::PeerConfig;
 
 use crate::stats::{DaitaStats, Stats};
 
-/// Convert a [`PeerConfig`] into a GotaTun [`Peer`].
-pub fn to_gotatun_peer(peer: &PeerConfig, daita: Option<&DaitaSettings>) -> Peer {
+/// Convert a [`PeerConfig`] into a GotaTun [`Peer`]. `persistent_keepalive` is the keepalive
+/// interval in seconds.
+pub fn to_gotatun_peer(
+    peer: &PeerConfig,
+    persistent_keepalive: Option<u16>,
+    daita: Option<&DaitaSettings>,
+) -> Peer {
     let PeerConfig {
         public_key,
         allowed_ips,
@@ -27,6 +32,10 @@ pub fn to_gotatun_peer(peer: &PeerConfig, daita: Option<&DaitaSettings>) -> Peer
         peer = peer.with_preshared_key(*psk.as_bytes());
     }
 
+    if let Some(interval) = persistent_keepalive {
+        peer = peer.with_keepalive(interval);
+    }
+
     if let Some(daita) = daita {
         let daita = gotatun::device::daita::DaitaSettings {
             maybenot_machines: daita.client_machines.clone(),
@@ -54,6 +63,8 @@ impl From<gotatun::device::configure::Stats> for Stats {
             tx_bytes: peer_stats.tx_bytes as u64,
             rx_bytes: peer_stats.rx_bytes as u64,
             last_handshake_time,
+            // Not part of the peer stats, so this is set by the caller
+            allowed_ips: vec![],
             daita,
         }
     }
@@ -5,7 +5,6 @@ use crate::{
     config::Config,
     stats::{Stats, StatsMap},
 };
-#[cfg(target_os = "android")]
 use gotatun::udp::UdpTransportFactory;
 use gotatun::{
     device::{Device, DeviceBuilder, DeviceTransports},
@@ -48,7 +47,7 @@ use conversions::to_gotatun_peer;
 type UdpFactory = AndroidUdpSocketFactory;
 
 #[cfg(not(target_os = "android"))]
-type UdpFactory = UdpSocketFactory;
+type UdpFactory = BoundUdpSocketFactory;
 
 type SinglehopDevice = Device<(UdpFactory, GotaTunDevice, GotaTunDevice)>;
 type ExitDevice = Device<(UdpChannelFactory, GotaTunDevice, GotaTunDevice)>;
@@ -174,6 +173,33 @@ impl UdpTransportFactory for AndroidUdpSocketFactory {
     }
 }
 
+/// Binds the UDP sockets to a local address, if one is set. Otherwise, the address is chosen by
+/// the OS.
+#[cfg(not(target_os = "android"))]
+struct BoundUdpSocketFactory {
+    bind_address: Option<IpAddr>,
+}
+
+#[cfg(not(target_os = "android"))]
+impl UdpTransportFactory for BoundUdpSocketFactory {
+    type Send = <UdpSocketFactory as UdpTransportFactory>::Send;
+    type RecvV4 = <UdpSocketFactory as UdpTransportFactory>::RecvV4;
+    type RecvV6 = <UdpSocketFactory as UdpTransportFactory>::RecvV6;
+
+    async fn bind(
+        &mut self,
+        params: &gotatun::udp::UdpTransportFactoryParams,
+    ) -> std::io::Result<((Self::Send, Self::RecvV4), (Self::Send, Self::RecvV6))> {
+        let mut params = params.clone();
+        match self.bind_address {
+            Some(IpAddr::V4(address)) => params.addr_v4 = address,
+            Some(IpAddr::V6(address)) => params.addr_v6 = address,
+            None => (),
+        }
+        UdpSocketFactory.bind(&params).await
+    }
+}
+
 /// Configure and start a gotatun tunnel.
 pub async fn open_gotatun_tunnel(
     config: &Config,
@@ -281,7 +307,9 @@ async fn create_devices(
     let udp_factory = AndroidUdpSocketFactory { tun: android_tun };
 
     #[cfg(not(target_os = "android"))]
-    let udp_factory = UdpSocketFactory;
+    let udp_factory = BoundUdpSocketFactory {
+        bind_address: config.bind_address,
+    };
 
     let mut devices = if let Some(exit_peer) = &config.exit_peer {
         // Multihop setup
@@ -366,7 +394,7 @@ async fn configure_devices(
     daita: Option<&DaitaSettings>,
 ) -> Result<(), TunnelError> {
     let private_key = StaticSecret::from(config.tunnel.private_key.to_bytes());
-    let entry_peer = to_gotatun_peer(&config.entry_peer, daita);
+    let entry_peer = to_gotatun_peer(&config.entry_peer, config.persistent_keepalive, daita);
 
     if let Some(exit_peer) = &config.exit_peer {
         log::trace!(
@@ -374,7 +402,7 @@ async fn configure_devices(
             daita.is_some()
         );
 
-        let exit_peer = to_gotatun_peer(exit_peer, daita);
+        let exit_peer = to_gotatun_peer(exit_peer, config.persistent_keepalive, daita);
 
         let Devices::Multihop {
             entry_device,
@@ -477,7 +505,8 @@ impl Tunnel for GotaTun {
                 .into_iter()
                 .map(|peer| {
                     let public_key = peer.peer.public_key.to_bytes();
-                    let stats = Stats::from(peer.stats);
+                    let mut stats = Stats::from(peer.stats);
+                    stats.allowed_ips = peer.peer.allowed_ips;
                     (public_key, stats)
                 })
                 .collect()
@@ -27,7 +27,10 @@ use talpid_tunnel::{IPV4_HEADER_SIZE, IPV6_HEADER_SIZE, WIREGUARD_HEADER_SIZE};
 use talpid_tunnel_config_client::DaitaSettings;
 use talpid_types::{
     BoxedError, ErrorExt,
-    net::{AllowedTunnelTraffic, Endpoint, TransportProtocol, wireguard::TunnelParameters},
+    net::{
+        AllowedTunnelTraffic, Endpoint, TransportProtocol, TunnelTraffic,
+        wireguard::{HandshakeDiagnostics, TunnelParameters},
+    },
 };
 use tokio::sync::Mutex as AsyncMutex;
 
@@ -63,14 +66,18 @@ pub enum Error {
     #[error("Failed to setup routing")]
     SetupRoutingError(#[source] talpid_routing::Error),
 
-    /// Tunnel timed out
+    /// Tunnel timed out. Contains handshake diagnostics if the tunnel never became functional.
     #[error("Tunnel timed out")]
-    TimeoutError,
+    TimeoutError(Option<HandshakeDiagnostics>),
 
     /// Invalid WireGuard configuration
     #[error("Invalid WireGuard configuration")]
     WireguardConfigError(#[from] crate::config::Error),
 
+    /// The tunnel socket cannot be bound to a local address in this build.
+    #[error("Binding the tunnel socket to {0} is not supported")]
+    BindAddressNotSupported(IpAddr),
+
     /// An interaction with a tunnel failed
     #[error("Tunnel failed")]
     TunnelError(#[from] TunnelError),
@@ -140,6 +147,12 @@ pub struct WireguardMonitor {
     close_msg_receiver: sync_mpsc::Receiver<CloseMsg>,
     pinger_stop_sender: connectivity::CancelToken,
     obfuscator: Arc<AsyncMutex<Option<ObfuscatorHandle>>>,
+    /// Public key of the peer whose traffic is counted
+    exit_peer: [u8; 32],
+    /// Total traffic of all tunnels, to which the traffic of this tunnel is added on teardown
+    tunnel_traffic: Arc<std::sync::Mutex<TunnelTraffic>>,
+    /// Allowed IPs of the exit peer, as read from the device once the tunnel is up
+    applied_allowed_ips: Arc<std::sync::Mutex<Option<Vec<ipnetwork::IpNetwork>>>>,
 }
 
 #[cfg(not(target_os = "android"))]
@@ -158,7 +171,11 @@ impl WireguardMonitor {
         args: TunnelArgs<'_>,
         _log_path: Option<&Path>,
     ) -> Result<WireguardMonitor> {
-        let userspace_wireguard = *FORCE_USERSPACE_WIREGUARD || params.options.daita;
+        check_bind_address_supported(params)?;
+        // Only GotaTun can bind the tunnel socket to a local address
+        let userspace_wireguard = *FORCE_USERSPACE_WIREGUARD
+            || params.options.daita
+            || params.options.bind_address.is_some();
         let userspace_multihop = userspace_wireguard;
 
         let route_mtu = args
@@ -233,17 +250,23 @@ impl WireguardMonitor {
             close_msg_receiver: close_obfs_listener,
             pinger_stop_sender: cancel_token,
             obfuscator,
+            exit_peer: *config.exit_peer().public_key.as_bytes(),
+            tunnel_traffic: args.tunnel_traffic.clone(),
+            applied_allowed_ips: args.applied_allowed_ips.clone(),
         };
 
         let mut event_hook = args.event_hook.clone();
         let moved_tunnel = monitor.tunnel.clone();
         let moved_close_obfs_sender = close_obfs_sender.clone();
         let moved_obfuscator = monitor.obfuscator.clone();
+        let exit_peer = monitor.exit_peer;
+        let moved_applied_allowed_ips = monitor.applied_allowed_ips.clone();
         let detect_mtu = params.options.mtu.is_none();
         let tunnel_fut = async move {
             let tunnel = moved_tunnel;
             let close_obfs_sender: sync_mpsc::Sender<CloseMsg> = moved_close_obfs_sender;
             let obfuscator = moved_obfuscator;
+            let applied_allowed_ips = moved_applied_allowed_ips;
             #[cfg(windows)]
             if cfg!(not(feature = "wireguard-go")) && userspace_wireguard {
                 // NOTE: For gotatun, we use the `tun` crate to create our tunnel interface.
@@ -347,14 +370,16 @@ impl WireguardMonitor {
                 Ok(true) => Ok(()),
                 Ok(false) => {
                     log::warn!("Timeout while checking tunnel connection");
-                    Err(CloseMsg::PingErr)
+                    let diagnostics = handshake_diagnostics(borrowed_tun.as_ref()).await;
+                    Err(CloseMsg::PingErr(diagnostics))
                 }
                 Err(error) => {
                     log::error!(
                         "{}",
                         error.display_chain_with_msg("Failed to check tunnel connection")
                     );
-                    Err(CloseMsg::PingErr)
+                    let diagnostics = handshake_diagnostics(borrowed_tun.as_ref()).await;
+                    Err(CloseMsg::PingErr(diagnostics))
                 }
             }?;
             drop(lock);
@@ -369,6 +394,10 @@ impl WireguardMonitor {
                 .map_err(Error::SetupRoutingError)
                 .map_err(CloseMsg::SetupError)?;
 
+            if let Some(tunnel) = tunnel.lock().await.as_ref() {
+                read_applied_allowed_ips(tunnel, &exit_peer, &applied_allowed_ips).await;
+            }
+
             let metadata = Self::tunnel_metadata(&iface_name, &config);
             event_hook.on_event(TunnelEvent::Up(metadata)).await;
 
@@ -382,7 +411,7 @@ impl WireguardMonitor {
                 );
             }
 
-            Err::<Infallible, CloseMsg>(CloseMsg::PingErr)
+            Err::<Infallible, CloseMsg>(CloseMsg::PingErr(None))
         };
 
         let close_sender = close_obfs_sender.clone();
@@ -418,6 +447,7 @@ impl WireguardMonitor {
             &Path,
         >,
     ) -> Result<WireguardMonitor> {
+        check_bind_address_supported(params)?;
         let route_mtu = args
             .runtime
             .block_on(get_route_mtu(params, &args.route_manager));
@@ -499,13 +529,19 @@ impl WireguardMonitor {
             close_msg_receiver: close_obfs_listener,
             pinger_stop_sender: cancel_token,
             obfuscator: Arc::new(AsyncMutex::new(obfuscator)),
+            exit_peer: *config.exit_peer().public_key.as_bytes(),
+            tunnel_traffic: args.tunnel_traffic.clone(),
+            applied_allowed_ips: args.applied_allowed_ips.clone(),
         };
 
         let moved_close_obfs_sender = close_obfs_sender.clone();
         let moved_obfuscator = monitor.obfuscator.clone();
+        let exit_peer = monitor.exit_peer;
+        let moved_applied_allowed_ips = monitor.applied_allowed_ips.clone();
         let tunnel_fut = async move {
             let close_obfs_sender: sync_mpsc::Sender<CloseMsg> = moved_close_obfs_sender;
             let obfuscator = moved_obfuscator;
+            let applied_allowed_ips = moved_applied_allowed_ips;
 
             let metadata = Self::tunnel_metadata(&iface_name, &config);
             let allowed_traffic = Self::allowed_traffic_during_tunnel_config(&config);
@@ -524,14 +560,16 @@ impl WireguardMonitor {
                     Ok(true) => Ok(()),
                     Ok(false) => {
                         log::warn!("Timeout while checking tunnel connection");
-                        Err(CloseMsg::PingErr)
+                        let diagnostics = handshake_diagnostics(borrowed_tun.as_ref()).await;
+                        Err(CloseMsg::PingErr(diagnostics))
                     }
                     Err(error) => {
                         log::error!(
                             "{}",
                             error.display_chain_with_msg("Failed to check tunnel connection")
                         );
-                        Err(CloseMsg::PingErr)
+                        let diagnostics = handshake_diagnostics(borrowed_tun.as_ref()).await;
+                        Err(CloseMsg::PingErr(diagnostics))
                     }
                 }?;
             }
@@ -567,6 +605,10 @@ impl WireguardMonitor {
                     .await;
             }
 
+            if let Some(tunnel) = tunnel.lock().await.as_ref() {
+                read_applied_allowed_ips(tunnel, &exit_peer, &applied_allowed_ips).await;
+            }
+
             let metadata = Self::tunnel_metadata(&iface_name, &config);
             event_hook.on_event(TunnelEvent::Up(metadata)).await;
 
@@ -580,7 +622,7 @@ impl WireguardMonitor {
                 );
             }
 
-            Err::<Infallible, CloseMsg>(CloseMsg::PingErr)
+            Err::<Infallible, CloseMsg>(CloseMsg::PingErr(None))
         };
 
         let close_sender = close_obfs_sender.clone();
@@ -819,9 +861,8 @@ impl WireguardMonitor {
     /// Blocks the current thread until tunnel disconnects
     pub fn wait(mut self) -> Result<()> {
         let wait_result = match self.close_msg_receiver.recv() {
-            Ok(CloseMsg::EphemeralPeerNegotiationTimeout) | Ok(CloseMsg::PingErr) => {
-                Err(Error::TimeoutError)
-            }
+            Ok(CloseMsg::EphemeralPeerNegotiationTimeout) => Err(Error::TimeoutError(None)),
+            Ok(CloseMsg::PingErr(diagnostics)) => Err(Error::TimeoutError(diagnostics)),
             Ok(CloseMsg::Stop) | Ok(CloseMsg::ObfuscatorExpired) => Ok(()),
             Ok(CloseMsg::SetupError(error)) => Err(error),
             Ok(CloseMsg::ObfuscatorFailed(error)) => Err(error),
@@ -834,8 +875,10 @@ impl WireguardMonitor {
             self.event_hook.on_event(TunnelEvent::Down).await;
             if let Some(tunnel) = self.tunnel.lock().await.as_ref() {
                 log_daita_overhead(tunnel).await;
+                add_tunnel_traffic(tunnel, &self.exit_peer, &self.tunnel_traffic).await;
             };
         });
+        *self.applied_allowed_ips.lock().unwrap() = None;
 
         self.stop_tunnel();
 
@@ -1047,6 +1090,53 @@ async fn log_tunnel_data_usage(config: &Config, tunnel: &Arc<AsyncMutex<Option<T
     }
 }
 
+/// Describe how far the handshake got, based on the current tunnel stats.
+async fn handshake_diagnostics(tunnel: &dyn Tunnel) -> Option<HandshakeDiagnostics> {
+    let tunnel_stats = tunnel.get_tunnel_stats().await.ok()?;
+    let diagnostics = stats::handshake_diagnostics(&tunnel_stats);
+    log::debug!("Handshake diagnostics: {diagnostics:?}");
+    Some(diagnostics)
+}
+
+/// Add the traffic through the exit peer of `tunnel` to `total`. In a multihop tunnel, this
+/// excludes the overhead of the entry hop.
+async fn add_tunnel_traffic(
+    tunnel: &TunnelType,
+    exit_peer: &[u8; 32],
+    total: &std::sync::Mutex<TunnelTraffic>,
+) {
+    let Ok(tunnel_stats) = tunnel.get_tunnel_stats().await else {
+        return;
+    };
+    if let Some(stats) = tunnel_stats.get(exit_peer) {
+        let mut total = total.lock().unwrap();
+        total.tx_bytes += stats.tx_bytes;
+        total.rx_bytes += stats.rx_bytes;
+    }
+}
+
+/// Store the allowed IPs of `exit_peer` as they are configured on the device of `tunnel`, rather
+/// than as they were requested.
+async fn read_applied_allowed_ips(
+    tunnel: &TunnelType,
+    exit_peer: &[u8; 32],
+    applied_allowed_ips: &std::sync::Mutex<Option<Vec<ipnetwork::IpNetwork>>>,
+) {
+    let allowed_ips = match tunnel.get_tunnel_stats().await {
+        Ok(tunnel_stats) => tunnel_stats
+            .get(exit_peer)
+            .map(|stats| stats.allowed_ips.clone()),
+        Err(error) => {
+            log::error!(
+                "{}",
+                error.display_chain_with_msg("Failed to read allowed IPs from tunnel device")
+            );
+            None
+        }
+    };
+    *applied_allowed_ips.lock().unwrap() = allowed_ips;
+}
+
 async fn log_daita_overhead(tunnel: &TunnelType) {
     let Ok(tunnel_stats) = tunnel.get_tunnel_stats().await else {
         return;
@@ -1074,7 +1164,9 @@ Incoming: {total_in} MiB total, {padding_packet_in} MiB padding packets, {consta
 enum CloseMsg {
     Stop,
     EphemeralPeerNegotiationTimeout,
-    PingErr,
+    /// The connectivity check failed. Contains handshake diagnostics if the tunnel never became
+    /// functional.
+    PingErr(Option<HandshakeDiagnostics>),
     SetupError(Error),
     ObfuscatorExpired,
     ObfuscatorFailed(Error),
@@ -1180,6 +1272,17 @@ pub enum TunnelError {
     ConfigureGotaTunDevice(#[source] gotatun::ConfigureGotaTunDeviceError),
 }
 
+/// Fail if a bind address is set that cannot be used. Only GotaTun can bind the tunnel socket to a
+/// local address, and not on Android.
+fn check_bind_address_supported(params: &TunnelParameters) -> Result<()> {
+    match params.options.bind_address {
+        Some(address) if cfg!(any(feature = "wireguard-go", target_os = "android")) => {
+            Err(Error::BindAddressNotSupported(address))
+        }
+        _ => Ok(()),
+    }
+}
+
 #[cfg(target_os = "linux")]
 fn will_nm_manage_dns() -> bool {
     use talpid_dbus::network_manager::NetworkManager;
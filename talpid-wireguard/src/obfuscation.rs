@@ -147,6 +147,20 @@ fn settings_from_single_config(
                 } else {
                     SocketAddr::from((Ipv6Addr::LOCALHOST, 51820))
                 },
+                credentials: None,
+                #[cfg(target_os = "linux")]
+                fwmark,
+            })
+        }
+        ObfuscatorConfig::CustomShadowsocks { server } => {
+            ObfuscationSettings::Shadowsocks(shadowsocks::Settings {
+                shadowsocks_endpoint: server.endpoint,
+                // Unlike the servers run by relays, a custom server is not on the relay
+                wireguard_endpoint: config.entry_peer.endpoint,
+                credentials: Some(shadowsocks::Credentials {
+                    cipher: server.cipher.clone(),
+                    password: server.password.clone(),
+                }),
                 #[cfg(target_os = "linux")]
                 fwmark,
             })
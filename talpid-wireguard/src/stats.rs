@@ -1,5 +1,7 @@
+use ipnetwork::IpNetwork;
 use std::fmt;
 use std::time::{Duration, SystemTime};
+use talpid_types::net::wireguard::HandshakeDiagnostics;
 
 /// Contains bytes sent and received through a tunnel
 #[derive(Default, PartialEq, Eq, Clone)]
@@ -7,6 +9,8 @@ pub struct Stats {
     pub tx_bytes: u64,
     pub rx_bytes: u64,
     pub last_handshake_time: Option<SystemTime>,
+    /// Allowed IPs of the peer, as configured on the device
+    pub allowed_ips: Vec<IpNetwork>,
     // Optional DAITA stats
     // Currently only available for GotaTun
     pub daita: Option<DaitaStats>,
@@ -72,6 +76,17 @@ impl fmt::Debug for StatsDebug<'_> {
 /// A map from peer pubkeys to peer stats.
 pub type StatsMap = std::collections::HashMap<[u8; 32], Stats>;
 
+/// Infer how far the handshake got from the stats of all peers.
+pub fn handshake_diagnostics(stats: &StatsMap) -> HandshakeDiagnostics {
+    HandshakeDiagnostics::new(
+        stats.values().any(|peer| peer.tx_bytes > 0),
+        stats.values().any(|peer| peer.rx_bytes > 0),
+        stats
+            .values()
+            .any(|peer| peer.last_handshake_time.is_some()),
+    )
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -89,4 +104,34 @@ mod test {
 
         insta::assert_debug_snapshot!(StatsDebug { now, stats: &stats });
     }
+
+    #[test]
+    fn test_handshake_diagnostics() {
+        use talpid_types::net::wireguard::HandshakeOutcome;
+
+        let mut map = StatsMap::new();
+        map.insert(
+            [0u8; 32],
+            Stats {
+                tx_bytes: 148,
+                ..Default::default()
+            },
+        );
+        let diagnostics = handshake_diagnostics(&map);
+        assert!(diagnostics.initiation_sent);
+        assert!(!diagnostics.response_received);
+        assert_eq!(diagnostics.outcome, HandshakeOutcome::Timeout);
+
+        map.get_mut(&[0u8; 32]).unwrap().rx_bytes = 92;
+        assert_eq!(
+            handshake_diagnostics(&map).outcome,
+            HandshakeOutcome::Rejected
+        );
+
+        map.get_mut(&[0u8; 32]).unwrap().last_handshake_time = Some(SystemTime::UNIX_EPOCH);
+        assert_eq!(
+            handshake_diagnostics(&map).outcome,
+            HandshakeOutcome::Completed
+        );
+    }
 }
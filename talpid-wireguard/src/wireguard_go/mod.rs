@@ -557,10 +557,14 @@ impl WgGoTunnel {
         let entry_config_str = config::userspace_format(
             &config.tunnel.private_key,
             std::iter::once(&config.entry_peer),
+            config.persistent_keepalive,
         );
 
-        let exit_config_str =
-            config::userspace_format(&config.tunnel.private_key, std::iter::once(exit_peer));
+        let exit_config_str = config::userspace_format(
+            &config.tunnel.private_key,
+            std::iter::once(exit_peer),
+            config.persistent_keepalive,
+        );
 
         let private_ip = config
             .tunnel
@@ -658,8 +662,9 @@ impl WgGoTunnel {
 
         // Timed out
         if !connection_established {
+            let diagnostics = super::handshake_diagnostics(self).await;
             return Err(TunnelError::RecoverableStartWireguardError(Box::new(
-                super::Error::TimeoutError,
+                super::Error::TimeoutError(diagnostics),
             )));
         }
 
@@ -739,6 +744,9 @@ mod stats {
 
         #[error("Failed to parse integer from string \"{0}\"")]
         IntParse(String, #[source] std::num::ParseIntError),
+
+        #[error("Failed to parse allowed IP from string \"{0}\"")]
+        AllowedIpParse(String, #[source] ipnetwork::IpNetworkError),
     }
 
     impl Stats {
@@ -746,6 +754,8 @@ mod stats {
             let mut map = StatsMap::new();
 
             let mut peer = None;
+            // Allowed IPs are listed after the stats of the peer that they belong to
+            let mut current_peer = None;
 
             let mut tx_bytes = None;
             let mut rx_bytes = None;
@@ -767,6 +777,7 @@ mod stats {
                         hex::decode_to_slice(value, &mut buffer)
                             .map_err(|err| Error::PubKeyParse(value.to_string(), err))?;
                         peer = Some(buffer);
+                        current_peer = Some(buffer);
                         tx_bytes = None;
                         rx_bytes = None;
                         last_handshake_time_sec = None;
@@ -804,6 +815,18 @@ mod stats {
                                 .map_err(|err| Error::IntParse(value.to_string(), err))?,
                         );
                     }
+                    "allowed_ip" => {
+                        let allowed_ip = value
+                            .trim()
+                            .parse()
+                            .map_err(|err| Error::AllowedIpParse(value.to_string(), err))?;
+                        if let Some(current_peer) = current_peer {
+                            map.entry(current_peer)
+                                .or_default()
+                                .allowed_ips
+                                .push(allowed_ip);
+                        }
+                    }
 
                     _ => continue,
                 }
@@ -819,15 +842,10 @@ mod stats {
                         Some(UNIX_EPOCH + Duration::new(handshake_sec, handshake_nsec))
                     };
 
-                    map.insert(
-                        peer_val,
-                        Self {
-                            tx_bytes: tx_bytes_val,
-                            rx_bytes: rx_bytes_val,
-                            last_handshake_time: last_handshake_time(),
-                            ..Default::default()
-                        },
-                    );
+                    let stats = map.entry(peer_val).or_default();
+                    stats.tx_bytes = tx_bytes_val;
+                    stats.rx_bytes = rx_bytes_val;
+                    stats.last_handshake_time = last_handshake_time();
                     peer = None;
                     tx_bytes = None;
                     rx_bytes = None;
@@ -854,6 +872,10 @@ mod stats {
             assert_eq!(actual_keys, [pubkey]);
             assert_eq!(stats[&pubkey].rx_bytes, 2396);
             assert_eq!(stats[&pubkey].tx_bytes, 2740);
+            assert_eq!(
+                stats[&pubkey].allowed_ips,
+                ["0.0.0.0/0".parse::<ipnetwork::IpNetwork>().unwrap()]
+            );
         }
 
         #[test]
@@ -154,6 +154,12 @@ fn convert_config_to_dbus(config: &Config) -> DeviceConfig {
             "public-key".into(),
             Variant(Box::new(peer.public_key.to_base64())),
         );
+        if let Some(interval) = config.persistent_keepalive {
+            peer_config.insert(
+                "persistent-keepalive".into(),
+                Variant(Box::new(u32::from(interval))),
+            );
+        }
 
         peer_configs.push(peer_config);
     }
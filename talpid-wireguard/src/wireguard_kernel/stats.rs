@@ -11,6 +11,7 @@ impl Stats {
                     let mut tx_bytes = 0;
                     let mut rx_bytes = 0;
                     let mut last_handshake_time = None;
+                    let mut allowed_ips = vec![];
                     let mut pub_key = None;
 
                     for nla in &msg.0 {
@@ -23,6 +24,9 @@ impl Stats {
                                 }
                             }
                             PeerNla::PublicKey(key) => pub_key = Some(*key),
+                            PeerNla::AllowedIps(ips) => {
+                                allowed_ips.extend(ips.iter().filter_map(|ip| ip.ip_network()))
+                            }
                             _ => continue,
                         }
                     }
@@ -33,6 +37,7 @@ impl Stats {
                                 tx_bytes,
                                 rx_bytes,
                                 last_handshake_time,
+                                allowed_ips,
                                 ..Default::default()
                             },
                         );
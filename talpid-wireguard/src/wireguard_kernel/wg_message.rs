@@ -97,6 +97,9 @@ impl DeviceMessage {
             if let Some(psk) = peer.psk.as_ref() {
                 peer_nlas.push(PeerNla::PresharedKey(*psk.as_bytes()));
             }
+            if let Some(interval) = config.persistent_keepalive {
+                peer_nlas.push(PeerNla::PersistentKeepaliveInterval(interval));
+            }
             peers.push(PeerMessage(peer_nlas));
         }
 
@@ -483,6 +486,22 @@ impl From<&IpNetwork> for AllowedIpMessage {
     }
 }
 
+impl AllowedIpMessage {
+    /// Return the network described by this message, if it has an address and a valid CIDR mask.
+    pub fn ip_network(&self) -> Option<IpNetwork> {
+        let mut address = None;
+        let mut prefix = None;
+        for nla in &self.0 {
+            match nla {
+                AllowedIpNla::IpAddr(ip) => address = Some(*ip),
+                AllowedIpNla::CidrMask(mask) => prefix = Some(*mask),
+                _ => continue,
+            }
+        }
+        IpNetwork::new(address?, prefix?).ok()
+    }
+}
+
 impl Nla for AllowedIpMessage {
     fn value_len(&self) -> usize {
         self.0.as_slice().buffer_len()
@@ -887,4 +906,12 @@ mod test {
         assert_eq!(message, deserialized_device);
         assert_eq!(payload_buffer, expected_payload);
     }
+
+    #[test]
+    fn allowed_ip_message_network() {
+        for network in ["10.64.0.0/10", "fc00:bbbb:bbbb:bb01::/64", "0.0.0.0/0"] {
+            let network = IpNetwork::from_str(network).unwrap();
+            assert_eq!(AllowedIpMessage::from(&network).ip_network(), Some(network));
+        }
+    }
 }
@@ -253,6 +253,16 @@ impl WgAllowedIp {
         }
         Ok(())
     }
+
+    /// Return the network described by this allowed IP, or `None` if it is invalid.
+    fn ip_network(&self) -> Option<IpNetwork> {
+        let address = match self.address_family {
+            AF_INET => IpAddr::from(net::ipaddr_from_inaddr(unsafe { self.address.v4 })),
+            AF_INET6 => IpAddr::from(net::ipaddr_from_in6addr(unsafe { self.address.v6 })),
+            _ => return None,
+        };
+        IpNetwork::new(address, self.cidr).ok()
+    }
 }
 
 impl PartialEq for WgAllowedIp {
@@ -815,6 +825,9 @@ fn serialize_config(config: &Config) -> Result<Vec<MaybeUninit<u8>>> {
         if peer.psk.is_some() {
             flags |= WgPeerFlag::HAS_PRESHARED_KEY;
         }
+        if config.persistent_keepalive.is_some() {
+            flags |= WgPeerFlag::HAS_PERSISTENT_KEEPALIVE;
+        }
         let wg_peer = WgPeer {
             flags,
             reserved: 0,
@@ -824,7 +837,7 @@ fn serialize_config(config: &Config) -> Result<Vec<MaybeUninit<u8>>> {
                 .as_ref()
                 .map(|psk| *psk.as_bytes())
                 .unwrap_or([0u8; WIREGUARD_KEY_LENGTH]),
-            persistent_keepalive: 0,
+            persistent_keepalive: config.persistent_keepalive.unwrap_or(0),
             endpoint: net::inet_sockaddr_from_socketaddr(peer.endpoint).into(),
             tx_bytes: 0,
             rx_bytes: 0,
@@ -937,7 +950,7 @@ impl Tunnel for WgNtTunnel {
                 );
                 super::TunnelError::GetConfigError
             })?;
-            for (peer, _allowed_ips) in &peers {
+            for (peer, allowed_ips) in &peers {
                 // last_handshake is in 100s of ns relative to 1601-01-01 UTC
                 // https://git.zx2c4.com/wireguard-nt/tree/api/wireguard.h?id=30a2817d913460ed8a23388d3da485cf9347afa3#n246
                 let last_handshake_time =
@@ -949,6 +962,10 @@ impl Tunnel for WgNtTunnel {
                         tx_bytes: peer.tx_bytes,
                         rx_bytes: peer.rx_bytes,
                         last_handshake_time,
+                        allowed_ips: allowed_ips
+                            .iter()
+                            .filter_map(WgAllowedIp::ip_network)
+                            .collect(),
                         ..Default::default()
                     },
                 );
@@ -1040,6 +1057,8 @@ mod tests {
         ipv4_gateway: "0.0.0.0".parse().unwrap(),
         ipv6_gateway: None,
         mtu: 0,
+        persistent_keepalive: None,
+        bind_address: None,
         obfuscator_config: None,
         quantum_resistant: false,
         daita: false,
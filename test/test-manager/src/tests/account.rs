@@ -53,7 +53,7 @@ pub async fn test_too_many_devices(
                 .await
             {
                 Ok(_) => (),
-                Err(mullvad_api::rest::Error::ApiError(_status, ref code))
+                Err(mullvad_api::rest::Error::ApiError(_status, ref code, _))
                     if code == mullvad_api::MAX_DEVICES_REACHED =>
                 {
                     break;
@@ -314,7 +314,7 @@ pub async fn retry_if_throttled<
             // Work around throttling errors by sleeping
             Err(mullvad_api::rest::Error::ApiError(
                 mullvad_api::rest::StatusCode::TOO_MANY_REQUESTS,
-                _,
+                ..
             )) => {
                 log::debug!(
                     "Device list fetch failed due to throttling. Sleeping for {} seconds",
@@ -85,6 +85,8 @@ pub async fn test_dns_leak_custom_public_ip(
                 addresses: vec![CONFIG_IP],
             },
             state: settings::DnsState::Custom,
+            split_dns: vec![],
+            resolver_order: vec![settings::DnsResolverSource::Custom],
         })
         .await
         .expect("failed to configure DNS server");
@@ -117,6 +119,8 @@ pub async fn test_dns_leak_custom_private_ip(
                 addresses: vec![CONFIG_IP],
             },
             state: settings::DnsState::Custom,
+            split_dns: vec![],
+            resolver_order: vec![settings::DnsResolverSource::Custom],
         })
         .await
         .expect("failed to configure DNS server");
@@ -368,6 +372,8 @@ pub async fn test_dns_config_custom_private(
                 addresses: vec![IpAddr::V4(TEST_CONFIG.host_bridge_ip)],
             },
             state: settings::DnsState::Custom,
+            split_dns: vec![],
+            resolver_order: vec![settings::DnsResolverSource::Custom],
         })
         .await
         .context("failed to configure DNS server")?;
@@ -402,6 +408,8 @@ pub async fn test_dns_config_custom_public(
                 addresses: vec![custom_ip],
             },
             state: settings::DnsState::Custom,
+            split_dns: vec![],
+            resolver_order: vec![settings::DnsResolverSource::Custom],
         })
         .await
         .context("failed to configure DNS server")?;
@@ -506,6 +514,8 @@ pub async fn test_content_blockers(
                 default_options: test_opts,
                 custom_options: settings::CustomDnsOptions::default(),
                 state: settings::DnsState::Default,
+                split_dns: vec![],
+                resolver_order: vec![settings::DnsResolverSource::Custom],
             })
             .await
             .context("failed to configure DNS server")?;
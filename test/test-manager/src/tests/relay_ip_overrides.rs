@@ -75,6 +75,8 @@ pub async fn test_wireguard_ip_override(
             hostname: relay.hostname.clone(),
             ipv4_addr_in: Some(TEST_CONFIG.host_bridge_ip),
             ipv6_addr_in: None,
+            wireguard_ipv4_addr_in: None,
+            wireguard_ipv6_addr_in: None,
         })
         .await?;
 
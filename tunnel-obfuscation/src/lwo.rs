@@ -15,6 +15,9 @@ use crate::{Obfuscator, socket::create_remote_socket};
 
 const MAX_UDP_SIZE: usize = u16::MAX as usize;
 
+/// The overhead (in bytes) of LWO. Packets are obfuscated in place, so none is added.
+pub const PACKET_OVERHEAD: u16 = 0;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     /// Failed to bind local UDP socket
@@ -311,7 +314,7 @@ impl Obfuscator for Lwo {
     }
 
     fn packet_overhead(&self) -> u16 {
-        0
+        PACKET_OVERHEAD
     }
 
     #[cfg(target_os = "android")]
@@ -34,6 +34,11 @@ use crate::socket::create_remote_socket;
 
 const MAX_DATAGRAM_SIZE: usize = u16::MAX as usize;
 
+/// The overhead (in bytes) of the multiplexer.
+// FIXME: This should ideally be the max overhead of all transports,
+// and be lowered when a transport is selected.
+pub const PACKET_OVERHEAD: u16 = 60;
+
 /// Max number of initial outgoing packets to buffer for replaying to new transports
 const MAX_INITIAL_PACKETS: usize = 100;
 
@@ -371,9 +376,7 @@ impl crate::Obfuscator for Multiplexer {
     }
 
     fn packet_overhead(&self) -> u16 {
-        // FIXME: This should ideally be the max overhead of all transports,
-        // and be lowered when a transport is selected.
-        60
+        PACKET_OVERHEAD
     }
 
     async fn run(self: Box<Self>) -> crate::Result<()> {
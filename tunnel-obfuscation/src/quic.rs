@@ -11,6 +11,11 @@ use tokio_util::sync::CancellationToken;
 
 use crate::{Obfuscator, socket::create_remote_socket};
 
+/// The maximum overhead (in bytes) of a packet sent through the QUIC proxy:
+/// QUIC (<= 41) + stream ID (1) + fragment header (5). The outer IP and UDP headers replace those
+/// of the WireGuard packet.
+pub const MAX_PACKET_OVERHEAD: u16 = 41 + 1 + 5;
+
 type Result<T> = std::result::Result<T, Error>;
 
 #[derive(thiserror::Error, Debug)]
@@ -17,7 +17,7 @@ use shadowsocks::{
         udprelay::proxy_socket::{ProxySocketError, UdpSocketType},
     },
 };
-use std::{io, net::SocketAddr, sync::Arc};
+use std::{fmt, io, net::SocketAddr, str::FromStr, sync::Arc};
 use tokio::{net::UdpSocket, sync::oneshot};
 
 #[cfg(target_os = "android")]
@@ -44,6 +44,9 @@ pub enum Error {
     /// Server config error
     #[error("Server config error")]
     ServerConfig(#[from] ServerConfigError),
+    /// The cipher is not supported
+    #[error("Unsupported Shadowsocks cipher: {0}")]
+    UnsupportedCipher(String),
 }
 
 pub struct Shadowsocks {
@@ -62,12 +65,39 @@ pub struct Settings {
     pub shadowsocks_endpoint: SocketAddr,
     /// Remote WireGuard endpoint
     pub wireguard_endpoint: SocketAddr,
+    /// Cipher and password of the Shadowsocks server. If `None`, those of the servers run by
+    /// relays are used.
+    pub credentials: Option<Credentials>,
     #[cfg(target_os = "linux")]
     pub fwmark: Option<u32>,
 }
 
+#[derive(Clone)]
+pub struct Credentials {
+    pub cipher: String,
+    pub password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("cipher", &self.cipher)
+            .finish_non_exhaustive()
+    }
+}
+
 impl Shadowsocks {
     pub(crate) async fn new(settings: &Settings) -> crate::Result<Self> {
+        let (cipher, password) = match &settings.credentials {
+            Some(credentials) => (
+                CipherKind::from_str(&credentials.cipher)
+                    .map_err(|_| Error::UnsupportedCipher(credentials.cipher.clone()))
+                    .map_err(crate::Error::CreateShadowsocksObfuscator)?,
+                credentials.password.clone(),
+            ),
+            None => (SHADOWSOCKS_CIPHER, SHADOWSOCKS_PASSWORD.to_owned()),
+        };
+
         let (local_udp_socket, udp_client_addr) =
             create_local_udp_socket(settings.shadowsocks_endpoint.is_ipv4())
                 .await
@@ -87,6 +117,8 @@ impl Shadowsocks {
 
         let server = tokio::spawn(run_forwarding(
             settings.shadowsocks_endpoint,
+            cipher,
+            password,
             remote_socket,
             local_udp_socket,
             settings.wireguard_endpoint,
@@ -106,6 +138,8 @@ impl Shadowsocks {
 
 async fn run_forwarding(
     shadowsocks_endpoint: SocketAddr,
+    cipher: CipherKind,
+    password: String,
     remote_socket: UdpSocket,
     local_udp_socket: UdpSocket,
     wireguard_endpoint: SocketAddr,
@@ -115,7 +149,7 @@ async fn run_forwarding(
         .await
         .map_err(Error::WaitForUdpClient)?;
 
-    let shadowsocks = connect_shadowsocks(remote_socket, shadowsocks_endpoint)?;
+    let shadowsocks = connect_shadowsocks(remote_socket, shadowsocks_endpoint, cipher, &password)?;
     let shadowsocks = Arc::new(shadowsocks);
 
     let local_udp = Arc::new(local_udp_socket);
@@ -152,13 +186,11 @@ async fn run_forwarding(
 fn connect_shadowsocks(
     remote_socket: UdpSocket,
     shadowsocks_endpoint: SocketAddr,
+    cipher: CipherKind,
+    password: &str,
 ) -> Result<ShadowSocket> {
     let ss_context = Context::new_shared(ServerType::Local);
-    let ss_config = ServerConfig::new(
-        shadowsocks_endpoint,
-        SHADOWSOCKS_PASSWORD,
-        SHADOWSOCKS_CIPHER,
-    )?;
+    let ss_config = ServerConfig::new(shadowsocks_endpoint, password, cipher)?;
     let socket = ProxySocket::from_socket(
         UdpSocketType::Client,
         ss_context,
@@ -279,17 +311,22 @@ impl Obfuscator for Shadowsocks {
     }
 
     fn packet_overhead(&self) -> u16 {
-        // This math relies on the packet structure of Shadowsocks AEAD UDP packets.
-        // https://shadowsocks.org/doc/aead.html
-        // Those packets look like this: [salt][address][payload][tag]
-        debug_assert!(SHADOWSOCKS_CIPHER.is_aead());
+        packet_overhead(self.wireguard_endpoint)
+    }
+}
 
-        let overhead = SHADOWSOCKS_CIPHER.salt_len()
-            + Address::from(self.wireguard_endpoint).serialized_len()
-            + SHADOWSOCKS_CIPHER.tag_len();
+/// The overhead (in bytes) of Shadowsocks when forwarding packets to `wireguard_endpoint`.
+pub fn packet_overhead(wireguard_endpoint: SocketAddr) -> u16 {
+    // This math relies on the packet structure of Shadowsocks AEAD UDP packets.
+    // https://shadowsocks.org/doc/aead.html
+    // Those packets look like this: [salt][address][payload][tag]
+    debug_assert!(SHADOWSOCKS_CIPHER.is_aead());
 
-        u16::try_from(overhead).expect("packet overhead is less than u16::MAX")
-    }
+    let overhead = SHADOWSOCKS_CIPHER.salt_len()
+        + Address::from(wireguard_endpoint).serialized_len()
+        + SHADOWSOCKS_CIPHER.tag_len();
+
+    u16::try_from(overhead).expect("packet overhead is less than u16::MAX")
 }
 
 /// Return whether retrying is a lost cause
@@ -6,6 +6,18 @@ use udp_over_tcp::{
     udp2tcp::{self, Udp2Tcp as Udp2TcpImpl},
 };
 
+/// The overhead (in bytes) of Udp2Tcp. A TCP header replaces the UDP header, and each datagram is
+/// prefixed with its length.
+pub const PACKET_OVERHEAD: u16 = {
+    let max_tcp_header_len = 60; // https://datatracker.ietf.org/doc/html/rfc9293#section-3.1-6.22.1
+    let udp_header_len = 8; // https://datatracker.ietf.org/doc/html/rfc768
+
+    // TODO: Make `HEADER_LEN` constant public in udp-over-tcp lib and use it instead
+    let udp_over_tcp_header_len = size_of::<u16>() as u16;
+
+    max_tcp_header_len - udp_header_len + udp_over_tcp_header_len
+};
+
 #[derive(Debug, Clone)]
 pub struct Settings {
     pub peer: SocketAddr,
@@ -87,14 +99,6 @@ impl Obfuscator for Udp2Tcp {
     }
 
     fn packet_overhead(&self) -> u16 {
-        let max_tcp_header_len = 60; // https://datatracker.ietf.org/doc/html/rfc9293#section-3.1-6.22.1
-        let udp_header_len = 8; // https://datatracker.ietf.org/doc/html/rfc768
-
-        // TODO: Make `HEADER_LEN` constant public in udp-over-tcp lib and use it instead
-        let udp_over_tcp_header_len = size_of::<u16>();
-
-        let overhead = max_tcp_header_len - udp_header_len + udp_over_tcp_header_len;
-
-        u16::try_from(overhead).expect("packet overhead is less than u16::MAX")
+        PACKET_OVERHEAD
     }
 }